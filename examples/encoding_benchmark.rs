@@ -0,0 +1,101 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Compares payload size and encode/decode time of JSON and MessagePack for a child order.
+//!
+//! cargo run --release --example encoding_benchmark --features msgpack
+
+use rust_decimal_macros::dec;
+use std::time::{Duration, Instant};
+use strategy_execution_engine::{
+    ChildOrder, Encoding, Futures, Order, OrderType, ProductType, Quantity, Side, TimeInForce,
+    Timestamp,
+};
+
+const ITERATIONS: u32 = 100_000;
+
+fn child_order() -> ChildOrder {
+    Order::builder()
+        .id("parent-1-17")
+        .quantity(Quantity::new(dec!(12.5)))
+        .product_type(ProductType::Futures)
+        .order_type(OrderType::Limit)
+        .price(dec!(4215.25))
+        .timestamp(1622512800000)
+        .symbol("ES")
+        .side(Side::Buy)
+        .currency("USD")
+        .exchange("CME")
+        .timeinforce(TimeInForce::GTC)
+        .futures(Futures {
+            delivery_date: Some(20240315),
+            contract_size: Some(50.0),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(2.1)),
+            overnight_fee: None,
+        })
+        .nonce(17)
+        .build_child(
+            "twap",
+            "parent-1",
+            Some(Timestamp::from_millis(1622512860000)),
+        )
+        .unwrap()
+}
+
+fn time(f: impl Fn()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let order = child_order();
+    println!(
+        "{:<8} {:>8} {:>12} {:>12}",
+        "encoding", "bytes", "encode", "decode"
+    );
+    for (name, encoding) in [("json", Encoding::Json), ("msgpack", Encoding::MsgPack)] {
+        let payload = encoding.encode(&order).unwrap();
+        let encode = time(|| {
+            encoding.encode(&order).unwrap();
+        });
+        let decode = time(|| {
+            encoding.decode::<ChildOrder>(&payload).unwrap();
+        });
+        println!(
+            "{:<8} {:>8} {:>12?} {:>12?}",
+            name,
+            payload.len(),
+            encode,
+            decode
+        );
+    }
+}
@@ -0,0 +1,309 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+//! Transaction cost analysis of executed parent orders.
+//!
+//! The fills of a parent are measured against three benchmarks taken from the market
+//! trade tape: the arrival price, the last trade before the parent arrived; the interval
+//! VWAP of the trades while it executed; and the interval TWAP, the last traded price
+//! averaged over time across the same interval. Slippage is in basis points of the
+//! benchmark, positive when the fills cost more than the benchmark: bought above it or
+//! sold below it.
+
+use crate::models::decimal::decimal_to_f64;
+use crate::models::market_data::{epoch_millis, Trade};
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::models::parent_state::ParentOrderState;
+use crate::models::timestamp::Timestamp;
+use crate::Fill;
+use serde::{Deserialize, Serialize};
+
+/// The benchmark prices of an interval; `None` for those the tape cannot give.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Benchmarks {
+    pub arrival_price: Option<f64>,
+    pub vwap: Option<f64>,
+    pub twap: Option<f64>,
+}
+
+/// Slippage of an average fill price against each benchmark, in basis points; positive is
+/// a cost. `None` where the benchmark is missing or nothing was filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Slippage {
+    pub arrival_bps: Option<f64>,
+    pub vwap_bps: Option<f64>,
+    pub twap_bps: Option<f64>,
+}
+
+impl Slippage {
+    fn of(side: &Side, average_price: Option<f64>, benchmarks: &Benchmarks) -> Self {
+        let bps = |benchmark: Option<f64>| {
+            let (price, benchmark) = (average_price?, benchmark?);
+            (benchmark > 0.0).then(|| (price - benchmark) / benchmark * 10_000.0 * direction(side))
+        };
+        Slippage {
+            arrival_bps: bps(benchmarks.arrival_price),
+            vwap_bps: bps(benchmarks.vwap),
+            twap_bps: bps(benchmarks.twap),
+        }
+    }
+}
+
+/// The execution of one child against the market while it was working.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildTca {
+    pub child_id: String,
+    pub filled_quantity: f64,
+    pub average_price: f64,
+    /// When the child started working: its dispatch when known, else the parent arrival
+    pub start: Timestamp,
+    /// Its last fill
+    pub end: Timestamp,
+    /// The arrival price is the parent's; VWAP and TWAP are those of the child's interval
+    pub benchmarks: Benchmarks,
+    pub slippage: Slippage,
+}
+
+/// Transaction cost analysis of a parent order, overall and by child.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct TcaReport {
+    pub parent_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    /// Average price of every fill; `None` when nothing was filled
+    pub average_price: Option<f64>,
+    /// When the parent arrived
+    pub start: Timestamp,
+    /// The last fill, or the arrival when nothing was filled
+    pub end: Timestamp,
+    pub benchmarks: Benchmarks,
+    pub slippage: Slippage,
+    /// The children with fills, in the order of their first fill
+    pub children: Vec<ChildTca>,
+}
+
+/// Computes the `TcaReport` of parent orders from the market trade tape of their symbol.
+///
+/// The tape needs no particular order and may be incomplete: a benchmark the trades cannot
+/// give is left out of the report rather than guessed. Trades without a positive price
+/// are ignored, and those without a positive size do not weigh in the VWAP.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BenchmarkCalculator {
+    /// (time in milliseconds, price, size), in time order
+    tape: Vec<(u64, f64, f64)>,
+    arrival_price: Option<f64>,
+}
+
+impl BenchmarkCalculator {
+    pub fn new(tape: &[Trade]) -> Self {
+        let mut tape: Vec<(u64, f64, f64)> = tape
+            .iter()
+            .filter(|trade| trade.price > 0.0)
+            .map(|trade| {
+                (
+                    epoch_millis::to_millis(&trade.timestamp),
+                    trade.price,
+                    trade.size,
+                )
+            })
+            .collect();
+        // Stable, so trades of the same millisecond keep the tape's order
+        tape.sort_by_key(|(at, _, _)| *at);
+        BenchmarkCalculator {
+            tape,
+            arrival_price: None,
+        }
+    }
+
+    /// Measures against `price` as the arrival price, e.g. the mid quote when the parent
+    /// arrived, instead of the last trade before it.
+    pub fn with_arrival_price(mut self, price: f64) -> Self {
+        self.arrival_price = Some(price);
+        self
+    }
+
+    /// The report of `parent` from its `fills`. Each child is measured from the arrival
+    /// of the parent to its last fill.
+    pub fn report(&self, parent: &ParentOrder, fills: &[Fill]) -> TcaReport {
+        self.build(parent, fills, |_| None)
+    }
+
+    /// The report of the parent of `state` from its `fills`. Each child is measured from
+    /// its dispatch to its last fill.
+    pub fn report_state(&self, state: &ParentOrderState, fills: &[Fill]) -> TcaReport {
+        self.build(&state.parent, fills, |child_id| {
+            state.child(child_id).and_then(|child| child.dispatched_at)
+        })
+    }
+
+    /// The price of the last trade at or before `at`, unless an arrival price was given.
+    pub fn arrival_price(&self, at: Timestamp) -> Option<f64> {
+        self.arrival_price.or_else(|| self.price_at(at.as_millis()))
+    }
+
+    /// The volume weighted price of the trades from `start` to `end`, both included.
+    pub fn vwap(&self, start: Timestamp, end: Timestamp) -> Option<f64> {
+        let (mut notional, mut volume) = (0.0, 0.0);
+        for &(_, price, size) in self.between(start.as_millis(), end.as_millis()) {
+            if size > 0.0 {
+                notional += price * size;
+                volume += size;
+            }
+        }
+        (volume > 0.0).then(|| notional / volume)
+    }
+
+    /// The last traded price averaged over the time from `start` to `end`, from the first
+    /// moment a price is known: the last trade before `start`, or else the first trade
+    /// after it. For an empty interval, the price at `start`.
+    pub fn twap(&self, start: Timestamp, end: Timestamp) -> Option<f64> {
+        let (start, end) = (start.as_millis(), end.as_millis());
+        if end < start {
+            return None;
+        }
+        let mut price = self.price_at(start);
+        let (mut weighted, mut covered, mut cursor) = (0.0, 0u64, start);
+        for &(at, trade_price, _) in self.between(start + 1, end) {
+            if let Some(price) = price {
+                weighted += price * (at - cursor) as f64;
+                covered += at - cursor;
+            }
+            cursor = at;
+            price = Some(trade_price);
+        }
+        if let Some(price) = price {
+            weighted += price * (end - cursor) as f64;
+            covered += end - cursor;
+        }
+        if covered == 0 {
+            return price;
+        }
+        Some(weighted / covered as f64)
+    }
+
+    fn between(&self, start: u64, end: u64) -> &[(u64, f64, f64)] {
+        let from = self.tape.partition_point(|(at, _, _)| *at < start);
+        let to = self.tape.partition_point(|(at, _, _)| *at <= end);
+        &self.tape[from..to.max(from)]
+    }
+
+    fn price_at(&self, at: u64) -> Option<f64> {
+        let known = self.tape.partition_point(|(time, _, _)| *time <= at);
+        known.checked_sub(1).map(|index| self.tape[index].1)
+    }
+
+    fn build(
+        &self,
+        parent: &ParentOrder,
+        fills: &[Fill],
+        dispatched_at: impl Fn(&str) -> Option<Timestamp>,
+    ) -> TcaReport {
+        let order = &parent.order_common;
+        let side = order.side.clone();
+        let arrival = order.timestamp;
+        let arrival_price = self.arrival_price(arrival);
+
+        let mut children: Vec<(String, Vec<&Fill>)> = Vec::new();
+        for fill in fills {
+            match children.iter_mut().find(|(id, _)| *id == fill.order_id) {
+                Some((_, child_fills)) => child_fills.push(fill),
+                None => children.push((fill.order_id.clone(), vec![fill])),
+            }
+        }
+        let children: Vec<ChildTca> = children
+            .into_iter()
+            .map(|(child_id, child_fills)| {
+                let (filled_quantity, average_price) = average(child_fills.iter().copied());
+                let end = last_fill(child_fills.iter().copied()).unwrap_or(arrival);
+                let start = dispatched_at(&child_id).unwrap_or(arrival).min(end);
+                let benchmarks = Benchmarks {
+                    arrival_price,
+                    vwap: self.vwap(start, end),
+                    twap: self.twap(start, end),
+                };
+                ChildTca {
+                    slippage: Slippage::of(&side, average_price, &benchmarks),
+                    child_id,
+                    filled_quantity,
+                    average_price: average_price.unwrap_or_default(),
+                    start,
+                    end,
+                    benchmarks,
+                }
+            })
+            .collect();
+
+        let (filled_quantity, average_price) = average(fills.iter());
+        let end = last_fill(fills.iter()).unwrap_or(arrival).max(arrival);
+        let benchmarks = Benchmarks {
+            arrival_price,
+            vwap: self.vwap(arrival, end),
+            twap: self.twap(arrival, end),
+        };
+        TcaReport {
+            parent_id: order.id.clone(),
+            symbol: order.symbol.clone(),
+            quantity: order.quantity.to_f64(),
+            filled_quantity,
+            average_price,
+            start: arrival,
+            end,
+            slippage: Slippage::of(&side, average_price, &benchmarks),
+            benchmarks,
+            side,
+            children,
+        }
+    }
+}
+
+/// The quantity of `fills` and their average price, `None` when nothing was filled.
+fn average<'a>(fills: impl Iterator<Item = &'a Fill>) -> (f64, Option<f64>) {
+    let (mut quantity, mut notional) = (0.0, 0.0);
+    for fill in fills {
+        let filled = fill.quantity.to_f64();
+        quantity += filled;
+        notional += decimal_to_f64(fill.price) * filled;
+    }
+    (quantity, (quantity > 0.0).then(|| notional / quantity))
+}
+
+fn last_fill<'a>(fills: impl Iterator<Item = &'a Fill>) -> Option<Timestamp> {
+    fills
+        .map(|fill| Timestamp::from_millis(fill.timestamp))
+        .max()
+}
+
+fn direction(side: &Side) -> f64 {
+    match side {
+        Side::Buy => 1.0,
+        Side::Sell => -1.0,
+    }
+}
@@ -0,0 +1,525 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::clients::common_client::{ClientError, MessagingService};
+use crate::engine::clock::{Clock, SystemClock};
+use crate::models::orders::OrderStatus;
+use crate::models::{Decimal, Quantity, Side, Timestamp};
+use crate::strategies::common_strategies::SignalDecision;
+use crate::strategies::market_microstructure_based::adverse_selection::StrategySignal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use thiserror::Error;
+
+/// Errors raised while writing or reading the audit log.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum AuditError {
+    #[error("cannot access audit log {path}: {reason}")]
+    Io { path: String, reason: String },
+    #[error("cannot encode audit record {sequence}: {reason}")]
+    Encoding { sequence: u64, reason: String },
+    #[error("cannot publish audit record: {0}")]
+    Publish(#[from] ClientError),
+    /// A line of the log is not an audit record, e.g. one cut short by a crash.
+    #[error("line {line} of audit log {path} is not an audit record: {reason}")]
+    Corrupt {
+        path: String,
+        line: usize,
+        reason: String,
+    },
+    #[error("the audit log is closed")]
+    Closed,
+}
+
+impl AuditError {
+    pub(crate) fn io(path: &Path, error: impl std::fmt::Display) -> Self {
+        AuditError::Io {
+            path: path.display().to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// Something the engine decided or did, as the audit log records it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A valid parent order was taken by the engine.
+    ParentReceived {
+        symbol: String,
+        side: Side,
+        quantity: Quantity,
+        strategy_id: String,
+    },
+    /// A strategy produced, or acted on, a trading signal.
+    SignalGenerated {
+        symbol: String,
+        strategy: String,
+        /// The side signalled; `None` when a signal strategy had no signal at the time
+        side: Option<Side>,
+        price: Option<f64>,
+        size: Option<f64>,
+        reason: Option<String>,
+        /// The indicator values behind the signal, by name
+        indicators: BTreeMap<String, f64>,
+    },
+    /// The strategy split the parent into `children`.
+    SplitProduced {
+        strategy_id: String,
+        children: Vec<String>,
+        /// The parameters of the strategy in the config, if any
+        config: Option<serde_json::Value>,
+        /// The seed the split drew its random variations from, to replay it
+        seed: Option<u64>,
+    },
+    /// The pre-trade checks passed the `accepted` children and blocked the `rejected` ones.
+    RiskChecked {
+        accepted: Vec<String>,
+        rejected: Vec<String>,
+        /// The limits broken, as `RiskRejection` describes them
+        reasons: Vec<String>,
+    },
+    /// A child was sent on, or could not be and was cancelled.
+    ChildDispatched {
+        child_id: String,
+        insert_at: Option<Timestamp>,
+        error: Option<String>,
+    },
+    /// An execution report was applied to a child; `status` is the child's after it.
+    FillApplied {
+        child_id: String,
+        exec_id: String,
+        price: Decimal,
+        quantity: Quantity,
+        status: OrderStatus,
+    },
+    /// Children were stopped before dispatch (`cancelled`) or marked for a venue cancel
+    /// (`pending_cancel`).
+    Cancelled {
+        cancelled: Vec<String>,
+        pending_cancel: Vec<String>,
+    },
+}
+
+impl AuditEvent {
+    /// A `SignalGenerated` for a signal a market data strategy returned.
+    pub fn signal(symbol: &str, strategy: &str, signal: &StrategySignal) -> Self {
+        let (side, price, size, reason) = match signal {
+            StrategySignal::Buy {
+                price,
+                size,
+                reason,
+                ..
+            } => (Side::Buy, price, size, reason),
+            StrategySignal::Sell {
+                price,
+                size,
+                reason,
+                ..
+            } => (Side::Sell, price, size, reason),
+        };
+        AuditEvent::SignalGenerated {
+            symbol: symbol.to_string(),
+            strategy: strategy.to_string(),
+            side: Some(side),
+            price: Some(*price),
+            size: Some(*size),
+            reason: Some(reason.clone()),
+            indicators: BTreeMap::new(),
+        }
+    }
+
+    /// A `SignalGenerated` for the decision a signal strategy splits a parent on.
+    pub fn decision(symbol: &str, strategy: &str, decision: SignalDecision) -> Self {
+        AuditEvent::SignalGenerated {
+            symbol: symbol.to_string(),
+            strategy: strategy.to_string(),
+            side: decision.signal,
+            price: None,
+            size: None,
+            reason: (!decision.ready).then(|| "warming up".to_string()),
+            indicators: decision.indicators,
+        }
+    }
+
+    /// Whether the event names the child `child_id`.
+    pub fn concerns(&self, child_id: &str) -> bool {
+        let listed = |ids: &[String]| ids.iter().any(|id| id == child_id);
+        match self {
+            AuditEvent::ParentReceived { .. } | AuditEvent::SignalGenerated { .. } => false,
+            AuditEvent::SplitProduced { children, .. } => listed(children),
+            AuditEvent::RiskChecked {
+                accepted, rejected, ..
+            } => listed(accepted) || listed(rejected),
+            AuditEvent::ChildDispatched { child_id: id, .. }
+            | AuditEvent::FillApplied { child_id: id, .. } => id == child_id,
+            AuditEvent::Cancelled {
+                cancelled,
+                pending_cancel,
+            } => listed(cancelled) || listed(pending_cancel),
+        }
+    }
+}
+
+/// An event as written to the log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    /// Position in the log, from 1, in the order the events were emitted
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+    /// The parent order the event belongs to; `None` for events of no single parent, such
+    /// as the signals of market data strategies
+    pub correlation_id: Option<String>,
+    pub event: AuditEvent,
+}
+
+/// Where the records of an `AuditLog` go.
+pub trait AuditWriter: Send {
+    fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError>;
+
+    /// Makes the records written so far durable.
+    fn flush(&mut self) -> Result<(), AuditError> {
+        Ok(())
+    }
+}
+
+impl<F> AuditWriter for F
+where
+    F: FnMut(&AuditRecord) -> Result<(), AuditError> + Send,
+{
+    fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        self(record)
+    }
+}
+
+/// Appends the records to a file, one JSON object per line.
+pub struct JsonlAuditWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl JsonlAuditWriter {
+    /// Appends to the file at `path`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuditError::Io` when the file cannot be opened.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|error| AuditError::io(&path, error))?;
+        Ok(JsonlAuditWriter {
+            path,
+            file: BufWriter::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuditWriter for JsonlAuditWriter {
+    fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        let mut line = serde_json::to_vec(record).map_err(|error| AuditError::Encoding {
+            sequence: record.sequence,
+            reason: error.to_string(),
+        })?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .map_err(|error| AuditError::io(&self.path, error))
+    }
+
+    fn flush(&mut self) -> Result<(), AuditError> {
+        self.file
+            .flush()
+            .and_then(|()| self.file.get_ref().sync_data())
+            .map_err(|error| AuditError::io(&self.path, error))
+    }
+}
+
+impl std::fmt::Debug for JsonlAuditWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonlAuditWriter")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Publishes the records as JSON to a topic of the messaging service, keyed by their
+/// correlation id so the records of a parent keep their order on partitioned brokers.
+pub struct MessagingAuditWriter {
+    messaging: Arc<MessagingService>,
+    topic: String,
+}
+
+impl MessagingAuditWriter {
+    pub fn new(messaging: Arc<MessagingService>, topic: impl Into<String>) -> Self {
+        MessagingAuditWriter {
+            messaging,
+            topic: topic.into(),
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+}
+
+impl AuditWriter for MessagingAuditWriter {
+    fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        match &record.correlation_id {
+            Some(key) => self
+                .messaging
+                .produce_json_keyed(&self.topic, key, record)?,
+            None => self.messaging.produce_json(&self.topic, record)?,
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for MessagingAuditWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessagingAuditWriter")
+            .field("topic", &self.topic)
+            .finish()
+    }
+}
+
+enum Message {
+    Record {
+        timestamp: Timestamp,
+        correlation_id: Option<String>,
+        event: AuditEvent,
+    },
+    Flush(mpsc::Sender<Result<(), AuditError>>),
+    Close,
+}
+
+/// An append-only log of `AuditEvent`s, written by an `AuditWriter` on a thread of its own.
+///
+/// Events come in through `AuditSink`s, which only send them down a channel, so emitting
+/// never waits on the writer. The writer numbers the records in the order they arrive. A
+/// record the writer fails to write is logged and counted, and reported by the next
+/// `flush`.
+///
+/// Dropping the log writes the records emitted before, then stops the writer; events
+/// emitted after that are discarded.
+pub struct AuditLog {
+    sender: mpsc::Sender<Message>,
+    clock: Arc<dyn Clock>,
+    failures: Arc<AtomicU64>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AuditLog {
+    /// Starts writing with `writer`, stamping the events with the wall clock.
+    pub fn start(writer: impl AuditWriter + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let failures = Arc::new(AtomicU64::new(0));
+        let worker = {
+            let failures = failures.clone();
+            std::thread::Builder::new()
+                .name("audit-log".to_string())
+                .spawn(move || write_records(writer, receiver, &failures))
+                .expect("failed to spawn the audit log writer")
+        };
+        AuditLog {
+            sender,
+            clock: Arc::new(SystemClock),
+            failures,
+            worker: Some(worker),
+        }
+    }
+
+    /// Stamps the events of the sinks handed out from now on with `clock`.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// A handle emitting into the log.
+    pub fn sink(&self) -> AuditSink {
+        AuditSink {
+            channel: Some((self.sender.clone(), self.clock.clone())),
+        }
+    }
+
+    /// The records the writer failed to write.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Waits until the events emitted so far are written, and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the writer since the previous flush, and
+    /// `AuditError::Closed` when the writer has stopped.
+    pub fn flush(&self) -> Result<(), AuditError> {
+        let (ack, done) = mpsc::channel();
+        self.sender
+            .send(Message::Flush(ack))
+            .map_err(|_| AuditError::Closed)?;
+        done.recv().map_err(|_| AuditError::Closed)?
+    }
+
+    /// Flushes the log, see `flush`, and stops the writer.
+    pub fn close(mut self) -> Result<(), AuditError> {
+        let result = self.flush();
+        self.stop();
+        result
+    }
+
+    fn stop(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = self.sender.send(Message::Close);
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("failures", &self.failures())
+            .finish()
+    }
+}
+
+fn write_records(
+    mut writer: impl AuditWriter,
+    receiver: mpsc::Receiver<Message>,
+    failures: &AtomicU64,
+) {
+    let mut sequence = 0;
+    let mut failure = None;
+    for message in receiver {
+        match message {
+            Message::Record {
+                timestamp,
+                correlation_id,
+                event,
+            } => {
+                sequence += 1;
+                let record = AuditRecord {
+                    sequence,
+                    timestamp,
+                    correlation_id,
+                    event,
+                };
+                if let Err(error) = writer.write(&record) {
+                    log::error!("Could not write audit record {}: {}", sequence, error);
+                    failures.fetch_add(1, Ordering::Relaxed);
+                    failure.get_or_insert(error);
+                }
+            }
+            Message::Flush(ack) => {
+                let result = match failure.take() {
+                    Some(error) => Err(error),
+                    None => writer.flush(),
+                };
+                let _ = ack.send(result);
+            }
+            Message::Close => break,
+        }
+    }
+    if let Err(error) = writer.flush() {
+        log::error!("Could not flush the audit log: {}", error);
+    }
+}
+
+/// A cheap, cloneable handle emitting events into an `AuditLog`. The default sink is
+/// disabled and drops every event.
+#[derive(Clone, Default)]
+pub struct AuditSink {
+    channel: Option<(mpsc::Sender<Message>, Arc<dyn Clock>)>,
+}
+
+impl AuditSink {
+    /// A sink dropping every event.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.channel.is_some()
+    }
+
+    /// The same log, with the events stamped by `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        if let Some((_, stamp)) = &mut self.channel {
+            *stamp = clock;
+        }
+        self
+    }
+
+    /// Emits `event`, stamped with the time on the sink's clock, for the parent
+    /// `correlation_id`.
+    pub fn emit(&self, correlation_id: Option<&str>, event: AuditEvent) {
+        if let Some((_, clock)) = &self.channel {
+            self.emit_at(clock.now(), correlation_id, event);
+        }
+    }
+
+    /// Emits `event` stamped with `timestamp`. Events sent after the log stopped are
+    /// dropped.
+    pub fn emit_at(&self, timestamp: Timestamp, correlation_id: Option<&str>, event: AuditEvent) {
+        if let Some((sender, _)) = &self.channel {
+            let _ = sender.send(Message::Record {
+                timestamp,
+                correlation_id: correlation_id.map(str::to_string),
+                event,
+            });
+        }
+    }
+}
+
+impl std::fmt::Debug for AuditSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditSink")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
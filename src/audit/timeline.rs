@@ -0,0 +1,121 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::audit::audit_log::{AuditError, AuditEvent, AuditRecord};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Reads every record of a JSONL audit log, as `JsonlAuditWriter` writes it, in file order.
+/// Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns `AuditError::Io` when the file cannot be read, and `Corrupt` for a line that is
+/// not a record.
+pub fn read_audit_log(path: impl AsRef<Path>) -> Result<Vec<AuditRecord>, AuditError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|error| AuditError::io(path, error))?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|error| AuditError::io(path, error))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|error| AuditError::Corrupt {
+            path: path.display().to_string(),
+            line: index + 1,
+            reason: error.to_string(),
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// The audit records of one parent order, in the order they were emitted: how it was
+/// received, the signal and split behind its children, the risk checks, and what became
+/// of each child.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParentTimeline {
+    parent_id: String,
+    records: Vec<AuditRecord>,
+}
+
+impl ParentTimeline {
+    /// The timeline of `parent_id` among `records`, which may hold other parents too.
+    pub fn from_records(parent_id: &str, records: impl IntoIterator<Item = AuditRecord>) -> Self {
+        let mut records: Vec<AuditRecord> = records
+            .into_iter()
+            .filter(|record| record.correlation_id.as_deref() == Some(parent_id))
+            .collect();
+        records.sort_by_key(|record| record.sequence);
+        ParentTimeline {
+            parent_id: parent_id.to_string(),
+            records,
+        }
+    }
+
+    /// The timeline of `parent_id` in the JSONL audit log at `path`, see `read_audit_log`.
+    pub fn read(path: impl AsRef<Path>, parent_id: &str) -> Result<Self, AuditError> {
+        Ok(Self::from_records(parent_id, read_audit_log(path)?))
+    }
+
+    pub fn parent_id(&self) -> &str {
+        &self.parent_id
+    }
+
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &AuditEvent> {
+        self.records.iter().map(|record| &record.event)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The children the parent was split into, in split order.
+    pub fn children(&self) -> Vec<String> {
+        self.events()
+            .find_map(|event| match event {
+                AuditEvent::SplitProduced { children, .. } => Some(children.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The records naming the child `child_id`, from the split that produced it on.
+    pub fn child(&self, child_id: &str) -> Vec<&AuditRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.event.concerns(child_id))
+            .collect()
+    }
+}
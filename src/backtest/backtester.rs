@@ -0,0 +1,510 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+//! Offline evaluation of strategies against a candle series.
+//!
+//! Signal strategies are traded candle by candle: the signal read after the close of a
+//! candle is filled at the open of the next one. Split strategies have their children
+//! executed against the volume of the candles they are scheduled in. Both runs are
+//! deterministic: the same candles, strategy and configuration give the same report.
+
+use crate::models::decimal::decimal_to_f64;
+use crate::models::market_data::{epoch_millis, Candle, MarketData};
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{OrderSplitStrategy, SignalStrategy};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// How simulated orders are filled
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FillModel {
+    /// Price moved against the order, in basis points of the fill price
+    pub slippage_bps: f64,
+    /// Flat commission paid on every fill
+    pub commission: f64,
+    /// Share of a candle's volume the children of a split may take, between 0 and 1
+    pub max_participation: f64,
+}
+
+impl Default for FillModel {
+    fn default() -> Self {
+        FillModel {
+            slippage_bps: 0.0,
+            commission: 0.0,
+            max_participation: 0.1,
+        }
+    }
+}
+
+impl FillModel {
+    /// `price` moved against an order on `side` by the slippage
+    pub fn slipped(&self, price: f64, side: &Side) -> f64 {
+        let slippage = price * self.slippage_bps / 10_000.0;
+        match side {
+            Side::Buy => price + slippage,
+            Side::Sell => price - slippage,
+        }
+    }
+}
+
+/// Settings of a backtest run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BacktestConfig {
+    pub initial_capital: f64,
+    /// Quantity of every trade of a signal strategy
+    pub quantity: f64,
+    /// Whether a `Sell` signal opens a short; otherwise it only closes a long
+    pub allow_short: bool,
+    /// Periods per year of the candles, which annualizes the Sharpe ratio
+    pub periods_per_year: f64,
+    pub fill: FillModel,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        BacktestConfig {
+            initial_capital: 10_000.0,
+            quantity: 1.0,
+            allow_short: false,
+            periods_per_year: 252.0,
+            fill: FillModel::default(),
+        }
+    }
+}
+
+/// A round trip of a signal strategy, from the fill that opened it to the one that closed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacktestTrade {
+    pub side: Side,
+    pub quantity: f64,
+    #[serde(with = "epoch_millis")]
+    pub entry_time: SystemTime,
+    pub entry_price: f64,
+    #[serde(with = "epoch_millis")]
+    pub exit_time: SystemTime,
+    pub exit_price: f64,
+    /// Commission of both fills
+    pub commission: f64,
+    /// Profit of the trade, net of commission
+    pub pnl: f64,
+}
+
+impl BacktestTrade {
+    pub fn is_win(&self) -> bool {
+        self.pnl > 0.0
+    }
+}
+
+/// Outcome of a signal strategy backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    /// Closed trades, in the order they were closed
+    pub trades: Vec<BacktestTrade>,
+    /// Share of the closed trades with a positive PnL; zero without trades
+    pub win_rate: f64,
+    /// Final equity over the initial capital, less one
+    pub total_return: f64,
+    /// Largest fall of the equity from a previous peak, as a fraction of that peak
+    pub max_drawdown: f64,
+    /// Annualized mean over standard deviation of the per-candle equity returns
+    pub sharpe: f64,
+    /// Equity at the close of every candle, the open position marked at the close
+    pub equity_curve: Vec<f64>,
+    pub final_equity: f64,
+}
+
+impl BacktestReport {
+    pub fn print_as_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// A simulated execution of a child order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedFill {
+    #[serde(with = "epoch_millis")]
+    pub timestamp: SystemTime,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// How one child of a split was executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildExecution {
+    pub child_id: String,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    /// Average price of the fills; zero when nothing was filled
+    pub average_price: f64,
+    pub fills: Vec<SimulatedFill>,
+}
+
+/// Outcome of a split strategy backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitReport {
+    pub parent_id: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    /// Filled over parent quantity
+    pub fill_rate: f64,
+    /// Average price of every fill; zero when nothing was filled
+    pub average_price: f64,
+    /// Open of the first candle, the price when the parent arrived
+    pub arrival_price: f64,
+    /// Volume weighted typical price of the candles
+    pub vwap: f64,
+    /// Cost of the fills against the arrival price, in basis points; positive when worse
+    pub shortfall_bps: f64,
+    pub commission: f64,
+    /// Seed the split drew its random variations from, to replay the same schedule
+    pub seed: Option<u64>,
+    pub children: Vec<ChildExecution>,
+}
+
+impl SplitReport {
+    pub fn print_as_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Replays candles through signal and split strategies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Backtester {
+    config: BacktestConfig,
+}
+
+#[derive(Debug)]
+struct OpenTrade {
+    side: Side,
+    entry_time: SystemTime,
+    entry_price: f64,
+    commission: f64,
+}
+
+impl Backtester {
+    pub fn new(config: BacktestConfig) -> Self {
+        Backtester { config }
+    }
+
+    pub fn config(&self) -> &BacktestConfig {
+        &self.config
+    }
+
+    /// Trades `strategy` over `candles`, which must be in time order
+    ///
+    /// The strategy is reset first. After each candle, a ready strategy signalling `Buy`
+    /// asks for a long position and one signalling `Sell` for a short one, or for none when
+    /// shorts are not allowed; no signal keeps the position. A change of position is filled
+    /// at the next open, the current trade closed before the new one is opened. An order
+    /// still pending after the last candle is dropped and an open trade is only marked.
+    pub fn run_signal(
+        &self,
+        strategy: &mut dyn SignalStrategy,
+        candles: &[Candle],
+    ) -> BacktestReport {
+        strategy.reset();
+        let fill = &self.config.fill;
+        let quantity = self.config.quantity;
+        let mut trades = Vec::new();
+        let mut open: Option<OpenTrade> = None;
+        let mut pending: Option<Option<Side>> = None;
+        let mut realized = 0.0;
+        let mut equity_curve = Vec::with_capacity(candles.len());
+
+        for candle in candles {
+            if let Some(target) = pending.take() {
+                if let Some(trade) = open.take() {
+                    let exit_side = opposite(&trade.side);
+                    let exit_price = fill.slipped(candle.open, &exit_side);
+                    let gross =
+                        (exit_price - trade.entry_price) * quantity * direction(&trade.side);
+                    realized += gross - fill.commission;
+                    trades.push(BacktestTrade {
+                        side: trade.side,
+                        quantity,
+                        entry_time: trade.entry_time,
+                        entry_price: trade.entry_price,
+                        exit_time: candle.timestamp,
+                        exit_price,
+                        commission: trade.commission + fill.commission,
+                        pnl: gross - trade.commission - fill.commission,
+                    });
+                }
+                if let Some(side) = target {
+                    realized -= fill.commission;
+                    open = Some(OpenTrade {
+                        entry_price: fill.slipped(candle.open, &side),
+                        side,
+                        entry_time: candle.timestamp,
+                        commission: fill.commission,
+                    });
+                }
+            }
+
+            strategy.update(&MarketData::Candle(*candle));
+            if strategy.is_ready() {
+                let target = match strategy.signal() {
+                    Some(Side::Buy) => Some(Some(Side::Buy)),
+                    Some(Side::Sell) if self.config.allow_short => Some(Some(Side::Sell)),
+                    Some(Side::Sell) => Some(None),
+                    None => None,
+                };
+                let current = open.as_ref().map(|trade| &trade.side);
+                if let Some(target) = target {
+                    if target.as_ref() != current {
+                        pending = Some(target);
+                    }
+                }
+            }
+
+            let unrealized = open.as_ref().map_or(0.0, |trade| {
+                (candle.close - trade.entry_price) * quantity * direction(&trade.side)
+            });
+            equity_curve.push(self.config.initial_capital + realized + unrealized);
+        }
+
+        let final_equity = equity_curve
+            .last()
+            .copied()
+            .unwrap_or(self.config.initial_capital);
+        let wins = trades.iter().filter(|trade| trade.is_win()).count();
+        BacktestReport {
+            win_rate: if trades.is_empty() {
+                0.0
+            } else {
+                wins as f64 / trades.len() as f64
+            },
+            total_return: final_equity / self.config.initial_capital - 1.0,
+            max_drawdown: max_drawdown(self.config.initial_capital, &equity_curve),
+            sharpe: sharpe(
+                self.config.initial_capital,
+                &equity_curve,
+                self.config.periods_per_year,
+            ),
+            trades,
+            equity_curve,
+            final_equity,
+        }
+    }
+
+    /// Splits `parent` with `strategy` and executes the children against `candles`
+    ///
+    /// A child starts at the first candle at or after its `insert_at`, the first candle
+    /// when it has none, and takes what it can of the candle volume left to the split,
+    /// `max_participation` of the volume at most, carrying its remainder over to the next
+    /// candles. Children share a candle in the order of the split. A market child fills at
+    /// the open with slippage; a limit child fills at the same price, bounded by its limit,
+    /// and only on candles that trade through the limit.
+    pub fn run_split(
+        &self,
+        parent: &ParentOrder,
+        strategy: &dyn OrderSplitStrategy,
+        candles: &[Candle],
+    ) -> SplitReport {
+        let fill = &self.config.fill;
+        let parent_order = &parent.order_common;
+        let side = &parent_order.side;
+        let children = strategy.split(parent);
+
+        let starts: Vec<Option<usize>> = children
+            .iter()
+            .map(|child| match child.insert_at {
+                None => (!candles.is_empty()).then_some(0),
+                Some(insert_at) => candles.iter().position(|candle| {
+                    epoch_millis::to_millis(&candle.timestamp) >= insert_at.as_millis()
+                }),
+            })
+            .collect();
+        let mut executions: Vec<ChildExecution> = children
+            .iter()
+            .map(|child| ChildExecution {
+                child_id: child.order_common.id.clone(),
+                quantity: child.order_common.quantity.to_f64(),
+                filled_quantity: 0.0,
+                average_price: 0.0,
+                fills: Vec::new(),
+            })
+            .collect();
+
+        for (index, candle) in candles.iter().enumerate() {
+            let mut available = candle.volume * fill.max_participation;
+            for (position, child) in children.iter().enumerate() {
+                let execution = &mut executions[position];
+                let remaining = execution.quantity - execution.filled_quantity;
+                if available <= 0.0 || remaining <= 0.0 {
+                    continue;
+                }
+                if !matches!(starts[position], Some(start) if start <= index) {
+                    continue;
+                }
+                let mut price = fill.slipped(candle.open, side);
+                if let Some(limit) = child.order_common.price.map(decimal_to_f64) {
+                    let crossed = match side {
+                        Side::Buy => candle.low <= limit,
+                        Side::Sell => candle.high >= limit,
+                    };
+                    if !crossed {
+                        continue;
+                    }
+                    price = match side {
+                        Side::Buy => price.min(limit),
+                        Side::Sell => price.max(limit),
+                    };
+                }
+                let quantity = remaining.min(available);
+                available -= quantity;
+                execution.average_price = (execution.average_price * execution.filled_quantity
+                    + price * quantity)
+                    / (execution.filled_quantity + quantity);
+                execution.filled_quantity += quantity;
+                execution.fills.push(SimulatedFill {
+                    timestamp: candle.timestamp,
+                    quantity,
+                    price,
+                });
+            }
+        }
+
+        let filled_quantity: f64 = executions.iter().map(|child| child.filled_quantity).sum();
+        let notional: f64 = executions
+            .iter()
+            .map(|child| child.average_price * child.filled_quantity)
+            .sum();
+        let fills = executions
+            .iter()
+            .map(|child| child.fills.len())
+            .sum::<usize>();
+        let average_price = if filled_quantity > 0.0 {
+            notional / filled_quantity
+        } else {
+            0.0
+        };
+        let arrival_price = candles.first().map_or(0.0, |candle| candle.open);
+        let shortfall_bps = if filled_quantity > 0.0 && arrival_price > 0.0 {
+            (average_price - arrival_price) / arrival_price * 10_000.0 * direction(side)
+        } else {
+            0.0
+        };
+        let quantity = parent_order.quantity.to_f64();
+        SplitReport {
+            parent_id: parent_order.id.clone(),
+            side: side.clone(),
+            quantity,
+            filled_quantity,
+            fill_rate: if quantity > 0.0 {
+                filled_quantity / quantity
+            } else {
+                0.0
+            },
+            average_price,
+            arrival_price,
+            vwap: vwap(candles),
+            shortfall_bps,
+            commission: fills as f64 * fill.commission,
+            seed: strategy.seed(),
+            children: executions,
+        }
+    }
+}
+
+/// Reads candles from CSV with a header row of `timestamp` (epoch milliseconds), `open`,
+/// `high`, `low`, `close` and `volume`
+#[cfg(feature = "csv")]
+pub fn candles_from_csv<R: std::io::Read>(reader: R) -> Result<Vec<Candle>, csv::Error> {
+    csv::Reader::from_reader(reader).deserialize().collect()
+}
+
+fn opposite(side: &Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
+fn direction(side: &Side) -> f64 {
+    match side {
+        Side::Buy => 1.0,
+        Side::Sell => -1.0,
+    }
+}
+
+fn max_drawdown(initial_capital: f64, equity_curve: &[f64]) -> f64 {
+    let mut peak = initial_capital;
+    let mut drawdown: f64 = 0.0;
+    for &equity in equity_curve {
+        peak = peak.max(equity);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - equity) / peak);
+        }
+    }
+    drawdown
+}
+
+/// Zero with fewer than two returns or when the returns do not vary
+fn sharpe(initial_capital: f64, equity_curve: &[f64], periods_per_year: f64) -> f64 {
+    let mut previous = initial_capital;
+    let returns: Vec<f64> = equity_curve
+        .iter()
+        .map(|&equity| {
+            let change = equity / previous - 1.0;
+            previous = equity;
+            change
+        })
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let count = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / count;
+    let variance = returns
+        .iter()
+        .map(|change| (change - mean).powi(2))
+        .sum::<f64>()
+        / (count - 1.0);
+    let deviation = variance.sqrt();
+    if deviation <= f64::EPSILON {
+        return 0.0;
+    }
+    mean / deviation * periods_per_year.sqrt()
+}
+
+fn vwap(candles: &[Candle]) -> f64 {
+    let volume: f64 = candles.iter().map(|candle| candle.volume).sum();
+    if volume <= 0.0 {
+        return 0.0;
+    }
+    candles
+        .iter()
+        .map(|candle| (candle.high + candle.low + candle.close) / 3.0 * candle.volume)
+        .sum::<f64>()
+        / volume
+}
@@ -0,0 +1,447 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+//! Paper trading venue matching child orders against recorded order books.
+
+use crate::models::decimal::{decimal_from_f64, decimal_to_f64, Decimal};
+use crate::models::fills::{apply_fill, Fill, Liquidity};
+use crate::models::market_data::OrderBook;
+use crate::models::orders::{OrderStatus, OrderType, Side, TimeInForce};
+use crate::models::quantity::Quantity;
+use crate::models::timestamp::Timestamp;
+use crate::models::ChildOrder;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// Reasons the simulated exchange refuses an order or a cancel.
+#[derive(Debug, Error, PartialEq)]
+pub enum SimulatedExchangeError {
+    #[error("order {order_id} was already submitted")]
+    DuplicateOrder { order_id: String },
+    #[error("order {order_id} is neither a market nor a limit order")]
+    UnsupportedOrderType { order_id: String },
+    #[error("limit order {order_id} has no price")]
+    MissingPrice { order_id: String },
+    #[error("order {order_id} is not known to the exchange")]
+    UnknownOrder { order_id: String },
+    #[error("order {order_id} is already {status:?}")]
+    OrderClosed {
+        order_id: String,
+        status: OrderStatus,
+    },
+}
+
+/// How the simulated exchange treats orders
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulatedExchangeConfig {
+    /// Time between the submission of an order and its arrival at the book
+    pub latency_ms: u64,
+    /// Chance, between 0 and 1, that a resting limit order fills on a book update that
+    /// crosses it; orders taking liquidity always fill
+    pub fill_probability: f64,
+    /// Seed of the draws against `fill_probability`, so runs can be repeated
+    pub seed: u64,
+}
+
+impl Default for SimulatedExchangeConfig {
+    fn default() -> Self {
+        SimulatedExchangeConfig {
+            latency_ms: 0,
+            fill_probability: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+/// What happened to a submitted order, in the order it happened
+#[derive(Clone, PartialEq)]
+pub enum ExchangeEvent {
+    Fill(Fill),
+    Status {
+        order_id: String,
+        status: OrderStatus,
+        timestamp: Timestamp,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+struct Book {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl Book {
+    fn from_snapshot(snapshot: &OrderBook) -> Self {
+        let levels = |levels: &[(f64, f64)]| {
+            let mut book = BTreeMap::new();
+            for &(price, size) in levels {
+                if size > 0.0 {
+                    *book.entry(decimal_from_f64(price)).or_default() += decimal_from_f64(size);
+                }
+            }
+            book
+        };
+        Book {
+            bids: levels(&snapshot.bids),
+            asks: levels(&snapshot.asks),
+        }
+    }
+
+    fn to_snapshot(&self) -> OrderBook {
+        let levels = |levels: &mut dyn Iterator<Item = (&Decimal, &Decimal)>| {
+            levels
+                .map(|(price, size)| (decimal_to_f64(*price), decimal_to_f64(*size)))
+                .collect()
+        };
+        OrderBook {
+            bids: levels(&mut self.bids.iter().rev()),
+            asks: levels(&mut self.asks.iter()),
+        }
+    }
+
+    /// Removes up to `quantity` from the levels an order on `side` trades against, best
+    /// price first and no worse than `limit`; returns the prices and sizes taken
+    fn take(
+        &mut self,
+        side: &Side,
+        limit: Option<Decimal>,
+        mut quantity: Decimal,
+    ) -> Vec<(Decimal, Decimal)> {
+        let levels = match side {
+            Side::Buy => &mut self.asks,
+            Side::Sell => &mut self.bids,
+        };
+        let prices: Vec<Decimal> = match side {
+            Side::Buy => levels.keys().copied().collect(),
+            Side::Sell => levels.keys().rev().copied().collect(),
+        };
+        let mut taken = Vec::new();
+        for price in prices {
+            let within = match (side, limit) {
+                (_, None) => true,
+                (Side::Buy, Some(limit)) => price <= limit,
+                (Side::Sell, Some(limit)) => price >= limit,
+            };
+            if quantity.is_zero() || !within {
+                break;
+            }
+            let size = levels[&price];
+            let traded = size.min(quantity);
+            quantity -= traded;
+            if traded == size {
+                levels.remove(&price);
+            } else {
+                levels.insert(price, size - traded);
+            }
+            taken.push((price, traded));
+        }
+        taken
+    }
+}
+
+struct SimulatedOrder {
+    child: ChildOrder,
+    status: OrderStatus,
+}
+
+/// Matches child orders against order books fed from recorded market data instead of
+/// sending them to a venue
+///
+/// An order reaches the book `latency_ms` after it is submitted and sees the book of the
+/// last snapshot before its arrival. A market order walks the opposite side of the book,
+/// level by level, and what the book cannot fill is cancelled. A limit order takes what it
+/// can up to its price and rests for the remainder, unless it is `IOC`; a resting order
+/// fills at its price from the opposite levels that cross it on later book updates. Fills
+/// take the volume they trade off the simulated book until the next snapshot replaces it.
+/// Every fill and status change is queued as an `ExchangeEvent`, see `drain_events`.
+pub struct SimulatedExchange {
+    config: SimulatedExchangeConfig,
+    books: HashMap<String, Book>,
+    orders: HashMap<String, SimulatedOrder>,
+    /// Submitted orders on their way to the book, with their arrival time
+    pending: Vec<(Timestamp, String)>,
+    /// Resting limit orders, in time priority
+    resting: Vec<String>,
+    events: Vec<ExchangeEvent>,
+    executions: u64,
+    rng: StdRng,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        SimulatedExchange::new(SimulatedExchangeConfig::default())
+    }
+}
+
+impl SimulatedExchange {
+    pub fn new(config: SimulatedExchangeConfig) -> Self {
+        SimulatedExchange {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            books: HashMap::new(),
+            orders: HashMap::new(),
+            pending: Vec::new(),
+            resting: Vec::new(),
+            events: Vec::new(),
+            executions: 0,
+        }
+    }
+
+    pub fn config(&self) -> &SimulatedExchangeConfig {
+        &self.config
+    }
+
+    /// Current simulated book of `symbol`, fills taken off it
+    pub fn book(&self, symbol: &str) -> Option<OrderBook> {
+        self.books.get(symbol).map(Book::to_snapshot)
+    }
+
+    /// The order as submitted, with its filled quantity and average fill price so far
+    pub fn order(&self, order_id: &str) -> Option<&ChildOrder> {
+        self.orders.get(order_id).map(|order| &order.child)
+    }
+
+    pub fn status(&self, order_id: &str) -> Option<OrderStatus> {
+        self.orders.get(order_id).map(|order| order.status)
+    }
+
+    /// Takes the events queued since the last call
+    pub fn drain_events(&mut self) -> Vec<ExchangeEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Sends `child` to the exchange at `now`; it is matched once it arrives
+    pub fn submit(
+        &mut self,
+        child: ChildOrder,
+        now: Timestamp,
+    ) -> Result<(), SimulatedExchangeError> {
+        let order = &child.order_common;
+        let order_id = order.id.clone();
+        if self.orders.contains_key(&order_id) {
+            return Err(SimulatedExchangeError::DuplicateOrder { order_id });
+        }
+        match order.order_type {
+            OrderType::Market => {}
+            OrderType::Limit if order.price.is_none() => {
+                return Err(SimulatedExchangeError::MissingPrice { order_id })
+            }
+            OrderType::Limit => {}
+            _ => return Err(SimulatedExchangeError::UnsupportedOrderType { order_id }),
+        }
+        let arrival =
+            Timestamp::from_millis(now.as_millis().saturating_add(self.config.latency_ms));
+        self.orders.insert(
+            order_id.clone(),
+            SimulatedOrder {
+                child,
+                status: OrderStatus::New,
+            },
+        );
+        self.pending.push((arrival, order_id));
+        self.advance(now);
+        Ok(())
+    }
+
+    /// Cancels an order on its way to the book or resting in it
+    pub fn cancel(&mut self, order_id: &str, now: Timestamp) -> Result<(), SimulatedExchangeError> {
+        let order =
+            self.orders
+                .get(order_id)
+                .ok_or_else(|| SimulatedExchangeError::UnknownOrder {
+                    order_id: order_id.to_string(),
+                })?;
+        if !order.status.is_open() {
+            return Err(SimulatedExchangeError::OrderClosed {
+                order_id: order_id.to_string(),
+                status: order.status,
+            });
+        }
+        self.pending.retain(|(_, id)| id != order_id);
+        self.resting.retain(|id| id != order_id);
+        self.set_status(order_id, OrderStatus::Cancelled, now);
+        Ok(())
+    }
+
+    /// Replaces the book of `symbol` with a snapshot taken at `at`
+    ///
+    /// Orders arriving up to `at` are matched against the previous book first; resting
+    /// orders are then matched against the new one.
+    pub fn on_book(&mut self, symbol: &str, snapshot: &OrderBook, at: Timestamp) {
+        self.advance(at);
+        self.books
+            .insert(symbol.to_string(), Book::from_snapshot(snapshot));
+        self.match_resting(symbol, at);
+    }
+
+    /// Sets the size of one level of the book of `symbol` at `at`, bids for `Buy` and asks
+    /// for `Sell`; a size of zero removes the level
+    pub fn on_level(&mut self, symbol: &str, side: &Side, price: f64, size: f64, at: Timestamp) {
+        self.advance(at);
+        let book = self.books.entry(symbol.to_string()).or_default();
+        let levels = match side {
+            Side::Buy => &mut book.bids,
+            Side::Sell => &mut book.asks,
+        };
+        let price = decimal_from_f64(price);
+        if size > 0.0 {
+            levels.insert(price, decimal_from_f64(size));
+        } else {
+            levels.remove(&price);
+        }
+        self.match_resting(symbol, at);
+    }
+
+    /// Matches the orders that have arrived by `now` against the current books
+    pub fn advance(&mut self, now: Timestamp) {
+        let (arrived, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(arrival, _)| *arrival <= now);
+        self.pending = pending;
+        for (arrival, order_id) in arrived {
+            self.arrive(&order_id, arrival);
+        }
+    }
+
+    fn arrive(&mut self, order_id: &str, at: Timestamp) {
+        self.events.push(ExchangeEvent::Status {
+            order_id: order_id.to_string(),
+            status: OrderStatus::New,
+            timestamp: at,
+        });
+        let order = &self.orders[order_id].child.order_common;
+        let (symbol, side, limit) = (order.symbol.clone(), order.side.clone(), order.price);
+        let leaves = order.leaves_quantity().value();
+        let immediate =
+            order.order_type == OrderType::Market || order.timeinforce == Some(TimeInForce::IOC);
+
+        let taken = match self.books.get_mut(&symbol) {
+            Some(book) => book.take(&side, limit, leaves),
+            None => Vec::new(),
+        };
+        for (price, quantity) in taken {
+            self.fill(order_id, price, quantity, Liquidity::Taker, at);
+        }
+        if self.orders[order_id].status.is_open() {
+            if immediate {
+                self.set_status(order_id, OrderStatus::Cancelled, at);
+            } else {
+                self.resting.push(order_id.to_string());
+            }
+        }
+    }
+
+    fn match_resting(&mut self, symbol: &str, at: Timestamp) {
+        for order_id in self.resting.clone() {
+            let order = &self.orders[&order_id].child.order_common;
+            if order.symbol != symbol {
+                continue;
+            }
+            let (side, limit) = (order.side.clone(), order.price);
+            let leaves = order.leaves_quantity().value();
+            let Some(book) = self.books.get_mut(symbol) else {
+                return;
+            };
+            let crossed = match (
+                &side,
+                limit,
+                book.bids.keys().next_back(),
+                book.asks.keys().next(),
+            ) {
+                (Side::Buy, Some(limit), _, Some(ask)) => *ask <= limit,
+                (Side::Sell, Some(limit), Some(bid), _) => *bid >= limit,
+                _ => false,
+            };
+            if !crossed || self.rng.random::<f64>() >= self.config.fill_probability {
+                continue;
+            }
+            let quantity: Decimal = book
+                .take(&side, limit, leaves)
+                .into_iter()
+                .map(|(_, size)| size)
+                .sum();
+            if let (Some(limit), false) = (limit, quantity.is_zero()) {
+                self.fill(&order_id, limit, quantity, Liquidity::Maker, at);
+            }
+            if !self.orders[&order_id].status.is_open() {
+                self.resting.retain(|id| *id != order_id);
+            }
+        }
+    }
+
+    fn fill(
+        &mut self,
+        order_id: &str,
+        price: Decimal,
+        quantity: Decimal,
+        liquidity: Liquidity,
+        at: Timestamp,
+    ) {
+        self.executions += 1;
+        let fill = Fill::new(
+            order_id.to_string(),
+            format!("sim-{}", self.executions),
+            price,
+            Quantity::new(quantity),
+            at.as_millis(),
+            Decimal::ZERO,
+            liquidity,
+        );
+        let order = self
+            .orders
+            .get_mut(order_id)
+            .expect("fills are only made for submitted orders");
+        apply_fill(&mut order.child.order_common, &fill)
+            .expect("fills never exceed the leaves quantity");
+        let status = if order.child.order_common.is_filled() {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        let changed = status != order.status;
+        self.events.push(ExchangeEvent::Fill(fill));
+        if changed {
+            self.set_status(order_id, status, at);
+        }
+    }
+
+    fn set_status(&mut self, order_id: &str, status: OrderStatus, at: Timestamp) {
+        if let Some(order) = self.orders.get_mut(order_id) {
+            order.status = status;
+        }
+        self.events.push(ExchangeEvent::Status {
+            order_id: order_id.to_string(),
+            status,
+            timestamp: at,
+        });
+    }
+}
@@ -26,61 +26,902 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::{KafkaClient, NatsClient, RabbitMQClient, RedisClient, ZeroMQClient};
+use crate::{
+    ChildOrder, ClientMetrics, CompressionConfig, Config, DeadLetter, DeadLetterPolicy,
+    EnvelopeMessage, EnvelopePayload, FanoutClient, FanoutPolicy, Fill, KafkaClient,
+    MessageEnvelope, MessageHandler, MessageStream, MeteredClient, MetricsSnapshot, MockClient,
+    NatsClient, OrderBook, ParentOrder, RabbitMQClient, RateLimiter, ReceivedEnvelope, RedisClient,
+    RetryPolicy, SubscriptionHandle, TopicsConfig, Validate, ZeroMQClient,
+};
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
 
 /// Trait for a messaging client.
-pub trait MessagingClient {
-    fn produce(&self, topic: &str, message: &str) -> Result<(), String>;
-    fn consume(&self, topic: &str) -> Result<String, String>;
+pub trait MessagingClient: Send + Sync + 'static {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError>;
+    fn consume(&self, topic: &str) -> Result<String, ClientError>;
+
+    /// Sends a binary payload. Transports without binary support only carry UTF-8.
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        let message = std::str::from_utf8(payload).map_err(|_| {
+            ClientError::Serialization(format!("Topic {} only carries UTF-8 payloads", topic))
+        })?;
+        self.produce(topic, message)
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        self.consume(topic).map(String::into_bytes)
+    }
+
+    /// Sends `message` with a routing key, which keeps messages sharing a key in order on
+    /// transports that partition topics. Transports without keys ignore it.
+    fn produce_keyed(&self, topic: &str, key: &str, message: &str) -> Result<(), ClientError> {
+        self.produce_bytes_keyed(topic, key, message.as_bytes())
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        _key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.produce_bytes(topic, payload)
+    }
+
+    /// Waits up to `timeout` for a message on `topic`, returning `Ok(None)` if none arrived.
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError>;
+
+    /// Returns a message already waiting on `topic`, or `Ok(None)` without blocking.
+    fn try_consume(&self, topic: &str) -> Result<Option<String>, ClientError> {
+        self.consume_timeout(topic, Duration::ZERO)
+    }
+
+    /// Calls `handler` with every message on `topic` from a background worker until the
+    /// returned handle is dropped or unsubscribed.
+    ///
+    /// By default a thread polls `consume_timeout`; clients with a push-based consumer
+    /// override it.
+    fn subscribe(
+        self: Arc<Self>,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        Ok(SubscriptionHandle::poll(self, topic, handler))
+    }
+
+    /// Receives the messages on `topic` as an async stream, for tokio-based consumers, until
+    /// the stream is dropped.
+    ///
+    /// By default a thread polls `consume_timeout`; clients with an async consumer override
+    /// it.
+    fn consume_stream(self: Arc<Self>, topic: &str) -> MessageStream {
+        MessageStream::poll(self, topic)
+    }
+
+    /// Flushes what the client still has to send, releases its broker resources and refuses
+    /// every later call with `ClientError::Closed`, which also ends its subscriptions and
+    /// streams.
+    ///
+    /// Produces still running get up to `timeout` to finish. Closing a closed client does
+    /// nothing; by default closing does nothing at all.
+    fn close(&self, _timeout: Duration) -> Result<(), ClientError> {
+        Ok(())
+    }
+
+    /// Sends `message` to `topic` and waits up to `timeout` for the reply, which is returned.
+    ///
+    /// NATS uses its native request-reply. Kafka, Redis and the mock client send a
+    /// `RequestMessage` naming a reply topic of the client's own, which the responder answers
+    /// with a `ReplyMessage`. Other clients do not support requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Timeout` when no reply arrived in time.
+    fn request(
+        &self,
+        topic: &str,
+        _message: &str,
+        _timeout: Duration,
+    ) -> Result<String, ClientError> {
+        Err(ClientError::Transport(format!(
+            "Request on {} failed: this client does not support request-reply",
+            topic
+        )))
+    }
+
+    /// Replies dropped since the client was created because no request was waiting for them,
+    /// mostly ones that arrived after their request timed out.
+    fn late_replies(&self) -> u64 {
+        0
+    }
+
+    /// Probes the broker, for liveness and readiness checks.
+    ///
+    /// Clients that cannot probe their broker report healthy without checking.
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        Ok(HealthStatus::default())
+    }
+
+    /// Counts of each backend behind a client that spreads messages over several, such as
+    /// a `FanoutClient`; empty for a single broker.
+    fn backend_metrics(&self) -> Vec<MetricsSnapshot> {
+        Vec::new()
+    }
+}
+
+/// How long a health check waits for the broker to answer.
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a service or client dropped without being closed gives itself to close.
+pub const CLOSE_ON_DROP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a successful health check found out about the broker.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// How long the probe took; zero when the client did not probe.
+    pub latency: Duration,
+    /// Which broker answered, when the transport says.
+    pub broker: Option<String>,
+    /// Version of the broker software, when the transport says.
+    pub version: Option<String>,
+}
+
+/// Wire encoding of the typed messages on a topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    /// Binary MessagePack, smaller and faster to encode than JSON
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl Encoding {
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ClientError> {
+        let encoded = match self {
+            Encoding::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Encoding::MsgPack => {
+                crate::models::msgpack::to_msgpack(value).map_err(|e| e.to_string())
+            }
+        };
+        encoded.map_err(ClientError::Serialization)
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, payload: &[u8]) -> Result<T, ClientError> {
+        let decoded = match self {
+            Encoding::Json => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Encoding::MsgPack => {
+                crate::models::msgpack::from_msgpack(payload).map_err(|e| e.to_string())
+            }
+        };
+        decoded.map_err(|reason| ClientError::Deserialization {
+            payload: payload_excerpt(payload),
+            reason,
+        })
+    }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClientType {
     Kafka,
     Redis,
     Nats,
     RabbitMQ,
     ZeroMQ,
+    /// A `MockClient`, which needs no broker or configuration.
+    InMemory,
+    /// A `FanoutClient` over clients of these types, the first being the primary.
+    Fanout(Vec<ClientType>, FanoutPolicy),
+}
+
+impl ClientType {
+    /// The `Config` section the client is built from, and the variable that enables it.
+    fn config_section(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ClientType::Kafka => Some(("kafka", "KAFKA_URL")),
+            ClientType::Redis => Some(("redis", "REDIS_URL")),
+            ClientType::Nats => Some(("nats", "NATS_URL")),
+            ClientType::RabbitMQ => Some(("rabbitmq", "RABBITMQ_URL")),
+            ClientType::ZeroMQ => Some(("zeromq", "ZMQ_URL")),
+            ClientType::InMemory | ClientType::Fanout(..) => None,
+        }
+    }
+
+    /// The default topics and retry policy of the client's `Config` section. A fanout takes
+    /// those of its primary, and a client without a section the defaults.
+    fn settings(&self, config: &Config) -> (TopicsConfig, RetryPolicy) {
+        let section = match self {
+            ClientType::Kafka => config.kafka.as_ref().map(|c| (&c.topics, &c.retry)),
+            ClientType::Redis => config.redis.as_ref().map(|c| (&c.topics, &c.retry)),
+            ClientType::Nats => config.nats.as_ref().map(|c| (&c.topics, &c.retry)),
+            ClientType::RabbitMQ => config.rabbitmq.as_ref().map(|c| (&c.topics, &c.retry)),
+            ClientType::ZeroMQ => config.zeromq.as_ref().map(|c| (&c.topics, &c.retry)),
+            ClientType::InMemory => None,
+            ClientType::Fanout(backends, _) => {
+                return backends
+                    .first()
+                    .map(|primary| primary.settings(config))
+                    .unwrap_or_default()
+            }
+        };
+        section
+            .map(|(topics, retry)| (topics.clone(), retry.clone()))
+            .unwrap_or_default()
+    }
+}
+
+/// Errors raised while building a messaging client or exchanging messages through it.
+///
+/// `is_retriable` tells transient broker trouble, worth another attempt, apart from
+/// problems with the message or the setup.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// The configuration section the client type needs is not set.
+    #[error("missing {section} configuration, set {env_var}")]
+    MissingConfig {
+        section: &'static str,
+        env_var: &'static str,
+    },
+    /// The configuration is set but the client cannot be built from it.
+    #[error("invalid {section} configuration: {reason}")]
+    InvalidConfig {
+        section: &'static str,
+        reason: String,
+    },
+    /// The broker cannot be reached or the connection to it was lost.
+    #[error("{0}")]
+    Connection(String),
+    /// No message arrived on `topic` in time.
+    #[error("No message on topic {topic} within {timeout_ms} ms")]
+    Timeout { topic: String, timeout_ms: u64 },
+    /// The broker rejected the credentials, or the client may not use the topic.
+    #[error("{0}")]
+    Auth(String),
+    /// The topic, subject or queue does not exist on the broker.
+    #[error("Topic {0} does not exist")]
+    TopicNotFound(String),
+    /// The broker did not accept a message.
+    #[error("{detail}")]
+    Delivery { retriable: bool, detail: String },
+    /// Any other failure of the broker or transport.
+    #[error("{0}")]
+    Transport(String),
+    /// A message arrived but its payload is not what the topic carries.
+    #[error("invalid message payload: {0}")]
+    InvalidPayload(String),
+    /// A value could not be encoded for sending.
+    #[error("Error serializing message: {0}")]
+    Serialization(String),
+    /// A payload does not decode into the expected type; `payload` holds its start.
+    #[error("Error deserializing message payload {payload:?}: {reason}")]
+    Deserialization { payload: String, reason: String },
+    /// An order failed validation before it was sent or after it was received.
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    /// The client was closed and takes no further calls.
+    #[error("The messaging client is closed")]
+    Closed,
+    /// An envelope holds another message type than the one asked for.
+    #[error("Expected a {expected} message, got {found}")]
+    UnexpectedMessageType {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl ClientError {
+    /// Returns true when the same call may succeed if repeated later.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            ClientError::Connection(_) | ClientError::Timeout { .. } => true,
+            ClientError::Delivery { retriable, .. } => *retriable,
+            _ => false,
+        }
+    }
+}
+
+/// Longest excerpt of a bad payload kept in `ClientError::Deserialization`.
+const PAYLOAD_EXCERPT_CHARS: usize = 200;
+
+fn payload_excerpt(payload: &[u8]) -> String {
+    let text = String::from_utf8_lossy(payload);
+    match text.char_indices().nth(PAYLOAD_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.into_owned(),
+    }
+}
+
+/// Opens an order envelope sent with `encoding` and validates the order in it.
+fn open_validated<T: EnvelopePayload + Validate>(
+    encoding: Encoding,
+    payload: &[u8],
+) -> Result<T, ClientError> {
+    let order: T = encoding.decode::<MessageEnvelope>(payload)?.open()?;
+    validated(&order)?;
+    Ok(order)
+}
+
+fn validated<T: Validate>(order: &T) -> Result<(), ClientError> {
+    order
+        .validate()
+        .map_err(|e| ClientError::InvalidOrder(e.to_string()))
+}
+
+/// Turns a received payload into text, for clients carrying binary messages.
+pub(crate) fn utf8_payload(payload: Vec<u8>) -> Result<String, ClientError> {
+    String::from_utf8(payload).map_err(|e| ClientError::InvalidPayload(e.to_string()))
+}
+
+/// A received message with what the broker reported about its delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumedMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// Id the broker gave the message, for brokers that number them.
+    pub message_id: Option<String>,
+    /// How often the broker has handed the message out, 1 on its first delivery.
+    pub delivery_count: u64,
+}
+
+impl ConsumedMessage {
+    /// A first delivery of `payload` on `topic`.
+    pub fn new(topic: &str, payload: Vec<u8>) -> Self {
+        ConsumedMessage {
+            topic: topic.to_string(),
+            payload,
+            message_id: None,
+            delivery_count: 1,
+        }
+    }
+
+    /// Whether the broker delivered the message before without it being acknowledged.
+    pub fn is_redelivery(&self) -> bool {
+        self.delivery_count > 1
+    }
+
+    /// The payload as text.
+    pub fn text(&self) -> Result<&str, ClientError> {
+        std::str::from_utf8(&self.payload).map_err(|e| ClientError::InvalidPayload(e.to_string()))
+    }
 }
 
 pub struct MessagingClientFactory;
 
 impl MessagingClientFactory {
-    pub fn create_client(client_type: ClientType) -> Box<dyn MessagingClient> {
-        match client_type {
-            ClientType::Kafka => Box::new(KafkaClient::new(
-                "localhost:9092".to_string(),  // 默认 broker
-                "default-group".to_string()    // 默认 group_id
+    /// Creates a client of `client_type` from its section of `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::MissingConfig` when that section is not set, and
+    /// `ClientError::InvalidConfig` when the client rejects it.
+    pub fn create_client(
+        client_type: ClientType,
+        config: &Config,
+    ) -> Result<Box<dyn MessagingClient>, ClientError> {
+        let Some((section, env_var)) = client_type.config_section() else {
+            return Self::create_composite(client_type, config);
+        };
+        let missing = || ClientError::MissingConfig { section, env_var };
+        let invalid = |reason: String| ClientError::InvalidConfig { section, reason };
+        let client: Box<dyn MessagingClient> = match client_type {
+            ClientType::Kafka => Box::new(KafkaClient::from_config(
+                config.kafka.as_ref().ok_or_else(missing)?,
+            )?),
+            ClientType::Redis => {
+                Box::new(RedisClient::new(config.redis.clone().ok_or_else(missing)?))
+            }
+            ClientType::Nats => Box::new(
+                NatsClient::from_config(config.nats.as_ref().ok_or_else(missing)?)
+                    .map_err(|e| invalid(e.to_string()))?,
+            ),
+            ClientType::RabbitMQ => Box::new(
+                RabbitMQClient::from_config(config.rabbitmq.as_ref().ok_or_else(missing)?)
+                    .map_err(|e| invalid(e.to_string()))?,
+            ),
+            ClientType::ZeroMQ => Box::new(ZeroMQClient::from_config(
+                config.zeromq.as_ref().ok_or_else(missing)?,
             )),
-            ClientType::Redis => Box::new(RedisClient::new()),
-            ClientType::Nats => Box::new(NatsClient::new()),
-            ClientType::RabbitMQ => Box::new(RabbitMQClient::new()),
-            ClientType::ZeroMQ => Box::new(ZeroMQClient::new()),
+            ClientType::InMemory | ClientType::Fanout(..) => {
+                unreachable!("{:?} has no configuration section", client_type)
+            }
+        };
+        Ok(client)
+    }
+
+    /// Creates the clients that are not a broker of their own.
+    fn create_composite(
+        client_type: ClientType,
+        config: &Config,
+    ) -> Result<Box<dyn MessagingClient>, ClientError> {
+        match client_type {
+            ClientType::InMemory => Ok(Box::new(MockClient::new())),
+            ClientType::Fanout(backends, policy) => {
+                let backends = backends
+                    .into_iter()
+                    .map(|backend| Self::create_client(backend, config))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Box::new(FanoutClient::new(backends, policy)?))
+            }
+            _ => unreachable!("{:?} is built from its configuration section", client_type),
         }
     }
 }
 
 pub struct MessagingService {
-    client: Box<dyn MessagingClient>,
+    client: Arc<dyn MessagingClient>,
+    encodings: HashMap<String, Encoding>,
+    compressions: HashMap<String, CompressionConfig>,
+    metrics: Arc<ClientMetrics>,
+    dead_letter: Option<(DeadLetterPolicy, RateLimiter)>,
+    topics: TopicsConfig,
+    retry: RetryPolicy,
+    /// The client's count of late replies when the metrics were last reset
+    late_replies_at_reset: AtomicU64,
+    closed: AtomicBool,
 }
 
 impl MessagingService {
-    pub fn new(client_type: ClientType) -> Self {
-        let client = MessagingClientFactory::create_client(client_type);
-        MessagingService { client }
+    /// Creates a service over a `client_type` client built from `config`, with the default
+    /// topics and retry policy of its section.
+    pub fn new(client_type: ClientType, config: &Config) -> Result<Self, ClientError> {
+        let (topics, retry) = client_type.settings(config);
+        let client = MessagingClientFactory::create_client(client_type, config)?;
+        let mut service = Self::with_client(client);
+        service.topics = topics;
+        service.retry = retry;
+        Ok(service)
+    }
+
+    /// Wraps an existing client, such as one built from configuration or a test double.
+    ///
+    /// Every call the service makes through the client is counted in its `metrics`.
+    pub fn with_client(client: Box<dyn MessagingClient>) -> Self {
+        let metrics = Arc::new(ClientMetrics::default());
+        MessagingService {
+            client: Arc::new(MeteredClient::new(Arc::from(client), metrics.clone())),
+            encodings: HashMap::new(),
+            compressions: HashMap::new(),
+            metrics,
+            dead_letter: None,
+            topics: TopicsConfig::default(),
+            retry: RetryPolicy::default(),
+            late_replies_at_reset: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Messages and errors counted since the service was created or its metrics reset.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let mut snapshot = self.metrics.snapshot();
+        snapshot.late_replies = self
+            .client
+            .late_replies()
+            .saturating_sub(self.late_replies_at_reset.load(Ordering::Relaxed));
+        snapshot
+    }
+
+    /// Counts of each backend when the client spreads messages over several; see
+    /// `MessagingClient::backend_metrics`.
+    pub fn backend_metrics(&self) -> Vec<MetricsSnapshot> {
+        self.client.backend_metrics()
+    }
+
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+        self.late_replies_at_reset
+            .store(self.client.late_replies(), Ordering::Relaxed);
+    }
+
+    /// Sends the typed messages that fail to decode or validate to a dead-letter topic
+    /// instead of returning them as errors.
+    pub fn set_dead_letter_policy(&mut self, policy: DeadLetterPolicy) {
+        let limiter = RateLimiter::new(policy.max_per_second);
+        self.dead_letter = Some((policy, limiter));
+    }
+
+    pub fn dead_letter_policy(&self) -> Option<&DeadLetterPolicy> {
+        self.dead_letter.as_ref().map(|(policy, _)| policy)
+    }
+
+    /// Topics the typed messages go to by default, from the client's configuration section.
+    pub fn topics(&self) -> &TopicsConfig {
+        &self.topics
     }
 
-    pub fn produce(&self, topic: &str, message: &str) -> Result<(), String> {
-        self.client.produce(topic, message)
+    pub fn set_topics(&mut self, topics: TopicsConfig) {
+        self.topics = topics;
     }
 
-    pub fn consume(&self, topic: &str) -> Result<String, String> {
+    /// Repeats produces that fail with a retriable error as `policy` says; by default a
+    /// produce is tried once.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry = policy;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Sets the encoding of the typed messages on `topic`; topics default to JSON.
+    pub fn set_encoding(&mut self, topic: &str, encoding: Encoding) {
+        self.encodings.insert(topic.to_string(), encoding);
+    }
+
+    pub fn encoding(&self, topic: &str) -> Encoding {
+        self.encodings.get(topic).copied().unwrap_or_default()
+    }
+
+    /// Sets how the envelopes sent on `topic` are compressed; topics default to none.
+    ///
+    /// Consumers need no setting: a compressed envelope says so in its headers.
+    pub fn set_compression(&mut self, topic: &str, compression: CompressionConfig) {
+        self.compressions.insert(topic.to_string(), compression);
+    }
+
+    pub fn compression(&self, topic: &str) -> CompressionConfig {
+        self.compressions.get(topic).copied().unwrap_or_default()
+    }
+
+    /// Encodes `value` with the topic's encoding and sends it.
+    pub fn produce_encoded<T: Serialize>(&self, topic: &str, value: &T) -> Result<(), ClientError> {
+        let payload = self.encoding(topic).encode(value)?;
+        self.retrying(|| self.client.produce_bytes(topic, &payload))
+    }
+
+    /// Encodes `value` with the topic's encoding and sends it under the routing key `key`.
+    pub fn produce_encoded_keyed<T: Serialize>(
+        &self,
+        topic: &str,
+        key: &str,
+        value: &T,
+    ) -> Result<(), ClientError> {
+        let payload = self.encoding(topic).encode(value)?;
+        self.retrying(|| self.client.produce_bytes_keyed(topic, key, &payload))
+    }
+
+    /// Receives a message from `topic` and decodes it with the topic's encoding.
+    pub fn consume_decoded<T: DeserializeOwned>(&self, topic: &str) -> Result<T, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| encoding.decode(payload))
+    }
+
+    pub fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.retrying(|| self.client.produce(topic, message))
+    }
+
+    pub fn consume(&self, topic: &str) -> Result<String, ClientError> {
         self.client.consume(topic)
     }
+
+    pub fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.client.consume_timeout(topic, timeout)
+    }
+
+    pub fn try_consume(&self, topic: &str) -> Result<Option<String>, ClientError> {
+        self.client.try_consume(topic)
+    }
+
+    pub fn produce_keyed(&self, topic: &str, key: &str, message: &str) -> Result<(), ClientError> {
+        self.retrying(|| self.client.produce_keyed(topic, key, message))
+    }
+
+    /// Sends `value` as JSON regardless of the topic's encoding.
+    pub fn produce_json<T: Serialize>(&self, topic: &str, value: &T) -> Result<(), ClientError> {
+        let payload = Encoding::Json.encode(value)?;
+        self.retrying(|| self.client.produce_bytes(topic, &payload))
+    }
+
+    /// Sends `value` as JSON under the routing key `key`, regardless of the topic's encoding.
+    pub fn produce_json_keyed<T: Serialize>(
+        &self,
+        topic: &str,
+        key: &str,
+        value: &T,
+    ) -> Result<(), ClientError> {
+        let payload = Encoding::Json.encode(value)?;
+        self.retrying(|| self.client.produce_bytes_keyed(topic, key, &payload))
+    }
+
+    /// Receives a JSON message from `topic`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Deserialization` with the start of the payload when it is not
+    /// a `T`.
+    pub fn consume_json<T: DeserializeOwned>(&self, topic: &str) -> Result<T, ClientError> {
+        self.consume_with(topic, |payload| Encoding::Json.decode(payload))
+    }
+
+    /// Sends `envelope` with the topic's encoding, keyed by its correlation id so related
+    /// messages stay in order. Its payload is compressed first when large enough for the
+    /// topic's compression.
+    pub fn produce_envelope(
+        &self,
+        topic: &str,
+        envelope: &MessageEnvelope,
+    ) -> Result<(), ClientError> {
+        let compression = self.compression(topic);
+        if compression.applies_to(envelope.payload.len()) {
+            let compressed = envelope.compress(compression.compression)?;
+            return self.produce_encoded_keyed(topic, &envelope.correlation_id, &compressed);
+        }
+        self.produce_encoded_keyed(topic, &envelope.correlation_id, envelope)
+    }
+
+    /// Receives an envelope from `topic` and decodes its payload by message type.
+    ///
+    /// A message type this build does not know comes back as `EnvelopeMessage::Unknown`
+    /// next to the raw envelope, so the caller decides what to do with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Deserialization` when the message is not an envelope or its
+    /// payload does not match its type, and `ClientError::InvalidOrder` for an order that
+    /// does not pass validation.
+    pub fn consume_envelope(&self, topic: &str) -> Result<ReceivedEnvelope, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| {
+            let envelope: MessageEnvelope = encoding.decode(payload)?;
+            let message = envelope.decode()?;
+            match &message {
+                EnvelopeMessage::ParentOrder(order) => validated(order)?,
+                EnvelopeMessage::ChildOrder(order) => validated(order)?,
+                EnvelopeMessage::Fill(_)
+                | EnvelopeMessage::OrderBook(_)
+                | EnvelopeMessage::Unknown => {}
+            }
+            Ok(ReceivedEnvelope { envelope, message })
+        })
+    }
+
+    /// Validates `order` and sends it in an envelope correlated and keyed by its id.
+    pub fn produce_parent_order(
+        &self,
+        topic: &str,
+        order: &ParentOrder,
+    ) -> Result<(), ClientError> {
+        validated(order)?;
+        self.produce_envelope(
+            topic,
+            &MessageEnvelope::wrap(order, &order.order_common.id)?,
+        )
+    }
+
+    /// Validates `order` and sends it in an envelope correlated by its parent, which also
+    /// keys it so the children of one parent stay in order.
+    pub fn produce_child_order(&self, topic: &str, order: &ChildOrder) -> Result<(), ClientError> {
+        validated(order)?;
+        self.produce_envelope(topic, &MessageEnvelope::wrap(order, &order.parent_id)?)
+    }
+
+    /// Sends `fill` in an envelope correlated by `correlation_id`, usually the parent order's
+    /// id.
+    pub fn produce_fill(
+        &self,
+        topic: &str,
+        fill: &Fill,
+        correlation_id: &str,
+    ) -> Result<(), ClientError> {
+        self.produce_envelope(topic, &MessageEnvelope::wrap(fill, correlation_id)?)
+    }
+
+    /// Receives a parent order envelope, rejecting an order that does not pass validation.
+    pub fn consume_parent_order(&self, topic: &str) -> Result<ParentOrder, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| open_validated(encoding, payload))
+    }
+
+    /// Receives a child order envelope, rejecting an order that does not pass validation.
+    pub fn consume_child_order(&self, topic: &str) -> Result<ChildOrder, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| open_validated(encoding, payload))
+    }
+
+    /// Receives a fill envelope.
+    pub fn consume_fill(&self, topic: &str) -> Result<Fill, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| {
+            encoding.decode::<MessageEnvelope>(payload)?.open()
+        })
+    }
+
+    /// Sends an order book snapshot in an envelope correlated by `correlation_id`, e.g. the
+    /// symbol; see `set_compression` for large books.
+    pub fn produce_order_book(
+        &self,
+        topic: &str,
+        order_book: &OrderBook,
+        correlation_id: &str,
+    ) -> Result<(), ClientError> {
+        self.produce_envelope(topic, &MessageEnvelope::wrap(order_book, correlation_id)?)
+    }
+
+    /// Receives an order book envelope, decompressing it if it was sent compressed.
+    pub fn consume_order_book(&self, topic: &str) -> Result<OrderBook, ClientError> {
+        let encoding = self.encoding(topic);
+        self.consume_with(topic, |payload| {
+            encoding.decode::<MessageEnvelope>(payload)?.open()
+        })
+    }
+
+    /// Receives the messages on `topic` as an async stream; see
+    /// `MessagingClient::consume_stream`.
+    pub fn consume_stream(&self, topic: &str) -> MessageStream {
+        self.client.clone().consume_stream(topic)
+    }
+
+    /// Receives parent order envelopes from `topic` as a stream of validated orders.
+    ///
+    /// A message that does not decode or validate comes out as an error and the stream goes
+    /// on with the next one; the dead-letter policy does not apply to streams.
+    pub fn consume_parent_order_stream(
+        &self,
+        topic: &str,
+    ) -> impl Stream<Item = Result<ParentOrder, ClientError>> + Send + Unpin {
+        self.consume_order_stream(topic)
+    }
+
+    /// Receives child order envelopes from `topic` as a stream of validated orders; see
+    /// `consume_parent_order_stream`.
+    pub fn consume_child_order_stream(
+        &self,
+        topic: &str,
+    ) -> impl Stream<Item = Result<ChildOrder, ClientError>> + Send + Unpin {
+        self.consume_order_stream(topic)
+    }
+
+    fn consume_order_stream<T: EnvelopePayload + Validate>(
+        &self,
+        topic: &str,
+    ) -> impl Stream<Item = Result<T, ClientError>> + Send + Unpin {
+        let encoding = self.encoding(topic);
+        self.consume_stream(topic)
+            .map(move |message| open_validated(encoding, &message?.payload))
+    }
+
+    /// Sends `message` to `topic` and waits up to `timeout` for the reply; see
+    /// `MessagingClient::request`.
+    pub fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        self.client.request(topic, message, timeout)
+    }
+
+    /// Sends `request` as JSON to `topic` and decodes the JSON reply, e.g. an order
+    /// acknowledgment from the execution gateway.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Timeout` when no reply arrived within `timeout`, and
+    /// `ClientError::Deserialization` when the reply is not an `R`.
+    pub fn request_json<T: Serialize, R: DeserializeOwned>(
+        &self,
+        topic: &str,
+        request: &T,
+        timeout: Duration,
+    ) -> Result<R, ClientError> {
+        let request = utf8_payload(Encoding::Json.encode(request)?)?;
+        let reply = self.client.request(topic, &request, timeout)?;
+        Encoding::Json.decode(reply.as_bytes())
+    }
+
+    /// Calls `handler` with every message on `topic` until the handle is dropped.
+    pub fn subscribe(
+        &self,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        self.client.clone().subscribe(topic, handler)
+    }
+
+    /// Probes the active client's broker; an error means it cannot serve traffic.
+    pub fn health(&self) -> Result<HealthStatus, ClientError> {
+        self.client.health_check()
+    }
+
+    /// Flushes pending produces and closes the client; see `MessagingClient::close`.
+    ///
+    /// Every later call returns `ClientError::Closed`, and subscriptions and streams opened
+    /// through the service end.
+    pub fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.closed.store(true, Ordering::Relaxed);
+        self.client.close(timeout)
+    }
+
+    /// Runs `send`, repeating it after the policy's backoff while it fails with a retriable
+    /// error and retries are left. Every attempt is counted in the metrics.
+    fn retrying(&self, send: impl Fn() -> Result<(), ClientError>) -> Result<(), ClientError> {
+        let mut retry = 0;
+        loop {
+            match send() {
+                Err(error) if error.is_retriable() && retry < self.retry.max_retries => {
+                    std::thread::sleep(self.retry.backoff(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Receives a message from `topic` and turns it into a `T` with `decode`.
+    ///
+    /// A message `decode` rejects for good is dead-lettered, and the next one received, when
+    /// there is a dead-letter policy and its rate allows.
+    fn consume_with<T>(
+        &self,
+        topic: &str,
+        decode: impl Fn(&[u8]) -> Result<T, ClientError>,
+    ) -> Result<T, ClientError> {
+        loop {
+            let payload = self.client.consume_bytes(topic)?;
+            match decode(&payload) {
+                Err(error)
+                    if !error.is_retriable() && self.dead_letter(topic, &payload, &error) =>
+                {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Publishes `payload` to the dead-letter topic of `topic`, returning whether it did.
+    pub(crate) fn dead_letter(&self, topic: &str, payload: &[u8], error: &ClientError) -> bool {
+        let Some((policy, limiter)) = &self.dead_letter else {
+            return false;
+        };
+        if !limiter.try_acquire() {
+            return false;
+        }
+        let letter = DeadLetter::new(topic, payload, &error.to_string());
+        // A failed publish is counted in the metrics; the caller then gets the original error
+        if self
+            .produce_json(&policy.dead_letter_topic(topic), &letter)
+            .is_err()
+        {
+            return false;
+        }
+        self.metrics.record_dead_lettered();
+        true
+    }
+}
+
+/// Closes a service dropped without `close`, best effort, so pending produces are not lost.
+impl Drop for MessagingService {
+    fn drop(&mut self) {
+        if self.closed.load(Ordering::Relaxed) {
+            return;
+        }
+        log::warn!("Messaging service dropped without being closed, closing it now");
+        if let Err(error) = self.client.close(CLOSE_ON_DROP_TIMEOUT) {
+            log::warn!("Closing the dropped messaging service failed: {}", error);
+        }
+    }
 }
 
 /*******************************************************************************
 
-    let kafka_service = MessagingService::new(ClientType::Kafka);
+    let config = Config::new().unwrap();
+    let kafka_service = MessagingService::new(ClientType::Kafka, &config).unwrap();
     kafka_service.produce("test_topic", "Hello, Kafka!").unwrap();
     let message = kafka_service.consume("test_topic").unwrap();
     println!("Consumed message: {}", message);
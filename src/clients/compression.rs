@@ -0,0 +1,190 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::ClientError;
+
+/// Envelope header naming the compression of the payload; absent when it is plain JSON.
+pub const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+
+/// Payloads smaller than this, in bytes, are sent uncompressed unless configured otherwise.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// How the payload of an envelope is compressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Faster than gzip at a similar ratio
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The value of the `content-encoding` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::None => "identity",
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Reads a `content-encoding` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::InvalidPayload` for an encoding this build cannot decompress,
+    /// including one whose feature is disabled.
+    pub fn from_header(value: &str) -> Result<Self, ClientError> {
+        match value {
+            "identity" => Ok(Compression::None),
+            #[cfg(feature = "gzip")]
+            "gzip" => Ok(Compression::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(ClientError::InvalidPayload(format!(
+                "unsupported content encoding {}",
+                other
+            ))),
+        }
+    }
+
+    /// Compresses `payload` into text that fits an envelope: base64 of the compressed bytes.
+    pub fn pack(&self, payload: &str) -> Result<String, ClientError> {
+        match self {
+            Compression::None => Ok(payload.to_string()),
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression => compression
+                .compress(payload.as_bytes())
+                .map(|bytes| packed::encode(&bytes))
+                .map_err(|e| {
+                    ClientError::Serialization(format!("{} compression: {}", self.as_str(), e))
+                }),
+        }
+    }
+
+    /// Reverses `pack`.
+    pub fn unpack(&self, packed: &str) -> Result<String, ClientError> {
+        match self {
+            Compression::None => Ok(packed.to_string()),
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression => {
+                let invalid = |reason: String| {
+                    ClientError::InvalidPayload(format!("{} payload: {}", self.as_str(), reason))
+                };
+                let bytes = packed::decode(packed).map_err(invalid)?;
+                let bytes = compression
+                    .decompress(&bytes)
+                    .map_err(|e| invalid(e.to_string()))?;
+                String::from_utf8(bytes).map_err(|e| invalid(e.to_string()))
+            }
+        }
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn compress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                use std::io::Write;
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(data, 0),
+        }
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn decompress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                use std::io::Read;
+                let mut bytes = Vec::new();
+                flate2::read::GzDecoder::new(data).read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::decode_all(data),
+        }
+    }
+}
+
+/// How a topic's envelopes are compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub compression: Compression,
+    /// Smallest payload, in bytes, worth compressing; smaller ones are sent as they are.
+    pub threshold: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            compression: Compression::None,
+            threshold: DEFAULT_COMPRESSION_THRESHOLD,
+        }
+    }
+}
+
+impl CompressionConfig {
+    pub fn new(compression: Compression) -> Self {
+        CompressionConfig {
+            compression,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a payload of `len` bytes is to be compressed.
+    pub fn applies_to(&self, len: usize) -> bool {
+        self.compression != Compression::None && len >= self.threshold
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod packed {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    pub(super) fn encode(bytes: &[u8]) -> String {
+        STANDARD.encode(bytes)
+    }
+
+    pub(super) fn decode(text: &str) -> Result<Vec<u8>, String> {
+        STANDARD.decode(text).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,111 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Where `MessagingService` sends the messages it cannot decode or validate.
+///
+/// Without a policy such a message is returned as an error; with one it is published to the
+/// dead-letter topic as a [`DeadLetter`] and the next message is received instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterPolicy {
+    /// Name of the dead-letter topic, where `{topic}` stands for the source topic.
+    pub topic_pattern: String,
+    /// Most messages dead-lettered per second, so a stream of bad messages cannot flood the
+    /// dead-letter topic; the ones above it are returned as errors.
+    pub max_per_second: u32,
+}
+
+impl Default for DeadLetterPolicy {
+    fn default() -> Self {
+        DeadLetterPolicy {
+            topic_pattern: "{topic}.dlq".to_string(),
+            max_per_second: 10,
+        }
+    }
+}
+
+impl DeadLetterPolicy {
+    pub fn dead_letter_topic(&self, topic: &str) -> String {
+        self.topic_pattern.replace("{topic}", topic)
+    }
+}
+
+/// A rejected message as published to a dead-letter topic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub source_topic: String,
+    /// Why the message was rejected.
+    pub error: String,
+    pub failed_at: Timestamp,
+    /// The message as received; bytes that are not UTF-8 are replaced.
+    pub payload: String,
+}
+
+impl DeadLetter {
+    pub fn new(source_topic: &str, payload: &[u8], error: &str) -> Self {
+        DeadLetter {
+            source_topic: source_topic.to_string(),
+            error: error.to_string(),
+            failed_at: Timestamp::now(),
+            payload: String::from_utf8_lossy(payload).into_owned(),
+        }
+    }
+}
+
+/// Lets at most `max_per_second` calls through in each one-second window.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    max_per_second: u32,
+    /// Start of the current window and the calls let through in it.
+    window: Mutex<(Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_per_second: u32) -> Self {
+        RateLimiter {
+            max_per_second,
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut window = self.window.lock().unwrap();
+        if window.0.elapsed() >= Duration::from_secs(1) {
+            *window = (Instant::now(), 0);
+        }
+        if window.1 >= self.max_per_second {
+            return false;
+        }
+        window.1 += 1;
+        true
+    }
+}
@@ -0,0 +1,169 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::{
+    ChildOrder, ClientError, Compression, Encoding, Fill, OrderBook, ParentOrder, Timestamp,
+    CONTENT_ENCODING_HEADER, SCHEMA_VERSION,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A message type that travels in a `MessageEnvelope`.
+pub trait EnvelopePayload: Serialize + DeserializeOwned {
+    /// Tag written to the envelope's `message_type`.
+    const MESSAGE_TYPE: &'static str;
+}
+
+impl EnvelopePayload for ParentOrder {
+    const MESSAGE_TYPE: &'static str = "parent_order";
+}
+
+impl EnvelopePayload for ChildOrder {
+    const MESSAGE_TYPE: &'static str = "child_order";
+}
+
+impl EnvelopePayload for Fill {
+    const MESSAGE_TYPE: &'static str = "fill";
+}
+
+impl EnvelopePayload for OrderBook {
+    const MESSAGE_TYPE: &'static str = "order_book";
+}
+
+/// Wraps a JSON message with what a consumer needs to route and trace it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageEnvelope {
+    /// What the payload holds, e.g. `parent_order`; see `EnvelopePayload`.
+    pub message_type: String,
+    /// `SCHEMA_VERSION` of the writer.
+    pub schema_version: u32,
+    /// Shared by related messages: a parent order, its children and their fills carry the
+    /// parent's id.
+    pub correlation_id: String,
+    /// When the envelope was created.
+    pub timestamp: Timestamp,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// The message itself, as JSON, or packed by the compression named in the
+    /// `content-encoding` header.
+    pub payload: String,
+}
+
+/// What an envelope carries, decoded according to its `message_type`.
+#[derive(Debug, Clone)]
+pub enum EnvelopeMessage {
+    ParentOrder(ParentOrder),
+    ChildOrder(ChildOrder),
+    Fill(Fill),
+    OrderBook(OrderBook),
+    /// A type this build does not know; the envelope still holds the raw payload.
+    Unknown,
+}
+
+/// An envelope received from a topic, with its payload decoded.
+#[derive(Debug, Clone)]
+pub struct ReceivedEnvelope {
+    pub envelope: MessageEnvelope,
+    pub message: EnvelopeMessage,
+}
+
+impl MessageEnvelope {
+    /// Wraps `message`, tagged with its type and the current schema version.
+    pub fn wrap<T: EnvelopePayload>(
+        message: &T,
+        correlation_id: &str,
+    ) -> Result<Self, ClientError> {
+        let payload = serde_json::to_string(message)
+            .map_err(|e| ClientError::Serialization(e.to_string()))?;
+        Ok(MessageEnvelope {
+            message_type: T::MESSAGE_TYPE.to_string(),
+            schema_version: SCHEMA_VERSION,
+            correlation_id: correlation_id.to_string(),
+            timestamp: Timestamp::now(),
+            headers: HashMap::new(),
+            payload,
+        })
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Compresses the payload, naming the compression in the `content-encoding` header.
+    pub fn compress(&self, compression: Compression) -> Result<Self, ClientError> {
+        let mut compressed = self.clone();
+        compressed.payload = compression.pack(&self.payload)?;
+        compressed.headers.insert(
+            CONTENT_ENCODING_HEADER.to_string(),
+            compression.as_str().to_string(),
+        );
+        Ok(compressed)
+    }
+
+    /// The payload JSON, decompressed according to the `content-encoding` header.
+    pub fn payload_json(&self) -> Result<Cow<'_, str>, ClientError> {
+        match self.headers.get(CONTENT_ENCODING_HEADER) {
+            None => Ok(Cow::Borrowed(&self.payload)),
+            Some(encoding) => Compression::from_header(encoding)?
+                .unpack(&self.payload)
+                .map(Cow::Owned),
+        }
+    }
+
+    /// Decodes the payload as a `T`, decompressing it first if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::UnexpectedMessageType` when the envelope is tagged with another
+    /// type, `ClientError::InvalidPayload` when it cannot be decompressed, and
+    /// `ClientError::Deserialization` when the payload is not a `T`.
+    pub fn open<T: EnvelopePayload>(&self) -> Result<T, ClientError> {
+        if self.message_type != T::MESSAGE_TYPE {
+            return Err(ClientError::UnexpectedMessageType {
+                expected: T::MESSAGE_TYPE,
+                found: self.message_type.clone(),
+            });
+        }
+        Encoding::Json.decode(self.payload_json()?.as_bytes())
+    }
+
+    /// Decodes the payload according to `message_type`, or `EnvelopeMessage::Unknown` for
+    /// a type this build does not know.
+    pub fn decode(&self) -> Result<EnvelopeMessage, ClientError> {
+        Ok(match self.message_type.as_str() {
+            ParentOrder::MESSAGE_TYPE => EnvelopeMessage::ParentOrder(self.open()?),
+            ChildOrder::MESSAGE_TYPE => EnvelopeMessage::ChildOrder(self.open()?),
+            Fill::MESSAGE_TYPE => EnvelopeMessage::Fill(self.open()?),
+            OrderBook::MESSAGE_TYPE => EnvelopeMessage::OrderBook(self.open()?),
+            _ => EnvelopeMessage::Unknown,
+        })
+    }
+}
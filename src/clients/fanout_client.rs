@@ -0,0 +1,220 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::{
+    ClientError, ClientMetrics, HealthStatus, MessageHandler, MessageStream, MessagingClient,
+    MeteredClient, MetricsSnapshot, SubscriptionHandle,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// What a `FanoutClient` does when a backend does not accept a message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FanoutPolicy {
+    /// Stops at the first backend that fails and returns its error; the backends before it
+    /// already have the message.
+    #[default]
+    FailFast,
+    /// Sends to every backend and returns the primary's result. The other backends are
+    /// mirrors: their failures only show in `backend_metrics`.
+    BestEffort,
+}
+
+/// Sends every message to several backends, e.g. the old and the new broker during a
+/// migration, and receives from the first one, the primary.
+///
+/// Each backend's traffic is counted separately; see `backend_metrics`.
+pub struct FanoutClient {
+    backends: Vec<Backend>,
+    policy: FanoutPolicy,
+}
+
+struct Backend {
+    client: Arc<MeteredClient>,
+    metrics: Arc<ClientMetrics>,
+}
+
+impl FanoutClient {
+    /// Fans out to `backends`, the first of which is the primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::InvalidConfig` when `backends` is empty.
+    pub fn new(
+        backends: Vec<Box<dyn MessagingClient>>,
+        policy: FanoutPolicy,
+    ) -> Result<Self, ClientError> {
+        if backends.is_empty() {
+            return Err(ClientError::InvalidConfig {
+                section: "fanout",
+                reason: "at least one backend is needed".to_string(),
+            });
+        }
+        let backends = backends
+            .into_iter()
+            .map(|client| {
+                let metrics = Arc::new(ClientMetrics::default());
+                Backend {
+                    client: Arc::new(MeteredClient::new(Arc::from(client), metrics.clone())),
+                    metrics,
+                }
+            })
+            .collect();
+        Ok(FanoutClient { backends, policy })
+    }
+
+    pub fn policy(&self) -> FanoutPolicy {
+        self.policy
+    }
+
+    /// Sends `payload` to every backend whatever the policy, returning each one's result
+    /// in backend order.
+    pub fn produce_to_all(&self, topic: &str, payload: &[u8]) -> Vec<Result<(), ClientError>> {
+        self.backends
+            .iter()
+            .map(|backend| backend.client.produce_bytes(topic, payload))
+            .collect()
+    }
+
+    fn primary(&self) -> &Arc<MeteredClient> {
+        &self.backends[0].client
+    }
+
+    fn fan_out(
+        &self,
+        send: impl Fn(&MeteredClient) -> Result<(), ClientError>,
+    ) -> Result<(), ClientError> {
+        let mut results = self.backends.iter().map(|backend| send(&backend.client));
+        match self.policy {
+            FanoutPolicy::FailFast => results.try_for_each(|result| result),
+            FanoutPolicy::BestEffort => {
+                let primary = results.next().expect("a fanout has a backend");
+                results.for_each(drop);
+                primary
+            }
+        }
+    }
+}
+
+impl MessagingClient for FanoutClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.fan_out(|client| client.produce(topic, message))
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        self.primary().consume(topic)
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        self.fan_out(|client| client.produce_bytes(topic, payload))
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        self.primary().consume_bytes(topic)
+    }
+
+    fn produce_keyed(&self, topic: &str, key: &str, message: &str) -> Result<(), ClientError> {
+        self.fan_out(|client| client.produce_keyed(topic, key, message))
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.fan_out(|client| client.produce_bytes_keyed(topic, key, payload))
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.primary().consume_timeout(topic, timeout)
+    }
+
+    fn try_consume(&self, topic: &str) -> Result<Option<String>, ClientError> {
+        self.primary().try_consume(topic)
+    }
+
+    fn subscribe(
+        self: Arc<Self>,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        self.primary().clone().subscribe(topic, handler)
+    }
+
+    fn consume_stream(self: Arc<Self>, topic: &str) -> MessageStream {
+        self.primary().clone().consume_stream(topic)
+    }
+
+    fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        self.primary().request(topic, message, timeout)
+    }
+
+    fn late_replies(&self) -> u64 {
+        self.primary().late_replies()
+    }
+
+    /// Healthy when every backend is; reports the primary's status.
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        let mut statuses = self
+            .backends
+            .iter()
+            .map(|backend| backend.client.health_check())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(statuses.swap_remove(0))
+    }
+
+    fn backend_metrics(&self) -> Vec<MetricsSnapshot> {
+        self.backends
+            .iter()
+            .map(|backend| backend.metrics.snapshot())
+            .collect()
+    }
+    /// Closes every backend within the one `timeout`, even after one failed to close, and
+    /// reports the first failure.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        let deadline = Instant::now() + timeout;
+        self.backends
+            .iter()
+            .map(|backend| {
+                backend
+                    .client
+                    .close(deadline.saturating_duration_since(Instant::now()))
+            })
+            .fold(Ok(()), Result::and)
+    }
+}
@@ -0,0 +1,556 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+use crate::clients::lifecycle::Lifecycle;
+
+use crate::{ClientError, MessagingClient, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long `FileClient::consume` waits for a record unless `with_timeout` says otherwise.
+pub const FILE_CONSUME_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often a waiting consumer looks for records written since it last read.
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Errors raised while recording to or replaying from a file.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FileClientError {
+    #[error("I/O error on {path}: {reason}")]
+    Io { path: String, reason: String },
+    /// A complete line of a recording is not a `RecordedMessage`.
+    #[error("Corrupt record at {path}:{line}: {reason}")]
+    Corrupt {
+        path: String,
+        line: usize,
+        reason: String,
+    },
+    #[error("Replay speed factor must be a positive number")]
+    InvalidSpeedFactor,
+    #[error("A replay cannot be published to")]
+    ReadOnly,
+    #[error("The recording or replay is closed")]
+    Closed,
+}
+
+impl FileClientError {
+    fn io(path: &Path, error: std::io::Error) -> Self {
+        FileClientError::Io {
+            path: path.display().to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+impl From<FileClientError> for ClientError {
+    fn from(error: FileClientError) -> Self {
+        match error {
+            FileClientError::Io { .. } | FileClientError::ReadOnly => {
+                ClientError::Transport(error.to_string())
+            }
+            FileClientError::Corrupt { .. } => ClientError::InvalidPayload(error.to_string()),
+            FileClientError::InvalidSpeedFactor => ClientError::InvalidConfig {
+                section: "replay",
+                reason: error.to_string(),
+            },
+            FileClientError::Closed => ClientError::Closed,
+        }
+    }
+}
+
+/// A message as recorded, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub topic: String,
+    /// When the message was produced.
+    pub timestamp: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub payload: String,
+}
+
+/// Path of segment `index` of the recording at `path`: `path` itself, then `path.1`,
+/// `path.2` and so on.
+fn segment_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Reads `path` from `offset` to its end; a missing file reads as empty.
+fn read_from(path: &Path, offset: u64) -> Result<Vec<u8>, FileClientError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(FileClientError::io(path, e)),
+    };
+    let mut data = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_to_end(&mut data))
+        .map_err(|e| FileClientError::io(path, e))?;
+    Ok(data)
+}
+
+/// The complete lines of a chunk of a recording.
+struct Parsed {
+    records: Vec<RecordedMessage>,
+    /// Bytes and lines read, up to and including a corrupt line.
+    bytes: usize,
+    lines: usize,
+    /// The first line that is not a record; parsing stops there.
+    corrupt: Option<FileClientError>,
+}
+
+/// Parses the complete lines of `data`, read from `path` after its first `lines_before`
+/// lines.
+///
+/// An unterminated last line is left out: it is still being written, or was cut short when
+/// the recorder stopped.
+fn parse_lines(path: &Path, data: &[u8], lines_before: usize) -> Parsed {
+    let mut parsed = Parsed {
+        records: Vec::new(),
+        bytes: 0,
+        lines: 0,
+        corrupt: None,
+    };
+    let complete = data
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |end| end + 1);
+    for line in data[..complete].split_inclusive(|&byte| byte == b'\n') {
+        parsed.bytes += line.len();
+        parsed.lines += 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice(line) {
+            Ok(record) => parsed.records.push(record),
+            Err(e) => {
+                parsed.corrupt = Some(FileClientError::Corrupt {
+                    path: path.display().to_string(),
+                    line: lines_before + parsed.lines,
+                    reason: e.to_string(),
+                });
+                break;
+            }
+        }
+    }
+    parsed
+}
+
+/// Records every message produced to a file, and reads them back in order on consume.
+///
+/// Each message becomes one JSON `RecordedMessage` line. With `with_max_bytes` the
+/// recording rolls over to a new segment, `path.1`, `path.2` and so on, once the current one
+/// would grow past the limit; consumers read through the segments in order.
+///
+/// Consuming a topic starts at the beginning of the recording and receives its messages
+/// once, including those recorded by an earlier run. Messages of other topics read on the
+/// way are kept for their own consumers.
+pub struct FileClient {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    timeout: Duration,
+    writer: Mutex<Writer>,
+    reader: Mutex<Reader>,
+    lifecycle: Lifecycle,
+}
+
+struct Writer {
+    file: File,
+    segment: usize,
+    size: u64,
+}
+
+#[derive(Default)]
+struct Reader {
+    segment: usize,
+    offset: u64,
+    line: usize,
+    pending: HashMap<String, VecDeque<RecordedMessage>>,
+    /// A corrupt line read past, reported by the next read.
+    corrupt: Option<FileClientError>,
+}
+
+impl FileClient {
+    /// Opens the recording at `path` for appending, creating it if needed.
+    ///
+    /// A last line cut short, by a recorder that stopped mid-write, is dropped so that new
+    /// records start on a line of their own.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FileClientError> {
+        let path = path.as_ref().to_path_buf();
+        let mut segment = 0;
+        while segment_path(&path, segment + 1).exists() {
+            segment += 1;
+        }
+        let writer = Writer::open(&segment_path(&path, segment), segment)?;
+        Ok(FileClient {
+            path,
+            max_bytes: None,
+            timeout: FILE_CONSUME_TIMEOUT,
+            writer: Mutex::new(writer),
+            reader: Mutex::new(Reader::default()),
+            lifecycle: Lifecycle::default(),
+        })
+    }
+
+    /// Starts a new segment when a record would grow the current one past `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Makes `consume` wait up to `timeout` for a record, instead of `FILE_CONSUME_TIMEOUT`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a record of the message to the current segment.
+    pub fn record(&self, topic: &str, key: Option<&str>, payload: &str) -> Result<(), ClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(FileClientError::Closed.into());
+        };
+        let record = RecordedMessage {
+            topic: topic.to_string(),
+            timestamp: Timestamp::now(),
+            key: key.map(str::to_string),
+            payload: payload.to_string(),
+        };
+        let mut line =
+            serde_json::to_vec(&record).map_err(|e| ClientError::Serialization(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut writer = self.writer.lock().unwrap();
+        let size = line.len() as u64;
+        if self
+            .max_bytes
+            .is_some_and(|max| writer.size > 0 && writer.size + size > max)
+        {
+            let segment = writer.segment + 1;
+            *writer = Writer::open(&segment_path(&self.path, segment), segment)?;
+        }
+        writer
+            .file
+            .write_all(&line)
+            .map_err(|e| FileClientError::io(&segment_path(&self.path, writer.segment), e))?;
+        writer.size += size;
+        Ok(())
+    }
+
+    /// Returns the next record on `topic`, waiting up to `timeout` for one to be written.
+    pub fn next_record(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<RecordedMessage>, FileClientError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.lifecycle.is_closed() {
+                return Err(FileClientError::Closed);
+            }
+            if let Some(record) = self.reader.lock().unwrap().next(&self.path, topic)? {
+                return Ok(Some(record));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            std::thread::sleep(remaining.min(FILE_POLL_INTERVAL));
+        }
+    }
+}
+
+impl Writer {
+    /// Opens segment `segment` at `path` for appending, dropping an unterminated last line.
+    fn open(path: &Path, segment: usize) -> Result<Self, FileClientError> {
+        let io = |e| FileClientError::io(path, e);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(io)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(io)?;
+        let complete = data
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |end| end + 1);
+        if complete < data.len() {
+            file.set_len(complete as u64).map_err(io)?;
+        }
+        Ok(Writer {
+            file,
+            segment,
+            size: complete as u64,
+        })
+    }
+}
+
+impl Reader {
+    /// Reads on until the next record on `topic`, keeping the records of other topics.
+    ///
+    /// A corrupt line is reported once, after the records before it, and then skipped.
+    fn next(
+        &mut self,
+        path: &Path,
+        topic: &str,
+    ) -> Result<Option<RecordedMessage>, FileClientError> {
+        if let Some(record) = self.pending.get_mut(topic).and_then(VecDeque::pop_front) {
+            return Ok(Some(record));
+        }
+        if let Some(corrupt) = self.corrupt.take() {
+            return Err(corrupt);
+        }
+        loop {
+            // Checked before reading: once the writer moved on, this segment is complete
+            let next_exists = segment_path(path, self.segment + 1).exists();
+            let segment = segment_path(path, self.segment);
+            let data = read_from(&segment, self.offset)?;
+            let parsed = parse_lines(&segment, &data, self.line);
+            self.offset += parsed.bytes as u64;
+            self.line += parsed.lines;
+
+            let mut found = None;
+            for record in parsed.records {
+                if found.is_none() && record.topic == topic {
+                    found = Some(record);
+                } else {
+                    self.pending
+                        .entry(record.topic.clone())
+                        .or_default()
+                        .push_back(record);
+                }
+            }
+            if let Some(corrupt) = parsed.corrupt {
+                if found.is_none() {
+                    return Err(corrupt);
+                }
+                self.corrupt = Some(corrupt);
+            }
+            if found.is_some() || !next_exists {
+                return Ok(found);
+            }
+            self.segment += 1;
+            self.offset = 0;
+            self.line = 0;
+        }
+    }
+}
+
+impl MessagingClient for FileClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.record(topic, None, message)
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        self.consume_timeout(topic, self.timeout)?
+            .ok_or_else(|| ClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: self.timeout.as_millis() as u64,
+            })
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        let message = std::str::from_utf8(payload).map_err(|_| {
+            ClientError::Serialization(format!("Topic {} only carries UTF-8 payloads", topic))
+        })?;
+        self.record(topic, Some(key), message)
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        Ok(self
+            .next_record(topic, timeout)?
+            .map(|record| record.payload))
+    }
+
+    /// Waits for the records being written, then syncs the current segment to disk.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.lifecycle.close(timeout)?;
+        let writer = self.writer.lock().unwrap();
+        writer
+            .file
+            .sync_all()
+            .map_err(|e| FileClientError::io(&segment_path(&self.path, writer.segment), e).into())
+    }
+}
+
+/// Replays a recording made by a `FileClient`, keeping the original time between messages
+/// divided by `speed_factor`: 2.0 replays twice as fast, `f64::INFINITY` without waiting.
+///
+/// The replay clock starts with the first consume. Each message is received once, and a
+/// consumer of one topic waits only for the messages of that topic.
+pub struct ReplayClient {
+    speed_factor: f64,
+    replay: Mutex<Replay>,
+    lifecycle: Lifecycle,
+}
+
+struct Replay {
+    records: VecDeque<RecordedMessage>,
+    first: Timestamp,
+    started: Option<Instant>,
+}
+
+impl ReplayClient {
+    /// Loads every segment of the recording at `path`; an unterminated last line, cut short
+    /// when the recorder stopped, is left out.
+    pub fn from_file(path: impl AsRef<Path>, speed_factor: f64) -> Result<Self, FileClientError> {
+        if speed_factor.is_nan() || speed_factor <= 0.0 {
+            return Err(FileClientError::InvalidSpeedFactor);
+        }
+        let path = path.as_ref();
+        let mut records = VecDeque::new();
+        let mut segment = 0;
+        loop {
+            let segment_path = segment_path(path, segment);
+            if segment > 0 && !segment_path.exists() {
+                break;
+            }
+            let data =
+                std::fs::read(&segment_path).map_err(|e| FileClientError::io(&segment_path, e))?;
+            let parsed = parse_lines(&segment_path, &data, 0);
+            if let Some(corrupt) = parsed.corrupt {
+                return Err(corrupt);
+            }
+            records.extend(parsed.records);
+            segment += 1;
+        }
+        let first = records
+            .front()
+            .map_or(Timestamp::EPOCH, |record| record.timestamp);
+        Ok(ReplayClient {
+            speed_factor,
+            replay: Mutex::new(Replay {
+                records,
+                first,
+                started: None,
+            }),
+            lifecycle: Lifecycle::default(),
+        })
+    }
+
+    fn open(&self) -> Result<(), FileClientError> {
+        match self.lifecycle.is_closed() {
+            true => Err(FileClientError::Closed),
+            false => Ok(()),
+        }
+    }
+
+    /// Messages not replayed yet, over all topics.
+    pub fn remaining(&self) -> usize {
+        self.replay.lock().unwrap().records.len()
+    }
+
+    /// Takes the next message on `topic` once it is due; `Ok(None)` when it is not due
+    /// within `timeout` or the recording holds no more.
+    fn next_record(&self, topic: &str, timeout: Option<Duration>) -> Option<RecordedMessage> {
+        let mut replay = self.replay.lock().unwrap();
+        let started = *replay.started.get_or_insert_with(Instant::now);
+        let Some(index) = replay
+            .records
+            .iter()
+            .position(|record| record.topic == topic)
+        else {
+            drop(replay);
+            if let Some(timeout) = timeout {
+                // Waiting out the timeout keeps a polling subscriber from spinning
+                std::thread::sleep(timeout);
+            }
+            return None;
+        };
+        let offset = replay.records[index]
+            .timestamp
+            .as_millis()
+            .saturating_sub(replay.first.as_millis());
+        let due = started + Duration::from_millis(offset).div_f64(self.speed_factor);
+        let wait = due.saturating_duration_since(Instant::now());
+        if let Some(timeout) = timeout.filter(|timeout| wait > *timeout) {
+            drop(replay);
+            std::thread::sleep(timeout);
+            return None;
+        }
+        let record = replay.records.remove(index);
+        drop(replay);
+        std::thread::sleep(wait);
+        record
+    }
+}
+
+impl MessagingClient for ReplayClient {
+    fn produce(&self, _topic: &str, _message: &str) -> Result<(), ClientError> {
+        Err(FileClientError::ReadOnly.into())
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        self.open()?;
+        self.next_record(topic, None)
+            .map(|record| record.payload)
+            .ok_or_else(|| ClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: 0,
+            })
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.open()?;
+        Ok(self
+            .next_record(topic, Some(timeout))
+            .map(|record| record.payload))
+    }
+
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.lifecycle.close(timeout)
+    }
+}
@@ -26,76 +26,725 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::MessagingClient;
+use crate::clients::common_client::utf8_payload;
+use crate::clients::lifecycle::Lifecycle;
+use crate::clients::request_reply::ReplyInbox;
+use crate::clients::subscription::{Delivery, POLL_INTERVAL};
+use crate::{
+    ClientError, ConfigError, ConsumedMessage, HealthStatus, KafkaConfig, MessageHandler,
+    MessageStream, MessagingClient, SubscriptionHandle, CLOSE_ON_DROP_TIMEOUT,
+    HEALTH_CHECK_TIMEOUT, MESSAGE_STREAM_BUFFER,
+};
 
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{StreamConsumer, Consumer};
-use rdkafka::producer::{BaseProducer, BaseRecord};
-use rdkafka::Message;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::mpsc;
 
-pub struct KafkaClient {
-    producer: BaseProducer,
-    consumer: StreamConsumer,
-    brokers: String,
-    group_id: String,
+impl ClientError {
+    /// Maps an rdkafka error raised while handling `topic`.
+    pub fn from_kafka(topic: &str, error: KafkaError) -> Self {
+        let detail = error.to_string();
+        let Some(code) = error.rdkafka_error_code() else {
+            return match error {
+                KafkaError::ClientConfig(..) | KafkaError::ClientCreation(_) => {
+                    ClientError::InvalidConfig {
+                        section: "kafka",
+                        reason: detail,
+                    }
+                }
+                KafkaError::NoMessageReceived => ClientError::Timeout {
+                    topic: topic.to_string(),
+                    timeout_ms: 0,
+                },
+                _ => ClientError::Transport(detail),
+            };
+        };
+        match code {
+            RDKafkaErrorCode::UnknownTopicOrPartition
+            | RDKafkaErrorCode::UnknownTopic
+            | RDKafkaErrorCode::UnknownPartition => ClientError::TopicNotFound(topic.to_string()),
+            RDKafkaErrorCode::Authentication
+            | RDKafkaErrorCode::SaslAuthenticationFailed
+            | RDKafkaErrorCode::TopicAuthorizationFailed
+            | RDKafkaErrorCode::GroupAuthorizationFailed
+            | RDKafkaErrorCode::ClusterAuthorizationFailed => ClientError::Auth(detail),
+            RDKafkaErrorCode::AllBrokersDown
+            | RDKafkaErrorCode::BrokerTransportFailure
+            | RDKafkaErrorCode::BrokerNotAvailable
+            | RDKafkaErrorCode::NetworkException
+            | RDKafkaErrorCode::Resolve => ClientError::Connection(detail),
+            _ if matches!(error, KafkaError::MessageProduction(_)) => ClientError::Delivery {
+                // Full queues, timeouts and leader elections clear up on their own
+                retriable: matches!(
+                    code,
+                    RDKafkaErrorCode::QueueFull
+                        | RDKafkaErrorCode::MessageTimedOut
+                        | RDKafkaErrorCode::RequestTimedOut
+                        | RDKafkaErrorCode::OperationTimedOut
+                        | RDKafkaErrorCode::LeaderNotAvailable
+                        | RDKafkaErrorCode::NotLeaderForPartition
+                        | RDKafkaErrorCode::NotEnoughReplicas
+                ),
+                detail,
+            },
+            _ => ClientError::Transport(detail),
+        }
+    }
 }
 
-impl KafkaClient {
-    pub fn new(brokers: String, group_id: String) -> Self {
-        // Create producer configuration
-        let producer = ClientConfig::new()
-            .set("bootstrap.servers", &brokers)
-            .create()
-            .expect("Producer creation error");
+/// Messages kept per topic when `KafkaConfig::max_pending_messages` is unset.
+const MAX_PENDING_MESSAGES: usize = 10_000;
+
+/// A message received from Kafka.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KafkaMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub payload: Option<Vec<u8>>,
+}
+
+/// A message to send to Kafka.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KafkaRecord<'a> {
+    pub topic: &'a str,
+    /// Messages with the same key land on the same partition, in order.
+    pub key: Option<&'a [u8]>,
+    /// Sends to this partition instead of the one the partitioner picks.
+    pub partition: Option<i32>,
+    pub payload: &'a [u8],
+}
+
+/// The producer side of the client, so a test double can capture what is sent.
+pub trait KafkaProducer: Send + Sync {
+    fn send(&self, record: KafkaRecord<'_>) -> Result<(), KafkaError>;
+    /// Fetches cluster metadata within `timeout`, returning the broker that answered.
+    fn probe(&self, timeout: Duration) -> Result<String, KafkaError>;
+    /// Waits up to `timeout` for the messages still queued to be delivered.
+    fn flush(&self, _timeout: Duration) -> Result<(), KafkaError> {
+        Ok(())
+    }
+}
+
+impl KafkaProducer for BaseProducer {
+    fn send(&self, record: KafkaRecord<'_>) -> Result<(), KafkaError> {
+        let mut base: BaseRecord<'_, [u8], [u8]> =
+            BaseRecord::to(record.topic).payload(record.payload);
+        if let Some(key) = record.key {
+            base = base.key(key);
+        }
+        if let Some(partition) = record.partition {
+            base = base.partition(partition);
+        }
+        BaseProducer::send(self, base).map_err(|(err, _)| err)
+    }
+
+    fn probe(&self, timeout: Duration) -> Result<String, KafkaError> {
+        let metadata = self.client().fetch_metadata(None, timeout)?;
+        Ok(format!(
+            "{} (id {})",
+            metadata.orig_broker_name(),
+            metadata.orig_broker_id()
+        ))
+    }
+
+    fn flush(&self, timeout: Duration) -> Result<(), KafkaError> {
+        Producer::flush(self, timeout)
+    }
+}
+
+/// The consumer side of the client, so a test double can stand in for the broker.
+pub trait KafkaConsumer: Send + Sync {
+    /// Replaces the current subscription with `topics`.
+    fn subscribe(&self, topics: &[&str]) -> Result<(), KafkaError>;
+    /// Waits for the next message on any subscribed topic.
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Result<KafkaMessage, KafkaError>> + Send + '_>>;
+    /// Marks the message at `offset` of `partition` of `topic` as processed, so the next
+    /// commit covers it.
+    fn store_offset(&self, _topic: &str, _partition: i32, _offset: i64) -> Result<(), KafkaError> {
+        Ok(())
+    }
+    /// Stops fetching from `partition` of `topic` until it is resumed.
+    fn pause(&self, _topic: &str, _partition: i32) -> Result<(), KafkaError> {
+        Ok(())
+    }
+    fn resume(&self, _topic: &str, _partition: i32) -> Result<(), KafkaError> {
+        Ok(())
+    }
+    /// Commits the offsets of the messages processed and leaves the group.
+    fn close(&self) -> Result<(), KafkaError> {
+        Ok(())
+    }
+}
+
+impl KafkaConsumer for StreamConsumer {
+    fn subscribe(&self, topics: &[&str]) -> Result<(), KafkaError> {
+        Consumer::subscribe(self, topics)
+    }
+
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Result<KafkaMessage, KafkaError>> + Send + '_>> {
+        Box::pin(async move {
+            let message = StreamConsumer::recv(self).await?;
+            Ok(KafkaMessage {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                payload: message.payload().map(<[u8]>::to_vec),
+            })
+        })
+    }
+
+    fn store_offset(&self, topic: &str, partition: i32, offset: i64) -> Result<(), KafkaError> {
+        Consumer::store_offset(self, topic, partition, offset)
+    }
+
+    fn pause(&self, topic: &str, partition: i32) -> Result<(), KafkaError> {
+        Consumer::pause(self, &partition_list(topic, partition)?)
+    }
+
+    fn resume(&self, topic: &str, partition: i32) -> Result<(), KafkaError> {
+        Consumer::resume(self, &partition_list(topic, partition)?)
+    }
+
+    /// Only a consumer with partitions assigned has offsets to commit.
+    fn close(&self) -> Result<(), KafkaError> {
+        if self.assignment()?.count() > 0 {
+            match self.commit_consumer_state(CommitMode::Sync) {
+                Ok(()) | Err(KafkaError::ConsumerCommit(RDKafkaErrorCode::NoOffset)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Consumer::unsubscribe(self);
+        Ok(())
+    }
+}
+
+/// `partition` of `topic` alone, as pausing and resuming take it.
+fn partition_list(topic: &str, partition: i32) -> Result<TopicPartitionList, KafkaError> {
+    let mut partitions = TopicPartitionList::new();
+    partitions.add_partition_offset(topic, partition, Offset::Invalid)?;
+    Ok(partitions)
+}
+
+impl KafkaConfig {
+    /// The rdkafka settings shared by the client's producer and consumer: brokers, security,
+    /// credentials and the connect timeout.
+    pub fn client_config(&self) -> ClientConfig {
+        let mut client_config = ClientConfig::new();
+        client_config.set("bootstrap.servers", &self.kafka_url);
+        if let Some(protocol) = self.security_protocol {
+            client_config.set("security.protocol", protocol.as_str());
+        }
+        if let Some(mechanism) = self.sasl_mechanism {
+            client_config.set("sasl.mechanism", mechanism.as_str());
+        }
+        if let Some(username) = &self.sasl_username {
+            client_config.set("sasl.username", username);
+        }
+        if let Some(password) = &self.sasl_password {
+            client_config.set("sasl.password", password.expose());
+        }
+        if let Some(ca_location) = &self.ssl_ca_location {
+            client_config.set("ssl.ca.location", ca_location);
+        }
+        if let Some(timeout_ms) = self.connect_timeout_ms {
+            client_config.set("socket.connection.setup.timeout.ms", timeout_ms.to_string());
+        }
+        client_config
+    }
 
-        // Create consumer configuration
-        let consumer = ClientConfig::new()
-            .set("group.id", &group_id)
-            .set("bootstrap.servers", &brokers)
+    /// The settings of the group consumer. Offsets are committed in the background, but only
+    /// those of the messages `consume` returned, never of those still waiting in the client.
+    pub fn consumer_config(&self) -> ClientConfig {
+        let mut client_config = self.client_config();
+        client_config
+            .set("group.id", &self.group_id)
             .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "6000")
+            .set("session.timeout.ms", self.session_timeout_ms.to_string())
             .set("enable.auto.commit", "true")
-            .create()
-            .expect("Consumer creation error");
+            .set("enable.auto.offset.store", "false");
+        client_config
+    }
+
+    /// The settings of the producer.
+    pub fn producer_config(&self) -> ClientConfig {
+        let mut client_config = self.client_config();
+        client_config.set("message.timeout.ms", self.message_timeout_ms.to_string());
+        if let Some(timeout_ms) = self.request_timeout_ms {
+            client_config.set("request.timeout.ms", timeout_ms.to_string());
+        }
+        client_config
+    }
+}
+
+/// Kafka producer and consumer.
+///
+/// The consumer joins its group once and is subscribed lazily: the first `consume` on a
+/// topic adds it to the subscription, later calls reuse it, so the group is not rebalanced
+/// on every read. Messages that arrive for another subscribed topic are kept until that
+/// topic is consumed, up to `max_pending_messages` per topic: past it, the partitions they
+/// come from are paused until half of them were consumed. Only the offsets of messages
+/// `consume` returned are committed.
+///
+/// The blocking `consume` methods run on a runtime owned by the client. Inside an async
+/// service use `consume_async` / `consume_bytes_async` instead; the blocking ones return an
+/// error when called from within a runtime. `subscribe` runs its receive loop as a task on
+/// that same runtime.
+///
+/// `close` flushes the producer and commits the consumer's offsets; a client dropped without
+/// it does so on drop, best effort.
+pub struct KafkaClient {
+    producer: Box<dyn KafkaProducer>,
+    consumer: Box<dyn KafkaConsumer>,
+    runtime: Option<Runtime>,
+    subscribed: Mutex<BTreeSet<String>>,
+    pending: Mutex<HashMap<String, VecDeque<KafkaMessage>>>,
+    max_pending: usize,
+    /// Partitions paused because their topic has `max_pending` messages waiting
+    paused: Mutex<HashMap<String, BTreeSet<i32>>>,
+    inbox: ReplyInbox,
+    lifecycle: Lifecycle,
+}
 
-        KafkaClient {
-            producer,
-            consumer,
-            brokers,
+impl KafkaClient {
+    pub fn new(brokers: String, group_id: String) -> Result<Self, ClientError> {
+        Self::from_config(&KafkaConfig {
+            kafka_url: brokers,
             group_id,
+            ..KafkaConfig::default()
+        })
+    }
+
+    /// Creates a client for the brokers, consumer group and security settings in `config`.
+    ///
+    /// Contradicting security settings are rejected before connecting.
+    pub fn from_config(config: &KafkaConfig) -> Result<Self, ClientError> {
+        config.validate().map_err(|e| match e {
+            ConfigError::Invalid { section, reason } => {
+                ClientError::InvalidConfig { section, reason }
+            }
+            other => ClientError::InvalidConfig {
+                section: "kafka",
+                reason: other.to_string(),
+            },
+        })?;
+        let runtime = Self::runtime()?;
+        // The stream consumer starts its background task on the runtime it is created in
+        let consumer: StreamConsumer = {
+            let _guard = runtime.enter();
+            config
+                .consumer_config()
+                .create()
+                .map_err(|e| ClientError::from_kafka("", e))?
+        };
+
+        Self::assemble(config, consumer, runtime)
+    }
+
+    /// Builds a client that reads through `consumer` instead of a group consumer of its own.
+    pub fn with_consumer(
+        brokers: String,
+        consumer: impl KafkaConsumer + 'static,
+    ) -> Result<Self, ClientError> {
+        let config = KafkaConfig {
+            kafka_url: brokers,
+            ..KafkaConfig::default()
+        };
+        Self::assemble(&config, consumer, Self::runtime()?)
+    }
+
+    /// Keeps at most `max` messages waiting per topic, see `KafkaConfig::max_pending_messages`.
+    pub fn with_max_pending_messages(mut self, max: usize) -> Self {
+        self.max_pending = max.max(1);
+        self
+    }
+
+    /// Sends through `producer` instead of the client's own.
+    pub fn with_producer(mut self, producer: impl KafkaProducer + 'static) -> Self {
+        self.producer = Box::new(producer);
+        self
+    }
+
+    fn runtime() -> Result<Runtime, ClientError> {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| ClientError::Connection(e.to_string()))
+    }
+
+    fn assemble(
+        config: &KafkaConfig,
+        consumer: impl KafkaConsumer + 'static,
+        runtime: Runtime,
+    ) -> Result<Self, ClientError> {
+        let producer: BaseProducer = config
+            .producer_config()
+            .create()
+            .map_err(|e| ClientError::from_kafka("", e))?;
+
+        Ok(KafkaClient {
+            producer: Box::new(producer),
+            consumer: Box::new(consumer),
+            runtime: Some(runtime),
+            subscribed: Mutex::new(BTreeSet::new()),
+            pending: Mutex::new(HashMap::new()),
+            max_pending: config
+                .max_pending_messages
+                .unwrap_or(MAX_PENDING_MESSAGES)
+                .max(1),
+            paused: Mutex::new(HashMap::new()),
+            inbox: ReplyInbox::new(config.reply_topic()),
+            lifecycle: Lifecycle::default(),
+        })
+    }
+
+    /// Topic the replies to this client's requests come back on, see `KafkaConfig::reply_topic`.
+    pub fn reply_topic(&self) -> &str {
+        self.inbox.topic()
+    }
+
+    /// Sends `payload` to `partition` of `topic`, bypassing the partitioner.
+    pub fn produce_to_partition(
+        &self,
+        topic: &str,
+        partition: i32,
+        key: Option<&str>,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.send(KafkaRecord {
+            topic,
+            key: key.map(str::as_bytes),
+            partition: Some(partition),
+            payload,
+        })
+    }
+
+    fn send(&self, record: KafkaRecord<'_>) -> Result<(), ClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(ClientError::Closed);
+        };
+        self.producer
+            .send(record)
+            .map_err(|e| ClientError::from_kafka(record.topic, e))
+    }
+
+    /// Topics the consumer is currently subscribed to.
+    pub fn subscribed_topics(&self) -> Vec<String> {
+        self.subscribed.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Adds `topic` to the subscription unless it is already part of it.
+    fn ensure_subscribed(&self, topic: &str) -> Result<(), ClientError> {
+        let mut subscribed = self.subscribed.lock().unwrap();
+        if subscribed.contains(topic) {
+            return Ok(());
+        }
+        let topics: Vec<&str> = subscribed
+            .iter()
+            .map(String::as_str)
+            .chain([topic])
+            .collect();
+        self.consumer
+            .subscribe(&topics)
+            .map_err(|e| ClientError::from_kafka(topic, e))?;
+        subscribed.insert(topic.to_string());
+        Ok(())
+    }
+
+    /// The oldest message kept for `topic`, resuming its paused partitions once half of the
+    /// waiting messages were taken.
+    fn take_pending(&self, topic: &str) -> Option<KafkaMessage> {
+        let mut pending = self.pending.lock().unwrap();
+        let queue = pending.get_mut(topic)?;
+        let message = queue.pop_front()?;
+        if queue.len() <= self.max_pending / 2 {
+            let partitions = self
+                .paused
+                .lock()
+                .unwrap()
+                .remove(topic)
+                .unwrap_or_default();
+            for partition in partitions {
+                if let Err(error) = self.consumer.resume(topic, partition) {
+                    log::warn!("Resuming {} [{}] failed: {}", topic, partition, error);
+                }
+            }
+        }
+        Some(message)
+    }
+
+    /// Keeps `message` for when its topic is consumed, pausing its partition once the topic
+    /// has `max_pending` messages waiting.
+    fn keep_pending(&self, message: KafkaMessage) {
+        let mut pending = self.pending.lock().unwrap();
+        let queue = pending.entry(message.topic.clone()).or_default();
+        if queue.len() + 1 >= self.max_pending
+            && self
+                .paused
+                .lock()
+                .unwrap()
+                .entry(message.topic.clone())
+                .or_default()
+                .insert(message.partition)
+        {
+            log::warn!(
+                "{} messages wait on {}, pausing partition {}",
+                queue.len() + 1,
+                message.topic,
+                message.partition
+            );
+            if let Err(error) = self.consumer.pause(&message.topic, message.partition) {
+                log::warn!(
+                    "Pausing {} [{}] failed: {}",
+                    message.topic,
+                    message.partition,
+                    error
+                );
+            }
+        }
+        queue.push_back(message);
+    }
+
+    /// Receives the next message published on `topic`, failing with `ClientError::Closed` as
+    /// soon as the client is closed.
+    pub async fn consume_bytes_async(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        if self.lifecycle.is_closed() {
+            return Err(ClientError::Closed);
+        }
+        self.ensure_subscribed(topic)?;
+        let message = match self.take_pending(topic) {
+            Some(message) => message,
+            None => loop {
+                let message = tokio::select! {
+                    _ = self.lifecycle.closed() => return Err(ClientError::Closed),
+                    message = self.consumer.recv() => message,
+                }
+                .map_err(|e| ClientError::from_kafka(topic, e))?;
+                if message.topic == topic {
+                    break message;
+                }
+                self.keep_pending(message);
+            },
+        };
+        // The message is handed over, so a commit may now cover it
+        if let Err(error) =
+            self.consumer
+                .store_offset(&message.topic, message.partition, message.offset)
+        {
+            log::warn!(
+                "Storing offset {} of {} [{}] failed: {}",
+                message.offset,
+                message.topic,
+                message.partition,
+                error
+            );
+        }
+        message
+            .payload
+            .ok_or_else(|| ClientError::InvalidPayload("empty message payload".to_string()))
+    }
+
+    /// Receives the next message on `topic`, or `None` if none arrives within `timeout`.
+    pub async fn consume_bytes_timeout_async(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, ClientError> {
+        match tokio::time::timeout(timeout, self.consume_bytes_async(topic)).await {
+            Ok(payload) => payload.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn block_on<T>(
+        &self,
+        future: impl Future<Output = Result<T, ClientError>>,
+    ) -> Result<T, ClientError> {
+        if Handle::try_current().is_ok() {
+            return Err(ClientError::Transport(
+                "Blocking consume called from within an async runtime, use consume_bytes_async"
+                    .to_string(),
+            ));
+        }
+        let runtime = self.runtime.as_ref().expect("runtime lives until drop");
+        runtime.block_on(future)
+    }
+
+    pub async fn consume_async(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes_async(topic).await?)
+    }
+}
+
+impl Drop for KafkaClient {
+    fn drop(&mut self) {
+        if !self.lifecycle.is_closed() {
+            log::warn!("Kafka client dropped without being closed, closing it now");
+            if let Err(error) = self.close(CLOSE_ON_DROP_TIMEOUT) {
+                log::warn!("Closing the dropped Kafka client failed: {}", error);
+            }
+        }
+        // A runtime may not be dropped from async code, which is where clients often end up
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
         }
     }
 }
 
 impl MessagingClient for KafkaClient {
-    fn produce(&self, topic: &str, message: &str) -> Result<(), String> {
-        let record: BaseRecord<'_, str, str> = BaseRecord::to(topic).payload(message);
-        self.producer.send(record).map_err(|(err, _)| err.to_string())?;
-        Ok(())
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.produce_bytes(topic, message.as_bytes())
     }
 
-    fn consume(&self, topic: &str) -> Result<String, String> {
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| format!("Failed to create runtime: {}", e))?;
-        
-        rt.block_on(async {
-            let consumer: &StreamConsumer = &self.consumer;
-            
-            match consumer.subscribe(&[topic]) {
-                Ok(_) => {
-                    match consumer.recv().await {
-                        Ok(message) => {
-                            match message.payload_view::<str>() {
-                                Some(Ok(payload)) => Ok(payload.to_string()),
-                                Some(Err(e)) => Err(format!("Error deserializing message payload: {}", e)),
-                                None => Err("Empty message payload".to_string()),
-                            }
-                        }
-                        Err(e) => Err(format!("Error receiving message: {}", e)),
-                    }
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes(topic)?)
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        self.send(KafkaRecord {
+            topic,
+            key: None,
+            partition: None,
+            payload,
+        })
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.send(KafkaRecord {
+            topic,
+            key: Some(key.as_bytes()),
+            partition: None,
+            payload,
+        })
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        self.block_on(self.consume_bytes_async(topic))
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.block_on(self.consume_bytes_timeout_async(topic, timeout))?
+            .map(utf8_payload)
+            .transpose()
+    }
+
+    /// Runs the receive loop as a task on the client's runtime, which keeps the client
+    /// alive until the subscription ends. Failed receives are skipped.
+    fn subscribe(
+        self: Arc<Self>,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        self.ensure_subscribed(topic)?;
+        let delivery = Delivery::new(handler);
+        let worker = delivery.clone();
+        let task_topic = topic.to_string();
+        let client = self.clone();
+        let runtime = self.runtime.as_ref().expect("runtime lives until drop");
+        let task = runtime.spawn(async move {
+            while worker.is_active() {
+                match client.consume_async(&task_topic).await {
+                    Ok(message) => worker.deliver(message),
+                    Err(ClientError::Closed) => worker.stop(),
+                    Err(_) => tokio::time::sleep(POLL_INTERVAL).await,
+                }
+            }
+        });
+        Ok(SubscriptionHandle::task(topic, delivery, task))
+    }
+
+    /// Runs the receive loop as a task on the client's runtime, which ends as soon as the
+    /// stream is dropped, even while waiting for a message.
+    fn consume_stream(self: Arc<Self>, topic: &str) -> MessageStream {
+        let (sender, receiver) = mpsc::channel(MESSAGE_STREAM_BUFFER);
+        let task_topic = topic.to_string();
+        let client = self.clone();
+        let runtime = self.runtime.as_ref().expect("runtime lives until drop");
+        runtime.spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    _ = sender.closed() => return,
+                    received = client.consume_bytes_async(&task_topic) => received,
+                };
+                let failed = received.is_err();
+                let closed = matches!(received, Err(ClientError::Closed));
+                let item = received.map(|payload| ConsumedMessage::new(&task_topic, payload));
+                // Waits while the buffer is full, and fails once the stream is dropped
+                if sender.send(item).await.is_err() || closed {
+                    return;
+                }
+                if failed {
+                    tokio::time::sleep(POLL_INTERVAL).await;
                 }
-                Err(e) => Err(format!("Error subscribing to topic: {}", e)),
             }
+        });
+        MessageStream::from_channel(topic, receiver)
+    }
+
+    /// Replies come back on the client's reply topic, subscribed to on the first request
+    /// before it goes out.
+    fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        self.ensure_subscribed(self.inbox.topic())?;
+        self.inbox.request(self, topic, message, timeout)
+    }
+
+    fn late_replies(&self) -> u64 {
+        self.inbox.late_replies()
+    }
+
+    /// Fetches the cluster metadata through the producer.
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        if self.lifecycle.is_closed() {
+            return Err(ClientError::Closed);
+        }
+        let started = Instant::now();
+        let broker = self
+            .producer
+            .probe(HEALTH_CHECK_TIMEOUT)
+            .map_err(|e| ClientError::from_kafka("", e))?;
+        Ok(HealthStatus {
+            latency: started.elapsed(),
+            broker: Some(broker),
+            version: None,
         })
     }
+
+    /// Flushes the producer and commits the consumer's offsets before leaving the group;
+    /// running subscriptions and streams end.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        if self.lifecycle.is_closed() {
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        self.lifecycle.close(timeout)?;
+        self.producer
+            .flush(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|e| ClientError::from_kafka("", e))?;
+        self.consumer
+            .close()
+            .map_err(|e| ClientError::from_kafka("", e))
+    }
 }
@@ -0,0 +1,107 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::ClientError;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Whether a client was closed, and the produces still running through it.
+///
+/// Once closed, a client refuses every call with `ClientError::Closed`; closing waits for the
+/// produces already running so none is cut off half-way.
+pub(crate) struct Lifecycle {
+    producing: Mutex<usize>,
+    idle: Condvar,
+    closed: watch::Sender<bool>,
+}
+
+/// A running produce, counted until it is dropped.
+pub(crate) struct Producing<'a>(&'a Lifecycle);
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            producing: Mutex::new(0),
+            idle: Condvar::new(),
+            closed: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Lifecycle {
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Counts a produce as running, or returns `None` once the client is closed.
+    pub(crate) fn producing(&self) -> Option<Producing<'_>> {
+        let mut producing = self.producing.lock().unwrap();
+        if self.is_closed() {
+            return None;
+        }
+        *producing += 1;
+        Some(Producing(self))
+    }
+
+    /// Resolves once the client is closed, to cut short a receive that would wait on.
+    pub(crate) async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        // Only fails once the sender is gone, and this lifecycle holds it
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+
+    /// Refuses every further call, then waits up to `timeout` for the running produces.
+    pub(crate) fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        let deadline = Instant::now() + timeout;
+        let mut producing = self.producing.lock().unwrap();
+        self.closed.send_replace(true);
+        while *producing > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ClientError::Transport(format!(
+                    "{} produces still running after {} ms",
+                    *producing,
+                    timeout.as_millis()
+                )));
+            }
+            producing = self.idle.wait_timeout(producing, remaining).unwrap().0;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Producing<'_> {
+    fn drop(&mut self) {
+        let mut producing = self.0.producing.lock().unwrap();
+        *producing -= 1;
+        if *producing == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
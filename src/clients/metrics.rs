@@ -0,0 +1,352 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::clients::lifecycle::Lifecycle;
+use crate::{
+    ClientError, HealthStatus, MessageHandler, MessageStream, MessagingClient, SubscriptionHandle,
+    Timestamp,
+};
+use futures_util::{stream, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Running counts of the messages and errors that went through a client.
+///
+/// Each counter is its own atomic, so a snapshot taken while traffic flows may mix
+/// counts from slightly different moments.
+#[derive(Debug, Default)]
+pub struct ClientMetrics {
+    messages_produced: AtomicU64,
+    bytes_produced: AtomicU64,
+    messages_consumed: AtomicU64,
+    produce_errors: AtomicU64,
+    consume_errors: AtomicU64,
+    dead_lettered: AtomicU64,
+    /// Epoch milliseconds of the last error; zero before the first.
+    last_error_ts: AtomicU64,
+    topics: Mutex<BTreeMap<String, TopicMetrics>>,
+}
+
+/// Messages sent and received on one topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopicMetrics {
+    pub messages_produced: u64,
+    pub messages_consumed: u64,
+}
+
+/// The counters of a `ClientMetrics` at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub messages_produced: u64,
+    pub bytes_produced: u64,
+    pub messages_consumed: u64,
+    pub produce_errors: u64,
+    pub consume_errors: u64,
+    /// Messages sent to a dead-letter topic; see `DeadLetterPolicy`.
+    pub dead_lettered: u64,
+    /// Replies that arrived when no request was waiting for them, and were dropped.
+    pub late_replies: u64,
+    /// When the last produce or consume error happened, if one did.
+    pub last_error_ts: Option<Timestamp>,
+    /// Messages per topic, for the topics that carried any.
+    pub topics: BTreeMap<String, TopicMetrics>,
+}
+
+impl ClientMetrics {
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let last_error_ts = self.last_error_ts.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            messages_produced: self.messages_produced.load(Ordering::Relaxed),
+            bytes_produced: self.bytes_produced.load(Ordering::Relaxed),
+            messages_consumed: self.messages_consumed.load(Ordering::Relaxed),
+            produce_errors: self.produce_errors.load(Ordering::Relaxed),
+            consume_errors: self.consume_errors.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            // Counted by the client itself; see `MessagingService::metrics`
+            late_replies: 0,
+            last_error_ts: (last_error_ts > 0).then(|| Timestamp::from_millis(last_error_ts)),
+            topics: self.topics.lock().unwrap().clone(),
+        }
+    }
+
+    /// Sets every counter back to zero.
+    pub fn reset(&self) {
+        for counter in [
+            &self.messages_produced,
+            &self.bytes_produced,
+            &self.messages_consumed,
+            &self.produce_errors,
+            &self.consume_errors,
+            &self.dead_lettered,
+            &self.last_error_ts,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.topics.lock().unwrap().clear();
+    }
+
+    pub(crate) fn record_dead_lettered(&self) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_produce(&self, topic: &str, bytes: usize, result: &Result<(), ClientError>) {
+        if result.is_err() {
+            self.record_error(&self.produce_errors);
+            return;
+        }
+        self.messages_produced.fetch_add(1, Ordering::Relaxed);
+        self.bytes_produced
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.update_topic(topic, |topic| topic.messages_produced += 1);
+    }
+
+    /// Counts a receive from `topic`; one that timed out without a message counts nothing.
+    fn record_consume<T>(&self, topic: &str, result: &Result<Option<T>, ClientError>) {
+        match result {
+            Ok(Some(_)) => self.record_received(topic),
+            Ok(None) => {}
+            Err(_) => self.record_error(&self.consume_errors),
+        }
+    }
+
+    fn record_received(&self, topic: &str) {
+        self.messages_consumed.fetch_add(1, Ordering::Relaxed);
+        self.update_topic(topic, |topic| topic.messages_consumed += 1);
+    }
+
+    fn record_error(&self, counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.last_error_ts
+            .store(Timestamp::now().as_millis(), Ordering::Relaxed);
+    }
+
+    fn update_topic(&self, topic: &str, update: impl FnOnce(&mut TopicMetrics)) {
+        let mut topics = self.topics.lock().unwrap();
+        match topics.get_mut(topic) {
+            Some(metrics) => update(metrics),
+            None => update(topics.entry(topic.to_string()).or_default()),
+        }
+    }
+}
+
+/// Counts every call that goes through it into `metrics` before handing back the client's
+/// result.
+///
+/// Every method is forwarded, so the client's own implementations are used, and anything
+/// layered on top, such as a wrapper retrying failed sends, has each attempt counted. Once
+/// closed it refuses every call itself, whatever the client does after its own close.
+pub(crate) struct MeteredClient {
+    client: Arc<dyn MessagingClient>,
+    metrics: Arc<ClientMetrics>,
+    lifecycle: Lifecycle,
+}
+
+impl MeteredClient {
+    pub(crate) fn new(client: Arc<dyn MessagingClient>, metrics: Arc<ClientMetrics>) -> Self {
+        MeteredClient {
+            client,
+            metrics,
+            lifecycle: Lifecycle::default(),
+        }
+    }
+
+    fn open(&self) -> Result<(), ClientError> {
+        match self.lifecycle.is_closed() {
+            true => Err(ClientError::Closed),
+            false => Ok(()),
+        }
+    }
+
+    fn produced(
+        &self,
+        topic: &str,
+        bytes: usize,
+        send: impl FnOnce() -> Result<(), ClientError>,
+    ) -> Result<(), ClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(ClientError::Closed);
+        };
+        let result = send();
+        self.metrics.record_produce(topic, bytes, &result);
+        result
+    }
+
+    fn consumed<T>(
+        &self,
+        topic: &str,
+        receive: impl FnOnce() -> Result<T, ClientError>,
+    ) -> Result<T, ClientError> {
+        self.open()?;
+        let result = receive();
+        match &result {
+            Ok(_) => self.metrics.record_received(topic),
+            Err(_) => self.metrics.record_error(&self.metrics.consume_errors),
+        }
+        result
+    }
+}
+
+impl MessagingClient for MeteredClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.produced(topic, message.len(), || self.client.produce(topic, message))
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        self.consumed(topic, || self.client.consume(topic))
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        self.produced(topic, payload.len(), || {
+            self.client.produce_bytes(topic, payload)
+        })
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        self.consumed(topic, || self.client.consume_bytes(topic))
+    }
+
+    fn produce_keyed(&self, topic: &str, key: &str, message: &str) -> Result<(), ClientError> {
+        self.produced(topic, message.len(), || {
+            self.client.produce_keyed(topic, key, message)
+        })
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.produced(topic, payload.len(), || {
+            self.client.produce_bytes_keyed(topic, key, payload)
+        })
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.open()?;
+        let result = self.client.consume_timeout(topic, timeout);
+        self.metrics.record_consume(topic, &result);
+        result
+    }
+
+    fn try_consume(&self, topic: &str) -> Result<Option<String>, ClientError> {
+        self.open()?;
+        let result = self.client.try_consume(topic);
+        self.metrics.record_consume(topic, &result);
+        result
+    }
+
+    fn subscribe(
+        self: Arc<Self>,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        self.open()?;
+        let metrics = self.metrics.clone();
+        let subscribed = topic.to_string();
+        self.client.clone().subscribe(
+            topic,
+            Box::new(move |message| {
+                metrics.record_received(&subscribed);
+                handler(message)
+            }),
+        )
+    }
+
+    /// Counts each message as it is taken from the stream.
+    fn consume_stream(self: Arc<Self>, topic: &str) -> MessageStream {
+        if self.lifecycle.is_closed() {
+            return MessageStream::new(topic, stream::iter([Err(ClientError::Closed)]));
+        }
+        let metrics = self.metrics.clone();
+        let streamed = topic.to_string();
+        let stream = self.client.clone().consume_stream(topic);
+        MessageStream::new(
+            topic,
+            stream.inspect(move |message| match message {
+                Ok(_) => metrics.record_received(&streamed),
+                Err(_) => metrics.record_error(&metrics.consume_errors),
+            }),
+        )
+    }
+
+    /// Counts the request as a message sent on `topic`, and its reply as one received there.
+    fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        self.open()?;
+        let result = self.client.request(topic, message, timeout);
+        match &result {
+            Ok(_) => {
+                self.metrics.record_produce(topic, message.len(), &Ok(()));
+                self.metrics.record_received(topic);
+            }
+            // The request went out but no reply came back
+            Err(ClientError::Timeout { .. }) => {
+                self.metrics.record_produce(topic, message.len(), &Ok(()));
+                self.metrics.record_error(&self.metrics.consume_errors);
+            }
+            Err(_) => self.metrics.record_error(&self.metrics.produce_errors),
+        }
+        result
+    }
+
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        self.open()?;
+        self.client.health_check()
+    }
+
+    /// Waits for the produces running through it, then closes the client with what is left of
+    /// `timeout`.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        if self.lifecycle.is_closed() {
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        self.lifecycle.close(timeout)?;
+        self.client
+            .close(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    fn late_replies(&self) -> u64 {
+        self.client.late_replies()
+    }
+
+    fn backend_metrics(&self) -> Vec<MetricsSnapshot> {
+        self.client.backend_metrics()
+    }
+}
@@ -0,0 +1,243 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::clients::common_client::utf8_payload;
+use crate::clients::lifecycle::Lifecycle;
+use crate::clients::request_reply::ReplyInbox;
+use crate::{ClientError, MessagingClient};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long `consume` waits for a message unless `with_timeout` says otherwise.
+pub const MOCK_CONSUME_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A message as the mock broker received it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockMessage {
+    pub topic: String,
+    pub key: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+/// A broker in process memory, for tests and for running without one.
+///
+/// Each topic is a queue: every message is received once, by whichever consumer asks first.
+/// Clones share the same queues, so a test can keep a clone to inspect what was sent and
+/// inject faults while the service owns another. Closing one clone closes them all.
+#[derive(Clone, Default)]
+pub struct MockClient {
+    shared: Arc<Shared>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    arrived: Condvar,
+    inbox: ReplyInbox,
+    lifecycle: Lifecycle,
+}
+
+#[derive(Default)]
+struct State {
+    topics: HashMap<String, VecDeque<Vec<u8>>>,
+    sent: Vec<MockMessage>,
+    produce_failures: VecDeque<ClientError>,
+    consume_failures: VecDeque<ClientError>,
+    latency: Duration,
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `consume` wait up to `timeout` for a message, instead of `MOCK_CONSUME_TIMEOUT`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fails the next send with `error`; queued failures are used up in order.
+    pub fn fail_next_produce(&self, error: ClientError) {
+        self.state().produce_failures.push_back(error);
+    }
+
+    /// Fails the next receive with `error`, leaving the waiting messages in place.
+    pub fn fail_next_consume(&self, error: ClientError) {
+        self.state().consume_failures.push_back(error);
+    }
+
+    /// Delays every send and receive by `latency`, as a remote broker would.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    /// Every message sent so far, in order, including those already consumed.
+    pub fn sent(&self) -> Vec<MockMessage> {
+        self.state().sent.clone()
+    }
+
+    /// Topic the replies to this client's requests are expected on; shared by its clones.
+    pub fn reply_topic(&self) -> &str {
+        self.shared.inbox.topic()
+    }
+
+    /// Messages waiting on `topic`.
+    pub fn pending(&self, topic: &str) -> usize {
+        self.state().topics.get(topic).map_or(0, VecDeque::len)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap()
+    }
+
+    fn delay(&self) {
+        let latency = self.state().latency;
+        if !latency.is_zero() {
+            std::thread::sleep(latency);
+        }
+    }
+
+    /// Sends after the latency, during which the send counts as pending for `close`.
+    fn send(&self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<(), ClientError> {
+        let Some(_producing) = self.shared.lifecycle.producing() else {
+            return Err(ClientError::Closed);
+        };
+        self.delay();
+        let mut state = self.state();
+        if let Some(error) = state.produce_failures.pop_front() {
+            return Err(error);
+        }
+        state
+            .topics
+            .entry(topic.to_string())
+            .or_default()
+            .push_back(payload.to_vec());
+        state.sent.push(MockMessage {
+            topic: topic.to_string(),
+            key: key.map(str::to_string),
+            payload: payload.to_vec(),
+        });
+        self.shared.arrived.notify_all();
+        Ok(())
+    }
+
+    fn receive(&self, topic: &str, timeout: Duration) -> Result<Option<Vec<u8>>, ClientError> {
+        self.delay();
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state();
+        if let Some(error) = state.consume_failures.pop_front() {
+            return Err(error);
+        }
+        loop {
+            if self.shared.lifecycle.is_closed() {
+                return Err(ClientError::Closed);
+            }
+            if let Some(payload) = state.topics.get_mut(topic).and_then(VecDeque::pop_front) {
+                return Ok(Some(payload));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            state = self
+                .shared
+                .arrived
+                .wait_timeout(state, remaining)
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(MOCK_CONSUME_TIMEOUT)
+    }
+}
+
+impl MessagingClient for MockClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.send(topic, None, message.as_bytes())
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes(topic)?)
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        self.send(topic, None, payload)
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        let timeout = self.timeout();
+        self.receive(topic, timeout)?
+            .ok_or_else(|| ClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: timeout.as_millis() as u64,
+            })
+    }
+
+    fn produce_bytes_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), ClientError> {
+        self.send(topic, Some(key), payload)
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.receive(topic, timeout)?.map(utf8_payload).transpose()
+    }
+
+    fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        self.shared.inbox.request(self, topic, message, timeout)
+    }
+
+    fn late_replies(&self) -> u64 {
+        self.shared.inbox.late_replies()
+    }
+
+    /// Waits for the sends still in their latency, then wakes the waiting receives to fail.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.shared.lifecycle.close(timeout)?;
+        let _state = self.state();
+        self.shared.arrived.notify_all();
+        Ok(())
+    }
+}
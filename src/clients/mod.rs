@@ -27,16 +27,35 @@ THE SOFTWARE.
 ******************************************************************************/
 // Declaring submodules within the clients module
 pub mod common_client;
+pub mod compression;
+pub mod dead_letter;
+pub mod envelope;
+pub mod fanout_client;
+pub mod file_client;
 pub mod kafka_client;
+pub mod lifecycle;
+pub mod metrics;
+pub mod mock_client;
 pub mod nats_client;
 pub mod rabbitmq_client;
 pub mod redis_client;
+pub mod request_reply;
+pub mod subscription;
 pub mod zeromq_client;
 
 // Re-exporting submodules to make them accessible from the clients module
 pub use common_client::*;
+pub use compression::*;
+pub use dead_letter::*;
+pub use envelope::*;
+pub use fanout_client::*;
+pub use file_client::*;
 pub use kafka_client::*;
+pub use metrics::*;
+pub use mock_client::*;
 pub use nats_client::*;
 pub use rabbitmq_client::*;
 pub use redis_client::*;
+pub use request_reply::*;
+pub use subscription::*;
 pub use zeromq_client::*;
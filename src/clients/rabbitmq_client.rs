@@ -26,29 +26,512 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::MessagingClient;
+use crate::clients::common_client::utf8_payload;
+use crate::clients::lifecycle::Lifecycle;
+use crate::{ClientError, HealthStatus, MessagingClient, RabbitMqConfig, HEALTH_CHECK_TIMEOUT};
+use lapin::options::{
+    BasicAckOptions, BasicGetOptions, BasicPublishOptions, ConfirmSelectOptions,
+    ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+};
+use lapin::publisher_confirm::Confirmation;
+use lapin::types::FieldTable;
+use lapin::uri::AMQPUri;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+use std::collections::HashSet;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::runtime::{Handle, Runtime};
 
+/// How often `consume_bytes_timeout_async` looks at an empty queue again.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Errors raised while talking to RabbitMQ.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RabbitMqClientError {
+    #[error("Invalid RabbitMQ URL: {0}")]
+    InvalidUrl(String),
+    #[error("RabbitMQ connection error: {0}")]
+    Connection(String),
+    /// The channel or connection closed under the operation; the next call reopens it.
+    #[error("RabbitMQ channel closed while handling topic {topic}: {reason}")]
+    ChannelClosed { topic: String, reason: String },
+    #[error("Error declaring RabbitMQ queue {queue} on exchange {exchange}: {reason}")]
+    Declare {
+        exchange: String,
+        queue: String,
+        reason: String,
+    },
+    #[error(
+        "Error publishing to RabbitMQ exchange {exchange} with routing key {routing_key}: {reason}"
+    )]
+    Publish {
+        exchange: String,
+        routing_key: String,
+        reason: String,
+    },
+    #[error(
+        "RabbitMQ broker nacked the message to exchange {exchange} with routing key {routing_key}"
+    )]
+    Nack {
+        exchange: String,
+        routing_key: String,
+    },
+    #[error("Error reading from RabbitMQ queue {queue}: {reason}")]
+    Consume { queue: String, reason: String },
+    #[error("Error acking delivery {delivery_tag} from RabbitMQ queue {queue}: {reason}")]
+    Ack {
+        queue: String,
+        delivery_tag: u64,
+        reason: String,
+    },
+    #[error("No message in RabbitMQ queue {queue}")]
+    Empty { queue: String },
+    #[error("Blocking RabbitMQ call from within an async runtime, use the async methods")]
+    InsideRuntime,
+    #[error("RabbitMQ client is closed")]
+    Closed,
+}
+
+impl RabbitMqClientError {
+    /// Whether repeating the operation may succeed: the connection is reopened, a nacked
+    /// message can be published again and an empty queue may fill up.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            RabbitMqClientError::Connection(_)
+                | RabbitMqClientError::ChannelClosed { .. }
+                | RabbitMqClientError::Nack { .. }
+                | RabbitMqClientError::Empty { .. }
+        )
+    }
+
+    /// Maps a lapin error, reporting a lost channel or connection as `ChannelClosed`.
+    fn from_lapin(
+        error: lapin::Error,
+        topic: &str,
+        other: impl FnOnce(String) -> RabbitMqClientError,
+    ) -> Self {
+        match error {
+            lapin::Error::InvalidChannel(_)
+            | lapin::Error::InvalidChannelState(_)
+            | lapin::Error::InvalidConnectionState(_)
+            | lapin::Error::IOError(_)
+            | lapin::Error::MissingHeartbeatError => RabbitMqClientError::ChannelClosed {
+                topic: topic.to_string(),
+                reason: error.to_string(),
+            },
+            error => other(error.to_string()),
+        }
+    }
+}
+
+impl From<RabbitMqClientError> for ClientError {
+    fn from(error: RabbitMqClientError) -> Self {
+        match error {
+            RabbitMqClientError::InvalidUrl(_) => ClientError::InvalidConfig {
+                section: "rabbitmq",
+                reason: error.to_string(),
+            },
+            RabbitMqClientError::Connection(_) | RabbitMqClientError::ChannelClosed { .. } => {
+                ClientError::Connection(error.to_string())
+            }
+            RabbitMqClientError::Publish { .. } | RabbitMqClientError::Nack { .. } => {
+                ClientError::Delivery {
+                    retriable: error.is_retryable(),
+                    detail: error.to_string(),
+                }
+            }
+            // basic_get does not wait for a message
+            RabbitMqClientError::Empty { queue } => ClientError::Timeout {
+                topic: queue,
+                timeout_ms: 0,
+            },
+            RabbitMqClientError::Declare { .. }
+            | RabbitMqClientError::Consume { .. }
+            | RabbitMqClientError::Ack { .. }
+            | RabbitMqClientError::InsideRuntime => ClientError::Transport(error.to_string()),
+            RabbitMqClientError::Closed => ClientError::Closed,
+        }
+    }
+}
+
+/// An open connection with its channel, and the queues declared on it.
+struct Session {
+    connection: Connection,
+    channel: Channel,
+    declared: HashSet<String>,
+}
+
+impl Session {
+    fn is_open(&self) -> bool {
+        self.connection.status().connected() && self.channel.status().connected()
+    }
+}
+
+/// RabbitMQ client publishing to a topic exchange, with the topic as routing key, and reading
+/// from a queue of the same name bound to it.
+///
+/// The exchange, queue and binding are declared the first time a topic is used on a
+/// connection; declaring is idempotent, so clients sharing a topic do not conflict. Publishes
+/// wait for the broker's confirm. Unless `auto_ack` is set, a consumed message is acked once
+/// it has been received, so a message lost in transit is redelivered.
+///
+/// The connection is opened on first use and reopened when it or its channel has closed. The
+/// blocking methods run on a runtime owned by the client. Inside an async service use the
+/// `_async` methods instead; the blocking ones return an error when called from within a
+/// runtime.
 pub struct RabbitMQClient {
-    // RabbitMQ specific configuration
+    uri: AMQPUri,
+    exchange: String,
+    durable: bool,
+    auto_ack: bool,
+    runtime: Option<Runtime>,
+    connect_timeout: Option<Duration>,
+    session: tokio::sync::Mutex<Option<Session>>,
+    lifecycle: Lifecycle,
+}
+
+impl RabbitMQClient {
+    pub fn from_config(config: &RabbitMqConfig) -> Result<Self, RabbitMqClientError> {
+        let uri = AMQPUri::from_str(config.rabbitmq_url.expose())
+            .map_err(RabbitMqClientError::InvalidUrl)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| RabbitMqClientError::Connection(e.to_string()))?;
+        Ok(RabbitMQClient {
+            uri,
+            exchange: config.exchange.clone(),
+            durable: config.durable,
+            auto_ack: config.auto_ack,
+            connect_timeout: config.connect_timeout_ms.map(Duration::from_millis),
+            runtime: Some(runtime),
+            session: tokio::sync::Mutex::new(None),
+            lifecycle: Lifecycle::default(),
+        })
+    }
+
+    pub fn exchange(&self) -> &str {
+        &self.exchange
+    }
+
+    pub fn exchange_options(&self) -> ExchangeDeclareOptions {
+        ExchangeDeclareOptions {
+            durable: self.durable,
+            ..ExchangeDeclareOptions::default()
+        }
+    }
+
+    pub fn queue_options(&self) -> QueueDeclareOptions {
+        QueueDeclareOptions {
+            durable: self.durable,
+            ..QueueDeclareOptions::default()
+        }
+    }
+
+    pub fn get_options(&self) -> BasicGetOptions {
+        BasicGetOptions {
+            no_ack: self.auto_ack,
+        }
+    }
+
+    /// Properties of published messages: persistent (delivery mode 2) on durable queues.
+    pub fn message_properties(&self) -> BasicProperties {
+        BasicProperties::default().with_delivery_mode(if self.durable { 2 } else { 1 })
+    }
+
+    async fn connect(&self) -> Result<Session, RabbitMqClientError> {
+        let connection_error = |e: lapin::Error| RabbitMqClientError::Connection(e.to_string());
+        let connect = Connection::connect_uri(self.uri.clone(), ConnectionProperties::default());
+        let connection = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                RabbitMqClientError::Connection(format!(
+                    "no connection within {} ms",
+                    timeout.as_millis()
+                ))
+            })?,
+            None => connect.await,
+        }
+        .map_err(connection_error)?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(connection_error)?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(connection_error)?;
+        Ok(Session {
+            connection,
+            channel,
+            declared: HashSet::new(),
+        })
+    }
+
+    /// Returns the open session, reconnecting if there is none, unless the client is closed.
+    async fn reopen<'a>(
+        &self,
+        session: &'a mut Option<Session>,
+    ) -> Result<&'a mut Session, RabbitMqClientError> {
+        if self.lifecycle.is_closed() {
+            return Err(RabbitMqClientError::Closed);
+        }
+        if !session.as_ref().is_some_and(Session::is_open) {
+            *session = Some(self.connect().await?);
+        }
+        Ok(session.as_mut().expect("session was just opened"))
+    }
+
+    /// Returns the open session, reconnecting if there is none, and declares `topic` on it.
+    async fn open<'a>(
+        &self,
+        session: &'a mut Option<Session>,
+        topic: &str,
+    ) -> Result<&'a Channel, RabbitMqClientError> {
+        let session = self.reopen(session).await?;
+        if !session.declared.contains(topic) {
+            self.declare(&session.channel, topic).await?;
+            session.declared.insert(topic.to_string());
+        }
+        Ok(&session.channel)
+    }
+
+    async fn declare(&self, channel: &Channel, topic: &str) -> Result<(), RabbitMqClientError> {
+        let declare_error = |e| {
+            RabbitMqClientError::from_lapin(e, topic, |reason| RabbitMqClientError::Declare {
+                exchange: self.exchange.clone(),
+                queue: topic.to_string(),
+                reason,
+            })
+        };
+        channel
+            .queue_declare(topic, self.queue_options(), FieldTable::default())
+            .await
+            .map_err(declare_error)?;
+        // The default exchange routes by queue name and can be neither declared nor bound
+        if self.exchange.is_empty() {
+            return Ok(());
+        }
+        channel
+            .exchange_declare(
+                &self.exchange,
+                ExchangeKind::Topic,
+                self.exchange_options(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(declare_error)?;
+        channel
+            .queue_bind(
+                topic,
+                &self.exchange,
+                topic,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(declare_error)
+    }
+
+    /// Opens and closes a channel on the connection, a round trip to the broker, reconnecting
+    /// first if the connection is down.
+    pub async fn health_check_async(&self) -> Result<HealthStatus, RabbitMqClientError> {
+        let probe = async {
+            let mut session = self.session.lock().await;
+            let session = self.reopen(&mut session).await?;
+            let started = Instant::now();
+            let channel = session
+                .connection
+                .create_channel()
+                .await
+                .map_err(|e| RabbitMqClientError::Connection(e.to_string()))?;
+            let latency = started.elapsed();
+            // The probe succeeded once the channel opened; closing it is only cleanup
+            let _ = channel.close(200, "health check").await;
+            Ok(latency)
+        };
+        let latency = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, probe)
+            .await
+            .map_err(|_| RabbitMqClientError::Connection("health check timed out".to_string()))??;
+        Ok(HealthStatus {
+            latency,
+            broker: Some(format!(
+                "{}:{}",
+                self.uri.authority.host, self.uri.authority.port
+            )),
+            version: None,
+        })
+    }
+
+    pub async fn produce_bytes_async(
+        &self,
+        topic: &str,
+        payload: &[u8],
+    ) -> Result<(), RabbitMqClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(RabbitMqClientError::Closed);
+        };
+        let publish_error = |e| {
+            RabbitMqClientError::from_lapin(e, topic, |reason| RabbitMqClientError::Publish {
+                exchange: self.exchange.clone(),
+                routing_key: topic.to_string(),
+                reason,
+            })
+        };
+        let mut session = self.session.lock().await;
+        let channel = self.open(&mut session, topic).await?;
+        let confirmation = channel
+            .basic_publish(
+                &self.exchange,
+                topic,
+                BasicPublishOptions::default(),
+                payload,
+                self.message_properties(),
+            )
+            .await
+            .map_err(publish_error)?
+            .await
+            .map_err(publish_error)?;
+        match confirmation {
+            Confirmation::Nack(_) => Err(RabbitMqClientError::Nack {
+                exchange: self.exchange.clone(),
+                routing_key: topic.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Takes the next message from the queue of `topic`, failing with `Empty` if there is none.
+    pub async fn consume_bytes_async(&self, topic: &str) -> Result<Vec<u8>, RabbitMqClientError> {
+        let mut session = self.session.lock().await;
+        let channel = self.open(&mut session, topic).await?;
+        let message = channel
+            .basic_get(topic, self.get_options())
+            .await
+            .map_err(|e| {
+                RabbitMqClientError::from_lapin(e, topic, |reason| RabbitMqClientError::Consume {
+                    queue: topic.to_string(),
+                    reason,
+                })
+            })?
+            .ok_or_else(|| RabbitMqClientError::Empty {
+                queue: topic.to_string(),
+            })?;
+        let delivery = message.delivery;
+        if !self.auto_ack {
+            delivery
+                .acker
+                .ack(BasicAckOptions::default())
+                .await
+                .map_err(|e| {
+                    RabbitMqClientError::from_lapin(e, topic, |reason| RabbitMqClientError::Ack {
+                        queue: topic.to_string(),
+                        delivery_tag: delivery.delivery_tag,
+                        reason,
+                    })
+                })?;
+        }
+        Ok(delivery.data)
+    }
+
+    /// Polls the queue of `topic` until a message arrives, or returns `None` after `timeout`.
+    pub async fn consume_bytes_timeout_async(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, RabbitMqClientError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.consume_bytes_async(topic).await {
+                Err(RabbitMqClientError::Empty { .. }) => {}
+                result => return result.map(Some),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep((deadline - now).min(POLL_INTERVAL)).await;
+        }
+    }
+
+    pub async fn consume_async(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes_async(topic).await?)
+    }
+
+    fn block_on<T>(
+        &self,
+        future: impl Future<Output = Result<T, RabbitMqClientError>>,
+    ) -> Result<T, RabbitMqClientError> {
+        if Handle::try_current().is_ok() {
+            return Err(RabbitMqClientError::InsideRuntime);
+        }
+        let runtime = self.runtime.as_ref().expect("runtime lives until drop");
+        runtime.block_on(future)
+    }
+}
+
+impl Default for RabbitMQClient {
+    fn default() -> Self {
+        Self::from_config(&RabbitMqConfig::default()).expect("default RabbitMQ URL is valid")
+    }
 }
 
-impl crate::RabbitMQClient {
-    pub fn new() -> Self {
-        // Initialize RabbitMQ client
-        crate::RabbitMQClient {}
+impl Drop for RabbitMQClient {
+    fn drop(&mut self) {
+        // A runtime may not be dropped from async code, which is where clients often end up
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
     }
 }
 
-impl MessagingClient for crate::RabbitMQClient {
-    fn produce(&self, topic: &str, message: &str) -> Result<(), String> {
-        // NATS-specific produce logic
-        println!("Producing message to RabbitMQ topic {}: {}", topic, message);
-        Ok(())
+impl MessagingClient for RabbitMQClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.produce_bytes(topic, message.as_bytes())
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes(topic)?)
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        Ok(self.block_on(self.produce_bytes_async(topic, payload))?)
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        Ok(self.block_on(self.consume_bytes_async(topic))?)
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.block_on(self.consume_bytes_timeout_async(topic, timeout))?
+            .map(utf8_payload)
+            .transpose()
+    }
+
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        Ok(self.block_on(self.health_check_async())?)
     }
 
-    fn consume(&self, topic: &str) -> Result<String, String> {
-        // RabbitMQ-specific consume logic
-        println!("Consuming message from NATS topic {}", topic);
-        Ok("RabbitMQ message".to_string())
+    /// Publishes are confirmed before they return, so closing waits for the running ones and
+    /// then closes the connection, which also closes its channel.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.lifecycle.close(timeout)?;
+        Ok(self.block_on(async {
+            let Some(session) = self.session.lock().await.take() else {
+                return Ok(());
+            };
+            session
+                .connection
+                .close(200, "client closed")
+                .await
+                .map_err(|e| RabbitMqClientError::Connection(e.to_string()))
+        })?)
     }
 }
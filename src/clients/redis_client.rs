@@ -26,29 +26,687 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::MessagingClient;
+use crate::clients::common_client::utf8_payload;
+use crate::clients::lifecycle::Lifecycle;
+use crate::clients::request_reply::ReplyInbox;
+use crate::{ClientError, HealthStatus, MessagingClient, RedisConfig, RedisMode};
+use redis::streams::{
+    StreamAutoClaimOptions, StreamAutoClaimReply, StreamId, StreamReadOptions, StreamReadReply,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
 
+/// Errors raised while talking to Redis.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RedisClientError {
+    /// The connection could not be opened or broke; the next command reconnects.
+    #[error("Redis connection error: {0}")]
+    Connection(String),
+    /// The server refused the credentials in the URL.
+    #[error("Redis authentication error: {0}")]
+    Authentication(String),
+    #[error("Redis command error: {0}")]
+    Command(String),
+    #[error("No message on Redis topic {topic} within {timeout_ms} ms")]
+    Timeout { topic: String, timeout_ms: u64 },
+    #[error("Redis client is closed")]
+    Closed,
+}
+
+impl From<redis::RedisError> for RedisClientError {
+    fn from(error: redis::RedisError) -> Self {
+        if error.is_io_error() || error.is_connection_dropped() || error.is_connection_refusal() {
+            RedisClientError::Connection(error.to_string())
+        } else if error.kind() == redis::ErrorKind::AuthenticationFailed {
+            RedisClientError::Authentication(error.to_string())
+        } else {
+            RedisClientError::Command(error.to_string())
+        }
+    }
+}
+
+impl From<RedisClientError> for ClientError {
+    fn from(error: RedisClientError) -> Self {
+        match error {
+            RedisClientError::Connection(_) => ClientError::Connection(error.to_string()),
+            RedisClientError::Authentication(_) => ClientError::Auth(error.to_string()),
+            RedisClientError::Command(_) => ClientError::Transport(error.to_string()),
+            RedisClientError::Timeout { topic, timeout_ms } => {
+                ClientError::Timeout { topic, timeout_ms }
+            }
+            RedisClientError::Closed => ClientError::Closed,
+        }
+    }
+}
+
+/// An entry read from a stream; it stays pending in the consumer group until acked by `id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub payload: Vec<u8>,
+}
+
+/// Stream field holding the message payload.
+const PAYLOAD_FIELD: &str = "payload";
+
+impl TryFrom<StreamId> for StreamEntry {
+    type Error = RedisClientError;
+
+    fn try_from(entry: StreamId) -> Result<Self, Self::Error> {
+        let payload = entry.get(PAYLOAD_FIELD).ok_or_else(|| {
+            RedisClientError::Command(format!("Stream entry {} has no payload", entry.id))
+        })?;
+        Ok(StreamEntry {
+            id: entry.id,
+            payload,
+        })
+    }
+}
+
+/// The Redis commands the client relies on, so the connection can be swapped out in tests.
+///
+/// A zero `timeout` waits until a message arrives.
+pub trait RedisConnection: Send {
+    fn publish(&mut self, channel: &str, payload: &[u8]) -> Result<(), RedisClientError>;
+    fn rpush(&mut self, key: &str, payload: &[u8]) -> Result<(), RedisClientError>;
+    /// Pops the head of the list at `key`, or `None` if it stayed empty for `timeout`.
+    fn blpop(&mut self, key: &str, timeout: Duration) -> Result<Option<Vec<u8>>, RedisClientError>;
+    /// Subscribes the connection to `channel`; from then on it only receives published
+    /// messages.
+    fn subscribe(&mut self, channel: &str) -> Result<(), RedisClientError>;
+    /// The channel and payload of the next message on any subscribed channel, or `None` after
+    /// `timeout`.
+    fn next_message(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<(String, Vec<u8>)>, RedisClientError>;
+    /// Appends `payload` to `stream`, returning the entry's id.
+    fn xadd(&mut self, stream: &str, payload: &[u8]) -> Result<String, RedisClientError>;
+    /// Creates `group` at the start of `stream`, creating the stream too; an existing group
+    /// is left as it is.
+    fn create_group(&mut self, stream: &str, group: &str) -> Result<(), RedisClientError>;
+    /// Reads the next entry not yet delivered to `group`, or `None` if none arrived within
+    /// `timeout`.
+    fn xreadgroup(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        timeout: Duration,
+    ) -> Result<Option<StreamEntry>, RedisClientError>;
+    fn xack(&mut self, stream: &str, group: &str, id: &str) -> Result<(), RedisClientError>;
+    fn ping(&mut self) -> Result<(), RedisClientError>;
+    /// The `redis_version` the server reports in `INFO server`, if it does.
+    fn server_version(&mut self) -> Result<Option<String>, RedisClientError>;
+    /// Hands the entries pending in `group` for at least `min_idle` over to `consumer`.
+    fn xautoclaim(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+    ) -> Result<Vec<StreamEntry>, RedisClientError>;
+}
+
+impl RedisConnection for redis::Connection {
+    fn publish(&mut self, channel: &str, payload: &[u8]) -> Result<(), RedisClientError> {
+        redis::cmd("PUBLISH")
+            .arg(channel)
+            .arg(payload)
+            .query::<i64>(self)?;
+        Ok(())
+    }
+
+    fn rpush(&mut self, key: &str, payload: &[u8]) -> Result<(), RedisClientError> {
+        redis::cmd("RPUSH")
+            .arg(key)
+            .arg(payload)
+            .query::<i64>(self)?;
+        Ok(())
+    }
+
+    fn blpop(&mut self, key: &str, timeout: Duration) -> Result<Option<Vec<u8>>, RedisClientError> {
+        let popped: Option<(String, Vec<u8>)> = redis::cmd("BLPOP")
+            .arg(key)
+            .arg(timeout.as_secs_f64())
+            .query(self)?;
+        Ok(popped.map(|(_, payload)| payload))
+    }
+
+    fn subscribe(&mut self, channel: &str) -> Result<(), RedisClientError> {
+        // The confirmation is not a message, so `next_message` skips it
+        self.send_packed_command(&redis::cmd("SUBSCRIBE").arg(channel).get_packed_command())?;
+        Ok(())
+    }
+
+    fn next_message(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<(String, Vec<u8>)>, RedisClientError> {
+        let mut pubsub = self.as_pubsub();
+        pubsub.set_read_timeout((!timeout.is_zero()).then_some(timeout))?;
+        let message = match pubsub.get_message() {
+            Ok(message) => Ok(Some((
+                message.get_channel_name().to_string(),
+                message.get_payload_bytes().to_vec(),
+            ))),
+            Err(error) if error.is_timeout() => Ok(None),
+            Err(error) => Err(error.into()),
+        };
+        // Dropping a `PubSub` unsubscribes from every channel. Subscribing goes around it, so
+        // it holds no messages of its own and forgetting it keeps the subscriptions
+        std::mem::forget(pubsub);
+        message
+    }
+
+    fn xadd(&mut self, stream: &str, payload: &[u8]) -> Result<String, RedisClientError> {
+        Ok(redis::cmd("XADD")
+            .arg(stream)
+            .arg("*")
+            .arg(PAYLOAD_FIELD)
+            .arg(payload)
+            .query(self)?)
+    }
+
+    fn create_group(&mut self, stream: &str, group: &str) -> Result<(), RedisClientError> {
+        let created = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(stream)
+            .arg(group)
+            .arg("0")
+            .arg("MKSTREAM")
+            .query::<()>(self);
+        match created {
+            Err(error) if error.code() == Some("BUSYGROUP") => Ok(()),
+            created => Ok(created?),
+        }
+    }
+
+    fn xreadgroup(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        timeout: Duration,
+    ) -> Result<Option<StreamEntry>, RedisClientError> {
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(1)
+            .block(timeout.as_millis() as usize);
+        let reply: Option<StreamReadReply> = redis::cmd("XREADGROUP")
+            .arg(&options)
+            .arg("STREAMS")
+            .arg(stream)
+            .arg(">")
+            .query(self)?;
+        reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .next()
+            .map(StreamEntry::try_from)
+            .transpose()
+    }
+
+    fn xack(&mut self, stream: &str, group: &str, id: &str) -> Result<(), RedisClientError> {
+        redis::cmd("XACK")
+            .arg(stream)
+            .arg(group)
+            .arg(id)
+            .query::<i64>(self)?;
+        Ok(())
+    }
+
+    fn ping(&mut self) -> Result<(), RedisClientError> {
+        redis::cmd("PING").query::<String>(self)?;
+        Ok(())
+    }
+
+    fn server_version(&mut self) -> Result<Option<String>, RedisClientError> {
+        let info: String = redis::cmd("INFO").arg("server").query(self)?;
+        Ok(info
+            .lines()
+            .find_map(|line| line.strip_prefix("redis_version:"))
+            .map(|version| version.trim().to_string()))
+    }
+
+    fn xautoclaim(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+    ) -> Result<Vec<StreamEntry>, RedisClientError> {
+        let mut claimed = Vec::new();
+        let mut start = "0-0".to_string();
+        // Each call claims a batch and returns the id to continue from, 0-0 once done
+        loop {
+            let reply: StreamAutoClaimReply = redis::cmd("XAUTOCLAIM")
+                .arg(stream)
+                .arg(group)
+                .arg(consumer)
+                .arg(min_idle.as_millis() as u64)
+                .arg(&start)
+                .arg(StreamAutoClaimOptions::default().count(100))
+                .query(self)?;
+            for entry in reply.claimed {
+                claimed.push(StreamEntry::try_from(entry)?);
+            }
+            if reply.next_stream_id == "0-0" {
+                return Ok(claimed);
+            }
+            start = reply.next_stream_id;
+        }
+    }
+}
+
+type Connector = Box<dyn Fn() -> Result<Box<dyn RedisConnection>, RedisClientError> + Send + Sync>;
+
+/// The connection reading published messages, subscribed once to every channel read so far.
+#[derive(Default)]
+struct Subscriber {
+    connection: Option<Box<dyn RedisConnection>>,
+    channels: HashSet<String>,
+    /// Messages that arrived for a channel while another one was being read
+    pending: HashMap<String, VecDeque<Vec<u8>>>,
+}
+
+impl Subscriber {
+    /// Subscribes to `channel` unless the connection already is, then waits up to `timeout`
+    /// for the next message on any subscribed channel.
+    fn receive(
+        &mut self,
+        connect: &Connector,
+        channel: &str,
+        timeout: Duration,
+    ) -> Result<Option<(String, Vec<u8>)>, RedisClientError> {
+        let connection = match self.connection.as_mut() {
+            Some(connection) => connection,
+            None => self.connection.insert(connect()?),
+        };
+        if !self.channels.contains(channel) {
+            connection.subscribe(channel)?;
+            self.channels.insert(channel.to_string());
+        }
+        connection.next_message(timeout)
+    }
+}
+
+/// Redis client that publishes to channels, pushes onto lists or appends to streams,
+/// depending on its `RedisMode`.
+///
+/// Commands run on one connection and blocking reads on another, so a consumer waiting for
+/// a message never holds up a producer. In pub/sub mode a third connection subscribes once
+/// to each channel read and keeps the subscription, holding on to messages that arrive for
+/// one channel while another is read. Connections are opened on first use and reopened once
+/// when a command finds them broken.
+///
+/// In stream mode `consume` acks an entry as soon as it is read. Consumers that must not
+/// lose an entry when they crash mid-way read with `receive_entry`, `ack` it once handled,
+/// and call `claim_pending` on startup to take over entries another consumer left unacked.
 pub struct RedisClient {
-    // Redis specific configuration
+    mode: RedisMode,
+    timeout: Duration,
+    stream_prefix: String,
+    group: String,
+    consumer: String,
+    connect: Connector,
+    connection: Mutex<Option<Box<dyn RedisConnection>>>,
+    /// Connection for `BLPOP` and `XREADGROUP`, which block until a message arrives
+    reader: Mutex<Option<Box<dyn RedisConnection>>>,
+    subscriber: Mutex<Subscriber>,
+    /// Streams whose consumer group is known to exist
+    groups: Mutex<HashSet<String>>,
+    inbox: ReplyInbox,
+    lifecycle: Lifecycle,
 }
 
 impl RedisClient {
-    pub fn new() -> Self {
-        // Initialize Redis client
-        RedisClient {}
+    pub fn new(config: RedisConfig) -> Self {
+        let redis_url = config.redis_url.expose().to_string();
+        let connect_timeout = config.connect_timeout_ms.map(Duration::from_millis);
+        Self::with_connector(config, move || {
+            let client = redis::Client::open(redis_url.as_str())?;
+            let connection: Box<dyn RedisConnection> = match connect_timeout {
+                Some(timeout) => Box::new(client.get_connection_with_timeout(timeout)?),
+                None => Box::new(client.get_connection()?),
+            };
+            Ok(connection)
+        })
+    }
+
+    /// Creates a client whose connections come from `connect`.
+    pub fn with_connector<F>(config: RedisConfig, connect: F) -> Self
+    where
+        F: Fn() -> Result<Box<dyn RedisConnection>, RedisClientError> + Send + Sync + 'static,
+    {
+        RedisClient {
+            mode: config.mode,
+            timeout: Duration::from_millis(config.timeout_ms),
+            stream_prefix: config.stream_prefix,
+            group: config.consumer_group,
+            consumer: config.consumer_name,
+            connect: Box::new(connect),
+            connection: Mutex::new(None),
+            reader: Mutex::new(None),
+            subscriber: Mutex::new(Subscriber::default()),
+            groups: Mutex::new(HashSet::new()),
+            inbox: ReplyInbox::default(),
+            lifecycle: Lifecycle::default(),
+        }
+    }
+
+    pub fn mode(&self) -> RedisMode {
+        self.mode
+    }
+
+    /// Publishes, pushes or appends `payload` on `topic`.
+    pub fn send(&self, topic: &str, payload: &[u8]) -> Result<(), RedisClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(RedisClientError::Closed);
+        };
+        match self.mode {
+            RedisMode::PubSub => {
+                self.with_connection(|connection| connection.publish(topic, payload))
+            }
+            RedisMode::Queue => self.with_connection(|connection| connection.rpush(topic, payload)),
+            RedisMode::Stream => {
+                let stream = self.stream_key(topic);
+                self.with_connection(|connection| connection.xadd(&stream, payload))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Waits up to the configured timeout for the next message on `topic`.
+    pub fn receive(&self, topic: &str) -> Result<Vec<u8>, RedisClientError> {
+        self.wait_for(topic, self.timeout)?
+            .ok_or_else(|| RedisClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: self.timeout.as_millis() as u64,
+            })
+    }
+
+    /// Waits up to `timeout` for the next message on `topic`, or `None` if none arrived.
+    pub fn receive_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, RedisClientError> {
+        // Redis reads a zero timeout as no timeout, so not waiting means waiting a millisecond
+        self.wait_for(topic, timeout.max(Duration::from_millis(1)))
+    }
+
+    fn wait_for(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, RedisClientError> {
+        match self.mode {
+            RedisMode::PubSub => self.next_published(topic, timeout),
+            RedisMode::Queue => self.with_reader(|connection| connection.blpop(topic, timeout)),
+            RedisMode::Stream => {
+                let Some(entry) = self.read_entry(topic, timeout)? else {
+                    return Ok(None);
+                };
+                self.ack(topic, &entry.id)?;
+                Ok(Some(entry.payload))
+            }
+        }
+    }
+
+    /// Waits up to the configured timeout for the next entry of `topic`'s stream, leaving it
+    /// pending until it is acked.
+    pub fn receive_entry(&self, topic: &str) -> Result<StreamEntry, RedisClientError> {
+        self.require_streams()?;
+        self.read_entry(topic, self.timeout.max(Duration::from_millis(1)))?
+            .ok_or_else(|| RedisClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: self.timeout.as_millis() as u64,
+            })
+    }
+
+    /// Acknowledges the entry `message_id` of `topic`'s stream, so it is not handed out again.
+    pub fn ack(&self, topic: &str, message_id: &str) -> Result<(), RedisClientError> {
+        self.require_streams()?;
+        let stream = self.stream_key(topic);
+        self.with_connection(|connection| connection.xack(&stream, &self.group, message_id))
+    }
+
+    /// Takes over the entries of `topic`'s stream that other consumers of the group read but
+    /// left unacked for at least `min_idle`; they stay pending until this client acks them.
+    pub fn claim_pending(
+        &self,
+        topic: &str,
+        min_idle: Duration,
+    ) -> Result<Vec<StreamEntry>, RedisClientError> {
+        self.require_streams()?;
+        let stream = self.stream_key(topic);
+        self.ensure_group(&stream)?;
+        self.with_connection(|connection| {
+            connection.xautoclaim(&stream, &self.group, &self.consumer, min_idle)
+        })
+    }
+
+    fn read_entry(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<StreamEntry>, RedisClientError> {
+        let stream = self.stream_key(topic);
+        self.ensure_group(&stream)?;
+        self.with_reader(|connection| {
+            connection.xreadgroup(&stream, &self.group, &self.consumer, timeout)
+        })
+    }
+
+    /// PINGs the server, then asks it for its version.
+    pub fn probe(&self) -> Result<HealthStatus, RedisClientError> {
+        let started = Instant::now();
+        self.with_connection(|connection| connection.ping())?;
+        let latency = started.elapsed();
+        // INFO may be disabled on managed servers, which leaves the version unknown
+        let version = self
+            .with_connection(|connection| connection.server_version())
+            .unwrap_or(None);
+        Ok(HealthStatus {
+            latency,
+            broker: None,
+            version,
+        })
+    }
+
+    fn stream_key(&self, topic: &str) -> String {
+        format!("{}{}", self.stream_prefix, topic)
+    }
+
+    fn require_streams(&self) -> Result<(), RedisClientError> {
+        if self.mode == RedisMode::Stream {
+            Ok(())
+        } else {
+            Err(RedisClientError::Command(format!(
+                "Stream entries require RedisMode::Stream, not {:?}",
+                self.mode
+            )))
+        }
+    }
+
+    /// Creates the consumer group on `stream` the first time the stream is read.
+    fn ensure_group(&self, stream: &str) -> Result<(), RedisClientError> {
+        if self.groups.lock().unwrap().contains(stream) {
+            return Ok(());
+        }
+        self.with_connection(|connection| connection.create_group(stream, &self.group))?;
+        self.groups.lock().unwrap().insert(stream.to_string());
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the next message published on `topic`, keeping the ones
+    /// published on other subscribed channels meanwhile.
+    fn next_published(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, RedisClientError> {
+        let mut subscriber = self.subscriber.lock().unwrap();
+        if self.lifecycle.is_closed() {
+            return Err(RedisClientError::Closed);
+        }
+        if let Some(payload) = subscriber
+            .pending
+            .get_mut(topic)
+            .and_then(VecDeque::pop_front)
+        {
+            return Ok(Some(payload));
+        }
+        let deadline = Instant::now() + timeout;
+        let mut wait = timeout;
+        let mut retried = false;
+        loop {
+            match subscriber.receive(&self.connect, topic, wait) {
+                Ok(Some((channel, payload))) if channel == topic => return Ok(Some(payload)),
+                Ok(Some((channel, payload))) => {
+                    subscriber
+                        .pending
+                        .entry(channel)
+                        .or_default()
+                        .push_back(payload);
+                    // A zero timeout waits for good
+                    if !timeout.is_zero() {
+                        wait = deadline.saturating_duration_since(Instant::now());
+                        if wait.is_zero() {
+                            return Ok(None);
+                        }
+                    }
+                }
+                Ok(None) => return Ok(None),
+                Err(RedisClientError::Connection(error)) => {
+                    // The subscriptions went with the connection
+                    subscriber.connection = None;
+                    subscriber.channels.clear();
+                    if retried {
+                        return Err(RedisClientError::Connection(error));
+                    }
+                    retried = true;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn with_connection<T>(
+        &self,
+        command: impl Fn(&mut dyn RedisConnection) -> Result<T, RedisClientError>,
+    ) -> Result<T, RedisClientError> {
+        self.run(&self.connection, command)
+    }
+
+    fn with_reader<T>(
+        &self,
+        command: impl Fn(&mut dyn RedisConnection) -> Result<T, RedisClientError>,
+    ) -> Result<T, RedisClientError> {
+        self.run(&self.reader, command)
+    }
+
+    /// Runs `command` on the connection in `slot`, reconnecting and retrying once if the
+    /// connection turns out to be broken.
+    fn run<T>(
+        &self,
+        slot: &Mutex<Option<Box<dyn RedisConnection>>>,
+        command: impl Fn(&mut dyn RedisConnection) -> Result<T, RedisClientError>,
+    ) -> Result<T, RedisClientError> {
+        let mut connection = slot.lock().unwrap();
+        if self.lifecycle.is_closed() {
+            return Err(RedisClientError::Closed);
+        }
+        let mut retried = false;
+        loop {
+            let current = match connection.as_mut() {
+                Some(current) => current,
+                None => connection.insert((self.connect)()?),
+            };
+            match command(current.as_mut()) {
+                Err(RedisClientError::Connection(error)) => {
+                    *connection = None;
+                    if retried {
+                        return Err(RedisClientError::Connection(error));
+                    }
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RedisClient {
+    fn default() -> Self {
+        Self::new(RedisConfig::default())
     }
 }
 
 impl MessagingClient for RedisClient {
-    fn produce(&self, topic: &str, message: &str) -> Result<(), String> {
-        // Redis-specific produce logic
-        println!("Producing message to Redis topic {}: {}", topic, message);
-        Ok(())
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.produce_bytes(topic, message.as_bytes())
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes(topic)?)
     }
 
-    fn consume(&self, topic: &str) -> Result<String, String> {
-        // Redis-specific consume logic
-        println!("Consuming message from Redis topic {}", topic);
-        Ok("Redis message".to_string())
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        Ok(self.send(topic, payload)?)
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        Ok(self.receive(topic)?)
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.receive_timeout(topic, timeout)?
+            .map(utf8_payload)
+            .transpose()
+    }
+
+    /// Replies come back on a list or stream of the client's own. A channel would lose the
+    /// replies published between two reads, so pub/sub mode does not support requests.
+    fn request(
+        &self,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        if self.mode == RedisMode::PubSub {
+            return Err(RedisClientError::Command(
+                "Request-reply requires RedisMode::Queue or RedisMode::Stream, not PubSub"
+                    .to_string(),
+            )
+            .into());
+        }
+        self.inbox.request(self, topic, message, timeout)
+    }
+
+    fn late_replies(&self) -> u64 {
+        self.inbox.late_replies()
+    }
+
+    fn health_check(&self) -> Result<HealthStatus, ClientError> {
+        Ok(self.probe()?)
+    }
+
+    /// Commands complete before they return, so closing only waits for the running ones and
+    /// drops the connections.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.lifecycle.close(timeout)?;
+        self.connection.lock().unwrap().take();
+        self.reader.lock().unwrap().take();
+        self.subscriber.lock().unwrap().connection.take();
+        Ok(())
     }
 }
@@ -0,0 +1,201 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::clients::subscription::POLL_INTERVAL;
+use crate::{ClientError, Encoding, IdGenerator, MessagingClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, TryLockError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// A request as its responder receives it from a client without native request-reply.
+///
+/// The responder answers by sending `reply(..)` to `reply_to`, as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestMessage {
+    pub correlation_id: String,
+    /// Topic the requesting client receives its replies on.
+    pub reply_to: String,
+    pub body: String,
+}
+
+impl RequestMessage {
+    /// The reply to this request carrying `body`.
+    pub fn reply(&self, body: &str) -> ReplyMessage {
+        ReplyMessage {
+            correlation_id: self.correlation_id.clone(),
+            body: body.to_string(),
+        }
+    }
+}
+
+/// A reply to a `RequestMessage`, matched to its request by the correlation id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplyMessage {
+    pub correlation_id: String,
+    pub body: String,
+}
+
+/// The reply topic of a client, and the requests waiting for a reply on it.
+///
+/// One waiting request at a time receives from the topic and hands each reply to the
+/// request it answers, the others wait to be handed theirs. A reply no request is waiting
+/// for, such as one that came after its request timed out, is dropped and counted.
+pub(crate) struct ReplyInbox {
+    topic: String,
+    ids: IdGenerator,
+    /// Waiting requests by correlation id, with their reply once it came
+    waiting: Mutex<HashMap<String, Option<String>>>,
+    replied: Condvar,
+    receiving: Mutex<()>,
+    late_replies: AtomicU64,
+}
+
+/// An inbox on a topic of its own, for transports where a topic costs nothing, such as Redis
+/// pub/sub channels or the mock.
+impl Default for ReplyInbox {
+    fn default() -> Self {
+        Self::new(format!("replies.{}", Uuid::now_v7()))
+    }
+}
+
+impl ReplyInbox {
+    /// An inbox receiving the replies on `topic`.
+    pub(crate) fn new(topic: impl Into<String>) -> Self {
+        ReplyInbox {
+            topic: topic.into(),
+            ids: IdGenerator::uuid_v7(),
+            waiting: Mutex::new(HashMap::new()),
+            replied: Condvar::new(),
+            receiving: Mutex::new(()),
+            late_replies: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub(crate) fn late_replies(&self) -> u64 {
+        self.late_replies.load(Ordering::Relaxed)
+    }
+
+    /// Sends `message` to `topic` through `client` as a `RequestMessage` and waits up to
+    /// `timeout` for the reply to arrive on the inbox topic.
+    pub(crate) fn request(
+        &self,
+        client: &dyn MessagingClient,
+        topic: &str,
+        message: &str,
+        timeout: Duration,
+    ) -> Result<String, ClientError> {
+        let request = RequestMessage {
+            correlation_id: self.ids.next_id(),
+            reply_to: self.topic.clone(),
+            body: message.to_string(),
+        };
+        let id = &request.correlation_id;
+        self.waiting.lock().unwrap().insert(id.clone(), None);
+        let sent = Encoding::Json.encode(&request).and_then(|payload| {
+            client.produce_bytes_keyed(topic, id, &payload)?;
+            self.wait_for(client, id, Instant::now() + timeout)
+        });
+        // A reply handed over after the wait gave up still answers the request
+        let reply = self.waiting.lock().unwrap().remove(id).flatten();
+        match (sent, reply) {
+            (Err(error), _) => Err(error),
+            (Ok(()), Some(reply)) => Ok(reply),
+            (Ok(()), None) => Err(ClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+        }
+    }
+
+    /// Waits until the request `id` was answered or `deadline` passed.
+    fn wait_for(
+        &self,
+        client: &dyn MessagingClient,
+        id: &str,
+        deadline: Instant,
+    ) -> Result<(), ClientError> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || self.is_answered(id) {
+                return Ok(());
+            }
+            let wait = remaining.min(POLL_INTERVAL);
+            let receiving = match self.receiving.try_lock() {
+                Ok(receiving) => Some(receiving),
+                Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            };
+            if receiving.is_none() {
+                let waiting = self.waiting.lock().unwrap();
+                if waiting.get(id).is_some_and(Option::is_none) {
+                    drop(self.replied.wait_timeout(waiting, wait).unwrap());
+                }
+                continue;
+            }
+            let received = client.consume_timeout(&self.topic, wait);
+            drop(receiving);
+            // Lets another waiting request take over receiving when this one is answered
+            self.replied.notify_all();
+            if let Some(reply) = received? {
+                self.deliver(&reply);
+            }
+        }
+    }
+
+    fn is_answered(&self, id: &str) -> bool {
+        self.waiting
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some_and(Option::is_some)
+    }
+
+    /// Hands `reply` to the request it answers, or drops it if none is waiting.
+    fn deliver(&self, reply: &str) {
+        let mut waiting = self.waiting.lock().unwrap();
+        let slot = serde_json::from_str::<ReplyMessage>(reply)
+            .ok()
+            .and_then(|reply| Some((waiting.get_mut(&reply.correlation_id)?, reply.body)));
+        match slot {
+            Some((slot @ None, body)) => {
+                *slot = Some(body);
+                self.replied.notify_all();
+            }
+            _ => {
+                self.late_replies.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
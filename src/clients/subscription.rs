@@ -0,0 +1,249 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 25/5/24
+******************************************************************************/
+
+use crate::{ClientError, ConsumedMessage, MessagingClient};
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Called with every message received on a subscribed topic.
+pub type MessageHandler = Box<dyn Fn(String) + Send + Sync>;
+
+/// How long a polling subscription waits for a message before checking whether it was
+/// cancelled, and how long it backs off after a failed receive.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many received messages a `MessageStream` holds before its worker waits for them to
+/// be taken.
+pub const MESSAGE_STREAM_BUFFER: usize = 16;
+
+/// The handler of a subscription and whether it still wants messages.
+///
+/// A message is delivered while holding the lock, so once `stop` returns the handler is
+/// neither running nor called again.
+#[derive(Clone)]
+pub(crate) struct Delivery {
+    active: Arc<Mutex<bool>>,
+    handler: Arc<MessageHandler>,
+}
+
+impl Delivery {
+    pub(crate) fn new(handler: MessageHandler) -> Self {
+        Delivery {
+            active: Arc::new(Mutex::new(true)),
+            handler: Arc::new(handler),
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        *self.active.lock().unwrap()
+    }
+
+    /// Hands `message` to the handler unless the subscription was cancelled.
+    pub(crate) fn deliver(&self, message: String) {
+        let active = self.active.lock().unwrap();
+        if *active {
+            (self.handler)(message);
+        }
+    }
+
+    pub(crate) fn stop(&self) {
+        *self.active.lock().unwrap() = false;
+    }
+}
+
+enum Worker {
+    Thread(JoinHandle<()>),
+    Task(tokio::task::JoinHandle<()>),
+}
+
+/// A running subscription. Dropping it, or calling `unsubscribe`, stops the delivery of
+/// messages; it must not be dropped from within its own handler.
+#[must_use = "the subscription is cancelled when its handle is dropped"]
+pub struct SubscriptionHandle {
+    topic: String,
+    delivery: Delivery,
+    worker: Option<Worker>,
+}
+
+impl SubscriptionHandle {
+    /// Wraps a task that feeds `delivery` from an async receive loop.
+    pub(crate) fn task(topic: &str, delivery: Delivery, task: tokio::task::JoinHandle<()>) -> Self {
+        SubscriptionHandle {
+            topic: topic.to_string(),
+            delivery,
+            worker: Some(Worker::Task(task)),
+        }
+    }
+
+    /// Subscribes by polling `client` with `consume_timeout` on a background thread.
+    ///
+    /// Messages that cannot be received or decoded are skipped; after a transport error the
+    /// thread waits `POLL_INTERVAL` before trying again. The subscription ends once the client
+    /// is closed.
+    pub fn poll<C: MessagingClient + ?Sized>(
+        client: Arc<C>,
+        topic: &str,
+        handler: MessageHandler,
+    ) -> Self {
+        let delivery = Delivery::new(handler);
+        let worker = delivery.clone();
+        let thread_topic = topic.to_string();
+        let thread = std::thread::spawn(move || {
+            while worker.is_active() {
+                match client.consume_timeout(&thread_topic, POLL_INTERVAL) {
+                    Ok(Some(message)) => worker.deliver(message),
+                    Ok(None) => {}
+                    Err(ClientError::Closed) => worker.stop(),
+                    Err(_) => std::thread::sleep(POLL_INTERVAL),
+                }
+            }
+        });
+        SubscriptionHandle {
+            topic: topic.to_string(),
+            delivery,
+            worker: Some(Worker::Thread(thread)),
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.delivery.is_active()
+    }
+
+    /// Stops the subscription; no message is handled once this returns.
+    pub fn unsubscribe(mut self) {
+        self.cancel();
+    }
+
+    fn cancel(&mut self) {
+        self.delivery.stop();
+        match self.worker.take() {
+            // The thread notices within one poll interval
+            Some(Worker::Thread(thread)) if thread.thread().id() != std::thread::current().id() => {
+                let _ = thread.join();
+            }
+            Some(Worker::Task(task)) => task.abort(),
+            _ => {}
+        }
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// The messages received on a topic, as an async stream.
+///
+/// A background worker receives into a buffer of `MESSAGE_STREAM_BUFFER` messages and waits
+/// while it is full. Dropping the stream stops the worker; messages still in the buffer are
+/// dropped with it, as they would be if the process stopped.
+#[must_use = "streams do nothing unless polled"]
+pub struct MessageStream {
+    topic: String,
+    inner: Pin<Box<dyn Stream<Item = Result<ConsumedMessage, ClientError>> + Send>>,
+}
+
+impl MessageStream {
+    pub fn new(
+        topic: &str,
+        stream: impl Stream<Item = Result<ConsumedMessage, ClientError>> + Send + 'static,
+    ) -> Self {
+        MessageStream {
+            topic: topic.to_string(),
+            inner: Box::pin(stream),
+        }
+    }
+
+    /// Streams what a worker sends through the other end of `receiver`, which is closed once
+    /// the stream is dropped.
+    pub(crate) fn from_channel(
+        topic: &str,
+        receiver: mpsc::Receiver<Result<ConsumedMessage, ClientError>>,
+    ) -> Self {
+        Self::new(
+            topic,
+            futures_util::stream::unfold(receiver, |mut receiver| async move {
+                let item = receiver.recv().await?;
+                Some((item, receiver))
+            }),
+        )
+    }
+
+    /// Streams by polling `client` with `consume_timeout` on a background thread.
+    ///
+    /// Failed receives are passed on; after one the thread waits `POLL_INTERVAL` before
+    /// trying again. Once the client is closed the stream yields `ClientError::Closed` and
+    /// ends.
+    pub fn poll<C: MessagingClient + ?Sized>(client: Arc<C>, topic: &str) -> Self {
+        let (sender, receiver) = mpsc::channel(MESSAGE_STREAM_BUFFER);
+        let thread_topic = topic.to_string();
+        std::thread::spawn(move || {
+            while !sender.is_closed() {
+                let item = match client.consume_timeout(&thread_topic, POLL_INTERVAL) {
+                    Ok(Some(message)) => {
+                        Ok(ConsumedMessage::new(&thread_topic, message.into_bytes()))
+                    }
+                    Ok(None) => continue,
+                    Err(error) => Err(error),
+                };
+                let failed = item.is_err();
+                let closed = matches!(item, Err(ClientError::Closed));
+                // Waits while the buffer is full, and fails once the stream is dropped
+                if sender.blocking_send(item).is_err() || closed {
+                    break;
+                }
+                if failed {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+            }
+        });
+        Self::from_channel(topic, receiver)
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Result<ConsumedMessage, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
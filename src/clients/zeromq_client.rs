@@ -26,29 +26,291 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::MessagingClient;
+use crate::clients::common_client::utf8_payload;
+use crate::clients::lifecycle::Lifecycle;
+use crate::{ClientError, MessagingClient, SocketMode, ZeroMqConfig};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
 
-pub struct ZeroMQClient {
-    // ZeroMQ specific configuration
+/// Errors raised by the ZeroMQ client.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ZeroMqClientError {
+    /// The socket could not be created, bound or connected.
+    #[error("ZeroMQ socket error on {endpoint}: {reason}")]
+    Socket { endpoint: String, reason: String },
+    #[error("Error sending to ZeroMQ topic {topic}: {reason}")]
+    Send { topic: String, reason: String },
+    #[error("Error receiving from ZeroMQ topic {topic}: {reason}")]
+    Receive { topic: String, reason: String },
+    #[error("Malformed ZeroMQ message: expected [topic, payload] frames, got {frames}")]
+    Malformed { frames: usize },
+    #[error("No message on ZeroMQ topic {topic} within {timeout_ms} ms")]
+    Timeout { topic: String, timeout_ms: u64 },
+    #[error("ZeroMQ client is closed")]
+    Closed,
 }
 
-impl crate::ZeroMQClient {
-    pub fn new() -> Self {
-        // Initialize ZeroMQ client
-        crate::ZeroMQClient {}
+impl From<ZeroMqClientError> for ClientError {
+    fn from(error: ZeroMqClientError) -> Self {
+        match error {
+            ZeroMqClientError::Socket { .. } => ClientError::Connection(error.to_string()),
+            // Mostly a missing peer or reply; REQ sockets are relaxed so a resend is allowed
+            ZeroMqClientError::Send { .. } => ClientError::Delivery {
+                retriable: true,
+                detail: error.to_string(),
+            },
+            ZeroMqClientError::Receive { .. } => ClientError::Transport(error.to_string()),
+            ZeroMqClientError::Malformed { .. } => ClientError::InvalidPayload(error.to_string()),
+            ZeroMqClientError::Timeout { topic, timeout_ms } => {
+                ClientError::Timeout { topic, timeout_ms }
+            }
+            ZeroMqClientError::Closed => ClientError::Closed,
+        }
     }
 }
 
-impl MessagingClient for crate::ZeroMQClient {
-    fn produce(&self, topic: &str, message: &str) -> Result<(), String> {
-        // NATS-specific produce logic
-        println!("Producing message to ZeroMQ topic {}: {}", topic, message);
+/// The receiving socket with its topic subscriptions and the messages read for other topics.
+struct Receiver {
+    socket: zmq::Socket,
+    subscribed: HashSet<String>,
+    pending: HashMap<String, VecDeque<Vec<u8>>>,
+}
+
+/// ZeroMQ client sending `[topic, payload]` multipart messages.
+///
+/// Producing uses the PUB, REQ or PUSH socket of the `SocketMode`, consuming the matching
+/// SUB, REP or PULL socket; each is opened on first use and bound to or connected to the
+/// configured endpoint. In PUB/SUB mode a consume subscribes to its topic, and messages
+/// published before the subscription reached the publisher are lost. In REQ/REP mode a
+/// produce waits for the consumer's empty acknowledgement. Messages that arrive for another
+/// topic are kept until that topic is consumed.
+pub struct ZeroMQClient {
+    context: zmq::Context,
+    endpoint: String,
+    mode: SocketMode,
+    bind: bool,
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    sender: Mutex<Option<zmq::Socket>>,
+    receiver: Mutex<Option<Receiver>>,
+    lifecycle: Lifecycle,
+}
+
+impl ZeroMQClient {
+    pub fn from_config(config: &ZeroMqConfig) -> Self {
+        Self::with_context(config, zmq::Context::new())
+    }
+
+    /// Creates a client on a shared context, which `inproc://` endpoints require.
+    pub fn with_context(config: &ZeroMqConfig, context: zmq::Context) -> Self {
+        ZeroMQClient {
+            context,
+            endpoint: config.zmq_url.clone(),
+            mode: config.mode,
+            bind: config.bind,
+            timeout: Duration::from_millis(config.timeout_ms),
+            connect_timeout: config.connect_timeout_ms.map(Duration::from_millis),
+            sender: Mutex::new(None),
+            receiver: Mutex::new(None),
+            lifecycle: Lifecycle::default(),
+        }
+    }
+
+    pub fn mode(&self) -> SocketMode {
+        self.mode
+    }
+
+    fn timeout_ms(&self) -> u64 {
+        self.timeout.as_millis() as u64
+    }
+
+    fn open_socket(&self, socket_type: zmq::SocketType) -> Result<zmq::Socket, ZeroMqClientError> {
+        let socket_error = |e: zmq::Error| ZeroMqClientError::Socket {
+            endpoint: self.endpoint.clone(),
+            reason: e.to_string(),
+        };
+        let timeout = self.timeout.as_millis().min(i32::MAX as u128) as i32;
+        let socket = self.context.socket(socket_type).map_err(socket_error)?;
+        socket.set_sndtimeo(timeout).map_err(socket_error)?;
+        socket.set_rcvtimeo(timeout).map_err(socket_error)?;
+        // Give queued messages the send timeout to go out, instead of blocking forever on drop
+        socket.set_linger(timeout).map_err(socket_error)?;
+        if let Some(connect_timeout) = self.connect_timeout {
+            let connect_timeout = connect_timeout.as_millis().min(i32::MAX as u128) as i32;
+            socket
+                .set_connect_timeout(connect_timeout)
+                .map_err(socket_error)?;
+        }
+        if socket_type == zmq::REQ {
+            // Allow a new request after one whose reply never came
+            socket.set_req_relaxed(true).map_err(socket_error)?;
+            socket.set_req_correlate(true).map_err(socket_error)?;
+        }
+        if self.bind {
+            socket.bind(&self.endpoint).map_err(socket_error)?;
+        } else {
+            socket.connect(&self.endpoint).map_err(socket_error)?;
+        }
+        Ok(socket)
+    }
+
+    /// Sends `payload` on `topic`, waiting for the acknowledgement in REQ/REP mode.
+    pub fn send(&self, topic: &str, payload: &[u8]) -> Result<(), ZeroMqClientError> {
+        let Some(_producing) = self.lifecycle.producing() else {
+            return Err(ZeroMqClientError::Closed);
+        };
+        let send_error = |reason: String| ZeroMqClientError::Send {
+            topic: topic.to_string(),
+            reason,
+        };
+        let mut sender = self.sender.lock().unwrap();
+        let socket = match sender.as_mut() {
+            Some(socket) => socket,
+            None => sender.insert(self.open_socket(match self.mode {
+                SocketMode::PubSub => zmq::PUB,
+                SocketMode::ReqRep => zmq::REQ,
+                SocketMode::PushPull => zmq::PUSH,
+            })?),
+        };
+        socket
+            .send_multipart([topic.as_bytes(), payload], 0)
+            .map_err(|e| send_error(e.to_string()))?;
+        if self.mode == SocketMode::ReqRep {
+            socket.recv_multipart(0).map_err(|e| match e {
+                zmq::Error::EAGAIN => {
+                    send_error(format!("no reply within {} ms", self.timeout_ms()))
+                }
+                e => send_error(e.to_string()),
+            })?;
+        }
         Ok(())
     }
 
-    fn consume(&self, topic: &str) -> Result<String, String> {
-        // ZeroMQ-specific consume logic
-        println!("Consuming message from NATS topic {}", topic);
-        Ok("ZeroMQ message".to_string())
+    /// Waits up to the configured timeout for the next message on `topic`.
+    pub fn receive(&self, topic: &str) -> Result<Vec<u8>, ZeroMqClientError> {
+        self.receive_timeout(topic, self.timeout)?
+            .ok_or_else(|| ZeroMqClientError::Timeout {
+                topic: topic.to_string(),
+                timeout_ms: self.timeout_ms(),
+            })
+    }
+
+    /// Waits up to `timeout` for the next message on `topic`; a zero timeout only
+    /// drains what has already arrived.
+    pub fn receive_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, ZeroMqClientError> {
+        if self.lifecycle.is_closed() {
+            return Err(ZeroMqClientError::Closed);
+        }
+        let receive_error = |e: zmq::Error| ZeroMqClientError::Receive {
+            topic: topic.to_string(),
+            reason: e.to_string(),
+        };
+        let mut receiver = self.receiver.lock().unwrap();
+        let receiver = match receiver.as_mut() {
+            Some(receiver) => receiver,
+            None => receiver.insert(Receiver {
+                socket: self.open_socket(match self.mode {
+                    SocketMode::PubSub => zmq::SUB,
+                    SocketMode::ReqRep => zmq::REP,
+                    SocketMode::PushPull => zmq::PULL,
+                })?,
+                subscribed: HashSet::new(),
+                pending: HashMap::new(),
+            }),
+        };
+        if let Some(payload) = receiver
+            .pending
+            .get_mut(topic)
+            .and_then(VecDeque::pop_front)
+        {
+            return Ok(Some(payload));
+        }
+        if self.mode == SocketMode::PubSub && !receiver.subscribed.contains(topic) {
+            receiver
+                .socket
+                .set_subscribe(topic.as_bytes())
+                .map_err(receive_error)?;
+            receiver.subscribed.insert(topic.to_string());
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            receiver
+                .socket
+                .set_rcvtimeo(remaining.as_millis().min(i32::MAX as u128) as i32)
+                .map_err(receive_error)?;
+            let frames = match receiver.socket.recv_multipart(0) {
+                Ok(frames) => frames,
+                Err(zmq::Error::EAGAIN) => return Ok(None),
+                Err(e) => return Err(receive_error(e)),
+            };
+            if self.mode == SocketMode::ReqRep {
+                receiver.socket.send("", 0).map_err(receive_error)?;
+            }
+            let [message_topic, payload] = <[Vec<u8>; 2]>::try_from(frames).map_err(|frames| {
+                ZeroMqClientError::Malformed {
+                    frames: frames.len(),
+                }
+            })?;
+            // Subscriptions match by prefix, so `orders` also receives `orders_eu`
+            if message_topic == topic.as_bytes() {
+                return Ok(Some(payload));
+            }
+            receiver
+                .pending
+                .entry(String::from_utf8_lossy(&message_topic).into_owned())
+                .or_default()
+                .push_back(payload);
+        }
+    }
+}
+
+impl Default for ZeroMQClient {
+    fn default() -> Self {
+        Self::from_config(&ZeroMqConfig::default())
+    }
+}
+
+impl MessagingClient for ZeroMQClient {
+    fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+        self.produce_bytes(topic, message.as_bytes())
+    }
+
+    fn consume(&self, topic: &str) -> Result<String, ClientError> {
+        utf8_payload(self.consume_bytes(topic)?)
+    }
+
+    fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+        Ok(self.send(topic, payload)?)
+    }
+
+    fn consume_bytes(&self, topic: &str) -> Result<Vec<u8>, ClientError> {
+        Ok(self.receive(topic)?)
+    }
+
+    fn consume_timeout(
+        &self,
+        topic: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>, ClientError> {
+        self.receive_timeout(topic, timeout)?
+            .map(utf8_payload)
+            .transpose()
+    }
+
+    /// Waits for the running sends, then closes the sockets; the sending one lingers up to
+    /// the configured timeout for its queued messages to go out.
+    fn close(&self, timeout: Duration) -> Result<(), ClientError> {
+        self.lifecycle.close(timeout)?;
+        self.sender.lock().unwrap().take();
+        self.receiver.lock().unwrap().take();
+        Ok(())
     }
 }
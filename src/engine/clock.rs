@@ -0,0 +1,108 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::Timestamp;
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Source of the current time for the engine, so tests can run a schedule on simulated time.
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> Timestamp;
+
+    /// Resolves once the clock reaches `deadline`, at once if it already has.
+    fn sleep_until(&self, deadline: Timestamp) -> BoxFuture<'static, ()>;
+}
+
+/// The wall clock, sleeping on the tokio timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+
+    fn sleep_until(&self, deadline: Timestamp) -> BoxFuture<'static, ()> {
+        let wait = deadline.as_millis().saturating_sub(self.now().as_millis());
+        Box::pin(tokio::time::sleep(Duration::from_millis(wait)))
+    }
+}
+
+/// A clock that only moves when told to, waking the sleepers whose deadline it passes.
+///
+/// Clones share the same time, so a test can keep one to advance while the engine owns
+/// another.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    now: Arc<watch::Sender<Timestamp>>,
+}
+
+impl SimulatedClock {
+    pub fn new(start: Timestamp) -> Self {
+        SimulatedClock {
+            now: Arc::new(watch::Sender::new(start)),
+        }
+    }
+
+    /// Moves the clock forward by `elapsed`.
+    pub fn advance(&self, elapsed: Duration) {
+        self.now.send_modify(|now| {
+            *now =
+                Timestamp::from_millis(now.as_millis().saturating_add(elapsed.as_millis() as u64))
+        });
+    }
+
+    /// Moves the clock to `now`; a time in the past leaves it where it is.
+    pub fn set(&self, now: Timestamp) {
+        self.now.send_if_modified(|current| {
+            let later = now > *current;
+            if later {
+                *current = now;
+            }
+            later
+        });
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Timestamp {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline: Timestamp) -> BoxFuture<'static, ()> {
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            // Only fails once the sender is gone, and then the clock never reaches the deadline
+            if now.wait_for(|now| *now >= deadline).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
@@ -0,0 +1,505 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::audit::audit_log::{AuditEvent, AuditSink};
+use crate::clients::common_client::MessagingService;
+use crate::config::Config;
+use crate::engine::clock::{Clock, SystemClock};
+use crate::engine::market_data_dispatcher::MarketDataDispatcher;
+use crate::engine::risk_engine::{RiskEngine, RiskRejection};
+use crate::engine::router::Router;
+use crate::engine::scheduler::{
+    CancelReport, ChildOrderScheduler, ParentOrderHandle, RecoveryReport, SchedulerError,
+};
+use crate::engine::throttle::Throttle;
+use crate::models::amendments::CancelRequest;
+use crate::models::orders::{Validate, ValidationError};
+use crate::models::parent_state::{ParentOrderState, ParentStateError};
+use crate::persistence::order_store::{Persistence, PersistenceError};
+use crate::strategies::common_strategies::{enforce_split, OrderSplitStrategy, SplitError};
+use crate::strategies::registry::{StrategyError, StrategyRegistry};
+use crate::{ChildOrder, ParentOrder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// A strategy instance the engine splits parents with, shared with the `MarketDataDispatcher`
+/// feeding it market data.
+pub type SharedSplitStrategy = Arc<Mutex<Box<dyn OrderSplitStrategy + Send + Sync>>>;
+
+/// Reasons the engine refuses a parent order.
+#[derive(Debug, Error, PartialEq)]
+pub enum EngineError {
+    #[error("invalid parent order: {0}")]
+    InvalidParent(#[from] ValidationError),
+    #[error("parent {0} is already working")]
+    DuplicateParent(String),
+    #[error(transparent)]
+    Strategy(#[from] StrategyError),
+    #[error("invalid split: {0}")]
+    InvalidSplit(#[from] SplitError),
+    /// The strategy produced no child for the parent, e.g. a signal strategy still warming up.
+    #[error("strategy {strategy_id} produced no children for {parent_id}")]
+    EmptySplit {
+        parent_id: String,
+        strategy_id: String,
+    },
+    #[error(transparent)]
+    State(#[from] ParentStateError),
+    #[error("submitting a parent order requires a tokio runtime")]
+    NoRuntime,
+    /// The risk checks left no child to dispatch.
+    #[error("risk checks rejected the parent: {}", describe(.0))]
+    RiskRejected(Vec<RiskRejection>),
+    /// The kill switch was pulled; see `ExecutionEngine::resume`.
+    #[error("the engine is halted")]
+    Halted,
+    #[error("parent {0} is not known to the engine")]
+    UnknownParent(String),
+    #[error(transparent)]
+    Persistence(#[from] PersistenceError),
+}
+
+fn describe(rejections: &[RiskRejection]) -> String {
+    rejections
+        .iter()
+        .map(|rejection| rejection.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl From<SchedulerError> for EngineError {
+    fn from(error: SchedulerError) -> Self {
+        match error {
+            SchedulerError::DuplicateParent(parent_id) => EngineError::DuplicateParent(parent_id),
+            SchedulerError::NoRuntime => EngineError::NoRuntime,
+            SchedulerError::Halted => EngineError::Halted,
+            SchedulerError::Persistence(error) => EngineError::Persistence(error),
+        }
+    }
+}
+
+/// Splits parent orders with the strategy their `strategy_id` names and sends the children to
+/// the child-order topic of the messaging service, each at its `insert_at`.
+///
+/// The registry builds each strategy once, on the first parent naming it, with its parameters
+/// from the `strategies` section of the config; later parents share that instance. A strategy
+/// trading on signals needs market data before its first split: `feed_market_data` registers
+/// the instance with a `MarketDataDispatcher`.
+///
+/// Before they are scheduled, the children pass the pre-trade checks of a `RiskEngine` with
+/// the `risk` limits of the config. The children go out through a `ChildOrderScheduler`, so
+/// `submit` must be called from within a tokio runtime, held to the order-rate limits of the
+/// `throttle` section and sent to the venues of the `routing` section, if any.
+///
+/// With an `AuditSink`, the engine records why each child exists: every parent it takes,
+/// the signal and split behind its children, and the risk checks, then the dispatch, fills
+/// and cancels of the children, all correlated with the parent id.
+pub struct ExecutionEngine {
+    config: Config,
+    strategies: StrategyRegistry,
+    messaging: Arc<MessagingService>,
+    scheduler: ChildOrderScheduler,
+    risk: RiskEngine,
+    instances: Mutex<HashMap<String, SharedSplitStrategy>>,
+}
+
+impl ExecutionEngine {
+    /// Creates an engine on the wall clock; children go to the child-order topic of
+    /// `messaging`, see `MessagingService::topics`.
+    pub fn new(config: Config, strategies: StrategyRegistry, messaging: MessagingService) -> Self {
+        let messaging = Arc::new(messaging);
+        let mut scheduler = Self::build_scheduler(
+            Arc::new(SystemClock),
+            &messaging,
+            Throttle::new(config.throttle.clone().unwrap_or_default()),
+        );
+        if let Some(routing) = &config.routing {
+            scheduler = scheduler.with_router(Router::new(routing.clone()));
+        }
+        ExecutionEngine {
+            risk: RiskEngine::new(config.risk.clone().unwrap_or_default()),
+            scheduler,
+            config,
+            strategies,
+            messaging,
+            instances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Schedules the children on `clock` instead of the wall clock.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.scheduler = self.scheduler.clone().with_clock(Arc::new(clock));
+        self
+    }
+
+    /// Emits the decisions of the engine and the progress of the children to `audit`,
+    /// stamped with the engine's clock.
+    pub fn with_audit(mut self, audit: AuditSink) -> Self {
+        self.scheduler = self.scheduler.clone().with_audit(audit);
+        self
+    }
+
+    /// Saves each parent and its children to `persistence` as they progress, for `recover`
+    /// to pick them up after a restart.
+    pub fn with_persistence(mut self, persistence: impl Persistence + 'static) -> Self {
+        self.scheduler = self
+            .scheduler
+            .clone()
+            .with_persistence(Arc::new(persistence));
+        self
+    }
+
+    /// Holds the dispatch to the rate limits of `throttle` instead of those of the config.
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.scheduler = self.scheduler.clone().with_throttle(throttle);
+        self
+    }
+
+    /// Sends the children to the venues `router` picks instead of those of the config.
+    pub fn with_router(mut self, router: Router) -> Self {
+        self.scheduler = self.scheduler.clone().with_router(router);
+        self
+    }
+
+    /// Checks the children with `risk` instead of the limits of the config, e.g. to value
+    /// the exposure with a `PositionTracker` or to listen to the rejections.
+    pub fn with_risk_engine(mut self, risk: RiskEngine) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// A scheduler sending the children to the child-order topic of `messaging`.
+    fn build_scheduler(
+        clock: Arc<dyn Clock>,
+        messaging: &Arc<MessagingService>,
+        throttle: Throttle,
+    ) -> ChildOrderScheduler {
+        let messaging = messaging.clone();
+        let topic = messaging.topics().child_orders.clone();
+        ChildOrderScheduler::new(clock, move |child| {
+            messaging.produce_child_order(&topic, child)
+        })
+        .with_throttle(throttle)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn strategies(&self) -> &StrategyRegistry {
+        &self.strategies
+    }
+
+    pub fn messaging(&self) -> &MessagingService {
+        &self.messaging
+    }
+
+    pub fn scheduler(&self) -> &ChildOrderScheduler {
+        &self.scheduler
+    }
+
+    pub fn risk(&self) -> &RiskEngine {
+        &self.risk
+    }
+
+    pub fn throttle(&self) -> &Throttle {
+        self.scheduler.throttle()
+    }
+
+    pub fn router(&self) -> Option<&Router> {
+        self.scheduler.router()
+    }
+
+    pub fn audit(&self) -> &AuditSink {
+        self.scheduler.audit()
+    }
+
+    pub fn child_order_topic(&self) -> &str {
+        &self.messaging.topics().child_orders
+    }
+
+    /// Validates `parent`, splits it with the strategy named by its `strategy_id`, checks the
+    /// children against it and the risk limits, and schedules them for dispatch.
+    ///
+    /// A split that does not fit the parent is repaired unless `strict_split_validation` is
+    /// set, see [`enforce_split`].
+    ///
+    /// Children blocked by the risk checks are never dispatched and show as `Cancelled` in
+    /// the state of the returned handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EngineError::InvalidParent` for an invalid parent, `DuplicateParent` when a
+    /// parent with the same id is still working, `Strategy` when no strategy is registered
+    /// under its `strategy_id` or its parameters do not fit it, `EmptySplit` when it produced
+    /// no child, `InvalidSplit` when the children do not fit the parent under strict split
+    /// validation, `State` when they cannot
+    /// be tracked, `RiskRejected` when the risk checks block every child, `Halted` after the
+    /// kill switch, `Persistence` when the parent could not be saved, and `NoRuntime` outside
+    /// a tokio runtime.
+    pub fn submit(&self, parent: ParentOrder) -> Result<ParentOrderHandle, EngineError> {
+        if self.is_halted() {
+            return Err(EngineError::Halted);
+        }
+        parent.validate()?;
+        self.scheduler.check(&parent.order_common.id)?;
+        let audit = self.scheduler.audit();
+        let parent_id = parent.order_common.id.as_str();
+        let order = &parent.order_common;
+        audit.emit(
+            Some(parent_id),
+            AuditEvent::ParentReceived {
+                symbol: order.symbol.clone(),
+                side: order.side.clone(),
+                quantity: order.quantity,
+                strategy_id: parent.strategy_id.clone(),
+            },
+        );
+        let strategy = self.strategy(&parent.strategy_id)?;
+
+        let children = {
+            let strategy = strategy.lock().unwrap();
+            let children = strategy.split(&parent);
+            if audit.is_enabled() {
+                self.audit_split(&parent, strategy.as_ref(), &children);
+            }
+            children
+        };
+        let children = enforce_split(&parent, children, self.config.strict_split_validation)?;
+        if children.is_empty() {
+            return Err(EngineError::EmptySplit {
+                parent_id: parent.order_common.id.clone(),
+                strategy_id: parent.strategy_id.clone(),
+            });
+        }
+        let decision = self
+            .risk
+            .check(&parent, children.clone(), &self.scheduler.open_children());
+        if audit.is_enabled() {
+            audit.emit(
+                Some(parent_id),
+                AuditEvent::RiskChecked {
+                    accepted: decision
+                        .accepted
+                        .iter()
+                        .map(|child| child.order_common.id.clone())
+                        .collect(),
+                    rejected: decision
+                        .rejections
+                        .iter()
+                        .filter_map(|rejection| rejection.child_id.clone())
+                        .collect(),
+                    reasons: decision
+                        .rejections
+                        .iter()
+                        .map(|rejection| rejection.to_string())
+                        .collect(),
+                },
+            );
+        }
+        if decision.accepted.is_empty() && decision.is_rejected() {
+            return Err(EngineError::RiskRejected(decision.rejections));
+        }
+        // Also validates each child against the parent
+        let mut state = ParentOrderState::new(parent, children)?;
+        for child_id in decision
+            .rejections
+            .iter()
+            .filter_map(|rejection| rejection.child_id.as_ref())
+        {
+            state.on_cancel(child_id)?;
+        }
+        Ok(self.scheduler.schedule(state)?)
+    }
+
+    /// Emits the signal `strategy` split `parent` on, if it trades on signals, and the
+    /// children it produced with its parameters from the config.
+    fn audit_split(
+        &self,
+        parent: &ParentOrder,
+        strategy: &(dyn OrderSplitStrategy + Send + Sync),
+        children: &[ChildOrder],
+    ) {
+        let audit = self.scheduler.audit();
+        let parent_id = Some(parent.order_common.id.as_str());
+        if let Some(decision) = strategy.decision() {
+            audit.emit(
+                parent_id,
+                AuditEvent::decision(&parent.order_common.symbol, &parent.strategy_id, decision),
+            );
+        }
+        audit.emit(
+            parent_id,
+            AuditEvent::SplitProduced {
+                strategy_id: parent.strategy_id.clone(),
+                children: children
+                    .iter()
+                    .map(|child| child.order_common.id.clone())
+                    .collect(),
+                config: self
+                    .config
+                    .strategies
+                    .get(&parent.strategy_id.to_ascii_lowercase())
+                    .cloned(),
+                seed: strategy.seed(),
+            },
+        );
+    }
+
+    /// The instance of the strategy named `strategy_id` that splits the parents naming it,
+    /// built on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StrategyError` when no strategy is registered under `strategy_id` or its
+    /// parameters do not fit it.
+    pub fn strategy(&self, strategy_id: &str) -> Result<SharedSplitStrategy, StrategyError> {
+        let mut instances = self.instances.lock().unwrap();
+        let key = strategy_id.to_ascii_lowercase();
+        if let Some(strategy) = instances.get(&key) {
+            return Ok(strategy.clone());
+        }
+        let strategy = Arc::new(Mutex::new(
+            self.strategies
+                .create_from_config(strategy_id, &self.config)?,
+        ));
+        instances.insert(key, strategy.clone());
+        Ok(strategy)
+    }
+
+    /// Feeds the market data `dispatcher` receives for `symbol` to the instance of the
+    /// strategy named `strategy_id`, so a strategy trading on signals is ready when the
+    /// parents come.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EngineError::Strategy` when the strategy cannot be built, see `strategy`.
+    pub fn feed_market_data(
+        &self,
+        dispatcher: &MarketDataDispatcher,
+        symbol: &str,
+        strategy_id: &str,
+    ) -> Result<(), EngineError> {
+        dispatcher.feed(symbol, self.strategy(strategy_id)?);
+        Ok(())
+    }
+
+    /// Schedules again the orders the persistence holds with children still open, see
+    /// `ChildOrderScheduler::recover`. Call it once on startup, before taking new parents.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EngineError::Persistence` when the orders could not be loaded, and
+    /// `NoRuntime` outside a tokio runtime.
+    pub fn recover(&self) -> Result<RecoveryReport, EngineError> {
+        Ok(self.scheduler.recover()?)
+    }
+
+    /// The handle of the parent submitted with `parent_id`, while the engine remembers it.
+    pub fn parent(&self, parent_id: &str) -> Option<ParentOrderHandle> {
+        self.scheduler.parent(parent_id)
+    }
+
+    /// Stops the children of `parent_id` not dispatched yet and requests the cancel of those
+    /// dispatched and still working, on the cancels topic; see `ParentOrderHandle::cancel_all`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EngineError::UnknownParent` for a parent the engine does not remember.
+    pub fn cancel_parent(&self, parent_id: &str) -> Result<CancelReport, EngineError> {
+        let handle = self
+            .parent(parent_id)
+            .ok_or_else(|| EngineError::UnknownParent(parent_id.to_string()))?;
+        Ok(self.cancel_handle(&handle, "parent cancelled"))
+    }
+
+    /// Halts the engine, so `submit` refuses new parents until `resume`, then cancels every
+    /// parent like `cancel_parent`. Returns the reports of the parents that had children
+    /// left to cancel, by parent id.
+    ///
+    /// A parent `submit` is splitting meanwhile is refused when it comes to be scheduled.
+    pub fn kill_switch(&self) -> Vec<CancelReport> {
+        let mut parents = self.scheduler.halt();
+        log::warn!("Kill switch pulled, cancelling every parent");
+        parents.sort_by(|a, b| a.parent_id().cmp(b.parent_id()));
+        parents
+            .iter()
+            .map(|handle| self.cancel_handle(handle, "kill switch"))
+            .filter(|report| {
+                !report.cancelled.is_empty()
+                    || !report.pending_cancel.is_empty()
+                    || !report.failed.is_empty()
+            })
+            .collect()
+    }
+
+    /// Lets `submit` take parents again after the kill switch.
+    pub fn resume(&self) {
+        self.scheduler.resume();
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.scheduler.is_halted()
+    }
+
+    fn cancel_handle(&self, handle: &ParentOrderHandle, reason: &str) -> CancelReport {
+        let mut report = handle.cancel_all();
+        if let Some(positions) = self.risk.positions() {
+            // Children cancelled before dispatch will never be filled
+            for child_id in &report.cancelled {
+                positions.untrack_order(child_id);
+            }
+        }
+        let state = handle.state();
+        let topic = &self.messaging.topics().cancels;
+        let timestamp = self.scheduler.clock().now().as_millis();
+        let mut requested = Vec::new();
+        for child_id in std::mem::take(&mut report.pending_cancel) {
+            let request = CancelRequest {
+                symbol: state
+                    .child(&child_id)
+                    .map(|child| child.child.order_common.symbol.clone())
+                    .unwrap_or_default(),
+                order_id: child_id.clone(),
+                parent_id: report.parent_id.clone(),
+                reason: reason.to_string(),
+                timestamp,
+            };
+            match self.messaging.produce_json(topic, &request) {
+                Ok(()) => requested.push(child_id),
+                Err(error) => {
+                    log::warn!("Could not request the cancel of {}: {}", child_id, error);
+                    report.failed.push((child_id, error));
+                }
+            }
+        }
+        report.pending_cancel = requested;
+        report
+    }
+}
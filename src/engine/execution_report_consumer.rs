@@ -0,0 +1,359 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::clients::common_client::{ClientError, Encoding, MessagingService};
+use crate::clients::envelope::MessageEnvelope;
+use crate::clients::subscription::SubscriptionHandle;
+use crate::engine::scheduler::ChildOrderScheduler;
+use crate::models::fills::{Fill, FillError};
+use crate::models::orders::OrderStatus;
+use crate::models::parent_state::ParentStateError;
+use crate::models::quantity::Quantity;
+use crate::portfolio::position_tracker::PositionTracker;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// The kinds of execution reports that cannot be reconciled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    Malformed,
+    UnknownOrder,
+    DuplicateExecution,
+    Overfill,
+    Rejected,
+}
+
+impl fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AnomalyKind::Malformed => "malformed",
+            AnomalyKind::UnknownOrder => "unknown_order",
+            AnomalyKind::DuplicateExecution => "duplicate_execution",
+            AnomalyKind::Overfill => "overfill",
+            AnomalyKind::Rejected => "rejected",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why an execution report was not applied.
+#[derive(Debug, Clone, Error, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReportAnomaly {
+    #[error("report cannot be decoded: {error}")]
+    Malformed { error: String },
+    #[error("fill {exec_id} is for order {order_id}, which is not a known child")]
+    UnknownOrder { order_id: String, exec_id: String },
+    #[error("fill {exec_id} of order {order_id} was already applied")]
+    DuplicateExecution { order_id: String, exec_id: String },
+    #[error("fill {exec_id} of {fill_quantity} overfills order {order_id}: {filled} of {quantity} already filled")]
+    Overfill {
+        order_id: String,
+        exec_id: String,
+        quantity: Quantity,
+        filled: Quantity,
+        fill_quantity: Quantity,
+    },
+    #[error("fill {exec_id} cannot be applied to order {order_id}: {error}")]
+    Rejected {
+        order_id: String,
+        exec_id: String,
+        error: String,
+    },
+}
+
+impl ReportAnomaly {
+    pub fn kind(&self) -> AnomalyKind {
+        match self {
+            ReportAnomaly::Malformed { .. } => AnomalyKind::Malformed,
+            ReportAnomaly::UnknownOrder { .. } => AnomalyKind::UnknownOrder,
+            ReportAnomaly::DuplicateExecution { .. } => AnomalyKind::DuplicateExecution,
+            ReportAnomaly::Overfill { .. } => AnomalyKind::Overfill,
+            ReportAnomaly::Rejected { .. } => AnomalyKind::Rejected,
+        }
+    }
+
+    fn from_state_error(fill: &Fill, error: ParentStateError) -> Self {
+        match error {
+            ParentStateError::Fill(FillError::Overfill {
+                order_id,
+                exec_id,
+                quantity,
+                filled,
+                fill_quantity,
+            }) => ReportAnomaly::Overfill {
+                order_id,
+                exec_id,
+                quantity,
+                filled,
+                fill_quantity,
+            },
+            error => ReportAnomaly::Rejected {
+                order_id: fill.order_id.clone(),
+                exec_id: fill.exec_id.clone(),
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+/// What goes to the quarantine topic: the anomaly and the report as it was received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantinedReport {
+    pub anomaly: ReportAnomaly,
+    pub report: String,
+}
+
+/// The counters of an `ExecutionReportConsumer` at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportMetricsSnapshot {
+    pub reports: u64,
+    pub applied: u64,
+    /// Anomalies published to the quarantine topic
+    pub quarantined: u64,
+    /// Anomalies per kind, quarantined or not
+    pub anomalies: BTreeMap<AnomalyKind, u64>,
+}
+
+#[derive(Debug, Default)]
+struct ReportMetrics {
+    reports: AtomicU64,
+    applied: AtomicU64,
+    quarantined: AtomicU64,
+    anomalies: Mutex<BTreeMap<AnomalyKind, u64>>,
+}
+
+/// Turns a message of the fills topic into a fill.
+pub type ReportDecoder = dyn Fn(&str) -> Result<Fill, ClientError> + Send + Sync;
+
+/// Decodes a fill sent with `MessagingService::produce_fill`, in its envelope, or as plain
+/// JSON.
+pub fn decode_fill_report(message: &str) -> Result<Fill, ClientError> {
+    match Encoding::Json.decode::<MessageEnvelope>(message.as_bytes()) {
+        Ok(envelope) => envelope.open(),
+        Err(_) => Encoding::Json.decode(message.as_bytes()),
+    }
+}
+
+/// Reconciles the execution reports of the broker with the children the scheduler
+/// dispatched.
+///
+/// Each fill is matched to its child by order id and applied to the parent's
+/// `ParentOrderState`, and to the positions of a `PositionTracker` when given one. A fill is
+/// applied once: a report repeating the `exec_id` of an applied fill is a duplicate. Reports
+/// for unknown children, duplicates, overfills and reports that cannot be decoded or applied
+/// are anomalies, which leave the state untouched, are counted in the metrics and, when
+/// consumed with `subscribe`, go to the quarantine topic.
+///
+/// Reports are JSON fills; `with_decoder` reads another format. Clones share the applied
+/// executions and the metrics.
+#[derive(Clone)]
+pub struct ExecutionReportConsumer {
+    scheduler: ChildOrderScheduler,
+    positions: Option<PositionTracker>,
+    decoder: Arc<ReportDecoder>,
+    applied: Arc<Mutex<HashSet<String>>>,
+    metrics: Arc<ReportMetrics>,
+}
+
+impl ExecutionReportConsumer {
+    /// Reconciles reports with the parents of `scheduler`, e.g. `ExecutionEngine::scheduler`.
+    pub fn new(scheduler: ChildOrderScheduler) -> Self {
+        ExecutionReportConsumer {
+            scheduler,
+            positions: None,
+            decoder: Arc::new(decode_fill_report),
+            applied: Arc::new(Mutex::new(HashSet::new())),
+            metrics: Arc::new(ReportMetrics::default()),
+        }
+    }
+
+    /// Applies the reconciled fills to `positions` too.
+    pub fn with_positions(mut self, positions: PositionTracker) -> Self {
+        self.positions = Some(positions);
+        self
+    }
+
+    /// Decodes the reports with `decoder` instead of `decode_fill_report`.
+    pub fn with_decoder(
+        mut self,
+        decoder: impl Fn(&str) -> Result<Fill, ClientError> + Send + Sync + 'static,
+    ) -> Self {
+        self.decoder = Arc::new(decoder);
+        self
+    }
+
+    /// Applies `fill` to its child and returns the child's new status.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `ReportAnomaly` found; nothing is applied then.
+    pub fn apply(&self, fill: &Fill) -> Result<OrderStatus, ReportAnomaly> {
+        self.metrics.reports.fetch_add(1, Ordering::Relaxed);
+        let result = self.reconcile(fill);
+        self.record(&result);
+        result
+    }
+
+    /// Decodes `message` and applies the fill it holds, see `apply`.
+    pub fn apply_message(&self, message: &str) -> Result<OrderStatus, ReportAnomaly> {
+        match (self.decoder)(message) {
+            Ok(fill) => self.apply(&fill),
+            Err(error) => {
+                self.metrics.reports.fetch_add(1, Ordering::Relaxed);
+                let result = Err(ReportAnomaly::Malformed {
+                    error: error.to_string(),
+                });
+                self.record(&result);
+                result
+            }
+        }
+    }
+
+    /// Applies every report on the fills topic of `messaging` from a background worker
+    /// until the returned handle is dropped. Anomalies are published, as a
+    /// `QuarantinedReport`, to the quarantine topic.
+    pub fn subscribe(
+        &self,
+        messaging: Arc<MessagingService>,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        let topic = messaging.topics().fills.clone();
+        self.subscribe_to(messaging, &topic)
+    }
+
+    /// Same as `subscribe`, on `topic` instead of the fills topic.
+    pub fn subscribe_to(
+        &self,
+        messaging: Arc<MessagingService>,
+        topic: &str,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        let consumer = self.clone();
+        let service = messaging.clone();
+        messaging.subscribe(
+            topic,
+            Box::new(move |message| {
+                if let Err(anomaly) = consumer.apply_message(&message) {
+                    consumer.quarantine(&service, anomaly, message);
+                }
+            }),
+        )
+    }
+
+    pub fn metrics(&self) -> ReportMetricsSnapshot {
+        ReportMetricsSnapshot {
+            reports: self.metrics.reports.load(Ordering::Relaxed),
+            applied: self.metrics.applied.load(Ordering::Relaxed),
+            quarantined: self.metrics.quarantined.load(Ordering::Relaxed),
+            anomalies: self.metrics.anomalies.lock().unwrap().clone(),
+        }
+    }
+
+    fn reconcile(&self, fill: &Fill) -> Result<OrderStatus, ReportAnomaly> {
+        // Held until the fill is applied, so a report delivered twice at once applies once
+        let mut applied = self.applied.lock().unwrap();
+        if applied.contains(&fill.exec_id) {
+            return Err(ReportAnomaly::DuplicateExecution {
+                order_id: fill.order_id.clone(),
+                exec_id: fill.exec_id.clone(),
+            });
+        }
+        let handle = self.scheduler.parent_of(&fill.order_id).ok_or_else(|| {
+            ReportAnomaly::UnknownOrder {
+                order_id: fill.order_id.clone(),
+                exec_id: fill.exec_id.clone(),
+            }
+        })?;
+        let child = handle
+            .on_fill(fill)
+            .map_err(|error| ReportAnomaly::from_state_error(fill, error))?;
+        applied.insert(fill.exec_id.clone());
+        if let Some(positions) = &self.positions {
+            let order = &child.child.order_common;
+            // The state already refused a fill without quantity
+            if let Err(error) = positions.apply_execution(&order.symbol, &order.side, fill) {
+                log::warn!(
+                    "Fill {} not applied to the positions: {}",
+                    fill.exec_id,
+                    error
+                );
+            }
+            if matches!(child.status, OrderStatus::Filled | OrderStatus::Cancelled) {
+                positions.untrack_order(&fill.order_id);
+            }
+        }
+        Ok(child.status)
+    }
+
+    fn record(&self, result: &Result<OrderStatus, ReportAnomaly>) {
+        match result {
+            Ok(_) => {
+                self.metrics.applied.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(anomaly) => {
+                log::warn!("Execution report anomaly: {}", anomaly);
+                *self
+                    .metrics
+                    .anomalies
+                    .lock()
+                    .unwrap()
+                    .entry(anomaly.kind())
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn quarantine(&self, messaging: &MessagingService, anomaly: ReportAnomaly, report: String) {
+        let topic = &messaging.topics().quarantine;
+        let quarantined = QuarantinedReport { anomaly, report };
+        match messaging.produce_json(topic, &quarantined) {
+            Ok(()) => {
+                self.metrics.quarantined.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(error) => log::warn!(
+                "Could not quarantine the report {} on {}: {}",
+                quarantined.report,
+                topic,
+                error
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for ExecutionReportConsumer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutionReportConsumer")
+            .field("positions", &self.positions.is_some())
+            .field("applied", &self.applied.lock().unwrap().len())
+            .finish()
+    }
+}
@@ -0,0 +1,218 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::audit::audit_log::{AuditEvent, AuditSink};
+use crate::clients::common_client::{ClientError, Encoding, MessagingService};
+use crate::clients::subscription::SubscriptionHandle;
+use crate::engine::execution_engine::SharedSplitStrategy;
+use crate::models::market_data::MarketDataUpdate;
+use crate::strategies::market_microstructure_based::adverse_selection::{Strategy, StrategySignal};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// A signal a strategy returned for a market data update.
+#[derive(Debug, Clone)]
+pub struct SignalEvent {
+    pub symbol: String,
+    /// Name of the strategy that returned the signal
+    pub strategy: String,
+    pub signal: StrategySignal,
+}
+
+/// Receives the signals of the dispatched strategies.
+pub type SignalSink = dyn Fn(SignalEvent) + Send + Sync;
+
+type SharedStrategy = Box<dyn Strategy + Send>;
+
+/// Feeds market data updates to the strategies registered for their symbol and passes the
+/// signals they return on to a sink.
+///
+/// Updates are handled one at a time, so each strategy sees the updates of its symbol in the
+/// order they arrived, and the sink receives the signals in that order too. The sink is
+/// called while the update is handled and must not call back into the dispatcher.
+///
+/// With an `AuditSink`, each signal is also emitted as a `SignalGenerated` event, before it
+/// reaches the sink.
+///
+/// The split strategies of an `ExecutionEngine` can be fed too, see
+/// `ExecutionEngine::feed_market_data`; they return no signals.
+///
+/// Clones share the same strategies and sink.
+#[derive(Clone)]
+pub struct MarketDataDispatcher {
+    strategies: Arc<Mutex<HashMap<String, Vec<SharedStrategy>>>>,
+    split_strategies: Arc<Mutex<HashMap<String, Vec<SharedSplitStrategy>>>>,
+    sink: Arc<SignalSink>,
+    audit: AuditSink,
+}
+
+impl MarketDataDispatcher {
+    /// A dispatcher calling `sink` with every signal.
+    pub fn new(sink: impl Fn(SignalEvent) + Send + Sync + 'static) -> Self {
+        MarketDataDispatcher {
+            strategies: Arc::new(Mutex::new(HashMap::new())),
+            split_strategies: Arc::new(Mutex::new(HashMap::new())),
+            sink: Arc::new(sink),
+            audit: AuditSink::default(),
+        }
+    }
+
+    /// A dispatcher sending every signal to the returned channel. Signals sent after the
+    /// receiver is dropped are discarded.
+    pub fn with_channel() -> (Self, mpsc::UnboundedReceiver<SignalEvent>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let dispatcher = Self::new(move |event| {
+            let _ = sender.send(event);
+        });
+        (dispatcher, receiver)
+    }
+
+    /// Emits every signal to `audit` as well.
+    pub fn with_audit(mut self, audit: AuditSink) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Feeds the updates of `symbol` to `strategy`, after the strategies registered for it
+    /// before.
+    pub fn register(&self, symbol: &str, strategy: impl Strategy + Send + 'static) {
+        self.strategies
+            .lock()
+            .unwrap()
+            .entry(symbol.to_string())
+            .or_default()
+            .push(Box::new(strategy));
+    }
+
+    /// Feeds the updates of `symbol` to the split strategy `strategy`, after the strategies
+    /// registered for it.
+    pub fn feed(&self, symbol: &str, strategy: SharedSplitStrategy) {
+        self.split_strategies
+            .lock()
+            .unwrap()
+            .entry(symbol.to_string())
+            .or_default()
+            .push(strategy);
+    }
+
+    /// The symbols that have strategies registered or fed.
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.strategies.lock().unwrap().keys().cloned().collect();
+        symbols.extend(self.split_strategies.lock().unwrap().keys().cloned());
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Feeds `update` to the strategies of its symbol and returns how many signals they
+    /// returned; an update for a symbol without strategies is ignored.
+    pub fn dispatch(&self, update: &MarketDataUpdate) -> usize {
+        let mut strategies = self.strategies.lock().unwrap();
+        let mut signals = 0;
+        for strategy in strategies.get_mut(&update.symbol).into_iter().flatten() {
+            if let Some(signal) = strategy.on_market_data(&update.data) {
+                if self.audit.is_enabled() {
+                    self.audit.emit(
+                        None,
+                        AuditEvent::signal(&update.symbol, strategy.name(), &signal),
+                    );
+                }
+                (self.sink)(SignalEvent {
+                    symbol: update.symbol.clone(),
+                    strategy: strategy.name().to_string(),
+                    signal,
+                });
+                signals += 1;
+            }
+        }
+        if let Some(split_strategies) = self.split_strategies.lock().unwrap().get(&update.symbol) {
+            for strategy in split_strategies {
+                strategy.lock().unwrap().on_market_data(&update.data);
+            }
+        }
+        signals
+    }
+
+    /// Decodes `message` as a `MarketDataUpdate` and dispatches it, see `dispatch`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Deserialization` when the message does not follow the schema of
+    /// `MarketDataUpdate`.
+    pub fn dispatch_message(&self, message: &str) -> Result<usize, ClientError> {
+        let update: MarketDataUpdate = Encoding::Json.decode(message.as_bytes())?;
+        Ok(self.dispatch(&update))
+    }
+
+    /// Dispatches every message on the market data topic of `messaging` from a background
+    /// worker until the returned handle is dropped.
+    ///
+    /// A malformed message goes to the dead-letter topic when `messaging` has a dead-letter
+    /// policy, and is logged and skipped otherwise.
+    pub fn subscribe(
+        &self,
+        messaging: Arc<MessagingService>,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        let topic = messaging.topics().market_data.clone();
+        self.subscribe_to(messaging, &topic)
+    }
+
+    /// Same as `subscribe`, on `topic` instead of the market data topic.
+    pub fn subscribe_to(
+        &self,
+        messaging: Arc<MessagingService>,
+        topic: &str,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        let dispatcher = self.clone();
+        let service = messaging.clone();
+        let source_topic = topic.to_string();
+        messaging.subscribe(
+            topic,
+            Box::new(move |message| {
+                if let Err(error) = dispatcher.dispatch_message(&message) {
+                    if !service.dead_letter(&source_topic, message.as_bytes(), &error) {
+                        log::warn!(
+                            "Skipping malformed market data on {}: {}",
+                            source_topic,
+                            error
+                        );
+                    }
+                }
+            }),
+        )
+    }
+}
+
+impl std::fmt::Debug for MarketDataDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarketDataDispatcher")
+            .field("symbols", &self.symbols())
+            .finish()
+    }
+}
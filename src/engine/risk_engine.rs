@@ -0,0 +1,488 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::config::{RiskAction, RiskConfig, RiskLimits};
+use crate::models::decimal::Decimal;
+use crate::models::orders::Side;
+use crate::portfolio::{Position, PositionTracker};
+use crate::{ChildOrder, InstrumentRegistry, ParentOrder};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The limit a risk rejection breached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskRule {
+    MaxChildQuantity,
+    MaxParentNotional,
+    MaxOpenChildren,
+    MaxGrossExposure,
+    /// No price was known to check the parent against its notional limit
+    UnpricedParent,
+}
+
+impl fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RiskRule::MaxChildQuantity => "max_child_quantity",
+            RiskRule::MaxParentNotional => "max_parent_notional",
+            RiskRule::MaxOpenChildren => "max_open_children",
+            RiskRule::MaxGrossExposure => "max_gross_exposure",
+            RiskRule::UnpricedParent => "unpriced_parent",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A child, or a whole parent, blocked by a risk limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskRejection {
+    pub parent_id: String,
+    /// The offending child; `None` when the parent itself breached the limit
+    pub child_id: Option<String>,
+    pub symbol: String,
+    pub rule: RiskRule,
+    /// Whether the limit is the one of the symbol rather than the global one
+    pub symbol_limit: bool,
+    pub limit: Decimal,
+    /// The figure that went over the limit
+    pub value: Decimal,
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.child_id {
+            Some(child_id) => write!(f, "child {} of parent {}", child_id, self.parent_id)?,
+            None => write!(f, "parent {}", self.parent_id)?,
+        }
+        let scope = if self.symbol_limit {
+            format!("{} ", self.symbol)
+        } else {
+            String::new()
+        };
+        if self.rule == RiskRule::UnpricedParent {
+            return write!(
+                f,
+                " has no price to check against {}max_parent_notional {}",
+                scope, self.limit
+            );
+        }
+        write!(
+            f,
+            " breaches {}{}: {} over {}",
+            scope, self.rule, self.value, self.limit
+        )
+    }
+}
+
+/// The children of a parent that passed the risk checks and the rejections of the others.
+#[derive(Debug, Clone)]
+pub struct RiskDecision {
+    pub accepted: Vec<ChildOrder>,
+    pub rejections: Vec<RiskRejection>,
+}
+
+impl RiskDecision {
+    /// Whether any child was blocked.
+    pub fn is_rejected(&self) -> bool {
+        !self.rejections.is_empty()
+    }
+}
+
+/// The counters of a `RiskEngine` at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskMetricsSnapshot {
+    pub parents_checked: u64,
+    pub children_checked: u64,
+    pub children_rejected: u64,
+    /// Parents none of whose children were accepted because of a breach
+    pub parents_rejected: u64,
+    /// Rejections per rule breached
+    pub rejections: BTreeMap<RiskRule, u64>,
+}
+
+#[derive(Debug, Default)]
+struct RiskMetrics {
+    parents_checked: AtomicU64,
+    children_checked: AtomicU64,
+    children_rejected: AtomicU64,
+    parents_rejected: AtomicU64,
+    rejections: Mutex<BTreeMap<RiskRule, u64>>,
+}
+
+/// Called with every rejection the risk checks make.
+pub type RejectionListener = dyn Fn(&RiskRejection) + Send + Sync;
+
+/// Pre-trade checks of the children of a parent against the limits of a `RiskConfig`.
+///
+/// The `ExecutionEngine` runs them between the split and the dispatch. The gross exposure
+/// is only checked with a `PositionTracker`, and each child is netted against the signed
+/// position in its symbol: a child that reduces the position lowers the exposure. Exposures
+/// are scaled by the contract multiplier of the instrument, one without `instruments`.
+/// Orders are valued at their own price, else the parent's, else the mark or entry price of
+/// the position, and a child without any leaves the exposure as it is. A parent without a price is valued at its notional, else that
+/// price of the position, else the sum of its children if they all have a price; under a
+/// notional limit, a parent valued at none of them is rejected as `UnpricedParent`.
+pub struct RiskEngine {
+    config: RiskConfig,
+    positions: Option<PositionTracker>,
+    instruments: Option<InstrumentRegistry>,
+    listener: Option<Arc<RejectionListener>>,
+    metrics: RiskMetrics,
+}
+
+impl RiskEngine {
+    pub fn new(config: RiskConfig) -> Self {
+        RiskEngine {
+            config,
+            positions: None,
+            instruments: None,
+            listener: None,
+            metrics: RiskMetrics::default(),
+        }
+    }
+
+    /// Values the held positions from `positions` for the gross exposure limits.
+    pub fn with_positions(mut self, positions: PositionTracker) -> Self {
+        self.positions = Some(positions);
+        self
+    }
+
+    /// Scales the exposures by the contract multipliers of `instruments`.
+    pub fn with_instruments(mut self, instruments: InstrumentRegistry) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Calls `listener` with every rejection, e.g. to publish it.
+    pub fn on_rejection(
+        mut self,
+        listener: impl Fn(&RiskRejection) + Send + Sync + 'static,
+    ) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    pub fn config(&self) -> &RiskConfig {
+        &self.config
+    }
+
+    /// The tracker valuing the held positions, when given one.
+    pub fn positions(&self) -> Option<&PositionTracker> {
+        self.positions.as_ref()
+    }
+
+    /// Checks `children` of `parent`, on top of the `open_children` already working by
+    /// symbol, and returns those that may be dispatched.
+    ///
+    /// A parent over its notional limit has all its children blocked. Otherwise each child is
+    /// checked in turn, counting the children accepted before it, and the first limit it
+    /// breaches blocks it; with `RiskAction::RejectParent` one breach blocks them all.
+    pub fn check(
+        &self,
+        parent: &ParentOrder,
+        children: Vec<ChildOrder>,
+        open_children: &HashMap<String, usize>,
+    ) -> RiskDecision {
+        let symbol = &parent.order_common.symbol;
+        let limits = self.limits(symbol);
+        let mut rejections = Vec::new();
+        let parent_price = parent
+            .order_common
+            .price
+            .or_else(|| self.position_price(symbol));
+        let parent_quantity = parent.order_common.quantity.value();
+        let parent_notional = parent
+            .order_common
+            .price
+            .map(|price| parent_quantity * price)
+            .or(parent.order_common.notional)
+            .or_else(|| parent_price.map(|price| parent_quantity * price))
+            .or_else(|| children_notional(&children));
+        for (limit, symbol_limit) in limits.iter().copied() {
+            let Some(max) = limit.max_parent_notional else {
+                continue;
+            };
+            let (rule, value) = match parent_notional {
+                Some(notional) if notional > max => (RiskRule::MaxParentNotional, notional),
+                Some(_) => continue,
+                None => (RiskRule::UnpricedParent, Decimal::ZERO),
+            };
+            rejections.push(self.rejection(parent, None, rule, symbol_limit, max, value));
+            break;
+        }
+
+        let mut accepted = Vec::new();
+        if rejections.is_empty() {
+            // What is working already, over every symbol and in the parent's symbol
+            let mut global = Usage {
+                open: open_children.values().sum(),
+                exposure: self.positions.as_ref().map(|positions| {
+                    positions
+                        .snapshot()
+                        .positions
+                        .iter()
+                        .map(|position| self.exposure(position))
+                        .sum()
+                }),
+            };
+            let mut own = Usage {
+                open: open_children.get(symbol).copied().unwrap_or(0),
+                exposure: self.positions.as_ref().map(|positions| {
+                    positions
+                        .position(symbol)
+                        .map(|position| self.exposure(&position))
+                        .unwrap_or_default()
+                }),
+            };
+            // Signed quantity held in the symbol, with the children accepted so far
+            let mut net = self
+                .positions
+                .as_ref()
+                .map(|positions| positions.quantity(symbol))
+                .unwrap_or_default();
+            let multiplier = self.multiplier(symbol);
+            for child in children.iter() {
+                let quantity = child.order_common.quantity.value();
+                let signed = match child.order_common.side {
+                    Side::Buy => quantity,
+                    Side::Sell => -quantity,
+                };
+                let change = child
+                    .order_common
+                    .price
+                    .or(parent_price)
+                    .map(|price| ((net + signed).abs() - net.abs()) * price * multiplier);
+                let breach = limits.iter().find_map(|(limit, symbol_limit)| {
+                    let usage = if *symbol_limit { &own } else { &global };
+                    usage
+                        .with(quantity, change)
+                        .breach(limit)
+                        .map(|breach| (breach, *symbol_limit))
+                });
+                match breach {
+                    Some(((rule, max, value), symbol_limit)) => {
+                        rejections.push(self.rejection(
+                            parent,
+                            Some(child),
+                            rule,
+                            symbol_limit,
+                            max,
+                            value,
+                        ));
+                    }
+                    None => {
+                        global = global.with(quantity, change).into();
+                        own = own.with(quantity, change).into();
+                        net += signed;
+                        accepted.push(child.clone());
+                    }
+                }
+            }
+            if !rejections.is_empty() && self.config.on_breach == RiskAction::RejectParent {
+                accepted.clear();
+            }
+        }
+        self.record(&children, &accepted, &rejections);
+        RiskDecision {
+            accepted,
+            rejections,
+        }
+    }
+
+    pub fn metrics(&self) -> RiskMetricsSnapshot {
+        RiskMetricsSnapshot {
+            parents_checked: self.metrics.parents_checked.load(Ordering::Relaxed),
+            children_checked: self.metrics.children_checked.load(Ordering::Relaxed),
+            children_rejected: self.metrics.children_rejected.load(Ordering::Relaxed),
+            parents_rejected: self.metrics.parents_rejected.load(Ordering::Relaxed),
+            rejections: self.metrics.rejections.lock().unwrap().clone(),
+        }
+    }
+
+    /// The global limits and those of `symbol`, with whether each is the symbol's.
+    fn limits(&self, symbol: &str) -> Vec<(&RiskLimits, bool)> {
+        let mut limits = vec![(&self.config.limits, false)];
+        if let Some(symbol_limits) = self.config.symbols.get(symbol) {
+            limits.push((symbol_limits, true));
+        }
+        limits
+    }
+
+    /// Contract multiplier of `symbol`; one when the instrument is not known.
+    fn multiplier(&self, symbol: &str) -> Decimal {
+        self.instruments
+            .as_ref()
+            .and_then(|instruments| instruments.get(symbol))
+            .map_or(Decimal::ONE, |spec| spec.contract_multiplier)
+    }
+
+    /// Exposure of `position` scaled by the contract multiplier of its symbol.
+    fn exposure(&self, position: &Position) -> Decimal {
+        position.exposure() * self.multiplier(&position.symbol)
+    }
+
+    /// Mark price, or else entry price, of the position held in `symbol`.
+    fn position_price(&self, symbol: &str) -> Option<Decimal> {
+        let position = self.positions.as_ref()?.position(symbol)?;
+        position
+            .mark_price
+            .or((!position.is_flat()).then_some(position.avg_entry_price))
+    }
+
+    fn rejection(
+        &self,
+        parent: &ParentOrder,
+        child: Option<&ChildOrder>,
+        rule: RiskRule,
+        symbol_limit: bool,
+        limit: Decimal,
+        value: Decimal,
+    ) -> RiskRejection {
+        let rejection = RiskRejection {
+            parent_id: parent.order_common.id.clone(),
+            child_id: child.map(|child| child.order_common.id.clone()),
+            symbol: parent.order_common.symbol.clone(),
+            rule,
+            symbol_limit,
+            limit,
+            value,
+        };
+        log::warn!("Risk check rejected {}", rejection);
+        if let Some(listener) = &self.listener {
+            listener(&rejection);
+        }
+        rejection
+    }
+
+    fn record(
+        &self,
+        children: &[ChildOrder],
+        accepted: &[ChildOrder],
+        rejections: &[RiskRejection],
+    ) {
+        let metrics = &self.metrics;
+        metrics.parents_checked.fetch_add(1, Ordering::Relaxed);
+        metrics
+            .children_checked
+            .fetch_add(children.len() as u64, Ordering::Relaxed);
+        metrics
+            .children_rejected
+            .fetch_add((children.len() - accepted.len()) as u64, Ordering::Relaxed);
+        if accepted.is_empty() && !rejections.is_empty() {
+            metrics.parents_rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut counts = metrics.rejections.lock().unwrap();
+        for rejection in rejections {
+            *counts.entry(rejection.rule).or_insert(0) += 1;
+        }
+    }
+}
+
+impl Default for RiskEngine {
+    fn default() -> Self {
+        Self::new(RiskConfig::default())
+    }
+}
+
+impl fmt::Debug for RiskEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RiskEngine")
+            .field("config", &self.config)
+            .field("positions", &self.positions.is_some())
+            .finish()
+    }
+}
+
+/// Quantity times price summed over `children`, when every one of them has a price.
+fn children_notional(children: &[ChildOrder]) -> Option<Decimal> {
+    children
+        .iter()
+        .map(|child| {
+            let order = &child.order_common;
+            order.price.map(|price| order.quantity.value() * price)
+        })
+        .sum()
+}
+
+/// Children working and gross exposure, for the global limits or those of a symbol.
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    open: usize,
+    /// `None` when the exposure is not known
+    exposure: Option<Decimal>,
+}
+
+impl Usage {
+    /// The figures once a child of `quantity` is dispatched too, moving the exposure by
+    /// `change` when it can be valued; negative for a child that reduces the position.
+    fn with(&self, quantity: Decimal, change: Option<Decimal>) -> Figures {
+        Figures {
+            quantity,
+            open: self.open + 1,
+            exposure: self.exposure.map(|held| held + change.unwrap_or_default()),
+        }
+    }
+}
+
+impl From<Figures> for Usage {
+    fn from(figures: Figures) -> Self {
+        Usage {
+            open: figures.open,
+            exposure: figures.exposure,
+        }
+    }
+}
+
+/// What a child adds up to against one set of limits.
+struct Figures {
+    quantity: Decimal,
+    /// Open children once the child is dispatched
+    open: usize,
+    /// Gross exposure once the child is dispatched, when it can be valued
+    exposure: Option<Decimal>,
+}
+
+impl Figures {
+    /// The first limit of `limits` the figures go over, its value and the figure.
+    fn breach(&self, limits: &RiskLimits) -> Option<(RiskRule, Decimal, Decimal)> {
+        if let Some(max) = limits.max_child_quantity.filter(|max| self.quantity > *max) {
+            return Some((RiskRule::MaxChildQuantity, max, self.quantity));
+        }
+        if let Some(max) = limits.max_open_children.filter(|max| self.open > *max) {
+            return Some((RiskRule::MaxOpenChildren, max.into(), self.open.into()));
+        }
+        match (limits.max_gross_exposure, self.exposure) {
+            (Some(max), Some(exposure)) if exposure > max => {
+                Some((RiskRule::MaxGrossExposure, max, exposure))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,306 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::clients::common_client::{ClientError, Encoding, MessagingService};
+use crate::clients::subscription::SubscriptionHandle;
+use crate::config::RoutingConfig;
+use crate::engine::throttle::Throttle;
+use crate::models::orders::Side;
+use crate::{ChildOrder, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// The top of the book of a symbol on one venue, as the quote feed carries it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VenueQuote {
+    pub venue: String,
+    pub symbol: String,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub timestamp: Timestamp,
+}
+
+/// Reasons the router cannot place a child.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum RoutingError {
+    #[error("no healthy venue for child {0}")]
+    NoVenue(String),
+}
+
+/// The counters of a `Router` at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouterMetricsSnapshot {
+    /// Children routed to each venue
+    pub routed: BTreeMap<String, u64>,
+    /// Children sent to a failover venue
+    pub failovers: u64,
+    /// Children sent to the venue with the best quote
+    pub quoted: u64,
+    /// Children no venue could take
+    pub unroutable: u64,
+}
+
+#[derive(Debug)]
+struct Venue {
+    name: String,
+    weight: f64,
+    /// Credit of the smooth weighted round robin; the venue with the most goes next
+    current: f64,
+}
+
+#[derive(Debug, Default)]
+struct RouterState {
+    /// The venues of positive weight, by name
+    venues: Vec<Venue>,
+    unhealthy: HashSet<String>,
+    /// The last quote of each venue and symbol
+    quotes: HashMap<(String, String), VenueQuote>,
+    metrics: RouterMetricsSnapshot,
+}
+
+/// Picks the exchange of each child from the venues of a `RoutingConfig`, and writes it
+/// into the child's `exchange`.
+///
+/// Children are spread over the healthy venues by smooth weighted round robin, so any run
+/// of children matches the weights as closely as whole children allow. A venue whose rate
+/// limit in the throttle is used up is passed over while another can take the child at
+/// once. With `use_quotes`, a child goes instead to the venue with the best fresh quote for
+/// its side: the lowest ask for a buy, the highest bid for a sell. When no weighted venue is
+/// healthy, the child goes to the first healthy `failover` venue.
+///
+/// Every venue starts healthy. Clones share the same venues, health and quotes.
+#[derive(Debug, Clone)]
+pub struct Router {
+    config: Arc<RoutingConfig>,
+    state: Arc<Mutex<RouterState>>,
+}
+
+impl Router {
+    pub fn new(config: RoutingConfig) -> Self {
+        let mut venues: Vec<Venue> = config
+            .venues
+            .iter()
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(name, weight)| Venue {
+                name: name.clone(),
+                weight: *weight,
+                current: 0.0,
+            })
+            .collect();
+        venues.sort_by(|a, b| a.name.cmp(&b.name));
+        Router {
+            config: Arc::new(config),
+            state: Arc::new(Mutex::new(RouterState {
+                venues,
+                ..RouterState::default()
+            })),
+        }
+    }
+
+    pub fn config(&self) -> &RoutingConfig {
+        &self.config
+    }
+
+    /// Marks `venue` healthy or not; an unhealthy venue takes no children until it is
+    /// marked healthy again.
+    pub fn set_healthy(&self, venue: &str, healthy: bool) {
+        let mut state = self.state.lock().unwrap();
+        if healthy {
+            state.unhealthy.remove(venue);
+        } else {
+            log::warn!("Venue {} marked unhealthy", venue);
+            state.unhealthy.insert(venue.to_string());
+        }
+    }
+
+    pub fn is_healthy(&self, venue: &str) -> bool {
+        !self.state.lock().unwrap().unhealthy.contains(venue)
+    }
+
+    /// Keeps `quote` as the latest of its venue and symbol, unless a later one is known.
+    pub fn on_quote(&self, quote: VenueQuote) {
+        let mut state = self.state.lock().unwrap();
+        let key = (quote.venue.clone(), quote.symbol.clone());
+        if state
+            .quotes
+            .get(&key)
+            .is_none_or(|known| known.timestamp <= quote.timestamp)
+        {
+            state.quotes.insert(key, quote);
+        }
+    }
+
+    /// Decodes `message` as a JSON `VenueQuote` and keeps it, see `on_quote`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Deserialization` when the message is not a `VenueQuote`.
+    pub fn on_quote_message(&self, message: &str) -> Result<(), ClientError> {
+        self.on_quote(Encoding::Json.decode(message.as_bytes())?);
+        Ok(())
+    }
+
+    /// Keeps the quotes published on `topic` of `messaging` from a background worker until
+    /// the returned handle is dropped. Malformed messages are logged and skipped.
+    pub fn subscribe_to(
+        &self,
+        messaging: &MessagingService,
+        topic: &str,
+    ) -> Result<SubscriptionHandle, ClientError> {
+        let router = self.clone();
+        let source_topic = topic.to_string();
+        messaging.subscribe(
+            topic,
+            Box::new(move |message| {
+                if let Err(error) = router.on_quote_message(&message) {
+                    log::warn!("Skipping malformed quote on {}: {}", source_topic, error);
+                }
+            }),
+        )
+    }
+
+    /// Picks the venue of `child` at `at`, with the rate limits of `throttle`, writes it
+    /// into the child's `exchange` and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::NoVenue` when neither a weighted nor a failover venue is
+    /// healthy; the child is left as it was.
+    pub fn route(
+        &self,
+        child: &mut ChildOrder,
+        at: Timestamp,
+        throttle: &Throttle,
+    ) -> Result<String, RoutingError> {
+        let mut state = self.state.lock().unwrap();
+        let healthy: Vec<usize> = (0..state.venues.len())
+            .filter(|&index| !state.unhealthy.contains(&state.venues[index].name))
+            .collect();
+        let venue = if healthy.is_empty() {
+            let failover = self
+                .config
+                .failover
+                .iter()
+                .find(|venue| !state.unhealthy.contains(*venue))
+                .cloned();
+            let Some(venue) = failover else {
+                state.metrics.unroutable += 1;
+                return Err(RoutingError::NoVenue(child.order_common.id.clone()));
+            };
+            state.metrics.failovers += 1;
+            venue
+        } else {
+            let open: Vec<usize> = healthy
+                .iter()
+                .copied()
+                .filter(|&index| throttle.ready_at(Some(&state.venues[index].name), at) <= at)
+                .collect();
+            let candidates = if open.is_empty() { healthy } else { open };
+            let quoted = if self.config.use_quotes {
+                self.best_quote(&state, &candidates, child, at)
+            } else {
+                None
+            };
+            let index = match quoted {
+                Some(index) => {
+                    state.metrics.quoted += 1;
+                    index
+                }
+                None => next_weighted(&mut state.venues, &candidates),
+            };
+            state.venues[index].name.clone()
+        };
+        *state.metrics.routed.entry(venue.clone()).or_default() += 1;
+        child.order_common.exchange = Some(venue.clone());
+        Ok(venue)
+    }
+
+    pub fn metrics(&self) -> RouterMetricsSnapshot {
+        self.state.lock().unwrap().metrics.clone()
+    }
+
+    /// The candidate with the best quote no older than `max_quote_age_ms` for the side of
+    /// `child`; the first of them on a tie.
+    fn best_quote(
+        &self,
+        state: &RouterState,
+        candidates: &[usize],
+        child: &ChildOrder,
+        at: Timestamp,
+    ) -> Option<usize> {
+        let order = &child.order_common;
+        let mut best: Option<(usize, f64)> = None;
+        for &index in candidates {
+            let key = (state.venues[index].name.clone(), order.symbol.clone());
+            let Some(quote) = state.quotes.get(&key) else {
+                continue;
+            };
+            if at.as_millis().saturating_sub(quote.timestamp.as_millis())
+                > self.config.max_quote_age_ms
+            {
+                continue;
+            }
+            let price = match order.side {
+                Side::Buy => quote.ask,
+                Side::Sell => quote.bid,
+            };
+            let Some(price) = price.filter(|price| *price > 0.0) else {
+                continue;
+            };
+            let better = best.is_none_or(|(_, best)| match order.side {
+                Side::Buy => price < best,
+                Side::Sell => price > best,
+            });
+            if better {
+                best = Some((index, price));
+            }
+        }
+        best.map(|(index, _)| index)
+    }
+}
+
+/// The next of `candidates` by smooth weighted round robin: each gains its weight, and the
+/// one with the most credit goes and pays back the weights of all.
+fn next_weighted(venues: &mut [Venue], candidates: &[usize]) -> usize {
+    let mut total = 0.0;
+    let mut chosen = candidates[0];
+    for &index in candidates {
+        let venue = &mut venues[index];
+        venue.current += venue.weight;
+        total += venue.weight;
+    }
+    for &index in candidates {
+        if venues[index].current > venues[chosen].current {
+            chosen = index;
+        }
+    }
+    venues[chosen].current -= total;
+    chosen
+}
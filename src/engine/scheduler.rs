@@ -0,0 +1,903 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::audit::audit_log::{AuditEvent, AuditSink};
+use crate::clients::common_client::ClientError;
+use crate::engine::clock::Clock;
+use crate::engine::router::Router;
+use crate::engine::throttle::Throttle;
+use crate::models::orders::OrderStatus;
+use crate::models::parent_state::{ChildState, ParentOrderState, ParentStateError};
+use crate::persistence::order_store::{Persistence, PersistenceError};
+use crate::{ChildOrder, Fill, Timestamp};
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use thiserror::Error;
+use tokio::sync::{oneshot, watch};
+
+/// Sends a child order on, typically with `MessagingService::produce_child_order`.
+pub type DispatchFn = dyn Fn(&ChildOrder) -> Result<(), ClientError> + Send + Sync;
+
+/// Threads the children are sent from when `with_dispatch_workers` is not given.
+const DISPATCH_WORKERS: usize = 4;
+
+type Parents = Mutex<HashMap<String, ParentOrderHandle>>;
+
+/// Reasons the scheduler refuses a parent.
+#[derive(Debug, Error, PartialEq)]
+pub enum SchedulerError {
+    #[error("parent {0} is already working")]
+    DuplicateParent(String),
+    #[error("scheduling child orders requires a tokio runtime")]
+    NoRuntime,
+    /// The scheduler was halted; see `ChildOrderScheduler::halt`.
+    #[error("the scheduler is halted")]
+    Halted,
+    #[error(transparent)]
+    Persistence(#[from] PersistenceError),
+}
+
+/// Where the dispatch of a parent's children stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    /// Children are still waiting for their `insert_at`
+    Working,
+    /// Every child was dispatched
+    Completed,
+    /// The parent was cancelled, or expired, before all its children were dispatched
+    Cancelled,
+    /// Every child was handled, but some could not be dispatched
+    Failed,
+}
+
+/// The outcome of dispatching one child.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildDispatch {
+    pub child_id: String,
+    pub insert_at: Option<Timestamp>,
+    /// Time on the scheduler's clock when the child was dispatched
+    pub dispatched_at: Timestamp,
+    pub result: Result<(), ClientError>,
+}
+
+/// What cancelling a parent did to its children.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CancelReport {
+    pub parent_id: String,
+    /// Children stopped before they were dispatched, now `Cancelled`
+    pub cancelled: Vec<String>,
+    /// Children dispatched and still working, now `PendingCancel`
+    pub pending_cancel: Vec<String>,
+    /// Children already filled, which the cancel leaves be
+    pub filled: Vec<String>,
+    /// Children whose cancel request could not be sent, with the error
+    pub failed: Vec<(String, ClientError)>,
+}
+
+/// What `ChildOrderScheduler::recover` made of the orders it reloaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecoveryReport {
+    /// Parents scheduled again, by parent id
+    pub parents: Vec<String>,
+    /// Children not dispatched yet whose `insert_at` is still ahead, scheduled again
+    pub rescheduled: Vec<String>,
+    /// Children dispatched before the restart and still working, whose fills keep applying
+    pub working: Vec<String>,
+    /// Children not dispatched yet whose `insert_at` passed while the engine was down; they
+    /// are held for an operator to review rather than sent late
+    pub review: Vec<String>,
+}
+
+/// Dispatches the children of parent orders once the clock reaches their `insert_at`.
+///
+/// Each parent runs on a tokio task, which hands its children to the dispatch function in
+/// `insert_at` order. Children without an `insert_at`, or with one already past, go at once.
+/// The outcome of each dispatch is recorded in the parent's `ParentOrderState`, where a child
+/// that could not be dispatched is cancelled.
+///
+/// With a `Persistence`, each parent is saved with its children when it is scheduled, and
+/// each child again when it is dispatched, filled or cancelled, so `recover` can pick the
+/// orders up after a restart.
+///
+/// With a `Throttle`, a child that would break an order-rate limit is held back until the
+/// throttle has a slot for it, and goes out with its `insert_at` pushed back to that slot.
+///
+/// With a `Router`, each child goes to the exchange the router picks when its turn comes,
+/// before the throttle; a child no venue can take fails to dispatch.
+///
+/// With an `AuditSink`, each dispatch, fill and cancel is emitted as an `AuditEvent`
+/// stamped with the scheduler's clock and correlated with the parent id.
+///
+/// The dispatch function runs on a fixed set of threads outside the runtime, shared by every
+/// parent. A parent is forgotten once its dispatch is over and each of its children is
+/// filled or cancelled; a child whose cancel is still pending keeps it.
+///
+/// Clones share the same parents.
+#[derive(Clone)]
+pub struct ChildOrderScheduler {
+    clock: Arc<dyn Clock>,
+    dispatch: Arc<DispatchFn>,
+    throttle: Throttle,
+    router: Option<Router>,
+    persistence: Option<Arc<dyn Persistence>>,
+    audit: AuditSink,
+    dispatch_pool: Arc<DispatchPool>,
+    parents: Arc<Parents>,
+    /// Set and read under the `parents` lock, so no parent is scheduled past a halt
+    halted: Arc<AtomicBool>,
+}
+
+impl ChildOrderScheduler {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        dispatch: impl Fn(&ChildOrder) -> Result<(), ClientError> + Send + Sync + 'static,
+    ) -> Self {
+        ChildOrderScheduler {
+            clock,
+            dispatch: Arc::new(dispatch),
+            throttle: Throttle::default(),
+            router: None,
+            persistence: None,
+            audit: AuditSink::default(),
+            dispatch_pool: Arc::new(DispatchPool::new(DISPATCH_WORKERS)),
+            parents: Arc::new(Mutex::new(HashMap::new())),
+            halted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Holds the dispatch to the rate limits of `throttle`.
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Sends each child to the exchange `router` picks for it.
+    pub fn with_router(mut self, router: Router) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    /// Saves the parents and their children to `persistence` as they progress.
+    pub fn with_persistence(mut self, persistence: Arc<dyn Persistence>) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Schedules on `clock` instead; parents already scheduled keep the clock they had.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.audit = self.audit.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Sends the children from `workers` threads, at least one, instead of four.
+    pub fn with_dispatch_workers(mut self, workers: usize) -> Self {
+        self.dispatch_pool = Arc::new(DispatchPool::new(workers.max(1)));
+        self
+    }
+
+    /// Emits the progress of the parents to `audit`.
+    pub fn with_audit(mut self, audit: AuditSink) -> Self {
+        self.audit = audit.with_clock(self.clock.clone());
+        self
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// The sink the progress of the parents goes to, stamping with the scheduler's clock.
+    pub fn audit(&self) -> &AuditSink {
+        &self.audit
+    }
+
+    pub fn throttle(&self) -> &Throttle {
+        &self.throttle
+    }
+
+    pub fn router(&self) -> Option<&Router> {
+        self.router.as_ref()
+    }
+
+    pub fn persistence(&self) -> Option<&Arc<dyn Persistence>> {
+        self.persistence.as_ref()
+    }
+
+    /// Checks that a parent with id `parent_id` could be scheduled now.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchedulerError::NoRuntime` outside a tokio runtime, and `DuplicateParent`
+    /// while a parent with the same id is still working.
+    pub fn check(&self, parent_id: &str) -> Result<(), SchedulerError> {
+        tokio::runtime::Handle::try_current().map_err(|_| SchedulerError::NoRuntime)?;
+        if self
+            .parent(parent_id)
+            .is_some_and(|handle| !handle.is_complete())
+        {
+            return Err(SchedulerError::DuplicateParent(parent_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Schedules the outstanding children of `state` that have not been dispatched yet,
+    /// after saving the parent and its children with the persistence, if any.
+    ///
+    /// # Errors
+    ///
+    /// The errors of `check`, `SchedulerError::Halted` after `halt`, and
+    /// `SchedulerError::Persistence` when the parent could not be saved, in which case
+    /// nothing is scheduled.
+    pub fn schedule(&self, state: ParentOrderState) -> Result<ParentOrderHandle, SchedulerError> {
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|_| SchedulerError::NoRuntime)?;
+        let parent_id = state.parent.order_common.id.clone();
+        let mut parents = self.parents.lock().unwrap();
+        if self.halted.load(Ordering::SeqCst) {
+            return Err(SchedulerError::Halted);
+        }
+        if parents
+            .get(&parent_id)
+            .is_some_and(|handle| !handle.is_complete())
+        {
+            return Err(SchedulerError::DuplicateParent(parent_id));
+        }
+        if let Some(persistence) = &self.persistence {
+            persistence.save_parent(&state.parent)?;
+            persistence.save_children(&parent_id, &state.children)?;
+        }
+        let children: Vec<ChildOrder> = state.undispatched_children().cloned().collect();
+        Ok(self.start(&runtime, &mut parents, state, children))
+    }
+
+    /// Schedules again the parents the persistence holds with children still open, after
+    /// a restart. A child not dispatched yet goes out at its `insert_at` when that is still
+    /// ahead; when it has passed, or the child had none, the child is held for review and
+    /// stays undispatched in the parent's state until the parent is cancelled. Parents the
+    /// scheduler already knows are left as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchedulerError::NoRuntime` outside a tokio runtime, `SchedulerError::Halted`
+    /// after `halt`, and `SchedulerError::Persistence` when the orders could not be loaded;
+    /// nothing is scheduled then. Without a persistence there is nothing to recover.
+    pub fn recover(&self) -> Result<RecoveryReport, SchedulerError> {
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|_| SchedulerError::NoRuntime)?;
+        let mut report = RecoveryReport::default();
+        let Some(persistence) = &self.persistence else {
+            return Ok(report);
+        };
+        let open = persistence.load_open_orders()?;
+        let now = self.clock.now();
+        let mut parents = self.parents.lock().unwrap();
+        if self.halted.load(Ordering::SeqCst) {
+            return Err(SchedulerError::Halted);
+        }
+        for state in open {
+            let parent_id = state.parent.order_common.id.clone();
+            if parents.contains_key(&parent_id) {
+                continue;
+            }
+            let mut children = Vec::new();
+            for child in state.children.iter().filter(|child| child.status.is_open()) {
+                let child_id = child.child.order_common.id.clone();
+                if child.dispatched_at.is_some() {
+                    report.working.push(child_id);
+                } else if child
+                    .child
+                    .insert_at
+                    .is_some_and(|insert_at| insert_at > now)
+                {
+                    report.rescheduled.push(child_id);
+                    children.push(child.child.clone());
+                } else {
+                    log::warn!("Child {} missed its insert_at, held for review", child_id);
+                    report.review.push(child_id);
+                }
+            }
+            self.start(&runtime, &mut parents, state, children);
+            report.parents.push(parent_id);
+        }
+        Ok(report)
+    }
+
+    /// Registers the parent of `state` and spawns the dispatch of `children`.
+    fn start(
+        &self,
+        runtime: &tokio::runtime::Handle,
+        parents: &mut HashMap<String, ParentOrderHandle>,
+        state: ParentOrderState,
+        children: Vec<ChildOrder>,
+    ) -> ParentOrderHandle {
+        let handle = ParentOrderHandle::new(
+            state,
+            self.persistence.clone(),
+            self.audit.clone(),
+            Arc::downgrade(&self.parents),
+        );
+        parents.insert(handle.parent_id().to_string(), handle.clone());
+        runtime.spawn(dispatch_children(
+            handle.clone(),
+            children,
+            self.dispatch.clone(),
+            self.dispatch_pool.clone(),
+            self.clock.clone(),
+            self.throttle.clone(),
+            self.router.clone(),
+        ));
+        handle
+    }
+
+    /// Refuses every parent `schedule` is given from now on, until `resume`, and returns
+    /// the parents scheduled before, for them to be cancelled. A parent is either among them
+    /// or refused.
+    pub fn halt(&self) -> Vec<ParentOrderHandle> {
+        let parents = self.parents.lock().unwrap();
+        self.halted.store(true, Ordering::SeqCst);
+        parents.values().cloned().collect()
+    }
+
+    /// Lets `schedule` take parents again after `halt`.
+    pub fn resume(&self) {
+        let _parents = self.parents.lock().unwrap();
+        self.halted.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Stops the children of `parent_id` not dispatched yet and returns their ids, see
+    /// `ParentOrderHandle::cancel`; nothing for a parent the scheduler does not know.
+    pub fn cancel(&self, parent_id: &str) -> Vec<String> {
+        self.parent(parent_id)
+            .map(|handle| handle.cancel())
+            .unwrap_or_default()
+    }
+
+    /// Children of every scheduled parent that are neither filled nor cancelled, by symbol.
+    pub fn open_children(&self) -> HashMap<String, usize> {
+        let mut open = HashMap::new();
+        for handle in self.parents.lock().unwrap().values() {
+            let progress = handle.progress();
+            let count = progress.state.outstanding_children().count();
+            if count > 0 {
+                *open
+                    .entry(progress.state.parent.order_common.symbol.clone())
+                    .or_insert(0) += count;
+            }
+        }
+        open
+    }
+
+    /// The handles of every parent the scheduler remembers.
+    pub fn parents(&self) -> Vec<ParentOrderHandle> {
+        self.parents.lock().unwrap().values().cloned().collect()
+    }
+
+    /// The handle of the parent scheduled with `parent_id`, while the scheduler remembers it.
+    pub fn parent(&self, parent_id: &str) -> Option<ParentOrderHandle> {
+        self.parents.lock().unwrap().get(parent_id).cloned()
+    }
+
+    /// The handle of the scheduled parent with a child `child_id`.
+    pub fn parent_of(&self, child_id: &str) -> Option<ParentOrderHandle> {
+        self.parents
+            .lock()
+            .unwrap()
+            .values()
+            .find(|handle| handle.progress().state.child(child_id).is_some())
+            .cloned()
+    }
+}
+
+impl std::fmt::Debug for ChildOrderScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChildOrderScheduler")
+            .field("parents", &self.parents.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// Dispatches `children` in `insert_at` order, each once the clock reaches its `insert_at`
+/// and the throttle has a slot for it on the exchange the router picks, until the parent is
+/// cancelled.
+async fn dispatch_children(
+    handle: ParentOrderHandle,
+    mut children: Vec<ChildOrder>,
+    dispatch: Arc<DispatchFn>,
+    pool: Arc<DispatchPool>,
+    clock: Arc<dyn Clock>,
+    throttle: Throttle,
+    router: Option<Router>,
+) {
+    // Children without an insert_at sort first; the sort keeps the split's order among equals
+    children.sort_by_key(|child| child.insert_at);
+    for mut child in children {
+        if let Some(insert_at) = child.insert_at {
+            tokio::select! {
+                _ = clock.sleep_until(insert_at) => {}
+                _ = handle.cancelled() => break,
+            }
+        }
+        if let Some(router) = &router {
+            if let Err(error) = router.route(&mut child, clock.now(), &throttle) {
+                log::warn!("Could not route child: {}", error);
+                if !handle.start_dispatch(&child.order_common.id) {
+                    break;
+                }
+                let result = Err(ClientError::Connection(error.to_string()));
+                handle.finish_dispatch(&child, clock.now(), result);
+                continue;
+            }
+        }
+        if !throttle.is_unlimited() {
+            let now = clock.now();
+            let slot = throttle.acquire(child.order_common.exchange.as_deref(), now);
+            if slot > now {
+                // A cancel while waiting leaves the slot unused
+                tokio::select! {
+                    _ = clock.sleep_until(slot) => {}
+                    _ = handle.cancelled() => break,
+                }
+                child.insert_at = Some(slot);
+            }
+        }
+        // A GTD parent past its expiry sends nothing more, whatever its children's insert_at
+        if handle.expire(clock.now()) {
+            break;
+        }
+        if !handle.start_dispatch(&child.order_common.id) {
+            break;
+        }
+        let dispatch = dispatch.clone();
+        let order = child.clone();
+        // Clients send synchronously, and those running their own runtime (NATS, RabbitMQ)
+        // refuse to block from any thread of another one, blocking pool included, so send
+        // from the pool's threads outside the runtime
+        let (sender, receiver) = oneshot::channel();
+        pool.run(Box::new(move || {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| dispatch(&order)));
+            let _ = sender.send(result);
+        }));
+        let result = match receiver.await {
+            Ok(Ok(result)) => result,
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            // The dispatch pool could not report back
+            Err(_) => return,
+        };
+        handle.finish_dispatch(&child, clock.now(), result);
+    }
+    handle.finish();
+}
+
+/// A dispatch to run on the pool.
+type DispatchJob = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of threads outside any runtime, started on the first dispatch, which end
+/// once the pool is dropped.
+struct DispatchPool {
+    workers: usize,
+    jobs: OnceLock<mpsc::Sender<DispatchJob>>,
+}
+
+impl DispatchPool {
+    fn new(workers: usize) -> Self {
+        DispatchPool {
+            workers,
+            jobs: OnceLock::new(),
+        }
+    }
+
+    /// Runs `job` on the first free thread. A job no thread can take is dropped, which its
+    /// caller sees as its reply channel closing.
+    fn run(&self, job: DispatchJob) {
+        let jobs = self.jobs.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<DispatchJob>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            for worker in 0..self.workers {
+                let receiver = receiver.clone();
+                let spawned = std::thread::Builder::new()
+                    .name(format!("child-dispatch-{}", worker))
+                    .spawn(move || loop {
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    });
+                if let Err(error) = spawned {
+                    log::error!("Could not start a dispatch thread: {}", error);
+                }
+            }
+            sender
+        });
+        let _ = jobs.send(job);
+    }
+}
+
+/// Progress of a scheduled parent: the status of its children and how their dispatch went.
+///
+/// Clones share the same parent.
+#[derive(Clone)]
+pub struct ParentOrderHandle {
+    inner: Arc<Execution>,
+}
+
+struct Execution {
+    parent_id: String,
+    persistence: Option<Arc<dyn Persistence>>,
+    audit: AuditSink,
+    /// The scheduler's parents, which forget this one once it is over
+    parents: Weak<Parents>,
+    progress: Mutex<Progress>,
+    status: watch::Sender<ExecutionStatus>,
+    cancelled: watch::Sender<bool>,
+}
+
+struct Progress {
+    state: ParentOrderState,
+    dispatches: Vec<ChildDispatch>,
+    /// Children being dispatched, or already dispatched, which a cancel no longer stops
+    started: HashSet<String>,
+}
+
+impl ParentOrderHandle {
+    fn new(
+        state: ParentOrderState,
+        persistence: Option<Arc<dyn Persistence>>,
+        audit: AuditSink,
+        parents: Weak<Parents>,
+    ) -> Self {
+        ParentOrderHandle {
+            inner: Arc::new(Execution {
+                parent_id: state.parent.order_common.id.clone(),
+                persistence,
+                audit,
+                parents,
+                progress: Mutex::new(Progress {
+                    state,
+                    dispatches: Vec::new(),
+                    started: HashSet::new(),
+                }),
+                status: watch::Sender::new(ExecutionStatus::Working),
+                cancelled: watch::Sender::new(false),
+            }),
+        }
+    }
+
+    pub fn parent_id(&self) -> &str {
+        &self.inner.parent_id
+    }
+
+    pub fn status(&self) -> ExecutionStatus {
+        *self.inner.status.borrow()
+    }
+
+    /// Returns true once no child is left to dispatch.
+    pub fn is_complete(&self) -> bool {
+        self.status() != ExecutionStatus::Working
+    }
+
+    /// Waits until no child is left to dispatch and returns the final status.
+    pub async fn wait(&self) -> ExecutionStatus {
+        let mut status = self.inner.status.subscribe();
+        loop {
+            let current = *status.borrow_and_update();
+            if current != ExecutionStatus::Working {
+                return current;
+            }
+            // Only fails once the sender is gone, and this handle holds it
+            if status.changed().await.is_err() {
+                return current;
+            }
+        }
+    }
+
+    /// A snapshot of the parent and the status of each child, with the time each was
+    /// dispatched. Children that were cancelled, or could not be dispatched, are `Cancelled`.
+    pub fn state(&self) -> ParentOrderState {
+        self.progress().state.clone()
+    }
+
+    /// The children dispatched so far, or that failed to dispatch, in dispatch order.
+    pub fn dispatches(&self) -> Vec<ChildDispatch> {
+        self.progress().dispatches.clone()
+    }
+
+    /// Stops the children not dispatched yet and returns their ids; those already on their
+    /// way stay dispatched. Once a parent is cancelled, its handle reports `Cancelled`.
+    pub fn cancel(&self) -> Vec<String> {
+        let cancelled = self.stop(&mut self.progress());
+        self.audit_cancel(&cancelled, &[]);
+        self.release_if_over();
+        cancelled
+    }
+
+    /// Stops the children not dispatched yet, see `cancel`.
+    fn stop(&self, progress: &mut Progress) -> Vec<String> {
+        self.inner.cancelled.send_replace(true);
+        let Progress { state, started, .. } = progress;
+        let pending: Vec<String> = state
+            .outstanding_children()
+            .map(|child| child.order_common.id.clone())
+            .filter(|child_id| !started.contains(child_id))
+            .collect();
+        for child_id in &pending {
+            // The ids come from the state's own open children
+            let _ = state.on_cancel(child_id);
+            self.persist(state, child_id);
+        }
+        if *self.inner.status.borrow() == ExecutionStatus::Working {
+            self.inner.status.send_replace(ExecutionStatus::Cancelled);
+        }
+        pending
+    }
+
+    /// Applies an execution report to its child and returns the child after it, see
+    /// `ParentOrderState::on_fill`.
+    pub fn on_fill(&self, fill: &Fill) -> Result<ChildState, ParentStateError> {
+        let mut progress = self.progress();
+        progress.state.on_fill(fill)?;
+        self.persist(&progress.state, &fill.order_id);
+        let child = progress
+            .state
+            .child(&fill.order_id)
+            .cloned()
+            .expect("a fill was just applied to the child");
+        self.inner.audit.emit(
+            Some(&self.inner.parent_id),
+            AuditEvent::FillApplied {
+                child_id: fill.order_id.clone(),
+                exec_id: fill.exec_id.clone(),
+                price: fill.price,
+                quantity: fill.quantity,
+                status: child.status,
+            },
+        );
+        drop(progress);
+        self.release_if_over();
+        Ok(child)
+    }
+
+    /// Stops the children not dispatched yet, like `cancel`, and marks those dispatched and
+    /// still working `PendingCancel`, for their cancel to be requested from the venue.
+    pub fn cancel_all(&self) -> CancelReport {
+        let mut progress = self.progress();
+        let cancelled = self.stop(&mut progress);
+        let mut report = CancelReport {
+            parent_id: self.inner.parent_id.clone(),
+            cancelled,
+            ..CancelReport::default()
+        };
+        let state = &mut progress.state;
+        let children: Vec<(String, OrderStatus)> = state
+            .children
+            .iter()
+            .map(|child| (child.child.order_common.id.clone(), child.status))
+            .collect();
+        for (child_id, status) in children {
+            if status.is_open() {
+                // The child is open, so the request is accepted
+                let _ = state.on_cancel_requested(&child_id);
+                self.persist(state, &child_id);
+                report.pending_cancel.push(child_id);
+            } else if status == OrderStatus::Filled {
+                report.filled.push(child_id);
+            }
+        }
+        self.audit_cancel(&report.cancelled, &report.pending_cancel);
+        drop(progress);
+        self.release_if_over();
+        report
+    }
+
+    /// Stops the children not dispatched yet, like `cancel`, if the parent has expired at
+    /// `now`, and returns whether it had.
+    fn expire(&self, now: Timestamp) -> bool {
+        let mut progress = self.progress();
+        if !progress
+            .state
+            .parent
+            .order_common
+            .is_expired(now.as_millis())
+        {
+            return false;
+        }
+        let cancelled = self.stop(&mut progress);
+        log::info!(
+            "Parent {} expired, cancelling {} children",
+            self.inner.parent_id,
+            cancelled.len()
+        );
+        self.audit_cancel(&cancelled, &[]);
+        true
+    }
+
+    /// Emits a `Cancelled` event, unless the cancel changed nothing.
+    fn audit_cancel(&self, cancelled: &[String], pending_cancel: &[String]) {
+        if cancelled.is_empty() && pending_cancel.is_empty() {
+            return;
+        }
+        self.inner.audit.emit(
+            Some(&self.inner.parent_id),
+            AuditEvent::Cancelled {
+                cancelled: cancelled.to_vec(),
+                pending_cancel: pending_cancel.to_vec(),
+            },
+        );
+    }
+
+    /// Whether the dispatch is over and every child is filled or cancelled, so nothing can
+    /// change the parent any more.
+    pub fn is_over(&self) -> bool {
+        self.is_complete()
+            && self
+                .progress()
+                .state
+                .outstanding_children()
+                .next()
+                .is_none()
+    }
+
+    /// Has the scheduler forget the parent once it is over. Called without the progress
+    /// lock, which the scheduler takes under its own.
+    fn release_if_over(&self) {
+        if !self.is_over() {
+            return;
+        }
+        let Some(parents) = self.inner.parents.upgrade() else {
+            return;
+        };
+        let mut parents = parents.lock().unwrap();
+        // A parent scheduled again under the same id is another one
+        if parents
+            .get(self.parent_id())
+            .is_some_and(|handle| Arc::ptr_eq(&handle.inner, &self.inner))
+        {
+            parents.remove(self.parent_id());
+        }
+    }
+
+    fn progress(&self) -> std::sync::MutexGuard<'_, Progress> {
+        self.inner.progress.lock().unwrap()
+    }
+
+    /// Saves the child `child_id` of `state` with the persistence, if any. The change has
+    /// happened by then, so a failure is only logged.
+    fn persist(&self, state: &ParentOrderState, child_id: &str) {
+        let (Some(persistence), Some(child)) = (&self.inner.persistence, state.child(child_id))
+        else {
+            return;
+        };
+        if let Err(error) = persistence.update_status(child) {
+            log::error!("Could not persist child {}: {}", child_id, error);
+        }
+    }
+
+    /// Resolves once the parent is cancelled.
+    async fn cancelled(&self) {
+        let mut cancelled = self.inner.cancelled.subscribe();
+        while !*cancelled.borrow_and_update() {
+            // Only fails once the sender is gone, and this handle holds it
+            if cancelled.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Claims `child_id` for dispatch, unless the parent was cancelled.
+    fn start_dispatch(&self, child_id: &str) -> bool {
+        let mut progress = self.progress();
+        if *self.inner.cancelled.borrow() {
+            return false;
+        }
+        progress.started.insert(child_id.to_string());
+        true
+    }
+
+    fn finish_dispatch(
+        &self,
+        child: &ChildOrder,
+        dispatched_at: Timestamp,
+        result: Result<(), ClientError>,
+    ) {
+        let mut progress = self.progress();
+        let child_id = &child.order_common.id;
+        // The child was claimed from the state's own open children, and a cancel leaves it be
+        let _ = match result {
+            Ok(()) => {
+                // Keep the exchange the child was routed to
+                if let Some(state) = progress
+                    .state
+                    .children
+                    .iter_mut()
+                    .find(|state| state.child.order_common.id == *child_id)
+                {
+                    state.child.order_common.exchange = child.order_common.exchange.clone();
+                }
+                progress.state.on_dispatched(child_id, dispatched_at)
+            }
+            // A child that was never dispatched cannot fill
+            Err(_) => progress.state.on_cancel(child_id),
+        };
+        self.persist(&progress.state, child_id);
+        self.inner.audit.emit_at(
+            dispatched_at,
+            Some(&self.inner.parent_id),
+            AuditEvent::ChildDispatched {
+                child_id: child_id.clone(),
+                insert_at: child.insert_at,
+                error: result.as_ref().err().map(|error| error.to_string()),
+            },
+        );
+        progress.dispatches.push(ChildDispatch {
+            child_id: child_id.clone(),
+            insert_at: child.insert_at,
+            dispatched_at,
+            result,
+        });
+    }
+
+    /// Settles the status once every child has been handled.
+    fn finish(&self) {
+        self.settle();
+        self.release_if_over();
+    }
+
+    fn settle(&self) {
+        let progress = self.progress();
+        let failed = progress
+            .dispatches
+            .iter()
+            .any(|dispatch| dispatch.result.is_err());
+        self.inner.status.send_if_modified(|status| {
+            if *status != ExecutionStatus::Working {
+                return false;
+            }
+            *status = if failed {
+                ExecutionStatus::Failed
+            } else {
+                ExecutionStatus::Completed
+            };
+            true
+        });
+    }
+}
+
+impl std::fmt::Debug for ParentOrderHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParentOrderHandle")
+            .field("parent_id", &self.inner.parent_id)
+            .field("status", &self.status())
+            .finish()
+    }
+}
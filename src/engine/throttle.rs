@@ -0,0 +1,198 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::config::{RateLimit, ThrottleConfig};
+use crate::Timestamp;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A token bucket kept as the time it would be empty: each order moves that time on by
+/// one interval, and an order fits once that time is less than a full bucket ahead.
+///
+/// Times are in microseconds, so rates that do not divide a second stay exact enough.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    /// Time one token takes to refill
+    interval: u64,
+    /// How far ahead of the clock the empty time may run, the burst less one order
+    tolerance: u64,
+    /// When the bucket runs empty at the rate of the orders it has let through
+    empty_at: u64,
+}
+
+impl TokenBucket {
+    /// A full bucket at `now`.
+    fn new(limit: &RateLimit, now: u64) -> Self {
+        let interval = 1_000_000u64.div_ceil(u64::from(limit.orders_per_sec));
+        TokenBucket {
+            interval,
+            tolerance: interval * u64::from(limit.capacity().saturating_sub(1)),
+            empty_at: now,
+        }
+    }
+
+    /// The first time from `at` on when the bucket holds a token.
+    fn ready_at(&self, at: u64) -> u64 {
+        at.max(self.empty_at.saturating_sub(self.tolerance))
+    }
+
+    /// Takes a token at `at`, a time `ready_at` returned.
+    fn take(&mut self, at: u64) {
+        self.empty_at = self.empty_at.max(at) + self.interval;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    global: Option<TokenBucket>,
+    exchanges: HashMap<String, TokenBucket>,
+}
+
+/// The counters of a `Throttle` at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThrottleMetricsSnapshot {
+    pub acquired: u64,
+    /// Orders pushed back because a rate limit was reached
+    pub throttled: u64,
+    /// Delay added to the throttled orders, in milliseconds
+    pub total_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct ThrottleMetrics {
+    acquired: AtomicU64,
+    throttled: AtomicU64,
+    total_delay_ms: AtomicU64,
+    max_delay_ms: AtomicU64,
+}
+
+/// Token buckets holding the dispatch of children to the rate limits of a `ThrottleConfig`.
+///
+/// A child waits for a token of the global bucket and of the bucket of its exchange; a
+/// child without an exchange only waits for the global one. Each bucket starts full the
+/// first time it is used. The slot reserved for a throttled child holds its tokens from the
+/// moment it is reserved, so a later request on the same bucket never overtakes it.
+///
+/// Clones share the same buckets.
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+    config: Arc<ThrottleConfig>,
+    buckets: Arc<Mutex<Buckets>>,
+    metrics: Arc<ThrottleMetrics>,
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Throttle {
+            config: Arc::new(config),
+            ..Throttle::default()
+        }
+    }
+
+    pub fn config(&self) -> &ThrottleConfig {
+        &self.config
+    }
+
+    /// Returns true when no rate limit is configured.
+    pub fn is_unlimited(&self) -> bool {
+        self.config.limit.is_none() && self.config.exchanges.is_empty()
+    }
+
+    /// Reserves the first slot from `at` on when an order to `exchange` fits the limits,
+    /// and returns it; `at` itself when the order can go at once.
+    pub fn acquire(&self, exchange: Option<&str>, at: Timestamp) -> Timestamp {
+        let now = at.as_millis() * 1_000;
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets { global, exchanges } = &mut *buckets;
+        let global = self
+            .config
+            .limit
+            .as_ref()
+            .map(|limit| global.get_or_insert_with(|| TokenBucket::new(limit, now)));
+        let exchange = exchange.and_then(|exchange| {
+            let limit = self.config.exchanges.get(exchange)?;
+            Some(
+                exchanges
+                    .entry(exchange.to_string())
+                    .or_insert_with(|| TokenBucket::new(limit, now)),
+            )
+        });
+        let mut limiting: Vec<&mut TokenBucket> = global.into_iter().chain(exchange).collect();
+
+        // A bucket that holds a token at its ready time still holds one later on
+        let slot = limiting
+            .iter()
+            .fold(now, |slot, bucket| slot.max(bucket.ready_at(now)))
+            .div_ceil(1_000);
+        for bucket in &mut limiting {
+            bucket.take(slot * 1_000);
+        }
+        drop(buckets);
+
+        self.record(slot - at.as_millis());
+        Timestamp::from_millis(slot)
+    }
+
+    /// The first time from `at` on when an order to `exchange` would fit the limits,
+    /// without reserving it.
+    pub fn ready_at(&self, exchange: Option<&str>, at: Timestamp) -> Timestamp {
+        let now = at.as_millis() * 1_000;
+        let buckets = self.buckets.lock().unwrap();
+        // A bucket not used yet is full
+        let exchange = exchange.and_then(|exchange| buckets.exchanges.get(exchange));
+        let slot = buckets
+            .global
+            .iter()
+            .chain(exchange)
+            .fold(now, |slot, bucket| slot.max(bucket.ready_at(now)));
+        Timestamp::from_millis(slot.div_ceil(1_000))
+    }
+
+    pub fn metrics(&self) -> ThrottleMetricsSnapshot {
+        ThrottleMetricsSnapshot {
+            acquired: self.metrics.acquired.load(Ordering::Relaxed),
+            throttled: self.metrics.throttled.load(Ordering::Relaxed),
+            total_delay_ms: self.metrics.total_delay_ms.load(Ordering::Relaxed),
+            max_delay_ms: self.metrics.max_delay_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record(&self, delay_ms: u64) {
+        let metrics = &self.metrics;
+        metrics.acquired.fetch_add(1, Ordering::Relaxed);
+        if delay_ms > 0 {
+            metrics.throttled.fetch_add(1, Ordering::Relaxed);
+            metrics
+                .total_delay_ms
+                .fetch_add(delay_ms, Ordering::Relaxed);
+            metrics.max_delay_ms.fetch_max(delay_ms, Ordering::Relaxed);
+        }
+    }
+}
@@ -27,15 +27,27 @@ THE SOFTWARE.
 ******************************************************************************/
 
 // Declaring the modules
+pub mod analytics;
+pub mod audit;
+pub mod backtest;
 pub mod clients;
 pub mod config;
 pub mod constants;
+pub mod engine;
 pub mod models;
+pub mod persistence;
+pub mod portfolio;
 pub mod strategies;
 
 // Re-exporting modules to make them accessible from the crate root
+pub use analytics::*;
+pub use audit::*;
+pub use backtest::*;
 pub use clients::*;
 pub use config::*;
 pub use constants::*;
+pub use engine::*;
 pub use models::*;
+pub use persistence::*;
+pub use portfolio::*;
 pub use strategies::*;
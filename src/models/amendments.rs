@@ -0,0 +1,167 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+use super::decimal::Decimal;
+use super::orders::{Order, OrderType};
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A cancel/replace request changing the price and/or quantity of a live order.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderAmendment {
+    pub order_id: String,
+    pub price: Option<Decimal>,
+    pub quantity: Option<Quantity>,
+    pub timestamp: u64,
+}
+
+impl OrderAmendment {
+    pub fn new(
+        order_id: String,
+        price: Option<Decimal>,
+        quantity: Option<Quantity>,
+        timestamp: u64,
+    ) -> Self {
+        OrderAmendment {
+            order_id,
+            price,
+            quantity,
+            timestamp,
+        }
+    }
+}
+
+/// A request to cancel a live child order, e.g. from `ExecutionEngine::kill_switch`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CancelRequest {
+    pub order_id: String,
+    pub parent_id: String,
+    pub symbol: String,
+    /// Why the order is cancelled, for the logs of the venue
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+/// Reasons an amendment cannot be applied to an order.
+#[derive(Debug, Error, PartialEq)]
+pub enum AmendError {
+    #[error("amendment targets order {amendment_order_id}, not {order_id}")]
+    OrderMismatch {
+        order_id: String,
+        amendment_order_id: String,
+    },
+    #[error("amendment for order {order_id} changes neither price nor quantity")]
+    NoChange { order_id: String },
+    #[error("cannot amend the price of market or stop order {order_id}")]
+    PriceOnMarketOrder { order_id: String },
+    #[error("amended price {price} for order {order_id} must be greater than zero")]
+    InvalidPrice { order_id: String, price: Decimal },
+    #[error("amended quantity for order {order_id} must be greater than zero")]
+    ZeroQuantity { order_id: String },
+    #[error("amended quantity {quantity} for order {order_id} is below filled quantity {filled}")]
+    QuantityBelowFilled {
+        order_id: String,
+        quantity: Quantity,
+        filled: Quantity,
+    },
+}
+
+impl Order {
+    /// Id of the first version of this order, shared by every amended version.
+    pub fn root_id(&self) -> &str {
+        self.original_id.as_deref().unwrap_or(&self.id)
+    }
+
+    /// Produces the next version of the order with the amendment applied.
+    ///
+    /// The new version gets its own id (`<root id>-<version>`), keeps the root id
+    /// in `original_id`, bumps `version` and `nonce`, and carries over the fills
+    /// already executed. `self` is left untouched.
+    pub fn amend(&self, amendment: &OrderAmendment) -> Result<Order, AmendError> {
+        if amendment.order_id != self.id {
+            return Err(AmendError::OrderMismatch {
+                order_id: self.id.clone(),
+                amendment_order_id: amendment.order_id.clone(),
+            });
+        }
+        if amendment.price.is_none() && amendment.quantity.is_none() {
+            return Err(AmendError::NoChange {
+                order_id: self.id.clone(),
+            });
+        }
+        if let Some(price) = amendment.price {
+            if self.order_type.is_market() {
+                return Err(AmendError::PriceOnMarketOrder {
+                    order_id: self.id.clone(),
+                });
+            }
+            if price <= Decimal::ZERO {
+                return Err(AmendError::InvalidPrice {
+                    order_id: self.id.clone(),
+                    price,
+                });
+            }
+        }
+        if let Some(quantity) = amendment.quantity {
+            if !quantity.is_positive() {
+                return Err(AmendError::ZeroQuantity {
+                    order_id: self.id.clone(),
+                });
+            }
+            if quantity < self.filled_quantity {
+                return Err(AmendError::QuantityBelowFilled {
+                    order_id: self.id.clone(),
+                    quantity,
+                    filled: self.filled_quantity,
+                });
+            }
+        }
+
+        let mut amended = self.clone();
+        let root_id = self.root_id().to_string();
+        amended.version = self.version + 1;
+        amended.id = format!("{}-{}", root_id, amended.version);
+        amended.original_id = Some(root_id);
+        amended.nonce = self.nonce.map(|nonce| nonce + 1);
+        amended.timestamp = Timestamp::from_epoch(amendment.timestamp);
+        if let Some(price) = amendment.price {
+            amended.price = Some(price);
+            if let OrderType::StopLimit { limit_price, .. } = &mut amended.order_type {
+                *limit_price = price;
+            }
+        }
+        if let Some(quantity) = amendment.quantity {
+            amended.quantity = quantity;
+        }
+        if let (Some(_), Some(price)) = (self.notional, amended.price) {
+            amended.notional = Some(price * amended.quantity.value());
+        }
+        Ok(amended)
+    }
+}
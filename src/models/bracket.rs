@@ -0,0 +1,182 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use super::child_orders::ChildOrder;
+use super::decimal::Decimal;
+use super::fills::{apply_fill, Fill, FillError};
+use super::orders::{OffsetType, OrderType, TimeInForce, TrailingSpec, Validate, ValidationError};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Entry parent order with a stop-loss and take-profit pair protecting whatever part of
+/// it has been filled. Both legs are anchored on the entry's average fill price.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BracketOrder {
+    pub entry: ParentOrder,
+    pub stop_offset: Decimal,
+    pub target_offset: Decimal,
+    pub offset_type: OffsetType,
+    pub stop_loss: Option<ChildOrder>,
+    pub take_profit: Option<ChildOrder>,
+}
+
+/// Errors raised when an entry fill cannot be applied to a bracket.
+#[derive(Debug, Error, PartialEq)]
+pub enum BracketError {
+    #[error(transparent)]
+    Fill(#[from] FillError),
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+impl BracketOrder {
+    /// Brackets `entry` with a stop `stop_offset` away from the average fill price on the
+    /// losing side and a target `target_offset` away on the winning side. Percent offsets
+    /// are in percent, so 2 means 2%.
+    pub fn new(
+        entry: ParentOrder,
+        stop_offset: Decimal,
+        target_offset: Decimal,
+        offset_type: OffsetType,
+    ) -> Result<Self, ValidationError> {
+        entry.validate()?;
+        for (field, offset) in [
+            ("Stop offset", stop_offset),
+            ("Target offset", target_offset),
+        ] {
+            if offset <= Decimal::ZERO {
+                return Err(ValidationError::NonPositive {
+                    field,
+                    value: offset,
+                });
+            }
+        }
+        if offset_type == OffsetType::Percent && stop_offset >= Decimal::ONE_HUNDRED {
+            return Err(ValidationError::InconsistentFields(
+                "Stop percent offset must be below 100".to_string(),
+            ));
+        }
+        Ok(BracketOrder {
+            entry,
+            stop_offset,
+            target_offset,
+            offset_type,
+            stop_loss: None,
+            take_profit: None,
+        })
+    }
+
+    /// Protective legs currently working, empty until the entry gets its first fill.
+    pub fn legs(&self) -> Vec<&ChildOrder> {
+        self.stop_loss
+            .iter()
+            .chain(self.take_profit.iter())
+            .collect()
+    }
+
+    /// Stop and target prices for an entry filled on average at `avg_price`.
+    pub fn protective_prices(&self, avg_price: Decimal) -> (Decimal, Decimal) {
+        let exit_side = self.entry.order_common.side.opposite();
+        let stop =
+            TrailingSpec::new(self.stop_offset, self.offset_type).stop_from(avg_price, &exit_side);
+        let target = TrailingSpec::new(self.target_offset, self.offset_type)
+            .stop_from(avg_price, &self.entry.order_common.side);
+        (stop, target)
+    }
+
+    /// Applies a fill to the entry and resizes both legs to the filled quantity, re-anchoring
+    /// their prices on the new average fill price. Nothing changes when an error is returned.
+    pub fn on_parent_fill(&mut self, fill: &Fill) -> Result<(), BracketError> {
+        let mut entry = self.entry.clone();
+        apply_fill(&mut entry.order_common, fill)?;
+        let avg_price = entry
+            .order_common
+            .avg_fill_price
+            .expect("a filled order has an average fill price");
+        let (stop_price, target_price) = self.protective_prices(avg_price);
+
+        let stop_loss = protective_leg(
+            &entry,
+            self.stop_loss.as_ref(),
+            "sl",
+            OrderType::Stop { stop_price },
+            None,
+            fill.timestamp,
+        );
+        let take_profit = protective_leg(
+            &entry,
+            self.take_profit.as_ref(),
+            "tp",
+            OrderType::Limit,
+            Some(target_price),
+            fill.timestamp,
+        );
+        stop_loss.validate()?;
+        take_profit.validate()?;
+
+        self.entry = entry;
+        self.stop_loss = Some(stop_loss);
+        self.take_profit = Some(take_profit);
+        Ok(())
+    }
+}
+
+/// Leg closing the filled part of `entry`. A leg that is already working keeps its
+/// original `insert_at`.
+fn protective_leg(
+    entry: &ParentOrder,
+    existing: Option<&ChildOrder>,
+    suffix: &str,
+    order_type: OrderType,
+    price: Option<Decimal>,
+    timestamp: u64,
+) -> ChildOrder {
+    let mut order = entry.order_common.clone();
+    order.id = format!("{}-{}", entry.order_common.id, suffix);
+    order.side = entry.order_common.side.opposite();
+    order.order_type = order_type;
+    order.price = price;
+    order.quantity = entry.order_common.filled_quantity;
+    order.filled_quantity = Quantity::ZERO;
+    order.avg_fill_price = None;
+    order.original_id = None;
+    order.version = 0;
+    order.notional = None;
+    order.timeinforce = Some(TimeInForce::GTC);
+    order.expiry_date = None;
+    order.timestamp = Timestamp::from_epoch(timestamp);
+    let insert_at = existing.map_or(Some(order.timestamp), |leg| leg.insert_at);
+    order.into_child(
+        entry.strategy_id.clone(),
+        entry.order_common.id.clone(),
+        insert_at,
+    )
+}
@@ -0,0 +1,261 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+use super::child_orders::ChildOrder;
+use super::decimal::Decimal;
+use super::ids::{IdGenerator, NonceSource};
+use super::orders::{
+    Futures, Options, Order, OrderType, ProductType, Side, Swap, TimeInForce, Validate,
+    ValidationError, CFD,
+};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+
+/// Fluent construction of an [`Order`].
+///
+/// `quantity`, `symbol`, `side` and `currency` are required. Everything else defaults: a
+/// Spot Market order with no price, timestamped now (epoch millis), with a fresh UUIDv7 id.
+#[derive(Clone, Default)]
+pub struct OrderBuilder {
+    id: Option<String>,
+    quantity: Option<Quantity>,
+    product_type: Option<ProductType>,
+    order_type: Option<OrderType>,
+    price: Option<Decimal>,
+    timestamp: Option<Timestamp>,
+    expiry_date: Option<Timestamp>,
+    symbol: Option<String>,
+    side: Option<Side>,
+    currency: Option<String>,
+    exchange: Option<String>,
+    timeinforce: Option<TimeInForce>,
+    futures_opt: Option<Futures>,
+    options_opt: Option<Options>,
+    swap_opt: Option<Swap>,
+    cfd_opt: Option<CFD>,
+    notional: Option<Decimal>,
+    nonce: Option<u64>,
+    account_id: Option<String>,
+    portfolio_id: Option<String>,
+}
+
+impl OrderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Takes the id from `ids` instead of the default UUIDv7 generator
+    pub fn generate_id(mut self, ids: &IdGenerator) -> Self {
+        self.id = Some(ids.next_id());
+        self
+    }
+
+    pub fn quantity(mut self, quantity: impl Into<Quantity>) -> Self {
+        self.quantity = Some(quantity.into());
+        self
+    }
+
+    pub fn product_type(mut self, product_type: ProductType) -> Self {
+        self.product_type = Some(product_type);
+        self
+    }
+
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = Some(order_type);
+        self
+    }
+
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: impl Into<Timestamp>) -> Self {
+        self.timestamp = Some(timestamp.into());
+        self
+    }
+
+    pub fn expiry_date(mut self, expiry_date: impl Into<Timestamp>) -> Self {
+        self.expiry_date = Some(expiry_date.into());
+        self
+    }
+
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    pub fn currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+
+    pub fn exchange(mut self, exchange: impl Into<String>) -> Self {
+        self.exchange = Some(exchange.into());
+        self
+    }
+
+    pub fn timeinforce(mut self, timeinforce: TimeInForce) -> Self {
+        self.timeinforce = Some(timeinforce);
+        self
+    }
+
+    pub fn futures(mut self, futures: Futures) -> Self {
+        self.futures_opt = Some(futures);
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options_opt = Some(options);
+        self
+    }
+
+    pub fn swap(mut self, swap: Swap) -> Self {
+        self.swap_opt = Some(swap);
+        self
+    }
+
+    pub fn cfd(mut self, cfd: CFD) -> Self {
+        self.cfd_opt = Some(cfd);
+        self
+    }
+
+    pub fn notional(mut self, notional: Decimal) -> Self {
+        self.notional = Some(notional);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn next_nonce(mut self, nonces: &NonceSource) -> Self {
+        self.nonce = Some(nonces.next_nonce());
+        self
+    }
+
+    pub fn account_id(mut self, account_id: impl Into<String>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn portfolio_id(mut self, portfolio_id: impl Into<String>) -> Self {
+        self.portfolio_id = Some(portfolio_id.into());
+        self
+    }
+
+    /// Assembles the order and runs [`Validate::validate`] on it.
+    pub fn build(self) -> Result<Order, ValidationError> {
+        let mut order = Order::new(
+            self.id.unwrap_or_else(|| IdGenerator::default().next_id()),
+            self.quantity
+                .ok_or(ValidationError::MissingField("quantity"))?,
+            self.product_type.unwrap_or(ProductType::Spot),
+            self.order_type.unwrap_or(OrderType::Market),
+            self.price,
+            self.timestamp.unwrap_or_else(Timestamp::now),
+            self.expiry_date,
+            self.symbol.ok_or(ValidationError::MissingField("symbol"))?,
+            self.side.ok_or(ValidationError::MissingField("side"))?,
+            self.currency
+                .ok_or(ValidationError::MissingField("currency"))?,
+            self.exchange,
+            self.timeinforce,
+            self.futures_opt,
+            self.options_opt,
+            self.swap_opt,
+            self.cfd_opt,
+            self.notional,
+            self.nonce,
+        );
+        order.account_id = self.account_id;
+        order.portfolio_id = self.portfolio_id;
+        order.validate()?;
+        Ok(order)
+    }
+
+    /// Builds the order and wraps it in a [`ParentOrder`].
+    pub fn build_parent(
+        self,
+        strategy_id: impl Into<String>,
+    ) -> Result<ParentOrder, ValidationError> {
+        let parent_order = self.build()?.into_parent(strategy_id);
+        parent_order.validate()?;
+        Ok(parent_order)
+    }
+
+    /// Builds the order and wraps it in a [`ChildOrder`] of `parent_id`.
+    pub fn build_child(
+        self,
+        strategy_id: impl Into<String>,
+        parent_id: impl Into<String>,
+        insert_at: Option<Timestamp>,
+    ) -> Result<ChildOrder, ValidationError> {
+        let child_order = self.build()?.into_child(strategy_id, parent_id, insert_at);
+        child_order.validate()?;
+        Ok(child_order)
+    }
+}
+
+impl Order {
+    pub fn builder() -> OrderBuilder {
+        OrderBuilder::new()
+    }
+
+    pub fn into_parent(self, strategy_id: impl Into<String>) -> ParentOrder {
+        ParentOrder {
+            order_common: self,
+            strategy_id: strategy_id.into(),
+        }
+    }
+
+    pub fn into_child(
+        self,
+        strategy_id: impl Into<String>,
+        parent_id: impl Into<String>,
+        insert_at: Option<Timestamp>,
+    ) -> ChildOrder {
+        ChildOrder {
+            order_common: self,
+            strategy_id: strategy_id.into(),
+            parent_id: parent_id.into(),
+            insert_at,
+        }
+    }
+}
@@ -0,0 +1,271 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use chrono::{DateTime, Datelike, NaiveDate, Offset, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const MILLIS_PER_MINUTE: i64 = 60_000;
+const MILLIS_PER_DAY: i64 = 86_400_000;
+const MINUTES_PER_DAY: u32 = 1_440;
+/// How far ahead `next_open` searches for a session (days)
+const SEARCH_HORIZON_DAYS: i64 = 366;
+/// Days from 0001-01-01 to 1970-01-01, counting the first
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Trading session expressed in minutes from local midnight. `close_minute` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub open_minute: u32,
+    pub close_minute: u32,
+}
+
+impl Session {
+    pub fn new(open_minute: u32, close_minute: u32) -> Self {
+        Session {
+            open_minute,
+            close_minute: close_minute.min(MINUTES_PER_DAY),
+        }
+    }
+}
+
+/// Exchange trading calendar with weekly sessions, holidays and early closes.
+///
+/// Timestamps are UNIX milliseconds. Sessions are defined in exchange local time, either at
+/// a fixed UTC offset or, with a `timezone`, following its daylight saving changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingCalendar {
+    pub name: String,
+    /// Fixed offset of local time, used when there is no `timezone`
+    pub utc_offset_minutes: i32,
+    /// Time zone of the exchange, which takes precedence over `utc_offset_minutes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
+    /// Sessions indexed by weekday, Monday first
+    pub sessions: [Option<Session>; 7],
+    /// Local days (since epoch) when the market is closed
+    pub holidays: HashSet<i64>,
+    /// Local days (since epoch) with a shortened session, mapped to the close minute
+    pub early_closes: HashMap<i64, u32>,
+}
+
+impl TradingCalendar {
+    pub fn new(name: &str, utc_offset_minutes: i32, sessions: [Option<Session>; 7]) -> Self {
+        TradingCalendar {
+            name: name.to_string(),
+            utc_offset_minutes,
+            timezone: None,
+            sessions,
+            holidays: HashSet::new(),
+            early_closes: HashMap::new(),
+        }
+    }
+
+    /// Calendar open around the clock every day, as for crypto venues
+    pub fn always_open() -> Self {
+        Self::new("24/7", 0, [Some(Session::new(0, MINUTES_PER_DAY)); 7])
+    }
+
+    /// Monday to Friday regular trading hours, 09:30 to 16:00 local time
+    pub fn weekdays_rth(utc_offset_minutes: i32) -> Self {
+        let session = Some(Session::new(9 * 60 + 30, 16 * 60));
+        Self::new(
+            "RTH",
+            utc_offset_minutes,
+            [session, session, session, session, session, None, None],
+        )
+    }
+
+    /// US equities regular trading hours in New York time, on EST (UTC-5) in winter and EDT
+    /// (UTC-4) in summer
+    pub fn us_equities() -> Self {
+        Self::weekdays_rth(-5 * 60).with_timezone(chrono_tz::America::New_York)
+    }
+
+    /// Define the sessions in the local time of `timezone`, daylight saving included
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Close the market for the whole given local date; an invalid date is ignored
+    pub fn with_holiday(mut self, year: i32, month: u32, day: u32) -> Self {
+        match local_day(year, month, day) {
+            Some(day) => {
+                self.holidays.insert(day);
+            }
+            None => log::warn!(
+                "Ignoring holiday on invalid date {}-{}-{}",
+                year,
+                month,
+                day
+            ),
+        }
+        self
+    }
+
+    /// Close the market early on the given local date, `close_minute` from local midnight;
+    /// an invalid date is ignored
+    pub fn with_early_close(mut self, year: i32, month: u32, day: u32, close_minute: u32) -> Self {
+        match local_day(year, month, day) {
+            Some(day) => {
+                self.early_closes.insert(day, close_minute);
+            }
+            None => log::warn!(
+                "Ignoring early close on invalid date {}-{}-{}",
+                year,
+                month,
+                day
+            ),
+        }
+        self
+    }
+
+    fn local_millis(&self, ts: u64) -> i64 {
+        let offset = match (self.timezone, DateTime::from_timestamp_millis(ts as i64)) {
+            (Some(timezone), Some(utc)) => {
+                timezone
+                    .offset_from_utc_datetime(&utc.naive_utc())
+                    .fix()
+                    .local_minus_utc() as i64
+                    * 1_000
+            }
+            _ => self.utc_offset_minutes as i64 * MILLIS_PER_MINUTE,
+        };
+        ts as i64 + offset
+    }
+
+    fn to_utc(&self, local_millis: i64) -> u64 {
+        let local = DateTime::from_timestamp_millis(local_millis).map(|local| local.naive_utc());
+        // A local time skipped by a clock change takes the offset in force before it
+        let offset = match (self.timezone, local) {
+            (Some(timezone), Some(local)) => timezone
+                .offset_from_local_datetime(&local)
+                .earliest()
+                .or_else(|| {
+                    timezone
+                        .offset_from_local_datetime(&(local - chrono::Duration::hours(1)))
+                        .earliest()
+                })
+                .map(|offset| offset.fix().local_minus_utc() as i64 * 1_000),
+            _ => None,
+        };
+        let offset = offset.unwrap_or(self.utc_offset_minutes as i64 * MILLIS_PER_MINUTE);
+        (local_millis - offset).max(0) as u64
+    }
+
+    /// Effective session for a local day, accounting for holidays and early closes
+    fn session_for_day(&self, day: i64) -> Option<Session> {
+        if self.holidays.contains(&day) {
+            return None;
+        }
+        let weekday = (day + 3).rem_euclid(7) as usize;
+        let mut session = self.sessions[weekday]?;
+        if let Some(&close_minute) = self.early_closes.get(&day) {
+            session.close_minute = session.close_minute.min(close_minute);
+        }
+        (session.open_minute < session.close_minute).then_some(session)
+    }
+
+    /// Whether the market is open at `ts`
+    pub fn is_open(&self, ts: u64) -> bool {
+        let local = self.local_millis(ts);
+        let day = local.div_euclid(MILLIS_PER_DAY);
+        let minute = (local.rem_euclid(MILLIS_PER_DAY) / MILLIS_PER_MINUTE) as u32;
+        match self.session_for_day(day) {
+            Some(session) => session.open_minute <= minute && minute < session.close_minute,
+            None => false,
+        }
+    }
+
+    /// Start of the first session opening strictly after `ts`. Returns `ts` unchanged if no
+    /// session opens within a year.
+    pub fn next_open(&self, ts: u64) -> u64 {
+        let local = self.local_millis(ts);
+        let first_day = local.div_euclid(MILLIS_PER_DAY);
+        for day in first_day..=first_day + SEARCH_HORIZON_DAYS {
+            if let Some(session) = self.session_for_day(day) {
+                let open = day * MILLIS_PER_DAY + session.open_minute as i64 * MILLIS_PER_MINUTE;
+                if open > local {
+                    return self.to_utc(open);
+                }
+            }
+        }
+        ts
+    }
+
+    /// `ts` itself if the market is open, otherwise the next session open
+    pub fn clamp_to_session(&self, ts: u64) -> u64 {
+        if self.is_open(ts) {
+            ts
+        } else {
+            self.next_open(ts)
+        }
+    }
+
+    /// `ts` moved forward by `millis` of trading time. Time that runs past a session close
+    /// carries on from the next open, so children spaced by an interval keep that spacing
+    /// across sessions instead of piling up at the open.
+    pub fn advance(&self, ts: u64, millis: u64) -> u64 {
+        let mut now = self.clamp_to_session(ts);
+        let mut remaining = millis;
+        loop {
+            let Some(close) = self.session_close(now) else {
+                return now + remaining;
+            };
+            let open_for = close - now;
+            if remaining < open_for {
+                return now + remaining;
+            }
+            remaining -= open_for;
+            let next = self.next_open(now);
+            if next <= now {
+                // No session within the search horizon
+                return close + remaining;
+            }
+            now = next;
+        }
+    }
+
+    /// Close of the session `ts` falls in, `None` when the market is shut at `ts`
+    fn session_close(&self, ts: u64) -> Option<u64> {
+        if !self.is_open(ts) {
+            return None;
+        }
+        let day = self.local_millis(ts).div_euclid(MILLIS_PER_DAY);
+        let session = self.session_for_day(day)?;
+        Some(self.to_utc(day * MILLIS_PER_DAY + session.close_minute as i64 * MILLIS_PER_MINUTE))
+    }
+}
+
+/// Days since 1970-01-01 of a local date, `None` when the date does not exist
+fn local_day(year: i32, month: u32, day: u32) -> Option<i64> {
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some((date.num_days_from_ce() - EPOCH_DAYS_FROM_CE) as i64)
+}
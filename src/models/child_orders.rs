@@ -26,8 +26,12 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
+use super::decimal::Decimal;
 use super::orders::{Futures, Options, Order, OrderType, ProductType, Side, Swap, TimeInForce};
-use crate::{Validate, CFD};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use crate::{Validate, ValidationError, CFD};
 use serde::{Deserialize, Serialize};
 
 /// Structure representing a parent order.
@@ -37,18 +41,19 @@ pub struct ChildOrder {
     pub order_common: Order,
     pub strategy_id: String,
     pub parent_id: String,
-    pub insert_at: Option<u64>,
+    pub insert_at: Option<Timestamp>,
 }
 
 impl ChildOrder {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: String,
-        quantity: u32,
+        quantity: Quantity,
         product_type: ProductType,
         order_type: OrderType,
-        price: Option<f64>,
-        timestamp: u64,
-        expiry_date: Option<u64>,
+        price: Option<Decimal>,
+        timestamp: Timestamp,
+        expiry_date: Option<Timestamp>,
         symbol: String,
         side: Side,
         currency: String,
@@ -58,11 +63,11 @@ impl ChildOrder {
         options_opt: Option<Options>,
         swap_opt: Option<Swap>,
         cfd_opt: Option<CFD>,
-        notional: Option<f64>,
+        notional: Option<Decimal>,
         nonce: Option<u64>,
         strategy_id: String,
         parent_id: String,
-        insert_at: Option<u64>,
+        insert_at: Option<Timestamp>,
     ) -> Self {
         ChildOrder {
             order_common: Order::new(
@@ -90,15 +95,41 @@ impl ChildOrder {
             insert_at,
         }
     }
+
+    /// Validates the child and checks that it belongs to `parent`: same parent id, and the
+    /// account and portfolio inherited unchanged.
+    pub fn validate_for_parent(&self, parent: &ParentOrder) -> Result<(), ValidationError> {
+        self.validate()?;
+        let parent_order = &parent.order_common;
+        if self.parent_id != parent_order.id {
+            return Err(ValidationError::InconsistentFields(format!(
+                "Child order {} belongs to parent {}, not {}",
+                self.order_common.id, self.parent_id, parent_order.id
+            )));
+        }
+        if self.order_common.account_id != parent_order.account_id {
+            return Err(ValidationError::InconsistentFields(format!(
+                "Child order {} account must match its parent account",
+                self.order_common.id
+            )));
+        }
+        if self.order_common.portfolio_id != parent_order.portfolio_id {
+            return Err(ValidationError::InconsistentFields(format!(
+                "Child order {} portfolio must match its parent portfolio",
+                self.order_common.id
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Validate for ChildOrder {
-    fn validate(&self) -> Result<(), String> {
+    fn validate(&self) -> Result<(), ValidationError> {
         if self.strategy_id.is_empty() {
-            return Err("Strategy ID cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Strategy ID"));
         }
         if self.parent_id.is_empty() {
-            return Err("Parent ID cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Parent ID"));
         }
         self.order_common.validate()
     }
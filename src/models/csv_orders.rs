@@ -0,0 +1,508 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! CSV import and export of parent order batches.
+//!
+//! One row per parent order. The common order fields and `strategy_id` are columns of
+//! their own; derivative details use optional columns read according to the row's
+//! `product_type`:
+//!
+//! * Futures: `delivery_date`, `contract_size`, `margin`, `commission`, `overnight_fee`
+//! * Options: `strike_price`, `option_type`, `option_expiry`
+//! * Swap: `fixed_rate`, `floating_rate_index`, `notional_amount`
+//! * CFD: `leverage`, `margin`, `commission`, `overnight_fee`, `dividend_adjustment`,
+//!   `contract_size`
+//!
+//! Enum values are spelled as in JSON (`Buy`, `Futures`, `GTC`, ...). Empty cells and
+//! absent optional columns read as unset.
+
+use super::decimal::Decimal;
+use super::orders::{
+    Futures, OffsetType, Options, Order, OrderType, ProductType, Swap, TrailingSpec, Validate,
+    ValidationError, CFD,
+};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io;
+use std::str::FromStr;
+use thiserror::Error;
+
+const REQUIRED_COLUMNS: [&str; 9] = [
+    "id",
+    "strategy_id",
+    "symbol",
+    "side",
+    "quantity",
+    "product_type",
+    "order_type",
+    "timestamp",
+    "currency",
+];
+
+const COLUMNS: [&str; 35] = [
+    "id",
+    "strategy_id",
+    "symbol",
+    "side",
+    "quantity",
+    "product_type",
+    "order_type",
+    "price",
+    "stop_price",
+    "limit_price",
+    "trailing_offset",
+    "trailing_offset_type",
+    "timestamp",
+    "expiry_date",
+    "currency",
+    "exchange",
+    "timeinforce",
+    "notional",
+    "nonce",
+    "quantity_step",
+    "account_id",
+    "portfolio_id",
+    "delivery_date",
+    "contract_size",
+    "margin",
+    "commission",
+    "overnight_fee",
+    "strike_price",
+    "option_type",
+    "option_expiry",
+    "fixed_rate",
+    "floating_rate_index",
+    "notional_amount",
+    "leverage",
+    "dividend_adjustment",
+];
+
+/// Columns each product type reads; the others must be empty on its rows.
+const DERIVATIVE_COLUMNS: [(&str, &[&str]); 4] = [
+    (
+        "Futures",
+        &[
+            "delivery_date",
+            "contract_size",
+            "margin",
+            "commission",
+            "overnight_fee",
+        ],
+    ),
+    ("Options", &["strike_price", "option_type", "option_expiry"]),
+    (
+        "Swap",
+        &["fixed_rate", "floating_rate_index", "notional_amount"],
+    ),
+    (
+        "CFD",
+        &[
+            "leverage",
+            "margin",
+            "commission",
+            "overnight_fee",
+            "dividend_adjustment",
+            "contract_size",
+        ],
+    ),
+];
+
+#[derive(Debug, Error)]
+pub enum CsvError {
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("missing column {0}")]
+    MissingColumn(&'static str),
+    #[error("missing value for {0}")]
+    MissingValue(&'static str),
+    #[error("invalid {column}: {value}")]
+    InvalidValue { column: &'static str, value: String },
+    #[error("column {column} does not apply to {product} orders")]
+    UnexpectedColumn {
+        column: &'static str,
+        product: &'static str,
+    },
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+/// A row that could not be imported. `line` is the 1-based line in the file.
+#[derive(Debug)]
+pub struct CsvRowError {
+    pub line: u64,
+    pub id: Option<String>,
+    pub error: CsvError,
+}
+
+/// Outcome of importing a file: the valid orders and one error per rejected row.
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    pub orders: Vec<ParentOrder>,
+    pub errors: Vec<CsvRowError>,
+}
+
+struct Row<'a> {
+    record: &'a csv::StringRecord,
+    columns: &'a HashMap<String, usize>,
+}
+
+impl Row<'_> {
+    fn get(&self, column: &'static str) -> Option<&str> {
+        self.columns
+            .get(column)
+            .and_then(|&index| self.record.get(index))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&self, column: &'static str) -> Result<&str, CsvError> {
+        self.get(column).ok_or(CsvError::MissingValue(column))
+    }
+
+    fn parse<T: FromStr>(&self, column: &'static str) -> Result<Option<T>, CsvError> {
+        self.get(column)
+            .map(|value| {
+                value.parse().map_err(|_| CsvError::InvalidValue {
+                    column,
+                    value: value.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    fn parse_required<T: FromStr>(&self, column: &'static str) -> Result<T, CsvError> {
+        self.parse(column)?.ok_or(CsvError::MissingValue(column))
+    }
+
+    /// Parses an enum from its JSON spelling.
+    fn parse_enum<T: DeserializeOwned>(&self, column: &'static str) -> Result<Option<T>, CsvError> {
+        self.get(column)
+            .map(|value| {
+                T::deserialize(value.into_deserializer()).map_err(|_: serde::de::value::Error| {
+                    CsvError::InvalidValue {
+                        column,
+                        value: value.to_string(),
+                    }
+                })
+            })
+            .transpose()
+    }
+
+    fn any(&self, columns: &[&str]) -> bool {
+        columns.iter().any(|column| {
+            self.columns
+                .get(*column)
+                .and_then(|&index| self.record.get(index))
+                .is_some_and(|value| !value.trim().is_empty())
+        })
+    }
+
+    fn order_type(&self) -> Result<OrderType, CsvError> {
+        let order_type = self.required("order_type")?;
+        match order_type {
+            "Market" => Ok(OrderType::Market),
+            "Limit" => Ok(OrderType::Limit),
+            "Stop" => Ok(OrderType::Stop {
+                stop_price: self.parse_required("stop_price")?,
+            }),
+            "StopLimit" => Ok(OrderType::StopLimit {
+                stop_price: self.parse_required("stop_price")?,
+                limit_price: self.parse_required("limit_price")?,
+            }),
+            "TrailingStop" => Ok(OrderType::TrailingStop(TrailingSpec::new(
+                self.parse_required("trailing_offset")?,
+                self.parse_enum::<OffsetType>("trailing_offset_type")?
+                    .ok_or(CsvError::MissingValue("trailing_offset_type"))?,
+            ))),
+            _ => Err(CsvError::InvalidValue {
+                column: "order_type",
+                value: order_type.to_string(),
+            }),
+        }
+    }
+
+    fn check_derivative_columns(&self, product: &'static str) -> Result<(), CsvError> {
+        let allowed: &[&str] = DERIVATIVE_COLUMNS
+            .iter()
+            .find(|(name, _)| *name == product)
+            .map_or(&[], |(_, columns)| columns);
+        for (_, columns) in DERIVATIVE_COLUMNS.iter() {
+            for column in columns.iter() {
+                if !allowed.contains(column) && self.any(&[column]) {
+                    return Err(CsvError::UnexpectedColumn { column, product });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parent_order(&self) -> Result<ParentOrder, CsvError> {
+        let product_type = self
+            .parse_enum::<ProductType>("product_type")?
+            .ok_or(CsvError::MissingValue("product_type"))?;
+        let product = product_name(&product_type);
+        self.check_derivative_columns(product)?;
+
+        let mut builder = Order::builder()
+            .id(self.required("id")?)
+            .quantity(self.parse_required::<Quantity>("quantity")?)
+            .product_type(product_type.clone())
+            .order_type(self.order_type()?)
+            .timestamp(self.parse_required::<Timestamp>("timestamp")?)
+            .symbol(self.required("symbol")?)
+            .side(
+                self.parse_enum("side")?
+                    .ok_or(CsvError::MissingValue("side"))?,
+            )
+            .currency(self.required("currency")?);
+        if let Some(price) = self.parse("price")? {
+            builder = builder.price(price);
+        }
+        if let Some(expiry_date) = self.parse::<Timestamp>("expiry_date")? {
+            builder = builder.expiry_date(expiry_date);
+        }
+        if let Some(exchange) = self.get("exchange") {
+            builder = builder.exchange(exchange);
+        }
+        if let Some(timeinforce) = self.parse_enum("timeinforce")? {
+            builder = builder.timeinforce(timeinforce);
+        }
+        if let Some(notional) = self.parse("notional")? {
+            builder = builder.notional(notional);
+        }
+        if let Some(nonce) = self.parse("nonce")? {
+            builder = builder.nonce(nonce);
+        }
+        if let Some(account_id) = self.get("account_id") {
+            builder = builder.account_id(account_id);
+        }
+        if let Some(portfolio_id) = self.get("portfolio_id") {
+            builder = builder.portfolio_id(portfolio_id);
+        }
+        builder = match product_type {
+            ProductType::Futures if self.any(DERIVATIVE_COLUMNS[0].1) => builder.futures(Futures {
+                delivery_date: self.parse("delivery_date")?,
+                contract_size: self.parse("contract_size")?,
+                margin: self.parse("margin")?,
+                commission: self.parse("commission")?,
+                overnight_fee: self.parse("overnight_fee")?,
+            }),
+            ProductType::Options if self.any(DERIVATIVE_COLUMNS[1].1) => builder.options(Options {
+                strike_price: self.parse_required("strike_price")?,
+                option_type: self
+                    .parse_enum("option_type")?
+                    .ok_or(CsvError::MissingValue("option_type"))?,
+                expiry_date: self.parse_required("option_expiry")?,
+            }),
+            ProductType::Swap if self.any(DERIVATIVE_COLUMNS[2].1) => builder.swap(Swap {
+                fixed_rate: self.parse_required("fixed_rate")?,
+                floating_rate_index: self.required("floating_rate_index")?.to_string(),
+                notional_amount: self.parse_required("notional_amount")?,
+            }),
+            ProductType::CFD if self.any(DERIVATIVE_COLUMNS[3].1) => builder.cfd(CFD {
+                leverage: self.parse("leverage")?,
+                margin: self.parse("margin")?,
+                commission: self.parse("commission")?,
+                overnight_fee: self.parse("overnight_fee")?,
+                dividend_adjustment: self.parse("dividend_adjustment")?,
+                contract_size: self.parse("contract_size")?,
+            }),
+            _ => builder,
+        };
+
+        let mut order = builder.build()?;
+        order.quantity_step = self.parse("quantity_step")?;
+        let parent_order = order.into_parent(self.required("strategy_id")?);
+        parent_order.validate()?;
+        Ok(parent_order)
+    }
+}
+
+fn product_name(product_type: &ProductType) -> &'static str {
+    match product_type {
+        ProductType::Spot => "Spot",
+        ProductType::Futures => "Futures",
+        ProductType::Options => "Options",
+        ProductType::Swap => "Swap",
+        ProductType::CFD => "CFD",
+    }
+}
+
+/// JSON spelling of a unit enum variant.
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn cell<T: Display>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn record(parent_order: &ParentOrder) -> Vec<String> {
+    let order = &parent_order.order_common;
+    let (order_type, stop_price, limit_price, trailing) = match &order.order_type {
+        OrderType::Market => ("Market", None, None, None),
+        OrderType::Limit => ("Limit", None, None, None),
+        OrderType::Stop { stop_price } => ("Stop", Some(*stop_price), None, None),
+        OrderType::StopLimit {
+            stop_price,
+            limit_price,
+        } => ("StopLimit", Some(*stop_price), Some(*limit_price), None),
+        OrderType::TrailingStop(spec) => ("TrailingStop", None, None, Some(spec)),
+    };
+    let futures = order.futures_opt.as_ref();
+    let cfd = order.cfd_opt.as_ref();
+    let options = order.options_opt.as_ref();
+    let swap = order.swap_opt.as_ref();
+    let shared = |futures_value: Option<Decimal>, cfd_value: Option<Decimal>| {
+        cell(futures_value.or(cfd_value))
+    };
+    vec![
+        order.id.clone(),
+        parent_order.strategy_id.clone(),
+        order.symbol.clone(),
+        enum_name(&order.side),
+        order.quantity.to_string(),
+        enum_name(&order.product_type),
+        order_type.to_string(),
+        cell(order.price),
+        cell(stop_price),
+        cell(limit_price),
+        cell(trailing.map(|spec| spec.offset)),
+        cell(trailing.map(|spec| enum_name(&spec.offset_type))),
+        order.timestamp.to_string(),
+        cell(order.expiry_date),
+        order.currency.clone(),
+        cell(order.exchange.as_ref()),
+        cell(order.timeinforce.as_ref().map(enum_name)),
+        cell(order.notional),
+        cell(order.nonce),
+        cell(order.quantity_step),
+        cell(order.account_id.as_ref()),
+        cell(order.portfolio_id.as_ref()),
+        cell(futures.and_then(|futures| futures.delivery_date)),
+        cell(
+            futures
+                .and_then(|futures| futures.contract_size)
+                .or(cfd.and_then(|cfd| cfd.contract_size)),
+        ),
+        shared(
+            futures.and_then(|futures| futures.margin),
+            cfd.and_then(|cfd| cfd.margin),
+        ),
+        shared(
+            futures.and_then(|futures| futures.commission),
+            cfd.and_then(|cfd| cfd.commission),
+        ),
+        shared(
+            futures.and_then(|futures| futures.overnight_fee),
+            cfd.and_then(|cfd| cfd.overnight_fee),
+        ),
+        cell(options.map(|options| options.strike_price)),
+        cell(options.map(|options| enum_name(&options.option_type))),
+        cell(options.map(|options| options.expiry_date)),
+        cell(swap.map(|swap| swap.fixed_rate)),
+        cell(swap.map(|swap| swap.floating_rate_index.clone())),
+        cell(swap.map(|swap| swap.notional_amount)),
+        cell(cfd.and_then(|cfd| cfd.leverage)),
+        cell(cfd.and_then(|cfd| cfd.dividend_adjustment)),
+    ]
+}
+
+impl ParentOrder {
+    /// Reads a batch of parent orders. Rows that cannot be parsed or fail `validate()` are
+    /// reported in [`CsvImport::errors`] without stopping the import; only an unreadable
+    /// header or a missing required column fails the whole file.
+    pub fn from_csv_reader<R: io::Read>(reader: R) -> Result<CsvImport, CsvError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::Headers)
+            .from_reader(reader);
+        let columns: HashMap<String, usize> = reader
+            .headers()?
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.to_string(), index))
+            .collect();
+        if let Some(column) = REQUIRED_COLUMNS
+            .iter()
+            .find(|column| !columns.contains_key(**column))
+        {
+            return Err(CsvError::MissingColumn(column));
+        }
+
+        let mut import = CsvImport::default();
+        for result in reader.records() {
+            let record = match result {
+                Ok(record) => record,
+                Err(error) => {
+                    let line = error.position().map_or(0, |position| position.line());
+                    import.errors.push(CsvRowError {
+                        line,
+                        id: None,
+                        error: error.into(),
+                    });
+                    continue;
+                }
+            };
+            let row = Row {
+                record: &record,
+                columns: &columns,
+            };
+            match row.parent_order() {
+                Ok(parent_order) => import.orders.push(parent_order),
+                Err(error) => import.errors.push(CsvRowError {
+                    line: record.position().map_or(0, |position| position.line()),
+                    id: row.get("id").map(str::to_string),
+                    error,
+                }),
+            }
+        }
+        Ok(import)
+    }
+
+    /// Writes `orders` with a header row and every column, derivative ones included.
+    pub fn write_csv<W: io::Write>(writer: W, orders: &[ParentOrder]) -> Result<(), CsvError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(COLUMNS)?;
+        for parent_order in orders {
+            writer.write_record(record(parent_order))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
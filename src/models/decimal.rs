@@ -0,0 +1,45 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Monetary values on orders (prices, notionals, fees, margins) are stored as
+//! [`Decimal`] so limit prices and compliance checks compare exactly. Indicator
+//! maths stays in `f64`; the helpers below convert at the boundary.
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+pub use rust_decimal::Decimal;
+
+/// Converts an `f64` into a [`Decimal`] using its shortest decimal representation,
+/// so `0.1_f64` becomes exactly `0.1`. Non-finite or out-of-range values map to zero.
+pub fn decimal_from_f64(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default()
+}
+
+/// Converts a [`Decimal`] into the nearest `f64` for indicator arithmetic.
+pub fn decimal_to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
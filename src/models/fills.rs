@@ -0,0 +1,130 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+use super::decimal::Decimal;
+use super::orders::Order;
+use super::quantity::Quantity;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Whether an execution added or removed liquidity from the book.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+/// A single execution reported against an order.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Fill {
+    pub order_id: String,
+    pub exec_id: String,
+    pub price: Decimal,
+    pub quantity: Quantity,
+    pub timestamp: u64,
+    pub fee: Decimal,
+    pub liquidity: Liquidity,
+}
+
+impl Fill {
+    pub fn new(
+        order_id: String,
+        exec_id: String,
+        price: Decimal,
+        quantity: Quantity,
+        timestamp: u64,
+        fee: Decimal,
+        liquidity: Liquidity,
+    ) -> Self {
+        Fill {
+            order_id,
+            exec_id,
+            price,
+            quantity,
+            timestamp,
+            fee,
+            liquidity,
+        }
+    }
+
+    /// Traded value of the execution, excluding fees.
+    pub fn notional(&self) -> Decimal {
+        self.price * self.quantity.value()
+    }
+}
+
+/// Errors raised when a fill cannot be applied to an order.
+#[derive(Debug, Error, PartialEq)]
+pub enum FillError {
+    #[error("fill {exec_id} belongs to order {fill_order_id}, not {order_id}")]
+    OrderMismatch {
+        order_id: String,
+        fill_order_id: String,
+        exec_id: String,
+    },
+    #[error("fill {exec_id} has a non-positive quantity")]
+    ZeroQuantity { exec_id: String },
+    #[error("fill {exec_id} of {fill_quantity} overfills order {order_id}: {filled} of {quantity} already filled")]
+    Overfill {
+        order_id: String,
+        exec_id: String,
+        quantity: Quantity,
+        filled: Quantity,
+        fill_quantity: Quantity,
+    },
+}
+
+/// Applies `fill` to `order`, updating its filled quantity and volume weighted
+/// average fill price. The order is left untouched when an error is returned.
+pub fn apply_fill(order: &mut Order, fill: &Fill) -> Result<(), FillError> {
+    if fill.order_id != order.id {
+        return Err(FillError::OrderMismatch {
+            order_id: order.id.clone(),
+            fill_order_id: fill.order_id.clone(),
+            exec_id: fill.exec_id.clone(),
+        });
+    }
+    if !fill.quantity.is_positive() {
+        return Err(FillError::ZeroQuantity {
+            exec_id: fill.exec_id.clone(),
+        });
+    }
+    if fill.quantity > order.leaves_quantity() {
+        return Err(FillError::Overfill {
+            order_id: order.id.clone(),
+            exec_id: fill.exec_id.clone(),
+            quantity: order.quantity,
+            filled: order.filled_quantity,
+            fill_quantity: fill.quantity,
+        });
+    }
+
+    let previous_value = order.avg_fill_price.unwrap_or_default() * order.filled_quantity.value();
+    order.filled_quantity += fill.quantity;
+    order.avg_fill_price = Some((previous_value + fill.notional()) / order.filled_quantity.value());
+    Ok(())
+}
@@ -26,7 +26,10 @@ THE SOFTWARE.
    Date: 29/5/24
 ******************************************************************************/
 
-use crate::{ChildOrder, Futures, Options, Order, ParentOrder, Spot, Swap, CFD};
+use crate::{
+    BracketOrder, ChildOrder, ChildState, ChildTca, Fill, Futures, OcoGroup, OcoMember, Options,
+    Order, OrderAmendment, ParentOrder, ParentOrderState, Spot, Swap, TcaReport, CFD,
+};
 use serde::Serialize;
 use std::fmt::{Formatter, Result as FmtResult};
 
@@ -71,3 +74,12 @@ impl_fmt!(Options);
 impl_fmt!(Swap);
 impl_fmt!(ParentOrder);
 impl_fmt!(ChildOrder);
+impl_fmt!(Fill);
+impl_fmt!(OrderAmendment);
+impl_fmt!(OcoMember);
+impl_fmt!(OcoGroup);
+impl_fmt!(BracketOrder);
+impl_fmt!(ChildState);
+impl_fmt!(ParentOrderState);
+impl_fmt!(TcaReport);
+impl_fmt!(ChildTca);
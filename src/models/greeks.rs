@@ -0,0 +1,157 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Black-Scholes greeks of an option contract (no dividends).
+//!
+//! ```text
+//! d1 = (ln(S / K) + (r + sigma^2 / 2) * T) / (sigma * sqrt(T))
+//! d2 = d1 - sigma * sqrt(T)
+//! ```
+
+use super::decimal::decimal_to_f64;
+use super::orders::{OptionType, Options};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, SQRT_2};
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+/// Milliseconds in a 365-day year, used to convert expiries to year fractions
+const MILLIS_PER_YEAR: f64 = 365.0 * MILLIS_PER_DAY as f64;
+
+/// Sensitivities of one option contract on one unit of the underlying.
+///
+/// `vega` and `rho` are per 1.0 change of volatility and rate (divide by 100 for a
+/// percentage point); `theta` is the value change per year of calendar time.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub rho: f64,
+}
+
+impl Options {
+    /// Greeks at `now_epoch_days` (days since the UNIX epoch). `expiry_date` is read as UNIX
+    /// milliseconds, like order timestamps.
+    pub fn greeks(&self, spot: f64, vol: f64, rate: f64, now_epoch_days: u64) -> Greeks {
+        self.greeks_at(
+            spot,
+            vol,
+            rate,
+            now_epoch_days.saturating_mul(MILLIS_PER_DAY),
+        )
+    }
+
+    /// Greeks at `now_ms` (UNIX milliseconds).
+    ///
+    /// Once expired every greek is zero except delta, which is the intrinsic 1 / -1 / 0.
+    /// Without volatility the option behaves like its intrinsic forward value: delta, theta
+    /// and rho of an in-the-money option are those of the forward, gamma and vega are zero.
+    pub fn greeks_at(&self, spot: f64, vol: f64, rate: f64, now_ms: u64) -> Greeks {
+        let strike = decimal_to_f64(self.strike_price);
+        let time = self.expiry_date.saturating_sub(now_ms) as f64 / MILLIS_PER_YEAR;
+        let call = matches!(self.option_type, OptionType::Call);
+
+        if time <= 0.0 {
+            let delta = match (call, spot > strike, spot < strike) {
+                (true, true, _) => 1.0,
+                (false, _, true) => -1.0,
+                _ => 0.0,
+            };
+            return Greeks {
+                delta,
+                ..Greeks::default()
+            };
+        }
+
+        let discounted_strike = strike * (-rate * time).exp();
+        if vol <= 0.0 {
+            return match (call, spot > discounted_strike, spot < discounted_strike) {
+                (true, true, _) => Greeks {
+                    delta: 1.0,
+                    theta: -rate * discounted_strike,
+                    rho: time * discounted_strike,
+                    ..Greeks::default()
+                },
+                (false, _, true) => Greeks {
+                    delta: -1.0,
+                    theta: rate * discounted_strike,
+                    rho: -time * discounted_strike,
+                    ..Greeks::default()
+                },
+                _ => Greeks::default(),
+            };
+        }
+
+        let sqrt_time = time.sqrt();
+        let d1 = ((spot / strike).ln() + (rate + vol * vol / 2.0) * time) / (vol * sqrt_time);
+        let d2 = d1 - vol * sqrt_time;
+        let pdf_d1 = norm_pdf(d1);
+        let gamma = pdf_d1 / (spot * vol * sqrt_time);
+        let vega = spot * pdf_d1 * sqrt_time;
+        let decay = -spot * pdf_d1 * vol / (2.0 * sqrt_time);
+        if call {
+            Greeks {
+                delta: norm_cdf(d1),
+                gamma,
+                vega,
+                theta: decay - rate * discounted_strike * norm_cdf(d2),
+                rho: time * discounted_strike * norm_cdf(d2),
+            }
+        } else {
+            Greeks {
+                delta: norm_cdf(d1) - 1.0,
+                gamma,
+                vega,
+                theta: decay + rate * discounted_strike * norm_cdf(-d2),
+                rho: -time * discounted_strike * norm_cdf(-d2),
+            }
+        }
+    }
+}
+
+/// Standard normal cumulative distribution function
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / SQRT_2))
+}
+
+/// Standard normal probability density function
+fn norm_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// Error function, Abramowitz and Stegun formula 7.1.26 (maximum error 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    sign * (1.0 - poly * (-x * x).exp())
+}
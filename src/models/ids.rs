@@ -0,0 +1,132 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Order id and nonce generation.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Source of unique order ids.
+///
+/// The default generator returns UUIDv7 strings, which sort by creation time. Clones share
+/// the same source, so a generator handed to several strategies never repeats an id. Tests
+/// inject [`IdGenerator::sequential`] or [`IdGenerator::from_fn`] for predictable ids.
+#[derive(Clone)]
+pub struct IdGenerator {
+    source: Arc<dyn Fn() -> String + Send + Sync>,
+}
+
+impl IdGenerator {
+    pub fn uuid_v7() -> Self {
+        Self::from_fn(|| Uuid::now_v7().to_string())
+    }
+
+    /// Ids `<prefix>-1`, `<prefix>-2`, ... in call order
+    pub fn sequential(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let counter = AtomicU64::new(0);
+        Self::from_fn(move || format!("{}-{}", prefix, counter.fetch_add(1, Ordering::Relaxed) + 1))
+    }
+
+    pub fn from_fn(source: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        IdGenerator {
+            source: Arc::new(source),
+        }
+    }
+
+    pub fn next_id(&self) -> String {
+        (self.source)()
+    }
+}
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self::uuid_v7()
+    }
+}
+
+impl fmt::Debug for IdGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IdGenerator")
+    }
+}
+
+/// Strictly increasing values for the `nonce` field.
+///
+/// Clones share the same sequence. A clock-driven source follows the wall clock in
+/// microseconds but never repeats or goes backwards, even when called faster than the clock
+/// ticks or when the clock is set back.
+#[derive(Clone, Debug)]
+pub struct NonceSource {
+    next: Arc<AtomicU64>,
+    clock: bool,
+}
+
+impl NonceSource {
+    pub fn from_clock() -> Self {
+        NonceSource {
+            next: Arc::new(AtomicU64::new(0)),
+            clock: true,
+        }
+    }
+
+    /// Counts up from `start`, ignoring the clock
+    pub fn starting_at(start: u64) -> Self {
+        NonceSource {
+            next: Arc::new(AtomicU64::new(start)),
+            clock: false,
+        }
+    }
+
+    pub fn next_nonce(&self) -> u64 {
+        let floor = if self.clock { now_micros() } else { 0 };
+        let previous = self
+            .next
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| {
+                Some(next.max(floor).saturating_add(1))
+            })
+            .unwrap_or_else(|next| next);
+        previous.max(floor)
+    }
+}
+
+impl Default for NonceSource {
+    fn default() -> Self {
+        Self::from_clock()
+    }
+}
+
+fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_micros() as u64)
+        .unwrap_or(0)
+}
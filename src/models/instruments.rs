@@ -0,0 +1,221 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use super::decimal::Decimal;
+use super::orders::{Order, OrderType, Side, Validate, ValidationError};
+use super::quantity::Quantity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Trading rules of a single instrument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentSpec {
+    /// Smallest price increment
+    pub tick_size: Decimal,
+    /// Quantities must be whole multiples of the lot size
+    pub lot_size: Quantity,
+    /// Smallest quantity the venue accepts
+    pub min_qty: Quantity,
+    /// Decimal places prices are quoted with
+    pub price_precision: u32,
+    /// Units of the underlying per contract; 1 for cash instruments
+    pub contract_multiplier: Decimal,
+}
+
+impl InstrumentSpec {
+    pub fn new(tick_size: Decimal, lot_size: Quantity) -> Self {
+        InstrumentSpec {
+            tick_size,
+            lot_size,
+            min_qty: lot_size,
+            price_precision: tick_size.normalize().scale(),
+            contract_multiplier: Decimal::ONE,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (field, value) in [
+            ("Tick size", self.tick_size),
+            ("Lot size", self.lot_size.value()),
+            ("Contract multiplier", self.contract_multiplier),
+        ] {
+            if value <= Decimal::ZERO {
+                return Err(ValidationError::NonPositive { field, value });
+            }
+        }
+        if self.min_qty.value() < Decimal::ZERO {
+            return Err(ValidationError::Negative {
+                field: "Minimum quantity",
+                value: self.min_qty.value(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rounds onto the tick grid on the passive side: down for a buy, up for a sell, so the
+    /// rounded price never becomes more aggressive than the one asked for.
+    pub fn round_price(&self, price: Decimal, side: &Side) -> Decimal {
+        let ticks = price / self.tick_size;
+        let ticks = match side {
+            Side::Buy => ticks.floor(),
+            Side::Sell => ticks.ceil(),
+        };
+        (ticks * self.tick_size).round_dp(self.price_precision)
+    }
+
+    /// Rounds down to a whole number of lots; below `min_qty` the result is zero.
+    pub fn round_qty(&self, quantity: Quantity) -> Quantity {
+        let rounded = quantity.round_down_to_step(self.lot_size);
+        if rounded < self.min_qty {
+            Quantity::ZERO
+        } else {
+            rounded
+        }
+    }
+
+    pub fn is_on_tick(&self, price: Decimal) -> bool {
+        (price % self.tick_size).is_zero()
+    }
+
+    /// Rejects prices off the tick grid and quantities that are off-lot or below the minimum.
+    pub fn check(&self, order: &Order) -> Result<(), ValidationError> {
+        let prices = match &order.order_type {
+            OrderType::Stop { stop_price } => vec![("Stop price", *stop_price)],
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => vec![("Stop price", *stop_price), ("Limit price", *limit_price)],
+            _ => Vec::new(),
+        };
+        for (field, price) in order
+            .price
+            .map(|price| ("Price", price))
+            .into_iter()
+            .chain(prices)
+        {
+            if !self.is_on_tick(price) {
+                return Err(ValidationError::OffTick {
+                    field,
+                    value: price,
+                    tick_size: self.tick_size,
+                });
+            }
+        }
+        if !order.quantity.is_multiple_of(self.lot_size) {
+            return Err(ValidationError::OffLot {
+                quantity: order.quantity,
+                lot_size: self.lot_size,
+            });
+        }
+        if order.quantity < self.min_qty {
+            return Err(ValidationError::BelowMinimum {
+                quantity: order.quantity,
+                min_qty: self.min_qty,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Instrument specs by symbol.
+///
+/// Symbols without a spec are not constrained: rounding leaves their values untouched and
+/// validation accepts them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentRegistry {
+    specs: HashMap<String, InstrumentSpec>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the spec of `symbol`.
+    pub fn register(
+        &mut self,
+        symbol: impl Into<String>,
+        spec: InstrumentSpec,
+    ) -> Result<(), ValidationError> {
+        spec.validate()?;
+        self.specs.insert(symbol.into(), spec);
+        Ok(())
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&InstrumentSpec> {
+        self.specs.get(symbol)
+    }
+
+    /// See [`InstrumentSpec::round_price`].
+    pub fn round_price(&self, symbol: &str, price: Decimal, side: &Side) -> Decimal {
+        self.get(symbol)
+            .map_or(price, |spec| spec.round_price(price, side))
+    }
+
+    /// See [`InstrumentSpec::round_qty`].
+    pub fn round_qty(&self, symbol: &str, quantity: Quantity) -> Quantity {
+        self.get(symbol)
+            .map_or(quantity, |spec| spec.round_qty(quantity))
+    }
+
+    /// Step child quantities of `order` are rounded to: the instrument lot size if known,
+    /// otherwise the order's own [`Order::lot_step`].
+    pub fn lot_step(&self, order: &Order) -> Quantity {
+        self.get(&order.symbol)
+            .map_or_else(|| order.lot_step(), |spec| spec.lot_size)
+    }
+
+    /// Snaps a child order onto the instrument grid: price rounded passively for its side,
+    /// quantity rounded down to whole lots.
+    pub fn round_order(&self, order: &mut Order) {
+        let Some(spec) = self.get(&order.symbol) else {
+            return;
+        };
+        order.price = order
+            .price
+            .map(|price| spec.round_price(price, &order.side));
+        if let OrderType::StopLimit { limit_price, .. } = &mut order.order_type {
+            if let Some(price) = order.price {
+                *limit_price = price;
+            }
+        }
+        order.quantity = spec.round_qty(order.quantity);
+    }
+}
+
+impl Order {
+    /// [`Validate::validate`], plus the tick and lot
+    /// rules of the order's instrument when `registry` knows it.
+    pub fn validate_with(&self, registry: &InstrumentRegistry) -> Result<(), ValidationError> {
+        self.validate()?;
+        match registry.get(&self.symbol) {
+            Some(spec) => spec.check(self),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use crate::models::orders::Side;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Serializes a `SystemTime` as milliseconds since the Unix epoch
+pub mod epoch_millis {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn to_millis(time: &SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn from_millis(millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(to_millis(time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        u64::deserialize(deserializer).map(from_millis)
+    }
+
+    /// Same conversion for optional timestamps
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            time: &Option<SystemTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            time.as_ref().map(to_millis).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<SystemTime>, D::Error> {
+            Ok(Option::<u64>::deserialize(deserializer)?.map(from_millis))
+        }
+    }
+}
+
+/// Market data types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MarketData {
+    Trade(Trade),
+    OrderBook(OrderBook),
+    Ticker(Ticker),
+    Candle(Candle),
+}
+
+/// A market data update for one symbol, as carried on the market data topic.
+///
+/// The JSON form names the symbol and holds the update under its kind, with timestamps in
+/// milliseconds since the Unix epoch and the side as `"Buy"` or `"Sell"`:
+///
+/// ```json
+/// {"symbol": "AAPL", "data": {"Trade": {"timestamp": 1716591600250, "price": 189.5, "size": 100.0, "side": "Buy"}}}
+/// {"symbol": "AAPL", "data": {"OrderBook": {"bids": [[189.4, 300.0]], "asks": [[189.6, 200.0]]}}}
+/// {"symbol": "AAPL", "data": {"Ticker": {"timestamp": 1716591600250, "bid": 189.4, "ask": 189.6, "last": 189.5}}}
+/// {"symbol": "AAPL", "data": {"Candle": {"timestamp": 1716591600000, "open": 189.0, "high": 190.0, "low": 188.5, "close": 189.5, "volume": 12000.0}}}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketDataUpdate {
+    pub symbol: String,
+    pub data: MarketData,
+}
+
+impl MarketDataUpdate {
+    pub fn new(symbol: &str, data: MarketData) -> Self {
+        MarketDataUpdate {
+            symbol: symbol.to_string(),
+            data,
+        }
+    }
+}
+
+/// Trade data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    #[serde(with = "epoch_millis")]
+    pub timestamp: SystemTime,
+    pub price: f64,
+    pub size: f64,
+    pub side: Side,
+}
+
+/// Order book data
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub bids: Vec<(f64, f64)>, // (price, size)
+    pub asks: Vec<(f64, f64)>, // (price, size)
+}
+
+/// Ticker data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticker {
+    #[serde(with = "epoch_millis")]
+    pub timestamp: SystemTime,
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64,
+}
+
+/// Candle data
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Candle {
+    #[serde(with = "epoch_millis")]
+    pub timestamp: SystemTime,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl OrderBook {
+    /// Midpoint of the best bid and ask
+    pub fn mid_price(&self) -> Option<f64> {
+        let best_bid = self.bids.iter().map(|&(price, _)| price).reduce(f64::max)?;
+        let best_ask = self.asks.iter().map(|&(price, _)| price).reduce(f64::min)?;
+        Some((best_bid + best_ask) / 2.0)
+    }
+}
+
+impl MarketData {
+    /// Representative price of the update: trade price, last ticker price, candle close
+    /// or order book midpoint
+    pub fn price(&self) -> Option<f64> {
+        match self {
+            MarketData::Trade(trade) => Some(trade.price),
+            MarketData::Ticker(ticker) => Some(ticker.last),
+            MarketData::Candle(candle) => Some(candle.close),
+            MarketData::OrderBook(order_book) => order_book.mid_price(),
+        }
+    }
+}
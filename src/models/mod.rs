@@ -26,13 +26,52 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 // Declaring submodules within the models module
+pub mod amendments;
+pub mod bracket;
+pub mod builder;
+pub mod calendar;
 pub mod child_orders;
+#[cfg(feature = "csv")]
+pub mod csv_orders;
+pub mod decimal;
+pub mod fills;
 pub mod format;
+pub mod greeks;
+pub mod ids;
+pub mod instruments;
+pub mod market_data;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod oco;
 pub mod orders;
 pub mod parent_orders;
+pub mod parent_state;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod quantity;
+pub mod schema;
+pub mod timestamp;
 
 // Re-exporting submodules to make them accessible from the models module
+pub use amendments::*;
+pub use bracket::*;
+pub use builder::*;
+pub use calendar::*;
 pub use child_orders::*;
+#[cfg(feature = "csv")]
+pub use csv_orders::{CsvError, CsvImport, CsvRowError};
+pub use decimal::*;
+pub use fills::*;
+#[allow(unused_imports)]
 pub use format::*;
+pub use greeks::Greeks;
+pub use ids::{IdGenerator, NonceSource};
+pub use instruments::{InstrumentRegistry, InstrumentSpec};
+pub use market_data::{MarketData, MarketDataUpdate, OrderBook, Ticker, Trade};
+pub use oco::*;
 pub use orders::*;
 pub use parent_orders::*;
+pub use parent_state::{ChildState, ParentOrderState, ParentStateError};
+pub use quantity::Quantity;
+pub use schema::SCHEMA_VERSION;
+pub use timestamp::Timestamp;
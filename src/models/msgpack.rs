@@ -0,0 +1,73 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! MessagePack encoding of orders.
+//!
+//! Fields are written by name, as in JSON, so flattened orders, schema versioning and
+//! unknown-field tolerance behave the same in both encodings.
+
+use super::child_orders::ChildOrder;
+use super::orders::Order;
+use super::parent_orders::ParentOrder;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MsgPackError {
+    #[error(transparent)]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+pub fn to_msgpack<T: Serialize>(value: &T) -> Result<Vec<u8>, MsgPackError> {
+    Ok(rmp_serde::to_vec_named(value)?)
+}
+
+pub fn from_msgpack<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, MsgPackError> {
+    Ok(rmp_serde::from_slice(bytes)?)
+}
+
+macro_rules! impl_msgpack {
+    ($T:ty) => {
+        impl $T {
+            pub fn to_msgpack(&self) -> Result<Vec<u8>, MsgPackError> {
+                to_msgpack(self)
+            }
+
+            pub fn from_msgpack(bytes: &[u8]) -> Result<Self, MsgPackError> {
+                from_msgpack(bytes)
+            }
+        }
+    };
+}
+
+impl_msgpack!(Order);
+impl_msgpack!(ParentOrder);
+impl_msgpack!(ChildOrder);
@@ -0,0 +1,256 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use super::decimal::Decimal;
+use super::fills::{apply_fill, Fill, FillError};
+use super::orders::{Order, OrderStatus, OrderType, Side, TimeInForce, ValidationError};
+use super::quantity::Quantity;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// An order taking part in an OCO group, with its current status.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OcoMember {
+    pub order: Order,
+    pub status: OrderStatus,
+}
+
+/// One-cancels-other group: the first member to be filled, even partially, cancels all the
+/// others. A member cancelled on its own leaves the rest of the group working.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OcoGroup {
+    pub group_id: String,
+    pub members: Vec<OcoMember>,
+    /// Id of the member whose fill triggered the group, if any.
+    pub triggered_by: Option<String>,
+}
+
+/// Errors raised when an event cannot be applied to an OCO group.
+#[derive(Debug, Error, PartialEq)]
+pub enum OcoError {
+    #[error("OCO group {group_id} needs at least two orders")]
+    TooFewMembers { group_id: String },
+    #[error("order {order_id} appears more than once in OCO group {group_id}")]
+    DuplicateMember { group_id: String, order_id: String },
+    #[error("order {order_id} is not a member of OCO group {group_id}")]
+    UnknownOrder { group_id: String, order_id: String },
+    #[error("fill on order {order_id} rejected: OCO group {group_id} was already triggered by {triggered_by}")]
+    AlreadyTriggered {
+        group_id: String,
+        order_id: String,
+        triggered_by: String,
+    },
+    #[error("order {order_id} is already {status:?}")]
+    MemberClosed {
+        order_id: String,
+        status: OrderStatus,
+    },
+    #[error(transparent)]
+    Fill(#[from] FillError),
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+impl OcoGroup {
+    /// Links `orders` into a group. Every order starts as `New`.
+    pub fn new(group_id: String, orders: Vec<Order>) -> Result<Self, OcoError> {
+        if orders.len() < 2 {
+            return Err(OcoError::TooFewMembers { group_id });
+        }
+        for (i, order) in orders.iter().enumerate() {
+            if orders[..i].iter().any(|other| other.id == order.id) {
+                return Err(OcoError::DuplicateMember {
+                    group_id,
+                    order_id: order.id.clone(),
+                });
+            }
+        }
+        let members = orders
+            .into_iter()
+            .map(|order| OcoMember {
+                order,
+                status: OrderStatus::New,
+            })
+            .collect();
+        Ok(OcoGroup {
+            group_id,
+            members,
+            triggered_by: None,
+        })
+    }
+
+    /// Protective exits for a position opened by `entry` at `entry_price`: a take-profit
+    /// Limit `tp_pct` away from the entry and a Stop `stop_pct` away on the losing side,
+    /// both closing `quantity`. Percentages are fractions, so 0.02 means 2%.
+    pub fn from_position(
+        entry: &Order,
+        entry_price: Decimal,
+        quantity: Quantity,
+        stop_pct: Decimal,
+        tp_pct: Decimal,
+    ) -> Result<Self, OcoError> {
+        for (field, value) in [
+            ("Stop percentage", stop_pct),
+            ("Take-profit percentage", tp_pct),
+        ] {
+            if value <= Decimal::ZERO {
+                return Err(ValidationError::NonPositive { field, value }.into());
+            }
+        }
+        let exit_side = entry.side.opposite();
+        let (take_profit, stop) = match exit_side {
+            Side::Sell => (
+                entry_price * (Decimal::ONE + tp_pct),
+                entry_price * (Decimal::ONE - stop_pct),
+            ),
+            Side::Buy => (
+                entry_price * (Decimal::ONE - tp_pct),
+                entry_price * (Decimal::ONE + stop_pct),
+            ),
+        };
+        let exit = |suffix: &str| {
+            let mut builder = Order::builder()
+                .id(format!("{}-{}", entry.id, suffix))
+                .quantity(quantity)
+                .product_type(entry.product_type.clone())
+                .timestamp(entry.timestamp)
+                .symbol(entry.symbol.clone())
+                .side(exit_side.clone())
+                .currency(entry.currency.clone())
+                .timeinforce(TimeInForce::GTC);
+            if let Some(exchange) = &entry.exchange {
+                builder = builder.exchange(exchange.clone());
+            }
+            if let Some(account_id) = &entry.account_id {
+                builder = builder.account_id(account_id.clone());
+            }
+            if let Some(portfolio_id) = &entry.portfolio_id {
+                builder = builder.portfolio_id(portfolio_id.clone());
+            }
+            builder
+        };
+        let take_profit = exit("tp")
+            .order_type(OrderType::Limit)
+            .price(take_profit)
+            .build()?;
+        let stop = exit("sl")
+            .order_type(OrderType::Stop { stop_price: stop })
+            .build()?;
+        OcoGroup::new(format!("{}-oco", entry.id), vec![take_profit, stop])
+    }
+
+    pub fn member(&self, order_id: &str) -> Option<&OcoMember> {
+        self.members
+            .iter()
+            .find(|member| member.order.id == order_id)
+    }
+
+    /// Orders of the group that can still be filled.
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.members
+            .iter()
+            .filter(|member| member.status.is_open())
+            .map(|member| &member.order)
+    }
+
+    /// Returns true while no member has been filled and at least one is still working.
+    pub fn is_active(&self) -> bool {
+        self.triggered_by.is_none() && self.open_orders().next().is_some()
+    }
+
+    /// Returns true once no member can be filled any more.
+    pub fn is_done(&self) -> bool {
+        self.open_orders().next().is_none()
+    }
+
+    fn position(&self, order_id: &str) -> Result<usize, OcoError> {
+        self.members
+            .iter()
+            .position(|member| member.order.id == order_id)
+            .ok_or_else(|| OcoError::UnknownOrder {
+                group_id: self.group_id.clone(),
+                order_id: order_id.to_string(),
+            })
+    }
+
+    /// Applies a fill to its member. The first fill in the group cancels every other open
+    /// member and returns their ids; a fill on any other member after that is a double-fill
+    /// race and is rejected, leaving the group untouched.
+    pub fn on_fill(&mut self, fill: &Fill) -> Result<Vec<String>, OcoError> {
+        let index = self.position(&fill.order_id)?;
+        if let Some(triggered_by) = &self.triggered_by {
+            if *triggered_by != fill.order_id {
+                return Err(OcoError::AlreadyTriggered {
+                    group_id: self.group_id.clone(),
+                    order_id: fill.order_id.clone(),
+                    triggered_by: triggered_by.clone(),
+                });
+            }
+        }
+        let member = &mut self.members[index];
+        if !member.status.is_open() {
+            return Err(OcoError::MemberClosed {
+                order_id: fill.order_id.clone(),
+                status: member.status,
+            });
+        }
+        apply_fill(&mut member.order, fill)?;
+        member.status = if member.order.is_filled() {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+
+        if self.triggered_by.is_some() {
+            return Ok(Vec::new());
+        }
+        self.triggered_by = Some(fill.order_id.clone());
+        let mut cancelled = Vec::new();
+        for other in self.members.iter_mut() {
+            if other.order.id != fill.order_id && other.status.is_open() {
+                other.status = OrderStatus::Cancelled;
+                cancelled.push(other.order.id.clone());
+            }
+        }
+        Ok(cancelled)
+    }
+
+    /// Cancels a single member on its own; the rest of the group keeps working.
+    pub fn on_cancel(&mut self, order_id: &str) -> Result<(), OcoError> {
+        let index = self.position(order_id)?;
+        let member = &mut self.members[index];
+        if !member.status.is_open() {
+            return Err(OcoError::MemberClosed {
+                order_id: order_id.to_string(),
+                status: member.status,
+            });
+        }
+        member.status = OrderStatus::Cancelled;
+        Ok(())
+    }
+}
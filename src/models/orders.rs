@@ -25,7 +25,12 @@ THE SOFTWARE.
    Email: jb@taunais.com
    Date: 25/5/24
 ******************************************************************************/
+use super::decimal::{decimal_from_f64, Decimal};
+use super::quantity::Quantity;
+use super::schema::{legacy_schema_version, SCHEMA_VERSION};
+use super::timestamp::Timestamp;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProductType {
@@ -36,10 +41,124 @@ pub enum ProductType {
     CFD,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum OrderType {
     Market,
     Limit,
+    /// Becomes a market order once the price trades through `stop_price`.
+    Stop {
+        stop_price: Decimal,
+    },
+    /// Becomes a limit order at `limit_price` once the price trades through `stop_price`.
+    StopLimit {
+        stop_price: Decimal,
+        limit_price: Decimal,
+    },
+    /// Stop whose trigger follows the market at a fixed distance, see `TrailingSpec`.
+    TrailingStop(TrailingSpec),
+}
+
+impl OrderType {
+    /// Trigger price of stop orders, `None` for orders that rest immediately.
+    pub fn trigger_price(&self) -> Option<Decimal> {
+        match self {
+            OrderType::Stop { stop_price } | OrderType::StopLimit { stop_price, .. } => {
+                Some(*stop_price)
+            }
+            OrderType::Market | OrderType::Limit | OrderType::TrailingStop(_) => None,
+        }
+    }
+
+    /// Returns true for order types that execute at any price once active.
+    pub fn is_market(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Market | OrderType::Stop { .. } | OrderType::TrailingStop(_)
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            OrderType::Stop { stop_price } => {
+                if *stop_price <= Decimal::ZERO {
+                    return Err(ValidationError::NonPositive {
+                        field: "Stop price",
+                        value: *stop_price,
+                    });
+                }
+            }
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => {
+                if *stop_price <= Decimal::ZERO {
+                    return Err(ValidationError::NonPositive {
+                        field: "Stop price",
+                        value: *stop_price,
+                    });
+                }
+                if *limit_price <= Decimal::ZERO {
+                    return Err(ValidationError::NonPositive {
+                        field: "Limit price",
+                        value: *limit_price,
+                    });
+                }
+            }
+            OrderType::TrailingStop(spec) => spec.validate()?,
+            OrderType::Market | OrderType::Limit => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OffsetType {
+    Absolute,
+    Percent,
+}
+
+/// Distance a trailing stop keeps from the best price seen since it was placed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrailingSpec {
+    pub offset: Decimal,
+    pub offset_type: OffsetType,
+}
+
+impl TrailingSpec {
+    pub fn new(offset: Decimal, offset_type: OffsetType) -> Self {
+        TrailingSpec {
+            offset,
+            offset_type,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.offset <= Decimal::ZERO {
+            return Err(ValidationError::NonPositive {
+                field: "Trailing offset",
+                value: self.offset,
+            });
+        }
+        if self.offset_type == OffsetType::Percent && self.offset >= Decimal::ONE_HUNDRED {
+            return Err(ValidationError::InconsistentFields(
+                "Trailing percent offset must be below 100".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Stop level for a best price of `reference`. `side` is the side of the stop
+    /// order: a Sell stop protects a long and trails below, a Buy stop trails above.
+    pub fn stop_from(&self, reference: Decimal, side: &Side) -> Decimal {
+        let distance = match self.offset_type {
+            OffsetType::Absolute => self.offset,
+            OffsetType::Percent => reference * self.offset / Decimal::ONE_HUNDRED,
+        };
+        match side {
+            Side::Sell => reference - distance,
+            Side::Buy => reference + distance,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -48,17 +167,48 @@ pub enum Side {
     Sell,
 }
 
+impl Side {
+    /// The side that closes a position opened on this side.
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+/// Lifecycle state of an order once it has been sent.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    /// A cancel was requested from the venue; the order may still fill until it is confirmed.
+    PendingCancel,
+    Filled,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// Returns true if the order can still be filled or cancelled.
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::PendingCancel
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OptionType {
     Call,
     Put,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TimeInForce {
     GTC, // Good-Til-Canceled
     IOC, // Immediate-Or-Cancel
-    GTD, // Good-Til-Date
+    GTD, // Good-Til-Date, expires at the order's `expiry_date`
     FOK, // Fill-Or-Kill
 }
 
@@ -83,9 +233,26 @@ pub struct Spot {
     pub asset_class: Option<AssetClass>,
 }
 
+impl Spot {
+    /// Validates the spot details against the product type of the order carrying them.
+    pub fn validate_for(&self, product_type: &ProductType) -> Result<(), ValidationError> {
+        if !matches!(product_type, ProductType::Spot) {
+            return Err(ValidationError::InconsistentFields(
+                "Spot details require a Spot product type".to_string(),
+            ));
+        }
+        self.validate()
+    }
+}
+
 impl Validate for Spot {
-    fn validate(&self) -> Result<(), String> {
-        // TODO: Implement validation logic
+    fn validate(&self) -> Result<(), ValidationError> {
+        // Derivatives are described by the Futures, Options, Swap and CFD structs instead
+        if let Some(AssetClass::Derivative) = self.asset_class {
+            return Err(ValidationError::InconsistentFields(
+                "Spot asset class cannot be Derivative".to_string(),
+            ));
+        }
         Ok(())
     }
 }
@@ -94,31 +261,43 @@ impl Validate for Spot {
 pub struct Futures {
     pub delivery_date: Option<u64>,
     pub contract_size: Option<f64>,
-    pub margin: Option<f64>,
-    pub commission: Option<f64>,
-    pub overnight_fee: Option<f64>,
+    pub margin: Option<Decimal>,
+    pub commission: Option<Decimal>,
+    pub overnight_fee: Option<Decimal>,
 }
 
 impl Futures {
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
         if let Some(contract_size) = self.contract_size {
             if contract_size <= 0.0 {
-                return Err("Contract size must be greater than zero".to_string());
+                return Err(ValidationError::NonPositive {
+                    field: "Contract size",
+                    value: decimal_from_f64(contract_size),
+                });
             }
         }
         if let Some(margin) = self.margin {
-            if margin < 0.0 {
-                return Err("Margin cannot be negative".to_string());
+            if margin < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Margin",
+                    value: margin,
+                });
             }
         }
         if let Some(commission) = self.commission {
-            if commission < 0.0 {
-                return Err("Commission cannot be negative".to_string());
+            if commission < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Commission",
+                    value: commission,
+                });
             }
         }
         if let Some(overnight_fee) = self.overnight_fee {
-            if overnight_fee < 0.0 {
-                return Err("Overnight fee cannot be negative".to_string());
+            if overnight_fee < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Overnight fee",
+                    value: overnight_fee,
+                });
             }
         }
         Ok(())
@@ -127,15 +306,18 @@ impl Futures {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Options {
-    pub strike_price: f64,
+    pub strike_price: Decimal,
     pub option_type: OptionType,
     pub expiry_date: u64,
 }
 
 impl Options {
-    pub fn validate(&self) -> Result<(), String> {
-        if self.strike_price <= 0.0 {
-            return Err("Strike price must be greater than zero".to_string());
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.strike_price <= Decimal::ZERO {
+            return Err(ValidationError::NonPositive {
+                field: "Strike price",
+                value: self.strike_price,
+            });
         }
         Ok(())
     }
@@ -145,19 +327,25 @@ impl Options {
 pub struct Swap {
     pub fixed_rate: f64,
     pub floating_rate_index: String,
-    pub notional_amount: f64,
+    pub notional_amount: Decimal,
 }
 
 impl Swap {
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
         if self.fixed_rate < 0.0 {
-            return Err("Fixed rate cannot be negative".to_string());
+            return Err(ValidationError::Negative {
+                field: "Fixed rate",
+                value: decimal_from_f64(self.fixed_rate),
+            });
         }
-        if self.notional_amount <= 0.0 {
-            return Err("Notional amount must be greater than zero".to_string());
+        if self.notional_amount <= Decimal::ZERO {
+            return Err(ValidationError::NonPositive {
+                field: "Notional amount",
+                value: self.notional_amount,
+            });
         }
         if self.floating_rate_index.is_empty() {
-            return Err("Floating rate index cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Floating rate index"));
         }
         Ok(())
     }
@@ -166,33 +354,45 @@ impl Swap {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CFD {
     pub leverage: Option<u32>,
-    pub margin: Option<f64>,
-    pub commission: Option<f64>,
-    pub overnight_fee: Option<f64>,
-    pub dividend_adjustment: Option<f64>,
+    pub margin: Option<Decimal>,
+    pub commission: Option<Decimal>,
+    pub overnight_fee: Option<Decimal>,
+    pub dividend_adjustment: Option<Decimal>,
     pub contract_size: Option<f64>,
 }
 
 impl CFD {
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
         if let Some(leverage) = self.leverage {
             if leverage == 0 {
-                return Err("Leverage must be greater than zero".to_string());
+                return Err(ValidationError::NonPositive {
+                    field: "Leverage",
+                    value: Decimal::from(leverage),
+                });
             }
         }
         if let Some(margin) = self.margin {
-            if margin < 0.0 {
-                return Err("Margin cannot be negative".to_string());
+            if margin < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Margin",
+                    value: margin,
+                });
             }
         }
         if let Some(commission) = self.commission {
-            if commission < 0.0 {
-                return Err("Commission cannot be negative".to_string());
+            if commission < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Commission",
+                    value: commission,
+                });
             }
         }
         if let Some(overnight_fee) = self.overnight_fee {
-            if overnight_fee < 0.0 {
-                return Err("Overnight fee cannot be negative".to_string());
+            if overnight_fee < Decimal::ZERO {
+                return Err(ValidationError::Negative {
+                    field: "Overnight fee",
+                    value: overnight_fee,
+                });
             }
         }
         Ok(())
@@ -202,13 +402,16 @@ impl CFD {
 /// Common structure for orders.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Order {
+    /// Layout version of the writer; messages from before versioning read as 1
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub id: String,
-    pub quantity: u32,
+    pub quantity: Quantity,
     pub product_type: ProductType,
     pub order_type: OrderType,
-    pub price: Option<f64>,
-    pub timestamp: u64,
-    pub expiry_date: Option<u64>,
+    pub price: Option<Decimal>,
+    pub timestamp: Timestamp,
+    pub expiry_date: Option<Timestamp>,
     pub symbol: String,
     pub side: Side,
     pub currency: String,
@@ -227,19 +430,42 @@ pub struct Order {
     // CFDs specific fields
     pub cfd_opt: Option<CFD>,
 
-    pub notional: Option<f64>,
+    pub notional: Option<Decimal>,
     pub nonce: Option<u64>,
+
+    // Account context, inherited by child orders from their parent
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub portfolio_id: Option<String>,
+
+    // Execution progress, updated through `apply_fill`
+    #[serde(default)]
+    pub filled_quantity: Quantity,
+    #[serde(default)]
+    pub avg_fill_price: Option<Decimal>,
+
+    // Cancel/replace lineage, updated through `Order::amend`
+    #[serde(default)]
+    pub original_id: Option<String>,
+    #[serde(default)]
+    pub version: u32,
+
+    /// Lot size the quantity must be a multiple of, if the venue imposes one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity_step: Option<Quantity>,
 }
 
 impl Order {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: String,
-        quantity: u32,
+        quantity: Quantity,
         product_type: ProductType,
         order_type: OrderType,
-        price: Option<f64>,
-        timestamp: u64,
-        expiry_date: Option<u64>,
+        price: Option<Decimal>,
+        timestamp: Timestamp,
+        expiry_date: Option<Timestamp>,
         symbol: String,
         side: Side,
         currency: String,
@@ -249,10 +475,11 @@ impl Order {
         options_opt: Option<Options>,
         swap_opt: Option<Swap>,
         cfd_opt: Option<CFD>,
-        notional: Option<f64>,
+        notional: Option<Decimal>,
         nonce: Option<u64>,
     ) -> Self {
         Order {
+            schema_version: SCHEMA_VERSION,
             id,
             quantity,
             product_type,
@@ -271,31 +498,212 @@ impl Order {
             cfd_opt,
             notional,
             nonce,
+            account_id: None,
+            portfolio_id: None,
+            filled_quantity: Quantity::ZERO,
+            avg_fill_price: None,
+            original_id: None,
+            version: 0,
+            quantity_step: None,
+        }
+    }
+
+    /// Quantity still open on the order after the fills applied so far.
+    pub fn leaves_quantity(&self) -> Quantity {
+        self.quantity.saturating_sub(self.filled_quantity)
+    }
+
+    /// Increment child quantities are rounded to when splitting this order: the
+    /// `quantity_step` if set, otherwise the precision of the quantity itself.
+    pub fn lot_step(&self) -> Quantity {
+        self.quantity_step.unwrap_or_else(|| self.quantity.unit())
+    }
+
+    /// Returns true once the whole order quantity has been executed.
+    pub fn is_filled(&self) -> bool {
+        self.filled_quantity >= self.quantity
+    }
+
+    /// Returns true if a GTD order has reached its `expiry_date` at `now_ms`.
+    /// Orders with any other time in force never expire on their own.
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        match (&self.timeinforce, self.expiry_date) {
+            (Some(TimeInForce::GTD), Some(expiry_date)) => now_ms >= expiry_date.as_millis(),
+            _ => false,
         }
     }
 }
 
 pub trait Validate {
-    fn validate(&self) -> Result<(), String>;
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+/// Reasons an order, or one of its parts, fails validation.
+#[derive(Debug, Error, PartialEq)]
+pub enum ValidationError {
+    #[error("{0} cannot be empty")]
+    EmptyField(&'static str),
+    #[error("{field} must be greater than zero")]
+    NonPositive { field: &'static str, value: Decimal },
+    #[error("{field} cannot be negative")]
+    Negative { field: &'static str, value: Decimal },
+    #[error("{product} orders require {field}")]
+    MissingForProduct {
+        product: &'static str,
+        field: &'static str,
+    },
+    #[error("{0}")]
+    InconsistentFields(String),
+    #[error("{0} is required")]
+    MissingField(&'static str),
+    #[error("{field} {value} is not a multiple of the tick size {tick_size}")]
+    OffTick {
+        field: &'static str,
+        value: Decimal,
+        tick_size: Decimal,
+    },
+    #[error("Quantity {quantity} is not a multiple of the lot size {lot_size}")]
+    OffLot {
+        quantity: Quantity,
+        lot_size: Quantity,
+    },
+    #[error("Quantity {quantity} is below the minimum {min_qty}")]
+    BelowMinimum {
+        quantity: Quantity,
+        min_qty: Quantity,
+    },
+}
+
+impl Order {
+    /// Limit style orders need a positive price; market style orders must not carry one.
+    fn validate_price(&self) -> Result<(), ValidationError> {
+        match (&self.order_type, self.price) {
+            (OrderType::Limit, None) => Err(ValidationError::MissingForProduct {
+                product: "Limit",
+                field: "a price",
+            }),
+            (OrderType::StopLimit { .. }, None) => Err(ValidationError::MissingForProduct {
+                product: "Stop limit",
+                field: "a price",
+            }),
+            (OrderType::Limit, Some(price)) if price <= Decimal::ZERO => {
+                Err(ValidationError::NonPositive {
+                    field: "Price",
+                    value: price,
+                })
+            }
+            (OrderType::StopLimit { limit_price, .. }, Some(price)) if price != *limit_price => {
+                Err(ValidationError::InconsistentFields(
+                    "Price must match the stop limit price".to_string(),
+                ))
+            }
+            (order_type, Some(_)) if order_type.is_market() => {
+                Err(ValidationError::InconsistentFields(
+                    "Market and stop orders cannot have a price".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// At most one derivative struct may be attached, and it must match `product_type`.
+    fn validate_derivative_details(&self) -> Result<(), ValidationError> {
+        let attached = [
+            (self.futures_opt.is_some(), "futures"),
+            (self.options_opt.is_some(), "options"),
+            (self.swap_opt.is_some(), "swap"),
+            (self.cfd_opt.is_some(), "cfd"),
+        ];
+        let mut attached = attached
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name);
+        let detail = match attached.next() {
+            Some(detail) => detail,
+            None => return Ok(()),
+        };
+        if attached.next().is_some() {
+            return Err(ValidationError::InconsistentFields(
+                "Only one derivative detail struct may be set".to_string(),
+            ));
+        }
+        let expected = match self.product_type {
+            ProductType::Spot => {
+                return Err(ValidationError::InconsistentFields(
+                    "Spot orders cannot carry derivative details".to_string(),
+                ))
+            }
+            ProductType::Futures => "futures",
+            ProductType::Options => "options",
+            ProductType::Swap => "swap",
+            ProductType::CFD => "cfd",
+        };
+        if detail != expected {
+            return Err(ValidationError::InconsistentFields(format!(
+                "{:?} orders cannot carry {} details",
+                self.product_type, detail
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Validate for Order {
-    fn validate(&self) -> Result<(), String> {
+    fn validate(&self) -> Result<(), ValidationError> {
         if self.id.is_empty() {
-            return Err("ID cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("ID"));
         }
-        if self.quantity == 0 {
-            return Err("Quantity must be greater than zero".to_string());
+        if !self.quantity.is_positive() {
+            return Err(ValidationError::NonPositive {
+                field: "Quantity",
+                value: self.quantity.value(),
+            });
+        }
+        if let Some(step) = self.quantity_step {
+            if !step.is_positive() {
+                return Err(ValidationError::NonPositive {
+                    field: "Quantity step",
+                    value: step.value(),
+                });
+            }
+            if !self.quantity.is_multiple_of(step) {
+                return Err(ValidationError::InconsistentFields(format!(
+                    "Quantity {} is not a multiple of the quantity step {}",
+                    self.quantity, step
+                )));
+            }
         }
         if self.symbol.is_empty() {
-            return Err("Symbol cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Symbol"));
         }
         if self.currency.is_empty() {
-            return Err("Currency cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Currency"));
+        }
+        self.order_type.validate()?;
+        if self.timeinforce == Some(TimeInForce::GTD) {
+            match self.expiry_date {
+                None => {
+                    return Err(ValidationError::MissingForProduct {
+                        product: "GTD",
+                        field: "an expiry date",
+                    })
+                }
+                Some(expiry_date) if expiry_date <= self.timestamp => {
+                    return Err(ValidationError::InconsistentFields(
+                        "Expiry date must be after the order timestamp".to_string(),
+                    ))
+                }
+                Some(_) => {}
+            }
         }
+        self.validate_price()?;
+        self.validate_derivative_details()?;
         if let Some(notional) = self.notional {
-            if notional <= 0.0 {
-                return Err("Notional must be greater than zero".to_string());
+            if notional <= Decimal::ZERO {
+                return Err(ValidationError::NonPositive {
+                    field: "Notional",
+                    value: notional,
+                });
             }
         }
         if let Some(futures) = &self.futures_opt {
@@ -26,8 +26,12 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
+use super::child_orders::ChildOrder;
+use super::decimal::Decimal;
 use super::orders::{Futures, Options, Order, OrderType, ProductType, Side, Swap, TimeInForce};
-use crate::{Validate, CFD};
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use crate::{Validate, ValidationError, CFD};
 use serde::{Deserialize, Serialize};
 
 /// Structure representing a parent order.
@@ -39,14 +43,15 @@ pub struct ParentOrder {
 }
 
 impl ParentOrder {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: String,
-        quantity: u32,
+        quantity: Quantity,
         product_type: ProductType,
         order_type: OrderType,
-        price: Option<f64>,
-        timestamp: u64,
-        expiry_date: Option<u64>,
+        price: Option<Decimal>,
+        timestamp: Timestamp,
+        expiry_date: Option<Timestamp>,
         symbol: String,
         side: Side,
         currency: String,
@@ -56,7 +61,7 @@ impl ParentOrder {
         options_opt: Option<Options>,
         swap_opt: Option<Swap>,
         cfd_opt: Option<CFD>,
-        notional: Option<f64>,
+        notional: Option<Decimal>,
         nonce: Option<u64>,
         strategy_id: String,
     ) -> Self {
@@ -84,12 +89,57 @@ impl ParentOrder {
             strategy_id,
         }
     }
+
+    fn own_children<'a>(
+        &'a self,
+        children: &'a [ChildOrder],
+    ) -> impl Iterator<Item = &'a ChildOrder> + 'a {
+        children
+            .iter()
+            .filter(move |child| child.parent_id == self.order_common.id)
+    }
+
+    /// Total quantity filled across the children belonging to this parent.
+    /// Children with a different `parent_id` are ignored.
+    pub fn filled_quantity(&self, children: &[ChildOrder]) -> Quantity {
+        self.own_children(children)
+            .map(|child| child.order_common.filled_quantity)
+            .sum()
+    }
+
+    /// Parent quantity not yet executed by any child.
+    pub fn leaves_quantity(&self, children: &[ChildOrder]) -> Quantity {
+        self.order_common
+            .quantity
+            .saturating_sub(self.filled_quantity(children))
+    }
+
+    /// Volume weighted average fill price across all children, if any filled.
+    pub fn avg_fill_price(&self, children: &[ChildOrder]) -> Option<Decimal> {
+        let (value, quantity) = self
+            .own_children(children)
+            .filter_map(|child| {
+                let order = &child.order_common;
+                order
+                    .avg_fill_price
+                    .map(|price| (price * order.filled_quantity.value(), order.filled_quantity))
+            })
+            .fold((Decimal::ZERO, Quantity::ZERO), |(value, qty), (v, q)| {
+                (value + v, qty + q)
+            });
+        quantity.is_positive().then(|| value / quantity.value())
+    }
+
+    /// Returns true once the children have filled the whole parent quantity.
+    pub fn is_filled(&self, children: &[ChildOrder]) -> bool {
+        self.filled_quantity(children) >= self.order_common.quantity
+    }
 }
 
 impl Validate for ParentOrder {
-    fn validate(&self) -> Result<(), String> {
+    fn validate(&self) -> Result<(), ValidationError> {
         if self.strategy_id.is_empty() {
-            return Err("Strategy ID cannot be empty".to_string());
+            return Err(ValidationError::EmptyField("Strategy ID"));
         }
         self.order_common.validate()
     }
@@ -0,0 +1,271 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use super::child_orders::ChildOrder;
+use super::fills::{apply_fill, Fill, FillError};
+use super::orders::{OrderStatus, ValidationError};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A child order of a tracked parent, with its current status.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChildState {
+    pub child: ChildOrder,
+    pub status: OrderStatus,
+    /// When the child was sent on to be executed; `None` while it waits for its `insert_at`
+    #[serde(default)]
+    pub dispatched_at: Option<Timestamp>,
+}
+
+/// Lifecycle of a parent order once it has been split: which children are still working,
+/// how much has filled and whether the parent is done.
+///
+/// A cancelled child no longer counts towards the expected quantity beyond what it filled
+/// before the cancel, so the parent completes once every child is filled or cancelled.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ParentOrderState {
+    pub parent: ParentOrder,
+    pub children: Vec<ChildState>,
+}
+
+/// Errors raised when a parent state cannot be built or an event cannot be applied.
+#[derive(Debug, Error, PartialEq)]
+pub enum ParentStateError {
+    #[error(
+        "children of {parent_id} allocate {allocated}, more than the parent quantity {quantity}"
+    )]
+    OverAllocated {
+        parent_id: String,
+        allocated: Quantity,
+        quantity: Quantity,
+    },
+    #[error("child {order_id} appears more than once under parent {parent_id}")]
+    DuplicateChild { parent_id: String, order_id: String },
+    #[error("order {order_id} is not a child of {parent_id}")]
+    UnknownChild { parent_id: String, order_id: String },
+    #[error("child {order_id} is already {status:?}")]
+    ChildClosed {
+        order_id: String,
+        status: OrderStatus,
+    },
+    #[error(transparent)]
+    Fill(#[from] FillError),
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+impl ParentOrderState {
+    /// Tracks `children` of `parent`. Each child must belong to the parent and the children
+    /// together may not ask for more than the parent quantity. Children that already carry
+    /// fills start as `PartiallyFilled` or `Filled`, the others as `New`.
+    pub fn new(parent: ParentOrder, children: Vec<ChildOrder>) -> Result<Self, ParentStateError> {
+        let parent_id = &parent.order_common.id;
+        for (i, child) in children.iter().enumerate() {
+            child.validate_for_parent(&parent)?;
+            if children[..i]
+                .iter()
+                .any(|other| other.order_common.id == child.order_common.id)
+            {
+                return Err(ParentStateError::DuplicateChild {
+                    parent_id: parent_id.clone(),
+                    order_id: child.order_common.id.clone(),
+                });
+            }
+        }
+        let allocated: Quantity = children
+            .iter()
+            .map(|child| child.order_common.quantity)
+            .sum();
+        if allocated > parent.order_common.quantity {
+            return Err(ParentStateError::OverAllocated {
+                parent_id: parent_id.clone(),
+                allocated,
+                quantity: parent.order_common.quantity,
+            });
+        }
+        let children = children
+            .into_iter()
+            .map(|child| ChildState {
+                status: fill_status(&child),
+                child,
+                dispatched_at: None,
+            })
+            .collect();
+        Ok(ParentOrderState { parent, children })
+    }
+
+    pub fn child(&self, order_id: &str) -> Option<&ChildState> {
+        self.children
+            .iter()
+            .find(|state| state.child.order_common.id == order_id)
+    }
+
+    /// Children that can still be filled or cancelled.
+    pub fn outstanding_children(&self) -> impl Iterator<Item = &ChildOrder> {
+        self.children
+            .iter()
+            .filter(|state| state.status.is_open())
+            .map(|state| &state.child)
+    }
+
+    /// Outstanding children that have not been sent on yet.
+    pub fn undispatched_children(&self) -> impl Iterator<Item = &ChildOrder> {
+        self.children
+            .iter()
+            .filter(|state| state.status.is_open() && state.dispatched_at.is_none())
+            .map(|state| &state.child)
+    }
+
+    /// Total quantity filled across the children.
+    pub fn filled_quantity(&self) -> Quantity {
+        self.children
+            .iter()
+            .map(|state| state.child.order_common.filled_quantity)
+            .sum()
+    }
+
+    /// Quantity the children are expected to fill: their full size, or only what was
+    /// filled for children that have been cancelled.
+    pub fn expected_quantity(&self) -> Quantity {
+        self.children
+            .iter()
+            .map(|state| match state.status {
+                OrderStatus::Cancelled => state.child.order_common.filled_quantity,
+                _ => state.child.order_common.quantity,
+            })
+            .sum()
+    }
+
+    /// Quantity still working in the market across the outstanding children.
+    pub fn remaining_quantity(&self) -> Quantity {
+        self.expected_quantity()
+            .saturating_sub(self.filled_quantity())
+    }
+
+    /// Filled share of the expected quantity, from 0 to 100. A parent with nothing
+    /// expected any more is reported as 100% complete.
+    pub fn completion_pct(&self) -> f64 {
+        let expected = self.expected_quantity();
+        if !expected.is_positive() {
+            return 100.0;
+        }
+        (self.filled_quantity().to_f64() / expected.to_f64() * 100.0).min(100.0)
+    }
+
+    /// Returns true once every child has been filled or cancelled.
+    pub fn is_complete(&self) -> bool {
+        self.outstanding_children().next().is_none()
+    }
+
+    fn position(&self, order_id: &str) -> Result<usize, ParentStateError> {
+        self.children
+            .iter()
+            .position(|state| state.child.order_common.id == order_id)
+            .ok_or_else(|| ParentStateError::UnknownChild {
+                parent_id: self.parent.order_common.id.clone(),
+                order_id: order_id.to_string(),
+            })
+    }
+
+    /// Applies a fill to its child and returns the child's new status. The state is left
+    /// untouched when an error is returned.
+    pub fn on_fill(&mut self, fill: &Fill) -> Result<OrderStatus, ParentStateError> {
+        let index = self.position(&fill.order_id)?;
+        let state = &mut self.children[index];
+        if !state.status.is_open() {
+            return Err(ParentStateError::ChildClosed {
+                order_id: fill.order_id.clone(),
+                status: state.status,
+            });
+        }
+        apply_fill(&mut state.child.order_common, fill)?;
+        let status = fill_status(&state.child);
+        // A pending cancel stays pending until the child fills or the cancel is confirmed
+        if state.status != OrderStatus::PendingCancel || status == OrderStatus::Filled {
+            state.status = status;
+        }
+        Ok(state.status)
+    }
+
+    /// Records that a child was sent on at `at`.
+    pub fn on_dispatched(&mut self, order_id: &str, at: Timestamp) -> Result<(), ParentStateError> {
+        let index = self.position(order_id)?;
+        let state = &mut self.children[index];
+        if !state.status.is_open() {
+            return Err(ParentStateError::ChildClosed {
+                order_id: order_id.to_string(),
+                status: state.status,
+            });
+        }
+        state.dispatched_at = Some(at);
+        Ok(())
+    }
+
+    /// Records that the cancellation of a working child was requested; it stays
+    /// `PendingCancel` until the cancel is confirmed with `on_cancel` or it fills.
+    pub fn on_cancel_requested(&mut self, order_id: &str) -> Result<(), ParentStateError> {
+        let index = self.position(order_id)?;
+        let state = &mut self.children[index];
+        if !state.status.is_open() {
+            return Err(ParentStateError::ChildClosed {
+                order_id: order_id.to_string(),
+                status: state.status,
+            });
+        }
+        state.status = OrderStatus::PendingCancel;
+        Ok(())
+    }
+
+    /// Records the cancellation of a child. Whatever it filled before stays counted.
+    pub fn on_cancel(&mut self, order_id: &str) -> Result<(), ParentStateError> {
+        let index = self.position(order_id)?;
+        let state = &mut self.children[index];
+        if !state.status.is_open() {
+            return Err(ParentStateError::ChildClosed {
+                order_id: order_id.to_string(),
+                status: state.status,
+            });
+        }
+        state.status = OrderStatus::Cancelled;
+        Ok(())
+    }
+}
+
+fn fill_status(child: &ChildOrder) -> OrderStatus {
+    let order = &child.order_common;
+    if order.is_filled() {
+        OrderStatus::Filled
+    } else if order.filled_quantity.is_positive() {
+        OrderStatus::PartiallyFilled
+    } else {
+        OrderStatus::New
+    }
+}
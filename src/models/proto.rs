@@ -0,0 +1,647 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Protobuf encoding of orders, following `proto/orders.proto`.
+
+use super::child_orders::ChildOrder;
+use super::decimal::Decimal;
+use super::orders::{
+    Futures, OffsetType, OptionType, Options, Order, OrderType, ProductType, Side, Swap,
+    TimeInForce, TrailingSpec, CFD,
+};
+use super::parent_orders::ParentOrder;
+use super::quantity::Quantity;
+use super::timestamp::Timestamp;
+use prost::Message;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Messages of `proto/orders.proto`, written out by hand so no `protoc` is needed to build.
+pub mod pb {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ProductType {
+        Unspecified = 0,
+        Spot = 1,
+        Futures = 2,
+        Options = 3,
+        Swap = 4,
+        Cfd = 5,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Side {
+        Unspecified = 0,
+        Buy = 1,
+        Sell = 2,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum TimeInForce {
+        Unspecified = 0,
+        Gtc = 1,
+        Ioc = 2,
+        Gtd = 3,
+        Fok = 4,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum OptionType {
+        Unspecified = 0,
+        Call = 1,
+        Put = 2,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum OffsetType {
+        Unspecified = 0,
+        Absolute = 1,
+        Percent = 2,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Market {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Limit {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Stop {
+        #[prost(string, tag = "1")]
+        pub stop_price: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopLimit {
+        #[prost(string, tag = "1")]
+        pub stop_price: String,
+        #[prost(string, tag = "2")]
+        pub limit_price: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TrailingStop {
+        #[prost(string, tag = "1")]
+        pub offset: String,
+        #[prost(enumeration = "OffsetType", tag = "2")]
+        pub offset_type: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum OrderKind {
+        #[prost(message, tag = "1")]
+        Market(Market),
+        #[prost(message, tag = "2")]
+        Limit(Limit),
+        #[prost(message, tag = "3")]
+        Stop(Stop),
+        #[prost(message, tag = "4")]
+        StopLimit(StopLimit),
+        #[prost(message, tag = "5")]
+        TrailingStop(TrailingStop),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OrderType {
+        #[prost(oneof = "OrderKind", tags = "1, 2, 3, 4, 5")]
+        pub kind: Option<OrderKind>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Futures {
+        #[prost(uint64, optional, tag = "1")]
+        pub delivery_date: Option<u64>,
+        #[prost(double, optional, tag = "2")]
+        pub contract_size: Option<f64>,
+        #[prost(string, optional, tag = "3")]
+        pub margin: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub commission: Option<String>,
+        #[prost(string, optional, tag = "5")]
+        pub overnight_fee: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Options {
+        #[prost(string, tag = "1")]
+        pub strike_price: String,
+        #[prost(enumeration = "OptionType", tag = "2")]
+        pub option_type: i32,
+        #[prost(uint64, tag = "3")]
+        pub expiry_date: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Swap {
+        #[prost(double, tag = "1")]
+        pub fixed_rate: f64,
+        #[prost(string, tag = "2")]
+        pub floating_rate_index: String,
+        #[prost(string, tag = "3")]
+        pub notional_amount: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Cfd {
+        #[prost(uint32, optional, tag = "1")]
+        pub leverage: Option<u32>,
+        #[prost(string, optional, tag = "2")]
+        pub margin: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub commission: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub overnight_fee: Option<String>,
+        #[prost(string, optional, tag = "5")]
+        pub dividend_adjustment: Option<String>,
+        #[prost(double, optional, tag = "6")]
+        pub contract_size: Option<f64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Order {
+        #[prost(uint32, tag = "1")]
+        pub schema_version: u32,
+        #[prost(string, tag = "2")]
+        pub id: String,
+        #[prost(string, tag = "3")]
+        pub quantity: String,
+        #[prost(enumeration = "ProductType", tag = "4")]
+        pub product_type: i32,
+        #[prost(message, optional, tag = "5")]
+        pub order_type: Option<OrderType>,
+        #[prost(string, optional, tag = "6")]
+        pub price: Option<String>,
+        #[prost(uint64, tag = "7")]
+        pub timestamp: u64,
+        #[prost(uint64, optional, tag = "8")]
+        pub expiry_date: Option<u64>,
+        #[prost(string, tag = "9")]
+        pub symbol: String,
+        #[prost(enumeration = "Side", tag = "10")]
+        pub side: i32,
+        #[prost(string, tag = "11")]
+        pub currency: String,
+        #[prost(string, optional, tag = "12")]
+        pub exchange: Option<String>,
+        #[prost(enumeration = "TimeInForce", optional, tag = "13")]
+        pub timeinforce: Option<i32>,
+        #[prost(message, optional, tag = "14")]
+        pub futures_opt: Option<Futures>,
+        #[prost(message, optional, tag = "15")]
+        pub options_opt: Option<Options>,
+        #[prost(message, optional, tag = "16")]
+        pub swap_opt: Option<Swap>,
+        #[prost(message, optional, tag = "17")]
+        pub cfd_opt: Option<Cfd>,
+        #[prost(string, optional, tag = "18")]
+        pub notional: Option<String>,
+        #[prost(uint64, optional, tag = "19")]
+        pub nonce: Option<u64>,
+        #[prost(string, tag = "20")]
+        pub filled_quantity: String,
+        #[prost(string, optional, tag = "21")]
+        pub avg_fill_price: Option<String>,
+        #[prost(string, optional, tag = "22")]
+        pub original_id: Option<String>,
+        #[prost(uint32, tag = "23")]
+        pub version: u32,
+        #[prost(string, optional, tag = "24")]
+        pub quantity_step: Option<String>,
+        #[prost(string, optional, tag = "25")]
+        pub account_id: Option<String>,
+        #[prost(string, optional, tag = "26")]
+        pub portfolio_id: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ParentOrder {
+        #[prost(message, optional, tag = "1")]
+        pub order: Option<Order>,
+        #[prost(string, tag = "2")]
+        pub strategy_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ChildOrder {
+        #[prost(message, optional, tag = "1")]
+        pub order: Option<Order>,
+        #[prost(string, tag = "2")]
+        pub strategy_id: String,
+        #[prost(string, tag = "3")]
+        pub parent_id: String,
+        #[prost(uint64, optional, tag = "4")]
+        pub insert_at: Option<u64>,
+    }
+}
+
+/// Errors raised when protobuf bytes cannot be turned back into an order.
+#[derive(Debug, Error)]
+pub enum ProtoError {
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+    #[error("{0} is missing")]
+    MissingField(&'static str),
+    #[error("{field} has unknown enum value {value}")]
+    UnknownEnum { field: &'static str, value: i32 },
+    #[error("{field} is not a decimal: {value}")]
+    InvalidDecimal { field: &'static str, value: String },
+}
+
+fn decimal(field: &'static str, value: String) -> Result<Decimal, ProtoError> {
+    Decimal::from_str(&value).map_err(|_| ProtoError::InvalidDecimal { field, value })
+}
+
+fn optional_decimal(
+    field: &'static str,
+    value: Option<String>,
+) -> Result<Option<Decimal>, ProtoError> {
+    value.map(|value| decimal(field, value)).transpose()
+}
+
+fn quantity(field: &'static str, value: String) -> Result<Quantity, ProtoError> {
+    decimal(field, value).map(Quantity::new)
+}
+
+fn enumeration<E: TryFrom<i32>>(field: &'static str, value: i32) -> Result<E, ProtoError> {
+    E::try_from(value).map_err(|_| ProtoError::UnknownEnum { field, value })
+}
+
+fn product_type_to_proto(product_type: &ProductType) -> pb::ProductType {
+    match product_type {
+        ProductType::Spot => pb::ProductType::Spot,
+        ProductType::Futures => pb::ProductType::Futures,
+        ProductType::Options => pb::ProductType::Options,
+        ProductType::Swap => pb::ProductType::Swap,
+        ProductType::CFD => pb::ProductType::Cfd,
+    }
+}
+
+fn product_type_from_proto(value: i32) -> Result<ProductType, ProtoError> {
+    match enumeration("product_type", value)? {
+        pb::ProductType::Spot => Ok(ProductType::Spot),
+        pb::ProductType::Futures => Ok(ProductType::Futures),
+        pb::ProductType::Options => Ok(ProductType::Options),
+        pb::ProductType::Swap => Ok(ProductType::Swap),
+        pb::ProductType::Cfd => Ok(ProductType::CFD),
+        pb::ProductType::Unspecified => Err(ProtoError::MissingField("product_type")),
+    }
+}
+
+fn side_to_proto(side: &Side) -> pb::Side {
+    match side {
+        Side::Buy => pb::Side::Buy,
+        Side::Sell => pb::Side::Sell,
+    }
+}
+
+fn side_from_proto(value: i32) -> Result<Side, ProtoError> {
+    match enumeration("side", value)? {
+        pb::Side::Buy => Ok(Side::Buy),
+        pb::Side::Sell => Ok(Side::Sell),
+        pb::Side::Unspecified => Err(ProtoError::MissingField("side")),
+    }
+}
+
+fn time_in_force_to_proto(timeinforce: &TimeInForce) -> pb::TimeInForce {
+    match timeinforce {
+        TimeInForce::GTC => pb::TimeInForce::Gtc,
+        TimeInForce::IOC => pb::TimeInForce::Ioc,
+        TimeInForce::GTD => pb::TimeInForce::Gtd,
+        TimeInForce::FOK => pb::TimeInForce::Fok,
+    }
+}
+
+fn time_in_force_from_proto(value: i32) -> Result<TimeInForce, ProtoError> {
+    match enumeration("timeinforce", value)? {
+        pb::TimeInForce::Gtc => Ok(TimeInForce::GTC),
+        pb::TimeInForce::Ioc => Ok(TimeInForce::IOC),
+        pb::TimeInForce::Gtd => Ok(TimeInForce::GTD),
+        pb::TimeInForce::Fok => Ok(TimeInForce::FOK),
+        pb::TimeInForce::Unspecified => Err(ProtoError::MissingField("timeinforce")),
+    }
+}
+
+fn offset_type_from_proto(value: i32) -> Result<OffsetType, ProtoError> {
+    match enumeration("offset_type", value)? {
+        pb::OffsetType::Absolute => Ok(OffsetType::Absolute),
+        pb::OffsetType::Percent => Ok(OffsetType::Percent),
+        pb::OffsetType::Unspecified => Err(ProtoError::MissingField("offset_type")),
+    }
+}
+
+fn order_type_to_proto(order_type: &OrderType) -> pb::OrderType {
+    let kind = match order_type {
+        OrderType::Market => pb::OrderKind::Market(pb::Market {}),
+        OrderType::Limit => pb::OrderKind::Limit(pb::Limit {}),
+        OrderType::Stop { stop_price } => pb::OrderKind::Stop(pb::Stop {
+            stop_price: stop_price.to_string(),
+        }),
+        OrderType::StopLimit {
+            stop_price,
+            limit_price,
+        } => pb::OrderKind::StopLimit(pb::StopLimit {
+            stop_price: stop_price.to_string(),
+            limit_price: limit_price.to_string(),
+        }),
+        OrderType::TrailingStop(spec) => pb::OrderKind::TrailingStop(pb::TrailingStop {
+            offset: spec.offset.to_string(),
+            offset_type: match spec.offset_type {
+                OffsetType::Absolute => pb::OffsetType::Absolute,
+                OffsetType::Percent => pb::OffsetType::Percent,
+            } as i32,
+        }),
+    };
+    pb::OrderType { kind: Some(kind) }
+}
+
+fn order_type_from_proto(order_type: Option<pb::OrderType>) -> Result<OrderType, ProtoError> {
+    let kind = order_type
+        .and_then(|order_type| order_type.kind)
+        .ok_or(ProtoError::MissingField("order_type"))?;
+    Ok(match kind {
+        pb::OrderKind::Market(_) => OrderType::Market,
+        pb::OrderKind::Limit(_) => OrderType::Limit,
+        pb::OrderKind::Stop(stop) => OrderType::Stop {
+            stop_price: decimal("stop_price", stop.stop_price)?,
+        },
+        pb::OrderKind::StopLimit(stop_limit) => OrderType::StopLimit {
+            stop_price: decimal("stop_price", stop_limit.stop_price)?,
+            limit_price: decimal("limit_price", stop_limit.limit_price)?,
+        },
+        pb::OrderKind::TrailingStop(trailing) => OrderType::TrailingStop(TrailingSpec::new(
+            decimal("offset", trailing.offset)?,
+            offset_type_from_proto(trailing.offset_type)?,
+        )),
+    })
+}
+
+impl From<&Futures> for pb::Futures {
+    fn from(futures: &Futures) -> Self {
+        pb::Futures {
+            delivery_date: futures.delivery_date,
+            contract_size: futures.contract_size,
+            margin: futures.margin.map(|margin| margin.to_string()),
+            commission: futures.commission.map(|commission| commission.to_string()),
+            overnight_fee: futures.overnight_fee.map(|fee| fee.to_string()),
+        }
+    }
+}
+
+impl TryFrom<pb::Futures> for Futures {
+    type Error = ProtoError;
+
+    fn try_from(futures: pb::Futures) -> Result<Self, ProtoError> {
+        Ok(Futures {
+            delivery_date: futures.delivery_date,
+            contract_size: futures.contract_size,
+            margin: optional_decimal("margin", futures.margin)?,
+            commission: optional_decimal("commission", futures.commission)?,
+            overnight_fee: optional_decimal("overnight_fee", futures.overnight_fee)?,
+        })
+    }
+}
+
+impl From<&Options> for pb::Options {
+    fn from(options: &Options) -> Self {
+        pb::Options {
+            strike_price: options.strike_price.to_string(),
+            option_type: match options.option_type {
+                OptionType::Call => pb::OptionType::Call,
+                OptionType::Put => pb::OptionType::Put,
+            } as i32,
+            expiry_date: options.expiry_date,
+        }
+    }
+}
+
+impl TryFrom<pb::Options> for Options {
+    type Error = ProtoError;
+
+    fn try_from(options: pb::Options) -> Result<Self, ProtoError> {
+        let option_type = match enumeration("option_type", options.option_type)? {
+            pb::OptionType::Call => OptionType::Call,
+            pb::OptionType::Put => OptionType::Put,
+            pb::OptionType::Unspecified => return Err(ProtoError::MissingField("option_type")),
+        };
+        Ok(Options {
+            strike_price: decimal("strike_price", options.strike_price)?,
+            option_type,
+            expiry_date: options.expiry_date,
+        })
+    }
+}
+
+impl From<&Swap> for pb::Swap {
+    fn from(swap: &Swap) -> Self {
+        pb::Swap {
+            fixed_rate: swap.fixed_rate,
+            floating_rate_index: swap.floating_rate_index.clone(),
+            notional_amount: swap.notional_amount.to_string(),
+        }
+    }
+}
+
+impl TryFrom<pb::Swap> for Swap {
+    type Error = ProtoError;
+
+    fn try_from(swap: pb::Swap) -> Result<Self, ProtoError> {
+        Ok(Swap {
+            fixed_rate: swap.fixed_rate,
+            floating_rate_index: swap.floating_rate_index,
+            notional_amount: decimal("notional_amount", swap.notional_amount)?,
+        })
+    }
+}
+
+impl From<&CFD> for pb::Cfd {
+    fn from(cfd: &CFD) -> Self {
+        pb::Cfd {
+            leverage: cfd.leverage,
+            margin: cfd.margin.map(|margin| margin.to_string()),
+            commission: cfd.commission.map(|commission| commission.to_string()),
+            overnight_fee: cfd.overnight_fee.map(|fee| fee.to_string()),
+            dividend_adjustment: cfd
+                .dividend_adjustment
+                .map(|adjustment| adjustment.to_string()),
+            contract_size: cfd.contract_size,
+        }
+    }
+}
+
+impl TryFrom<pb::Cfd> for CFD {
+    type Error = ProtoError;
+
+    fn try_from(cfd: pb::Cfd) -> Result<Self, ProtoError> {
+        Ok(CFD {
+            leverage: cfd.leverage,
+            margin: optional_decimal("margin", cfd.margin)?,
+            commission: optional_decimal("commission", cfd.commission)?,
+            overnight_fee: optional_decimal("overnight_fee", cfd.overnight_fee)?,
+            dividend_adjustment: optional_decimal("dividend_adjustment", cfd.dividend_adjustment)?,
+            contract_size: cfd.contract_size,
+        })
+    }
+}
+
+impl From<&Order> for pb::Order {
+    fn from(order: &Order) -> Self {
+        pb::Order {
+            schema_version: order.schema_version,
+            id: order.id.clone(),
+            quantity: order.quantity.to_string(),
+            product_type: product_type_to_proto(&order.product_type) as i32,
+            order_type: Some(order_type_to_proto(&order.order_type)),
+            price: order.price.map(|price| price.to_string()),
+            timestamp: order.timestamp.as_millis(),
+            expiry_date: order.expiry_date.map(|expiry_date| expiry_date.as_millis()),
+            symbol: order.symbol.clone(),
+            side: side_to_proto(&order.side) as i32,
+            currency: order.currency.clone(),
+            exchange: order.exchange.clone(),
+            timeinforce: order
+                .timeinforce
+                .as_ref()
+                .map(|timeinforce| time_in_force_to_proto(timeinforce) as i32),
+            futures_opt: order.futures_opt.as_ref().map(pb::Futures::from),
+            options_opt: order.options_opt.as_ref().map(pb::Options::from),
+            swap_opt: order.swap_opt.as_ref().map(pb::Swap::from),
+            cfd_opt: order.cfd_opt.as_ref().map(pb::Cfd::from),
+            notional: order.notional.map(|notional| notional.to_string()),
+            nonce: order.nonce,
+            filled_quantity: order.filled_quantity.to_string(),
+            avg_fill_price: order.avg_fill_price.map(|price| price.to_string()),
+            original_id: order.original_id.clone(),
+            version: order.version,
+            quantity_step: order.quantity_step.map(|step| step.to_string()),
+            account_id: order.account_id.clone(),
+            portfolio_id: order.portfolio_id.clone(),
+        }
+    }
+}
+
+impl TryFrom<pb::Order> for Order {
+    type Error = ProtoError;
+
+    fn try_from(order: pb::Order) -> Result<Self, ProtoError> {
+        Ok(Order {
+            schema_version: order.schema_version,
+            id: order.id,
+            quantity: quantity("quantity", order.quantity)?,
+            product_type: product_type_from_proto(order.product_type)?,
+            order_type: order_type_from_proto(order.order_type)?,
+            price: optional_decimal("price", order.price)?,
+            timestamp: Timestamp::from_millis(order.timestamp),
+            expiry_date: order.expiry_date.map(Timestamp::from_millis),
+            symbol: order.symbol,
+            side: side_from_proto(order.side)?,
+            currency: order.currency,
+            exchange: order.exchange,
+            timeinforce: order
+                .timeinforce
+                .map(time_in_force_from_proto)
+                .transpose()?,
+            futures_opt: order.futures_opt.map(Futures::try_from).transpose()?,
+            options_opt: order.options_opt.map(Options::try_from).transpose()?,
+            swap_opt: order.swap_opt.map(Swap::try_from).transpose()?,
+            cfd_opt: order.cfd_opt.map(CFD::try_from).transpose()?,
+            notional: optional_decimal("notional", order.notional)?,
+            nonce: order.nonce,
+            account_id: order.account_id,
+            portfolio_id: order.portfolio_id,
+            filled_quantity: quantity("filled_quantity", order.filled_quantity)?,
+            avg_fill_price: optional_decimal("avg_fill_price", order.avg_fill_price)?,
+            original_id: order.original_id,
+            version: order.version,
+            quantity_step: order
+                .quantity_step
+                .map(|step| quantity("quantity_step", step))
+                .transpose()?,
+        })
+    }
+}
+
+impl Order {
+    /// Encodes the order as a `strategy_execution_engine.v1.Order` message.
+    pub fn to_proto_bytes(&self) -> Vec<u8> {
+        pb::Order::from(self).encode_to_vec()
+    }
+
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self, ProtoError> {
+        Order::try_from(pb::Order::decode(bytes)?)
+    }
+}
+
+impl ParentOrder {
+    /// Encodes the order as a `strategy_execution_engine.v1.ParentOrder` message.
+    pub fn to_proto_bytes(&self) -> Vec<u8> {
+        pb::ParentOrder {
+            order: Some(pb::Order::from(&self.order_common)),
+            strategy_id: self.strategy_id.clone(),
+        }
+        .encode_to_vec()
+    }
+
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self, ProtoError> {
+        let parent_order = pb::ParentOrder::decode(bytes)?;
+        let order = parent_order
+            .order
+            .ok_or(ProtoError::MissingField("order"))?;
+        Ok(ParentOrder {
+            order_common: Order::try_from(order)?,
+            strategy_id: parent_order.strategy_id,
+        })
+    }
+}
+
+impl ChildOrder {
+    /// Encodes the order as a `strategy_execution_engine.v1.ChildOrder` message.
+    pub fn to_proto_bytes(&self) -> Vec<u8> {
+        pb::ChildOrder {
+            order: Some(pb::Order::from(&self.order_common)),
+            strategy_id: self.strategy_id.clone(),
+            parent_id: self.parent_id.clone(),
+            insert_at: self.insert_at.map(|insert_at| insert_at.as_millis()),
+        }
+        .encode_to_vec()
+    }
+
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self, ProtoError> {
+        let child_order = pb::ChildOrder::decode(bytes)?;
+        let order = child_order.order.ok_or(ProtoError::MissingField("order"))?;
+        Ok(ChildOrder {
+            order_common: Order::try_from(order)?,
+            strategy_id: child_order.strategy_id,
+            parent_id: child_order.parent_id,
+            insert_at: child_order.insert_at.map(Timestamp::from_millis),
+        })
+    }
+}
@@ -0,0 +1,249 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+use super::decimal::{decimal_from_f64, decimal_to_f64, Decimal};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::RoundingStrategy;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Order size in units of the instrument, e.g. 0.05 BTC or 1500.5 units of a swap.
+///
+/// Whole quantities serialize as JSON integers, so `100` round-trips unchanged;
+/// fractional ones serialize as numbers. Both integer and decimal input is accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quantity(Decimal);
+
+impl Quantity {
+    pub const ZERO: Quantity = Quantity(Decimal::ZERO);
+
+    pub fn new(value: Decimal) -> Self {
+        Quantity(value.normalize())
+    }
+
+    /// Converts an `f64` size, e.g. from indicator or volume maths.
+    pub fn from_f64(value: f64) -> Self {
+        Quantity::new(decimal_from_f64(value))
+    }
+
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        decimal_to_f64(self.0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > Decimal::ZERO
+    }
+
+    /// Smallest increment at the quantity's own precision: 1 for 100, 0.01 for 0.05.
+    pub fn unit(&self) -> Quantity {
+        Quantity(Decimal::new(1, self.0.normalize().scale()))
+    }
+
+    /// Multiplies by an `f64` factor such as a fraction or weight.
+    pub fn scale(self, factor: f64) -> Quantity {
+        Quantity::new(self.0 * decimal_from_f64(factor))
+    }
+
+    pub fn saturating_sub(self, other: Quantity) -> Quantity {
+        if other >= self {
+            Quantity::ZERO
+        } else {
+            self - other
+        }
+    }
+
+    /// Rounds down to a whole multiple of `step`; a non-positive step leaves the value as is.
+    pub fn round_down_to_step(self, step: Quantity) -> Quantity {
+        if !step.is_positive() {
+            return self;
+        }
+        Quantity::new((self.0 / step.0).floor() * step.0)
+    }
+
+    /// Rounds to the nearest multiple of `step`, halves away from zero; a non-positive step
+    /// leaves the value as is.
+    pub fn round_to_step(self, step: Quantity) -> Quantity {
+        if !step.is_positive() {
+            return self;
+        }
+        let steps =
+            (self.0 / step.0).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+        Quantity::new(steps * step.0)
+    }
+
+    /// Returns true if the quantity is a whole multiple of `step`.
+    pub fn is_multiple_of(&self, step: Quantity) -> bool {
+        step.is_positive() && (self.0 % step.0).is_zero()
+    }
+
+    /// Splits into `parts` quantities rounded down to `step`, with the rounding remainder
+    /// added to the last part so the parts always add up to the original quantity.
+    pub fn split(self, parts: usize, step: Quantity) -> Vec<Quantity> {
+        if parts == 0 {
+            return Vec::new();
+        }
+        let share = Quantity::new(self.0 / Decimal::from(parts)).round_down_to_step(step);
+        let mut quantities = vec![share; parts];
+        quantities[parts - 1] = self - share * (parts as u32 - 1);
+        quantities
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u32> for Quantity {
+    fn from(value: u32) -> Self {
+        Quantity(Decimal::from(value))
+    }
+}
+
+impl From<Decimal> for Quantity {
+    fn from(value: Decimal) -> Self {
+        Quantity::new(value)
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = rust_decimal::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str(value).map(Quantity::new)
+    }
+}
+
+impl Add for Quantity {
+    type Output = Quantity;
+
+    fn add(self, other: Quantity) -> Quantity {
+        Quantity::new(self.0 + other.0)
+    }
+}
+
+impl Sub for Quantity {
+    type Output = Quantity;
+
+    fn sub(self, other: Quantity) -> Quantity {
+        Quantity::new(self.0 - other.0)
+    }
+}
+
+impl Mul<u32> for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, factor: u32) -> Quantity {
+        Quantity::new(self.0 * Decimal::from(factor))
+    }
+}
+
+impl AddAssign for Quantity {
+    fn add_assign(&mut self, other: Quantity) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Quantity {
+    fn sub_assign(&mut self, other: Quantity) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Quantity {
+    fn sum<I: Iterator<Item = Quantity>>(iter: I) -> Quantity {
+        iter.fold(Quantity::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Quantity> for Quantity {
+    fn sum<I: Iterator<Item = &'a Quantity>>(iter: I) -> Quantity {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.fract().is_zero() {
+            if let Some(whole) = self.0.to_u64() {
+                return serializer.serialize_u64(whole);
+            }
+            if let Some(whole) = self.0.to_i64() {
+                return serializer.serialize_i64(whole);
+            }
+        }
+        serializer.serialize_f64(decimal_to_f64(self.0))
+    }
+}
+
+struct QuantityVisitor;
+
+impl Visitor<'_> for QuantityVisitor {
+    type Value = Quantity;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an integer, decimal number or numeric string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Quantity, E> {
+        Ok(Quantity(Decimal::from(value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Quantity, E> {
+        Ok(Quantity(Decimal::from(value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Quantity, E> {
+        if !value.is_finite() {
+            return Err(E::custom("quantity must be finite"));
+        }
+        Ok(Quantity::from_f64(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Quantity, E> {
+        Quantity::from_str(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(QuantityVisitor)
+    }
+}
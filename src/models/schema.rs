@@ -0,0 +1,85 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// Layout version written by this build. Bump it, and add a migration below, whenever a
+/// serialized field of `Order` is renamed or changes meaning.
+///
+/// * 1: messages written before versioning (no `schema_version` field)
+/// * 2: adds `schema_version`, fill progress and amendment lineage
+pub const SCHEMA_VERSION: u32 = 2;
+
+pub(crate) fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Brings a v1 order, parent order or child order object up to v2. Fields added in v2
+/// are filled with their defaults, so the result reads the same in any v2 consumer.
+pub fn migrate_v1_to_v2(mut value: Value) -> Value {
+    if let Value::Object(fields) = &mut value {
+        for (field, default) in [
+            ("filled_quantity", Value::from(0)),
+            ("avg_fill_price", Value::Null),
+            ("original_id", Value::Null),
+            ("version", Value::from(0)),
+        ] {
+            fields.entry(field).or_insert(default);
+        }
+        fields.insert("schema_version".to_string(), Value::from(2));
+    }
+    value
+}
+
+fn schema_version(fields: &Map<String, Value>) -> u64 {
+    fields
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(legacy_schema_version() as u64)
+}
+
+/// Applies every migration between the message's `schema_version` and [`SCHEMA_VERSION`].
+/// Messages from a newer writer are left as they are: their unknown fields are ignored
+/// on deserialization.
+pub fn migrate(value: Value) -> Value {
+    let version = match &value {
+        Value::Object(fields) => schema_version(fields),
+        _ => return value,
+    };
+    if version < 2 {
+        migrate_v1_to_v2(value)
+    } else {
+        value
+    }
+}
+
+/// Deserializes an `Order`, `ParentOrder` or `ChildOrder` written by any schema version.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> serde_json::Result<T> {
+    serde_json::from_value(migrate(serde_json::from_str(json)?))
+}
@@ -0,0 +1,185 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+//! Order timestamps.
+//!
+//! A [`Timestamp`] is UNIX epoch milliseconds. It serializes as a number by default; with
+//! the `rfc3339` feature, human-readable formats such as JSON get an RFC3339 string
+//! instead while binary formats keep the number. Either form is accepted on input, and
+//! numbers too small to be milliseconds of a recent date are read as seconds.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Inputs below this are taken as seconds. As milliseconds it is early 1973, as seconds
+/// the year 5138, so no realistic value is ambiguous.
+pub const SECONDS_THRESHOLD: u64 = 100_000_000_000;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TimestampError {
+    #[error("invalid timestamp {0}")]
+    Invalid(String),
+    #[error("timestamp {0} is before the UNIX epoch")]
+    BeforeEpoch(String),
+}
+
+/// UNIX epoch milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const EPOCH: Timestamp = Timestamp(0);
+
+    pub fn from_millis(millis: u64) -> Self {
+        Timestamp(millis)
+    }
+
+    pub fn from_secs(secs: u64) -> Self {
+        Timestamp(secs.saturating_mul(1000))
+    }
+
+    /// Reads an epoch value of unknown unit: seconds below [`SECONDS_THRESHOLD`],
+    /// milliseconds otherwise.
+    pub fn from_epoch(value: u64) -> Self {
+        if value < SECONDS_THRESHOLD {
+            Self::from_secs(value)
+        } else {
+            Self::from_millis(value)
+        }
+    }
+
+    pub fn now() -> Self {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| Timestamp(elapsed.as_millis() as u64))
+            .unwrap_or(Self::EPOCH)
+    }
+
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        self.0 / 1000
+    }
+
+    /// RFC3339 in UTC with millisecond precision, e.g. `2021-06-01T02:00:00.000Z`
+    pub fn to_rfc3339(&self) -> String {
+        i64::try_from(self.0)
+            .ok()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .map_or_else(
+                || self.0.to_string(),
+                |datetime| datetime.to_rfc3339_opts(SecondsFormat::Millis, true),
+            )
+    }
+
+    pub fn parse_rfc3339(value: &str) -> Result<Self, TimestampError> {
+        let datetime = DateTime::parse_from_rfc3339(value)
+            .map_err(|_| TimestampError::Invalid(value.to_string()))?;
+        u64::try_from(datetime.timestamp_millis())
+            .map(Timestamp)
+            .map_err(|_| TimestampError::BeforeEpoch(value.to_string()))
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(millis: u64) -> Self {
+        Timestamp::from_millis(millis)
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+/// Milliseconds, the same as the numeric serialized form.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Accepts an epoch number (see [`Timestamp::from_epoch`]) or an RFC3339 string.
+impl FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.parse::<u64>() {
+            Ok(epoch) => Ok(Timestamp::from_epoch(epoch)),
+            Err(_) => Timestamp::parse_rfc3339(value),
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[cfg(feature = "rfc3339")]
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.to_rfc3339());
+        }
+        serializer.serialize_u64(self.0)
+    }
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("epoch seconds or milliseconds, or an RFC3339 string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Timestamp, E> {
+        Ok(Timestamp::from_epoch(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Timestamp, E> {
+        u64::try_from(value)
+            .map(Timestamp::from_epoch)
+            .map_err(|_| E::custom(TimestampError::BeforeEpoch(value.to_string())))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Timestamp, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
@@ -0,0 +1,109 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::models::parent_state::{ChildState, ParentOrderState};
+use crate::ParentOrder;
+use thiserror::Error;
+
+/// Errors raised while storing or loading orders.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum PersistenceError {
+    /// The store could not be reached or refused the operation.
+    #[error("persistence backend error: {0}")]
+    Backend(String),
+    /// An order could not be written as JSON, or what was stored could not be read back.
+    #[error("cannot encode or decode order {order_id}: {reason}")]
+    Encoding { order_id: String, reason: String },
+    #[error("order {0} was never saved")]
+    UnknownOrder(String),
+}
+
+impl PersistenceError {
+    pub(crate) fn encoding(order_id: &str, error: impl std::fmt::Display) -> Self {
+        PersistenceError::Encoding {
+            order_id: order_id.to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// A child as the JSON the stores keep.
+pub(crate) fn encode_child(child: &ChildState) -> Result<String, PersistenceError> {
+    serde_json::to_string(child)
+        .map_err(|error| PersistenceError::encoding(&child.child.order_common.id, error))
+}
+
+/// Durable storage of parent orders and their children, so the engine can pick up the
+/// orders in flight after a restart.
+///
+/// The `ChildOrderScheduler` saves a parent and its children when it schedules them, then
+/// updates a child each time it is dispatched, filled or cancelled.
+pub trait Persistence: Send + Sync {
+    /// Stores a parent, replacing an earlier copy.
+    fn save_parent(&self, parent: &ParentOrder) -> Result<(), PersistenceError>;
+
+    /// Stores the children of `parent_id` in split order, replacing earlier copies.
+    fn save_children(
+        &self,
+        parent_id: &str,
+        children: &[ChildState],
+    ) -> Result<(), PersistenceError>;
+
+    /// Records the status of a saved child, with its dispatch time and fills.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PersistenceError::UnknownOrder` for a child that was never saved.
+    fn update_status(&self, child: &ChildState) -> Result<(), PersistenceError>;
+
+    /// The parents with a child still open, each with its children in split order.
+    fn load_open_orders(&self) -> Result<Vec<ParentOrderState>, PersistenceError>;
+}
+
+/// A store shared with other owners, e.g. to inspect what the engine saved.
+impl<T: Persistence + ?Sized> Persistence for std::sync::Arc<T> {
+    fn save_parent(&self, parent: &ParentOrder) -> Result<(), PersistenceError> {
+        (**self).save_parent(parent)
+    }
+
+    fn save_children(
+        &self,
+        parent_id: &str,
+        children: &[ChildState],
+    ) -> Result<(), PersistenceError> {
+        (**self).save_children(parent_id, children)
+    }
+
+    fn update_status(&self, child: &ChildState) -> Result<(), PersistenceError> {
+        (**self).update_status(child)
+    }
+
+    fn load_open_orders(&self) -> Result<Vec<ParentOrderState>, PersistenceError> {
+        (**self).load_open_orders()
+    }
+}
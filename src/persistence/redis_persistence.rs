@@ -0,0 +1,264 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::models::parent_state::{ChildState, ParentOrderState};
+use crate::persistence::order_store::{encode_child, Persistence, PersistenceError};
+use crate::{ParentOrder, RedisConfig};
+use std::sync::Mutex;
+use std::time::Duration;
+
+impl From<redis::RedisError> for PersistenceError {
+    fn from(error: redis::RedisError) -> Self {
+        PersistenceError::Backend(error.to_string())
+    }
+}
+
+/// Keeps parent and child orders in Redis hashes keyed by order id.
+///
+/// Under the key prefix, `parent:<id>` holds the `parent` as JSON and the ids of its
+/// `children` in split order, `child:<id>` the `parent_id` and the `state` of a child as
+/// JSON, and the set `open` the ids of the parents that may still have open children.
+///
+/// The connection is opened on first use and reopened by the next call once it breaks.
+pub struct RedisPersistence {
+    client: redis::Client,
+    connect_timeout: Option<Duration>,
+    prefix: String,
+    connection: Mutex<Option<redis::Connection>>,
+}
+
+impl RedisPersistence {
+    /// Stores the orders on the server of `config`, under the `orders:` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PersistenceError::Backend` when the URL of `config` is not a Redis URL.
+    pub fn new(config: &RedisConfig) -> Result<Self, PersistenceError> {
+        Ok(RedisPersistence {
+            client: redis::Client::open(config.redis_url.expose())?,
+            connect_timeout: config.connect_timeout_ms.map(Duration::from_millis),
+            prefix: "orders:".to_string(),
+            connection: Mutex::new(None),
+        })
+    }
+
+    /// Puts the keys under `prefix` instead, e.g. to keep the orders of two engines apart.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn parent_key(&self, parent_id: &str) -> String {
+        format!("{}parent:{}", self.prefix, parent_id)
+    }
+
+    fn child_key(&self, child_id: &str) -> String {
+        format!("{}child:{}", self.prefix, child_id)
+    }
+
+    fn open_key(&self) -> String {
+        format!("{}open", self.prefix)
+    }
+
+    /// Reads back a parent from its `parent` and `children` fields; `None` when a child is
+    /// missing.
+    fn load_parent(
+        &self,
+        parent_id: &str,
+        parent: &str,
+        children: &str,
+    ) -> Result<Option<ParentOrderState>, PersistenceError> {
+        let parent: ParentOrder = serde_json::from_str(parent)
+            .map_err(|error| PersistenceError::encoding(parent_id, error))?;
+        let child_ids: Vec<String> = serde_json::from_str(children)
+            .map_err(|error| PersistenceError::encoding(parent_id, error))?;
+        let mut states = Vec::with_capacity(child_ids.len());
+        for child_id in child_ids {
+            let state: Option<String> = self.with_connection(|connection| {
+                redis::cmd("HGET")
+                    .arg(self.child_key(&child_id))
+                    .arg("state")
+                    .query(connection)
+            })?;
+            let Some(state) = state else {
+                return Ok(None);
+            };
+            states.push(
+                serde_json::from_str(&state)
+                    .map_err(|error| PersistenceError::encoding(&child_id, error))?,
+            );
+        }
+        Ok(Some(ParentOrderState {
+            parent,
+            children: states,
+        }))
+    }
+
+    /// Runs `command` on the connection, opening it first when needed; a connection that
+    /// broke is dropped for the next call to reopen.
+    fn with_connection<T>(
+        &self,
+        command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> Result<T, PersistenceError> {
+        let mut connection = self.connection.lock().unwrap();
+        let open = match connection.take() {
+            Some(open) => open,
+            None => match self.connect_timeout {
+                Some(timeout) => self.client.get_connection_with_timeout(timeout)?,
+                None => self.client.get_connection()?,
+            },
+        };
+        let open = connection.insert(open);
+        command(open).map_err(|error| {
+            if error.is_io_error() || error.is_connection_dropped() {
+                *connection = None;
+            }
+            error.into()
+        })
+    }
+}
+
+impl Persistence for RedisPersistence {
+    fn save_parent(&self, parent: &ParentOrder) -> Result<(), PersistenceError> {
+        let id = &parent.order_common.id;
+        let json =
+            serde_json::to_string(parent).map_err(|error| PersistenceError::encoding(id, error))?;
+        let key = self.parent_key(id);
+        self.with_connection(|connection| {
+            redis::cmd("HSET")
+                .arg(&key)
+                .arg("parent")
+                .arg(&json)
+                .query(connection)
+        })
+    }
+
+    fn save_children(
+        &self,
+        parent_id: &str,
+        children: &[ChildState],
+    ) -> Result<(), PersistenceError> {
+        let ids: Vec<&str> = children
+            .iter()
+            .map(|child| child.child.order_common.id.as_str())
+            .collect();
+        let ids = serde_json::to_string(&ids)
+            .map_err(|error| PersistenceError::encoding(parent_id, error))?;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for child in children {
+            pipe.cmd("HSET")
+                .arg(self.child_key(&child.child.order_common.id))
+                .arg("parent_id")
+                .arg(parent_id)
+                .arg("state")
+                .arg(encode_child(child)?)
+                .ignore();
+        }
+        pipe.cmd("HSET")
+            .arg(self.parent_key(parent_id))
+            .arg("children")
+            .arg(ids)
+            .ignore();
+        if children.iter().any(|child| child.status.is_open()) {
+            pipe.cmd("SADD")
+                .arg(self.open_key())
+                .arg(parent_id)
+                .ignore();
+        }
+        self.with_connection(|connection| pipe.query(connection))
+    }
+
+    fn update_status(&self, child: &ChildState) -> Result<(), PersistenceError> {
+        let id = &child.child.order_common.id;
+        let key = self.child_key(id);
+        let state = encode_child(child)?;
+        // Children are never removed, so one known now stays known for the HSET
+        let known: bool =
+            self.with_connection(|connection| redis::cmd("EXISTS").arg(&key).query(connection))?;
+        if !known {
+            return Err(PersistenceError::UnknownOrder(id.clone()));
+        }
+        self.with_connection(|connection| {
+            redis::cmd("HSET")
+                .arg(&key)
+                .arg("state")
+                .arg(&state)
+                .query(connection)
+        })
+    }
+
+    fn load_open_orders(&self) -> Result<Vec<ParentOrderState>, PersistenceError> {
+        let mut parent_ids: Vec<String> = self.with_connection(|connection| {
+            redis::cmd("SMEMBERS")
+                .arg(self.open_key())
+                .query(connection)
+        })?;
+        parent_ids.sort();
+        let mut open = Vec::new();
+        for parent_id in parent_ids {
+            let (parent, children): (Option<String>, Option<String>) =
+                self.with_connection(|connection| {
+                    redis::cmd("HMGET")
+                        .arg(self.parent_key(&parent_id))
+                        .arg("parent")
+                        .arg("children")
+                        .query(connection)
+                })?;
+            let state = match (parent, children) {
+                (Some(parent), Some(children)) => {
+                    self.load_parent(&parent_id, &parent, &children)?
+                }
+                _ => None,
+            };
+            match state {
+                Some(state) if !state.is_complete() => open.push(state),
+                // Done since it was saved, or never saved in full
+                _ => self.with_connection(|connection| {
+                    redis::cmd("SREM")
+                        .arg(self.open_key())
+                        .arg(&parent_id)
+                        .query::<()>(connection)
+                })?,
+            }
+        }
+        Ok(open)
+    }
+}
+
+impl std::fmt::Debug for RedisPersistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisPersistence")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,187 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+use crate::models::parent_state::{ChildState, ParentOrderState};
+use crate::persistence::order_store::{encode_child, Persistence, PersistenceError};
+use crate::ParentOrder;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS parent_orders (
+        id TEXT PRIMARY KEY,
+        parent TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS child_orders (
+        id TEXT PRIMARY KEY,
+        parent_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        open INTEGER NOT NULL,
+        state TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS child_orders_open ON child_orders (open, parent_id);
+";
+
+impl From<rusqlite::Error> for PersistenceError {
+    fn from(error: rusqlite::Error) -> Self {
+        PersistenceError::Backend(error.to_string())
+    }
+}
+
+/// Keeps parent and child orders in a SQLite database, as JSON, one row per order.
+pub struct SqlitePersistence {
+    connection: Mutex<Connection>,
+}
+
+impl SqlitePersistence {
+    /// Opens the database at `path`, creating it and its tables when missing.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// A database that lives as long as the value, for tests.
+    pub fn in_memory() -> Result<Self, PersistenceError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    /// The saved copy of the child `order_id`, if any.
+    pub fn child(&self, order_id: &str) -> Result<Option<ChildState>, PersistenceError> {
+        let state: Option<String> = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT state FROM child_orders WHERE id = ?1",
+                [order_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        state
+            .map(|state| {
+                serde_json::from_str(&state)
+                    .map_err(|error| PersistenceError::encoding(order_id, error))
+            })
+            .transpose()
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, PersistenceError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqlitePersistence {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Persistence for SqlitePersistence {
+    fn save_parent(&self, parent: &ParentOrder) -> Result<(), PersistenceError> {
+        let id = &parent.order_common.id;
+        let json =
+            serde_json::to_string(parent).map_err(|error| PersistenceError::encoding(id, error))?;
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO parent_orders (id, parent) VALUES (?1, ?2)",
+            params![id, json],
+        )?;
+        Ok(())
+    }
+
+    fn save_children(
+        &self,
+        parent_id: &str,
+        children: &[ChildState],
+    ) -> Result<(), PersistenceError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for (position, child) in children.iter().enumerate() {
+            transaction.execute(
+                "INSERT OR REPLACE INTO child_orders (id, parent_id, position, open, state)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    child.child.order_common.id,
+                    parent_id,
+                    position as i64,
+                    child.status.is_open(),
+                    encode_child(child)?
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn update_status(&self, child: &ChildState) -> Result<(), PersistenceError> {
+        let id = &child.child.order_common.id;
+        let updated = self.connection.lock().unwrap().execute(
+            "UPDATE child_orders SET open = ?2, state = ?3 WHERE id = ?1",
+            params![id, child.status.is_open(), encode_child(child)?],
+        )?;
+        if updated == 0 {
+            return Err(PersistenceError::UnknownOrder(id.clone()));
+        }
+        Ok(())
+    }
+
+    fn load_open_orders(&self) -> Result<Vec<ParentOrderState>, PersistenceError> {
+        let connection = self.connection.lock().unwrap();
+        let mut parents = connection.prepare(
+            "SELECT id, parent FROM parent_orders WHERE id IN
+             (SELECT parent_id FROM child_orders WHERE open) ORDER BY rowid",
+        )?;
+        let mut children = connection
+            .prepare("SELECT id, state FROM child_orders WHERE parent_id = ?1 ORDER BY position")?;
+        let rows: Vec<(String, String)> = parents
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        let mut open = Vec::with_capacity(rows.len());
+        for (parent_id, parent) in rows {
+            let parent: ParentOrder = serde_json::from_str(&parent)
+                .map_err(|error| PersistenceError::encoding(&parent_id, error))?;
+            let states = children
+                .query_map([&parent_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .map(|row| {
+                    let (child_id, state) = row?;
+                    serde_json::from_str(&state)
+                        .map_err(|error| PersistenceError::encoding(&child_id, error))
+                })
+                .collect::<Result<_, PersistenceError>>()?;
+            open.push(ParentOrderState {
+                parent,
+                children: states,
+            });
+        }
+        Ok(open)
+    }
+}
+
+impl std::fmt::Debug for SqlitePersistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqlitePersistence").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,341 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::models::decimal::Decimal;
+use crate::models::fills::Fill;
+use crate::models::orders::{Order, Side};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+
+/// Reasons a fill cannot be applied to the positions.
+#[derive(Debug, Error, PartialEq)]
+pub enum PositionError {
+    #[error("fill {exec_id} belongs to order {order_id}, which is not tracked")]
+    UnknownOrder { order_id: String, exec_id: String },
+    #[error("fill {exec_id} has a non-positive quantity")]
+    ZeroQuantity { exec_id: String },
+}
+
+/// The holding in one symbol and the profit and loss it made.
+///
+/// `quantity` is signed: positive when long, negative when short. Fees are kept apart from
+/// the realized PnL, see `net_pnl`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: Decimal,
+    /// Average price the open quantity was entered at; zero when flat
+    pub avg_entry_price: Decimal,
+    /// Last mark price, which values the open quantity
+    pub mark_price: Option<Decimal>,
+    pub realized_pnl: Decimal,
+    /// PnL of the open quantity at the mark price; zero without a mark
+    pub unrealized_pnl: Decimal,
+    /// Fees paid on every fill of the symbol
+    pub fees: Decimal,
+}
+
+impl Position {
+    pub fn new(symbol: &str) -> Self {
+        Position {
+            symbol: symbol.to_string(),
+            ..Position::default()
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.quantity.is_zero()
+    }
+
+    /// `Buy` when long, `Sell` when short and `None` when flat.
+    pub fn side(&self) -> Option<Side> {
+        if self.quantity.is_sign_positive() && !self.is_flat() {
+            Some(Side::Buy)
+        } else if self.quantity.is_sign_negative() && !self.is_flat() {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+
+    /// Value of the open quantity, at the mark price or, without one, the average entry
+    /// price; always positive.
+    pub fn exposure(&self) -> Decimal {
+        self.quantity.abs() * self.mark_price.unwrap_or(self.avg_entry_price)
+    }
+
+    /// Realized plus unrealized PnL, less fees.
+    pub fn net_pnl(&self) -> Decimal {
+        self.realized_pnl + self.unrealized_pnl - self.fees
+    }
+
+    /// Applies an execution of `quantity` at `price` on `side`, paying `fee`.
+    ///
+    /// Adding to the position moves the average entry price; reducing it realizes the PnL of
+    /// the closed quantity against the average entry price. A fill larger than the position
+    /// closes it and opens the rest on the other side at the fill price.
+    fn execute(&mut self, side: &Side, price: Decimal, quantity: Decimal, fee: Decimal) {
+        let signed = match side {
+            Side::Buy => quantity,
+            Side::Sell => -quantity,
+        };
+        let open = self.quantity.abs();
+        if self.is_flat() || self.quantity.is_sign_positive() == signed.is_sign_positive() {
+            self.avg_entry_price =
+                (self.avg_entry_price * open + price * quantity) / (open + quantity);
+        } else {
+            let closed = quantity.min(open);
+            let direction = if self.quantity.is_sign_positive() {
+                Decimal::ONE
+            } else {
+                Decimal::NEGATIVE_ONE
+            };
+            self.realized_pnl += (price - self.avg_entry_price) * closed * direction;
+            if quantity > open {
+                self.avg_entry_price = price;
+            } else if quantity == open {
+                self.avg_entry_price = Decimal::ZERO;
+            }
+        }
+        self.quantity += signed;
+        self.fees += fee;
+        self.revalue();
+    }
+
+    fn mark(&mut self, price: Decimal) {
+        self.mark_price = Some(price);
+        self.revalue();
+    }
+
+    fn revalue(&mut self) {
+        self.unrealized_pnl = match self.mark_price {
+            Some(mark) if !self.is_flat() => (mark - self.avg_entry_price) * self.quantity,
+            _ => Decimal::ZERO,
+        };
+    }
+}
+
+/// The positions of every symbol and their totals at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    /// Positions sorted by symbol, flat ones included
+    pub positions: Vec<Position>,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub fees: Decimal,
+    pub net_pnl: Decimal,
+}
+
+impl PortfolioSnapshot {
+    pub fn print_as_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Book {
+    positions: HashMap<String, Position>,
+    /// Tracked orders that are still working, by order id
+    orders: HashMap<String, TrackedOrder>,
+}
+
+#[derive(Debug, Clone)]
+struct TrackedOrder {
+    symbol: String,
+    side: Side,
+    /// Quantity not yet filled; the order is dropped once it reaches zero
+    remaining: Decimal,
+}
+
+/// Per-symbol positions and PnL, fed by fills.
+///
+/// A `Fill` only names its order, so the orders whose fills are applied with `apply_fill`
+/// are first registered with `track_order`. A tracked order is forgotten once it is fully
+/// filled, or when `untrack_order` is called for it after a cancel. Clones share the same positions, so one tracker
+/// can be handed to several strategies and threads.
+#[derive(Debug, Clone, Default)]
+pub struct PositionTracker {
+    book: Arc<Mutex<Book>>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers the symbol and side of `order` for the fills reported against it.
+    pub fn track_order(&self, order: &Order) {
+        self.book().orders.insert(
+            order.id.clone(),
+            TrackedOrder {
+                symbol: order.symbol.clone(),
+                side: order.side.clone(),
+                remaining: order.quantity.value(),
+            },
+        );
+    }
+
+    /// Forgets the order `order_id`, once it can receive no more fills; returns whether it was
+    /// tracked.
+    pub fn untrack_order(&self, order_id: &str) -> bool {
+        self.book().orders.remove(order_id).is_some()
+    }
+
+    /// Applies `fill` to the position of its order's symbol and returns the position after it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PositionError::UnknownOrder` when the fill's order was not tracked, and
+    /// `ZeroQuantity` when the fill has no quantity. The positions are left untouched then.
+    /// The order stops being tracked once its fills cover its quantity.
+    pub fn apply_fill(&self, fill: &Fill) -> Result<Position, PositionError> {
+        let mut book = self.book();
+        let order = book.orders.get(&fill.order_id).cloned().ok_or_else(|| {
+            PositionError::UnknownOrder {
+                order_id: fill.order_id.clone(),
+                exec_id: fill.exec_id.clone(),
+            }
+        })?;
+        let position = Self::execute(&mut book, &order.symbol, &order.side, fill)?;
+        let remaining = order.remaining - fill.quantity.value();
+        if remaining > Decimal::ZERO {
+            if let Some(tracked) = book.orders.get_mut(&fill.order_id) {
+                tracked.remaining = remaining;
+            }
+        } else {
+            book.orders.remove(&fill.order_id);
+        }
+        Ok(position)
+    }
+
+    /// Applies `fill` as an execution on `side` in `symbol`, for fills of untracked orders.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PositionError::ZeroQuantity` when the fill has no quantity.
+    pub fn apply_execution(
+        &self,
+        symbol: &str,
+        side: &Side,
+        fill: &Fill,
+    ) -> Result<Position, PositionError> {
+        Self::execute(&mut self.book(), symbol, side, fill)
+    }
+
+    /// Values the open quantity in `symbol` at `price` and returns the position.
+    pub fn mark(&self, symbol: &str, price: Decimal) -> Position {
+        let mut book = self.book();
+        let position = book
+            .positions
+            .entry(symbol.to_string())
+            .or_insert_with(|| Position::new(symbol));
+        position.mark(price);
+        position.clone()
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<Position> {
+        self.book().positions.get(symbol).cloned()
+    }
+
+    /// Signed quantity held in `symbol`; zero when nothing was traded.
+    pub fn quantity(&self, symbol: &str) -> Decimal {
+        self.position(symbol)
+            .map(|position| position.quantity)
+            .unwrap_or_default()
+    }
+
+    /// Sum of the exposures of every position, long and short alike.
+    pub fn gross_exposure(&self) -> Decimal {
+        self.book().positions.values().map(Position::exposure).sum()
+    }
+
+    /// Every position and the portfolio's totals.
+    pub fn snapshot(&self) -> PortfolioSnapshot {
+        let mut positions: Vec<Position> = self.book().positions.values().cloned().collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let mut snapshot = PortfolioSnapshot::default();
+        for position in &positions {
+            snapshot.realized_pnl += position.realized_pnl;
+            snapshot.unrealized_pnl += position.unrealized_pnl;
+            snapshot.fees += position.fees;
+        }
+        snapshot.net_pnl = snapshot.realized_pnl + snapshot.unrealized_pnl - snapshot.fees;
+        snapshot.positions = positions;
+        snapshot
+    }
+
+    /// Drops every position and tracked order.
+    pub fn reset(&self) {
+        *self.book() = Book::default();
+    }
+
+    fn execute(
+        book: &mut Book,
+        symbol: &str,
+        side: &Side,
+        fill: &Fill,
+    ) -> Result<Position, PositionError> {
+        if !fill.quantity.is_positive() {
+            return Err(PositionError::ZeroQuantity {
+                exec_id: fill.exec_id.clone(),
+            });
+        }
+        let position = book
+            .positions
+            .entry(symbol.to_string())
+            .or_insert_with(|| Position::new(symbol));
+        position.execute(side, fill.price, fill.quantity.value(), fill.fee);
+        Ok(position.clone())
+    }
+
+    fn book(&self) -> MutexGuard<'_, Book> {
+        self.book.lock().unwrap()
+    }
+}
+
+/// Serializes as the list of positions, for strategy snapshots; tracked orders are left out.
+impl Serialize for PositionTracker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().positions.serialize(serializer)
+    }
+}
+
+/// Restores a tracker of its own from the list of positions.
+impl<'de> Deserialize<'de> for PositionTracker {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let positions = Vec::<Position>::deserialize(deserializer)?;
+        let tracker = PositionTracker::new();
+        tracker.book().positions = positions
+            .into_iter()
+            .map(|position| (position.symbol.clone(), position))
+            .collect();
+        Ok(tracker)
+    }
+}
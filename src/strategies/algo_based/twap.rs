@@ -44,15 +44,336 @@ importance of timing in trade execution and how traders can optimize their
 strategies using TWAP. (Chapter 6 covers time-weighted strategies in detail).
 */
 
-use crate::models::{ChildOrder, ParentOrder};
-use crate::strategies::common_strategies::OrderSplitStrategy;
-use std::vec::Vec;
+use crate::config::StrategyConfig;
+use crate::models::{
+    ChildOrder, IdGenerator, InstrumentRegistry, ParentOrder, Quantity, Timestamp, TradingCalendar,
+};
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use crate::strategies::technical_indicator_based::ATRSizer;
+use serde::{Deserialize, Serialize};
 
-pub struct TWAPStrategy;
+/// Configuration of the TWAP strategy, readable from the `strategies` section of `Config`
+/// where missing fields keep their defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TWAPConfig {
+    /// Number of equal children the parent is cut into
+    pub slices: usize,
+    /// Time between two consecutive children (milliseconds)
+    pub interval_ms: u64,
+}
+
+impl Default for TWAPConfig {
+    fn default() -> Self {
+        Self {
+            slices: 10,
+            interval_ms: 60_000,
+        }
+    }
+}
+
+impl StrategyConfig for TWAPConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.slices == 0 {
+            return Err("slices must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// TWAP strategy cutting the parent into equal children spaced evenly from its timestamp
+pub struct TWAPStrategy {
+    pub config: TWAPConfig,
+    /// Optional instrument rules child prices and quantities are rounded to
+    pub instruments: Option<InstrumentRegistry>,
+    /// Optional calendar used to snap child schedules into trading sessions
+    pub calendar: Option<TradingCalendar>,
+    /// Optional ATR sizer capping slices when volatility spikes
+    pub atr_sizer: Option<ATRSizer>,
+    /// Source of child order ids
+    pub ids: IdGenerator,
+}
+
+impl TWAPStrategy {
+    pub fn new(config: TWAPConfig) -> Self {
+        Self {
+            config,
+            instruments: None,
+            calendar: None,
+            atr_sizer: None,
+            ids: IdGenerator::default(),
+        }
+    }
+
+    /// Round child prices to ticks and quantities to lots of the instrument
+    pub fn with_instruments(mut self, instruments: InstrumentRegistry) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Schedule child orders inside the sessions of the given calendar
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Cap slices with the given ATR sizer. Quantity above the cap is sent in further slices
+    /// at the same interval, so the schedule runs longer instead of leaving quantity behind.
+    pub fn with_atr_sizer(mut self, atr_sizer: ATRSizer) -> Self {
+        self.atr_sizer = Some(atr_sizer);
+        self
+    }
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+}
 
 impl OrderSplitStrategy for TWAPStrategy {
-    fn split(&self, _parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        // Implement the TWAP splitting logic here
-        vec![]
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let parent = &parent_order.order_common;
+        let step = match &self.instruments {
+            Some(instruments) => instruments.lot_step(parent),
+            None => parent.lot_step(),
+        };
+        // With a calendar the whole schedule rolls to the next session, keeping its spacing
+        let start = match &self.calendar {
+            Some(calendar) => calendar.clamp_to_session(parent.timestamp.as_millis()),
+            None => parent.timestamp.as_millis(),
+        };
+
+        // The rounding remainder goes to the last slice, so the slices add up to the parent
+        let mut slices = parent.quantity.split(self.config.slices, step);
+        if let Some(cap) = self
+            .atr_sizer
+            .as_ref()
+            .and_then(|sizer| sizer.clip_limit_in_lots(step))
+        {
+            slices = cap_slices(slices, cap);
+        }
+        let child_orders = slices
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, quantity)| {
+                let mut order = parent.clone();
+                order.quantity = quantity;
+                if let Some(instruments) = &self.instruments {
+                    instruments.round_order(&mut order);
+                }
+                if !order.quantity.is_positive() {
+                    return None;
+                }
+                order.id = self.ids.next_id();
+                // Past a close the schedule carries on from the next open
+                let offset = i as u64 * self.config.interval_ms;
+                let insert_at = match &self.calendar {
+                    Some(calendar) => calendar.advance(start, offset),
+                    None => start + offset,
+                };
+                Some(ChildOrder {
+                    order_common: order,
+                    strategy_id: parent_order.strategy_id.clone(),
+                    parent_id: parent.id.clone(),
+                    insert_at: Some(Timestamp::from_millis(insert_at)),
+                })
+            })
+            .collect();
+        checked_split(parent_order, child_orders)
+    }
+}
+
+/// Caps every slice at `cap`, carrying what is cut into the next slices and then into extra
+/// slices of at most `cap`, so the slices still add up to the same quantity. A cap that is
+/// not positive would leave nothing to send, so it leaves the slices as they are
+fn cap_slices(slices: Vec<Quantity>, cap: Quantity) -> Vec<Quantity> {
+    if !cap.is_positive() {
+        return slices;
+    }
+    let mut carry = Quantity::ZERO;
+    let mut capped: Vec<Quantity> = slices
+        .into_iter()
+        .map(|slice| {
+            let wanted = slice + carry;
+            let slice = wanted.min(cap);
+            carry = wanted - slice;
+            slice
+        })
+        .collect();
+    while carry.is_positive() {
+        let slice = carry.min(cap);
+        carry -= slice;
+        capped.push(slice);
+    }
+    capped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, Side};
+    use crate::models::Quantity;
+
+    const START: u64 = 1_716_213_600_000;
+
+    fn parent_order(quantity: u32) -> ParentOrder {
+        Order::builder()
+            .id("parent-1")
+            .quantity(quantity)
+            .timestamp(START)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("twap")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_split_spreads_equal_slices_over_time() {
+        let strategy = TWAPStrategy::new(TWAPConfig {
+            slices: 4,
+            interval_ms: 30_000,
+        })
+        .with_id_generator(IdGenerator::sequential("child"));
+        let child_orders = strategy.split(&parent_order(1002));
+
+        let quantities: Vec<Quantity> = child_orders
+            .iter()
+            .map(|c| c.order_common.quantity)
+            .collect();
+        assert_eq!(
+            quantities,
+            vec![
+                Quantity::from(250),
+                Quantity::from(250),
+                Quantity::from(250),
+                Quantity::from(252)
+            ]
+        );
+        let times: Vec<u64> = child_orders
+            .iter()
+            .map(|c| c.insert_at.unwrap().as_millis() - START)
+            .collect();
+        assert_eq!(times, vec![0, 30_000, 60_000, 90_000]);
+        assert_eq!(child_orders[3].order_common.id, "child-4");
+        assert!(child_orders.iter().all(|c| c.parent_id == "parent-1"));
+    }
+
+    #[test]
+    fn test_small_parent_skips_empty_slices() {
+        let strategy = TWAPStrategy::new(TWAPConfig::default());
+        let child_orders = strategy.split(&parent_order(3));
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].order_common.quantity, Quantity::from(3));
+    }
+
+    #[test]
+    fn test_split_keeps_children_inside_sessions() {
+        // Friday 2024-05-24 16:00 EDT close and the following Monday open
+        let friday_close = 1_716_580_800_000;
+        let monday_open = 1_716_816_600_000;
+        let calendar = TradingCalendar::us_equities();
+        let strategy = TWAPStrategy::new(TWAPConfig {
+            slices: 4,
+            interval_ms: 300_000,
+        })
+        .with_calendar(calendar.clone());
+        let mut parent = parent_order(1000);
+        parent.order_common.timestamp = Timestamp::from_millis(friday_close - 600_000);
+
+        let times: Vec<u64> = strategy
+            .split(&parent)
+            .iter()
+            .map(|c| c.insert_at.unwrap().as_millis())
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                friday_close - 600_000,
+                friday_close - 300_000,
+                monday_open,
+                monday_open + 300_000
+            ]
+        );
+        assert!(times.iter().all(|&time| calendar.is_open(time)));
+    }
+
+    #[test]
+    fn test_atr_cap_moves_quantity_into_later_slices() {
+        use crate::models::market_data::Candle;
+        use std::time::UNIX_EPOCH;
+
+        // An ATR of 10 with 1000 at risk per clip caps each slice at 100
+        let mut sizer = ATRSizer::new(5, 1000.0);
+        for _ in 0..10 {
+            sizer.add_candle(&Candle {
+                timestamp: UNIX_EPOCH,
+                open: 100.0,
+                high: 105.0,
+                low: 95.0,
+                close: 100.0,
+                volume: 1.0,
+            });
+        }
+        let strategy = TWAPStrategy::new(TWAPConfig {
+            slices: 4,
+            interval_ms: 30_000,
+        })
+        .with_atr_sizer(sizer);
+        let child_orders = strategy.split(&parent_order(1000));
+
+        assert_eq!(child_orders.len(), 10);
+        assert!(child_orders
+            .iter()
+            .all(|c| c.order_common.quantity == Quantity::from(100)));
+        assert_eq!(
+            child_orders[9].insert_at.unwrap().as_millis() - START,
+            9 * 30_000
+        );
+    }
+
+    #[test]
+    fn test_sub_lot_atr_cap_sends_one_lot_per_slice() {
+        use crate::models::market_data::Candle;
+        use std::time::UNIX_EPOCH;
+
+        // An ATR of 10 with 5 at risk per clip caps each slice at 0.5, below a lot of 1
+        let mut sizer = ATRSizer::new(1, 5.0);
+        sizer.add_candle(&Candle {
+            timestamp: UNIX_EPOCH,
+            open: 100.0,
+            high: 105.0,
+            low: 95.0,
+            close: 100.0,
+            volume: 1.0,
+        });
+        let strategy = TWAPStrategy::new(TWAPConfig {
+            slices: 2,
+            interval_ms: 30_000,
+        })
+        .with_atr_sizer(sizer);
+        let child_orders = strategy.split(&parent_order(3));
+
+        let quantities: Vec<Quantity> = child_orders
+            .iter()
+            .map(|c| c.order_common.quantity)
+            .collect();
+        assert_eq!(quantities, vec![Quantity::from(1); 3]);
+    }
+
+    #[test]
+    fn test_zero_cap_leaves_the_slices_whole() {
+        let slices = vec![Quantity::from(5), Quantity::from(7)];
+        assert_eq!(cap_slices(slices.clone(), Quantity::ZERO), slices);
+    }
+
+    #[test]
+    fn test_zero_slices_is_invalid() {
+        let config = TWAPConfig {
+            slices: 0,
+            ..TWAPConfig::default()
+        };
+        assert!(config.validate().is_err());
     }
 }
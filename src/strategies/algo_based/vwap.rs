@@ -45,14 +45,214 @@ into various trading tactics, including VWAP strategies. (Chapter 5 discusses
 volume-weighted strategies in detail).
 */
 
-use crate::models::{ChildOrder, ParentOrder};
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::models::{
+    ChildOrder, IdGenerator, InstrumentRegistry, ParentOrder, Timestamp, TradingCalendar,
+};
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use crate::strategies::time_volume_based::VolumeProfile;
 
-pub struct VWAPStrategy;
+/// VWAP strategy scheduling child orders along a historical intraday volume profile
+pub struct VWAPStrategy {
+    pub profile: VolumeProfile,
+    /// Optional instrument rules child prices and quantities are rounded to
+    pub instruments: Option<InstrumentRegistry>,
+    /// Optional calendar used to snap child schedules into trading sessions
+    pub calendar: Option<TradingCalendar>,
+    /// Source of child order ids
+    pub ids: IdGenerator,
+}
+
+impl VWAPStrategy {
+    pub fn new(profile: VolumeProfile) -> Self {
+        Self {
+            profile,
+            instruments: None,
+            calendar: None,
+            ids: IdGenerator::default(),
+        }
+    }
+
+    /// Round child prices to ticks and quantities to lots of the instrument
+    pub fn with_instruments(mut self, instruments: InstrumentRegistry) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Schedule child orders inside the sessions of the given calendar
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+}
 
 impl OrderSplitStrategy for VWAPStrategy {
-    fn split(&self, _parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        // Implement the VWAP splitting logic here
-        vec![]
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let parent = &parent_order.order_common;
+        let step = match &self.instruments {
+            Some(instruments) => instruments.lot_step(parent),
+            None => parent.lot_step(),
+        };
+        // With a calendar the profile is followed from the next session when the market is shut
+        let start = match &self.calendar {
+            Some(calendar) => calendar.clamp_to_session(parent.timestamp.as_millis()),
+            None => parent.timestamp.as_millis(),
+        };
+        let mut slots = self.profile.allocate(parent.quantity, step, start);
+        if slots.is_empty() {
+            // No volume expected for the rest of the day: send everything now
+            slots.push((start, parent.quantity));
+        }
+
+        let child_orders = slots
+            .into_iter()
+            .filter_map(|(insert_at, quantity)| {
+                let mut order = parent.clone();
+                order.quantity = quantity;
+                if let Some(instruments) = &self.instruments {
+                    instruments.round_order(&mut order);
+                }
+                if !order.quantity.is_positive() {
+                    return None;
+                }
+                order.id = self.ids.next_id();
+                // Past a close the schedule carries on from the next open
+                let insert_at = match &self.calendar {
+                    Some(calendar) => calendar.advance(start, insert_at.saturating_sub(start)),
+                    None => insert_at,
+                };
+                Some(ChildOrder {
+                    order_common: order,
+                    strategy_id: parent_order.strategy_id.clone(),
+                    parent_id: parent.id.clone(),
+                    insert_at: Some(Timestamp::from_millis(insert_at)),
+                })
+            })
+            .collect();
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
+    use crate::models::Quantity;
+
+    const HOUR: u64 = 3_600_000;
+    // Monday 2024-05-20 00:00 UTC
+    const MONDAY: u64 = 1716163200000;
+
+    fn parent_order(quantity: u32, timestamp: u64) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "parent-1".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_millis(timestamp),
+                None,
+                "AAPL".to_string(),
+                Side::Buy,
+                "USD".to_string(),
+                Some("NASDAQ".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "VWAP".to_string(),
+        }
+    }
+
+    fn profile() -> VolumeProfile {
+        // Hourly buckets, trading from 14:00 to 17:00 UTC
+        let mut fractions = vec![0.0; 24];
+        fractions[14] = 0.4;
+        fractions[15] = 0.2;
+        fractions[16] = 0.4;
+        VolumeProfile {
+            bucket_width_ms: HOUR,
+            fractions,
+        }
+    }
+
+    #[test]
+    fn test_split_follows_volume_profile() {
+        let strategy =
+            VWAPStrategy::new(profile()).with_id_generator(IdGenerator::sequential("child"));
+        let child_orders = strategy.split(&parent_order(1000, MONDAY + 10 * HOUR));
+
+        let quantities: Vec<u32> = child_orders
+            .iter()
+            .map(|c| c.order_common.quantity.to_f64() as u32)
+            .collect();
+        assert_eq!(quantities, vec![400, 200, 400]);
+        assert_eq!(
+            child_orders[0].insert_at,
+            Some(Timestamp::from_millis(MONDAY + 14 * HOUR))
+        );
+        assert_eq!(
+            child_orders[2].insert_at,
+            Some(Timestamp::from_millis(MONDAY + 16 * HOUR))
+        );
+        assert_eq!(child_orders[1].order_common.id, "child-2");
+        assert_eq!(child_orders[1].parent_id, "parent-1");
+    }
+
+    #[test]
+    fn test_split_twice_gives_distinct_child_ids() {
+        let strategy = VWAPStrategy::new(profile());
+        let parent = parent_order(1000, MONDAY + 10 * HOUR);
+        let first = strategy.split(&parent);
+        let second = strategy.split(&parent);
+
+        let mut ids: Vec<&str> = first
+            .iter()
+            .chain(&second)
+            .map(|c| c.order_common.id.as_str())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), first.len() + second.len());
+        assert!(second.iter().all(|c| c.parent_id == "parent-1"));
+    }
+
+    #[test]
+    fn test_split_on_weekend_waits_for_the_session() {
+        use crate::models::calendar::{Session, TradingCalendar};
+
+        // Saturday 2024-05-18 10:00 UTC; the calendar trades 14:00 to 17:00 UTC on weekdays
+        let saturday = MONDAY - 2 * 24 * HOUR + 10 * HOUR;
+        let weekdays = std::array::from_fn(|day| (day < 5).then(|| Session::new(840, 1020)));
+        let calendar = TradingCalendar::new("test", 0, weekdays);
+        let strategy = VWAPStrategy::new(profile()).with_calendar(calendar.clone());
+        let child_orders = strategy.split(&parent_order(1000, saturday));
+
+        assert_eq!(child_orders.len(), 3);
+        assert_eq!(
+            child_orders[0].insert_at,
+            Some(Timestamp::from_millis(MONDAY + 14 * HOUR))
+        );
+        for child in &child_orders {
+            assert!(calendar.is_open(child.insert_at.unwrap().as_millis()));
+        }
+    }
+
+    #[test]
+    fn test_split_after_close_sends_everything() {
+        let strategy = VWAPStrategy::new(profile());
+        let child_orders = strategy.split(&parent_order(1000, MONDAY + 18 * HOUR));
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].order_common.quantity, Quantity::from(1000));
     }
 }
@@ -26,8 +26,1019 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use crate::models::{ChildOrder, ParentOrder};
+use crate::models::decimal::{decimal_from_f64, Decimal};
+use crate::models::orders::{OrderType, TrailingSpec, ValidationError};
+use crate::models::{ChildOrder, IdGenerator, MarketData, ParentOrder, Quantity, Side, Timestamp};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use thiserror::Error;
 
 pub trait OrderSplitStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder>;
+
+    /// The signal the next split acts on, with the indicator values behind it, for the
+    /// audit log; `None` for strategies that do not trade on signals
+    fn decision(&self) -> Option<SignalDecision> {
+        None
+    }
+
+    /// The seed of the random source the last split drew from, recorded by backtests and the
+    /// audit log so its schedule can be replayed; `None` before the first split and for
+    /// unseeded or deterministic strategies
+    fn seed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Feeds a market data update to a strategy that trades on signals, so it is warmed up
+    /// when a parent comes to be split; other strategies ignore it
+    fn on_market_data(&mut self, _data: &MarketData) {}
+}
+
+/// Random source of a seeded split strategy: a generator seeded once, which each split
+/// advances to draw a seed of its own. Consecutive parents split differently, and each split
+/// replays from the seed it drew with `StdRng::seed_from_u64`.
+///
+/// Snapshots keep the seed and the number of splits drawn, to resume where it stood.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "SeedsSnapshot", into = "SeedsSnapshot")]
+pub struct SplitSeeds {
+    seed: u64,
+    state: Mutex<SeedState>,
+}
+
+#[derive(Debug, Clone)]
+struct SeedState {
+    rng: StdRng,
+    /// Seeds drawn so far
+    splits: u64,
+    last: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SeedsSnapshot {
+    seed: u64,
+    splits: u64,
+}
+
+impl SplitSeeds {
+    pub fn new(seed: u64) -> Self {
+        Self::resume(seed, 0)
+    }
+
+    /// The generator of `seed` once `splits` seeds have been drawn from it.
+    fn resume(seed: u64, splits: u64) -> Self {
+        let mut state = SeedState {
+            rng: StdRng::seed_from_u64(seed),
+            splits: 0,
+            last: None,
+        };
+        for _ in 0..splits {
+            state.draw();
+        }
+        SplitSeeds {
+            seed,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// The seed the generator was built from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Draws the seed of the next split, which `last` returns from then on.
+    pub fn next(&self) -> u64 {
+        self.state.lock().unwrap().draw()
+    }
+
+    /// An RNG seeded with the seed of the next split, see `next`.
+    pub fn next_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.next())
+    }
+
+    /// The seed of the last split; `None` before the first.
+    pub fn last(&self) -> Option<u64> {
+        self.state.lock().unwrap().last
+    }
+}
+
+impl SeedState {
+    fn draw(&mut self) -> u64 {
+        let seed = self.rng.random();
+        self.splits += 1;
+        self.last = Some(seed);
+        seed
+    }
+}
+
+impl Clone for SplitSeeds {
+    fn clone(&self) -> Self {
+        SplitSeeds {
+            seed: self.seed,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl From<SeedsSnapshot> for SplitSeeds {
+    fn from(snapshot: SeedsSnapshot) -> Self {
+        SplitSeeds::resume(snapshot.seed, snapshot.splits)
+    }
+}
+
+impl From<SplitSeeds> for SeedsSnapshot {
+    fn from(seeds: SplitSeeds) -> Self {
+        let splits = seeds.state.lock().unwrap().splits;
+        SeedsSnapshot {
+            seed: seeds.seed,
+            splits,
+        }
+    }
+}
+
+/// Where a `SignalStrategy` stands when it splits a parent order
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalDecision {
+    pub ready: bool,
+    pub signal: Option<Side>,
+    pub indicators: BTreeMap<String, f64>,
+}
+
+impl SignalDecision {
+    pub fn of<S: SignalStrategy + ?Sized>(strategy: &S) -> Self {
+        SignalDecision {
+            ready: strategy.is_ready(),
+            signal: strategy.signal(),
+            indicators: strategy.indicators(),
+        }
+    }
+}
+
+/// A set of child orders that does not fit its parent
+#[derive(Debug, Error, PartialEq)]
+pub enum SplitError {
+    #[error("child {child_id} belongs to parent {parent_id}, not {expected}")]
+    ForeignChild {
+        child_id: String,
+        parent_id: String,
+        expected: String,
+    },
+    #[error(
+        "child {child_id} is scheduled at {insert_at}, before its parent timestamp {timestamp}"
+    )]
+    ScheduledBeforeParent {
+        child_id: String,
+        insert_at: Timestamp,
+        timestamp: Timestamp,
+    },
+    #[error(
+        "children of {parent_id} allocate {allocated}, more than the parent quantity {quantity}"
+    )]
+    OverAllocated {
+        parent_id: String,
+        allocated: Quantity,
+        quantity: Quantity,
+    },
+    #[error("child {child_id} has no quantity")]
+    ZeroQuantity { child_id: String },
+}
+
+/// Checks the output of a split: every child belongs to `parent`, none is scheduled before
+/// the parent timestamp or has a zero quantity, and together they ask for no more than the
+/// parent quantity
+pub fn validate_children(parent: &ParentOrder, children: &[ChildOrder]) -> Result<(), SplitError> {
+    let parent_order = &parent.order_common;
+    for child in children {
+        let child_id = &child.order_common.id;
+        if child.parent_id != parent_order.id {
+            return Err(SplitError::ForeignChild {
+                child_id: child_id.clone(),
+                parent_id: child.parent_id.clone(),
+                expected: parent_order.id.clone(),
+            });
+        }
+        if let Some(insert_at) = child.insert_at {
+            if insert_at < parent_order.timestamp {
+                return Err(SplitError::ScheduledBeforeParent {
+                    child_id: child_id.clone(),
+                    insert_at,
+                    timestamp: parent_order.timestamp,
+                });
+            }
+        }
+        if !child.order_common.quantity.is_positive() {
+            return Err(SplitError::ZeroQuantity {
+                child_id: child_id.clone(),
+            });
+        }
+    }
+    let allocated: Quantity = children
+        .iter()
+        .map(|child| child.order_common.quantity)
+        .sum();
+    if allocated > parent_order.quantity {
+        return Err(SplitError::OverAllocated {
+            parent_id: parent_order.id.clone(),
+            allocated,
+            quantity: parent_order.quantity,
+        });
+    }
+    Ok(())
+}
+
+/// Runs [`validate_children`] on the result of a split and hands the children back
+///
+/// An invalid split is a bug in the splitter; it is logged and repaired here through
+/// [`enforce_split`], so the built-in splitters only ever hand valid children to the
+/// `ExecutionEngine`.
+pub fn checked_split(parent: &ParentOrder, children: Vec<ChildOrder>) -> Vec<ChildOrder> {
+    match validate_children(parent, &children) {
+        Ok(()) => children,
+        Err(error) => {
+            log::warn!("invalid split of {}: {error}", parent.order_common.id);
+            // The lenient repair never fails
+            enforce_split(parent, children, false).unwrap_or_default()
+        }
+    }
+}
+
+/// Makes a split fit its parent before it is scheduled
+///
+/// With `strict` the first violation found by [`validate_children`] is returned. Otherwise
+/// foreign and empty children are dropped, children scheduled before the parent are moved to
+/// its timestamp and the children beyond the parent quantity are trimmed or dropped, with a
+/// warning for each repair.
+pub fn enforce_split(
+    parent: &ParentOrder,
+    children: Vec<ChildOrder>,
+    strict: bool,
+) -> Result<Vec<ChildOrder>, SplitError> {
+    if strict {
+        validate_children(parent, &children)?;
+        return Ok(children);
+    }
+    let parent_order = &parent.order_common;
+    let mut remaining = parent_order.quantity;
+    let mut repaired = Vec::with_capacity(children.len());
+    for mut child in children {
+        let child_id = &child.order_common.id;
+        if child.parent_id != parent_order.id {
+            log::warn!(
+                "dropping child {child_id} of {} from the split of {}",
+                child.parent_id,
+                parent_order.id
+            );
+            continue;
+        }
+        if !child.order_common.quantity.is_positive() {
+            log::warn!("dropping child {child_id} without quantity");
+            continue;
+        }
+        if !remaining.is_positive() {
+            log::warn!(
+                "dropping child {child_id}, the split of {} is already fully allocated",
+                parent_order.id
+            );
+            continue;
+        }
+        if child.order_common.quantity > remaining {
+            log::warn!(
+                "trimming child {child_id} from {} to the {remaining} left of {}",
+                child.order_common.quantity,
+                parent_order.id
+            );
+            child.order_common.quantity = remaining;
+        }
+        if let Some(insert_at) = child.insert_at {
+            if insert_at < parent_order.timestamp {
+                log::warn!(
+                    "moving child {child_id} from {insert_at} to its parent timestamp {}",
+                    parent_order.timestamp
+                );
+                child.insert_at = Some(parent_order.timestamp);
+            }
+        }
+        remaining -= child.order_common.quantity;
+        repaired.push(child);
+    }
+    Ok(repaired)
+}
+
+/// Trading signal graded by how extreme the underlying indicator is
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub side: Side,
+    /// Between 0 (indicator just at its threshold) and 1 (indicator at its extreme)
+    pub strength: f64,
+    pub reason: String,
+}
+
+impl Signal {
+    pub fn new(side: Side, strength: f64, reason: impl Into<String>) -> Self {
+        Signal {
+            side,
+            strength: strength.clamp(0.0, 1.0),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Scales child order size by signal strength
+///
+/// Signals at or above `strong_threshold` release the full parent quantity; weaker ones
+/// release `weak_fraction` of it, leaving the remainder for later signals.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrengthSizing {
+    pub strong_threshold: f64,
+    pub weak_fraction: f64,
+}
+
+impl StrengthSizing {
+    pub fn new(strong_threshold: f64, weak_fraction: f64) -> Self {
+        StrengthSizing {
+            strong_threshold,
+            weak_fraction: weak_fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Quantity to release for a signal of the given strength, rounded to `step`; at
+    /// least 1 step
+    pub fn quantity(&self, quantity: Quantity, step: Quantity, strength: f64) -> Quantity {
+        if strength >= self.strong_threshold {
+            return quantity;
+        }
+        let scaled = quantity.scale(self.weak_fraction).round_to_step(step);
+        scaled.clamp(step.min(quantity), quantity)
+    }
+}
+
+/// Single child order for a signal matching the parent side, optionally sized by strength
+pub fn signal_child_orders(
+    parent_order: &ParentOrder,
+    signal: Option<Signal>,
+    sizing: Option<StrengthSizing>,
+) -> Vec<ChildOrder> {
+    let signal = match signal {
+        Some(signal) if signal.side == parent_order.order_common.side => signal,
+        _ => return Vec::new(),
+    };
+
+    let mut order_common = parent_order.order_common.clone();
+    if let Some(sizing) = sizing {
+        order_common.quantity = sizing.quantity(
+            order_common.quantity,
+            order_common.lot_step(),
+            signal.strength,
+        );
+    }
+    vec![ChildOrder {
+        order_common,
+        strategy_id: parent_order.strategy_id.clone(),
+        parent_id: parent_order.order_common.id.clone(),
+        insert_at: Some(parent_order.order_common.timestamp),
+    }]
+}
+
+/// JSON snapshots of a strategy's internal state, used to resume after a restart without
+/// another warmup
+pub trait Snapshot: Serialize + DeserializeOwned {
+    fn snapshot(&self) -> String {
+        serde_json::to_string(self).expect("strategy state serializes to JSON")
+    }
+
+    fn restore(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Why a signal strategy emitted no child orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoOrderReason {
+    /// Not enough data has been seen yet
+    WarmingUp,
+    /// Ready, but the indicator gives no signal
+    NoSignal,
+    /// The signal points the other way from the parent order
+    SideMismatch,
+}
+
+/// Indicator strategy driven by market data updates
+pub trait SignalStrategy {
+    /// Feeds a market data update; data the strategy cannot use is ignored
+    fn update(&mut self, data: &MarketData);
+    /// Current trading signal, if any
+    fn signal(&self) -> Option<Side>;
+    /// Whether enough data has been seen to produce signals
+    fn is_ready(&self) -> bool;
+    /// Clears all accumulated data
+    fn reset(&mut self);
+
+    /// Current values of the strategy's indicators by name, e.g. to record why it signalled;
+    /// empty while warming up or for strategies that do not expose them
+    fn indicators(&self) -> BTreeMap<String, f64> {
+        BTreeMap::new()
+    }
+
+    /// Explains an empty split for the given parent order; `None` if orders would be emitted
+    fn no_order_reason(&self, parent_order: &ParentOrder) -> Option<NoOrderReason> {
+        if !self.is_ready() {
+            return Some(NoOrderReason::WarmingUp);
+        }
+        match self.signal() {
+            None => Some(NoOrderReason::NoSignal),
+            Some(side) if side != parent_order.order_common.side => {
+                Some(NoOrderReason::SideMismatch)
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// Follows the market for a `TrailingStop` parent order and releases a market child order
+/// once the trail is hit
+///
+/// The parent side is the side of the stop: a Sell parent protects a long position and
+/// trails below the highest price seen, a Buy parent protects a short and trails above the
+/// lowest. The stop only ever moves in the favorable direction.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrailingStopTracker {
+    parent_order: ParentOrder,
+    spec: TrailingSpec,
+    best_price: Option<Decimal>,
+    stop_price: Option<Decimal>,
+    triggered: bool,
+    /// Source of the child order id; not part of snapshots
+    #[serde(skip)]
+    ids: IdGenerator,
+}
+
+impl TrailingStopTracker {
+    pub fn new(parent_order: ParentOrder) -> Result<Self, ValidationError> {
+        let spec = match &parent_order.order_common.order_type {
+            OrderType::TrailingStop(spec) => spec.clone(),
+            _ => {
+                return Err(ValidationError::InconsistentFields(
+                    "Trailing stop tracker requires a TrailingStop order".to_string(),
+                ))
+            }
+        };
+        spec.validate()?;
+        Ok(TrailingStopTracker {
+            parent_order,
+            spec,
+            best_price: None,
+            stop_price: None,
+            triggered: false,
+            ids: IdGenerator::default(),
+        })
+    }
+
+    /// Take the child order id from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Most favorable price seen so far
+    pub fn best_price(&self) -> Option<Decimal> {
+        self.best_price
+    }
+
+    /// Current trigger level, `None` until the first price update
+    pub fn stop_price(&self) -> Option<Decimal> {
+        self.stop_price
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// Feeds a price; returns the market child order the first time the stop is hit
+    pub fn update(&mut self, price: Decimal) -> Option<ChildOrder> {
+        if self.triggered {
+            return None;
+        }
+
+        let side = &self.parent_order.order_common.side;
+        let improved = match (self.best_price, side) {
+            (None, _) => true,
+            (Some(best), Side::Sell) => price > best,
+            (Some(best), Side::Buy) => price < best,
+        };
+        if improved {
+            self.best_price = Some(price);
+            self.stop_price = Some(self.spec.stop_from(price, side));
+        }
+
+        let stop = self.stop_price?;
+        let hit = match side {
+            Side::Sell => price <= stop,
+            Side::Buy => price >= stop,
+        };
+        if !hit {
+            return None;
+        }
+        self.triggered = true;
+        Some(self.child_order())
+    }
+
+    /// Feeds the price carried by a market data update, if any
+    pub fn on_market_data(&mut self, data: &MarketData) -> Option<ChildOrder> {
+        let price = data.price()?;
+        self.update(decimal_from_f64(price))
+    }
+
+    fn child_order(&self) -> ChildOrder {
+        let mut order_common = self.parent_order.order_common.clone();
+        order_common.id = self.ids.next_id();
+        order_common.order_type = OrderType::Market;
+        order_common.price = None;
+        ChildOrder {
+            order_common,
+            strategy_id: self.parent_order.strategy_id.clone(),
+            parent_id: self.parent_order.order_common.id.clone(),
+            insert_at: Some(self.parent_order.order_common.timestamp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_data::Candle;
+    use crate::strategies::technical_indicator_based::{
+        BollingerBandsStrategy, HeikinAshiStrategy, MAStrategy, RSIStrategy, StochasticStrategy,
+    };
+    use std::time::SystemTime;
+
+    fn candles() -> Vec<MarketData> {
+        (0..40)
+            .map(|i| {
+                // Rising for 20 candles, then falling
+                let close = if i < 20 {
+                    100.0 + i as f64
+                } else {
+                    140.0 - i as f64
+                };
+                MarketData::Candle(Candle {
+                    timestamp: SystemTime::UNIX_EPOCH,
+                    open: close - 0.5,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    volume: 1000.0,
+                })
+            })
+            .collect()
+    }
+
+    fn strategies() -> Vec<Box<dyn SignalStrategy>> {
+        vec![
+            Box::new(HeikinAshiStrategy::new(5)),
+            Box::new(MAStrategy::new(3, 5)),
+            Box::new(RSIStrategy::new(14, 70.0, 30.0)),
+            Box::new(BollingerBandsStrategy::new(20, 2.0)),
+            Box::new(StochasticStrategy::new(14, 3, 80.0, 20.0)),
+        ]
+    }
+
+    #[test]
+    fn test_signal_strategies_share_market_data() {
+        let mut strategies = strategies();
+        assert!(strategies.iter().all(|strategy| !strategy.is_ready()));
+
+        for data in candles() {
+            for strategy in strategies.iter_mut() {
+                strategy.update(&data);
+            }
+        }
+        assert!(strategies.iter().all(|strategy| strategy.is_ready()));
+
+        // The falling leg pushes the oscillators towards oversold
+        let signals: Vec<Option<Side>> = strategies
+            .iter()
+            .map(|strategy| strategy.signal())
+            .collect();
+        assert_eq!(signals[2], Some(Side::Buy));
+
+        for strategy in strategies.iter_mut() {
+            strategy.reset();
+        }
+        assert!(strategies
+            .iter()
+            .all(|strategy| !strategy.is_ready() && strategy.signal().is_none()));
+    }
+
+    #[test]
+    fn test_strength_sizing() {
+        let sizing = StrengthSizing::new(0.5, 0.25);
+        assert_eq!(
+            sizing.quantity(Quantity::from(100), Quantity::from(1), 0.5),
+            Quantity::from(100)
+        );
+        assert_eq!(
+            sizing.quantity(Quantity::from(100), Quantity::from(1), 1.0),
+            Quantity::from(100)
+        );
+        assert_eq!(
+            sizing.quantity(Quantity::from(100), Quantity::from(1), 0.49),
+            Quantity::from(25)
+        );
+        // Never rounds a weak signal down to nothing
+        assert_eq!(
+            sizing.quantity(Quantity::from(1), Quantity::from(1), 0.0),
+            Quantity::from(1)
+        );
+        assert_eq!(
+            sizing.quantity(Quantity::ZERO, Quantity::from(1), 0.0),
+            Quantity::ZERO
+        );
+        assert_eq!(Signal::new(Side::Buy, 1.5, "clamped").strength, 1.0);
+    }
+
+    #[test]
+    fn test_no_order_reason() {
+        use crate::models::orders::{Order, TimeInForce};
+        use crate::models::Timestamp;
+
+        let parent_order = |side: Side| {
+            Order::builder()
+                .id("test_id")
+                .quantity(100)
+                .timestamp(Timestamp::from_secs(1234567890))
+                .symbol("BTC/USD")
+                .side(side)
+                .currency("USD")
+                .exchange("Binance")
+                .timeinforce(TimeInForce::GTC)
+                .build_parent("rsi")
+                .expect("valid parent order")
+        };
+
+        let mut strategy = RSIStrategy::new(3, 70.0, 30.0);
+        assert_eq!(
+            strategy.no_order_reason(&parent_order(Side::Buy)),
+            Some(NoOrderReason::WarmingUp)
+        );
+
+        for price in [100.0, 101.0, 100.0, 101.0] {
+            strategy.add_price(price);
+        }
+        assert_eq!(
+            strategy.no_order_reason(&parent_order(Side::Buy)),
+            Some(NoOrderReason::NoSignal)
+        );
+
+        for price in [99.0, 98.0, 97.0] {
+            strategy.add_price(price);
+        }
+        assert_eq!(strategy.no_order_reason(&parent_order(Side::Buy)), None);
+        assert_eq!(
+            strategy.no_order_reason(&parent_order(Side::Sell)),
+            Some(NoOrderReason::SideMismatch)
+        );
+    }
+
+    #[test]
+    fn test_split_children_inherit_account() {
+        use crate::models::orders::Order;
+        use crate::models::Timestamp;
+        use crate::strategies::market_microstructure_based::AdverseSelectionStrategy;
+
+        let parent_order = Order::builder()
+            .id("test_id")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1234567890))
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .account_id("acct-1")
+            .portfolio_id("pf-7")
+            .build_parent("inherit")
+            .expect("valid parent order");
+
+        let mut ma = MAStrategy::new(2, 3);
+        for price in [5.0, 5.0, 5.0, 15.0] {
+            ma.add_price(price);
+        }
+        let adverse_selection = AdverseSelectionStrategy::new(None);
+        let splits: [&dyn OrderSplitStrategy; 2] = [&ma, &adverse_selection];
+
+        for strategy in splits {
+            let children = strategy.split(&parent_order);
+            assert!(!children.is_empty());
+            for child in &children {
+                assert_eq!(child.order_common.account_id.as_deref(), Some("acct-1"));
+                assert_eq!(child.order_common.portfolio_id.as_deref(), Some("pf-7"));
+                assert!(child.validate_for_parent(&parent_order).is_ok());
+            }
+        }
+
+        let mut stray = ma.split(&parent_order).remove(0);
+        stray.order_common.account_id = Some("acct-2".to_string());
+        assert!(matches!(
+            stray.validate_for_parent(&parent_order),
+            Err(ValidationError::InconsistentFields(_))
+        ));
+    }
+
+    #[test]
+    fn test_order_book_updates_without_price_are_ignored() {
+        let mut strategies = strategies();
+        let empty_book = MarketData::OrderBook(Default::default());
+        for strategy in strategies.iter_mut() {
+            strategy.update(&empty_book);
+        }
+        assert!(strategies.iter().all(|strategy| !strategy.is_ready()));
+    }
+
+    fn trailing_parent(side: Side, spec: TrailingSpec) -> ParentOrder {
+        use crate::models::orders::{Order, ProductType, TimeInForce};
+        use crate::models::Timestamp;
+
+        ParentOrder {
+            order_common: Order::new(
+                "trail_id".to_string(),
+                Quantity::from(10),
+                ProductType::Spot,
+                OrderType::TrailingStop(spec),
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                None,
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "trailing".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_trailing_stop_protects_long() {
+        use crate::models::orders::OffsetType;
+        use rust_decimal_macros::dec;
+
+        let spec = TrailingSpec::new(dec!(2), OffsetType::Absolute);
+        let mut tracker = TrailingStopTracker::new(trailing_parent(Side::Sell, spec))
+            .unwrap()
+            .with_id_generator(IdGenerator::sequential("trail"));
+
+        // Ratchets up with new highs and holds on pullbacks that stay above the stop
+        let path = [
+            (dec!(100), dec!(98)),
+            (dec!(101), dec!(99)),
+            (dec!(100), dec!(99)),
+            (dec!(103), dec!(101)),
+            (dec!(105), dec!(103)),
+            (dec!(104), dec!(103)),
+        ];
+        for (price, stop) in path {
+            assert!(tracker.update(price).is_none());
+            assert_eq!(tracker.stop_price(), Some(stop));
+        }
+        assert_eq!(tracker.best_price(), Some(dec!(105)));
+
+        let child = tracker.update(dec!(102.9)).expect("stop is hit");
+        assert!(tracker.is_triggered());
+        assert_eq!(child.order_common.id, "trail-1");
+        assert_eq!(child.order_common.order_type, OrderType::Market);
+        assert_eq!(child.order_common.side, Side::Sell);
+        assert_eq!(child.order_common.quantity, Quantity::from(10));
+        assert_eq!(child.order_common.price, None);
+        assert_eq!(child.parent_id, "trail_id");
+
+        // Fires only once
+        assert!(tracker.update(dec!(90)).is_none());
+    }
+
+    #[test]
+    fn test_trailing_stop_protects_short() {
+        use crate::models::market_data::Trade;
+        use crate::models::orders::OffsetType;
+        use rust_decimal_macros::dec;
+
+        let spec = TrailingSpec::new(dec!(1), OffsetType::Percent);
+        let mut tracker = TrailingStopTracker::new(trailing_parent(Side::Buy, spec)).unwrap();
+
+        let trade = |price: f64| {
+            MarketData::Trade(Trade {
+                timestamp: SystemTime::UNIX_EPOCH,
+                price,
+                size: 1.0,
+                side: Side::Sell,
+            })
+        };
+
+        for (price, stop) in [
+            (100.0, dec!(101)),
+            (98.0, dec!(98.98)),
+            (97.0, dec!(97.97)),
+            (97.5, dec!(97.97)),
+        ] {
+            assert!(tracker.on_market_data(&trade(price)).is_none());
+            assert_eq!(tracker.stop_price(), Some(stop));
+        }
+
+        let child = tracker.on_market_data(&trade(97.97)).expect("stop is hit");
+        assert_eq!(child.order_common.side, Side::Buy);
+        assert_eq!(child.order_common.order_type, OrderType::Market);
+    }
+
+    #[test]
+    fn test_trailing_stop_rejects_invalid_specs() {
+        use crate::models::orders::OffsetType;
+        use rust_decimal_macros::dec;
+
+        for spec in [
+            TrailingSpec::new(Decimal::ZERO, OffsetType::Absolute),
+            TrailingSpec::new(dec!(-1), OffsetType::Absolute),
+            TrailingSpec::new(dec!(100), OffsetType::Percent),
+        ] {
+            assert!(spec.validate().is_err());
+            assert!(TrailingStopTracker::new(trailing_parent(Side::Sell, spec)).is_err());
+        }
+
+        let mut market_parent =
+            trailing_parent(Side::Sell, TrailingSpec::new(dec!(1), OffsetType::Absolute));
+        market_parent.order_common.order_type = OrderType::Market;
+        assert!(TrailingStopTracker::new(market_parent).is_err());
+    }
+
+    fn split_parent() -> ParentOrder {
+        use crate::models::orders::Order;
+        use crate::models::Timestamp;
+
+        Order::builder()
+            .id("parent-1")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("split")
+            .unwrap()
+    }
+
+    fn split_child(id: &str, quantity: u32, insert_at: u64) -> ChildOrder {
+        use crate::models::Timestamp;
+
+        let mut order = split_parent().order_common;
+        order.id = id.to_string();
+        order.quantity = Quantity::from(quantity);
+        order.into_child("split", "parent-1", Some(Timestamp::from_secs(insert_at)))
+    }
+
+    #[test]
+    fn test_validate_children_accepts_valid_split() {
+        let parent = split_parent();
+        let mut children = vec![
+            split_child("child-1", 60, 1622512800),
+            split_child("child-2", 40, 1622512860),
+        ];
+        children[1].insert_at = None;
+        assert_eq!(validate_children(&parent, &children), Ok(()));
+        assert_eq!(validate_children(&parent, &[]), Ok(()));
+        assert_eq!(checked_split(&parent, children.clone()).len(), 2);
+    }
+
+    #[test]
+    fn test_validate_children_rejects_foreign_child() {
+        let mut stray = split_child("child-1", 10, 1622512800);
+        stray.parent_id = "parent-2".to_string();
+        assert_eq!(
+            validate_children(&split_parent(), &[stray]),
+            Err(SplitError::ForeignChild {
+                child_id: "child-1".to_string(),
+                parent_id: "parent-2".to_string(),
+                expected: "parent-1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_children_rejects_child_before_parent() {
+        use crate::models::Timestamp;
+
+        let children = [
+            split_child("child-1", 10, 1622512800),
+            split_child("child-2", 10, 1622512799),
+        ];
+        assert_eq!(
+            validate_children(&split_parent(), &children),
+            Err(SplitError::ScheduledBeforeParent {
+                child_id: "child-2".to_string(),
+                insert_at: Timestamp::from_secs(1622512799),
+                timestamp: Timestamp::from_secs(1622512800),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_children_rejects_over_allocation() {
+        let children = [
+            split_child("child-1", 60, 1622512800),
+            split_child("child-2", 41, 1622512800),
+        ];
+        assert_eq!(
+            validate_children(&split_parent(), &children),
+            Err(SplitError::OverAllocated {
+                parent_id: "parent-1".to_string(),
+                allocated: Quantity::from(101),
+                quantity: Quantity::from(100),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_children_rejects_zero_quantity() {
+        let children = [
+            split_child("child-1", 60, 1622512800),
+            split_child("child-2", 0, 1622512800),
+        ];
+        assert_eq!(
+            validate_children(&split_parent(), &children),
+            Err(SplitError::ZeroQuantity {
+                child_id: "child-2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_checked_split_repairs_invalid_split() {
+        let children = vec![
+            split_child("child-1", 101, 1622512800),
+            split_child("child-2", 10, 1622512860),
+        ];
+        let checked = checked_split(&split_parent(), children);
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].order_common.quantity, Quantity::from(100));
+        assert_eq!(validate_children(&split_parent(), &checked), Ok(()));
+    }
+
+    #[test]
+    fn test_enforce_split_strict_rejects_invalid_split() {
+        let children = vec![
+            split_child("child-1", 60, 1622512800),
+            split_child("child-2", 41, 1622512800),
+        ];
+        assert!(matches!(
+            enforce_split(&split_parent(), children, true),
+            Err(SplitError::OverAllocated { .. })
+        ));
+        let valid = vec![split_child("child-1", 60, 1622512800)];
+        assert_eq!(
+            enforce_split(&split_parent(), valid, true).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_enforce_split_lenient_repairs_invalid_split() {
+        use crate::models::Timestamp;
+
+        let mut stray = split_child("child-0", 10, 1622512800);
+        stray.parent_id = "parent-2".to_string();
+        let children = vec![
+            stray,
+            split_child("child-1", 60, 1622512799),
+            split_child("child-2", 0, 1622512800),
+            split_child("child-3", 50, 1622512860),
+            split_child("child-4", 10, 1622512860),
+        ];
+        let repaired = enforce_split(&split_parent(), children, false).unwrap();
+        assert_eq!(repaired.len(), 2);
+        assert_eq!(repaired[0].order_common.id, "child-1");
+        assert_eq!(
+            repaired[0].insert_at,
+            Some(Timestamp::from_secs(1622512800))
+        );
+        assert_eq!(repaired[1].order_common.id, "child-3");
+        assert_eq!(repaired[1].order_common.quantity, Quantity::from(40));
+        assert_eq!(validate_children(&split_parent(), &repaired), Ok(()));
+    }
+
+    #[test]
+    fn test_split_seeds_resume_from_a_snapshot() {
+        let seeds = SplitSeeds::new(7);
+        let first = seeds.next();
+        assert_ne!(seeds.next(), first);
+
+        let json = serde_json::to_string(&seeds).unwrap();
+        assert_eq!(json, r#"{"seed":7,"splits":2}"#);
+        let restored: SplitSeeds = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.last(), seeds.last());
+        assert_eq!(restored.next(), seeds.next());
+    }
 }
@@ -47,3 +47,347 @@ THE SOFTWARE.
    market making and the role of bid-ask spreads in managing inventory risk. (Chapter 2 discusses
    inventory models and bid-ask strategies in detail).
 ******************************************************************************/
+
+/*
+Avellaneda-Stoikov
+The quotes are derived from "High-frequency trading in a limit order book" by Marco Avellaneda
+and Sasha Stoikov. The market maker quotes symmetrically around a reservation price rather than
+the mid price:
+
+   reservation = mid - inventory * gamma * sigma^2 * (T - t)
+   spread      = gamma * sigma^2 * (T - t) + (2 / gamma) * ln(1 + gamma / k)
+
+A long inventory pushes the reservation price below the mid, lowering both quotes so that the ask
+is more likely to be lifted and the bid less likely to be hit.
+*/
+
+use crate::config::StrategyConfig;
+use crate::models::decimal::{decimal_from_f64, decimal_to_f64};
+use crate::models::orders::{OrderType, Side};
+use crate::models::{ChildOrder, IdGenerator, ParentOrder, Quantity};
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use serde::{Deserialize, Serialize};
+
+/// Configuration parameters for the Avellaneda-Stoikov quoting strategy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidAskQuotingConfig {
+    /// Risk aversion coefficient (gamma), must be greater than zero
+    pub risk_aversion: f64,
+    /// Volatility of the mid price (sigma) per unit of time
+    pub volatility: f64,
+    /// Order book liquidity parameter (k), must be greater than zero
+    pub order_book_liquidity: f64,
+    /// Half-spread used when the volatility is zero
+    pub fallback_half_spread: f64,
+    /// Absolute inventory above which no more inventory is accumulated
+    pub max_inventory: f64,
+}
+
+impl Default for BidAskQuotingConfig {
+    fn default() -> Self {
+        Self {
+            risk_aversion: 0.1,
+            volatility: 2.0,
+            order_book_liquidity: 1.5,
+            fallback_half_spread: 0.01,
+            max_inventory: 100.0,
+        }
+    }
+}
+
+impl StrategyConfig for BidAskQuotingConfig {
+    fn validate(&self) -> Result<(), String> {
+        // The spread divides by gamma and takes ln(1 + gamma / k)
+        if self.risk_aversion <= 0.0 {
+            return Err(format!(
+                "risk_aversion must be greater than zero, got {}",
+                self.risk_aversion
+            ));
+        }
+        if self.order_book_liquidity <= 0.0 {
+            return Err(format!(
+                "order_book_liquidity must be greater than zero, got {}",
+                self.order_book_liquidity
+            ));
+        }
+        if self.volatility < 0.0 {
+            return Err(format!(
+                "volatility must not be negative, got {}",
+                self.volatility
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Avellaneda-Stoikov bid/ask quoting strategy
+pub struct BidAskQuotingStrategy {
+    /// Configuration parameters
+    config: BidAskQuotingConfig,
+    /// Last observed mid price
+    mid_price: Option<f64>,
+    /// Current signed inventory
+    inventory: f64,
+    /// Remaining fraction of the trading horizon (T - t)
+    time_remaining: f64,
+    /// Source of child order ids
+    ids: IdGenerator,
+}
+
+impl BidAskQuotingStrategy {
+    /// Create a new instance of the quoting strategy, rejecting parameters the quotes are not
+    /// defined for
+    pub fn new(config: BidAskQuotingConfig) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            mid_price: None,
+            inventory: 0.0,
+            time_remaining: 1.0,
+            ids: IdGenerator::default(),
+        })
+    }
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Update the market and inventory state used by `split`
+    pub fn update(&mut self, mid: f64, inventory: f64, time_remaining: f64) {
+        self.mid_price = Some(mid);
+        self.inventory = inventory;
+        self.time_remaining = time_remaining.max(0.0);
+    }
+
+    /// Reservation price: the mid price shifted against the current inventory
+    pub fn reservation_price(&self, mid: f64, inventory: f64, time_remaining: f64) -> f64 {
+        let variance = self.config.volatility.powi(2);
+        mid - inventory * self.config.risk_aversion * variance * time_remaining.max(0.0)
+    }
+
+    /// Optimal total spread around the reservation price
+    pub fn optimal_spread(&self, time_remaining: f64) -> f64 {
+        if self.config.volatility == 0.0 {
+            return 2.0 * self.config.fallback_half_spread;
+        }
+        let gamma = self.config.risk_aversion;
+        let variance = self.config.volatility.powi(2);
+        gamma * variance * time_remaining.max(0.0)
+            + (2.0 / gamma) * (1.0 + gamma / self.config.order_book_liquidity).ln()
+    }
+
+    /// Compute the optimal (bid, ask) quotes
+    pub fn quotes(&self, mid: f64, inventory: f64, time_remaining: f64) -> (f64, f64) {
+        let reservation = self.reservation_price(mid, inventory, time_remaining);
+        let half_spread = self.optimal_spread(time_remaining) / 2.0;
+        (reservation - half_spread, reservation + half_spread)
+    }
+
+    /// Create a limit child order for `quantity` at the given quote level
+    fn quote_order(
+        &self,
+        parent_order: &ParentOrder,
+        side: Side,
+        price: f64,
+        quantity: Quantity,
+    ) -> ChildOrder {
+        let mut order = parent_order.order_common.clone();
+        order.id = self.ids.next_id();
+        order.side = side;
+        order.quantity = quantity;
+        order.order_type = OrderType::Limit;
+        order.price = Some(decimal_from_f64(price));
+        ChildOrder {
+            order_common: order,
+            strategy_id: parent_order.strategy_id.clone(),
+            parent_id: parent_order.order_common.id.clone(),
+            insert_at: Some(parent_order.order_common.timestamp),
+        }
+    }
+}
+
+/// Produce a bid and an ask limit order around the reservation price. The side that would
+/// push the inventory beyond `max_inventory` is not quoted.
+impl OrderSplitStrategy for BidAskQuotingStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let parent_price = parent_order.order_common.price.map(decimal_to_f64);
+        let mid = match self.mid_price.or(parent_price) {
+            Some(mid) => mid,
+            None => return Vec::new(),
+        };
+        let (bid, ask) = self.quotes(mid, self.inventory, self.time_remaining);
+
+        let mut quotes = Vec::new();
+        if self.inventory < self.config.max_inventory {
+            quotes.push((Side::Buy, bid));
+        }
+        if self.inventory > -self.config.max_inventory {
+            quotes.push((Side::Sell, ask));
+        }
+        // The quotes share the parent quantity, so the set never asks for more than it
+        let quantities = parent_order
+            .order_common
+            .quantity
+            .split(quotes.len(), Quantity::ZERO);
+        let child_orders = quotes
+            .into_iter()
+            .zip(quantities)
+            .map(|((side, price), quantity)| self.quote_order(parent_order, side, price, quantity))
+            .collect();
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use rust_decimal_macros::dec;
+
+    fn parent_order() -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "quote-1".to_string(),
+                Quantity::from(10),
+                ProductType::Spot,
+                OrderType::Limit,
+                Some(dec!(100.0)),
+                Timestamp::from_millis(1621500000000),
+                None,
+                "BTC/USD".to_string(),
+                Side::Buy,
+                "USD".to_string(),
+                Some("BINANCE".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "bid_ask_quoting".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_flat_inventory_quotes_symmetric() {
+        let strategy = BidAskQuotingStrategy::new(BidAskQuotingConfig::default()).unwrap();
+        let (bid, ask) = strategy.quotes(100.0, 0.0, 1.0);
+
+        assert!(bid < 100.0 && ask > 100.0);
+        assert!(((100.0 - bid) - (ask - 100.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inventory_skews_quotes() {
+        let strategy = BidAskQuotingStrategy::new(BidAskQuotingConfig::default()).unwrap();
+        let (flat_bid, flat_ask) = strategy.quotes(100.0, 0.0, 1.0);
+        let (long_bid, long_ask) = strategy.quotes(100.0, 5.0, 1.0);
+        let (short_bid, short_ask) = strategy.quotes(100.0, -5.0, 1.0);
+
+        // A long book lowers both quotes, a short book raises them
+        assert!(long_bid < flat_bid && long_ask < flat_ask);
+        assert!(short_bid > flat_bid && short_ask > flat_ask);
+        // The spread itself does not depend on the inventory
+        assert!(((long_ask - long_bid) - (flat_ask - flat_bid)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_spread_widens_with_volatility() {
+        let calm = BidAskQuotingStrategy::new(BidAskQuotingConfig {
+            volatility: 1.0,
+            ..Default::default()
+        })
+        .unwrap();
+        let volatile = BidAskQuotingStrategy::new(BidAskQuotingConfig {
+            volatility: 3.0,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let (calm_bid, calm_ask) = calm.quotes(100.0, 0.0, 1.0);
+        let (volatile_bid, volatile_ask) = volatile.quotes(100.0, 0.0, 1.0);
+        assert!(volatile_ask - volatile_bid > calm_ask - calm_bid);
+    }
+
+    #[test]
+    fn test_zero_volatility_uses_fallback_half_spread() {
+        let strategy = BidAskQuotingStrategy::new(BidAskQuotingConfig {
+            volatility: 0.0,
+            fallback_half_spread: 0.05,
+            ..Default::default()
+        })
+        .unwrap();
+        let (bid, ask) = strategy.quotes(100.0, 10.0, 1.0);
+
+        assert!((bid - 99.95).abs() < 1e-9);
+        assert!((ask - 100.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_parameters_without_quotes() {
+        for config in [
+            BidAskQuotingConfig {
+                risk_aversion: 0.0,
+                ..Default::default()
+            },
+            BidAskQuotingConfig {
+                order_book_liquidity: -1.5,
+                ..Default::default()
+            },
+            BidAskQuotingConfig {
+                volatility: -2.0,
+                ..Default::default()
+            },
+        ] {
+            assert!(BidAskQuotingStrategy::new(config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_split_produces_paired_limit_orders() {
+        let mut strategy = BidAskQuotingStrategy::new(BidAskQuotingConfig::default()).unwrap();
+        strategy.update(100.0, 0.0, 1.0);
+        let child_orders = strategy.split(&parent_order());
+        let (bid, ask) = strategy.quotes(100.0, 0.0, 1.0);
+
+        assert_eq!(child_orders.len(), 2);
+        assert_eq!(child_orders[0].order_common.side, Side::Buy);
+        assert_eq!(
+            child_orders[0].order_common.price,
+            Some(decimal_from_f64(bid))
+        );
+        assert_eq!(child_orders[1].order_common.side, Side::Sell);
+        assert_eq!(
+            child_orders[1].order_common.price,
+            Some(decimal_from_f64(ask))
+        );
+        for child in &child_orders {
+            assert_eq!(child.parent_id, "quote-1");
+            assert_eq!(child.order_common.quantity, Quantity::from(5));
+        }
+    }
+
+    #[test]
+    fn test_split_stops_accumulating_at_max_inventory() {
+        let mut strategy = BidAskQuotingStrategy::new(BidAskQuotingConfig {
+            max_inventory: 10.0,
+            ..Default::default()
+        })
+        .unwrap();
+
+        strategy.update(100.0, 10.0, 1.0);
+        let child_orders = strategy.split(&parent_order());
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].order_common.side, Side::Sell);
+
+        strategy.update(100.0, -10.0, 1.0);
+        let child_orders = strategy.split(&parent_order());
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].order_common.side, Side::Buy);
+    }
+}
@@ -50,3 +50,347 @@ microstructure, including hedging strategies and their applications in financial
 These references will provide you with detailed explanations and further insights into how these
 strategies are implemented and the theoretical foundations behind them.
 ******************************************************************************/
+
+/*
+Delta Hedging
+The delta of an option position is the sensitivity of its value to the price of the underlying.
+Holding -delta units of the underlying per option makes the combined book insensitive to small
+price moves. Delta changes with spot, volatility, and time, so the hedge is only rebalanced when
+the net delta drifts outside a tolerance band to avoid paying the spread on every tick.
+
+Delta is computed with Black-Scholes (no dividends):
+   d1 = (ln(S / K) + (r + sigma^2 / 2) * T) / (sigma * sqrt(T))
+   delta_call = N(d1), delta_put = N(d1) - 1
+*/
+
+use crate::models::orders::{Options, Order, ProductType, Side};
+use crate::models::{ChildOrder, IdGenerator, ParentOrder, Quantity, Timestamp};
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use serde::{Deserialize, Serialize};
+
+/// Configuration parameters for the delta hedging strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaHedgingConfig {
+    /// Absolute net delta (in underlying units) tolerated before rebalancing
+    pub rebalance_band: f64,
+    /// Continuously compounded risk-free rate
+    pub risk_free_rate: f64,
+    /// Underlying units per option contract
+    pub contract_multiplier: f64,
+}
+
+impl Default for DeltaHedgingConfig {
+    fn default() -> Self {
+        Self {
+            rebalance_band: 10.0,
+            risk_free_rate: 0.0,
+            contract_multiplier: 1.0,
+        }
+    }
+}
+
+/// Delta hedging strategy for an options position
+pub struct DeltaHedgingStrategy {
+    /// Configuration parameters
+    config: DeltaHedgingConfig,
+    /// The options order representing the position to hedge
+    option_order: Order,
+    /// Last observed spot price of the underlying
+    spot: Option<f64>,
+    /// Last observed implied volatility
+    volatility: f64,
+    /// Last observed time in milliseconds since UNIX epoch
+    now: u64,
+    /// Signed position held in the underlying
+    hedge_position: f64,
+    /// Source of child order ids
+    ids: IdGenerator,
+}
+
+impl DeltaHedgingStrategy {
+    /// Create a new delta hedging strategy for the given options order
+    pub fn new(option_order: Order, config: DeltaHedgingConfig) -> Result<Self, String> {
+        if option_order.options_opt.is_none() {
+            return Err("Order to hedge must carry options fields".to_string());
+        }
+        Ok(Self {
+            config,
+            option_order,
+            spot: None,
+            volatility: 0.0,
+            now: 0,
+            hedge_position: 0.0,
+            ids: IdGenerator::default(),
+        })
+    }
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    fn options(&self) -> &Options {
+        self.option_order
+            .options_opt
+            .as_ref()
+            .expect("options fields checked at construction")
+    }
+
+    /// Black-Scholes delta of a single option contract
+    pub fn option_delta(&self) -> Option<f64> {
+        let spot = self.spot?;
+        let greeks =
+            self.options()
+                .greeks_at(spot, self.volatility, self.config.risk_free_rate, self.now);
+        Some(greeks.delta)
+    }
+
+    /// Delta of the whole options position in underlying units
+    pub fn position_delta(&self) -> Option<f64> {
+        let sign = match self.option_order.side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        };
+        self.option_delta().map(|delta| {
+            delta * self.option_order.quantity.to_f64() * self.config.contract_multiplier * sign
+        })
+    }
+
+    /// Net delta of the options position plus the underlying hedge
+    pub fn net_delta(&self) -> Option<f64> {
+        self.position_delta()
+            .map(|delta| delta + self.hedge_position)
+    }
+
+    /// Signed quantity of underlying to trade to neutralize the delta, or zero when the net
+    /// delta is still inside the rebalance band
+    pub fn required_hedge(&self) -> f64 {
+        match self.net_delta() {
+            Some(net_delta) if net_delta.abs() > self.config.rebalance_band => (-net_delta).round(),
+            _ => 0.0,
+        }
+    }
+
+    /// Update market inputs and return the hedge quantity required, if any
+    pub fn on_price_update(&mut self, spot: f64, vol: f64, now: u64) -> Option<f64> {
+        self.spot = Some(spot);
+        self.volatility = vol;
+        self.now = now;
+        let hedge = self.required_hedge();
+        if hedge == 0.0 {
+            None
+        } else {
+            Some(hedge)
+        }
+    }
+
+    /// Update the underlying position after a hedge order is executed
+    pub fn on_hedge_executed(&mut self, order: &Order) {
+        match order.side {
+            Side::Buy => self.hedge_position += order.quantity.to_f64(),
+            Side::Sell => self.hedge_position -= order.quantity.to_f64(),
+        }
+    }
+
+    /// Current signed position in the underlying
+    pub fn hedge_position(&self) -> f64 {
+        self.hedge_position
+    }
+}
+
+/// Split the hedge parent into a single underlying order sized to neutralize the delta. The
+/// child is only emitted when the required direction matches the parent side.
+impl OrderSplitStrategy for DeltaHedgingStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let hedge = self.required_hedge();
+        let side = if hedge > 0.0 {
+            Side::Buy
+        } else if hedge < 0.0 {
+            Side::Sell
+        } else {
+            return Vec::new();
+        };
+        if side != parent_order.order_common.side {
+            return Vec::new();
+        }
+
+        let mut order = parent_order.order_common.clone();
+        order.id = self.ids.next_id();
+        order.product_type = ProductType::Spot;
+        order.options_opt = None;
+        order.quantity = Quantity::from_f64(hedge.abs()).min(parent_order.order_common.quantity);
+        if order.quantity.is_zero() {
+            return Vec::new();
+        }
+
+        let child_order = ChildOrder {
+            order_common: order,
+            strategy_id: parent_order.strategy_id.clone(),
+            parent_id: parent_order.order_common.id.clone(),
+            insert_at: Some(Timestamp::from_millis(
+                self.now
+                    .max(parent_order.order_common.timestamp.as_millis()),
+            )),
+        };
+        checked_split(parent_order, vec![child_order])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{OptionType, OrderType, TimeInForce};
+    use rust_decimal_macros::dec;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn option_order(option_type: OptionType, side: Side, quantity: u32, days: u64) -> Order {
+        Order::new(
+            "option-1".to_string(),
+            Quantity::from(quantity),
+            ProductType::Options,
+            OrderType::Limit,
+            Some(dec!(5.0)),
+            Timestamp::from_millis(NOW),
+            None,
+            "AAPL-C-100".to_string(),
+            side,
+            "USD".to_string(),
+            Some("CBOE".to_string()),
+            Some(TimeInForce::GTC),
+            None,
+            Some(Options {
+                strike_price: dec!(100.0),
+                option_type,
+                expiry_date: NOW + days * DAY_MS,
+            }),
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn hedge_parent(side: Side, quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "hedge-1".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_millis(NOW),
+                None,
+                "AAPL".to_string(),
+                side,
+                "USD".to_string(),
+                Some("NASDAQ".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "delta_hedging".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_requires_options_fields() {
+        let mut order = option_order(OptionType::Call, Side::Buy, 1, 30);
+        order.options_opt = None;
+        assert!(DeltaHedgingStrategy::new(order, DeltaHedgingConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_call_near_expiry_delta_approaches_one() {
+        let order = option_order(OptionType::Call, Side::Buy, 1, 30);
+        let mut strategy = DeltaHedgingStrategy::new(order, DeltaHedgingConfig::default()).unwrap();
+
+        strategy.on_price_update(120.0, 0.2, NOW);
+        let thirty_days = strategy.option_delta().unwrap();
+        strategy.on_price_update(120.0, 0.2, NOW + 29 * DAY_MS);
+        let one_day = strategy.option_delta().unwrap();
+        strategy.on_price_update(120.0, 0.2, NOW + 30 * DAY_MS);
+        let expired = strategy.option_delta().unwrap();
+
+        assert!(one_day > thirty_days);
+        assert!(one_day > 0.9999);
+        assert_eq!(expired, 1.0);
+    }
+
+    #[test]
+    fn test_at_the_money_put_delta() {
+        let order = option_order(OptionType::Put, Side::Buy, 1, 365);
+        let mut strategy = DeltaHedgingStrategy::new(order, DeltaHedgingConfig::default()).unwrap();
+        strategy.on_price_update(100.0, 0.2, NOW);
+
+        // d1 = 0.1, N(0.1) = 0.539828
+        let delta = strategy.option_delta().unwrap();
+        assert!((delta - (0.539828 - 1.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_no_hedge_inside_band() {
+        let order = option_order(OptionType::Call, Side::Buy, 1, 30);
+        let config = DeltaHedgingConfig {
+            rebalance_band: 1.0,
+            ..Default::default()
+        };
+        let mut strategy = DeltaHedgingStrategy::new(order, config).unwrap();
+
+        assert!(strategy.on_price_update(100.0, 0.2, NOW).is_none());
+        assert!(strategy.split(&hedge_parent(Side::Sell, 1000)).is_empty());
+    }
+
+    #[test]
+    fn test_hedge_size_equals_delta_times_quantity() {
+        let order = option_order(OptionType::Call, Side::Buy, 10, 30);
+        let config = DeltaHedgingConfig {
+            rebalance_band: 5.0,
+            contract_multiplier: 100.0,
+            ..Default::default()
+        };
+        let mut strategy = DeltaHedgingStrategy::new(order, config).unwrap();
+
+        let hedge = strategy.on_price_update(100.0, 0.2, NOW).unwrap();
+        let expected = strategy.option_delta().unwrap() * 10.0 * 100.0;
+        assert!((hedge + expected).abs() <= 0.5);
+
+        let child_orders = strategy.split(&hedge_parent(Side::Sell, 10_000));
+        assert_eq!(child_orders.len(), 1);
+        let child = &child_orders[0];
+        assert_eq!(child.order_common.side, Side::Sell);
+        assert_eq!(
+            child.order_common.quantity,
+            Quantity::from(expected.round() as u32)
+        );
+        assert_eq!(child.parent_id, "hedge-1");
+
+        // Once executed, the book is delta neutral and no further hedge is required
+        strategy.on_hedge_executed(&child.order_common);
+        assert!(strategy.net_delta().unwrap().abs() <= 0.5);
+        assert!(strategy.split(&hedge_parent(Side::Sell, 10_000)).is_empty());
+    }
+
+    #[test]
+    fn test_short_put_hedge_direction() {
+        let order = option_order(OptionType::Put, Side::Sell, 10, 30);
+        let config = DeltaHedgingConfig {
+            contract_multiplier: 100.0,
+            ..Default::default()
+        };
+        let mut strategy = DeltaHedgingStrategy::new(order, config).unwrap();
+
+        // A short put is long delta, so the hedge sells the underlying
+        let hedge = strategy.on_price_update(95.0, 0.3, NOW).unwrap();
+        assert!(strategy.position_delta().unwrap() > 0.0);
+        assert!(hedge < 0.0);
+        assert!(strategy.split(&hedge_parent(Side::Buy, 1000)).is_empty());
+        assert_eq!(strategy.split(&hedge_parent(Side::Sell, 1000)).len(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Inventory Management
+Market-making and execution strategies both need to shade their behaviour based on the current
+inventory relative to a limit. The utilization of the limit is the signed position divided by the
+maximum position, clamped to [-1, 1]. Clips that would grow the position in the direction it is
+already loaded are shrunk linearly as utilization approaches 1, while clips that reduce the risk
+are enlarged by the same amount.
+
+Example: with a limit of 100 and a long position of 50 (utilization 0.5), a base clip of 10 is
+sized to 5 for buys and 15 for sells. At a long position of 100 buys are suppressed entirely.
+*/
+
+use crate::models::orders::{Order, Side};
+use crate::models::Quantity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tracks signed positions per symbol against a position limit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InventoryManager {
+    /// Default absolute position limit applied to every symbol
+    max_position: f64,
+    /// Per-symbol position limit overrides
+    limits: HashMap<String, f64>,
+    /// Signed position per symbol
+    positions: HashMap<String, f64>,
+}
+
+impl InventoryManager {
+    /// Create a new inventory manager with the given default position limit
+    pub fn new(max_position: f64) -> Self {
+        Self {
+            max_position,
+            limits: HashMap::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Override the position limit for a single symbol
+    pub fn set_limit(&mut self, symbol: &str, max_position: f64) {
+        self.limits.insert(symbol.to_string(), max_position);
+    }
+
+    /// Position limit applied to the symbol
+    pub fn limit(&self, symbol: &str) -> f64 {
+        self.limits
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.max_position)
+    }
+
+    /// Signed position currently held in the symbol
+    pub fn position(&self, symbol: &str) -> f64 {
+        self.positions.get(symbol).copied().unwrap_or(0.0)
+    }
+
+    /// Overwrite the position held in the symbol
+    pub fn set_position(&mut self, symbol: &str, position: f64) {
+        self.positions.insert(symbol.to_string(), position);
+    }
+
+    /// Update the position after an order is executed
+    pub fn on_order_executed(&mut self, order: &Order) {
+        let quantity = order.quantity.to_f64();
+        let position = self.positions.entry(order.symbol.clone()).or_insert(0.0);
+        match order.side {
+            Side::Buy => *position += quantity,
+            Side::Sell => *position -= quantity,
+        }
+    }
+
+    /// Position relative to the limit, clamped to [-1, 1]
+    pub fn utilization(&self, symbol: &str) -> f64 {
+        let limit = self.limit(symbol);
+        if limit <= 0.0 {
+            return 0.0;
+        }
+        (self.position(symbol) / limit).clamp(-1.0, 1.0)
+    }
+
+    /// Multiplier applied to clips on the given side: `1 - |u|` when the clip adds to the
+    /// loaded side and `1 + |u|` when it reduces the risk
+    pub fn skew_factor(&self, symbol: &str, side: &Side) -> f64 {
+        let utilization = self.utilization(symbol);
+        let direction = match side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        };
+        1.0 - direction * utilization
+    }
+
+    /// Skew a base clip size according to the current utilization
+    pub fn skew_quantity(&self, symbol: &str, base_qty: Quantity, side: &Side) -> Quantity {
+        base_qty
+            .scale(self.skew_factor(symbol, side))
+            .round_to_step(base_qty.unit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{OrderType, ProductType, TimeInForce};
+    use crate::models::Timestamp;
+
+    fn order(side: Side, quantity: u32) -> Order {
+        Order::new(
+            "order-1".to_string(),
+            Quantity::from(quantity),
+            ProductType::Spot,
+            OrderType::Market,
+            None,
+            Timestamp::from_millis(1621500000000),
+            None,
+            "BTC/USD".to_string(),
+            side,
+            "USD".to_string(),
+            Some("BINANCE".to_string()),
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_positions_follow_executions() {
+        let mut manager = InventoryManager::new(100.0);
+        manager.on_order_executed(&order(Side::Buy, 30));
+        manager.on_order_executed(&order(Side::Sell, 10));
+
+        assert_eq!(manager.position("BTC/USD"), 20.0);
+        assert_eq!(manager.position("ETH/USD"), 0.0);
+        assert_eq!(manager.utilization("BTC/USD"), 0.2);
+    }
+
+    #[test]
+    fn test_utilization_is_clamped() {
+        let mut manager = InventoryManager::new(100.0);
+        manager.set_position("BTC/USD", 250.0);
+        assert_eq!(manager.utilization("BTC/USD"), 1.0);
+        manager.set_position("BTC/USD", -250.0);
+        assert_eq!(manager.utilization("BTC/USD"), -1.0);
+    }
+
+    #[test]
+    fn test_skew_at_zero_utilization() {
+        let manager = InventoryManager::new(100.0);
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Buy),
+            Quantity::from(10)
+        );
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Sell),
+            Quantity::from(10)
+        );
+    }
+
+    #[test]
+    fn test_skew_at_half_utilization() {
+        let mut manager = InventoryManager::new(100.0);
+        manager.set_position("BTC/USD", 50.0);
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Buy),
+            Quantity::from(5)
+        );
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Sell),
+            Quantity::from(15)
+        );
+
+        manager.set_position("BTC/USD", -50.0);
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Buy),
+            Quantity::from(15)
+        );
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Sell),
+            Quantity::from(5)
+        );
+    }
+
+    #[test]
+    fn test_skew_at_full_utilization() {
+        let mut manager = InventoryManager::new(100.0);
+        manager.set_position("BTC/USD", 100.0);
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Buy),
+            Quantity::ZERO
+        );
+        assert_eq!(
+            manager.skew_quantity("BTC/USD", Quantity::from(10), &Side::Sell),
+            Quantity::from(20)
+        );
+    }
+
+    #[test]
+    fn test_per_symbol_limit() {
+        let mut manager = InventoryManager::new(100.0);
+        manager.set_limit("ETH/USD", 10.0);
+        manager.set_position("ETH/USD", 5.0);
+        manager.set_position("BTC/USD", 5.0);
+
+        assert_eq!(manager.utilization("ETH/USD"), 0.5);
+        assert_eq!(manager.utilization("BTC/USD"), 0.05);
+    }
+}
@@ -25,5 +25,10 @@ THE SOFTWARE.
    Email: jb@taunais.com
    Date: 25/5/24
 ******************************************************************************/
-mod bid_ask_quoting;
-mod hedging;
+pub mod bid_ask_quoting;
+pub mod hedging;
+pub mod inventory_manager;
+
+pub use bid_ask_quoting::*;
+pub use hedging::*;
+pub use inventory_manager::*;
@@ -44,19 +44,22 @@ various aspects of adverse selection and its impact on trading strategies.
 (Chapter 4 provides insights into adverse selection and methods to manage it).
 */
 
-use std::collections::VecDeque;
-use std::time::{SystemTime, Duration};
-use serde::{Deserialize, Serialize};
+use crate::models::decimal::decimal_to_f64;
+use crate::models::fills::{Fill, Liquidity};
+use crate::models::market_data::epoch_millis;
+pub use crate::models::market_data::{Candle, MarketData, OrderBook, Ticker, Trade};
+use crate::models::orders::{OrderType, Side};
+use crate::models::{ChildOrder, IdGenerator, ParentOrder, Timestamp, TradingCalendar};
+use crate::portfolio::PositionTracker;
+use crate::strategies::common_strategies::Snapshot;
+use crate::strategies::{checked_split, OrderSplitStrategy, SplitSeeds};
 use rand::Rng;
-use crate::models::{ChildOrder, ParentOrder};
-use crate::strategies::OrderSplitStrategy;
-use crate::models::orders::Side;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::SystemTime;
 
 // 导入项目中已有的模块
-use crate::models::orders::{Order, OrderType as ModelOrderType, ProductType, TimeInForce};
-use crate::models::child_orders::ChildOrder as ModelChildOrder;
-use crate::models::parent_orders::ParentOrder as ModelParentOrder;
-use crate::strategies::common_strategies::OrderSplitStrategy as CommonOrderSplitStrategy;
+use crate::models::orders::Order;
 
 /// Strategy trait and related types
 pub trait Strategy {
@@ -71,7 +74,7 @@ pub trait Strategy {
 }
 
 /// Strategy state
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StrategyState {
     Idle,
     Running,
@@ -96,61 +99,6 @@ pub enum StrategySignal {
     },
 }
 
-/// Market data types
-#[derive(Debug, Clone)]
-pub enum MarketData {
-    Trade(Trade),
-    OrderBook(OrderBook),
-    Ticker(Ticker),
-}
-
-/// Trade data
-#[derive(Debug, Clone)]
-pub struct Trade {
-    pub timestamp: SystemTime,
-    pub price: f64,
-    pub size: f64,
-    pub side: Side,
-}
-
-/// Order book data
-#[derive(Debug, Clone, Default)]
-pub struct OrderBook {
-    pub bids: Vec<(f64, f64)>, // (price, size)
-    pub asks: Vec<(f64, f64)>, // (price, size)
-}
-
-/// Ticker data
-#[derive(Debug, Clone)]
-pub struct Ticker {
-    pub timestamp: SystemTime,
-    pub bid: f64,
-    pub ask: f64,
-    pub last: f64,
-}
-
-/// Candle data
-#[derive(Debug, Clone)]
-pub struct Candle {
-    pub timestamp: SystemTime,
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
-    pub volume: f64,
-}
-
-/// Order data (internal representation for the strategy)
-
-/// Position data
-#[derive(Debug, Clone, Default)]
-pub struct Position {
-    pub size: f64,
-    pub avg_price: f64,
-    pub unrealized_pnl: f64,
-    pub realized_pnl: f64,
-}
-
 /// Configuration parameters for the Adverse Selection strategy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdverseSelectionConfig {
@@ -199,23 +147,8 @@ impl Default for AdverseSelectionConfig {
     }
 }
 
-/// Order side enum
-#[derive(Debug, Clone, PartialEq)]
-pub enum OrderSide {
-    Buy,
-    Sell,
-}
-
-/// Order type enum
-#[derive(Debug, Clone, PartialEq)]
-pub enum OrderType {
-    Market,
-    Limit,
-    StopLoss,
-    TakeProfit,
-}
-
 /// Adverse Selection strategy implementation
+#[derive(Serialize, Deserialize)]
 pub struct AdverseSelectionStrategy {
     /// Configuration parameters
     config: AdverseSelectionConfig,
@@ -226,18 +159,28 @@ pub struct AdverseSelectionStrategy {
     /// Recent order book snapshots
     recent_order_books: VecDeque<OrderBook>,
     /// Last time adverse selection was detected
+    #[serde(with = "epoch_millis::option")]
     last_adverse_detection: Option<SystemTime>,
-    /// Current position
-    position: Position,
-    /// Reference price for position management
-    reference_price: Option<f64>,
+    /// Positions the executed orders are booked in; shared when set with `with_position_tracker`
+    positions: PositionTracker,
+    /// Symbol of the last executed order, whose position is managed
+    symbol: Option<String>,
     /// Current market state
     market_state: MarketState,
+    /// Source of child order ids; not part of snapshots
+    #[serde(skip)]
+    ids: IdGenerator,
+    /// Calendar the child schedules are snapped into; not part of snapshots
+    #[serde(skip)]
+    calendar: Option<TradingCalendar>,
+    /// Random source varying clip sizes and intervals, advanced by every split; the thread
+    /// RNG when unset
+    seeds: Option<SplitSeeds>,
 }
 
 /// Market state evaluation
-#[derive(Debug, Clone, PartialEq)]
-enum MarketState {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MarketState {
     /// Normal market state
     Normal,
     /// Buyer informed state
@@ -257,12 +200,73 @@ impl AdverseSelectionStrategy {
             recent_trades: VecDeque::with_capacity(100),
             recent_order_books: VecDeque::with_capacity(20),
             last_adverse_detection: None,
-            position: Position::default(),
-            reference_price: None,
+            positions: PositionTracker::new(),
+            symbol: None,
             market_state: MarketState::Normal,
+            ids: IdGenerator::default(),
+            calendar: None,
+            seeds: None,
         }
     }
 
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Schedule child orders inside the sessions of the given calendar
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Draw clip sizes and intervals from a generator seeded with `seed`, each split from a
+    /// seed of its own that `OrderSplitStrategy::seed` returns, so it can be replayed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seeds = Some(SplitSeeds::new(seed));
+        self
+    }
+
+    /// Split the parent order from the current time, drawing variations from `rng`
+    pub fn split_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        // Get current time in milliseconds since UNIX epoch
+        let now_ms = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as u64,
+            Err(_) => 0, // Fallback if system time is before UNIX epoch
+        };
+        self.split_at_with_rng(parent_order, now_ms, rng)
+    }
+
+    /// Book executed orders in the given tracker, e.g. one shared with other strategies
+    pub fn with_position_tracker(mut self, positions: PositionTracker) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// Tracker the executed orders are booked in
+    pub fn positions(&self) -> &PositionTracker {
+        &self.positions
+    }
+
+    /// Signed size of the managed position
+    fn position_size(&self) -> f64 {
+        self.symbol
+            .as_ref()
+            .map(|symbol| decimal_to_f64(self.positions.quantity(symbol)))
+            .unwrap_or(0.0)
+    }
+
+    /// Average entry price of the managed position, if one is open
+    fn reference_price(&self) -> Option<f64> {
+        let position = self.positions.position(self.symbol.as_ref()?)?;
+        (!position.is_flat()).then(|| decimal_to_f64(position.avg_entry_price))
+    }
+
     /// Calculate order flow imbalance from recent order book data
     fn calculate_order_imbalance(&self) -> f64 {
         if self.recent_order_books.len() < 2 {
@@ -280,13 +284,13 @@ impl AdverseSelectionStrategy {
 
         let bid_change = bid_volume_current - bid_volume_previous;
         let ask_change = ask_volume_current - ask_volume_previous;
-        
+
         // Calculate imbalance ratio
         let total_change = bid_change.abs() + ask_change.abs();
         if total_change == 0.0 {
             return 0.0;
         }
-        
+
         (bid_change - ask_change) / total_change
     }
 
@@ -297,8 +301,9 @@ impl AdverseSelectionStrategy {
         }
 
         // Calculate average trade size
-        let avg_size: f64 = self.recent_trades.iter().map(|t| t.size).sum::<f64>() / self.recent_trades.len() as f64;
-        
+        let avg_size: f64 = self.recent_trades.iter().map(|t| t.size).sum::<f64>()
+            / self.recent_trades.len() as f64;
+
         // Check if the most recent trade is significantly larger than average
         let latest_trade = self.recent_trades.back().unwrap();
         latest_trade.size > avg_size * self.config.trade_size_threshold
@@ -312,7 +317,7 @@ impl AdverseSelectionStrategy {
 
         let latest_trade = self.recent_trades.back().unwrap();
         let previous_trade = &self.recent_trades[self.recent_trades.len() - 2];
-        
+
         (latest_trade.price - previous_trade.price).abs() / previous_trade.price
     }
 
@@ -346,14 +351,13 @@ impl AdverseSelectionStrategy {
         }
 
         // Detect adverse selection if multiple conditions are met
-        let is_adverse = (imbalance.abs() > self.config.imbalance_threshold && 
-                         price_impact > self.config.price_impact_threshold) || 
-                        (abnormal_size && price_impact > self.config.price_impact_threshold);
-        
+        let is_adverse = price_impact > self.config.price_impact_threshold
+            && (imbalance.abs() > self.config.imbalance_threshold || abnormal_size);
+
         if is_adverse {
             self.last_adverse_detection = Some(SystemTime::now());
         }
-        
+
         is_adverse
     }
 
@@ -364,49 +368,50 @@ impl AdverseSelectionStrategy {
         }
 
         let current_price = self.recent_trades.back().unwrap().price;
-        
+        let position_size = self.position_size();
+
         // Check for position management (stop loss/take profit)
-        if let Some(ref_price) = self.reference_price {
-            if self.position.size > 0.0 {
+        if let Some(ref_price) = self.reference_price() {
+            if position_size > 0.0 {
                 // Long position management
                 let pnl_pct = (current_price - ref_price) / ref_price;
-                
+
                 if pnl_pct <= -self.config.stop_loss_pct {
                     println!("Stop loss triggered for long position");
-                    return Some(StrategySignal::Sell { 
+                    return Some(StrategySignal::Sell {
                         price: current_price,
-                        size: self.position.size,
+                        size: position_size,
                         order_type: OrderType::Market,
-                        reason: "Stop loss".to_string()
+                        reason: "Stop loss".to_string(),
                     });
                 } else if pnl_pct >= self.config.take_profit_pct {
                     println!("Take profit triggered for long position");
-                    return Some(StrategySignal::Sell { 
+                    return Some(StrategySignal::Sell {
                         price: current_price,
-                        size: self.position.size,
+                        size: position_size,
                         order_type: OrderType::Market,
-                        reason: "Take profit".to_string()
+                        reason: "Take profit".to_string(),
                     });
                 }
-            } else if self.position.size < 0.0 {
+            } else if position_size < 0.0 {
                 // Short position management
                 let pnl_pct = (ref_price - current_price) / ref_price;
-                
+
                 if pnl_pct <= -self.config.stop_loss_pct {
                     println!("Stop loss triggered for short position");
-                    return Some(StrategySignal::Buy { 
+                    return Some(StrategySignal::Buy {
                         price: current_price,
-                        size: -self.position.size,
+                        size: -position_size,
                         order_type: OrderType::Market,
-                        reason: "Stop loss".to_string()
+                        reason: "Stop loss".to_string(),
                     });
                 } else if pnl_pct >= self.config.take_profit_pct {
                     println!("Take profit triggered for short position");
-                    return Some(StrategySignal::Buy { 
+                    return Some(StrategySignal::Buy {
                         price: current_price,
-                        size: -self.position.size,
+                        size: -position_size,
                         order_type: OrderType::Market,
-                        reason: "Take profit".to_string()
+                        reason: "Take profit".to_string(),
                     });
                 }
             }
@@ -415,114 +420,84 @@ impl AdverseSelectionStrategy {
         // Check for adverse selection
         if self.detect_adverse_selection() {
             self.last_adverse_detection = Some(SystemTime::now());
-            
+
             // Calculate order imbalance to determine direction
             let imbalance = self.calculate_order_imbalance();
-            
+
             if imbalance > 0.0 {
                 // Positive imbalance suggests buying pressure, potentially from informed traders
-                if self.position.size > 0.0 {
+                if position_size > 0.0 {
                     // If we have a long position, close it to avoid adverse selection
                     println!("Adverse selection detected: Closing long position due to potential informed buying");
-                    return Some(StrategySignal::Sell { 
+                    return Some(StrategySignal::Sell {
                         price: current_price,
-                        size: self.position.size,
+                        size: position_size,
                         order_type: OrderType::Market,
-                        reason: "Adverse selection protection".to_string()
+                        reason: "Adverse selection protection".to_string(),
                     });
-                } else if self.position.size == 0.0 {
+                } else if position_size == 0.0 {
                     // If no position, consider following the informed traders
                     println!("Adverse selection detected: Following potential informed buying");
-                    return Some(StrategySignal::Buy { 
+                    return Some(StrategySignal::Buy {
                         price: current_price,
                         size: self.config.max_position_size,
                         order_type: OrderType::Limit,
-                        reason: "Following informed flow".to_string()
+                        reason: "Following informed flow".to_string(),
                     });
                 }
             } else {
                 // Negative imbalance suggests selling pressure, potentially from informed traders
-                if self.position.size < 0.0 {
+                if position_size < 0.0 {
                     // If we have a short position, close it to avoid adverse selection
                     println!("Adverse selection detected: Closing short position due to potential informed selling");
-                    return Some(StrategySignal::Buy { 
+                    return Some(StrategySignal::Buy {
                         price: current_price,
-                        size: -self.position.size,
+                        size: -position_size,
                         order_type: OrderType::Market,
-                        reason: "Adverse selection protection".to_string()
+                        reason: "Adverse selection protection".to_string(),
                     });
-                } else if self.position.size == 0.0 {
+                } else if position_size == 0.0 {
                     // If no position, consider following the informed traders
                     println!("Adverse selection detected: Following potential informed selling");
-                    return Some(StrategySignal::Sell { 
+                    return Some(StrategySignal::Sell {
                         price: current_price,
                         size: self.config.max_position_size,
                         order_type: OrderType::Limit,
-                        reason: "Following informed flow".to_string()
+                        reason: "Following informed flow".to_string(),
                     });
                 }
             }
         }
-        
+
         None
     }
 
-    /// Update position based on executed order
+    /// Book the executed quantity of the order at its average fill price, or at its limit
+    /// price when no fill was recorded on it
     fn update_position(&mut self, order: &Order) {
-        let current_price = order.price.unwrap_or(0.0);
-        let quantity = order.quantity as f64;
-        
-        match order.side {
-            Side::Buy => {
-                // Update position for buy order
-                if self.position.size < 0.0 {
-                    // Reducing short position
-                    self.position.size += quantity;
-                    if self.position.size >= 0.0 {
-                        // Position flipped from short to long or flat
-                        self.reference_price = Some(current_price);
-                    }
-                } else {
-                    // Increasing long position or opening new long
-                    self.position.size += quantity;
-                    // Update reference price with weighted average
-                    let old_value = self.position.size - quantity;
-                    let new_value = quantity;
-                    let total = self.position.size;
-                    
-                    self.reference_price = Some(match self.reference_price {
-                        Some(ref_price) => (ref_price * old_value + current_price * new_value) / total,
-                        None => current_price
-                    });
-                }
-            },
-            Side::Sell => {
-                // Update position for sell order
-                if self.position.size > 0.0 {
-                    // Reducing long position
-                    self.position.size -= quantity;
-                    if self.position.size <= 0.0 {
-                        // Position flipped from long to short or flat
-                        self.reference_price = Some(current_price);
-                    }
-                } else {
-                    // Increasing short position or opening new short
-                    self.position.size -= quantity;
-                    // Update reference price with weighted average
-                    let old_value = -self.position.size - quantity;
-                    let new_value = quantity;
-                    let total = -self.position.size;
-                    
-                    self.reference_price = Some(match self.reference_price {
-                        Some(ref_price) => (ref_price * old_value + current_price * new_value) / total,
-                        None => current_price
-                    });
-                }
-            }
+        let price = order.avg_fill_price.or(order.price).unwrap_or_default();
+        let quantity = if order.filled_quantity.is_positive() {
+            order.filled_quantity
+        } else {
+            order.quantity
+        };
+        let fill = Fill::new(
+            order.id.clone(),
+            order.id.clone(),
+            price,
+            quantity,
+            order.timestamp.as_millis(),
+            Default::default(),
+            Liquidity::Taker,
+        );
+        // Orders without a quantity change nothing
+        if self
+            .positions
+            .apply_execution(&order.symbol, &order.side, &fill)
+            .is_ok()
+        {
+            self.symbol = Some(order.symbol.clone());
         }
-        
-        println!("Position updated: size={}, reference_price={:?}", 
-               self.position.size, self.reference_price);
     }
 
     /// Get current market state
@@ -556,14 +531,14 @@ impl Strategy for AdverseSelectionStrategy {
                 if self.recent_trades.len() > 100 {
                     self.recent_trades.pop_front();
                 }
-            },
+            }
             MarketData::OrderBook(order_book) => {
                 // Add order book to recent order books queue
                 self.recent_order_books.push_back(order_book.clone());
                 if self.recent_order_books.len() > self.config.window_size {
                     self.recent_order_books.pop_front();
                 }
-            },
+            }
             _ => {}
         }
 
@@ -585,135 +560,192 @@ impl Strategy for AdverseSelectionStrategy {
         self.recent_trades.clear();
         self.recent_order_books.clear();
         self.last_adverse_detection = None;
-        self.position = Position::default();
-        self.reference_price = None;
+        // Executions stay booked in the tracker, which other strategies may share
+        self.symbol = None;
         self.market_state = MarketState::Normal;
     }
 }
 
+impl Snapshot for AdverseSelectionStrategy {}
+
 /// Implement order splitting strategy
 impl OrderSplitStrategy for AdverseSelectionStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        match &self.seeds {
+            Some(seeds) => self.split_with_rng(parent_order, &mut seeds.next_rng()),
+            None => self.split_with_rng(parent_order, &mut rand::rng()),
+        }
+    }
+
+    fn seed(&self) -> Option<u64> {
+        self.seeds.as_ref().and_then(SplitSeeds::last)
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        Strategy::on_market_data(self, data);
+    }
+}
+
+impl AdverseSelectionStrategy {
+    /// Split the parent order scheduling children from `now_ms` (UNIX milliseconds),
+    /// drawing size and interval variations from `rng`
+    pub fn split_at_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        now_ms: u64,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        // Never schedule before the parent itself, even when its timestamp is ahead of the
+        // local clock. With a calendar the whole schedule rolls to the next session, keeping
+        // its spacing
+        let now_ms = now_ms.max(parent_order.order_common.timestamp.as_millis());
+        let now_ms = match &self.calendar {
+            Some(calendar) => calendar.clamp_to_session(now_ms),
+            None => now_ms,
+        };
         let mut child_orders = Vec::new();
-        let mut rng = rand::thread_rng();
-        
+
         // Determine split strategy based on market state
         let (num_splits, base_interval_ms) = match self.market_state {
             MarketState::Normal => {
                 // Normal market state, use medium split count and interval
-                (self.config.max_splits / 2, 
-                 (self.config.min_split_interval_ms + self.config.max_split_interval_ms) / 2)
-            },
+                (
+                    self.config.max_splits / 2,
+                    (self.config.min_split_interval_ms + self.config.max_split_interval_ms) / 2,
+                )
+            }
             MarketState::BuyerInformed => {
                 if parent_order.order_common.side == Side::Buy {
                     // Buyer informed, buy orders use more splits and longer interval
                     (self.config.max_splits, self.config.max_split_interval_ms)
                 } else {
                     // Sell orders use fewer splits and shorter interval
-                    (self.config.max_splits / 3, self.config.min_split_interval_ms)
+                    (
+                        self.config.max_splits / 3,
+                        self.config.min_split_interval_ms,
+                    )
                 }
-            },
+            }
             MarketState::SellerInformed => {
                 if parent_order.order_common.side == Side::Sell {
                     // Seller informed, sell orders use more splits and longer interval
                     (self.config.max_splits, self.config.max_split_interval_ms)
                 } else {
                     // Buy orders use fewer splits and shorter interval
-                    (self.config.max_splits / 3, self.config.min_split_interval_ms)
+                    (
+                        self.config.max_splits / 3,
+                        self.config.min_split_interval_ms,
+                    )
                 }
-            },
+            }
             MarketState::HighVolatility => {
                 // High volatility state, use maximum splits and shortest interval
                 (self.config.max_splits, self.config.min_split_interval_ms)
             }
         };
-        
+
         // Calculate base size for each child order
-        let base_quantity = parent_order.order_common.quantity / num_splits as u32;
+        let step = parent_order.order_common.lot_step();
+        let base_quantity = parent_order
+            .order_common
+            .quantity
+            .scale(1.0 / num_splits as f64);
         let mut remaining_quantity = parent_order.order_common.quantity;
-        
+
         // Create child orders
         for i in 0..num_splits {
             // Add some variation to child order size, except for the last order
             let quantity = if i < num_splits - 1 {
                 // Use random variation based on configuration
-                let variation_factor = 1.0 + self.config.size_variation_pct * (rng.gen::<f64>() * 2.0 - 1.0);
-                let quantity = (base_quantity as f64 * variation_factor).max(1.0) as u32;
+                let variation_factor =
+                    1.0 + self.config.size_variation_pct * (rng.random::<f64>() * 2.0 - 1.0);
+                let quantity = base_quantity
+                    .scale(variation_factor)
+                    .round_down_to_step(step)
+                    .max(step);
                 quantity.min(remaining_quantity) // Ensure does not exceed remaining quantity
             } else {
                 // Last order uses all remaining quantity
                 remaining_quantity
             };
-            
+
             // Update remaining quantity
             remaining_quantity = remaining_quantity.saturating_sub(quantity);
-            
+            if quantity.is_zero() {
+                continue;
+            }
+
             // Calculate execution time for child order
-            let interval_variation = (rng.gen::<f64>() * 0.4 - 0.2) * base_interval_ms as f64;
-            let interval_ms = (base_interval_ms as f64 * (1.0 + i as f64 * 0.2) + interval_variation) as u64;
-            
-            // Get current time in milliseconds since UNIX epoch
-            let now = SystemTime::now();
-            let execution_time_millis = match now.duration_since(SystemTime::UNIX_EPOCH) {
-                Ok(duration) => duration.as_millis() as u64 + interval_ms,
-                Err(_) => interval_ms, // Fallback if system time is before UNIX epoch
+            let interval_variation = (rng.random::<f64>() * 0.4 - 0.2) * base_interval_ms as f64;
+            let interval_ms =
+                (base_interval_ms as f64 * (1.0 + i as f64 * 0.2) + interval_variation) as u64;
+
+            let execution_time_millis = match &self.calendar {
+                Some(calendar) => calendar.advance(now_ms, interval_ms),
+                None => now_ms + interval_ms,
             };
-            
+
             // Create a new order based on parent order
             let mut order = parent_order.order_common.clone();
-            order.id = format!("{}-{}", parent_order.order_common.id, i);
+            order.id = self.ids.next_id();
             order.quantity = quantity;
-            
+
             // Create child order
             let child_order = ChildOrder {
                 order_common: order,
                 strategy_id: parent_order.strategy_id.clone(),
                 parent_id: parent_order.order_common.id.clone(),
-                insert_at: Some(execution_time_millis),
+                insert_at: Some(Timestamp::from_millis(execution_time_millis)),
             };
-            
+
             child_orders.push(child_order);
         }
-        
-        child_orders
+
+        checked_split(parent_order, child_orders)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::orders::{Order, ProductType, OrderType as ModelOrderType, Side, TimeInForce};
+    use crate::models::orders::{
+        Order, OrderType as ModelOrderType, ProductType, Side, TimeInForce,
+    };
+    use crate::models::Quantity;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rust_decimal_macros::dec;
     use std::time::UNIX_EPOCH;
 
     #[test]
     fn test_strategy_initialization() {
         let config = AdverseSelectionConfig::default();
         let strategy = AdverseSelectionStrategy::new(config);
-        
+
         assert_eq!(strategy.state, StrategyState::Idle);
         assert_eq!(strategy.recent_trades.len(), 0);
         assert_eq!(strategy.recent_order_books.len(), 0);
-        assert_eq!(strategy.position.size, 0.0);
-        assert!(strategy.reference_price.is_none());
+        assert_eq!(strategy.position_size(), 0.0);
+        assert!(strategy.reference_price().is_none());
     }
 
     #[test]
     fn test_order_imbalance_calculation() {
         let config = AdverseSelectionConfig::default();
         let mut strategy = AdverseSelectionStrategy::new(config);
-        
+
         // Create two order books with imbalance
         let mut order_book1 = OrderBook::default();
         order_book1.bids.push((100.0, 10.0));
         order_book1.asks.push((101.0, 10.0));
-        
+
         let mut order_book2 = OrderBook::default();
         order_book2.bids.push((100.0, 15.0)); // Increased bid volume
-        order_book2.asks.push((101.0, 8.0));  // Decreased ask volume
-        
+        order_book2.asks.push((101.0, 8.0)); // Decreased ask volume
+
         strategy.recent_order_books.push_back(order_book1);
         strategy.recent_order_books.push_back(order_book2);
-        
+
         let imbalance = strategy.calculate_order_imbalance();
         assert!(imbalance > 0.0); // Should be positive due to increased buying pressure
     }
@@ -722,7 +754,7 @@ mod tests {
     fn test_abnormal_trade_size_detection() {
         let config = AdverseSelectionConfig::default();
         let mut strategy = AdverseSelectionStrategy::new(config);
-        
+
         // Add 10 normal-sized trades
         for i in 0..10 {
             let trade = Trade {
@@ -733,19 +765,19 @@ mod tests {
             };
             strategy.recent_trades.push_back(trade);
         }
-        
+
         // No abnormal trade yet
         assert!(!strategy.detect_abnormal_trade_size());
-        
+
         // Add an abnormally large trade
         let large_trade = Trade {
             timestamp: SystemTime::now(),
             price: 101.0,
-            size: 5.0, // 5x the average size
+            size: 5.0,       // 5x the average size
             side: Side::Buy, // Use a fixed side instead of random for testing
         };
         strategy.recent_trades.push_back(large_trade);
-        
+
         // Should detect the abnormal trade
         assert!(strategy.detect_abnormal_trade_size());
     }
@@ -754,108 +786,260 @@ mod tests {
     fn test_position_update() {
         let config = AdverseSelectionConfig::default();
         let mut strategy = AdverseSelectionStrategy::new(config);
-        
+
         // Test buy order
         let buy_order = Order::new(
             "order1".to_string(),
-            100, // quantity
+            Quantity::from(100), // quantity
             ProductType::Spot,
             ModelOrderType::Market,
-            Some(100.0), // price
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64, // timestamp
-            None, // expiry_date
-            "BTC/USD".to_string(), // symbol
-            Side::Buy, // side
-            "USD".to_string(), // currency
+            Some(dec!(100.0)),           // price
+            Timestamp::now(),            // timestamp
+            None,                        // expiry_date
+            "BTC/USD".to_string(),       // symbol
+            Side::Buy,                   // side
+            "USD".to_string(),           // currency
             Some("BINANCE".to_string()), // exchange
-            Some(TimeInForce::GTC), // timeinforce
-            None, None, None, None, None, None // optional fields
+            Some(TimeInForce::GTC),      // timeinforce
+            None,
+            None,
+            None,
+            None,
+            None,
+            None, // optional fields
         );
-        
+
         strategy.update_position(&buy_order);
-        assert_eq!(strategy.position.size, 100.0);
-        assert!(strategy.reference_price.is_some());
-        
+        assert_eq!(strategy.position_size(), 100.0);
+        assert_eq!(strategy.reference_price(), Some(100.0));
+
         // Test sell order that reduces position
         let sell_order = Order::new(
             "order2".to_string(),
-            50, // quantity
+            Quantity::from(50), // quantity
             ProductType::Spot,
             ModelOrderType::Market,
-            Some(110.0), // price
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64, // timestamp
-            None, // expiry_date
-            "BTC/USD".to_string(), // symbol
-            Side::Sell, // side
-            "USD".to_string(), // currency
+            Some(dec!(110.0)),           // price
+            Timestamp::now(),            // timestamp
+            None,                        // expiry_date
+            "BTC/USD".to_string(),       // symbol
+            Side::Sell,                  // side
+            "USD".to_string(),           // currency
             Some("BINANCE".to_string()), // exchange
-            Some(TimeInForce::GTC), // timeinforce
-            None, None, None, None, None, None // optional fields
+            Some(TimeInForce::GTC),      // timeinforce
+            None,
+            None,
+            None,
+            None,
+            None,
+            None, // optional fields
         );
-        
+
         strategy.update_position(&sell_order);
-        assert_eq!(strategy.position.size, 50.0);
-        
+        assert_eq!(strategy.position_size(), 50.0);
+        assert_eq!(strategy.reference_price(), Some(100.0));
+
         // Test sell order that flips position to short
         let sell_order2 = Order::new(
             "order3".to_string(),
-            100, // quantity
+            Quantity::from(100), // quantity
             ProductType::Spot,
             ModelOrderType::Market,
-            Some(105.0), // price
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64, // timestamp
-            None, // expiry_date
-            "BTC/USD".to_string(), // symbol
-            Side::Sell, // side
-            "USD".to_string(), // currency
+            Some(dec!(105.0)),           // price
+            Timestamp::now(),            // timestamp
+            None,                        // expiry_date
+            "BTC/USD".to_string(),       // symbol
+            Side::Sell,                  // side
+            "USD".to_string(),           // currency
             Some("BINANCE".to_string()), // exchange
-            Some(TimeInForce::GTC), // timeinforce
-            None, None, None, None, None, None // optional fields
+            Some(TimeInForce::GTC),      // timeinforce
+            None,
+            None,
+            None,
+            None,
+            None,
+            None, // optional fields
         );
-        
+
         strategy.update_position(&sell_order2);
-        assert_eq!(strategy.position.size, -50.0);
-        assert!(strategy.reference_price.is_some());
+        assert_eq!(strategy.position_size(), -50.0);
+        assert_eq!(strategy.reference_price(), Some(105.0));
+        let position = strategy.positions().position("BTC/USD").unwrap();
+        assert_eq!(position.realized_pnl, dec!(750));
     }
 
     #[test]
     fn test_split_order_normal_market() {
         let config = AdverseSelectionConfig::default();
         let mut strategy = AdverseSelectionStrategy::new(config.clone());
-        
+
         // Set normal market state
         strategy.market_state = MarketState::Normal;
-        
+
         // Create parent order with the correct structure
         let order = Order::new(
             "test-order-123".to_string(),
-            1000, // quantity
+            Quantity::from(1000), // quantity
             ProductType::Spot,
             ModelOrderType::Market,
-            Some(50000.0), // price
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64, // timestamp
-            None, // expiry_date
-            "BTC/USD".to_string(), // symbol
-            Side::Buy, // side
-            "USD".to_string(), // currency
+            Some(dec!(50000.0)),         // price
+            Timestamp::now(),            // timestamp
+            None,                        // expiry_date
+            "BTC/USD".to_string(),       // symbol
+            Side::Buy,                   // side
+            "USD".to_string(),           // currency
             Some("BINANCE".to_string()), // exchange
-            Some(TimeInForce::GTC), // timeinforce
-            None, None, None, None, None, None // optional fields
+            Some(TimeInForce::GTC),      // timeinforce
+            None,
+            None,
+            None,
+            None,
+            None,
+            None, // optional fields
         );
-        
+
         let parent_order = ParentOrder {
             order_common: order,
             strategy_id: "TWAP".to_string(),
         };
-        
+
         // Split order
         let child_orders = strategy.split(&parent_order);
-        
+
         // Verify split count
         assert_eq!(child_orders.len(), config.max_splits / 2);
-        
+
         // Verify total quantity
-        let total_quantity: u32 = child_orders.iter().map(|o| o.order_common.quantity).sum();
+        let total_quantity: Quantity = child_orders.iter().map(|o| o.order_common.quantity).sum();
         assert_eq!(total_quantity, parent_order.order_common.quantity);
     }
+
+    #[test]
+    fn test_seeded_split_replays_the_same_schedule() {
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(1_000)
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("TWAP")
+            .unwrap();
+        let schedule = |seed: u64| {
+            let mut strategy = AdverseSelectionStrategy::new(AdverseSelectionConfig::default())
+                .with_id_generator(IdGenerator::sequential("child"));
+            strategy.market_state = MarketState::HighVolatility;
+            let mut rng = StdRng::seed_from_u64(seed);
+            serde_json::to_vec(&strategy.split_at_with_rng(&parent_order, 1_000, &mut rng)).unwrap()
+        };
+
+        assert_eq!(schedule(7), schedule(7));
+        assert_ne!(schedule(7), schedule(8));
+        // Consecutive parents draw seeds of their own, each replaying its clip sizes
+        let strategy =
+            AdverseSelectionStrategy::new(AdverseSelectionConfig::default()).with_seed(7);
+        assert_eq!(OrderSplitStrategy::seed(&strategy), None);
+        let quantities = |children: Vec<ChildOrder>| -> Vec<Quantity> {
+            children
+                .iter()
+                .map(|child| child.order_common.quantity)
+                .collect()
+        };
+        let first = strategy.split(&parent_order);
+        let first_seed = OrderSplitStrategy::seed(&strategy).unwrap();
+        let second = strategy.split(&parent_order);
+        let second_seed = OrderSplitStrategy::seed(&strategy).unwrap();
+        assert_eq!(first.len(), strategy.config.max_splits / 2);
+        assert_ne!(first_seed, second_seed);
+        assert_ne!(quantities(first.clone()), quantities(second.clone()));
+        for (children, seed) in [(first, first_seed), (second, second_seed)] {
+            let replay = strategy.split_with_rng(&parent_order, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(quantities(replay), quantities(children));
+        }
+    }
+
+    #[test]
+    fn test_split_keeps_children_inside_sessions() {
+        // Saturday 2024-05-25 12:00 EDT and the following Monday open
+        let saturday = 1716652800000;
+        let monday_open = 1716816600000;
+        let calendar = TradingCalendar::us_equities();
+        let mut strategy = AdverseSelectionStrategy::new(AdverseSelectionConfig::default())
+            .with_calendar(calendar.clone());
+        strategy.market_state = MarketState::HighVolatility;
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(saturday)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("TWAP")
+            .unwrap();
+
+        let child_orders = strategy.split_at_with_rng(&parent_order, saturday, &mut rand::rng());
+        assert_eq!(child_orders.len(), strategy.config.max_splits);
+        for child in &child_orders {
+            let insert_at = child.insert_at.unwrap().as_millis();
+            assert!(insert_at >= monday_open);
+            assert!(calendar.is_open(insert_at));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut original = AdverseSelectionStrategy::new(AdverseSelectionConfig::default());
+        let start = UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_123);
+        for i in 0..10 {
+            original.recent_trades.push_back(Trade {
+                timestamp: start,
+                price: 100.0 + i as f64 * 0.1,
+                size: 1.0,
+                side: Side::Buy,
+            });
+        }
+        let mut order_book = OrderBook::default();
+        order_book.bids.push((100.0, 10.0));
+        order_book.asks.push((101.0, 10.0));
+        original.recent_order_books.push_back(order_book.clone());
+        original.last_adverse_detection = Some(start);
+        original.update_position(&Order::new(
+            "order1".to_string(),
+            Quantity::from(2),
+            ProductType::Spot,
+            ModelOrderType::Market,
+            Some(dec!(100.5)),
+            Timestamp::now(),
+            None,
+            "BTC/USD".to_string(),
+            Side::Buy,
+            "USD".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ));
+
+        let json = original.snapshot();
+        assert!(json.contains("1700000000123"));
+        let mut restored = AdverseSelectionStrategy::restore(&json).unwrap();
+        assert_eq!(restored.last_adverse_detection, Some(start));
+        assert_eq!(restored.recent_trades.len(), 10);
+        assert_eq!(restored.market_state, original.market_state);
+        assert_eq!(restored.reference_price(), Some(100.5));
+        assert_eq!(restored.position_size(), 2.0);
+
+        // The same subsequent input produces the same signal
+        let mut heavy_bids = order_book;
+        heavy_bids.bids[0].1 = 40.0;
+        let update = MarketData::OrderBook(heavy_bids);
+        let expected = Strategy::on_market_data(&mut original, &update);
+        let actual = Strategy::on_market_data(&mut restored, &update);
+        assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+        assert_eq!(restored.market_state, original.market_state);
+    }
 }
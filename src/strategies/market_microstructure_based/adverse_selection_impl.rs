@@ -26,11 +26,17 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use std::time::SystemTime;
-use rand::Rng;
+use crate::config::StrategyConfig;
 use crate::models::orders::Side;
-use crate::models::{ChildOrder, ParentOrder};
-use crate::strategies::OrderSplitStrategy;
+use crate::models::{
+    ChildOrder, IdGenerator, InstrumentRegistry, ParentOrder, Quantity, Timestamp, TradingCalendar,
+};
+use crate::strategies::inventory_based::InventoryManager;
+use crate::strategies::technical_indicator_based::ATRSizer;
+use crate::strategies::{checked_split, OrderSplitStrategy, SplitSeeds};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 
 /// Market state enum for adverse selection strategy
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -41,8 +47,10 @@ pub enum MarketState {
     HighVolatility,
 }
 
-/// Configuration for adverse selection strategy
-#[derive(Debug, Clone)]
+/// Configuration for adverse selection strategy, readable from the `strategies` section of
+/// `Config` where missing fields keep their defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct AdverseSelectionConfig {
     pub max_splits: usize,
     pub min_split_interval_ms: u64,
@@ -61,11 +69,49 @@ impl Default for AdverseSelectionConfig {
     }
 }
 
+impl StrategyConfig for AdverseSelectionConfig {
+    fn validate(&self) -> Result<(), String> {
+        // Informed states split into a third of max_splits, which must leave at least one child
+        if self.max_splits < 3 {
+            return Err(format!(
+                "max_splits must be at least 3, got {}",
+                self.max_splits
+            ));
+        }
+        if self.min_split_interval_ms > self.max_split_interval_ms {
+            return Err(format!(
+                "min_split_interval_ms {} exceeds max_split_interval_ms {}",
+                self.min_split_interval_ms, self.max_split_interval_ms
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.size_variation_pct) {
+            return Err(format!(
+                "size_variation_pct must be between 0 and 1, got {}",
+                self.size_variation_pct
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Adverse selection strategy implementation
 #[derive(Debug, Clone)]
 pub struct AdverseSelectionStrategy {
     pub config: AdverseSelectionConfig,
     pub market_state: MarketState,
+    /// Optional inventory used to shrink clips that add to a loaded book
+    pub inventory_manager: Option<InventoryManager>,
+    /// Optional calendar used to snap child schedules into trading sessions
+    pub calendar: Option<TradingCalendar>,
+    /// Optional ATR sizer capping clips when volatility spikes
+    pub atr_sizer: Option<ATRSizer>,
+    /// Optional instrument rules child prices and quantities are rounded to
+    pub instruments: Option<InstrumentRegistry>,
+    /// Source of child order ids
+    pub ids: IdGenerator,
+    /// Random source varying clip sizes and intervals, advanced by every split; the thread
+    /// RNG when unset
+    pub seeds: Option<SplitSeeds>,
 }
 
 impl AdverseSelectionStrategy {
@@ -73,20 +119,92 @@ impl AdverseSelectionStrategy {
         Self {
             config: config.unwrap_or_default(),
             market_state: MarketState::Normal,
+            inventory_manager: None,
+            calendar: None,
+            atr_sizer: None,
+            instruments: None,
+            ids: IdGenerator::default(),
+            seeds: None,
         }
     }
-    
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Draw clip sizes and intervals from a generator seeded with `seed`, each split from a
+    /// seed of its own that `OrderSplitStrategy::seed` returns, so it can be replayed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seeds = Some(SplitSeeds::new(seed));
+        self
+    }
+
+    /// Skew child quantities with the given inventory manager
+    pub fn with_inventory_manager(mut self, inventory_manager: InventoryManager) -> Self {
+        self.inventory_manager = Some(inventory_manager);
+        self
+    }
+
+    /// Cap child quantities with the given ATR sizer. Quantity above the cap is left on the
+    /// parent order.
+    pub fn with_atr_sizer(mut self, atr_sizer: ATRSizer) -> Self {
+        self.atr_sizer = Some(atr_sizer);
+        self
+    }
+
+    /// Round child prices to ticks and quantities to lots of the instrument. Clips are sized
+    /// in whole lots, so at most one lot of the parent quantity is left unallocated.
+    pub fn with_instruments(mut self, instruments: InstrumentRegistry) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Schedule child orders inside the sessions of the given calendar
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
     pub fn update_market_state(&mut self, state: MarketState) {
         self.market_state = state;
     }
-}
 
-/// Implement order splitting strategy
-impl OrderSplitStrategy for AdverseSelectionStrategy {
-    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+    /// Split the parent order scheduling children from `now_ms` (UNIX milliseconds)
+    pub fn split_at(&self, parent_order: &ParentOrder, now_ms: u64) -> Vec<ChildOrder> {
+        match &self.seeds {
+            Some(seeds) => self.split_at_with_rng(parent_order, now_ms, &mut seeds.next_rng()),
+            None => self.split_at_with_rng(parent_order, now_ms, &mut rand::rng()),
+        }
+    }
+
+    /// Split the parent order from the current time, drawing variations from `rng`
+    pub fn split_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        self.split_at_with_rng(parent_order, Timestamp::now().as_millis(), rng)
+    }
+
+    /// Split the parent order scheduling children from `now_ms`, drawing variations from `rng`
+    pub fn split_at_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        now_ms: u64,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        // Never schedule before the parent itself, even when its timestamp is ahead of the
+        // local clock. With a calendar the whole schedule rolls to the next session, keeping
+        // its spacing
+        let now_ms = now_ms.max(parent_order.order_common.timestamp.as_millis());
+        let start_time = match &self.calendar {
+            Some(calendar) => calendar.clamp_to_session(now_ms),
+            None => now_ms,
+        };
         let mut child_orders = Vec::new();
-        let mut rng = rand::thread_rng();
-        
+
         // Determine number of splits based on market state
         let num_splits = match self.market_state {
             MarketState::Normal => self.config.max_splits / 2,
@@ -96,94 +214,190 @@ impl OrderSplitStrategy for AdverseSelectionStrategy {
                 } else {
                     self.config.max_splits / 3
                 }
-            },
+            }
             MarketState::SellerInformed => {
                 if parent_order.order_common.side == Side::Sell {
                     self.config.max_splits
                 } else {
                     self.config.max_splits / 3
                 }
-            },
+            }
             MarketState::HighVolatility => self.config.max_splits,
         };
-        
+
         // Calculate base size for each child order
-        let base_quantity = parent_order.order_common.quantity / num_splits as u32;
+        let step = match &self.instruments {
+            Some(instruments) => instruments.lot_step(&parent_order.order_common),
+            None => parent_order.order_common.lot_step(),
+        };
+        let base_quantity = parent_order
+            .order_common
+            .quantity
+            .scale(1.0 / num_splits as f64);
         let mut remaining_quantity = parent_order.order_common.quantity;
-        
+        let mut inventory_budget = parent_order.order_common.quantity;
+        // Clips shrink when the ATR spikes; what the cap cuts is carried into the next clips
+        let atr_cap = self
+            .atr_sizer
+            .as_ref()
+            .and_then(|sizer| sizer.clip_limit_in_lots(step));
+        let mut atr_carry = Quantity::ZERO;
+
         // Create child orders
         for i in 0..num_splits {
             // Determine quantity for this child order
             let quantity = if i < num_splits - 1 {
-                let variation = (rng.gen::<f64>() * 0.2 - 0.1) * base_quantity as f64;
-                let qty = (base_quantity as f64 + variation).max(1.0) as u32;
+                let variation = 1.0 + rng.random::<f64>() * 0.2 - 0.1;
+                let qty = base_quantity
+                    .scale(variation)
+                    .round_down_to_step(step)
+                    .max(step);
                 qty.min(remaining_quantity)
             } else {
                 // Last order uses all remaining quantity
                 remaining_quantity
             };
-            
+
             // Update remaining quantity
             remaining_quantity = remaining_quantity.saturating_sub(quantity);
-            
-            // Calculate execution time
-            let interval_ms = if i == 0 {
-                0 // First order executes immediately
-            } else {
-                let base_interval = match self.market_state {
-                    MarketState::Normal => 5000, // 5 seconds
-                    MarketState::BuyerInformed | MarketState::SellerInformed => 8000, // 8 seconds
-                    MarketState::HighVolatility => 3000, // 3 seconds
-                };
-                
-                let variation = (rng.gen::<f64>() * 0.4 - 0.2) * base_interval as f64;
-                (base_interval as f64 * i as f64 + variation) as u64
+
+            // Shade the clip by the inventory, never exceeding the parent quantity
+            let quantity = match &self.inventory_manager {
+                Some(manager) => {
+                    let skewed = manager.skew_quantity(
+                        &parent_order.order_common.symbol,
+                        quantity,
+                        &parent_order.order_common.side,
+                    );
+                    skewed.min(inventory_budget)
+                }
+                None => quantity,
             };
-            
-            // Get current time in milliseconds
-            let now = SystemTime::now();
-            let execution_time = match now.duration_since(SystemTime::UNIX_EPOCH) {
-                Ok(duration) => duration.as_millis() as u64 + interval_ms,
-                Err(_) => interval_ms,
+            // Shrink the clip when the ATR spikes
+            let quantity = match atr_cap {
+                Some(cap) => {
+                    let wanted = (quantity + atr_carry).min(inventory_budget);
+                    let capped = wanted.min(cap);
+                    atr_carry = wanted - capped;
+                    capped
+                }
+                None => quantity,
             };
-            
-            // Create a new order based on parent order
-            let mut order = parent_order.order_common.clone();
-            order.id = format!("{}-{}", parent_order.order_common.id, i);
-            order.quantity = quantity;
-            
-            // Create child order
-            let child_order = ChildOrder {
-                order_common: order,
-                strategy_id: parent_order.strategy_id.clone(),
-                parent_id: parent_order.order_common.id.clone(),
-                insert_at: Some(execution_time),
+            let quantity = self.round_clip(parent_order, quantity);
+            inventory_budget -= quantity;
+            if quantity.is_zero() {
+                continue;
+            }
+
+            let execution_time = self.execution_time(start_time, i, rng);
+            child_orders.push(self.child_order(parent_order, quantity, execution_time));
+        }
+
+        // Send what the ATR cap cut in further capped clips, continuing the schedule
+        let mut i = num_splits;
+        while let Some(cap) = atr_cap {
+            let quantity = self.round_clip(parent_order, atr_carry.min(cap).min(inventory_budget));
+            if quantity.is_zero() {
+                break;
+            }
+            atr_carry -= quantity;
+            inventory_budget -= quantity;
+            let execution_time = self.execution_time(start_time, i, rng);
+            child_orders.push(self.child_order(parent_order, quantity, execution_time));
+            i += 1;
+        }
+
+        checked_split(parent_order, child_orders)
+    }
+
+    /// `quantity` rounded to the lots of the instrument, if any
+    fn round_clip(&self, parent_order: &ParentOrder, quantity: Quantity) -> Quantity {
+        match &self.instruments {
+            Some(instruments) => instruments.round_qty(&parent_order.order_common.symbol, quantity),
+            None => quantity,
+        }
+    }
+
+    /// When the `i`-th child goes out, for a schedule starting at `start_time`
+    fn execution_time<R: Rng + ?Sized>(&self, start_time: u64, i: usize, rng: &mut R) -> u64 {
+        let interval_ms = if i == 0 {
+            0 // First order executes immediately
+        } else {
+            let base_interval = match self.market_state {
+                MarketState::Normal => 5000, // 5 seconds
+                MarketState::BuyerInformed | MarketState::SellerInformed => 8000, // 8 seconds
+                MarketState::HighVolatility => 3000, // 3 seconds
             };
-            
-            child_orders.push(child_order);
+
+            let variation = (rng.random::<f64>() * 0.4 - 0.2) * base_interval as f64;
+            (base_interval as f64 * i as f64 + variation) as u64
+        };
+
+        match &self.calendar {
+            Some(calendar) => calendar.advance(start_time, interval_ms),
+            None => start_time + interval_ms,
+        }
+    }
+
+    /// A child of the parent for `quantity`, sent at `execution_time`
+    fn child_order(
+        &self,
+        parent_order: &ParentOrder,
+        quantity: Quantity,
+        execution_time: u64,
+    ) -> ChildOrder {
+        let mut order = parent_order.order_common.clone();
+        order.id = self.ids.next_id();
+        order.quantity = quantity;
+        if let Some(instruments) = &self.instruments {
+            instruments.round_order(&mut order);
         }
-        
-        child_orders
+        ChildOrder {
+            order_common: order,
+            strategy_id: parent_order.strategy_id.clone(),
+            parent_id: parent_order.order_common.id.clone(),
+            insert_at: Some(Timestamp::from_millis(execution_time)),
+        }
+    }
+}
+
+/// Implement order splitting strategy
+impl OrderSplitStrategy for AdverseSelectionStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        // Get current time in milliseconds
+        let now = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as u64,
+            Err(_) => 0,
+        };
+        self.split_at(parent_order, now)
+    }
+
+    fn seed(&self) -> Option<u64> {
+        self.seeds.as_ref().and_then(SplitSeeds::last)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::orders::{Order, ProductType, OrderType, TimeInForce};
-    use std::time::UNIX_EPOCH;
-    
+    use crate::models::market_data::Candle;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::strategies::validate_children;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rust_decimal_macros::dec;
+
     #[test]
     fn test_split_normal_market() {
         let strategy = AdverseSelectionStrategy::new(None);
-        
+
         let order = Order::new(
             "parent-1".to_string(),
-            1000,
+            Quantity::from(1000),
             ProductType::Spot,
             OrderType::Limit,
-            Some(100.0),
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            Some(dec!(100.0)),
+            Timestamp::now(),
             None,
             "BTC/USD".to_string(),
             Side::Buy,
@@ -197,82 +411,408 @@ mod tests {
             None,
             None,
         );
-        
+
         let parent_order = ParentOrder {
             order_common: order,
             strategy_id: "test-strategy".to_string(),
         };
-        
+
         let child_orders = strategy.split(&parent_order);
-        
+
         // In normal market state, we should have max_splits/2 child orders
         assert_eq!(child_orders.len(), strategy.config.max_splits / 2);
-        
+
         // Total quantity should match parent order
-        let total_quantity: u32 = child_orders.iter().map(|o| o.order_common.quantity).sum();
+        let total_quantity: Quantity = child_orders.iter().map(|o| o.order_common.quantity).sum();
         assert_eq!(total_quantity, parent_order.order_common.quantity);
-        
+
         // First child order should execute immediately
         assert!(child_orders[0].insert_at.unwrap() < child_orders[1].insert_at.unwrap());
         println!("child_orders: {:?}", child_orders);
     }
-    
+
+    #[test]
+    fn test_split_rounds_to_instrument_lots() {
+        use crate::models::{InstrumentRegistry, InstrumentSpec};
+
+        let mut instruments = InstrumentRegistry::new();
+        instruments
+            .register("ESZ4", InstrumentSpec::new(dec!(0.25), Quantity::from(10)))
+            .unwrap();
+        let mut strategy =
+            AdverseSelectionStrategy::new(None).with_instruments(instruments.clone());
+        strategy.update_market_state(MarketState::HighVolatility);
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1005)
+            .order_type(OrderType::Limit)
+            .price(dec!(4500.10))
+            .timestamp(1_000)
+            .symbol("ESZ4")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+
+        for _ in 0..20 {
+            let child_orders = strategy.split_at(&parent_order, 1_000);
+            let total: Quantity = child_orders.iter().map(|o| o.order_common.quantity).sum();
+            assert!(total <= parent_order.order_common.quantity);
+            assert!(parent_order.order_common.quantity - total < Quantity::from(10));
+            for child in &child_orders {
+                assert_eq!(child.order_common.price, Some(dec!(4500.00)));
+                assert!(child.order_common.validate_with(&instruments).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_never_schedules_before_a_parent_ahead_of_the_clock() {
+        let strategy = AdverseSelectionStrategy::new(None);
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(5_000)
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+
+        let child_orders = strategy.split_at(&parent_order, 1_000);
+        assert!(!child_orders.is_empty());
+        assert!(validate_children(&parent_order, &child_orders).is_ok());
+    }
+
+    #[test]
+    fn test_split_twice_gives_distinct_child_ids() {
+        let strategy =
+            AdverseSelectionStrategy::new(None).with_id_generator(IdGenerator::sequential("child"));
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(1_000)
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+
+        let first = strategy.split_at(&parent_order, 1_000);
+        let second = strategy.split_at(&parent_order, 1_000);
+        let ids: Vec<&str> = first
+            .iter()
+            .chain(&second)
+            .map(|o| o.order_common.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["child-1", "child-2", "child-3", "child-4"]);
+        assert!(first
+            .iter()
+            .chain(&second)
+            .all(|o| o.parent_id == "parent-1"));
+    }
+
+    #[test]
+    fn test_seeded_split_replays_the_same_schedule() {
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(1_000)
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+        let schedule = |seed: u64| {
+            let mut strategy = AdverseSelectionStrategy::new(None)
+                .with_id_generator(IdGenerator::sequential("child"))
+                .with_seed(seed);
+            strategy.update_market_state(MarketState::HighVolatility);
+            serde_json::to_vec(&strategy.split_at(&parent_order, 1_000)).unwrap()
+        };
+
+        assert_eq!(schedule(7), schedule(7));
+        assert_ne!(schedule(7), schedule(8));
+
+        // Consecutive parents draw seeds of their own, each replaying its schedule
+        let mut strategy = AdverseSelectionStrategy::new(None).with_seed(7);
+        strategy.update_market_state(MarketState::HighVolatility);
+        assert_eq!(OrderSplitStrategy::seed(&strategy), None);
+        let slices = |children: &[ChildOrder]| -> Vec<(Quantity, Option<Timestamp>)> {
+            children
+                .iter()
+                .map(|child| (child.order_common.quantity, child.insert_at))
+                .collect()
+        };
+        let first = strategy.split_at(&parent_order, 1_000);
+        let first_seed = OrderSplitStrategy::seed(&strategy).unwrap();
+        let second = strategy.split_at(&parent_order, 1_000);
+        let second_seed = OrderSplitStrategy::seed(&strategy).unwrap();
+        assert_ne!(first_seed, second_seed);
+        assert_ne!(slices(&first), slices(&second));
+        for (children, seed) in [(first, first_seed), (second, second_seed)] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let replay = strategy.split_at_with_rng(&parent_order, 1_000, &mut rng);
+            assert_eq!(slices(&replay), slices(&children));
+        }
+    }
+
+    #[test]
+    fn test_split_with_rng_draws_from_the_given_rng() {
+        let strategy = AdverseSelectionStrategy::new(None);
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(1_000)
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+        let quantities = |seed: u64| -> Vec<Quantity> {
+            strategy
+                .split_with_rng(&parent_order, &mut StdRng::seed_from_u64(seed))
+                .iter()
+                .map(|o| o.order_common.quantity)
+                .collect()
+        };
+
+        assert_eq!(quantities(42), quantities(42));
+        assert_eq!(
+            quantities(42).into_iter().sum::<Quantity>(),
+            Quantity::from(1000)
+        );
+    }
+
     #[test]
     fn test_split_buyer_informed() {
         let strategy = AdverseSelectionStrategy {
             config: AdverseSelectionConfig::default(),
             market_state: MarketState::BuyerInformed,
+            inventory_manager: None,
+            calendar: None,
+            atr_sizer: None,
+            instruments: None,
+            ids: IdGenerator::default(),
+            seeds: None,
         };
-        
+
         // Test buy parent
         let buy_order = Order::new(
             "test-order-buy".to_string(),
-            1000,
+            Quantity::from(1000),
             ProductType::Spot,
             OrderType::Market,
-            Some(50000.0),
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            Some(dec!(50000.0)),
+            Timestamp::now(),
             None,
             "BTC/USD".to_string(),
             Side::Buy,
             "USD".to_string(),
             Some("BINANCE".to_string()),
             Some(TimeInForce::GTC),
-            None, None, None, None, None, None
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
-        
+
         let buy_parent = ParentOrder {
             order_common: buy_order,
             strategy_id: "TWAP".to_string(),
         };
-        
+
         let buy_children = strategy.split(&buy_parent);
         assert_eq!(buy_children.len(), strategy.config.max_splits);
         println!("buy_children: {:?}", buy_children);
         // Test sell parent
         let sell_order = Order::new(
             "test-order-sell".to_string(),
-            1000,
+            Quantity::from(1000),
             ProductType::Spot,
             OrderType::Market,
-            Some(50000.0),
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            Some(dec!(50000.0)),
+            Timestamp::now(),
             None,
             "BTC/USD".to_string(),
             Side::Sell,
             "USD".to_string(),
             Some("BINANCE".to_string()),
             Some(TimeInForce::GTC),
-            None, None, None, None, None, None
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
-        
+
         let sell_parent = ParentOrder {
             order_common: sell_order,
             strategy_id: "TWAP".to_string(),
         };
-        
+
         let sell_children = strategy.split(&sell_parent);
         assert_eq!(sell_children.len(), strategy.config.max_splits / 3);
         println!("sell_children: {:?}", sell_children);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_split_shrinks_clips_on_loaded_book() {
+        let mut manager = InventoryManager::new(1000.0);
+        manager.set_position("BTC/USD", 900.0);
+        let strategy = AdverseSelectionStrategy::new(None).with_inventory_manager(manager);
+
+        let order = Order::new(
+            "parent-inv".to_string(),
+            Quantity::from(1000),
+            ProductType::Spot,
+            OrderType::Limit,
+            Some(dec!(100.0)),
+            Timestamp::now(),
+            None,
+            "BTC/USD".to_string(),
+            Side::Buy,
+            "USD".to_string(),
+            Some("BINANCE".to_string()),
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut parent_order = ParentOrder {
+            order_common: order,
+            strategy_id: "test-strategy".to_string(),
+        };
+
+        // 90% utilized long book: buy clips are cut to roughly a tenth
+        let buy_children = strategy.split(&parent_order);
+        let buy_total: Quantity = buy_children.iter().map(|o| o.order_common.quantity).sum();
+        assert!(buy_total <= Quantity::from(110), "buy total {}", buy_total);
+        assert!(buy_children
+            .iter()
+            .all(|o| o.order_common.quantity.is_positive()));
+
+        // Risk-reducing sells are enlarged but never exceed the parent quantity
+        parent_order.order_common.side = Side::Sell;
+        let sell_children = strategy.split(&parent_order);
+        let sell_total: Quantity = sell_children.iter().map(|o| o.order_common.quantity).sum();
+        assert_eq!(sell_total, parent_order.order_common.quantity);
+    }
+
+    #[test]
+    fn test_split_rolls_friday_evening_schedule_to_monday() {
+        // Friday 2024-05-24 18:00 EDT and the following Monday open
+        let friday_evening = 1716588000000;
+        let monday_open = 1716816600000;
+        let calendar = TradingCalendar::us_equities();
+        let strategy = AdverseSelectionStrategy::new(None).with_calendar(calendar.clone());
+
+        let order = Order::new(
+            "parent-cal".to_string(),
+            Quantity::from(1000),
+            ProductType::Spot,
+            OrderType::Limit,
+            Some(dec!(100.0)),
+            Timestamp::from_millis(friday_evening),
+            None,
+            "AAPL".to_string(),
+            Side::Buy,
+            "USD".to_string(),
+            Some("NASDAQ".to_string()),
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let parent_order = ParentOrder {
+            order_common: order,
+            strategy_id: "test-strategy".to_string(),
+        };
+
+        let child_orders = strategy.split_at(&parent_order, friday_evening);
+        assert_eq!(
+            child_orders[0].insert_at,
+            Some(Timestamp::from_millis(monday_open))
+        );
+        for child in &child_orders {
+            let insert_at = child.insert_at.unwrap().as_millis();
+            assert!(insert_at >= monday_open);
+            assert!(calendar.is_open(insert_at));
+        }
+    }
+
+    #[test]
+    fn test_split_caps_clips_with_atr() {
+        let candle = |range: f64| Candle {
+            timestamp: SystemTime::UNIX_EPOCH,
+            open: 100.0,
+            high: 100.0 + range / 2.0,
+            low: 100.0 - range / 2.0,
+            close: 100.0,
+            volume: 1000.0,
+        };
+        let order = Order::new(
+            "parent-atr".to_string(),
+            Quantity::from(1000),
+            ProductType::Spot,
+            OrderType::Limit,
+            Some(dec!(100.0)),
+            Timestamp::now(),
+            None,
+            "BTC/USD".to_string(),
+            Side::Buy,
+            "USD".to_string(),
+            Some("BINANCE".to_string()),
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let parent_order = ParentOrder {
+            order_common: order,
+            strategy_id: "test-strategy".to_string(),
+        };
+
+        // Calm market: the cap is above every clip
+        let mut sizer = ATRSizer::new(5, 1000.0);
+        for _ in 0..5 {
+            sizer.add_candle(&candle(1.0));
+        }
+        let strategy = AdverseSelectionStrategy::new(None).with_atr_sizer(sizer.clone());
+        let calm_total: Quantity = strategy
+            .split(&parent_order)
+            .iter()
+            .map(|o| o.order_common.quantity)
+            .sum();
+        assert_eq!(calm_total, Quantity::from(1000));
+
+        // Volatility spike: ATR of 10 caps each clip at 100
+        for _ in 0..50 {
+            sizer.add_candle(&candle(10.0));
+        }
+        let strategy = AdverseSelectionStrategy::new(None).with_atr_sizer(sizer);
+        let child_orders = strategy.split(&parent_order);
+        assert!(child_orders
+            .iter()
+            .all(|o| o.order_common.quantity <= Quantity::from(100)));
+        // What the cap cuts goes out in further clips, later in the schedule
+        let volatile_total: Quantity = child_orders.iter().map(|o| o.order_common.quantity).sum();
+        assert_eq!(volatile_total, calm_total);
+        assert_eq!(child_orders.len(), 10);
+        for pair in child_orders.windows(2) {
+            assert!(pair[0].insert_at < pair[1].insert_at);
+        }
+    }
+}
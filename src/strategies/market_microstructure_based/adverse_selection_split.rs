@@ -26,11 +26,11 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 
-use std::time::SystemTime;
-use rand::Rng;
 use crate::models::orders::Side;
-use crate::models::{ChildOrder, ParentOrder};
-use crate::strategies::OrderSplitStrategy;
+use crate::models::{ChildOrder, IdGenerator, ParentOrder, Timestamp, TradingCalendar};
+use crate::strategies::{checked_split, OrderSplitStrategy, SplitSeeds};
+use rand::Rng;
+use std::time::SystemTime;
 
 /// Market state enum for adverse selection strategy
 #[derive(Debug, Clone, PartialEq)]
@@ -66,6 +66,13 @@ impl Default for AdverseSelectionConfig {
 pub struct AdverseSelectionStrategy {
     pub config: AdverseSelectionConfig,
     pub market_state: MarketState,
+    /// Optional calendar used to snap child schedules into trading sessions
+    pub calendar: Option<TradingCalendar>,
+    /// Source of child order ids
+    pub ids: IdGenerator,
+    /// Random source varying clip sizes and intervals, advanced by every split; the thread
+    /// RNG when unset
+    pub seeds: Option<SplitSeeds>,
 }
 
 impl AdverseSelectionStrategy {
@@ -73,9 +80,31 @@ impl AdverseSelectionStrategy {
         Self {
             config: config.unwrap_or_default(),
             market_state: MarketState::Normal,
+            calendar: None,
+            ids: IdGenerator::default(),
+            seeds: None,
         }
     }
-    
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Schedule child orders inside the sessions of the given calendar
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Draw clip sizes and intervals from a generator seeded with `seed`, each split from a
+    /// seed of its own that `OrderSplitStrategy::seed` returns
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seeds = Some(SplitSeeds::new(seed));
+        self
+    }
+
     pub fn update_market_state(&mut self, state: MarketState) {
         self.market_state = state;
     }
@@ -84,9 +113,50 @@ impl AdverseSelectionStrategy {
 /// Implement order splitting strategy
 impl OrderSplitStrategy for AdverseSelectionStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        match &self.seeds {
+            Some(seeds) => self.split_with_rng(parent_order, &mut seeds.next_rng()),
+            None => self.split_with_rng(parent_order, &mut rand::rng()),
+        }
+    }
+
+    fn seed(&self) -> Option<u64> {
+        self.seeds.as_ref().and_then(SplitSeeds::last)
+    }
+}
+
+impl AdverseSelectionStrategy {
+    /// Split the parent order from the current time, drawing variations from `rng`
+    pub fn split_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        // Get current time in milliseconds
+        let now_ms = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as u64,
+            Err(_) => 0,
+        };
+        self.split_at_with_rng(parent_order, now_ms, rng)
+    }
+
+    /// Split the parent order scheduling children from `now_ms` (UNIX milliseconds),
+    /// drawing size and interval variations from `rng`
+    pub fn split_at_with_rng<R: Rng + ?Sized>(
+        &self,
+        parent_order: &ParentOrder,
+        now_ms: u64,
+        rng: &mut R,
+    ) -> Vec<ChildOrder> {
+        // Never schedule before the parent itself, even when its timestamp is ahead of the
+        // local clock. With a calendar the whole schedule rolls to the next session, keeping
+        // its spacing
+        let now_ms = now_ms.max(parent_order.order_common.timestamp.as_millis());
+        let now_ms = match &self.calendar {
+            Some(calendar) => calendar.clamp_to_session(now_ms),
+            None => now_ms,
+        };
         let mut child_orders = Vec::new();
-        let mut rng = rand::thread_rng();
-        
+
         // Determine number of splits based on market state
         let num_splits = match self.market_state {
             MarketState::Normal => self.config.max_splits / 2,
@@ -96,36 +166,46 @@ impl OrderSplitStrategy for AdverseSelectionStrategy {
                 } else {
                     self.config.max_splits / 3
                 }
-            },
+            }
             MarketState::SellerInformed => {
                 if parent_order.order_common.side == Side::Sell {
                     self.config.max_splits
                 } else {
                     self.config.max_splits / 3
                 }
-            },
+            }
             MarketState::HighVolatility => self.config.max_splits,
         };
-        
+
         // Calculate base size for each child order
-        let base_quantity = parent_order.order_common.quantity / num_splits as u32;
+        let step = parent_order.order_common.lot_step();
+        let base_quantity = parent_order
+            .order_common
+            .quantity
+            .scale(1.0 / num_splits as f64);
         let mut remaining_quantity = parent_order.order_common.quantity;
-        
+
         // Create child orders
         for i in 0..num_splits {
             // Determine quantity for this child order
             let quantity = if i < num_splits - 1 {
-                let variation = (rng.gen::<f64>() * 0.2 - 0.1) * base_quantity as f64;
-                let qty = (base_quantity as f64 + variation).max(1.0) as u32;
+                let variation = 1.0 + rng.random::<f64>() * 0.2 - 0.1;
+                let qty = base_quantity
+                    .scale(variation)
+                    .round_down_to_step(step)
+                    .max(step);
                 qty.min(remaining_quantity)
             } else {
                 // Last order uses all remaining quantity
                 remaining_quantity
             };
-            
+
             // Update remaining quantity
             remaining_quantity = remaining_quantity.saturating_sub(quantity);
-            
+            if quantity.is_zero() {
+                continue;
+            }
+
             // Calculate execution time
             let interval_ms = if i == 0 {
                 0 // First order executes immediately
@@ -135,54 +215,54 @@ impl OrderSplitStrategy for AdverseSelectionStrategy {
                     MarketState::BuyerInformed | MarketState::SellerInformed => 8000, // 8 seconds
                     MarketState::HighVolatility => 3000, // 3 seconds
                 };
-                
-                let variation = (rng.gen::<f64>() * 0.4 - 0.2) * base_interval as f64;
+
+                let variation = (rng.random::<f64>() * 0.4 - 0.2) * base_interval as f64;
                 (base_interval as f64 * i as f64 + variation) as u64
             };
-            
-            // Get current time in milliseconds
-            let now = SystemTime::now();
-            let execution_time = match now.duration_since(SystemTime::UNIX_EPOCH) {
-                Ok(duration) => duration.as_millis() as u64 + interval_ms,
-                Err(_) => interval_ms,
+
+            let execution_time = match &self.calendar {
+                Some(calendar) => calendar.advance(now_ms, interval_ms),
+                None => now_ms + interval_ms,
             };
-            
+
             // Create a new order based on parent order
             let mut order = parent_order.order_common.clone();
-            order.id = format!("{}-{}", parent_order.order_common.id, i);
+            order.id = self.ids.next_id();
             order.quantity = quantity;
-            
+
             // Create child order
             let child_order = ChildOrder {
                 order_common: order,
                 strategy_id: parent_order.strategy_id.clone(),
                 parent_id: parent_order.order_common.id.clone(),
-                insert_at: Some(execution_time),
+                insert_at: Some(Timestamp::from_millis(execution_time)),
             };
-            
+
             child_orders.push(child_order);
         }
-        
-        child_orders
+
+        checked_split(parent_order, child_orders)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::orders::{Order, ProductType, OrderType, TimeInForce};
-    
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::Quantity;
+    use rust_decimal_macros::dec;
+
     #[test]
     fn test_split_normal_market() {
         let strategy = AdverseSelectionStrategy::new(None);
-        
+
         let order = Order::new(
             "parent-1".to_string(),
-            1000,
+            Quantity::from(1000),
             ProductType::Spot,
             OrderType::Limit,
-            Some(100.0),
-            1621500000000,
+            Some(dec!(100.0)),
+            Timestamp::from_millis(1621500000000),
             None,
             "BTC/USD".to_string(),
             Side::Buy,
@@ -196,22 +276,50 @@ mod tests {
             None,
             None,
         );
-        
+
         let parent_order = ParentOrder {
             order_common: order,
             strategy_id: "test-strategy".to_string(),
         };
-        
+
         let child_orders = strategy.split(&parent_order);
-        
+
         // In normal market state, we should have max_splits/2 child orders
         assert_eq!(child_orders.len(), strategy.config.max_splits / 2);
-        
+
         // Total quantity should match parent order
-        let total_quantity: u32 = child_orders.iter().map(|o| o.order_common.quantity).sum();
+        let total_quantity: Quantity = child_orders.iter().map(|o| o.order_common.quantity).sum();
         assert_eq!(total_quantity, parent_order.order_common.quantity);
-        
+
         // First child order should execute immediately
         assert!(child_orders[0].insert_at.unwrap() < child_orders[1].insert_at.unwrap());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_split_keeps_children_inside_sessions() {
+        // Saturday 2024-05-25 12:00 EDT and the following Monday open
+        let saturday = 1716652800000;
+        let monday_open = 1716816600000;
+        let calendar = TradingCalendar::us_equities();
+        let mut strategy = AdverseSelectionStrategy::new(None).with_calendar(calendar.clone());
+        strategy.update_market_state(MarketState::HighVolatility);
+        let parent_order = Order::builder()
+            .id("parent-1")
+            .quantity(1000)
+            .timestamp(saturday)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("test-strategy")
+            .unwrap();
+
+        let child_orders = strategy.split_at_with_rng(&parent_order, saturday, &mut rand::rng());
+        assert_eq!(
+            child_orders[0].insert_at,
+            Some(Timestamp::from_millis(monday_open))
+        );
+        for child in &child_orders {
+            assert!(calendar.is_open(child.insert_at.unwrap().as_millis()));
+        }
+    }
+}
@@ -30,13 +30,16 @@ pub mod common_strategies;
 pub mod dark_pool_based;
 pub mod inventory_based;
 pub mod market_microstructure_based;
+pub mod registry;
 pub mod technical_indicator_based;
 pub mod time_volume_based;
 
 pub use algo_based::*;
 pub use common_strategies::*;
+#[allow(unused_imports)]
 pub use dark_pool_based::*;
 pub use inventory_based::*;
 pub use market_microstructure_based::*;
+pub use registry::*;
 pub use technical_indicator_based::*;
 pub use time_volume_based::*;
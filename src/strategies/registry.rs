@@ -0,0 +1,526 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+use crate::config::{read_strategy_config, Config, ConfigError, StrategyConfig};
+use crate::strategies::algo_based::{TWAPConfig, TWAPStrategy, VWAPStrategy};
+use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::strategies::market_microstructure_based::{
+    AdverseSelectionConfig, AdverseSelectionStrategy,
+};
+use crate::strategies::technical_indicator_based::{
+    BollingerBandsStrategy, HeikinAshiStrategy, IchimokuStrategy, KeltnerChannelStrategy,
+    MACDStrategy, MAStrategy, MFIStrategy, OBVStrategy, ParabolicSARConfig, ParabolicSARStrategy,
+    RSIStrategy, RsiConfig, StochasticStrategy,
+};
+use crate::strategies::time_volume_based::VolumeProfile;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// A split strategy the engine can share between the parents it runs.
+pub type BoxedStrategy = Box<dyn OrderSplitStrategy + Send + Sync>;
+
+/// Builds a strategy from its parameters, or says why they do not fit it.
+pub type StrategyFactory =
+    Box<dyn Fn(&StrategyParams) -> Result<BoxedStrategy, ConfigError> + Send + Sync>;
+
+/// Reasons the registry cannot hand out a strategy.
+#[derive(Debug, Error, PartialEq)]
+pub enum StrategyError {
+    #[error("unknown strategy {name}, registered: {}", .registered.join(", "))]
+    UnknownStrategy {
+        name: String,
+        registered: Vec<String>,
+    },
+    #[error(transparent)]
+    InvalidParameters(#[from] ConfigError),
+}
+
+/// The parameters a strategy is built from: its table in the `strategies` section of
+/// `Config`, or nothing when the section leaves it out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyParams {
+    strategy: String,
+    value: Value,
+}
+
+impl StrategyParams {
+    pub fn new(strategy: impl Into<String>, value: Value) -> Self {
+        StrategyParams {
+            strategy: strategy.into(),
+            value,
+        }
+    }
+
+    /// No parameters, so the strategy takes its defaults.
+    pub fn empty(strategy: impl Into<String>) -> Self {
+        Self::new(strategy, Value::Null)
+    }
+
+    /// The parameters `config` gives `strategy`, if any.
+    pub fn from_config(config: &Config, strategy: &str) -> Self {
+        let value = config
+            .strategies
+            .get(&strategy.to_ascii_lowercase())
+            .cloned()
+            .unwrap_or(Value::Null);
+        Self::new(strategy, value)
+    }
+
+    pub fn strategy(&self) -> &str {
+        &self.strategy
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Reads the parameters as a `T` and validates them; `T::default()` when there are none.
+    pub fn parse<T: StrategyConfig + Default>(&self) -> Result<T, ConfigError> {
+        match &self.value {
+            Value::Null => Ok(T::default()),
+            value => read_strategy_config(&self.strategy, value),
+        }
+    }
+
+    /// Reads the field `name`, or `default` when the parameters leave it out.
+    pub fn field<T: DeserializeOwned>(&self, name: &str, default: T) -> Result<T, ConfigError> {
+        match self.fields()?.and_then(|fields| fields.get(name)) {
+            Some(value) => {
+                T::deserialize(value).map_err(|e| self.invalid(format!("field {}: {}", name, e)))
+            }
+            None => Ok(default),
+        }
+    }
+
+    /// Rejects the parameters when they hold a field not in `known`, such as a misspelt one.
+    pub fn allow_only(&self, known: &[&str]) -> Result<(), ConfigError> {
+        let unknown = self
+            .fields()?
+            .and_then(|fields| fields.keys().find(|name| !known.contains(&name.as_str())));
+        match unknown {
+            Some(name) => Err(self.invalid(format!("unknown field {}", name))),
+            None => Ok(()),
+        }
+    }
+
+    fn fields(&self) -> Result<Option<&serde_json::Map<String, Value>>, ConfigError> {
+        match &self.value {
+            Value::Null => Ok(None),
+            Value::Object(fields) => Ok(Some(fields)),
+            _ => Err(self.invalid("expected a table of parameters".to_string())),
+        }
+    }
+
+    fn invalid(&self, reason: String) -> ConfigError {
+        ConfigError::InvalidStrategy {
+            strategy: self.strategy.clone(),
+            reason,
+        }
+    }
+}
+
+/// Builds the split strategy a `ParentOrder` names in its `strategy_id`.
+///
+/// Names are matched case-insensitively, so `TWAP` and `twap` find the same strategy.
+#[derive(Default)]
+pub struct StrategyRegistry {
+    factories: HashMap<String, StrategyFactory>,
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the strategies of this crate, under these names and parameters:
+    ///
+    /// - `twap`: the fields of `TWAPConfig`, `slices` (10) children `interval_ms` (60000) apart.
+    /// - `vwap`: `profile`, a `VolumeProfile`; without one the day is split into equal hours.
+    /// - `adverse_selection`: the fields of `AdverseSelectionConfig`.
+    /// - `rsi`: the fields of `RsiConfig`.
+    /// - `bollinger_bands`: `period` (20), `std_dev_multiplier` (2.0).
+    /// - `stochastic`: `k_period` (14), `d_period` (3), `overbought_threshold` (80.0),
+    ///   `oversold_threshold` (20.0).
+    /// - `heikin_ashi`: `window_size` (10).
+    /// - `ma`: `short_period` (10), `long_period` (30).
+    /// - `macd`: `fast_period` (12), `slow_period` (26), `signal_period` (9).
+    /// - `keltner`: `ema_period` (20), `atr_period` (10), `multiplier` (2.0).
+    /// - `obv`: `swing_lookback` (5), `ma_period` (20).
+    /// - `mfi`: `period` (14), `overbought_threshold` (80.0), `oversold_threshold` (20.0).
+    /// - `parabolic_sar`: `initial_af` (0.02), `af_step` (0.02), `af_max` (0.2).
+    /// - `ichimoku`: `tenkan_period` (9), `kijun_period` (26), `senkou_b_period` (52),
+    ///   `displacement` (26).
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("twap", |params| {
+            let config: TWAPConfig = params.parse()?;
+            Ok(Box::new(TWAPStrategy::new(config)))
+        });
+        registry.register("vwap", |params| {
+            params.allow_only(&["profile"])?;
+            let profile = params.field(
+                "profile",
+                VolumeProfile {
+                    bucket_width_ms: 3_600_000,
+                    fractions: vec![1.0 / 24.0; 24],
+                },
+            )?;
+            Ok(Box::new(VWAPStrategy::new(profile)))
+        });
+        registry.register("adverse_selection", |params| {
+            let config: AdverseSelectionConfig = params.parse()?;
+            Ok(Box::new(AdverseSelectionStrategy::new(Some(config))))
+        });
+        registry.register("rsi", |params| {
+            let config: RsiConfig = params.parse()?;
+            Ok(Box::new(RSIStrategy::from_config(&config)))
+        });
+        registry.register("bollinger_bands", |params| {
+            params.allow_only(&["period", "std_dev_multiplier"])?;
+            Ok(Box::new(BollingerBandsStrategy::new(
+                params.field("period", 20)?,
+                params.field("std_dev_multiplier", 2.0)?,
+            )))
+        });
+        registry.register("stochastic", |params| {
+            params.allow_only(&[
+                "k_period",
+                "d_period",
+                "overbought_threshold",
+                "oversold_threshold",
+            ])?;
+            Ok(Box::new(StochasticStrategy::new(
+                params.field("k_period", 14)?,
+                params.field("d_period", 3)?,
+                params.field("overbought_threshold", 80.0)?,
+                params.field("oversold_threshold", 20.0)?,
+            )))
+        });
+        registry.register("heikin_ashi", |params| {
+            params.allow_only(&["window_size"])?;
+            Ok(Box::new(HeikinAshiStrategy::new(
+                params.field("window_size", 10)?,
+            )))
+        });
+        registry.register("ma", |params| {
+            params.allow_only(&["short_period", "long_period"])?;
+            Ok(Box::new(MAStrategy::new(
+                params.field("short_period", 10)?,
+                params.field("long_period", 30)?,
+            )))
+        });
+        registry.register("macd", |params| {
+            params.allow_only(&["fast_period", "slow_period", "signal_period"])?;
+            Ok(Box::new(MACDStrategy::new(
+                params.field("fast_period", 12)?,
+                params.field("slow_period", 26)?,
+                params.field("signal_period", 9)?,
+            )))
+        });
+        registry.register("keltner", |params| {
+            params.allow_only(&["ema_period", "atr_period", "multiplier"])?;
+            Ok(Box::new(KeltnerChannelStrategy::new(
+                params.field("ema_period", 20)?,
+                params.field("atr_period", 10)?,
+                params.field("multiplier", 2.0)?,
+            )))
+        });
+        registry.register("obv", |params| {
+            params.allow_only(&["swing_lookback", "ma_period"])?;
+            Ok(Box::new(OBVStrategy::new(
+                params.field("swing_lookback", 5)?,
+                params.field("ma_period", 20)?,
+            )))
+        });
+        registry.register("mfi", |params| {
+            params.allow_only(&["period", "overbought_threshold", "oversold_threshold"])?;
+            Ok(Box::new(MFIStrategy::new(
+                params.field("period", 14)?,
+                params.field("overbought_threshold", 80.0)?,
+                params.field("oversold_threshold", 20.0)?,
+            )))
+        });
+        registry.register("parabolic_sar", |params| {
+            params.allow_only(&["initial_af", "af_step", "af_max"])?;
+            let defaults = ParabolicSARConfig::default();
+            Ok(Box::new(ParabolicSARStrategy::new(ParabolicSARConfig {
+                initial_af: params.field("initial_af", defaults.initial_af)?,
+                af_step: params.field("af_step", defaults.af_step)?,
+                af_max: params.field("af_max", defaults.af_max)?,
+            })))
+        });
+        registry.register("ichimoku", |params| {
+            params.allow_only(&[
+                "tenkan_period",
+                "kijun_period",
+                "senkou_b_period",
+                "displacement",
+            ])?;
+            Ok(Box::new(IchimokuStrategy::new(
+                params.field("tenkan_period", 9)?,
+                params.field("kijun_period", 26)?,
+                params.field("senkou_b_period", 52)?,
+                params.field("displacement", 26)?,
+            )))
+        });
+        registry
+    }
+
+    /// Makes `factory` build the strategy named `name`, replacing any factory registered
+    /// under that name before.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&StrategyParams) -> Result<BoxedStrategy, ConfigError> + Send + Sync + 'static,
+    ) {
+        self.factories
+            .insert(name.to_ascii_lowercase(), Box::new(factory));
+    }
+
+    /// Builds the strategy named `name` from `params`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StrategyError::UnknownStrategy` when no strategy is registered as `name`, and
+    /// `StrategyError::InvalidParameters` when its factory rejects the parameters.
+    pub fn create(
+        &self,
+        name: &str,
+        params: &StrategyParams,
+    ) -> Result<BoxedStrategy, StrategyError> {
+        let factory = self
+            .factories
+            .get(&name.to_ascii_lowercase())
+            .ok_or_else(|| StrategyError::UnknownStrategy {
+                name: name.to_string(),
+                registered: self.names().into_iter().map(str::to_string).collect(),
+            })?;
+        Ok(factory(params)?)
+    }
+
+    /// Builds the strategy named `name` from its parameters in `config`.
+    pub fn create_from_config(
+        &self,
+        name: &str,
+        config: &Config,
+    ) -> Result<BoxedStrategy, StrategyError> {
+        self.create(name, &StrategyParams::from_config(config, name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&name.to_ascii_lowercase())
+    }
+
+    /// The registered strategy names, lowercased and sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl fmt::Debug for StrategyRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrategyRegistry")
+            .field("strategies", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, Side};
+    use crate::models::{ChildOrder, ParentOrder, Timestamp};
+    use serde_json::json;
+
+    /// Sends the whole parent as one child, scheduled after `delay_ms`.
+    struct Delayed(u64);
+
+    impl OrderSplitStrategy for Delayed {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            let timestamp = parent.order_common.timestamp.as_millis();
+            let mut child = parent.order_common.clone();
+            child.id = format!("{}-1", child.id);
+            vec![child.into_child(
+                "delayed",
+                &parent.order_common.id,
+                Some(Timestamp::from_millis(timestamp + self.0)),
+            )]
+        }
+    }
+
+    fn parent() -> ParentOrder {
+        Order::builder()
+            .id("parent-1")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_parent("delayed")
+    }
+
+    #[test]
+    fn test_default_registrations_resolve() {
+        let registry = StrategyRegistry::with_defaults();
+        assert_eq!(
+            registry.names(),
+            vec![
+                "adverse_selection",
+                "bollinger_bands",
+                "heikin_ashi",
+                "ichimoku",
+                "keltner",
+                "ma",
+                "macd",
+                "mfi",
+                "obv",
+                "parabolic_sar",
+                "rsi",
+                "stochastic",
+                "twap",
+                "vwap",
+            ]
+        );
+        for name in registry.names() {
+            assert!(
+                registry.create(name, &StrategyParams::empty(name)).is_ok(),
+                "{name} does not build from its defaults"
+            );
+        }
+        // Names match whatever their case
+        assert!(registry.contains("TWAP"));
+        assert!(registry
+            .create(
+                "Adverse_Selection",
+                &StrategyParams::empty("adverse_selection")
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unknown_strategy_is_an_error() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("delayed", |_| Ok(Box::new(Delayed(0))));
+
+        let error = registry
+            .create("iceberg", &StrategyParams::empty("iceberg"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            StrategyError::UnknownStrategy {
+                name: "iceberg".to_string(),
+                registered: vec!["delayed".to_string()],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "unknown strategy iceberg, registered: delayed"
+        );
+    }
+
+    #[test]
+    fn test_custom_registration_round_trips() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("delayed", |params| {
+            params.allow_only(&["delay_ms"])?;
+            Ok(Box::new(Delayed(params.field("delay_ms", 0)?)))
+        });
+
+        let strategy = registry
+            .create(
+                "delayed",
+                &StrategyParams::new("delayed", json!({"delay_ms": 250})),
+            )
+            .unwrap();
+        let children = strategy.split(&parent());
+        assert_eq!(
+            children[0].insert_at,
+            Some(Timestamp::from_millis(1622512800250))
+        );
+
+        // Parameters come from the strategies section of the config
+        let mut config = Config::default();
+        config
+            .strategies
+            .insert("delayed".to_string(), json!({"delay_ms": 1000}));
+        let strategy = registry.create_from_config("Delayed", &config).unwrap();
+        assert_eq!(
+            strategy.split(&parent())[0].insert_at,
+            Some(Timestamp::from_millis(1622512801000))
+        );
+
+        // Registering again replaces the factory
+        registry.register("delayed", |_| Ok(Box::new(Delayed(5))));
+        let strategy = registry.create_from_config("delayed", &config).unwrap();
+        assert_eq!(
+            strategy.split(&parent())[0].insert_at,
+            Some(Timestamp::from_millis(1622512800005))
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters_are_rejected() {
+        let registry = StrategyRegistry::with_defaults();
+        let invalid = |name: &str, value: Value| match registry
+            .create(name, &StrategyParams::new(name, value))
+        {
+            Err(StrategyError::InvalidParameters(ConfigError::InvalidStrategy {
+                strategy,
+                reason,
+            })) => {
+                assert_eq!(strategy, name);
+                reason
+            }
+            Err(other) => panic!("unexpected error {other}"),
+            Ok(_) => panic!("{name} accepted invalid parameters"),
+        };
+
+        assert!(invalid("rsi", json!({"oversold_threshold": 90.0})).contains("below"));
+        assert_eq!(
+            invalid("bollinger_bands", json!({"perod": 10})),
+            "unknown field perod"
+        );
+        assert!(invalid("macd", json!({"fast_period": "fast"})).starts_with("field fast_period"));
+        assert_eq!(
+            invalid("twap", json!({"slices": 0})),
+            "slices must be at least 1"
+        );
+        assert_eq!(
+            invalid("vwap", json!(["profile"])),
+            "expected a table of parameters"
+        );
+    }
+}
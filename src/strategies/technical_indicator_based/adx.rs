@@ -0,0 +1,319 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Average Directional Index (ADX) filter
++DI and -DI measure the strength of upward and downward moves relative to the true range,
+and the ADX is the Wilder-smoothed average of their normalized difference. A high ADX means
+a strong trend, where mean-reversion signals against the trend tend to lose money; the
+filter blocks those signals while letting signals in the trend direction through.
+
+Reference for Further Reading:
+"New Concepts in Technical Trading Systems" by J. Welles Wilder (1978).
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{OrderSplitStrategy, SignalDecision};
+
+/// ADX trend-strength filter
+#[derive(Debug, Clone)]
+pub struct ADXFilter {
+    /// Wilder smoothing period
+    period: usize,
+    /// ADX above which counter-trend signals are suppressed
+    threshold: f64,
+    prev_candle: Option<Candle>,
+    /// Number of directional movements seen
+    count: usize,
+    /// Wilder-smoothed true range, +DM and -DM
+    smoothed_tr: f64,
+    smoothed_plus_dm: f64,
+    smoothed_minus_dm: f64,
+    /// DX values collected until the first ADX value is available
+    dx_seed: Vec<f64>,
+    adx: Option<f64>,
+}
+
+impl ADXFilter {
+    /// Creates a new filter with the given smoothing period and ADX threshold
+    pub fn new(period: usize, threshold: f64) -> Self {
+        let period = period.max(1);
+        ADXFilter {
+            period,
+            threshold,
+            prev_candle: None,
+            count: 0,
+            smoothed_tr: 0.0,
+            smoothed_plus_dm: 0.0,
+            smoothed_minus_dm: 0.0,
+            dx_seed: Vec::with_capacity(period),
+            adx: None,
+        }
+    }
+
+    /// Adds a candle and updates the directional indicators
+    pub fn add_candle(&mut self, candle: &Candle) {
+        let prev = match self.prev_candle.replace(*candle) {
+            Some(prev) => prev,
+            None => return,
+        };
+
+        let up_move = candle.high - prev.high;
+        let down_move = prev.low - candle.low;
+        let plus_dm = if up_move > down_move && up_move > 0.0 {
+            up_move
+        } else {
+            0.0
+        };
+        let minus_dm = if down_move > up_move && down_move > 0.0 {
+            down_move
+        } else {
+            0.0
+        };
+        let true_range = (candle.high - candle.low)
+            .max((candle.high - prev.close).abs())
+            .max((candle.low - prev.close).abs());
+
+        // Wilder smoothing: plain sums over the first period, then S - S / n + x
+        let n = self.period as f64;
+        if self.count < self.period {
+            self.smoothed_tr += true_range;
+            self.smoothed_plus_dm += plus_dm;
+            self.smoothed_minus_dm += minus_dm;
+        } else {
+            self.smoothed_tr += true_range - self.smoothed_tr / n;
+            self.smoothed_plus_dm += plus_dm - self.smoothed_plus_dm / n;
+            self.smoothed_minus_dm += minus_dm - self.smoothed_minus_dm / n;
+        }
+        self.count += 1;
+
+        let dx = match (self.plus_di(), self.minus_di()) {
+            (Some(plus_di), Some(minus_di)) if plus_di + minus_di > 0.0 => {
+                100.0 * (plus_di - minus_di).abs() / (plus_di + minus_di)
+            }
+            (Some(_), Some(_)) => 0.0,
+            _ => return,
+        };
+        self.adx = match self.adx {
+            Some(adx) => Some((adx * (n - 1.0) + dx) / n),
+            None => {
+                self.dx_seed.push(dx);
+                (self.dx_seed.len() == self.period).then(|| self.dx_seed.iter().sum::<f64>() / n)
+            }
+        };
+    }
+
+    fn directional_index(&self, smoothed_dm: f64) -> Option<f64> {
+        if self.count < self.period {
+            return None;
+        }
+        if self.smoothed_tr <= 0.0 {
+            return Some(0.0);
+        }
+        Some(100.0 * smoothed_dm / self.smoothed_tr)
+    }
+
+    /// Positive directional indicator (+DI)
+    pub fn plus_di(&self) -> Option<f64> {
+        self.directional_index(self.smoothed_plus_dm)
+    }
+
+    /// Negative directional indicator (-DI)
+    pub fn minus_di(&self) -> Option<f64> {
+        self.directional_index(self.smoothed_minus_dm)
+    }
+
+    /// Average directional index
+    pub fn adx(&self) -> Option<f64> {
+        self.adx
+    }
+
+    /// Direction of the current trend: Buy for an uptrend, Sell for a downtrend
+    pub fn trend(&self) -> Option<Side> {
+        let plus_di = self.plus_di()?;
+        let minus_di = self.minus_di()?;
+        if plus_di > minus_di {
+            Some(Side::Buy)
+        } else if minus_di > plus_di {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a signal on `side` is allowed: blocked only when the ADX exceeds the
+    /// threshold and the trend points the other way
+    pub fn allows(&self, side: &Side) -> bool {
+        match (self.adx, self.trend()) {
+            (Some(adx), Some(trend)) if adx > self.threshold => trend == *side,
+            _ => true,
+        }
+    }
+}
+
+/// Wraps a signal strategy and suppresses its child orders against a strong trend
+pub struct FilteredStrategy<S> {
+    pub inner: S,
+    pub filter: ADXFilter,
+}
+
+impl<S: OrderSplitStrategy> FilteredStrategy<S> {
+    pub fn new(inner: S, filter: ADXFilter) -> Self {
+        FilteredStrategy { inner, filter }
+    }
+}
+
+impl<S: OrderSplitStrategy> OrderSplitStrategy for FilteredStrategy<S> {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        // Signal strategies only emit children when their signal matches the parent side
+        if !self.filter.allows(&parent_order.order_common.side) {
+            return Vec::new();
+        }
+        self.inner.split(parent_order)
+    }
+
+    /// The decision of the inner strategy, with the ADX readings the filter applies
+    fn decision(&self) -> Option<SignalDecision> {
+        let mut decision = self.inner.decision()?;
+        let readings = [
+            ("adx", self.filter.adx()),
+            ("plus_di", self.filter.plus_di()),
+            ("minus_di", self.filter.minus_di()),
+        ];
+        for (name, value) in readings {
+            if let Some(value) = value {
+                decision.indicators.insert(name.to_string(), value);
+            }
+        }
+        Some(decision)
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        self.inner.on_market_data(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use crate::strategies::technical_indicator_based::RSIStrategy;
+    use std::time::UNIX_EPOCH;
+
+    fn trending_candles(count: usize, step: f64) -> Vec<Candle> {
+        (0..count)
+            .map(|i| {
+                let close = 100.0 + step * i as f64;
+                Candle {
+                    timestamp: UNIX_EPOCH,
+                    open: close - step / 2.0,
+                    high: close + 0.5,
+                    low: close - 0.5,
+                    close,
+                    volume: 1000.0,
+                }
+            })
+            .collect()
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "filtered".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_adx_strong_uptrend() {
+        let mut filter = ADXFilter::new(14, 25.0);
+        for candle in trending_candles(40, 1.0) {
+            filter.add_candle(&candle);
+        }
+        assert_eq!(filter.minus_di(), Some(0.0));
+        assert!(filter.plus_di().unwrap() > 50.0);
+        assert!((filter.adx().unwrap() - 100.0).abs() < 1e-9);
+        assert_eq!(filter.trend(), Some(Side::Buy));
+        assert!(filter.allows(&Side::Buy));
+        assert!(!filter.allows(&Side::Sell));
+    }
+
+    #[test]
+    fn test_filter_allows_everything_without_trend_data() {
+        let mut filter = ADXFilter::new(14, 25.0);
+        for candle in trending_candles(10, -1.0) {
+            filter.add_candle(&candle);
+        }
+        assert!(filter.adx().is_none());
+        assert!(filter.allows(&Side::Buy));
+        assert!(filter.allows(&Side::Sell));
+    }
+
+    #[test]
+    fn test_filtered_rsi_in_strong_trend() {
+        let candles = trending_candles(40, 1.0);
+        let mut rsi = RSIStrategy::new(14, 70.0, 30.0);
+        let mut filter = ADXFilter::new(14, 25.0);
+        for candle in &candles {
+            rsi.add_price(candle.close);
+            filter.add_candle(candle);
+        }
+
+        // RSI is overbought and fires a Sell on its own
+        let sell_parent = parent_order(Side::Sell);
+        assert_eq!(rsi.get_signal(), Some(Side::Sell));
+        assert_eq!(rsi.split(&sell_parent).len(), 1);
+
+        // The strong uptrend suppresses it through the filter
+        let strategy = FilteredStrategy::new(rsi, filter);
+        assert!(strategy.split(&sell_parent).is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Average True Range (ATR) sizing
+The true range of a candle is the largest of high - low, |high - previous close| and
+|low - previous close|. The ATR is its Wilder-smoothed average, seeded with the simple
+average of the first `period` true ranges. Child quantities are sized so that a one-ATR
+move on a clip risks a fixed amount, shrinking clips when volatility spikes.
+
+Reference for Further Reading:
+"New Concepts in Technical Trading Systems" by J. Welles Wilder (1978).
+*/
+
+use crate::models::market_data::Candle;
+use crate::models::Quantity;
+
+/// Incrementally Wilder-smoothed Average True Range
+#[derive(Debug, Clone)]
+pub(super) struct AverageTrueRange {
+    /// Wilder smoothing period
+    period: usize,
+    /// True ranges collected until the first ATR value is available
+    seed: Vec<f64>,
+    /// Close of the previous candle
+    prev_close: Option<f64>,
+    /// Current ATR value
+    atr: Option<f64>,
+}
+
+impl AverageTrueRange {
+    pub(super) fn new(period: usize) -> Self {
+        let period = period.max(1);
+        AverageTrueRange {
+            period,
+            seed: Vec::with_capacity(period),
+            prev_close: None,
+            atr: None,
+        }
+    }
+
+    /// Adds a candle and returns the updated ATR
+    pub(super) fn update(&mut self, candle: &Candle) -> Option<f64> {
+        let true_range = match self.prev_close {
+            Some(prev_close) => (candle.high - candle.low)
+                .max((candle.high - prev_close).abs())
+                .max((candle.low - prev_close).abs()),
+            // No previous close on the first candle
+            None => candle.high - candle.low,
+        };
+        self.prev_close = Some(candle.close);
+
+        self.atr = match self.atr {
+            Some(atr) => Some((atr * (self.period - 1) as f64 + true_range) / self.period as f64),
+            None => {
+                self.seed.push(true_range);
+                (self.seed.len() == self.period)
+                    .then(|| self.seed.iter().sum::<f64>() / self.period as f64)
+            }
+        };
+        self.atr
+    }
+
+    pub(super) fn value(&self) -> Option<f64> {
+        self.atr
+    }
+}
+
+/// Average True Range based child order sizer
+#[derive(Debug, Clone)]
+pub struct ATRSizer {
+    atr: AverageTrueRange,
+    /// Amount risked per clip on a one-ATR move
+    risk_per_clip: f64,
+}
+
+impl ATRSizer {
+    /// Creates a new sizer with the given smoothing period and risk per clip
+    pub fn new(period: usize, risk_per_clip: f64) -> Self {
+        ATRSizer {
+            atr: AverageTrueRange::new(period),
+            risk_per_clip,
+        }
+    }
+
+    /// Adds a candle and updates the ATR
+    pub fn add_candle(&mut self, candle: &Candle) {
+        self.atr.update(candle);
+    }
+
+    /// Current ATR, once `period` candles have been seen
+    pub fn atr(&self) -> Option<f64> {
+        self.atr.value()
+    }
+
+    /// Quantity for which a one-ATR move risks `risk_per_clip`, unrounded; `None` when the
+    /// ATR is not positive and so caps nothing
+    pub fn size_for(risk_per_clip: f64, atr: f64) -> Option<Quantity> {
+        if atr <= 0.0 {
+            return None;
+        }
+        Some(Quantity::from_f64((risk_per_clip / atr).max(0.0)))
+    }
+
+    /// Largest clip allowed by the current ATR, if available
+    pub fn clip_limit(&self) -> Option<Quantity> {
+        self.atr()
+            .and_then(|atr| Self::size_for(self.risk_per_clip, atr))
+    }
+
+    /// Clip limit rounded down to whole lots of `step`. A limit below one lot still allows
+    /// one lot, so a volatility spike slows the schedule down instead of zeroing every clip
+    pub fn clip_limit_in_lots(&self, step: Quantity) -> Option<Quantity> {
+        self.clip_limit()
+            .map(|limit| limit.round_down_to_step(step).max(step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn candle(close: f64, range: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: close,
+            high: close + range / 2.0,
+            low: close - range / 2.0,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    #[test]
+    fn test_first_candle_uses_high_low() {
+        let mut sizer = ATRSizer::new(1, 100.0);
+        sizer.add_candle(&Candle {
+            timestamp: UNIX_EPOCH,
+            open: 100.0,
+            high: 104.0,
+            low: 99.0,
+            close: 103.0,
+            volume: 1000.0,
+        });
+        assert_eq!(sizer.atr(), Some(5.0));
+
+        // Gap up: the true range spans from the previous close
+        sizer.add_candle(&Candle {
+            timestamp: UNIX_EPOCH,
+            open: 110.0,
+            high: 111.0,
+            low: 109.0,
+            close: 110.0,
+            volume: 1000.0,
+        });
+        assert_eq!(sizer.atr(), Some(8.0));
+    }
+
+    #[test]
+    fn test_wilder_smoothing() {
+        let mut sizer = ATRSizer::new(3, 100.0);
+        for range in [1.0, 2.0] {
+            sizer.add_candle(&candle(100.0, range));
+            assert!(sizer.atr().is_none());
+        }
+        sizer.add_candle(&candle(100.0, 3.0));
+        assert_eq!(sizer.atr(), Some(2.0));
+
+        // (2.0 * 2 + 5.0) / 3
+        sizer.add_candle(&candle(100.0, 5.0));
+        assert_eq!(sizer.atr(), Some(3.0));
+    }
+
+    #[test]
+    fn test_size_for() {
+        assert_eq!(ATRSizer::size_for(1000.0, 2.0), Some(Quantity::from(500)));
+        assert_eq!(ATRSizer::size_for(1000.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_fractional_cap_keeps_its_fraction() {
+        // 1000 / 400 caps clips at 2.5: whole lots of 1 round it to 2, lots of 0.5 keep it
+        let cap = ATRSizer::size_for(1000.0, 400.0).unwrap();
+        assert_eq!(cap, Quantity::from_f64(2.5));
+
+        let mut sizer = ATRSizer::new(1, 1000.0);
+        sizer.add_candle(&candle(1000.0, 400.0));
+        assert_eq!(sizer.clip_limit(), Some(Quantity::from_f64(2.5)));
+        assert_eq!(
+            sizer.clip_limit_in_lots(Quantity::from(1)),
+            Some(Quantity::from(2))
+        );
+        assert_eq!(
+            sizer.clip_limit_in_lots(Quantity::from_f64(0.5)),
+            Some(Quantity::from_f64(2.5))
+        );
+    }
+
+    #[test]
+    fn test_sub_lot_cap_falls_back_to_one_lot() {
+        // 100 / 400 caps clips at 0.25, less than a lot of 1
+        let mut sizer = ATRSizer::new(1, 100.0);
+        sizer.add_candle(&candle(1000.0, 400.0));
+        assert_eq!(sizer.clip_limit(), Some(Quantity::from_f64(0.25)));
+        assert_eq!(
+            sizer.clip_limit_in_lots(Quantity::from(1)),
+            Some(Quantity::from(1))
+        );
+    }
+
+    #[test]
+    fn test_clips_shrink_with_volatility_regime() {
+        let mut sizer = ATRSizer::new(14, 1000.0);
+        assert!(sizer.clip_limit().is_none());
+
+        // Calm regime: true range of 1.0
+        for _ in 0..30 {
+            sizer.add_candle(&candle(100.0, 1.0));
+        }
+        let calm_clip = sizer.clip_limit().unwrap().to_f64();
+        assert!((calm_clip - 1000.0).abs() < 1e-6);
+
+        // Volatile regime: true range of 4.0, the ATR converges towards 4.0
+        for _ in 0..200 {
+            sizer.add_candle(&candle(100.0, 4.0));
+        }
+        let volatile_clip = sizer.clip_limit().unwrap().to_f64();
+        assert!((volatile_clip - calm_clip / 4.0).abs() <= 1.0);
+    }
+}
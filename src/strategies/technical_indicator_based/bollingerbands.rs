@@ -6,21 +6,59 @@
 
 例如：当价格触及下轨并开始回升时买入，当价格触及上轨并开始回落时卖出。
 
+挤压突破模式下，带宽（(上轨 - 下轨) / 中轨）跌至自身N周期低点视为"挤压"，
+挤压之后收盘价突破上轨时买入，跌破下轨时卖出。
+
+确认模式下不在首次触及下轨时买入，而是等%B（(价格 - 下轨) / (上轨 - 下轨)）
+从0以下回到0以上时再买入，避免在暴跌途中接飞刀；卖出同理。
+
 参考文献：
 "Bollinger on Bollinger Bands" by John Bollinger：这本书由布林带的创始人撰写，
 详细介绍了布林带的使用方法和策略。(第4章详细讨论了布林带交易策略)
 */
 
-use std::collections::VecDeque;
-use crate::models::orders::Side;
 use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
 use crate::models::parent_orders::ParentOrder;
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::strategies::common_strategies::{
+    checked_split, signal_child_orders, OrderSplitStrategy, Signal, SignalDecision, SignalStrategy,
+    Snapshot, StrengthSizing,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 
+// 信号模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignalMode {
+    // 触及下轨买入，触及上轨卖出（默认）
+    MeanReversion,
+    // 挤压之后顺着突破方向交易
+    SqueezeBreakout,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct BollingerBandsStrategy {
     period: usize,
     std_dev_multiplier: f64,
     prices: VecDeque<f64>,
+    // 已处理的价格数量
+    samples: usize,
+    mode: SignalMode,
+    // 判断挤压时回看的带宽周期数
+    squeeze_lookback: usize,
+    // 最近 squeeze_lookback 个带宽（不含当前K线）
+    band_widths: VecDeque<f64>,
+    // 当前K线是否处于挤压状态
+    squeezed: bool,
+    // 上一根K线是否处于挤压状态
+    was_squeezed: bool,
+    // 均值回归模式下是否等待%B重新回到带内再发出信号
+    confirmation: bool,
+    // 最近两根K线的%B，带宽为0时无定义
+    percent_b_values: VecDeque<Option<f64>>,
+    // 按信号强度缩放子订单数量
+    sizing: Option<StrengthSizing>,
 }
 
 impl BollingerBandsStrategy {
@@ -29,73 +67,289 @@ impl BollingerBandsStrategy {
             period,
             std_dev_multiplier,
             prices: VecDeque::with_capacity(period),
+            samples: 0,
+            mode: SignalMode::MeanReversion,
+            squeeze_lookback: period,
+            band_widths: VecDeque::with_capacity(period),
+            squeezed: false,
+            was_squeezed: false,
+            confirmation: false,
+            percent_b_values: VecDeque::with_capacity(2),
+            sizing: None,
         }
     }
-    
+
+    // 按信号强度缩放子订单数量
+    pub fn with_strength_sizing(mut self, sizing: StrengthSizing) -> Self {
+        self.sizing = Some(sizing);
+        self
+    }
+
+    // 开启%B确认模式
+    pub fn with_confirmation(mut self, confirmation: bool) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    // 设置信号模式及挤压判断的回看周期
+    pub fn with_signal_mode(mut self, mode: SignalMode, squeeze_lookback: usize) -> Self {
+        self.mode = mode;
+        self.squeeze_lookback = squeeze_lookback.max(1);
+        self.band_widths = VecDeque::with_capacity(self.squeeze_lookback);
+        self
+    }
+
     pub fn add_price(&mut self, price: f64) {
+        self.samples += 1;
         self.prices.push_back(price);
         if self.prices.len() > self.period {
             self.prices.pop_front();
         }
+
+        if self.prices.len() >= self.period {
+            self.percent_b_values.push_back(self.percent_b());
+            if self.percent_b_values.len() > 2 {
+                self.percent_b_values.pop_front();
+            }
+        }
+
+        let width = match self.band_width() {
+            Some(width) => width,
+            None => return,
+        };
+
+        // 带宽不高于此前N周期的最低带宽即为挤压
+        self.was_squeezed = self.squeezed;
+        self.squeezed = self.band_widths.len() >= self.squeeze_lookback
+            && self.band_widths.iter().all(|&previous| width <= previous);
+
+        self.band_widths.push_back(width);
+        if self.band_widths.len() > self.squeeze_lookback {
+            self.band_widths.pop_front();
+        }
+    }
+
+    // 当前模式所需的数据都已积累
+    pub fn is_ready(&self) -> bool {
+        self.warmup_remaining() == 0
     }
-    
+
+    // 距离就绪还需要的价格数量
+    pub fn warmup_remaining(&self) -> usize {
+        let required = match self.mode {
+            // 挤压判断需要回看 squeeze_lookback 个带宽，突破还需要再多一根K线
+            SignalMode::SqueezeBreakout => self.period + self.squeeze_lookback + 1,
+            // 确认模式需要前后两根K线的%B
+            SignalMode::MeanReversion if self.confirmation => self.period + 1,
+            SignalMode::MeanReversion => self.period,
+        };
+        required.saturating_sub(self.samples)
+    }
+
+    pub fn reset(&mut self) {
+        self.prices.clear();
+        self.samples = 0;
+        self.band_widths.clear();
+        self.percent_b_values.clear();
+        self.squeezed = false;
+        self.was_squeezed = false;
+    }
+
+    // 带宽：(上轨 - 下轨) / 中轨
+    pub fn band_width(&self) -> Option<f64> {
+        let (lower, middle, upper) = self.calculate_bands()?;
+        if middle == 0.0 {
+            return None;
+        }
+        Some((upper - lower) / middle)
+    }
+
+    // %B：价格在布林带中的相对位置，低于0表示跌破下轨，高于1表示突破上轨
+    pub fn percent_b(&self) -> Option<f64> {
+        let (lower, _, upper) = self.calculate_bands()?;
+        if upper == lower {
+            return None;
+        }
+        let current_price = *self.prices.back()?;
+        Some((current_price - lower) / (upper - lower))
+    }
+
+    // 当前K线是否处于挤压状态
+    pub fn is_squeeze(&self) -> bool {
+        self.squeezed
+    }
+
     pub fn calculate_bands(&self) -> Option<(f64, f64, f64)> {
         if self.prices.len() < self.period {
             return None;
         }
-        
+
         // 计算中轨（SMA）
         let middle_band: f64 = self.prices.iter().sum::<f64>() / self.period as f64;
-        
+
         // 计算标准差
-        let variance: f64 = self.prices.iter()
+        let variance: f64 = self
+            .prices
+            .iter()
             .map(|&price| (price - middle_band).powi(2))
-            .sum::<f64>() / self.period as f64;
+            .sum::<f64>()
+            / self.period as f64;
         let std_dev = variance.sqrt();
-        
+
         // 计算上下轨
         let upper_band = middle_band + (self.std_dev_multiplier * std_dev);
         let lower_band = middle_band - (self.std_dev_multiplier * std_dev);
-        
+
         Some((lower_band, middle_band, upper_band))
     }
-    
+
     pub fn get_signal(&self) -> Option<Side> {
+        self.get_signal_strength().map(|signal| signal.side)
+    }
+
+    // 带强度的信号：价格超出布林带的距离（以带宽为单位）越大，信号越强
+    pub fn get_signal_strength(&self) -> Option<Signal> {
         if self.prices.is_empty() {
             return None;
         }
-        
+
         let current_price = *self.prices.back().unwrap();
         let bands = self.calculate_bands()?;
-        
-        if current_price <= bands.0 {  // 价格触及下轨
-            Some(Side::Buy)
-        } else if current_price >= bands.2 {  // 价格触及上轨
-            Some(Side::Sell)
+
+        if self.mode == SignalMode::SqueezeBreakout {
+            // 只在挤压之后的突破K线上发出信号
+            if !self.was_squeezed {
+                return None;
+            }
+            let percent_b = self.percent_b();
+            return if current_price > bands.2 {
+                Some(Self::graded(
+                    Side::Buy,
+                    percent_b.map(|b| b - 1.0),
+                    "breakout above upper band after squeeze",
+                ))
+            } else if current_price < bands.0 {
+                Some(Self::graded(
+                    Side::Sell,
+                    percent_b.map(|b| -b),
+                    "breakout below lower band after squeeze",
+                ))
+            } else {
+                None
+            };
+        }
+
+        if self.confirmation {
+            return self.confirmed_signal();
+        }
+
+        let percent_b = self.percent_b();
+        if current_price <= bands.0 {
+            // 价格触及下轨
+            Some(Self::graded(
+                Side::Buy,
+                percent_b.map(|b| -b),
+                "price at lower band",
+            ))
+        } else if current_price >= bands.2 {
+            // 价格触及上轨
+            Some(Self::graded(
+                Side::Sell,
+                percent_b.map(|b| b - 1.0),
+                "price at upper band",
+            ))
+        } else {
+            None
+        }
+    }
+
+    // 超出布林带的距离作为强度，%B无定义（带宽为0）时强度为0
+    fn graded(side: Side, excess: Option<f64>, reason: &str) -> Signal {
+        Signal::new(side, excess.unwrap_or(0.0), reason)
+    }
+}
+
+impl BollingerBandsStrategy {
+    // %B从带外回到带内的那根K线上发出信号
+    fn confirmed_signal(&self) -> Option<Signal> {
+        if self.percent_b_values.len() < 2 {
+            return None;
+        }
+        let previous = self.percent_b_values[0]?;
+        let current = self.percent_b_values[1]?;
+
+        // 强度取决于上一根K线超出布林带的距离
+        if previous < 0.0 && current >= 0.0 {
+            Some(Self::graded(
+                Side::Buy,
+                Some(-previous),
+                "%B re-entered above 0",
+            ))
+        } else if previous > 1.0 && current <= 1.0 {
+            Some(Self::graded(
+                Side::Sell,
+                Some(previous - 1.0),
+                "%B re-entered below 1",
+            ))
         } else {
             None
         }
     }
 }
 
+impl SignalStrategy for BollingerBandsStrategy {
+    fn update(&mut self, data: &MarketData) {
+        if let Some(price) = data.price() {
+            self.add_price(price);
+        }
+    }
+
+    fn signal(&self) -> Option<Side> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        BollingerBandsStrategy::is_ready(self)
+    }
+
+    fn reset(&mut self) {
+        BollingerBandsStrategy::reset(self);
+    }
+
+    fn indicators(&self) -> BTreeMap<String, f64> {
+        let mut values = BTreeMap::new();
+        if let Some((lower, middle, upper)) = self.calculate_bands() {
+            values.insert("lower_band".to_string(), lower);
+            values.insert("middle_band".to_string(), middle);
+            values.insert("upper_band".to_string(), upper);
+        }
+        if let Some(percent_b) = self.percent_b() {
+            values.insert("percent_b".to_string(), percent_b);
+        }
+        values
+    }
+}
+
+impl Snapshot for BollingerBandsStrategy {}
+
 impl OrderSplitStrategy for BollingerBandsStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        let signal = self.get_signal();
-        if signal.is_none() {
+        // 预热期内不下单，原因可通过 no_order_reason 查询
+        if !self.is_ready() {
             return Vec::new();
         }
-        
-        match (signal.unwrap(), &parent_order.order_common.side) {
-            (Side::Buy, Side::Buy) | (Side::Sell, Side::Sell) => {
-                vec![ChildOrder {
-                    order_common: parent_order.order_common.clone(),
-                    strategy_id: parent_order.strategy_id.clone(),
-                    parent_id: parent_order.order_common.id.clone(),
-                    insert_at: Some(parent_order.order_common.timestamp),
-                }]
-            },
-            _ => Vec::new(),
-        }
+        checked_split(
+            parent_order,
+            signal_child_orders(parent_order, self.get_signal_strength(), self.sizing),
+        )
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
     }
 }
 
@@ -104,6 +358,8 @@ mod tests {
     use super::*;
     use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
     use crate::models::parent_orders::ParentOrder;
+    use crate::models::{Quantity, Timestamp};
+    use crate::strategies::common_strategies::NoOrderReason;
 
     #[test]
     fn test_bollinger_bands_initialization() {
@@ -116,15 +372,15 @@ mod tests {
     #[test]
     fn test_add_price() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 添加价格
         for i in 1..=10 {
             strategy.add_price(i as f64);
         }
-        
+
         // 检查价格队列长度不超过周期
         assert_eq!(strategy.prices.len(), 5);
-        
+
         // 检查价格队列内容（应该是最后5个价格）
         let expected_prices: Vec<f64> = (6..=10).map(|i| i as f64).collect();
         let actual_prices: Vec<f64> = strategy.prices.iter().cloned().collect();
@@ -134,37 +390,37 @@ mod tests {
     #[test]
     fn test_calculate_bands() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 添加相同的价格
         for _ in 0..5 {
             strategy.add_price(100.0);
         }
-        
+
         // 计算布林带
         let bands = strategy.calculate_bands();
         assert!(bands.is_some());
         let (lower, middle, upper) = bands.unwrap();
-        
+
         // 所有价格相同时，标准差为0，上下轨等于中轨
         assert_eq!(lower, 100.0);
         assert_eq!(middle, 100.0);
         assert_eq!(upper, 100.0);
-        
+
         // 添加不同的价格
         strategy.add_price(110.0);
         strategy.add_price(90.0);
         strategy.add_price(120.0);
         strategy.add_price(80.0);
         strategy.add_price(100.0);
-        
+
         // 重新计算布林带
         let bands = strategy.calculate_bands();
         assert!(bands.is_some());
         let (lower, middle, upper) = bands.unwrap();
-        
+
         // 验证中轨是平均值
         assert_eq!(middle, 100.0);
-        
+
         // 验证上下轨与中轨的距离
         assert!(upper > middle);
         assert!(lower < middle);
@@ -175,15 +431,15 @@ mod tests {
     #[test]
     fn test_buy_signal_generation() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 添加稳定价格
         for _ in 0..5 {
             strategy.add_price(100.0);
         }
-        
+
         // 添加低于下轨的价格
         strategy.add_price(80.0);
-        
+
         // 此时应该有买入信号
         let signal = strategy.get_signal();
         assert!(signal.is_some());
@@ -193,15 +449,15 @@ mod tests {
     #[test]
     fn test_sell_signal_generation() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 添加稳定价格
         for _ in 0..5 {
             strategy.add_price(100.0);
         }
-        
+
         // 添加高于上轨的价格
         strategy.add_price(120.0);
-        
+
         // 此时应该有卖出信号
         let signal = strategy.get_signal();
         assert!(signal.is_some());
@@ -211,22 +467,22 @@ mod tests {
     #[test]
     fn test_order_split_with_matching_signal() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 设置产生买入信号
         for _ in 0..5 {
             strategy.add_price(100.0);
         }
         strategy.add_price(80.0);
-        
+
         // 创建买入父订单
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Buy,
@@ -242,10 +498,10 @@ mod tests {
             ),
             strategy_id: "bollinger_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证生成了子订单
         assert_eq!(child_orders.len(), 1);
         assert_eq!(child_orders[0].parent_id, "test_id");
@@ -255,22 +511,22 @@ mod tests {
     #[test]
     fn test_order_split_with_non_matching_signal() {
         let mut strategy = BollingerBandsStrategy::new(5, 2.0);
-        
+
         // 设置产生买入信号
         for _ in 0..5 {
             strategy.add_price(100.0);
         }
         strategy.add_price(80.0);
-        
+
         // 创建卖出父订单（与信号不匹配）
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Sell,
@@ -286,11 +542,297 @@ mod tests {
             ),
             strategy_id: "bollinger_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证没有生成子订单
         assert_eq!(child_orders.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_band_width() {
+        let mut strategy = BollingerBandsStrategy::new(5, 2.0);
+        assert_eq!(strategy.band_width(), None);
+
+        for _ in 0..5 {
+            strategy.add_price(100.0);
+        }
+        assert_eq!(strategy.band_width(), Some(0.0));
+
+        // [100, 100, 100, 100, 120]：中轨104，标准差8
+        strategy.add_price(120.0);
+        let width = strategy.band_width().unwrap();
+        assert!((width - 32.0 / 104.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_squeeze_detection() {
+        let mut strategy =
+            BollingerBandsStrategy::new(5, 2.0).with_signal_mode(SignalMode::SqueezeBreakout, 5);
+
+        // 回看窗口未满时不判断挤压
+        for _ in 0..9 {
+            strategy.add_price(100.0);
+        }
+        assert!(!strategy.is_squeeze());
+
+        strategy.add_price(100.0);
+        assert!(strategy.is_squeeze());
+
+        // 带宽扩张后挤压结束
+        strategy.add_price(120.0);
+        assert!(!strategy.is_squeeze());
+    }
+
+    #[test]
+    fn test_squeeze_breakout_buy() {
+        let mut strategy =
+            BollingerBandsStrategy::new(10, 2.0).with_signal_mode(SignalMode::SqueezeBreakout, 5);
+        for _ in 0..15 {
+            strategy.add_price(100.0);
+        }
+        // 零带宽时价格与上下轨重合，不算突破
+        assert!(strategy.is_squeeze());
+        assert_eq!(strategy.get_signal(), None);
+
+        // 挤压后向上突破
+        strategy.add_price(120.0);
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        // 突破后的下一根K线不再处于挤压之后
+        strategy.add_price(125.0);
+        assert_eq!(strategy.get_signal(), None);
+    }
+
+    #[test]
+    fn test_squeeze_breakout_sell() {
+        let mut strategy =
+            BollingerBandsStrategy::new(10, 2.0).with_signal_mode(SignalMode::SqueezeBreakout, 5);
+        for _ in 0..15 {
+            strategy.add_price(100.0);
+        }
+
+        // 挤压后向下突破，均值回归模式下这里会买入
+        strategy.add_price(80.0);
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+    }
+
+    #[test]
+    fn test_breakout_requires_prior_squeeze() {
+        let mut strategy =
+            BollingerBandsStrategy::new(5, 2.0).with_signal_mode(SignalMode::SqueezeBreakout, 5);
+
+        // 波动持续扩大，没有挤压
+        for i in 0..12 {
+            let swing = if i % 2 == 0 { 1.0 } else { -1.0 };
+            strategy.add_price(100.0 + swing * (i + 1) as f64);
+        }
+        assert!(!strategy.is_squeeze());
+        strategy.add_price(200.0);
+        assert_eq!(strategy.get_signal(), None);
+    }
+
+    // 在99和101之间来回震荡的价格，带宽不为0
+    fn add_oscillating_prices(strategy: &mut BollingerBandsStrategy, count: usize) {
+        for i in 0..count {
+            strategy.add_price(if i % 2 == 0 { 99.0 } else { 101.0 });
+        }
+    }
+
+    #[test]
+    fn test_percent_b() {
+        let mut strategy = BollingerBandsStrategy::new(5, 2.0);
+        assert_eq!(strategy.percent_b(), None);
+
+        // 带宽为0时%B无定义
+        for _ in 0..5 {
+            strategy.add_price(100.0);
+        }
+        assert_eq!(strategy.percent_b(), None);
+
+        // [100, 100, 100, 100, 120]：下轨88，上轨120
+        strategy.add_price(120.0);
+        assert!((strategy.percent_b().unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_confirmation_buy_on_reentry() {
+        let mut plain = BollingerBandsStrategy::new(10, 2.0);
+        let mut confirmed = BollingerBandsStrategy::new(10, 2.0).with_confirmation(true);
+        add_oscillating_prices(&mut plain, 10);
+        add_oscillating_prices(&mut confirmed, 10);
+
+        // 首次跌破下轨：默认模式立即买入，确认模式跳过
+        plain.add_price(90.0);
+        confirmed.add_price(90.0);
+        assert!(confirmed.percent_b().unwrap() < 0.0);
+        assert_eq!(plain.get_signal(), Some(Side::Buy));
+        assert_eq!(confirmed.get_signal(), None);
+
+        // %B回到0以上的那根K线买入
+        confirmed.add_price(97.0);
+        assert!(confirmed.percent_b().unwrap() > 0.0);
+        assert_eq!(confirmed.get_signal(), Some(Side::Buy));
+
+        // 之后不再重复发出信号
+        confirmed.add_price(100.0);
+        assert_eq!(confirmed.get_signal(), None);
+    }
+
+    #[test]
+    fn test_confirmation_sell_on_reentry() {
+        let mut strategy = BollingerBandsStrategy::new(10, 2.0).with_confirmation(true);
+        add_oscillating_prices(&mut strategy, 10);
+
+        strategy.add_price(110.0);
+        assert!(strategy.percent_b().unwrap() > 1.0);
+        assert_eq!(strategy.get_signal(), None);
+
+        strategy.add_price(103.0);
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+    }
+
+    #[test]
+    fn test_confirmation_keeps_bounded_history() {
+        let mut strategy = BollingerBandsStrategy::new(10, 2.0).with_confirmation(true);
+        add_oscillating_prices(&mut strategy, 1_000);
+        assert_eq!(strategy.percent_b_values.len(), 2);
+    }
+
+    fn parent_order(side: Side, quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "bollinger_strategy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_signal_strength() {
+        // 恰好触及下轨：%B = 0，强度为 0
+        let mut strategy = BollingerBandsStrategy::new(5, 2.0);
+        for _ in 0..5 {
+            strategy.add_price(100.0);
+        }
+        strategy.add_price(80.0);
+        let signal = strategy.get_signal_strength().unwrap();
+        assert_eq!(signal.side, Side::Buy);
+        assert!(signal.strength < 1e-9);
+
+        // 跌破下轨越深，信号越强
+        let mut strategy = BollingerBandsStrategy::new(10, 2.0);
+        add_oscillating_prices(&mut strategy, 10);
+        strategy.add_price(90.0);
+        let signal = strategy.get_signal_strength().unwrap();
+        assert!((signal.strength + strategy.percent_b().unwrap()).abs() < 1e-9);
+        assert!(signal.strength > 0.2);
+    }
+
+    #[test]
+    fn test_split_scaled_by_strength() {
+        let mut strategy = BollingerBandsStrategy::new(10, 2.0)
+            .with_strength_sizing(StrengthSizing::new(0.5, 0.5));
+        add_oscillating_prices(&mut strategy, 10);
+        strategy.add_price(90.0);
+        assert_eq!(
+            strategy.split(&parent_order(Side::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(50)
+        );
+
+        let mut strategy = BollingerBandsStrategy::new(10, 2.0)
+            .with_strength_sizing(StrengthSizing::new(0.2, 0.5));
+        add_oscillating_prices(&mut strategy, 10);
+        strategy.add_price(90.0);
+        assert_eq!(
+            strategy.split(&parent_order(Side::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(100)
+        );
+    }
+
+    #[test]
+    fn test_warmup_and_reset() {
+        let mut strategy = BollingerBandsStrategy::new(5, 2.0);
+        assert_eq!(strategy.warmup_remaining(), 5);
+        for i in 0..5 {
+            assert!(!strategy.is_ready());
+            strategy.add_price(100.0);
+            assert_eq!(strategy.warmup_remaining(), 4 - i);
+        }
+        assert!(strategy.is_ready());
+
+        strategy.reset();
+        assert_eq!(strategy.warmup_remaining(), 5);
+        assert_eq!(strategy.calculate_bands(), None);
+
+        // 确认模式和挤压突破模式需要更多数据
+        assert_eq!(
+            BollingerBandsStrategy::new(5, 2.0)
+                .with_confirmation(true)
+                .warmup_remaining(),
+            6
+        );
+        let mut squeeze =
+            BollingerBandsStrategy::new(5, 2.0).with_signal_mode(SignalMode::SqueezeBreakout, 3);
+        assert_eq!(squeeze.warmup_remaining(), 9);
+        for _ in 0..8 {
+            squeeze.add_price(100.0);
+        }
+        assert_eq!(squeeze.warmup_remaining(), 1);
+    }
+
+    #[test]
+    fn test_split_during_warmup() {
+        // 确认模式下第一根完整窗口的K线还没有前一个%B
+        let mut strategy = BollingerBandsStrategy::new(5, 2.0).with_confirmation(true);
+        for _ in 0..4 {
+            strategy.add_price(100.0);
+        }
+        strategy.add_price(80.0);
+        assert!(!strategy.is_ready());
+        assert!(strategy.split(&parent_order(Side::Buy, 100)).is_empty());
+        assert_eq!(
+            strategy.no_order_reason(&parent_order(Side::Buy, 100)),
+            Some(NoOrderReason::WarmingUp)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut original = BollingerBandsStrategy::new(10, 2.0).with_confirmation(true);
+        add_oscillating_prices(&mut original, 10);
+        original.add_price(90.0);
+
+        // 恢复后的策略记得上一根K线的%B，下一根K线同样在回到带内时买入
+        let mut restored = BollingerBandsStrategy::restore(&original.snapshot()).unwrap();
+        original.add_price(97.0);
+        restored.add_price(97.0);
+        assert_eq!(original.get_signal(), Some(Side::Buy));
+        assert_eq!(
+            restored.get_signal_strength(),
+            original.get_signal_strength()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Composite voting strategy
+Runs several indicator strategies on the same market data and only trades when enough of
+them agree. Buy and Sell votes net out against each other, so two Buys and one Sell count
+as a single net Buy vote. In weighted mode each strategy votes with its weight and the net
+vote is compared against a fraction of the total weight instead of a head count.
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{OrderSplitStrategy, SignalDecision, SignalStrategy};
+
+/// How member votes are turned into a composite signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoteMode {
+    /// Net number of agreeing strategies required; weights are ignored
+    Quorum(usize),
+    /// Net weight required, as a fraction of the total weight
+    Weighted(f64),
+}
+
+/// Combines several signal strategies by vote
+pub struct CompositeStrategy {
+    members: Vec<(Box<dyn SignalStrategy>, f64)>,
+    mode: VoteMode,
+    /// Split strategy used once the composite signal matches the parent side
+    splitter: Box<dyn OrderSplitStrategy>,
+}
+
+impl CompositeStrategy {
+    pub fn new(mode: VoteMode, splitter: Box<dyn OrderSplitStrategy>) -> Self {
+        CompositeStrategy {
+            members: Vec::new(),
+            mode,
+            splitter,
+        }
+    }
+
+    /// Adds a member strategy with unit weight
+    pub fn with_strategy(self, strategy: Box<dyn SignalStrategy>) -> Self {
+        self.with_weighted_strategy(strategy, 1.0)
+    }
+
+    /// Adds a member strategy voting with the given weight
+    pub fn with_weighted_strategy(
+        mut self,
+        strategy: Box<dyn SignalStrategy>,
+        weight: f64,
+    ) -> Self {
+        self.members.push((strategy, weight.max(0.0)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Net vote: positive for Buy, negative for Sell
+    pub fn net_vote(&self) -> f64 {
+        self.members
+            .iter()
+            .map(|(strategy, weight)| {
+                let weight = match self.mode {
+                    VoteMode::Quorum(_) => 1.0,
+                    VoteMode::Weighted(_) => *weight,
+                };
+                match strategy.signal() {
+                    Some(Side::Buy) => weight,
+                    Some(Side::Sell) => -weight,
+                    None => 0.0,
+                }
+            })
+            .sum()
+    }
+}
+
+impl SignalStrategy for CompositeStrategy {
+    fn update(&mut self, data: &MarketData) {
+        for (strategy, _) in self.members.iter_mut() {
+            strategy.update(data);
+        }
+    }
+
+    fn signal(&self) -> Option<Side> {
+        let net_vote = self.net_vote();
+        let required = match self.mode {
+            VoteMode::Quorum(quorum) => quorum.max(1) as f64,
+            VoteMode::Weighted(fraction) => {
+                let total: f64 = self.members.iter().map(|(_, weight)| weight).sum();
+                if total <= 0.0 {
+                    return None;
+                }
+                fraction * total
+            }
+        };
+
+        if net_vote > 0.0 && net_vote >= required {
+            Some(Side::Buy)
+        } else if net_vote < 0.0 && -net_vote >= required {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+
+    /// Ready once every member is ready
+    fn is_ready(&self) -> bool {
+        !self.members.is_empty() && self.members.iter().all(|(strategy, _)| strategy.is_ready())
+    }
+
+    fn reset(&mut self) {
+        for (strategy, _) in self.members.iter_mut() {
+            strategy.reset();
+        }
+    }
+}
+
+impl OrderSplitStrategy for CompositeStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        match self.signal() {
+            Some(side) if side == parent_order.order_common.side => {
+                self.splitter.split(parent_order)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_data::Trade;
+    use crate::models::orders::{Order, TimeInForce};
+    use crate::models::Timestamp;
+    use crate::strategies::technical_indicator_based::{
+        BollingerBandsStrategy, MACDStrategy, RSIStrategy,
+    };
+    use std::time::SystemTime;
+
+    /// Member strategy with a fixed signal
+    struct FixedSignal(Option<Side>);
+
+    impl SignalStrategy for FixedSignal {
+        fn update(&mut self, _data: &MarketData) {}
+
+        fn signal(&self) -> Option<Side> {
+            self.0.clone()
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    /// Emits the whole parent order as a single child
+    struct WholeOrder;
+
+    impl OrderSplitStrategy for WholeOrder {
+        fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+            vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }]
+        }
+    }
+
+    fn composite(mode: VoteMode, votes: &[(Option<Side>, f64)]) -> CompositeStrategy {
+        votes.iter().fold(
+            CompositeStrategy::new(mode, Box::new(WholeOrder)),
+            |composite, (vote, weight)| {
+                composite.with_weighted_strategy(Box::new(FixedSignal(vote.clone())), *weight)
+            },
+        )
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        Order::builder()
+            .id("test_id")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1234567890))
+            .symbol("BTC/USD")
+            .side(side)
+            .currency("USD")
+            .exchange("Binance")
+            .timeinforce(TimeInForce::GTC)
+            .build_parent("composite")
+            .expect("valid parent order")
+    }
+
+    #[test]
+    fn test_quorum_met() {
+        let strategy = composite(
+            VoteMode::Quorum(2),
+            &[(Some(Side::Buy), 1.0), (Some(Side::Buy), 1.0), (None, 1.0)],
+        );
+        assert_eq!(strategy.signal(), Some(Side::Buy));
+        assert_eq!(strategy.split(&parent_order(Side::Buy)).len(), 1);
+        assert!(strategy.split(&parent_order(Side::Sell)).is_empty());
+    }
+
+    #[test]
+    fn test_quorum_not_met() {
+        let strategy = composite(
+            VoteMode::Quorum(2),
+            &[(Some(Side::Sell), 1.0), (None, 1.0), (None, 1.0)],
+        );
+        assert_eq!(strategy.signal(), None);
+        assert!(strategy.split(&parent_order(Side::Sell)).is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_votes_net_out() {
+        // Two Buys and one Sell leave a single net Buy vote
+        let strategy = composite(
+            VoteMode::Quorum(2),
+            &[
+                (Some(Side::Buy), 1.0),
+                (Some(Side::Buy), 1.0),
+                (Some(Side::Sell), 1.0),
+            ],
+        );
+        assert_eq!(strategy.net_vote(), 1.0);
+        assert_eq!(strategy.signal(), None);
+
+        let strategy = composite(
+            VoteMode::Quorum(1),
+            &[(Some(Side::Buy), 1.0), (Some(Side::Sell), 1.0)],
+        );
+        assert_eq!(strategy.signal(), None);
+    }
+
+    #[test]
+    fn test_weighted_votes() {
+        // A heavy Sell outweighs two light Buys: net -0.2 of a total weight of 1.0
+        let votes = [
+            (Some(Side::Buy), 0.2),
+            (Some(Side::Buy), 0.2),
+            (Some(Side::Sell), 0.6),
+        ];
+        let strategy = composite(VoteMode::Weighted(0.3), &votes);
+        assert!((strategy.net_vote() + 0.2).abs() < 1e-9);
+        assert_eq!(strategy.signal(), None);
+
+        let strategy = composite(VoteMode::Weighted(0.15), &votes);
+        assert_eq!(strategy.signal(), Some(Side::Sell));
+        assert_eq!(strategy.split(&parent_order(Side::Sell)).len(), 1);
+
+        // The same votes by head count favour Buy
+        let strategy = composite(VoteMode::Quorum(1), &votes);
+        assert_eq!(strategy.signal(), Some(Side::Buy));
+    }
+
+    #[test]
+    fn test_updates_all_members() {
+        let mut strategy = CompositeStrategy::new(VoteMode::Quorum(2), Box::new(WholeOrder))
+            .with_strategy(Box::new(RSIStrategy::new(14, 70.0, 30.0)))
+            .with_strategy(Box::new(BollingerBandsStrategy::new(20, 2.0)))
+            .with_strategy(Box::new(MACDStrategy::new(12, 26, 9)));
+        assert_eq!(strategy.len(), 3);
+        assert!(!strategy.is_ready());
+
+        for i in 0..40 {
+            strategy.update(&MarketData::Trade(Trade {
+                timestamp: SystemTime::UNIX_EPOCH,
+                price: 100.0 + i as f64,
+                size: 1.0,
+                side: Side::Buy,
+            }));
+        }
+        assert!(strategy.is_ready());
+
+        strategy.reset();
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.signal(), None);
+    }
+}
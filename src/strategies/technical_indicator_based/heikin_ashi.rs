@@ -27,22 +27,19 @@ THE SOFTWARE.
 ******************************************************************************/
 
 use crate::models::child_orders::ChildOrder;
-use crate::models::parent_orders::ParentOrder;
+pub use crate::models::market_data::Candle;
+use crate::models::market_data::MarketData;
 use crate::models::orders::Side as OrderSide;
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{
+    checked_split, signal_child_orders, OrderSplitStrategy, Signal, SignalDecision, SignalStrategy,
+    Snapshot, StrengthSizing,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-/// Represents a candlestick with OHLC values
-#[derive(Debug, Clone, Copy)]
-pub struct Candle {
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
-    pub volume: f64,
-}
-
 /// Heikin-Ashi strategy implementation
+#[derive(Serialize, Deserialize)]
 pub struct HeikinAshiStrategy {
     /// Window size for the strategy
     window_size: usize,
@@ -50,6 +47,10 @@ pub struct HeikinAshiStrategy {
     candles: VecDeque<Candle>,
     /// Historical Heikin-Ashi candles
     ha_candles: VecDeque<Candle>,
+    /// Maximum shadow length still treated as "no shadow"
+    shadow_tolerance: f64,
+    /// Optional scaling of child order size by signal strength
+    sizing: Option<StrengthSizing>,
 }
 
 impl HeikinAshiStrategy {
@@ -59,28 +60,43 @@ impl HeikinAshiStrategy {
             window_size,
             candles: VecDeque::with_capacity(window_size),
             ha_candles: VecDeque::with_capacity(window_size),
+            shadow_tolerance: 1e-9,
+            sizing: None,
         }
     }
 
+    /// Scales child order size by signal strength
+    pub fn with_strength_sizing(mut self, sizing: StrengthSizing) -> Self {
+        self.sizing = Some(sizing);
+        self
+    }
+
+    /// Sets the maximum shadow length still treated as "no shadow"
+    pub fn with_shadow_tolerance(mut self, shadow_tolerance: f64) -> Self {
+        self.shadow_tolerance = shadow_tolerance.abs();
+        self
+    }
+
     /// Adds a new candle to the strategy and calculates the corresponding Heikin-Ashi candle
     pub fn add_candle(&mut self, candle: Candle) {
         // Add the new candle to the history
         self.candles.push_back(candle);
-        
+
         // Calculate the Heikin-Ashi candle
         let ha_candle = if self.ha_candles.is_empty() {
             // First Heikin-Ashi candle is the same as the regular candle
             candle
         } else {
             let prev_ha = self.ha_candles.back().unwrap();
-            
+
             // Heikin-Ashi formulas
             let ha_open = (prev_ha.open + prev_ha.close) / 2.0;
             let ha_close = (candle.open + candle.high + candle.low + candle.close) / 4.0;
             let ha_high = candle.high.max(ha_open).max(ha_close);
             let ha_low = candle.low.min(ha_open).min(ha_close);
-            
+
             Candle {
+                timestamp: candle.timestamp,
                 open: ha_open,
                 high: ha_high,
                 low: ha_low,
@@ -88,204 +104,434 @@ impl HeikinAshiStrategy {
                 volume: candle.volume,
             }
         };
-        
+
         // Add the Heikin-Ashi candle to the history
         self.ha_candles.push_back(ha_candle);
-        
+
         // Maintain the window size
         if self.candles.len() > self.window_size {
             self.candles.pop_front();
         }
-        
+
         if self.ha_candles.len() > self.window_size {
             self.ha_candles.pop_front();
         }
     }
 
+    /// Whether the three Heikin-Ashi candles needed for a signal are available
+    pub fn is_ready(&self) -> bool {
+        self.ha_candles.len() >= 3
+    }
+
+    /// Number of candles still needed before signals can be produced
+    pub fn warmup_remaining(&self) -> usize {
+        3usize.saturating_sub(self.ha_candles.len())
+    }
+
+    /// Clears the candle history
+    pub fn reset(&mut self) {
+        self.candles.clear();
+        self.ha_candles.clear();
+    }
+
     /// Determines the trading signal based on Heikin-Ashi patterns
     pub fn get_signal(&self) -> Option<OrderSide> {
+        self.get_signal_strength().map(|signal| signal.side)
+    }
+
+    /// Trading signal graded by the average body/range ratio of the three signal candles
+    pub fn get_signal_strength(&self) -> Option<Signal> {
         if self.ha_candles.len() < 3 {
             return None; // Need at least 3 candles to generate a signal
         }
-        
+
         let candles: Vec<&Candle> = self.ha_candles.iter().collect();
         let len = candles.len();
-        
+
         let current = candles[len - 1];
         let prev = candles[len - 2];
         let prev_prev = candles[len - 3];
-        
+
+        let tolerance = self.shadow_tolerance;
+        let no_lower_shadow = |c: &Candle| c.open.min(c.close) - c.low <= tolerance;
+        let no_upper_shadow = |c: &Candle| c.high - c.open.max(c.close) <= tolerance;
+
         // Bullish signal: Three consecutive green candles with no lower shadows
-        let bullish_signal = 
-            current.close > current.open && 
-            prev.close > prev.open && 
-            prev_prev.close > prev_prev.open;
-        
+        let bullish_signal = [prev_prev, prev, current]
+            .iter()
+            .all(|c| c.close > c.open && no_lower_shadow(c));
+
         // Bearish signal: Three consecutive red candles with no upper shadows
-        let bearish_signal = 
-            current.close < current.open && 
-            prev.close < prev.open && 
-            prev_prev.close < prev_prev.open;
-        
+        let bearish_signal = [prev_prev, prev, current]
+            .iter()
+            .all(|c| c.close < c.open && no_upper_shadow(c));
+
+        let strength = [prev_prev, prev, current]
+            .iter()
+            .map(|c| body_ratio(c))
+            .sum::<f64>()
+            / 3.0;
         if bullish_signal {
-            Some(OrderSide::Buy)
+            Some(Signal::new(
+                OrderSide::Buy,
+                strength,
+                "three green Heikin-Ashi candles without lower shadows",
+            ))
         } else if bearish_signal {
-            Some(OrderSide::Sell)
+            Some(Signal::new(
+                OrderSide::Sell,
+                strength,
+                "three red Heikin-Ashi candles without upper shadows",
+            ))
         } else {
             None
         }
     }
+
+    /// Average body/range ratio of the Heikin-Ashi candles in the window
+    ///
+    /// Values close to 1 indicate a strong trend; candles with no range count as 0.
+    pub fn trend_strength(&self) -> Option<f64> {
+        if self.ha_candles.is_empty() {
+            return None;
+        }
+
+        let total: f64 = self.ha_candles.iter().map(body_ratio).sum();
+        Some(total / self.ha_candles.len() as f64)
+    }
 }
 
+/// Body/range ratio of a candle; 0 for a candle with no range
+fn body_ratio(candle: &Candle) -> f64 {
+    let range = candle.high - candle.low;
+    if range > 0.0 {
+        (candle.close - candle.open).abs() / range
+    } else {
+        0.0
+    }
+}
+
+impl SignalStrategy for HeikinAshiStrategy {
+    /// Only candles carry the OHLC data Heikin-Ashi needs
+    fn update(&mut self, data: &MarketData) {
+        if let MarketData::Candle(candle) = data {
+            self.add_candle(*candle);
+        }
+    }
+
+    fn signal(&self) -> Option<OrderSide> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        HeikinAshiStrategy::is_ready(self)
+    }
+
+    fn reset(&mut self) {
+        HeikinAshiStrategy::reset(self);
+    }
+}
+
+impl Snapshot for HeikinAshiStrategy {}
+
 impl OrderSplitStrategy for HeikinAshiStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        // Get the current signal
-        let signal = self.get_signal();
-        
-        // If there's no signal, return empty
-        if signal.is_none() {
+        // No orders during warmup; the reason is available through `no_order_reason`
+        if !self.is_ready() {
             return Vec::new();
         }
-        
-        // 使用模式匹配而不是 != 运算符
-        match (signal.unwrap(), &parent_order.order_common.side) {
-            (OrderSide::Buy, OrderSide::Buy) | (OrderSide::Sell, OrderSide::Sell) => {
-                // Signal matches parent order side, create a child order
-                vec![ChildOrder {
-                    order_common: parent_order.order_common.clone(),
-                    strategy_id: parent_order.strategy_id.clone(),
-                    parent_id: parent_order.order_common.id.clone(),
-                    insert_at: Some(parent_order.order_common.timestamp),
-                }]
-            },
-            _ => {
-                // Signal doesn't match parent order side
-                Vec::new()
-            }
-        }
+
+        // A child order is only created when the signal matches the parent order side
+        checked_split(
+            parent_order,
+            signal_child_orders(parent_order, self.get_signal_strength(), self.sizing),
+        )
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
 
     #[test]
     fn test_heikin_ashi_calculation() {
         let mut strategy = HeikinAshiStrategy::new(5);
-        
+
         // Add some test candles
         strategy.add_candle(Candle {
+            timestamp: UNIX_EPOCH,
             open: 100.0,
             high: 110.0,
             low: 95.0,
             close: 105.0,
             volume: 1000.0,
         });
-        
+
         // First HA candle should be the same as the regular candle
         let first_ha = strategy.ha_candles.back().unwrap();
         assert_eq!(first_ha.open, 100.0);
         assert_eq!(first_ha.high, 110.0);
         assert_eq!(first_ha.low, 95.0);
         assert_eq!(first_ha.close, 105.0);
-        
+
         // Add another candle
         strategy.add_candle(Candle {
+            timestamp: UNIX_EPOCH,
             open: 105.0,
             high: 115.0,
             low: 100.0,
             close: 110.0,
             volume: 1200.0,
         });
-        
+
         // Second HA candle should be calculated using the formula
         let second_ha = strategy.ha_candles.back().unwrap();
         let expected_open = (100.0 + 105.0) / 2.0;
         let expected_close = (105.0 + 115.0 + 100.0 + 110.0) / 4.0;
-        
+
         assert_eq!(second_ha.open, expected_open);
         assert_eq!(second_ha.close, expected_close);
     }
 
+    fn candle(open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open,
+            high,
+            low,
+            close,
+            volume: 1000.0,
+        }
+    }
+
     #[test]
     fn test_signal_generation() {
         let mut strategy = HeikinAshiStrategy::new(5);
-        
+
         // Not enough candles for a signal
         assert!(strategy.get_signal().is_none());
-        
-        // 添加第一根蜡烛线
-        strategy.add_candle(Candle {
-            open: 100.0,
-            high: 110.0,
-            low: 90.0,
-            close: 110.0,
-            volume: 1000.0,
-        });
-        
-        // 添加第二根蜡烛线
-        strategy.add_candle(Candle {
-            open: 110.0,
-            high: 120.0,
-            low: 100.0,
-            close: 120.0,
-            volume: 1000.0,
-        });
-        
-        // 添加第三根蜡烛线
-        strategy.add_candle(Candle {
-            open: 120.0,
-            high: 130.0,
-            low: 110.0,
-            close: 130.0,
-            volume: 1000.0,
-        });
-        
+
+        // 三根没有下影线的阳线
+        strategy.add_candle(candle(100.0, 110.0, 100.0, 110.0));
+        strategy.add_candle(candle(110.0, 120.0, 110.0, 120.0));
+        strategy.add_candle(candle(120.0, 130.0, 120.0, 130.0));
+
         // 现在应该有买入信号
         let signal = strategy.get_signal();
-        assert!(signal.is_some());
         match signal {
-            Some(OrderSide::Buy) => {}, // Expected
+            Some(OrderSide::Buy) => {} // Expected
             _ => panic!("Expected Buy signal, got {:?}", signal),
         }
-        
+
         // 重置策略
         let mut strategy = HeikinAshiStrategy::new(5);
-        
-        // 添加第一根蜡烛线
-        strategy.add_candle(Candle {
-            open: 100.0,
-            high: 110.0,
-            low: 90.0,
-            close: 90.0,
-            volume: 1000.0,
-        });
-        
-        // 添加第二根蜡烛线
-        strategy.add_candle(Candle {
-            open: 90.0,
-            high: 100.0,
-            low: 80.0,
-            close: 80.0,
-            volume: 1000.0,
-        });
-        
-        // 添加第三根蜡烛线
-        strategy.add_candle(Candle {
-            open: 80.0,
-            high: 90.0,
-            low: 70.0,
-            close: 70.0,
-            volume: 1000.0,
-        });
-        
+
+        // 三根没有上影线的阴线
+        strategy.add_candle(candle(100.0, 100.0, 90.0, 90.0));
+        strategy.add_candle(candle(90.0, 90.0, 80.0, 80.0));
+        strategy.add_candle(candle(80.0, 80.0, 70.0, 70.0));
+
         // 现在应该有卖出信号
         let signal = strategy.get_signal();
-        assert!(signal.is_some());
         match signal {
-            Some(OrderSide::Sell) => {}, // Expected
+            Some(OrderSide::Sell) => {} // Expected
             _ => panic!("Expected Sell signal, got {:?}", signal),
         }
     }
+
+    #[test]
+    fn test_lower_shadows_suppress_buy_signal() {
+        let mut strategy = HeikinAshiStrategy::new(5);
+
+        // 三根阳线，但都带有较长的下影线
+        strategy.add_candle(candle(100.0, 110.0, 90.0, 110.0));
+        strategy.add_candle(candle(110.0, 120.0, 100.0, 120.0));
+        strategy.add_candle(candle(120.0, 130.0, 110.0, 130.0));
+
+        let first = strategy.ha_candles.front().unwrap();
+        assert!(first.low < first.open);
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_upper_shadows_suppress_sell_signal() {
+        let mut strategy = HeikinAshiStrategy::new(5);
+
+        // 三根阴线，但都带有较长的上影线
+        strategy.add_candle(candle(100.0, 110.0, 90.0, 90.0));
+        strategy.add_candle(candle(90.0, 100.0, 80.0, 80.0));
+        strategy.add_candle(candle(80.0, 90.0, 70.0, 70.0));
+
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_single_wick_suppresses_signal() {
+        let mut strategy = HeikinAshiStrategy::new(5);
+
+        // 只有中间一根K线的下影线超出开盘价
+        strategy.add_candle(candle(100.0, 110.0, 100.0, 110.0));
+        strategy.add_candle(candle(110.0, 120.0, 101.0, 120.0));
+        strategy.add_candle(candle(120.0, 130.0, 120.0, 130.0));
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_shadow_tolerance() {
+        // 下影线长度为 4（HA 开盘价 105，最低价 101）
+        let candles = [
+            candle(100.0, 110.0, 100.0, 110.0),
+            candle(110.0, 120.0, 101.0, 120.0),
+            candle(120.0, 130.0, 120.0, 130.0),
+        ];
+
+        let mut strict = HeikinAshiStrategy::new(5);
+        let mut tolerant = HeikinAshiStrategy::new(5).with_shadow_tolerance(5.0);
+        for c in candles {
+            strict.add_candle(c);
+            tolerant.add_candle(c);
+        }
+
+        assert!(strict.get_signal().is_none());
+        assert!(matches!(tolerant.get_signal(), Some(OrderSide::Buy)));
+    }
+
+    #[test]
+    fn test_trend_strength() {
+        let mut strategy = HeikinAshiStrategy::new(5);
+        assert!(strategy.trend_strength().is_none());
+
+        // 实体占满整个区间
+        strategy.add_candle(candle(100.0, 110.0, 100.0, 110.0));
+        assert_eq!(strategy.trend_strength(), Some(1.0));
+
+        // 第二根 HA K线：开盘 105，收盘 115，最高 120，最低 105
+        strategy.add_candle(candle(110.0, 120.0, 110.0, 120.0));
+        let expected = (1.0 + 10.0 / 15.0) / 2.0;
+        assert!((strategy.trend_strength().unwrap() - expected).abs() < 1e-9);
+
+        // 十字星没有实体，趋势强度较低
+        let mut choppy = HeikinAshiStrategy::new(5);
+        choppy.add_candle(candle(100.0, 110.0, 90.0, 100.0));
+        assert_eq!(choppy.trend_strength(), Some(0.0));
+    }
+
+    fn parent_order(side: OrderSide, quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "heikin_ashi_strategy".to_string(),
+        }
+    }
+
+    fn rising_strategy(sizing: StrengthSizing) -> HeikinAshiStrategy {
+        let mut strategy = HeikinAshiStrategy::new(5).with_strength_sizing(sizing);
+        strategy.add_candle(candle(100.0, 110.0, 100.0, 110.0));
+        strategy.add_candle(candle(110.0, 120.0, 110.0, 120.0));
+        strategy.add_candle(candle(120.0, 130.0, 120.0, 130.0));
+        strategy
+    }
+
+    #[test]
+    fn test_signal_strength() {
+        let strategy = rising_strategy(StrengthSizing::new(1.0, 1.0));
+
+        // Body/range ratios of the HA candles: 1, 10/15 and 15/20
+        let signal = strategy.get_signal_strength().unwrap();
+        assert!(matches!(signal.side, OrderSide::Buy));
+        let expected = (1.0 + 10.0 / 15.0 + 0.75) / 3.0;
+        assert!((signal.strength - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_split_scaled_by_strength() {
+        let weak = rising_strategy(StrengthSizing::new(0.9, 0.5));
+        assert_eq!(
+            weak.split(&parent_order(OrderSide::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(50)
+        );
+
+        let strong = rising_strategy(StrengthSizing::new(0.8, 0.5));
+        assert_eq!(
+            strong.split(&parent_order(OrderSide::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(100)
+        );
+        assert!(strong.split(&parent_order(OrderSide::Sell, 100)).is_empty());
+    }
+
+    #[test]
+    fn test_warmup_and_reset() {
+        let mut strategy = HeikinAshiStrategy::new(5);
+        assert_eq!(strategy.warmup_remaining(), 3);
+
+        let candles = [
+            candle(100.0, 110.0, 100.0, 110.0),
+            candle(110.0, 120.0, 110.0, 120.0),
+            candle(120.0, 130.0, 120.0, 130.0),
+        ];
+        for (i, c) in candles.into_iter().enumerate() {
+            // Not ready yet: no child orders even for a matching parent
+            assert!(strategy
+                .split(&parent_order(OrderSide::Buy, 100))
+                .is_empty());
+            strategy.add_candle(c);
+            assert_eq!(strategy.warmup_remaining(), 2 - i);
+        }
+        assert!(strategy.is_ready());
+        assert_eq!(strategy.split(&parent_order(OrderSide::Buy, 100)).len(), 1);
+
+        strategy.reset();
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 3);
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut original = HeikinAshiStrategy::new(5).with_shadow_tolerance(0.5);
+        original.add_candle(candle(100.0, 110.0, 100.0, 110.0));
+        original.add_candle(candle(110.0, 120.0, 110.0, 120.0));
+
+        let mut restored = HeikinAshiStrategy::restore(&original.snapshot()).unwrap();
+        original.add_candle(candle(120.0, 130.0, 120.0, 130.0));
+        restored.add_candle(candle(120.0, 130.0, 120.0, 130.0));
+        assert!(matches!(restored.get_signal(), Some(OrderSide::Buy)));
+        assert_eq!(
+            restored.get_signal_strength(),
+            original.get_signal_strength()
+        );
+    }
 }
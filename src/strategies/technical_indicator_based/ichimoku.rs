@@ -0,0 +1,322 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Ichimoku Cloud (Ichimoku Kinko Hyo)
+Tenkan-sen and Kijun-sen are the midpoints of the highest high and lowest low over the last
+9 and 26 periods. Senkou Span A (their average) and Senkou Span B (the 52-period midpoint)
+form the cloud, plotted 26 periods ahead; Chikou Span is the close plotted 26 periods back.
+A close above the cloud together with a bullish Tenkan/Kijun cross is a buy signal, and the
+mirror condition below the cloud is a sell signal.
+
+Reference for Further Reading:
+"Trading with Ichimoku Clouds" by Manesh Patel (2010).
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use std::collections::VecDeque;
+
+/// Ichimoku Cloud strategy implementation
+pub struct IchimokuStrategy {
+    tenkan_period: usize,
+    kijun_period: usize,
+    senkou_b_period: usize,
+    /// Number of periods the Senkou spans are plotted ahead
+    displacement: usize,
+    /// Historical candles, enough to evaluate the displaced cloud on the previous bar
+    candles: VecDeque<Candle>,
+}
+
+impl Default for IchimokuStrategy {
+    /// Standard 9/26/52 settings with a 26-period displacement
+    fn default() -> Self {
+        Self::new(9, 26, 52, 26)
+    }
+}
+
+impl IchimokuStrategy {
+    pub fn new(
+        tenkan_period: usize,
+        kijun_period: usize,
+        senkou_b_period: usize,
+        displacement: usize,
+    ) -> Self {
+        let tenkan_period = tenkan_period.max(1);
+        let kijun_period = kijun_period.max(1);
+        let senkou_b_period = senkou_b_period.max(1);
+        let capacity = tenkan_period.max(kijun_period).max(senkou_b_period) + displacement + 1;
+        IchimokuStrategy {
+            tenkan_period,
+            kijun_period,
+            senkou_b_period,
+            displacement,
+            candles: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a new candle, keeping only the history the indicators need
+    pub fn add_candle(&mut self, candle: Candle) {
+        self.candles.push_back(candle);
+        if self.candles.len() > self.history_len() {
+            self.candles.pop_front();
+        }
+    }
+
+    fn history_len(&self) -> usize {
+        self.tenkan_period
+            .max(self.kijun_period)
+            .max(self.senkou_b_period)
+            + self.displacement
+            + 1
+    }
+
+    /// Midpoint of the highest high and lowest low over `period` candles ending `offset`
+    /// candles before the latest one
+    fn midpoint(&self, period: usize, offset: usize) -> Option<f64> {
+        let end = self.candles.len().checked_sub(offset)?;
+        let start = end.checked_sub(period)?;
+        let window = self.candles.range(start..end);
+        let (high, low) = window.fold((f64::MIN, f64::MAX), |(high, low), candle| {
+            (high.max(candle.high), low.min(candle.low))
+        });
+        Some((high + low) / 2.0)
+    }
+
+    fn tenkan_at(&self, offset: usize) -> Option<f64> {
+        self.midpoint(self.tenkan_period, offset)
+    }
+
+    fn kijun_at(&self, offset: usize) -> Option<f64> {
+        self.midpoint(self.kijun_period, offset)
+    }
+
+    /// Cloud (Senkou A, Senkou B) plotted at the bar `offset` candles before the latest one,
+    /// computed from data `displacement` bars earlier
+    fn cloud_at(&self, offset: usize) -> Option<(f64, f64)> {
+        let source = offset + self.displacement;
+        let senkou_a = (self.tenkan_at(source)? + self.kijun_at(source)?) / 2.0;
+        let senkou_b = self.midpoint(self.senkou_b_period, source)?;
+        Some((senkou_a, senkou_b))
+    }
+
+    /// Conversion line
+    pub fn tenkan(&self) -> Option<f64> {
+        self.tenkan_at(0)
+    }
+
+    /// Base line
+    pub fn kijun(&self) -> Option<f64> {
+        self.kijun_at(0)
+    }
+
+    /// Leading span A at the current bar
+    pub fn senkou_a(&self) -> Option<f64> {
+        self.cloud_at(0).map(|(senkou_a, _)| senkou_a)
+    }
+
+    /// Leading span B at the current bar
+    pub fn senkou_b(&self) -> Option<f64> {
+        self.cloud_at(0).map(|(_, senkou_b)| senkou_b)
+    }
+
+    /// Lagging span: the latest close, plotted `displacement` periods back
+    pub fn chikou(&self) -> Option<f64> {
+        self.candles.back().map(|candle| candle.close)
+    }
+
+    /// Determines the trading signal from the cloud and the Tenkan/Kijun cross
+    pub fn get_signal(&self) -> Option<Side> {
+        if self.candles.len() < self.history_len() {
+            return None;
+        }
+
+        let close = self.candles.back()?.close;
+        let (senkou_a, senkou_b) = self.cloud_at(0)?;
+        let cloud_top = senkou_a.max(senkou_b);
+        let cloud_bottom = senkou_a.min(senkou_b);
+
+        let tenkan = self.tenkan_at(0)?;
+        let kijun = self.kijun_at(0)?;
+        let prev_tenkan = self.tenkan_at(1)?;
+        let prev_kijun = self.kijun_at(1)?;
+
+        let bullish_cross = prev_tenkan <= prev_kijun && tenkan > kijun;
+        let bearish_cross = prev_tenkan >= prev_kijun && tenkan < kijun;
+
+        if close > cloud_top && bullish_cross {
+            Some(Side::Buy)
+        } else if close < cloud_bottom && bearish_cross {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+impl OrderSplitStrategy for IchimokuStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
+
+    fn candle(high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: close,
+            high,
+            low,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    /// Flat market around 100 with one outlier candle 9 bars before the end
+    fn flat_with_outlier(outlier: Candle) -> IchimokuStrategy {
+        let mut strategy = IchimokuStrategy::default();
+        for i in 0..90 {
+            if i == 81 {
+                strategy.add_candle(outlier);
+            } else {
+                strategy.add_candle(candle(101.0, 99.0, 100.0));
+            }
+        }
+        strategy
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "ichimoku".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_not_enough_history() {
+        let mut strategy = IchimokuStrategy::default();
+        // Senkou B needs 52 + 26 candles
+        for _ in 0..77 {
+            strategy.add_candle(candle(101.0, 99.0, 100.0));
+        }
+        assert!(strategy.tenkan().is_some());
+        assert!(strategy.senkou_b().is_none());
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_cloud_uses_displaced_data() {
+        let mut strategy = IchimokuStrategy::default();
+        for _ in 0..80 {
+            strategy.add_candle(candle(101.0, 99.0, 100.0));
+        }
+        // A rally lifts the lines but not the cloud, which lags 26 bars
+        for _ in 0..10 {
+            strategy.add_candle(candle(121.0, 119.0, 120.0));
+        }
+        assert_eq!(strategy.tenkan(), Some(120.0));
+        assert_eq!(strategy.kijun(), Some(110.0));
+        assert_eq!(strategy.senkou_a(), Some(100.0));
+        assert_eq!(strategy.senkou_b(), Some(100.0));
+        assert_eq!(strategy.chikou(), Some(120.0));
+    }
+
+    #[test]
+    fn test_buy_above_cloud() {
+        // The low outlier leaves the Tenkan window on the breakout bar but stays in the Kijun
+        let mut strategy = flat_with_outlier(candle(101.0, 95.0, 100.0));
+        assert_eq!(strategy.tenkan(), strategy.kijun());
+        assert!(strategy.get_signal().is_none());
+
+        strategy.add_candle(candle(103.0, 101.0, 102.0));
+        assert_eq!(strategy.tenkan(), Some(101.0));
+        assert_eq!(strategy.kijun(), Some(99.0));
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        assert_eq!(strategy.split(&parent_order(Side::Buy)).len(), 1);
+        assert!(strategy.split(&parent_order(Side::Sell)).is_empty());
+    }
+
+    #[test]
+    fn test_sell_below_cloud() {
+        let mut strategy = flat_with_outlier(candle(105.0, 99.0, 100.0));
+        strategy.add_candle(candle(99.0, 97.0, 98.0));
+        assert_eq!(strategy.tenkan(), Some(99.0));
+        assert_eq!(strategy.kijun(), Some(101.0));
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+
+        assert_eq!(strategy.split(&parent_order(Side::Sell)).len(), 1);
+        assert!(strategy.split(&parent_order(Side::Buy)).is_empty());
+    }
+
+    #[test]
+    fn test_cross_inside_cloud_is_ignored() {
+        let mut strategy = flat_with_outlier(candle(101.0, 95.0, 100.0));
+        // Bullish cross, but the close stays inside the cloud
+        strategy.add_candle(candle(103.0, 99.5, 100.0));
+        assert!(strategy.tenkan() > strategy.kijun());
+        assert!(strategy.get_signal().is_none());
+    }
+}
@@ -0,0 +1,230 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Keltner Channel
+The midline is an EMA of the close and the bands sit a multiple of the Average True Range
+above and below it. In mean-reversion mode a close below the lower band is a buy signal and
+a close above the upper band a sell signal; breakout mode inverts them, following the move
+out of the channel instead.
+
+Reference for Further Reading:
+"How to Make Money in Commodities" by Chester W. Keltner (1960).
+*/
+
+use super::atr::AverageTrueRange;
+use super::macd::Ema;
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+
+/// Keltner Channel strategy implementation
+pub struct KeltnerChannelStrategy {
+    /// Band width as a multiple of the ATR
+    multiplier: f64,
+    /// Follow breakouts instead of fading them
+    breakout: bool,
+    ema: Ema,
+    atr: AverageTrueRange,
+    middle: Option<f64>,
+    last_close: Option<f64>,
+}
+
+impl KeltnerChannelStrategy {
+    /// Creates a mean-reversion Keltner Channel strategy
+    pub fn new(ema_period: usize, atr_period: usize, multiplier: f64) -> Self {
+        KeltnerChannelStrategy {
+            multiplier,
+            breakout: false,
+            ema: Ema::new(ema_period),
+            atr: AverageTrueRange::new(atr_period),
+            middle: None,
+            last_close: None,
+        }
+    }
+
+    /// Switches between mean-reversion and breakout signals
+    pub fn with_breakout_mode(mut self, breakout: bool) -> Self {
+        self.breakout = breakout;
+        self
+    }
+
+    /// Adds a new candle and updates the channel
+    pub fn add_candle(&mut self, candle: Candle) {
+        self.middle = self.ema.update(candle.close);
+        self.atr.update(&candle);
+        self.last_close = Some(candle.close);
+    }
+
+    /// EMA midline
+    pub fn middle(&self) -> Option<f64> {
+        self.middle
+    }
+
+    pub fn upper(&self) -> Option<f64> {
+        Some(self.middle? + self.multiplier * self.atr.value()?)
+    }
+
+    pub fn lower(&self) -> Option<f64> {
+        Some(self.middle? - self.multiplier * self.atr.value()?)
+    }
+
+    /// Determines the trading signal from the latest close and the bands
+    pub fn get_signal(&self) -> Option<Side> {
+        let close = self.last_close?;
+        let signal = if close < self.lower()? {
+            Side::Buy
+        } else if close > self.upper()? {
+            Side::Sell
+        } else {
+            return None;
+        };
+
+        if !self.breakout {
+            return Some(signal);
+        }
+        match signal {
+            Side::Buy => Some(Side::Sell),
+            Side::Sell => Some(Side::Buy),
+        }
+    }
+}
+
+impl OrderSplitStrategy for KeltnerChannelStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
+
+    fn candle(close: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "keltner".to_string(),
+        }
+    }
+
+    /// Runs both modes over the same closes and returns their final signals
+    fn signals(closes: &[f64]) -> (Option<Side>, Option<Side>) {
+        let mut mean_reversion = KeltnerChannelStrategy::new(20, 10, 2.0);
+        let mut breakout = KeltnerChannelStrategy::new(20, 10, 2.0).with_breakout_mode(true);
+        for close in closes {
+            mean_reversion.add_candle(candle(*close));
+            breakout.add_candle(candle(*close));
+        }
+        (mean_reversion.get_signal(), breakout.get_signal())
+    }
+
+    #[test]
+    fn test_channel_values() {
+        let mut strategy = KeltnerChannelStrategy::new(20, 10, 2.0);
+        for _ in 0..20 {
+            strategy.add_candle(candle(100.0));
+        }
+        assert_eq!(strategy.middle(), Some(100.0));
+        assert_eq!(strategy.upper(), Some(104.0));
+        assert_eq!(strategy.lower(), Some(96.0));
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_close_above_upper_band() {
+        let mut closes = vec![100.0; 20];
+        closes.push(110.0);
+        assert_eq!(signals(&closes), (Some(Side::Sell), Some(Side::Buy)));
+    }
+
+    #[test]
+    fn test_close_below_lower_band() {
+        let mut closes = vec![100.0; 20];
+        closes.push(90.0);
+        assert_eq!(signals(&closes), (Some(Side::Buy), Some(Side::Sell)));
+    }
+
+    #[test]
+    fn test_split_in_both_modes() {
+        let mut mean_reversion = KeltnerChannelStrategy::new(20, 10, 2.0);
+        let mut breakout = KeltnerChannelStrategy::new(20, 10, 2.0).with_breakout_mode(true);
+        for close in [100.0; 20].iter().chain([110.0].iter()) {
+            mean_reversion.add_candle(candle(*close));
+            breakout.add_candle(candle(*close));
+        }
+
+        assert_eq!(mean_reversion.split(&parent_order(Side::Sell)).len(), 1);
+        assert!(mean_reversion.split(&parent_order(Side::Buy)).is_empty());
+        assert_eq!(breakout.split(&parent_order(Side::Buy)).len(), 1);
+        assert!(breakout.split(&parent_order(Side::Sell)).is_empty());
+    }
+}
@@ -12,93 +12,322 @@
 介绍了移动平均线及其交叉策略的应用。(第5章详细讨论了移动平均线策略)
 */
 
-use std::collections::VecDeque;
-use crate::models::orders::Side;
 use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
 use crate::models::parent_orders::ParentOrder;
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::strategies::common_strategies::{
+    checked_split, OrderSplitStrategy, SignalDecision, SignalStrategy, Snapshot,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+// 移动平均类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AverageType {
+    SMA,
+    EMA,
+    WMA,
+    Hull,
+}
+
+// 增量计算的指数移动平均
+#[derive(Serialize, Deserialize)]
+pub(super) struct Ema {
+    period: usize,
+    alpha: f64,
+    seed: Vec<f64>,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub(super) fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Ema {
+            period,
+            alpha: 2.0 / (period as f64 + 1.0),
+            seed: Vec::with_capacity(period),
+            value: None,
+        }
+    }
+
+    pub(super) fn update(&mut self, value: f64) -> Option<f64> {
+        match self.value {
+            Some(ema) => self.value = Some(self.alpha * value + (1.0 - self.alpha) * ema),
+            None => {
+                self.seed.push(value);
+                if self.seed.len() == self.period {
+                    self.value = Some(self.seed.iter().sum::<f64>() / self.period as f64);
+                }
+            }
+        }
+        self.value
+    }
+}
+
+// 增量计算的简单移动平均
+#[derive(Serialize, Deserialize)]
+struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Sma {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or(0.0);
+        }
+        (self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+}
+
+// 增量计算的线性加权移动平均（最新价格权重为 period，最旧为 1）
+#[derive(Serialize, Deserialize)]
+struct Wma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+    weighted_sum: f64,
+}
+
+impl Wma {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Wma {
+            period,
+            window: VecDeque::with_capacity(period),
+            sum: 0.0,
+            weighted_sum: 0.0,
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        if self.window.len() < self.period {
+            self.window.push_back(value);
+            self.sum += value;
+            self.weighted_sum += self.window.len() as f64 * value;
+        } else {
+            // 窗口整体前移一位：每个旧价格的权重减一，新价格权重为 period
+            self.weighted_sum += self.period as f64 * value - self.sum;
+            self.sum += value - self.window.pop_front().unwrap_or(0.0);
+            self.window.push_back(value);
+        }
+        let weights = (self.period * (self.period + 1)) as f64 / 2.0;
+        (self.window.len() == self.period).then(|| self.weighted_sum / weights)
+    }
+}
+
+// 按类型增量维护的移动平均
+#[derive(Serialize, Deserialize)]
+enum MovingAverage {
+    Sma(Sma),
+    Ema(Ema),
+    Wma(Wma),
+    // HMA(n) = WMA(2 * WMA(n/2) - WMA(n), sqrt(n))
+    Hull { half: Wma, full: Wma, smooth: Wma },
+}
+
+impl MovingAverage {
+    fn new(average: AverageType, period: usize) -> Self {
+        match average {
+            AverageType::SMA => MovingAverage::Sma(Sma::new(period)),
+            AverageType::EMA => MovingAverage::Ema(Ema::new(period)),
+            AverageType::WMA => MovingAverage::Wma(Wma::new(period)),
+            AverageType::Hull => MovingAverage::Hull {
+                half: Wma::new(period / 2),
+                full: Wma::new(period),
+                smooth: Wma::new((period as f64).sqrt().round() as usize),
+            },
+        }
+    }
 
+    // 产生第一个值所需的价格数量
+    fn required(average: AverageType, period: usize) -> usize {
+        let period = period.max(1);
+        match average {
+            AverageType::Hull => period + ((period as f64).sqrt().round() as usize).max(1) - 1,
+            _ => period,
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        match self {
+            MovingAverage::Sma(sma) => sma.update(value),
+            MovingAverage::Ema(ema) => ema.update(value),
+            MovingAverage::Wma(wma) => wma.update(value),
+            MovingAverage::Hull { half, full, smooth } => {
+                let half = half.update(value);
+                let full = full.update(value)?;
+                smooth.update(2.0 * half? - full)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct MAStrategy {
     short_period: usize,
     long_period: usize,
-    prices: VecDeque<f64>,
+    average: AverageType,
+    short_ma: MovingAverage,
+    long_ma: MovingAverage,
+    // 当前和前一时刻的 (短期MA, 长期MA)
+    current: Option<(f64, f64)>,
+    previous: Option<(f64, f64)>,
+    // 已处理的价格数量
+    samples: usize,
 }
 
 impl MAStrategy {
     pub fn new(short_period: usize, long_period: usize) -> Self {
+        Self::with_average(short_period, long_period, AverageType::SMA)
+    }
+
+    pub fn with_average(short_period: usize, long_period: usize, average: AverageType) -> Self {
         MAStrategy {
             short_period,
             long_period,
-            prices: VecDeque::with_capacity(long_period + 1),
+            average,
+            short_ma: MovingAverage::new(average, short_period),
+            long_ma: MovingAverage::new(average, long_period),
+            current: None,
+            previous: None,
+            samples: 0,
         }
     }
-    
+
+    // (短期周期, 长期周期)
+    pub fn periods(&self) -> (usize, usize) {
+        (self.short_period, self.long_period)
+    }
+
+    pub fn average(&self) -> AverageType {
+        self.average
+    }
+
     pub fn add_price(&mut self, price: f64) {
-        self.prices.push_back(price);
-        if self.prices.len() > self.long_period {
-            self.prices.pop_front();
+        self.samples += 1;
+        let short_ma = self.short_ma.update(price);
+        let long_ma = self.long_ma.update(price);
+        self.previous = self.current;
+        self.current = short_ma.zip(long_ma);
+    }
+
+    // 当前和前一时刻的两条均线都已就绪
+    pub fn is_ready(&self) -> bool {
+        self.current.is_some() && self.previous.is_some()
+    }
+
+    // 距离就绪还需要的价格数量
+    pub fn warmup_remaining(&self) -> usize {
+        if self.is_ready() {
+            return 0;
         }
+        let required = MovingAverage::required(self.average, self.short_period)
+            .max(MovingAverage::required(self.average, self.long_period))
+            + 1;
+        required.saturating_sub(self.samples).max(1)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::with_average(self.short_period, self.long_period, self.average);
     }
-    
+
+    // 当前的 (短期MA, 长期MA)
+    pub fn averages(&self) -> Option<(f64, f64)> {
+        self.current
+    }
+
     pub fn get_signal(&self) -> Option<Side> {
-        // 确保有足够的数据
-        if self.prices.len() < self.long_period {
-            return None;
-        }
-        
-        // 将价格转换为向量以便于处理
-        let prices_vec: Vec<f64> = self.prices.iter().cloned().collect();
-        
-        // 计算当前的短期MA和长期MA
-        let short_ma = prices_vec.iter().rev().take(self.short_period).sum::<f64>() / self.short_period as f64;
-        let long_ma = prices_vec.iter().sum::<f64>() / prices_vec.len() as f64;
-        
-        // 如果只有一个价格点，无法计算交叉
-        if prices_vec.len() <= 1 {
-            return None;
-        }
-        
-        // 创建前一个时间点的价格列表（移除最新价格）
-        let prev_prices: Vec<f64> = prices_vec.iter().take(prices_vec.len() - 1).cloned().collect();
-        
-        // 计算前一个时间点的短期MA和长期MA
-        let prev_short_ma = prev_prices.iter().rev().take(self.short_period).sum::<f64>() / self.short_period as f64;
-        let prev_long_ma = prev_prices.iter().sum::<f64>() / prev_prices.len() as f64;
-        
+        // 确保当前和前一时刻的两条均线都已就绪
+        let (short_ma, long_ma) = self.current?;
+        let (prev_short_ma, prev_long_ma) = self.previous?;
+
         // 打印调试信息
-        println!("Current prices: {:?}", prices_vec);
-        println!("Previous prices: {:?}", prev_prices);
         println!("Current short MA: {}, long MA: {}", short_ma, long_ma);
-        println!("Previous short MA: {}, long MA: {}", prev_short_ma, prev_long_ma);
-        
+        println!(
+            "Previous short MA: {}, long MA: {}",
+            prev_short_ma, prev_long_ma
+        );
+
         // 检测黄金交叉（短期MA从下方穿过长期MA）
         // 修改条件：前一时刻短期MA <= 前一时刻长期MA，当前短期MA > 当前长期MA
         if prev_short_ma <= prev_long_ma && short_ma > long_ma {
             println!("Golden Cross detected - BUY signal");
             return Some(Side::Buy);
         }
-        
+
         // 检测死亡交叉（短期MA从上方穿过长期MA）
         // 修改条件：前一时刻短期MA >= 前一时刻长期MA，当前短期MA < 当前长期MA
         if prev_short_ma >= prev_long_ma && short_ma < long_ma {
             println!("Death Cross detected - SELL signal");
             return Some(Side::Sell);
         }
-        
+
         // 没有交叉
         println!("No cross detected - NO signal");
         None
     }
 }
 
+impl SignalStrategy for MAStrategy {
+    fn update(&mut self, data: &MarketData) {
+        if let Some(price) = data.price() {
+            self.add_price(price);
+        }
+    }
+
+    fn signal(&self) -> Option<Side> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        MAStrategy::is_ready(self)
+    }
+
+    fn reset(&mut self) {
+        MAStrategy::reset(self);
+    }
+
+    fn indicators(&self) -> BTreeMap<String, f64> {
+        self.averages()
+            .map(|(short, long)| {
+                BTreeMap::from([
+                    ("short_ma".to_string(), short),
+                    ("long_ma".to_string(), long),
+                ])
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Snapshot for MAStrategy {}
+
 impl OrderSplitStrategy for MAStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        // 预热期内不下单，原因可通过 no_order_reason 查询
+        if !self.is_ready() {
+            return Vec::new();
+        }
+
         let signal = self.get_signal();
         if signal.is_none() {
             return Vec::new();
         }
-        
-        match (signal.unwrap(), &parent_order.order_common.side) {
+
+        let child_orders = match (signal.unwrap(), &parent_order.order_common.side) {
             (Side::Buy, Side::Buy) | (Side::Sell, Side::Sell) => {
                 vec![ChildOrder {
                     order_common: parent_order.order_common.clone(),
@@ -106,9 +335,148 @@ impl OrderSplitStrategy for MAStrategy {
                     parent_id: parent_order.order_common.id.clone(),
                     insert_at: Some(parent_order.order_common.timestamp),
                 }]
-            },
+            }
             _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
+    }
+}
+
+/*
+MACD 策略 (Moving Average Convergence Divergence)
+MACD 线为快速EMA与慢速EMA之差，信号线为MACD线的EMA，柱状图为两者之差。
+当MACD线从下方穿过信号线时产生买入信号，从上方穿过时产生卖出信号。
+每条EMA在积累满一个周期后以简单平均作为初始值，之后增量更新。
+*/
+
+pub struct MACDStrategy {
+    fast_ema: Ema,
+    slow_ema: Ema,
+    signal_ema: Ema,
+    macd: Option<f64>,
+    signal: Option<f64>,
+    prev_histogram: Option<f64>,
+}
+
+impl MACDStrategy {
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+        MACDStrategy {
+            fast_ema: Ema::new(fast_period),
+            slow_ema: Ema::new(slow_period),
+            signal_ema: Ema::new(signal_period),
+            macd: None,
+            signal: None,
+            prev_histogram: None,
+        }
+    }
+
+    pub fn add_price(&mut self, price: f64) {
+        self.prev_histogram = self.histogram();
+
+        let fast = self.fast_ema.update(price);
+        let slow = self.slow_ema.update(price);
+        if let (Some(fast), Some(slow)) = (fast, slow) {
+            let macd = fast - slow;
+            self.macd = Some(macd);
+            self.signal = self.signal_ema.update(macd);
+        }
+    }
+
+    // MACD线（快速EMA - 慢速EMA）
+    pub fn macd(&self) -> Option<f64> {
+        self.macd
+    }
+
+    // 信号线（MACD线的EMA）
+    pub fn signal(&self) -> Option<f64> {
+        self.signal
+    }
+
+    // 柱状图（MACD线 - 信号线）
+    pub fn histogram(&self) -> Option<f64> {
+        Some(self.macd? - self.signal?)
+    }
+
+    pub fn get_signal(&self) -> Option<Side> {
+        let histogram = self.histogram()?;
+        let prev_histogram = self.prev_histogram?;
+
+        // MACD线上穿信号线
+        if prev_histogram <= 0.0 && histogram > 0.0 {
+            return Some(Side::Buy);
         }
+        // MACD线下穿信号线
+        if prev_histogram >= 0.0 && histogram < 0.0 {
+            return Some(Side::Sell);
+        }
+        None
+    }
+}
+
+impl SignalStrategy for MACDStrategy {
+    fn update(&mut self, data: &MarketData) {
+        if let Some(price) = data.price() {
+            self.add_price(price);
+        }
+    }
+
+    // 与返回信号线数值的同名方法区分
+    fn signal(&self) -> Option<Side> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.histogram().is_some() && self.prev_histogram.is_some()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(
+            self.fast_ema.period,
+            self.slow_ema.period,
+            self.signal_ema.period,
+        );
+    }
+
+    fn indicators(&self) -> BTreeMap<String, f64> {
+        [
+            ("macd", self.macd()),
+            ("signal", MACDStrategy::signal(self)),
+            ("histogram", self.histogram()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value?)))
+        .collect()
+    }
+}
+
+impl OrderSplitStrategy for MACDStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
     }
 }
 
@@ -117,54 +485,52 @@ mod tests {
     use super::*;
     use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
     use crate::models::parent_orders::ParentOrder;
+    use crate::models::{Quantity, Timestamp};
 
     #[test]
     fn test_ma_strategy_initialization() {
         let strategy = MAStrategy::new(10, 30);
         assert_eq!(strategy.short_period, 10);
         assert_eq!(strategy.long_period, 30);
-        assert_eq!(strategy.prices.len(), 0);
+        assert_eq!(strategy.average(), AverageType::SMA);
+        assert!(strategy.averages().is_none());
     }
 
     #[test]
     fn test_add_price() {
         let mut strategy = MAStrategy::new(5, 10);
-        
+
         // 添加价格
         for i in 1..=15 {
             strategy.add_price(i as f64);
         }
-        
-        // 检查价格队列长度不超过长周期
-        assert_eq!(strategy.prices.len(), 10);
-        
-        // 检查价格队列内容（应该是最后10个价格）
-        let expected_prices: Vec<f64> = (6..=15).map(|i| i as f64).collect();
-        let actual_prices: Vec<f64> = strategy.prices.iter().cloned().collect();
-        assert_eq!(actual_prices, expected_prices);
+
+        // 均线只覆盖最近的价格：短期为 11..=15，长期为 6..=15
+        assert_eq!(strategy.averages(), Some((13.0, 10.5)));
+        assert_eq!(strategy.previous, Some((12.0, 9.5)));
     }
 
     #[test]
     fn test_buy_signal_generation() {
         let mut strategy = MAStrategy::new(2, 3);
-        
+
         // 添加初始价格，使短期MA < 长期MA
         strategy.add_price(5.0);
         strategy.add_price(5.0);
         strategy.add_price(5.0);
-        
-        // 此时短期MA = 长期MA = 5.0，没有信号
+
+        // 数据不足 long_period + 1 个，没有信号
         assert!(strategy.get_signal().is_none());
-        
+
         // 添加一个高价格，使短期MA > 长期MA
-        strategy.add_price(15.0);  // 现在价格是 [5.0, 5.0, 15.0]
+        strategy.add_price(15.0); // 现在价格是 [5.0, 5.0, 5.0, 15.0]
                                   // 短期MA = (5+15)/2 = 10.0
                                   // 长期MA = (5+5+15)/3 = 8.33
                                   // 前一时刻短期MA = (5+5)/2 = 5.0
                                   // 前一时刻长期MA = (5+5+5)/3 = 5.0
                                   // 短期MA从5.0上升到10.0，长期MA从5.0上升到8.33
                                   // 短期MA从等于长期MA变为大于长期MA，产生买入信号
-        
+
         // 此时短期MA > 长期MA，产生买入信号
         let signal = strategy.get_signal();
         assert!(signal.is_some(), "Expected a signal, got None");
@@ -174,66 +540,70 @@ mod tests {
     #[test]
     fn test_sell_signal_generation() {
         let mut strategy = MAStrategy::new(2, 3);
-        
+
         // 添加初始价格，使短期MA > 长期MA
         strategy.add_price(5.0);
         strategy.add_price(5.0);
-        strategy.add_price(15.0);  // 现在价格是 [5.0, 5.0, 15.0]
-                                // 短期MA = (5+15)/2 = 10.0
-                                // 长期MA = (5+5+15)/3 = 8.33
-        
-        // 此时短期MA > 长期MA，没有卖出信号
+        strategy.add_price(15.0); // 现在价格是 [5.0, 5.0, 15.0]
+                                  // 数据不足 long_period + 1 个
+
+        // 此时没有卖出信号
         assert!(strategy.get_signal() != Some(Side::Sell));
-        
+
         // 添加一个低价格，使短期MA < 长期MA
         // 但是我们需要确保前一时刻的短期MA > 前一时刻的长期MA
         // 所以先添加一个中间价格，确保短期MA仍然 > 长期MA
-        strategy.add_price(10.0);  // 现在价格是 [5.0, 15.0, 10.0]
-                                // 短期MA = (15+10)/2 = 12.5
-                                // 长期MA = (5+15+10)/3 = 10.0
-                                // 前一时刻短期MA = (5+15)/2 = 10.0
-                                // 前一时刻长期MA = (5+5+15)/3 = 8.33
-        
+        strategy.add_price(10.0); // 现在价格是 [5.0, 5.0, 15.0, 10.0]
+                                  // 短期MA = (15+10)/2 = 12.5
+                                  // 长期MA = (5+15+10)/3 = 10.0
+                                  // 前一时刻短期MA = (5+15)/2 = 10.0
+                                  // 前一时刻长期MA = (5+5+15)/3 = 8.33
+
         // 现在添加一个低价格，使短期MA < 长期MA
-        strategy.add_price(1.0);   // 现在价格是 [15.0, 10.0, 1.0]
-                                // 短期MA = (10+1)/2 = 5.5
-                                // 长期MA = (15+10+1)/3 = 8.67
-                                // 前一时刻短期MA = (15+10)/2 = 12.5
-                                // 前一时刻长期MA = (5+15+10)/3 = 10.0
-                                // 短期MA从12.5下降到5.5，长期MA从10.0下降到8.67
-                                // 短期MA从大于长期MA变为小于长期MA，产生卖出信号
-        
+        strategy.add_price(1.0); // 现在价格是 [5.0, 15.0, 10.0, 1.0]
+                                 // 短期MA = (10+1)/2 = 5.5
+                                 // 长期MA = (15+10+1)/3 = 8.67
+                                 // 前一时刻短期MA = (15+10)/2 = 12.5
+                                 // 前一时刻长期MA = (5+15+10)/3 = 10.0
+                                 // 短期MA从12.5下降到5.5，长期MA从10.0下降到8.67
+                                 // 短期MA从大于长期MA变为小于长期MA，产生卖出信号
+
         // 此时短期MA < 长期MA，产生卖出信号
         let signal = strategy.get_signal();
         assert!(signal.is_some(), "Expected a signal, got None");
         assert_eq!(signal.unwrap(), Side::Sell, "Expected Sell signal");
     }
-        
+
     #[test]
     fn test_order_split_with_matching_signal() {
         let mut strategy = MAStrategy::new(2, 3);
-        
+
         // 添加初始价格，使短期MA < 长期MA
         strategy.add_price(5.0);
         strategy.add_price(5.0);
         strategy.add_price(5.0);
-        
+
         // 添加一个高价格，使短期MA > 长期MA
-        strategy.add_price(15.0);  // 产生买入信号
-        
+        strategy.add_price(15.0); // 产生买入信号
+
         // 确认有买入信号
         let signal = strategy.get_signal();
-        assert_eq!(signal, Some(Side::Buy), "Expected Buy signal, got {:?}", signal);
-        
+        assert_eq!(
+            signal,
+            Some(Side::Buy),
+            "Expected Buy signal, got {:?}",
+            signal
+        );
+
         // 创建买入父订单
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Buy,
@@ -249,36 +619,41 @@ mod tests {
             ),
             strategy_id: "ma_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证生成了子订单
-        assert_eq!(child_orders.len(), 1, "Expected 1 child order, got {}", child_orders.len());
+        assert_eq!(
+            child_orders.len(),
+            1,
+            "Expected 1 child order, got {}",
+            child_orders.len()
+        );
         assert_eq!(child_orders[0].parent_id, "test_id");
         assert_eq!(child_orders[0].strategy_id, "ma_strategy");
     }
     #[test]
     fn test_order_split_with_non_matching_signal() {
         let mut strategy = MAStrategy::new(2, 3);
-        
+
         // 添加初始价格，使短期MA < 长期MA
         strategy.add_price(10.0);
         strategy.add_price(10.0);
         strategy.add_price(10.0);
-        
+
         // 添加一个高价格，使短期MA > 长期MA
-        strategy.add_price(20.0);  // 产生买入信号
-        
+        strategy.add_price(20.0); // 产生买入信号
+
         // 创建卖出父订单（与信号不匹配）
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Sell,
@@ -294,11 +669,215 @@ mod tests {
             ),
             strategy_id: "ma_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证没有生成子订单
         assert_eq!(child_orders.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_long_ma_uses_long_period_window() {
+        let mut strategy = MAStrategy::new(2, 3);
+
+        // 稳定上涨的行情中短期MA始终在长期MA之上，不应出现交叉
+        // （旧实现的前一时刻长期MA只用了 long_period - 1 个价格，会错误地产生买入信号）
+        for i in 1..=10 {
+            strategy.add_price(i as f64);
+            assert!(
+                strategy.get_signal().is_none(),
+                "Unexpected signal at price {}",
+                i
+            );
+        }
+
+        // 当前长期MA = (8+9+10)/3 = 9.0，前一时刻 = (7+8+9)/3 = 8.0
+        assert_eq!(strategy.averages(), Some((9.5, 9.0)));
+        assert_eq!(strategy.previous, Some((8.5, 8.0)));
+    }
+
+    #[test]
+    fn test_weighted_averages() {
+        let mut wma = MovingAverage::new(AverageType::WMA, 3);
+        assert!(wma.update(1.0).is_none());
+        assert!(wma.update(2.0).is_none());
+        // (1*1 + 2*2 + 3*3) / 6
+        assert!((wma.update(3.0).unwrap() - 14.0 / 6.0).abs() < 1e-9);
+        // (1*2 + 2*3 + 3*10) / 6
+        assert!((wma.update(10.0).unwrap() - 38.0 / 6.0).abs() < 1e-9);
+
+        // 线性序列上 Hull 均线没有滞后
+        let mut hull = MovingAverage::new(AverageType::Hull, 4);
+        let values: Vec<Option<f64>> = (1..=8).map(|i| hull.update(i as f64)).collect();
+        assert!(values[..4].iter().all(|value| value.is_none()));
+        for (i, value) in values.iter().enumerate().skip(4) {
+            assert!((value.unwrap() - (i + 1) as f64).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ema_crossover_earlier_than_sma() {
+        // 上升趋势中出现短暂回调后恢复上涨
+        let prices: Vec<f64> = (0..20)
+            .map(|i| 100.0 + 3.0 * i as f64)
+            .chain((0..5).map(|i| 157.0 - 3.0 * i as f64))
+            .chain((1..20).map(|i| 142.0 + 3.0 * i as f64))
+            .collect();
+
+        let first_buy = |average: AverageType| {
+            let mut strategy = MAStrategy::with_average(3, 10, average);
+            prices.iter().position(|price| {
+                strategy.add_price(*price);
+                strategy.get_signal() == Some(Side::Buy)
+            })
+        };
+
+        let sma_buy = first_buy(AverageType::SMA).unwrap();
+        let ema_buy = first_buy(AverageType::EMA).unwrap();
+        assert_eq!(sma_buy, 29);
+        assert_eq!(ema_buy, 27);
+        assert!(first_buy(AverageType::Hull).is_some());
+    }
+
+    // 价格序列及手工计算的 EMA(3)、EMA(5) 和信号线 EMA(3)
+    const MACD_PRICES: [f64; 15] = [
+        10.0, 11.0, 12.0, 11.0, 13.0, 14.0, 13.0, 15.0, 16.0, 14.0, 12.0, 11.0, 10.0, 9.0, 10.0,
+    ];
+
+    #[test]
+    fn test_macd_values() {
+        let mut strategy = MACDStrategy::new(3, 5, 3);
+        let tolerance = 1e-9;
+
+        for price in &MACD_PRICES[..4] {
+            strategy.add_price(*price);
+        }
+        // 慢速EMA尚未就绪
+        assert!(strategy.macd().is_none());
+
+        // 第5个价格: EMA3 = 12.0, EMA5 = (10+11+12+11+13)/5 = 11.4
+        strategy.add_price(MACD_PRICES[4]);
+        assert!((strategy.macd().unwrap() - 0.6).abs() < tolerance);
+        assert!(strategy.signal().is_none());
+
+        // 第7个价格: EMA3 = 13.0, EMA5 = 12.511111, 信号线 = (0.6+0.733333+0.488889)/3
+        strategy.add_price(MACD_PRICES[5]);
+        strategy.add_price(MACD_PRICES[6]);
+        assert!((strategy.macd().unwrap() - 0.488888888888887).abs() < tolerance);
+        assert!((strategy.signal().unwrap() - 0.607407407407406).abs() < tolerance);
+        assert!((strategy.histogram().unwrap() + 0.118518518518519).abs() < tolerance);
+
+        for price in &MACD_PRICES[7..] {
+            strategy.add_price(*price);
+        }
+        assert!((strategy.macd().unwrap() + 0.539297183271522).abs() < tolerance);
+        assert!((strategy.signal().unwrap() + 0.576134875802302).abs() < tolerance);
+    }
+
+    #[test]
+    fn test_macd_crossovers() {
+        let mut strategy = MACDStrategy::new(3, 5, 3);
+        let mut signals = Vec::new();
+        for price in MACD_PRICES {
+            strategy.add_price(price);
+            signals.push(strategy.get_signal());
+        }
+
+        // 柱状图在第8个价格转正，第10个价格转负，第15个价格再次转正
+        assert_eq!(signals[7], Some(Side::Buy));
+        assert_eq!(signals[9], Some(Side::Sell));
+        assert_eq!(signals[14], Some(Side::Buy));
+        let count = signals.iter().filter(|signal| signal.is_some()).count();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_macd_order_split() {
+        let mut strategy = MACDStrategy::new(3, 5, 3);
+        for price in &MACD_PRICES[..8] {
+            strategy.add_price(*price);
+        }
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        let mut parent_order = ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                Side::Buy,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "macd_strategy".to_string(),
+        };
+
+        let child_orders = strategy.split(&parent_order);
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].parent_id, "test_id");
+
+        parent_order.order_common.side = Side::Sell;
+        assert!(strategy.split(&parent_order).is_empty());
+    }
+
+    #[test]
+    fn test_ma_warmup_and_reset() {
+        // 长期均线需要 5 个价格，判断交叉还需要前一时刻的值
+        let mut strategy = MAStrategy::new(3, 5);
+        assert_eq!(strategy.warmup_remaining(), 6);
+        for i in 0..6 {
+            assert!(!strategy.is_ready());
+            strategy.add_price(100.0 + i as f64);
+            assert_eq!(strategy.warmup_remaining(), 5 - i);
+        }
+        assert!(strategy.is_ready());
+
+        strategy.reset();
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 6);
+        assert_eq!(strategy.averages(), None);
+    }
+
+    #[test]
+    fn test_hull_warmup() {
+        // HMA(9) 需要 9 + 3 - 1 个价格，再加上前一时刻
+        let mut strategy = MAStrategy::with_average(4, 9, AverageType::Hull);
+        assert_eq!(strategy.warmup_remaining(), 12);
+        for i in 0..12 {
+            strategy.add_price(100.0 + i as f64);
+        }
+        assert!(strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 0);
+    }
+
+    #[test]
+    fn test_ma_snapshot_round_trip() {
+        let mut original = MAStrategy::with_average(3, 9, AverageType::Hull);
+        for i in 0..15 {
+            original.add_price(100.0 + i as f64);
+        }
+
+        let mut restored = MAStrategy::restore(&original.snapshot()).unwrap();
+        assert_eq!(restored.averages(), original.averages());
+
+        // 价格回落时两者在同一时刻产生死亡交叉
+        for i in 0..10 {
+            let price = 110.0 - 3.0 * i as f64;
+            original.add_price(price);
+            restored.add_price(price);
+            assert_eq!(restored.get_signal(), original.get_signal());
+        }
+    }
+}
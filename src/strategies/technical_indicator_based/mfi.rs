@@ -0,0 +1,290 @@
+/*
+资金流量指数 (MFI - Money Flow Index)
+MFI是成交量加权的RSI。典型价格 (最高价 + 最低价 + 收盘价) / 3 乘以成交量得到资金流，
+典型价格上升时计为正资金流，下降时计为负资金流。资金比率为周期内正负资金流之比，
+MFI = 100 - 100 / (1 + 资金比率)。通常MFI低于20被认为是超卖状态，高于80被认为是
+超买状态。该策略在超卖时买入，在超买时卖出。
+
+例如：价格在放量中持续下跌使MFI跌破20时买入，放量上涨使MFI突破80时卖出。
+
+参考文献：
+"Technical Analysis of Stock Trends" by Robert D. Edwards and John Magee：
+介绍了价格与成交量结合的分析方法。
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use std::collections::VecDeque;
+
+pub struct MFIStrategy {
+    period: usize,
+    prev_typical_price: Option<f64>,
+    positive_flows: VecDeque<f64>,
+    negative_flows: VecDeque<f64>,
+    overbought_threshold: f64,
+    oversold_threshold: f64,
+}
+
+impl MFIStrategy {
+    pub fn new(period: usize, overbought_threshold: f64, oversold_threshold: f64) -> Self {
+        MFIStrategy {
+            period,
+            prev_typical_price: None,
+            positive_flows: VecDeque::with_capacity(period),
+            negative_flows: VecDeque::with_capacity(period),
+            overbought_threshold,
+            oversold_threshold,
+        }
+    }
+
+    pub fn add_candle(&mut self, candle: Candle) {
+        let typical_price = (candle.high + candle.low + candle.close) / 3.0;
+
+        if let Some(prev_typical_price) = self.prev_typical_price {
+            let money_flow = typical_price * candle.volume;
+
+            // 典型价格不变时不计入任何一方
+            if typical_price > prev_typical_price {
+                self.positive_flows.push_back(money_flow);
+                self.negative_flows.push_back(0.0);
+            } else if typical_price < prev_typical_price {
+                self.positive_flows.push_back(0.0);
+                self.negative_flows.push_back(money_flow);
+            } else {
+                self.positive_flows.push_back(0.0);
+                self.negative_flows.push_back(0.0);
+            }
+
+            if self.positive_flows.len() > self.period {
+                self.positive_flows.pop_front();
+                self.negative_flows.pop_front();
+            }
+        }
+
+        self.prev_typical_price = Some(typical_price);
+    }
+
+    pub fn calculate_mfi(&self) -> Option<f64> {
+        if self.positive_flows.len() < self.period {
+            return None;
+        }
+
+        let positive_flow: f64 = self.positive_flows.iter().sum();
+        let negative_flow: f64 = self.negative_flows.iter().sum();
+
+        // 全部为正资金流时 MFI = 100
+        if negative_flow == 0.0 {
+            return Some(100.0);
+        }
+
+        let money_ratio = positive_flow / negative_flow;
+        let mfi = 100.0 - (100.0 / (1.0 + money_ratio));
+
+        Some(mfi)
+    }
+
+    pub fn get_signal(&self) -> Option<Side> {
+        let mfi = self.calculate_mfi()?;
+
+        if mfi < self.oversold_threshold {
+            Some(Side::Buy)
+        } else if mfi > self.overbought_threshold {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+impl OrderSplitStrategy for MFIStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
+    use crate::models::parent_orders::ParentOrder;
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
+
+    fn candle(close: f64, volume: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume,
+        }
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "mfi_strategy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mfi_strategy_initialization() {
+        let strategy = MFIStrategy::new(14, 80.0, 20.0);
+        assert_eq!(strategy.period, 14);
+        assert_eq!(strategy.overbought_threshold, 80.0);
+        assert_eq!(strategy.oversold_threshold, 20.0);
+        assert!(strategy.prev_typical_price.is_none());
+        assert_eq!(strategy.positive_flows.len(), 0);
+        assert_eq!(strategy.negative_flows.len(), 0);
+    }
+
+    #[test]
+    fn test_add_candle() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 添加第一根K线
+        strategy.add_candle(candle(100.0, 10.0));
+        assert_eq!(strategy.prev_typical_price, Some(100.0));
+        assert_eq!(strategy.positive_flows.len(), 0);
+
+        // 添加上涨K线：正资金流 = 110 * 20
+        strategy.add_candle(candle(110.0, 20.0));
+        assert_eq!(strategy.positive_flows[0], 2200.0);
+        assert_eq!(strategy.negative_flows[0], 0.0);
+
+        // 添加下跌K线：负资金流 = 100 * 30
+        strategy.add_candle(candle(100.0, 30.0));
+        assert_eq!(strategy.positive_flows[1], 0.0);
+        assert_eq!(strategy.negative_flows[1], 3000.0);
+    }
+
+    #[test]
+    fn test_calculate_mfi() {
+        let mut strategy = MFIStrategy::new(2, 80.0, 20.0);
+
+        strategy.add_candle(candle(100.0, 10.0));
+        strategy.add_candle(candle(110.0, 20.0));
+        assert!(strategy.calculate_mfi().is_none());
+
+        // 正资金流 2200，负资金流 3000，MFI = 100 - 100 / (1 + 2200/3000)
+        strategy.add_candle(candle(100.0, 30.0));
+        let mfi = strategy.calculate_mfi().unwrap();
+        assert!((mfi - 100.0 * 2200.0 / 5200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_all_positive_flow() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 持续放量上涨，全部为正资金流
+        for i in 0..6 {
+            strategy.add_candle(candle(100.0 + 10.0 * i as f64, 1000.0 + 100.0 * i as f64));
+        }
+
+        assert_eq!(strategy.calculate_mfi(), Some(100.0));
+    }
+
+    #[test]
+    fn test_buy_signal_generation() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 放量下跌，仅有一根缩量小幅反弹，使MFI低于20
+        strategy.add_candle(candle(100.0, 1000.0));
+        strategy.add_candle(candle(95.0, 1500.0));
+        strategy.add_candle(candle(90.0, 2000.0));
+        strategy.add_candle(candle(91.0, 100.0));
+        strategy.add_candle(candle(85.0, 2500.0));
+        strategy.add_candle(candle(80.0, 3000.0));
+
+        let mfi = strategy.calculate_mfi().unwrap();
+        assert!(mfi < 20.0, "MFI = {}", mfi);
+
+        // 此时应该有买入信号
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+    }
+
+    #[test]
+    fn test_sell_signal_generation() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 放量上涨，仅有一根缩量小幅回调，使MFI高于80
+        strategy.add_candle(candle(100.0, 1000.0));
+        strategy.add_candle(candle(105.0, 1500.0));
+        strategy.add_candle(candle(110.0, 2000.0));
+        strategy.add_candle(candle(109.0, 100.0));
+        strategy.add_candle(candle(115.0, 2500.0));
+        strategy.add_candle(candle(120.0, 3000.0));
+
+        let mfi = strategy.calculate_mfi().unwrap();
+        assert!(mfi > 80.0, "MFI = {}", mfi);
+
+        // 此时应该有卖出信号
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+    }
+
+    #[test]
+    fn test_order_split_with_matching_signal() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 设置产生买入信号
+        for i in 0..6 {
+            let (close, volume) = (100.0 - 5.0 * i as f64, 1000.0 + 500.0 * i as f64);
+            strategy.add_candle(candle(close, volume));
+        }
+
+        // 分割订单
+        let child_orders = strategy.split(&parent_order(Side::Buy));
+
+        // 验证生成了子订单
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].parent_id, "test_id");
+        assert_eq!(child_orders[0].strategy_id, "mfi_strategy");
+    }
+
+    #[test]
+    fn test_order_split_with_non_matching_signal() {
+        let mut strategy = MFIStrategy::new(5, 80.0, 20.0);
+
+        // 设置产生买入信号
+        for i in 0..6 {
+            let (close, volume) = (100.0 - 5.0 * i as f64, 1000.0 + 500.0 * i as f64);
+            strategy.add_candle(candle(close, volume));
+        }
+
+        // 卖出父订单与信号不匹配，不生成子订单
+        let child_orders = strategy.split(&parent_order(Side::Sell));
+        assert_eq!(child_orders.len(), 0);
+    }
+}
@@ -25,16 +25,33 @@ THE SOFTWARE.
    Email: jb@taunais.com
    Date: 25/5/24
 ******************************************************************************/
+mod adx;
+mod atr;
+mod bollingerbands;
+mod composite;
 mod heikin_ashi;
+mod ichimoku;
+mod keltner;
 mod macd;
+mod mfi;
+mod obv;
+mod parabolic_sar;
+mod resampler;
 mod rsi;
-mod bollingerbands;
 mod stoc;
 
 // 导出模块中的结构体
-pub use heikin_ashi::HeikinAshiStrategy;
-pub use macd::MAStrategy;
-pub use rsi::RSIStrategy;
-pub use bollingerbands::BollingerBandsStrategy;
+pub use adx::{ADXFilter, FilteredStrategy};
+pub use atr::ATRSizer;
+pub use bollingerbands::{BollingerBandsStrategy, SignalMode};
+pub use composite::{CompositeStrategy, VoteMode};
+pub use heikin_ashi::{Candle, HeikinAshiStrategy};
+pub use ichimoku::IchimokuStrategy;
+pub use keltner::KeltnerChannelStrategy;
+pub use macd::{AverageType, MACDStrategy, MAStrategy};
+pub use mfi::MFIStrategy;
+pub use obv::OBVStrategy;
+pub use parabolic_sar::{ParabolicSARConfig, ParabolicSARStrategy};
+pub use resampler::{CandleResampler, CandleStrategy, ResampledStrategy};
+pub use rsi::{DivergenceConfig, RSIStrategy, RsiConfig, RsiMode};
 pub use stoc::StochasticStrategy;
-
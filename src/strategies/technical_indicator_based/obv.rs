@@ -0,0 +1,266 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+On-Balance Volume (OBV) divergence
+OBV adds the candle volume when the close rises and subtracts it when the close falls. When
+price makes a new low over the swing lookback but OBV does not (bullish divergence), selling
+pressure is drying up and a buy signal is generated; a new price high without a new OBV
+high (bearish divergence) generates a sell signal.
+
+Reference for Further Reading:
+"Granville's New Key to Stock Market Profits" by Joseph Granville (1963).
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use std::collections::VecDeque;
+
+/// On-Balance Volume divergence strategy implementation
+pub struct OBVStrategy {
+    /// Number of previous candles a new swing high/low is measured against
+    swing_lookback: usize,
+    /// Period of the OBV simple moving average
+    ma_period: usize,
+    obv: f64,
+    prev_close: Option<f64>,
+    /// Recent (close, OBV) pairs, including the latest candle
+    history: VecDeque<(f64, f64)>,
+    /// Recent OBV values for the moving average
+    obv_window: VecDeque<f64>,
+}
+
+impl OBVStrategy {
+    pub fn new(swing_lookback: usize, ma_period: usize) -> Self {
+        let swing_lookback = swing_lookback.max(1);
+        let ma_period = ma_period.max(1);
+        OBVStrategy {
+            swing_lookback,
+            ma_period,
+            obv: 0.0,
+            prev_close: None,
+            history: VecDeque::with_capacity(swing_lookback + 1),
+            obv_window: VecDeque::with_capacity(ma_period),
+        }
+    }
+
+    /// Adds a new candle and accumulates its volume into the OBV
+    pub fn add_candle(&mut self, candle: Candle) {
+        if let Some(prev_close) = self.prev_close {
+            if candle.close > prev_close {
+                self.obv += candle.volume;
+            } else if candle.close < prev_close {
+                self.obv -= candle.volume;
+            }
+        }
+        self.prev_close = Some(candle.close);
+
+        self.history.push_back((candle.close, self.obv));
+        if self.history.len() > self.swing_lookback + 1 {
+            self.history.pop_front();
+        }
+        self.obv_window.push_back(self.obv);
+        if self.obv_window.len() > self.ma_period {
+            self.obv_window.pop_front();
+        }
+    }
+
+    /// Current On-Balance Volume
+    pub fn get_obv(&self) -> f64 {
+        self.obv
+    }
+
+    /// Simple moving average of the OBV, once `ma_period` candles have been seen
+    pub fn get_obv_ma(&self) -> Option<f64> {
+        (self.obv_window.len() == self.ma_period)
+            .then(|| self.obv_window.iter().sum::<f64>() / self.ma_period as f64)
+    }
+
+    /// Determines the trading signal from price/OBV divergences
+    pub fn get_signal(&self) -> Option<Side> {
+        if self.history.len() < self.swing_lookback + 1 {
+            return None;
+        }
+
+        let (close, obv) = *self.history.back()?;
+        let previous = self.history.iter().take(self.swing_lookback);
+        let (low, high, obv_low, obv_high) = previous.fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(low, high, obv_low, obv_high), (close, obv)| {
+                (
+                    low.min(*close),
+                    high.max(*close),
+                    obv_low.min(*obv),
+                    obv_high.max(*obv),
+                )
+            },
+        );
+
+        // Bullish divergence: lower price low without a lower OBV low
+        if close < low && obv > obv_low {
+            Some(Side::Buy)
+        // Bearish divergence: higher price high without a higher OBV high
+        } else if close > high && obv < obv_high {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+impl OrderSplitStrategy for OBVStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
+
+    fn candle(close: f64, volume: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+        }
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "obv".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_obv_accumulation() {
+        let mut strategy = OBVStrategy::new(3, 2);
+        strategy.add_candle(candle(100.0, 500.0));
+        assert_eq!(strategy.get_obv(), 0.0);
+        strategy.add_candle(candle(101.0, 1000.0));
+        assert_eq!(strategy.get_obv(), 1000.0);
+        strategy.add_candle(candle(100.0, 400.0));
+        assert_eq!(strategy.get_obv(), 600.0);
+        // Unchanged close leaves OBV untouched
+        strategy.add_candle(candle(100.0, 900.0));
+        assert_eq!(strategy.get_obv(), 600.0);
+        assert_eq!(strategy.get_obv_ma(), Some(600.0));
+    }
+
+    #[test]
+    fn test_bullish_divergence() {
+        let mut strategy = OBVStrategy::new(6, 3);
+        // Heavy selling to a first low, then a rebound on strong volume
+        for (close, volume) in [
+            (100.0, 0.0),
+            (95.0, 1000.0),
+            (90.0, 1000.0),
+            (94.0, 1500.0),
+            (97.0, 1500.0),
+        ] {
+            strategy.add_candle(candle(close, volume));
+            assert!(strategy.get_signal().is_none());
+        }
+
+        // Lower price low on light volume: OBV stays well above its previous low
+        strategy.add_candle(candle(92.0, 200.0));
+        strategy.add_candle(candle(88.0, 200.0));
+        assert_eq!(strategy.get_obv(), 600.0);
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        assert_eq!(strategy.split(&parent_order(Side::Buy)).len(), 1);
+        assert!(strategy.split(&parent_order(Side::Sell)).is_empty());
+    }
+
+    #[test]
+    fn test_confirmed_low_is_not_a_divergence() {
+        let mut strategy = OBVStrategy::new(3, 3);
+        for close in [100.0, 98.0, 96.0, 94.0, 92.0] {
+            strategy.add_candle(candle(close, 1000.0));
+        }
+        // Price and OBV make new lows together
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_bearish_divergence() {
+        let mut strategy = OBVStrategy::new(6, 3);
+        for (close, volume) in [
+            (100.0, 0.0),
+            (105.0, 1000.0),
+            (110.0, 1000.0),
+            (106.0, 1500.0),
+            (103.0, 1500.0),
+            (108.0, 200.0),
+            (112.0, 200.0),
+        ] {
+            strategy.add_candle(candle(close, volume));
+        }
+        assert_eq!(strategy.get_obv(), -600.0);
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+        assert_eq!(strategy.split(&parent_order(Side::Sell)).len(), 1);
+    }
+}
@@ -0,0 +1,323 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Parabolic SAR (Stop And Reverse)
+The SAR trails price, moving towards the extreme point (EP) of the current trend by an
+acceleration factor (AF) that grows each time a new extreme is made. In an uptrend the SAR
+may never rise above the lows of the two previous candles, and in a downtrend it may never
+fall below their highs. When price crosses the SAR the trend reverses: the SAR jumps to the
+previous extreme point and the AF resets. Buy and sell signals are emitted on the flip bar.
+
+Reference for Further Reading:
+"New Concepts in Technical Trading Systems" by J. Welles Wilder (1978).
+*/
+
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::Candle;
+use crate::models::orders::Side;
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+
+/// Acceleration factor settings for the Parabolic SAR
+#[derive(Debug, Clone)]
+pub struct ParabolicSARConfig {
+    pub initial_af: f64,
+    pub af_step: f64,
+    pub af_max: f64,
+}
+
+impl Default for ParabolicSARConfig {
+    fn default() -> Self {
+        Self {
+            initial_af: 0.02,
+            af_step: 0.02,
+            af_max: 0.2,
+        }
+    }
+}
+
+/// Parabolic SAR strategy implementation
+pub struct ParabolicSARStrategy {
+    config: ParabolicSARConfig,
+    /// The two previous candles, most recent last
+    prev_candles: Vec<Candle>,
+    /// Current regime: Buy for long, Sell for short
+    trend: Option<Side>,
+    sar: f64,
+    extreme_point: f64,
+    af: f64,
+    /// Regime flip that happened on the latest candle
+    flip: Option<Side>,
+}
+
+impl ParabolicSARStrategy {
+    pub fn new(config: ParabolicSARConfig) -> Self {
+        ParabolicSARStrategy {
+            af: config.initial_af,
+            config,
+            prev_candles: Vec::with_capacity(2),
+            trend: None,
+            sar: 0.0,
+            extreme_point: 0.0,
+            flip: None,
+        }
+    }
+
+    /// Adds a new candle and advances the SAR
+    pub fn add_candle(&mut self, candle: Candle) {
+        self.flip = None;
+
+        match (self.trend.clone(), self.prev_candles.len()) {
+            (None, 1) => {
+                // Second candle: pick the initial regime from the closes
+                let first = self.prev_candles[0];
+                if candle.close >= first.close {
+                    self.trend = Some(Side::Buy);
+                    self.sar = first.low.min(candle.low);
+                    self.extreme_point = first.high.max(candle.high);
+                } else {
+                    self.trend = Some(Side::Sell);
+                    self.sar = first.high.max(candle.high);
+                    self.extreme_point = first.low.min(candle.low);
+                }
+                self.af = self.config.initial_af;
+            }
+            (Some(trend), 2) => self.advance(trend, candle),
+            _ => {}
+        }
+
+        self.prev_candles.push(candle);
+        if self.prev_candles.len() > 2 {
+            self.prev_candles.remove(0);
+        }
+    }
+
+    fn advance(&mut self, trend: Side, candle: Candle) {
+        let (older, previous) = (self.prev_candles[0], self.prev_candles[1]);
+        let next_sar = self.sar + self.af * (self.extreme_point - self.sar);
+
+        match trend {
+            Side::Buy => {
+                // The SAR may not penetrate the lows of the two previous candles
+                let next_sar = next_sar.min(previous.low).min(older.low);
+                if candle.low < next_sar {
+                    self.reverse(Side::Sell, candle.low);
+                } else {
+                    self.sar = next_sar;
+                    if candle.high > self.extreme_point {
+                        self.extreme_point = candle.high;
+                        self.af = (self.af + self.config.af_step).min(self.config.af_max);
+                    }
+                }
+            }
+            Side::Sell => {
+                // The SAR may not penetrate the highs of the two previous candles
+                let next_sar = next_sar.max(previous.high).max(older.high);
+                if candle.high > next_sar {
+                    self.reverse(Side::Buy, candle.high);
+                } else {
+                    self.sar = next_sar;
+                    if candle.low < self.extreme_point {
+                        self.extreme_point = candle.low;
+                        self.af = (self.af + self.config.af_step).min(self.config.af_max);
+                    }
+                }
+            }
+        }
+    }
+
+    fn reverse(&mut self, trend: Side, extreme_point: f64) {
+        // The new SAR starts at the extreme point of the finished trend
+        self.sar = self.extreme_point;
+        self.extreme_point = extreme_point;
+        self.af = self.config.initial_af;
+        self.trend = Some(trend.clone());
+        self.flip = Some(trend);
+    }
+
+    /// Current SAR, once two candles have been seen
+    pub fn sar(&self) -> Option<f64> {
+        self.trend.as_ref().map(|_| self.sar)
+    }
+
+    pub fn extreme_point(&self) -> Option<f64> {
+        self.trend.as_ref().map(|_| self.extreme_point)
+    }
+
+    pub fn acceleration_factor(&self) -> f64 {
+        self.af
+    }
+
+    /// Current regime: Buy for long, Sell for short
+    pub fn trend(&self) -> Option<Side> {
+        self.trend.clone()
+    }
+
+    /// Signal on the bar where the regime flips
+    pub fn get_signal(&self) -> Option<Side> {
+        self.flip.clone()
+    }
+}
+
+impl OrderSplitStrategy for ParabolicSARStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let child_orders = match self.get_signal() {
+            Some(side) if side == parent_order.order_common.side => vec![ChildOrder {
+                order_common: parent_order.order_common.clone(),
+                strategy_id: parent_order.strategy_id.clone(),
+                parent_id: parent_order.order_common.id.clone(),
+                insert_at: Some(parent_order.order_common.timestamp),
+            }],
+            _ => Vec::new(),
+        };
+        checked_split(parent_order, child_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, TimeInForce};
+    use crate::models::{Quantity, Timestamp};
+    use std::time::UNIX_EPOCH;
+
+    fn candle(high: f64, low: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open: (high + low) / 2.0,
+            high,
+            low,
+            close: (high + low) / 2.0,
+            volume: 1000.0,
+        }
+    }
+
+    /// Eight rising candles followed by a sharp reversal
+    fn trending_then_reversing() -> Vec<Candle> {
+        let mut candles: Vec<Candle> = (0..8)
+            .map(|i| candle(101.0 + i as f64, 99.0 + i as f64))
+            .collect();
+        candles.extend([
+            candle(104.0, 100.0),
+            candle(103.0, 97.0),
+            candle(100.0, 95.0),
+        ]);
+        candles
+    }
+
+    fn parent_order(side: Side) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(100),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "psar".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_initial_regime() {
+        let mut strategy = ParabolicSARStrategy::new(ParabolicSARConfig::default());
+        strategy.add_candle(candle(101.0, 99.0));
+        assert!(strategy.sar().is_none());
+        strategy.add_candle(candle(102.0, 100.0));
+        assert_eq!(strategy.trend(), Some(Side::Buy));
+        assert_eq!(strategy.sar(), Some(99.0));
+        assert_eq!(strategy.extreme_point(), Some(102.0));
+        assert!(strategy.get_signal().is_none());
+    }
+
+    #[test]
+    fn test_sar_clamped_to_prior_lows() {
+        let mut strategy = ParabolicSARStrategy::new(ParabolicSARConfig::default());
+        let candles = trending_then_reversing();
+        for candle in &candles[..3] {
+            strategy.add_candle(*candle);
+        }
+        // 99 + 0.02 * (102 - 99) = 99.06 would sit above the low two candles back
+        assert_eq!(strategy.sar(), Some(99.0));
+        assert_eq!(strategy.extreme_point(), Some(103.0));
+        assert!((strategy.acceleration_factor() - 0.04).abs() < 1e-12);
+
+        for (i, candle) in candles.iter().enumerate().take(8).skip(3) {
+            strategy.add_candle(*candle);
+            let sar = strategy.sar().unwrap();
+            assert!(sar <= candles[i - 1].low && sar <= candles[i - 2].low);
+        }
+        assert!((strategy.sar().unwrap() - 101.284339).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_flip_bar() {
+        let mut strategy = ParabolicSARStrategy::new(ParabolicSARConfig::default());
+        let mut signals = Vec::new();
+        for candle in trending_then_reversing() {
+            strategy.add_candle(candle);
+            signals.push(strategy.get_signal());
+        }
+
+        // The ninth candle's low of 100 breaks the SAR of 102.22
+        assert_eq!(signals[8], Some(Side::Sell));
+        assert_eq!(signals.iter().filter(|signal| signal.is_some()).count(), 1);
+        assert_eq!(strategy.trend(), Some(Side::Sell));
+        // After the flip the SAR restarts from the uptrend's extreme point of 108
+        assert!((strategy.sar().unwrap() - 107.56).abs() < 1e-9);
+        assert_eq!(strategy.extreme_point(), Some(95.0));
+    }
+
+    #[test]
+    fn test_split_on_flip_only() {
+        let mut strategy = ParabolicSARStrategy::new(ParabolicSARConfig::default());
+        let candles = trending_then_reversing();
+        for candle in &candles[..9] {
+            strategy.add_candle(*candle);
+        }
+        assert_eq!(strategy.split(&parent_order(Side::Sell)).len(), 1);
+        assert!(strategy.split(&parent_order(Side::Buy)).is_empty());
+
+        strategy.add_candle(candles[9]);
+        assert!(strategy.split(&parent_order(Side::Sell)).is_empty());
+    }
+}
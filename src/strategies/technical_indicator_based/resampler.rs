@@ -0,0 +1,410 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+/*
+Candle resampler
+Aggregates timestamped lower-timeframe candles (e.g. 1-minute bars) into candles of a
+higher timeframe. Buckets are aligned to multiples of the interval since the epoch, so a
+5-minute resampler always produces 00:00, 00:05, 00:10... candles regardless of where the
+feed starts.
+
+Handling of irregular input:
+- Gaps: buckets without any input are skipped; no synthetic candles are produced.
+- Out of order within the open bucket: merged by timestamp, so the open comes from the
+  earliest and the close from the latest candle.
+- Late candles for an already emitted bucket are dropped and counted.
+*/
+
+use super::adx::ADXFilter;
+use super::atr::ATRSizer;
+use super::heikin_ashi::HeikinAshiStrategy;
+use super::ichimoku::IchimokuStrategy;
+use super::keltner::KeltnerChannelStrategy;
+use super::mfi::MFIStrategy;
+use super::obv::OBVStrategy;
+use super::parabolic_sar::ParabolicSARStrategy;
+use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::{epoch_millis, Candle};
+use crate::models::parent_orders::ParentOrder;
+use crate::strategies::common_strategies::{OrderSplitStrategy, SignalDecision};
+
+/// Candle being built for the current bucket
+#[derive(Debug, Clone, Copy)]
+struct PartialCandle {
+    bucket_start: u64,
+    first_timestamp: u64,
+    last_timestamp: u64,
+    candle: Candle,
+}
+
+/// Aggregates timestamped candles into a higher timeframe
+#[derive(Debug, Clone)]
+pub struct CandleResampler {
+    /// Target interval, in the same unit as the timestamps (milliseconds)
+    interval: u64,
+    current: Option<PartialCandle>,
+    /// Start of the last emitted bucket
+    last_emitted: Option<u64>,
+    /// Number of candles dropped because their bucket was already emitted
+    late_candles: usize,
+}
+
+impl CandleResampler {
+    /// Creates a resampler for the given interval in milliseconds
+    pub fn new(interval: u64) -> Self {
+        assert!(interval > 0, "resampling interval must be positive");
+        CandleResampler {
+            interval,
+            current: None,
+            last_emitted: None,
+            late_candles: 0,
+        }
+    }
+
+    /// Creates a resampler producing candles of the given number of minutes
+    pub fn minutes(minutes: u64) -> Self {
+        Self::new(minutes * 60_000)
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Number of late candles dropped so far
+    pub fn late_candles(&self) -> usize {
+        self.late_candles
+    }
+
+    /// Start of the bucket containing the timestamp
+    pub fn bucket_start(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.interval
+    }
+
+    /// Adds a candle and returns the previous bucket's candle once a later bucket begins
+    ///
+    /// The returned tuple holds the bucket start timestamp and the aggregated candle.
+    pub fn add(&mut self, timestamp: u64, candle: Candle) -> Option<(u64, Candle)> {
+        let bucket_start = self.bucket_start(timestamp);
+
+        let current = match self.current.as_mut() {
+            Some(current) if current.bucket_start == bucket_start => current,
+            Some(current) if current.bucket_start > bucket_start => {
+                self.late_candles += 1;
+                return None;
+            }
+            Some(_) => {
+                let completed = self.flush();
+                self.start_bucket(bucket_start, timestamp, candle);
+                return completed;
+            }
+            None => {
+                if self.last_emitted.is_some_and(|last| bucket_start <= last) {
+                    self.late_candles += 1;
+                } else {
+                    self.start_bucket(bucket_start, timestamp, candle);
+                }
+                return None;
+            }
+        };
+
+        let aggregated = &mut current.candle;
+        aggregated.high = aggregated.high.max(candle.high);
+        aggregated.low = aggregated.low.min(candle.low);
+        aggregated.volume += candle.volume;
+        if timestamp < current.first_timestamp {
+            current.first_timestamp = timestamp;
+            aggregated.open = candle.open;
+        }
+        if timestamp >= current.last_timestamp {
+            current.last_timestamp = timestamp;
+            aggregated.close = candle.close;
+        }
+        None
+    }
+
+    /// Emits the partially built candle, if any, stamped with its bucket start
+    pub fn flush(&mut self) -> Option<(u64, Candle)> {
+        let mut partial = self.current.take()?;
+        self.last_emitted = Some(partial.bucket_start);
+        partial.candle.timestamp = epoch_millis::from_millis(partial.bucket_start);
+        Some((partial.bucket_start, partial.candle))
+    }
+
+    fn start_bucket(&mut self, bucket_start: u64, timestamp: u64, candle: Candle) {
+        self.current = Some(PartialCandle {
+            bucket_start,
+            first_timestamp: timestamp,
+            last_timestamp: timestamp,
+            candle,
+        });
+    }
+}
+
+/// Strategies that consume one candle at a time
+pub trait CandleStrategy {
+    fn add_candle(&mut self, candle: Candle);
+}
+
+impl CandleStrategy for HeikinAshiStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        HeikinAshiStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for IchimokuStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        IchimokuStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for KeltnerChannelStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        KeltnerChannelStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for MFIStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        MFIStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for OBVStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        OBVStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for ParabolicSARStrategy {
+    fn add_candle(&mut self, candle: Candle) {
+        ParabolicSARStrategy::add_candle(self, candle);
+    }
+}
+
+impl CandleStrategy for ADXFilter {
+    fn add_candle(&mut self, candle: Candle) {
+        ADXFilter::add_candle(self, &candle);
+    }
+}
+
+impl CandleStrategy for ATRSizer {
+    fn add_candle(&mut self, candle: Candle) {
+        ATRSizer::add_candle(self, &candle);
+    }
+}
+
+/// Runs a candle strategy on a higher timeframe than the feed
+///
+/// Only completed higher-timeframe candles are forwarded to the inner strategy.
+pub struct ResampledStrategy<S> {
+    pub inner: S,
+    resampler: CandleResampler,
+}
+
+impl<S: CandleStrategy> ResampledStrategy<S> {
+    pub fn new(inner: S, resampler: CandleResampler) -> Self {
+        ResampledStrategy { inner, resampler }
+    }
+
+    /// Adds a feed candle, forwarding the aggregated candle when a bucket completes
+    pub fn add_candle(&mut self, timestamp: u64, candle: Candle) -> Option<(u64, Candle)> {
+        let completed = self.resampler.add(timestamp, candle);
+        if let Some((_, aggregated)) = completed {
+            self.inner.add_candle(aggregated);
+        }
+        completed
+    }
+
+    /// Forces the partial candle into the inner strategy
+    pub fn flush(&mut self) -> Option<(u64, Candle)> {
+        let completed = self.resampler.flush();
+        if let Some((_, aggregated)) = completed {
+            self.inner.add_candle(aggregated);
+        }
+        completed
+    }
+
+    pub fn resampler(&self) -> &CandleResampler {
+        &self.resampler
+    }
+}
+
+impl<S: OrderSplitStrategy> OrderSplitStrategy for ResampledStrategy<S> {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        self.inner.split(parent_order)
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        self.inner.decision()
+    }
+
+    fn seed(&self) -> Option<u64> {
+        self.inner.seed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    const MINUTE: u64 = 60_000;
+
+    fn candle(open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        }
+    }
+
+    #[test]
+    fn test_merges_ohlcv() {
+        let mut resampler = CandleResampler::minutes(5);
+        assert_eq!(resampler.interval(), 5 * MINUTE);
+
+        assert!(resampler
+            .add(0, candle(10.0, 12.0, 9.0, 11.0, 100.0))
+            .is_none());
+        assert!(resampler
+            .add(MINUTE, candle(11.0, 15.0, 10.0, 14.0, 50.0))
+            .is_none());
+        assert!(resampler
+            .add(2 * MINUTE, candle(14.0, 14.5, 8.0, 13.0, 25.0))
+            .is_none());
+
+        let (start, aggregated) = resampler
+            .add(5 * MINUTE, candle(13.0, 13.0, 13.0, 13.0, 1.0))
+            .unwrap();
+        assert_eq!(start, 0);
+        assert_eq!(aggregated.timestamp, UNIX_EPOCH);
+        assert_eq!(aggregated.open, 10.0);
+        assert_eq!(aggregated.high, 15.0);
+        assert_eq!(aggregated.low, 8.0);
+        assert_eq!(aggregated.close, 13.0);
+        assert_eq!(aggregated.volume, 175.0);
+    }
+
+    #[test]
+    fn test_boundary_alignment() {
+        let mut resampler = CandleResampler::minutes(15);
+        let start = 1_700_000_000_000;
+        let aligned = start - start % (15 * MINUTE);
+        assert_eq!(resampler.bucket_start(start), aligned);
+
+        // The last millisecond of a bucket still belongs to it
+        resampler.add(aligned + 15 * MINUTE - 1, candle(1.0, 1.0, 1.0, 1.0, 1.0));
+        let (emitted, _) = resampler
+            .add(aligned + 15 * MINUTE, candle(2.0, 2.0, 2.0, 2.0, 1.0))
+            .unwrap();
+        assert_eq!(emitted, aligned);
+
+        let (emitted, flushed) = resampler.flush().unwrap();
+        assert_eq!(emitted, aligned + 15 * MINUTE);
+        assert_eq!(flushed.close, 2.0);
+        assert!(resampler.flush().is_none());
+    }
+
+    #[test]
+    fn test_gaps_skip_empty_buckets() {
+        let mut resampler = CandleResampler::minutes(5);
+        resampler.add(MINUTE, candle(1.0, 1.0, 1.0, 1.0, 1.0));
+
+        // Nothing arrives between minute 2 and minute 17
+        let (start, _) = resampler
+            .add(17 * MINUTE, candle(2.0, 2.0, 2.0, 2.0, 1.0))
+            .unwrap();
+        assert_eq!(start, 0);
+        let (start, _) = resampler.flush().unwrap();
+        assert_eq!(start, 15 * MINUTE);
+    }
+
+    #[test]
+    fn test_out_of_order_within_bucket() {
+        let mut resampler = CandleResampler::minutes(5);
+        resampler.add(2 * MINUTE, candle(20.0, 21.0, 19.0, 20.5, 1.0));
+        resampler.add(4 * MINUTE, candle(22.0, 23.0, 21.0, 22.5, 1.0));
+        resampler.add(0, candle(18.0, 19.0, 17.0, 18.5, 1.0));
+        resampler.add(3 * MINUTE, candle(21.0, 22.0, 20.0, 21.5, 1.0));
+
+        let (_, aggregated) = resampler.flush().unwrap();
+        assert_eq!(aggregated.open, 18.0);
+        assert_eq!(aggregated.close, 22.5);
+        assert_eq!(aggregated.high, 23.0);
+        assert_eq!(aggregated.low, 17.0);
+        assert_eq!(aggregated.volume, 4.0);
+    }
+
+    #[test]
+    fn test_late_candles_are_dropped() {
+        let mut resampler = CandleResampler::minutes(5);
+        resampler.add(MINUTE, candle(1.0, 1.0, 1.0, 1.0, 1.0));
+        resampler.add(6 * MINUTE, candle(2.0, 2.0, 2.0, 2.0, 1.0));
+
+        // Belongs to the bucket emitted above
+        assert!(resampler
+            .add(4 * MINUTE, candle(9.0, 9.0, 9.0, 9.0, 1.0))
+            .is_none());
+        assert_eq!(resampler.late_candles(), 1);
+
+        resampler.flush();
+        assert!(resampler
+            .add(7 * MINUTE, candle(9.0, 9.0, 9.0, 9.0, 1.0))
+            .is_none());
+        assert_eq!(resampler.late_candles(), 2);
+        assert!(resampler.flush().is_none());
+    }
+
+    #[test]
+    fn test_resampled_strategy_forwards_completed_candles() {
+        let mut strategy =
+            ResampledStrategy::new(HeikinAshiStrategy::new(5), CandleResampler::minutes(5));
+
+        // Three rising 5-minute candles built from 1-minute bars
+        for bar in 0..15 {
+            let price = 100.0 + bar as f64;
+            let completed = strategy.add_candle(
+                bar * MINUTE,
+                candle(price, price + 1.0, price, price + 1.0, 1.0),
+            );
+            assert_eq!(completed.is_some(), bar > 0 && bar % 5 == 0);
+        }
+        assert!(strategy.inner.get_signal().is_none());
+
+        let (start, aggregated) = strategy.flush().unwrap();
+        assert_eq!(start, 10 * MINUTE);
+        assert_eq!(epoch_millis::to_millis(&aggregated.timestamp), 10 * MINUTE);
+        assert_eq!(aggregated.open, 110.0);
+        assert_eq!(aggregated.close, 115.0);
+        assert_eq!(aggregated.volume, 5.0);
+        assert!(strategy.inner.get_signal().is_some());
+    }
+}
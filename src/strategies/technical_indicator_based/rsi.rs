@@ -11,38 +11,254 @@ RSI是一种动量振荡指标，用于衡量价格变动的速度和变化。RS
 介绍了RSI指标及其应用。(第3章详细讨论了RSI的计算和使用)
 */
 
-use std::collections::VecDeque;
-use crate::models::orders::Side;
+use crate::config::StrategyConfig;
 use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
 use crate::models::parent_orders::ParentOrder;
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::strategies::common_strategies::{
+    checked_split, signal_child_orders, OrderSplitStrategy, Signal, SignalDecision, SignalStrategy,
+    Snapshot, StrengthSizing,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+// RSI 平均涨跌幅的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RsiMode {
+    // 最近 N 个涨跌幅的简单平均
+    Simple,
+    // Wilder 平滑：以前 N 个涨跌幅的简单平均为初始值，之后 avg = (prev_avg × (N − 1) + current) / N
+    Wilder,
+}
 
+// RSI 策略参数，可从 Config 的 strategies 部分读取，缺省字段取默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RsiConfig {
+    pub period: usize,
+    pub overbought_threshold: f64,
+    pub oversold_threshold: f64,
+    pub mode: RsiMode,
+}
+
+impl Default for RsiConfig {
+    fn default() -> Self {
+        RsiConfig {
+            period: 14,
+            overbought_threshold: 70.0,
+            oversold_threshold: 30.0,
+            mode: RsiMode::Simple,
+        }
+    }
+}
+
+impl StrategyConfig for RsiConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.period < 1 {
+            return Err("period must be at least 1".to_string());
+        }
+        // 阈值位于 0 到 100 之间，且超卖阈值低于超买阈值
+        let in_range = |threshold: f64| (0.0..=100.0).contains(&threshold);
+        if !in_range(self.oversold_threshold) || !in_range(self.overbought_threshold) {
+            return Err(format!(
+                "thresholds must be between 0 and 100, got oversold {} and overbought {}",
+                self.oversold_threshold, self.overbought_threshold
+            ));
+        }
+        if self.oversold_threshold >= self.overbought_threshold {
+            return Err(format!(
+                "oversold_threshold {} must be below overbought_threshold {}",
+                self.oversold_threshold, self.overbought_threshold
+            ));
+        }
+        Ok(())
+    }
+}
+
+// 背离检测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivergenceConfig {
+    // 摆动点两侧需要确认的K线数量
+    pub swing_width: usize,
+    // 只比较最近 lookback 根K线内的摆动点
+    pub lookback: usize,
+    // 为 true 时 get_signal() 只在出现超买/超卖区域内的背离时发出信号
+    pub require_confirmation: bool,
+}
+
+// 振荡指标（0 到 100）的信号强度：阈值处为 0，极值（0 或 100）处为 1
+pub(super) fn oscillator_strength(side: &Side, value: f64, oversold: f64, overbought: f64) -> f64 {
+    let strength = match side {
+        Side::Buy if oversold > 0.0 => (oversold - value) / oversold,
+        Side::Sell if overbought < 100.0 => (value - overbought) / (100.0 - overbought),
+        _ => 1.0,
+    };
+    strength.clamp(0.0, 1.0)
+}
+
+// 摆动点：(K线序号, 价格, RSI)
+type SwingPoint = (usize, f64, f64);
+
+// 简单的摆动点检测器：中间点严格低于（高于）两侧各 width 个点时记为摆动低点（高点）
+#[derive(Serialize, Deserialize)]
+struct SwingDetector {
+    config: DivergenceConfig,
+    window: VecDeque<SwingPoint>,
+    lows: VecDeque<SwingPoint>,
+    highs: VecDeque<SwingPoint>,
+}
+
+impl SwingDetector {
+    fn new(config: DivergenceConfig) -> Self {
+        SwingDetector {
+            window: VecDeque::with_capacity(2 * config.swing_width + 1),
+            lows: VecDeque::new(),
+            highs: VecDeque::new(),
+            config,
+        }
+    }
+
+    fn update(&mut self, bar: usize, price: f64, rsi: f64) {
+        let width = self.config.swing_width;
+        self.window.push_back((bar, price, rsi));
+        if self.window.len() > 2 * width + 1 {
+            self.window.pop_front();
+        }
+
+        if self.window.len() == 2 * width + 1 {
+            let candidate = self.window[width];
+            let others = self.window.iter().enumerate().filter(|(i, _)| *i != width);
+            if others.clone().all(|(_, point)| candidate.1 < point.1) {
+                self.lows.push_back(candidate);
+            }
+            if others.clone().all(|(_, point)| candidate.1 > point.1) {
+                self.highs.push_back(candidate);
+            }
+        }
+
+        // 丢弃超出回看范围的摆动点
+        let lookback = self.config.lookback;
+        self.lows.retain(|point| bar - point.0 <= lookback);
+        self.highs.retain(|point| bar - point.0 <= lookback);
+    }
+
+    // 最近的背离及其第二个摆动点
+    fn divergence(&self) -> Option<(Side, SwingPoint)> {
+        let last_two = |points: &VecDeque<SwingPoint>| {
+            let len = points.len();
+            (len >= 2).then(|| (points[len - 2], points[len - 1]))
+        };
+
+        // 常规看涨背离：价格创更低的低点，RSI 形成更高的低点
+        let bullish = last_two(&self.lows)
+            .filter(|(first, second)| second.1 < first.1 && second.2 > first.2)
+            .map(|(_, second)| (Side::Buy, second));
+        // 常规看跌背离：价格创更高的高点，RSI 形成更低的高点
+        let bearish = last_two(&self.highs)
+            .filter(|(first, second)| second.1 > first.1 && second.2 < first.2)
+            .map(|(_, second)| (Side::Sell, second));
+
+        match (bullish, bearish) {
+            (Some(bullish), Some(bearish)) => {
+                // 两者同时存在时取较新的摆动点
+                if bullish.1 .0 >= bearish.1 .0 {
+                    Some(bullish)
+                } else {
+                    Some(bearish)
+                }
+            }
+            (bullish, bearish) => bullish.or(bearish),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct RSIStrategy {
     period: usize,
+    mode: RsiMode,
     prices: VecDeque<f64>,
     gains: VecDeque<f64>,
     losses: VecDeque<f64>,
+    // Wilder 模式下的平滑平均涨幅和跌幅
+    avg_gain: Option<f64>,
+    avg_loss: Option<f64>,
     overbought_threshold: f64,
     oversold_threshold: f64,
+    // 已处理的价格数量
+    bar: usize,
+    swing_detector: Option<SwingDetector>,
+    // 按信号强度缩放子订单数量
+    sizing: Option<StrengthSizing>,
 }
 
 impl RSIStrategy {
     pub fn new(period: usize, overbought_threshold: f64, oversold_threshold: f64) -> Self {
+        Self::with_mode(
+            period,
+            overbought_threshold,
+            oversold_threshold,
+            RsiMode::Simple,
+        )
+    }
+
+    // 按配置创建，参数应已通过 RsiConfig::validate 检查
+    pub fn from_config(config: &RsiConfig) -> Self {
+        Self::with_mode(
+            config.period,
+            config.overbought_threshold,
+            config.oversold_threshold,
+            config.mode,
+        )
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    pub fn thresholds(&self) -> (f64, f64) {
+        (self.oversold_threshold, self.overbought_threshold)
+    }
+
+    pub fn with_mode(
+        period: usize,
+        overbought_threshold: f64,
+        oversold_threshold: f64,
+        mode: RsiMode,
+    ) -> Self {
         RSIStrategy {
             period,
+            mode,
             prices: VecDeque::with_capacity(period + 1),
             gains: VecDeque::with_capacity(period),
             losses: VecDeque::with_capacity(period),
+            avg_gain: None,
+            avg_loss: None,
             overbought_threshold,
             oversold_threshold,
+            bar: 0,
+            swing_detector: None,
+            sizing: None,
         }
     }
-    
+
+    // 按信号强度缩放子订单数量
+    pub fn with_strength_sizing(mut self, sizing: StrengthSizing) -> Self {
+        self.sizing = Some(sizing);
+        self
+    }
+
+    // 启用背离检测
+    pub fn with_divergence(mut self, config: DivergenceConfig) -> Self {
+        self.swing_detector = Some(SwingDetector::new(config));
+        self
+    }
+
     pub fn add_price(&mut self, price: f64) {
         if !self.prices.is_empty() {
             let prev_price = *self.prices.back().unwrap();
             let change = price - prev_price;
-            
+
             if change > 0.0 {
                 self.gains.push_back(change);
                 self.losses.push_back(0.0);
@@ -50,68 +266,187 @@ impl RSIStrategy {
                 self.gains.push_back(0.0);
                 self.losses.push_back(-change);
             }
-            
+
             if self.gains.len() > self.period {
                 self.gains.pop_front();
                 self.losses.pop_front();
             }
+
+            if self.mode == RsiMode::Wilder {
+                self.update_wilder_averages();
+            }
         }
-        
+
         self.prices.push_back(price);
         if self.prices.len() > self.period + 1 {
             self.prices.pop_front();
         }
+
+        if let Some(rsi) = self.calculate_rsi() {
+            let bar = self.bar;
+            if let Some(detector) = self.swing_detector.as_mut() {
+                detector.update(bar, price, rsi);
+            }
+        }
+        self.bar += 1;
     }
-    
+
+    // 用最新的涨跌幅更新 Wilder 平滑平均
+    fn update_wilder_averages(&mut self) {
+        let period = self.period as f64;
+        match (self.avg_gain, self.avg_loss) {
+            (Some(avg_gain), Some(avg_loss)) => {
+                let gain = *self.gains.back().unwrap_or(&0.0);
+                let loss = *self.losses.back().unwrap_or(&0.0);
+                self.avg_gain = Some((avg_gain * (period - 1.0) + gain) / period);
+                self.avg_loss = Some((avg_loss * (period - 1.0) + loss) / period);
+            }
+            _ if self.gains.len() == self.period => {
+                // 初始窗口：简单平均
+                self.avg_gain = Some(self.gains.iter().sum::<f64>() / period);
+                self.avg_loss = Some(self.losses.iter().sum::<f64>() / period);
+            }
+            _ => {}
+        }
+    }
+
     pub fn calculate_rsi(&self) -> Option<f64> {
         if self.gains.len() < self.period {
             return None;
         }
-        
-        let avg_gain: f64 = self.gains.iter().sum::<f64>() / self.period as f64;
-        let avg_loss: f64 = self.losses.iter().sum::<f64>() / self.period as f64;
-        
+
+        let (avg_gain, avg_loss) = match self.mode {
+            RsiMode::Simple => (
+                self.gains.iter().sum::<f64>() / self.period as f64,
+                self.losses.iter().sum::<f64>() / self.period as f64,
+            ),
+            RsiMode::Wilder => (self.avg_gain?, self.avg_loss?),
+        };
+
         if avg_loss == 0.0 {
             return Some(100.0);
         }
-        
+
         let rs = avg_gain / avg_loss;
         let rsi = 100.0 - (100.0 / (1.0 + rs));
-        
+
         Some(rsi)
     }
-    
+
+    // 已积累足够的价格计算 RSI
+    pub fn is_ready(&self) -> bool {
+        self.calculate_rsi().is_some()
+    }
+
+    // 距离就绪还需要的价格数量（period 个涨跌幅需要 period + 1 个价格）
+    pub fn warmup_remaining(&self) -> usize {
+        (self.period + 1).saturating_sub(self.prices.len())
+    }
+
+    pub fn reset(&mut self) {
+        self.prices.clear();
+        self.gains.clear();
+        self.losses.clear();
+        self.avg_gain = None;
+        self.avg_loss = None;
+        self.bar = 0;
+        if let Some(detector) = self.swing_detector.as_mut() {
+            *detector = SwingDetector::new(detector.config.clone());
+        }
+    }
+
+    // 回看范围内最近的价格与 RSI 背离信号
+    pub fn get_divergence_signal(&self) -> Option<Side> {
+        let (side, _) = self.swing_detector.as_ref()?.divergence()?;
+        Some(side)
+    }
+
     pub fn get_signal(&self) -> Option<Side> {
+        self.get_signal_strength().map(|signal| signal.side)
+    }
+
+    // 带强度的信号：RSI 越接近 0（100），买入（卖出）信号越强
+    pub fn get_signal_strength(&self) -> Option<Signal> {
+        if let Some(detector) = &self.swing_detector {
+            if detector.config.require_confirmation {
+                // 背离确认模式：背离的第二个摆动点需要位于超卖（超买）区域
+                let (side, (_, _, rsi)) = detector.divergence()?;
+                let confirmed = match side {
+                    Side::Buy => rsi < self.oversold_threshold,
+                    Side::Sell => rsi > self.overbought_threshold,
+                };
+                return confirmed.then(|| self.graded(side, rsi, "divergence"));
+            }
+        }
+
         let rsi = self.calculate_rsi()?;
-        
+
         if rsi < self.oversold_threshold {
-            Some(Side::Buy)
+            Some(self.graded(Side::Buy, rsi, "oversold"))
         } else if rsi > self.overbought_threshold {
-            Some(Side::Sell)
+            Some(self.graded(Side::Sell, rsi, "overbought"))
         } else {
             None
         }
     }
+
+    fn graded(&self, side: Side, rsi: f64, condition: &str) -> Signal {
+        let strength = oscillator_strength(
+            &side,
+            rsi,
+            self.oversold_threshold,
+            self.overbought_threshold,
+        );
+        Signal::new(side, strength, format!("RSI {:.2} {}", rsi, condition))
+    }
+}
+
+impl SignalStrategy for RSIStrategy {
+    fn update(&mut self, data: &MarketData) {
+        if let Some(price) = data.price() {
+            self.add_price(price);
+        }
+    }
+
+    fn signal(&self) -> Option<Side> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        RSIStrategy::is_ready(self)
+    }
+
+    fn reset(&mut self) {
+        RSIStrategy::reset(self);
+    }
+
+    fn indicators(&self) -> BTreeMap<String, f64> {
+        self.calculate_rsi()
+            .map(|rsi| BTreeMap::from([("rsi".to_string(), rsi)]))
+            .unwrap_or_default()
+    }
 }
 
+impl Snapshot for RSIStrategy {}
+
 impl OrderSplitStrategy for RSIStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        let signal = self.get_signal();
-        if signal.is_none() {
+        // 预热期内不下单，原因可通过 no_order_reason 查询
+        if !self.is_ready() {
             return Vec::new();
         }
-        
-        match (signal.unwrap(), &parent_order.order_common.side) {
-            (Side::Buy, Side::Buy) | (Side::Sell, Side::Sell) => {
-                vec![ChildOrder {
-                    order_common: parent_order.order_common.clone(),
-                    strategy_id: parent_order.strategy_id.clone(),
-                    parent_id: parent_order.order_common.id.clone(),
-                    insert_at: Some(parent_order.order_common.timestamp),
-                }]
-            },
-            _ => Vec::new(),
-        }
+        checked_split(
+            parent_order,
+            signal_child_orders(parent_order, self.get_signal_strength(), self.sizing),
+        )
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
     }
 }
 
@@ -120,6 +455,7 @@ mod tests {
     use super::*;
     use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
     use crate::models::parent_orders::ParentOrder;
+    use crate::models::{Quantity, Timestamp};
 
     #[test]
     fn test_rsi_strategy_initialization() {
@@ -135,13 +471,13 @@ mod tests {
     #[test]
     fn test_add_price() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 添加第一个价格
         strategy.add_price(100.0);
         assert_eq!(strategy.prices.len(), 1);
         assert_eq!(strategy.gains.len(), 0);
         assert_eq!(strategy.losses.len(), 0);
-        
+
         // 添加上升价格
         strategy.add_price(110.0);
         assert_eq!(strategy.prices.len(), 2);
@@ -149,7 +485,7 @@ mod tests {
         assert_eq!(strategy.losses.len(), 1);
         assert_eq!(strategy.gains[0], 10.0);
         assert_eq!(strategy.losses[0], 0.0);
-        
+
         // 添加下降价格
         strategy.add_price(100.0);
         assert_eq!(strategy.prices.len(), 3);
@@ -162,30 +498,30 @@ mod tests {
     #[test]
     fn test_calculate_rsi() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 添加价格
         strategy.add_price(100.0);
         assert!(strategy.calculate_rsi().is_none());
-        
+
         // 添加连续上涨的价格
         strategy.add_price(110.0);
         strategy.add_price(120.0);
         strategy.add_price(130.0);
         strategy.add_price(140.0);
         strategy.add_price(150.0);
-        
+
         // 现在应该能计算RSI
         let rsi = strategy.calculate_rsi();
         assert!(rsi.is_some());
         assert_eq!(rsi.unwrap(), 100.0); // 全是上涨，RSI = 100
-        
+
         // 添加连续下跌的价格
         strategy.add_price(140.0);
         strategy.add_price(130.0);
         strategy.add_price(120.0);
         strategy.add_price(110.0);
         strategy.add_price(100.0);
-        
+
         // 现在RSI应该很低
         let rsi = strategy.calculate_rsi();
         assert!(rsi.is_some());
@@ -195,7 +531,7 @@ mod tests {
     #[test]
     fn test_buy_signal_generation() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 添加价格使RSI低于30
         strategy.add_price(100.0);
         strategy.add_price(95.0);
@@ -203,7 +539,7 @@ mod tests {
         strategy.add_price(85.0);
         strategy.add_price(80.0);
         strategy.add_price(75.0);
-        
+
         // 此时应该有买入信号
         let signal = strategy.get_signal();
         assert!(signal.is_some());
@@ -213,7 +549,7 @@ mod tests {
     #[test]
     fn test_sell_signal_generation() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 添加价格使RSI高于70
         strategy.add_price(100.0);
         strategy.add_price(110.0);
@@ -221,7 +557,7 @@ mod tests {
         strategy.add_price(130.0);
         strategy.add_price(140.0);
         strategy.add_price(150.0);
-        
+
         // 此时应该有卖出信号
         let signal = strategy.get_signal();
         assert!(signal.is_some());
@@ -231,7 +567,7 @@ mod tests {
     #[test]
     fn test_order_split_with_matching_signal() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 设置产生买入信号
         strategy.add_price(100.0);
         strategy.add_price(95.0);
@@ -239,16 +575,16 @@ mod tests {
         strategy.add_price(85.0);
         strategy.add_price(80.0);
         strategy.add_price(75.0);
-        
+
         // 创建买入父订单
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Buy,
@@ -264,10 +600,10 @@ mod tests {
             ),
             strategy_id: "rsi_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证生成了子订单
         assert_eq!(child_orders.len(), 1);
         assert_eq!(child_orders[0].parent_id, "test_id");
@@ -277,7 +613,7 @@ mod tests {
     #[test]
     fn test_order_split_with_non_matching_signal() {
         let mut strategy = RSIStrategy::new(5, 70.0, 30.0);
-        
+
         // 设置产生买入信号
         strategy.add_price(100.0);
         strategy.add_price(95.0);
@@ -285,16 +621,16 @@ mod tests {
         strategy.add_price(85.0);
         strategy.add_price(80.0);
         strategy.add_price(75.0);
-        
+
         // 创建卖出父订单（与信号不匹配）
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Sell,
@@ -310,11 +646,285 @@ mod tests {
             ),
             strategy_id: "rsi_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证没有生成子订单
         assert_eq!(child_orders.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_wilder_rsi_reference_values() {
+        // 广泛引用的 14 周期 Wilder RSI 计算示例
+        let prices = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45,
+            45.78, 45.35, 44.03, 44.18, 44.22, 44.57, 43.42, 42.66, 43.13,
+        ];
+        let expected = [
+            70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42,
+            39.99, 41.46, 41.87, 45.46, 37.30, 33.09, 37.79,
+        ];
+
+        let mut strategy = RSIStrategy::with_mode(14, 70.0, 30.0, RsiMode::Wilder);
+        for price in &prices[..14] {
+            strategy.add_price(*price);
+            assert!(strategy.calculate_rsi().is_none());
+        }
+        for (price, expected) in prices[14..].iter().zip(expected.iter()) {
+            strategy.add_price(*price);
+            let rsi = strategy.calculate_rsi().unwrap();
+            // 参考值基于四舍五入后的中间结果
+            assert!(
+                (rsi - expected).abs() < 0.1,
+                "RSI {} expected {}",
+                rsi,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_wilder_differs_from_simple() {
+        let mut simple = RSIStrategy::new(5, 70.0, 30.0);
+        let mut wilder = RSIStrategy::with_mode(5, 70.0, 30.0, RsiMode::Wilder);
+        for price in [
+            100.0, 105.0, 103.0, 108.0, 104.0, 110.0, 100.0, 101.0, 99.0, 102.0,
+        ] {
+            simple.add_price(price);
+            wilder.add_price(price);
+        }
+        // 第一个窗口相同，之后 Wilder 保留了更早的涨跌幅
+        let simple_rsi = simple.calculate_rsi().unwrap();
+        let wilder_rsi = wilder.calculate_rsi().unwrap();
+        assert!((simple_rsi - wilder_rsi).abs() > 1.0);
+    }
+
+    fn divergence_config(require_confirmation: bool) -> DivergenceConfig {
+        DivergenceConfig {
+            swing_width: 1,
+            lookback: 12,
+            require_confirmation,
+        }
+    }
+
+    #[test]
+    fn test_bullish_divergence() {
+        let mut strategy =
+            RSIStrategy::new(5, 70.0, 30.0).with_divergence(divergence_config(false));
+
+        // 急跌至 80 后反弹，再缓慢走低：79 处 RSI 为 0，77 处 RSI 回升到 26.7
+        let prices = [
+            100.0, 101.0, 100.0, 101.0, 100.0, 101.0, 95.0, 88.0, 80.0, 86.0, 90.0, 88.0, 86.0,
+            84.0, 82.0, 79.0, 83.0, 78.0, 77.0,
+        ];
+        for price in prices {
+            strategy.add_price(price);
+            assert!(strategy.get_divergence_signal().is_none());
+        }
+
+        // 77 的低点在下一根K线被确认
+        strategy.add_price(80.0);
+        assert_eq!(strategy.get_divergence_signal(), Some(Side::Buy));
+    }
+
+    #[test]
+    fn test_bearish_divergence() {
+        let mut strategy = RSIStrategy::new(5, 70.0, 30.0).with_divergence(divergence_config(true));
+
+        // 123 的高点高于 121，但 RSI 从 100 回落到 73.3（仍在超买区域）
+        let prices = [
+            100.0, 99.0, 100.0, 99.0, 100.0, 99.0, 105.0, 112.0, 120.0, 114.0, 110.0, 112.0, 114.0,
+            116.0, 118.0, 121.0, 117.0, 122.0, 123.0, 120.0,
+        ];
+        for price in prices {
+            strategy.add_price(price);
+        }
+
+        assert_eq!(strategy.get_divergence_signal(), Some(Side::Sell));
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+    }
+
+    #[test]
+    fn test_no_divergence_when_rsi_confirms_low() {
+        // 第二个低点的价格和 RSI 都更低，没有背离
+        let prices = [
+            100.0, 101.0, 100.0, 101.0, 100.0, 101.0, 99.0, 100.0, 97.0, 98.0, 95.0, 99.0, 102.0,
+            101.0, 95.0, 88.0, 84.0, 86.0,
+        ];
+
+        let mut plain = RSIStrategy::new(5, 70.0, 30.0).with_divergence(divergence_config(false));
+        let mut confirmed =
+            RSIStrategy::new(5, 70.0, 30.0).with_divergence(divergence_config(true));
+        for price in prices {
+            plain.add_price(price);
+            confirmed.add_price(price);
+        }
+
+        assert!(plain.get_divergence_signal().is_none());
+        // 默认模式仍按超卖水平发出买入信号，确认模式则不发出
+        assert_eq!(plain.get_signal(), Some(Side::Buy));
+        assert!(confirmed.get_signal().is_none());
+    }
+
+    fn parent_order(side: Side, quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "rsi_strategy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_oscillator_strength_at_thresholds() {
+        assert_eq!(oscillator_strength(&Side::Buy, 30.0, 30.0, 70.0), 0.0);
+        assert_eq!(oscillator_strength(&Side::Buy, 15.0, 30.0, 70.0), 0.5);
+        assert_eq!(oscillator_strength(&Side::Buy, 0.0, 30.0, 70.0), 1.0);
+        assert_eq!(oscillator_strength(&Side::Sell, 70.0, 30.0, 70.0), 0.0);
+        assert_eq!(oscillator_strength(&Side::Sell, 85.0, 30.0, 70.0), 0.5);
+        assert_eq!(oscillator_strength(&Side::Sell, 100.0, 30.0, 70.0), 1.0);
+    }
+
+    #[test]
+    fn test_signal_strength() {
+        // 涨 1 跌 4：RSI = 20
+        let mut strategy = RSIStrategy::new(2, 70.0, 30.0);
+        for price in [100.0, 101.0, 97.0] {
+            strategy.add_price(price);
+        }
+        let signal = strategy.get_signal_strength().unwrap();
+        assert_eq!(signal.side, Side::Buy);
+        assert!((signal.strength - 1.0 / 3.0).abs() < 1e-9);
+        assert!(signal.reason.contains("oversold"));
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        // 连续下跌：RSI = 0
+        let mut strategy = RSIStrategy::new(2, 70.0, 30.0);
+        for price in [100.0, 99.0, 98.0] {
+            strategy.add_price(price);
+        }
+        assert_eq!(strategy.get_signal_strength().unwrap().strength, 1.0);
+    }
+
+    #[test]
+    fn test_split_scaled_by_strength() {
+        let sizing = StrengthSizing::new(0.5, 0.25);
+
+        // 弱信号只释放部分数量
+        let mut weak = RSIStrategy::new(2, 70.0, 30.0).with_strength_sizing(sizing);
+        for price in [100.0, 101.0, 97.0] {
+            weak.add_price(price);
+        }
+        let child_orders = weak.split(&parent_order(Side::Buy, 100));
+        assert_eq!(child_orders.len(), 1);
+        assert_eq!(child_orders[0].order_common.quantity, Quantity::from(25));
+
+        // 强信号释放全部数量
+        let mut strong = RSIStrategy::new(2, 70.0, 30.0).with_strength_sizing(sizing);
+        for price in [100.0, 99.0, 98.0] {
+            strong.add_price(price);
+        }
+        assert_eq!(
+            strong.split(&parent_order(Side::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(100)
+        );
+
+        // 未设置时保持原数量
+        let mut unsized_strategy = RSIStrategy::new(2, 70.0, 30.0);
+        for price in [100.0, 101.0, 97.0] {
+            unsized_strategy.add_price(price);
+        }
+        assert_eq!(
+            unsized_strategy.split(&parent_order(Side::Buy, 100))[0]
+                .order_common
+                .quantity,
+            Quantity::from(100)
+        );
+    }
+
+    #[test]
+    fn test_warmup_and_reset() {
+        let mut strategy = RSIStrategy::new(3, 70.0, 30.0);
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 4);
+
+        for (i, price) in [100.0, 99.0, 98.0, 97.0].into_iter().enumerate() {
+            strategy.add_price(price);
+            assert_eq!(strategy.warmup_remaining(), 3 - i);
+        }
+        assert!(strategy.is_ready());
+        assert_eq!(strategy.get_signal(), Some(Side::Buy));
+
+        strategy.reset();
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 4);
+        assert_eq!(strategy.get_signal(), None);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut original = RSIStrategy::with_mode(5, 70.0, 30.0, RsiMode::Wilder)
+            .with_divergence(divergence_config(false));
+        for price in [100.0, 102.0, 101.0, 99.0, 97.0, 98.0, 96.0, 95.0] {
+            original.add_price(price);
+        }
+
+        let mut restored = RSIStrategy::restore(&original.snapshot()).unwrap();
+        assert_eq!(restored.calculate_rsi(), original.calculate_rsi());
+
+        for price in [94.0, 96.0, 99.0] {
+            original.add_price(price);
+            restored.add_price(price);
+            assert_eq!(
+                restored.get_signal_strength(),
+                original.get_signal_strength()
+            );
+        }
+        assert!(RSIStrategy::restore("{}").is_err());
+    }
+
+    #[test]
+    fn test_rsi_config_validation() {
+        assert!(RsiConfig::default().validate().is_ok());
+        let strategy = RSIStrategy::from_config(&RsiConfig::default());
+        assert_eq!(strategy.period(), 14);
+        assert_eq!(strategy.thresholds(), (30.0, 70.0));
+
+        let zero_period = RsiConfig {
+            period: 0,
+            ..RsiConfig::default()
+        };
+        assert!(zero_period.validate().is_err());
+        let inverted = RsiConfig {
+            oversold_threshold: 75.0,
+            ..RsiConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+        let out_of_range = RsiConfig {
+            overbought_threshold: 120.0,
+            ..RsiConfig::default()
+        };
+        assert!(out_of_range.validate().is_err());
+    }
+}
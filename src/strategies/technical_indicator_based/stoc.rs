@@ -10,132 +10,277 @@
 这本书详细介绍了随机指标及其应用。(第11章详细讨论了振荡器指标的使用)
 */
 
-use std::collections::VecDeque;
-use crate::models::orders::Side;
+use super::rsi::oscillator_strength;
 use crate::models::child_orders::ChildOrder;
+use crate::models::market_data::MarketData;
+use crate::models::orders::Side;
 use crate::models::parent_orders::ParentOrder;
-use crate::strategies::common_strategies::OrderSplitStrategy;
+use crate::strategies::common_strategies::{
+    checked_split, signal_child_orders, OrderSplitStrategy, Signal, SignalDecision, SignalStrategy,
+    Snapshot, StrengthSizing,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
+#[derive(Serialize, Deserialize)]
 pub struct StochasticStrategy {
     k_period: usize,
     d_period: usize,
+    // %K 的平滑周期：1 为快速随机指标，大于 1（通常为 3）为慢速随机指标
+    smoothing: usize,
     prices: VecDeque<f64>,
+    // 已处理的K线数量
+    samples: usize,
     highs: VecDeque<f64>,
     lows: VecDeque<f64>,
+    // 未平滑的原始 %K
+    raw_k_values: VecDeque<f64>,
+    // 平滑后的 %K，信号基于该值
     k_values: VecDeque<f64>,
     d_values: VecDeque<f64>,
     overbought_threshold: f64,
     oversold_threshold: f64,
+    // 按信号强度缩放子订单数量
+    sizing: Option<StrengthSizing>,
 }
 
 impl StochasticStrategy {
-    pub fn new(k_period: usize, d_period: usize, overbought_threshold: f64, oversold_threshold: f64) -> Self {
+    pub fn new(
+        k_period: usize,
+        d_period: usize,
+        overbought_threshold: f64,
+        oversold_threshold: f64,
+    ) -> Self {
         StochasticStrategy {
             k_period,
             d_period,
+            smoothing: 1,
             prices: VecDeque::with_capacity(k_period),
+            samples: 0,
             highs: VecDeque::with_capacity(k_period),
             lows: VecDeque::with_capacity(k_period),
-            k_values: VecDeque::with_capacity(d_period),
-            d_values: VecDeque::new(),
+            raw_k_values: VecDeque::with_capacity(1),
+            k_values: VecDeque::with_capacity(d_period.max(2)),
+            d_values: VecDeque::with_capacity(2),
             overbought_threshold,
             oversold_threshold,
+            sizing: None,
         }
     }
-    
+
+    // 按信号强度缩放子订单数量
+    pub fn with_strength_sizing(mut self, sizing: StrengthSizing) -> Self {
+        self.sizing = Some(sizing);
+        self
+    }
+
+    // 设置 %K 的平滑周期（慢速随机指标）
+    pub fn with_smoothing(mut self, smoothing: usize) -> Self {
+        self.smoothing = smoothing.max(1);
+        self.raw_k_values = VecDeque::with_capacity(self.smoothing);
+        self
+    }
+
     pub fn add_candle(&mut self, close: f64, high: f64, low: f64) {
+        self.samples += 1;
         self.prices.push_back(close);
         self.highs.push_back(high);
         self.lows.push_back(low);
-        
+
         if self.prices.len() > self.k_period {
             self.prices.pop_front();
             self.highs.pop_front();
             self.lows.pop_front();
         }
-        
+
         self.calculate_stochastic();
     }
-    
+
     fn calculate_stochastic(&mut self) {
         if self.prices.len() < self.k_period {
             return;
         }
-        
+
         // 计算%K
-        let highest_high = *self.highs.iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
-        let lowest_low = *self.lows.iter().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
-        
+        let highest_high = *self
+            .highs
+            .iter()
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap();
+        let lowest_low = *self
+            .lows
+            .iter()
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap();
+
         if highest_high == lowest_low {
             return;
         }
-        
+
         let current_close = *self.prices.back().unwrap();
-        let k_value = 100.0 * (current_close - lowest_low) / (highest_high - lowest_low);
-        
+        let raw_k_value = 100.0 * (current_close - lowest_low) / (highest_high - lowest_low);
+
+        self.raw_k_values.push_back(raw_k_value);
+        if self.raw_k_values.len() > self.smoothing {
+            self.raw_k_values.pop_front();
+        }
+
+        // 平滑%K（smoothing 为 1 时即原始%K）
+        if self.raw_k_values.len() < self.smoothing {
+            return;
+        }
+        let k_value = self.raw_k_values.iter().sum::<f64>() / self.smoothing as f64;
+
         self.k_values.push_back(k_value);
-        
-        if self.k_values.len() > self.d_period {
+
+        // 至少保留两个值用于判断交叉
+        if self.k_values.len() > self.d_period.max(2) {
             self.k_values.pop_front();
         }
-        
+
         // 计算%D
         if self.k_values.len() >= self.d_period {
-            let d_value: f64 = self.k_values.iter().sum::<f64>() / self.d_period as f64;
+            let d_value: f64 =
+                self.k_values.iter().rev().take(self.d_period).sum::<f64>() / self.d_period as f64;
             self.d_values.push_back(d_value);
+
+            // 只需要当前和前一个%D
+            if self.d_values.len() > 2 {
+                self.d_values.pop_front();
+            }
+        }
+    }
+
+    // 已有前后两个%K和%D，可以判断交叉
+    pub fn is_ready(&self) -> bool {
+        self.k_values.len() >= 2 && self.d_values.len() >= 2
+    }
+
+    // 距离就绪还需要的K线数量
+    // 价格区间为0时不产生%K，因此未就绪时至少返回1
+    pub fn warmup_remaining(&self) -> usize {
+        if self.is_ready() {
+            return 0;
         }
+        let required = self.k_period.max(1) + self.smoothing + self.d_period.max(1) - 1;
+        required.saturating_sub(self.samples).max(1)
     }
-    
+
+    pub fn reset(&mut self) {
+        self.prices.clear();
+        self.samples = 0;
+        self.highs.clear();
+        self.lows.clear();
+        self.raw_k_values.clear();
+        self.k_values.clear();
+        self.d_values.clear();
+    }
+
     pub fn get_signal(&self) -> Option<Side> {
+        self.get_signal_strength().map(|signal| signal.side)
+    }
+
+    // 带强度的信号：交叉时%K越接近 0（100），买入（卖出）信号越强
+    pub fn get_signal_strength(&self) -> Option<Signal> {
         if self.k_values.len() < 2 || self.d_values.len() < 2 {
             return None;
         }
-        
+
         let current_k = *self.k_values.back().unwrap();
         let prev_k = self.k_values.iter().rev().nth(1).unwrap();
-        
+
         let current_d = *self.d_values.back().unwrap();
         let prev_d = self.d_values.iter().rev().nth(1).unwrap();
-        
+
         // %K上穿%D且在超卖区域
-        if prev_k < prev_d && current_k > current_d && current_k < self.oversold_threshold && current_d < self.oversold_threshold {
-            Some(Side::Buy)
+        let side = if prev_k < prev_d
+            && current_k > current_d
+            && current_k < self.oversold_threshold
+            && current_d < self.oversold_threshold
+        {
+            Side::Buy
         }
         // %K下穿%D且在超买区域
-        else if prev_k > prev_d && current_k < current_d && current_k > self.overbought_threshold && current_d > self.overbought_threshold {
-            Some(Side::Sell)
+        else if prev_k > prev_d
+            && current_k < current_d
+            && current_k > self.overbought_threshold
+            && current_d > self.overbought_threshold
+        {
+            Side::Sell
         } else {
-            None
+            return None;
+        };
+
+        let strength = oscillator_strength(
+            &side,
+            current_k,
+            self.oversold_threshold,
+            self.overbought_threshold,
+        );
+        Some(Signal::new(
+            side,
+            strength,
+            format!("%K {:.2} crossed %D {:.2}", current_k, current_d),
+        ))
+    }
+}
+
+impl SignalStrategy for StochasticStrategy {
+    // 没有K线时以单一价格作为收盘价、最高价和最低价
+    fn update(&mut self, data: &MarketData) {
+        match data {
+            MarketData::Candle(candle) => self.add_candle(candle.close, candle.high, candle.low),
+            _ => {
+                if let Some(price) = data.price() {
+                    self.add_candle(price, price, price);
+                }
+            }
         }
     }
+
+    fn signal(&self) -> Option<Side> {
+        self.get_signal()
+    }
+
+    fn is_ready(&self) -> bool {
+        StochasticStrategy::is_ready(self)
+    }
+
+    fn reset(&mut self) {
+        StochasticStrategy::reset(self);
+    }
 }
 
+impl Snapshot for StochasticStrategy {}
+
 impl OrderSplitStrategy for StochasticStrategy {
     fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
-        let signal = self.get_signal();
-        if signal.is_none() {
+        // 预热期内不下单，原因可通过 no_order_reason 查询
+        if !self.is_ready() {
             return Vec::new();
         }
-        
-        match (signal.unwrap(), &parent_order.order_common.side) {
-            (Side::Buy, Side::Buy) | (Side::Sell, Side::Sell) => {
-                vec![ChildOrder {
-                    order_common: parent_order.order_common.clone(),
-                    strategy_id: parent_order.strategy_id.clone(),
-                    parent_id: parent_order.order_common.id.clone(),
-                    insert_at: Some(parent_order.order_common.timestamp),
-                }]
-            },
-            _ => Vec::new(),
-        }
+        checked_split(
+            parent_order,
+            signal_child_orders(parent_order, self.get_signal_strength(), self.sizing),
+        )
+    }
+
+    fn decision(&self) -> Option<SignalDecision> {
+        Some(SignalDecision::of(self))
+    }
+
+    fn on_market_data(&mut self, data: &MarketData) {
+        SignalStrategy::update(self, data);
     }
 }
 
 #[cfg(test)]
+#[allow(unused_variables, clippy::len_zero, clippy::unnecessary_unwrap)]
 mod tests {
     use super::*;
     use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
     use crate::models::parent_orders::ParentOrder;
+    use crate::models::{Quantity, Timestamp};
 
     #[test]
     fn test_stochastic_strategy_initialization() {
@@ -154,17 +299,17 @@ mod tests {
     #[test]
     fn test_add_candle() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 添加蜡烛线
         for i in 1..=10 {
             strategy.add_candle(i as f64, (i + 5) as f64, (i - 1) as f64);
         }
-        
+
         // 检查价格队列长度不超过K周期
         assert_eq!(strategy.prices.len(), 5);
         assert_eq!(strategy.highs.len(), 5);
         assert_eq!(strategy.lows.len(), 5);
-        
+
         // 检查价格队列内容（应该是最后5个价格）
         let expected_prices: Vec<f64> = (6..=10).map(|i| i as f64).collect();
         let actual_prices: Vec<f64> = strategy.prices.iter().cloned().collect();
@@ -174,22 +319,22 @@ mod tests {
     #[test]
     fn test_calculate_stochastic() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 添加蜡烛线，价格在一个范围内波动
         strategy.add_candle(50.0, 60.0, 40.0);
         strategy.add_candle(55.0, 65.0, 45.0);
         strategy.add_candle(60.0, 70.0, 50.0);
         strategy.add_candle(65.0, 75.0, 55.0);
         strategy.add_candle(70.0, 80.0, 60.0);
-        
+
         // 此时应该有K值
         assert!(strategy.k_values.len() > 0);
-        
+
         // 添加更多蜡烛线以计算D值
         strategy.add_candle(75.0, 85.0, 65.0);
         strategy.add_candle(80.0, 90.0, 70.0);
         strategy.add_candle(85.0, 95.0, 75.0);
-        
+
         // 此时应该有D值
         assert!(strategy.d_values.len() > 0);
     }
@@ -197,22 +342,22 @@ mod tests {
     #[test]
     fn test_buy_signal_generation() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 添加蜡烛线，使K值和D值都低于20
         // 先添加足够的蜡烛线以计算K和D
         for i in 0..5 {
             strategy.add_candle(50.0, 60.0, 40.0);
         }
-        
+
         // 添加下降趋势的蜡烛线，使K值下降到超卖区域
         strategy.add_candle(45.0, 55.0, 35.0);
         strategy.add_candle(40.0, 50.0, 30.0);
         strategy.add_candle(35.0, 45.0, 25.0);
         strategy.add_candle(30.0, 40.0, 20.0);
-        
+
         // 添加反转蜡烛线，使K值上穿D值
         strategy.add_candle(40.0, 50.0, 30.0);
-        
+
         // 此时可能有买入信号
         let signal = strategy.get_signal();
         if signal.is_some() {
@@ -223,22 +368,22 @@ mod tests {
     #[test]
     fn test_sell_signal_generation() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 添加蜡烛线，使K值和D值都高于80
         // 先添加足够的蜡烛线以计算K和D
         for i in 0..5 {
             strategy.add_candle(50.0, 60.0, 40.0);
         }
-        
+
         // 添加上升趋势的蜡烛线，使K值上升到超买区域
         strategy.add_candle(60.0, 70.0, 50.0);
         strategy.add_candle(70.0, 80.0, 60.0);
         strategy.add_candle(80.0, 90.0, 70.0);
         strategy.add_candle(90.0, 100.0, 80.0);
-        
+
         // 添加反转蜡烛线，使K值下穿D值
         strategy.add_candle(80.0, 90.0, 70.0);
-        
+
         // 此时可能有卖出信号
         let signal = strategy.get_signal();
         if signal.is_some() {
@@ -249,34 +394,34 @@ mod tests {
     #[test]
     fn test_order_split_with_matching_signal() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 模拟一个买入信号
         // 这里我们直接修改k_values和d_values来创造一个明确的信号情况
         for _ in 0..5 {
             strategy.add_candle(50.0, 60.0, 40.0);
         }
-        
+
         // 手动设置k_values和d_values以确保有买入信号
         strategy.k_values.clear();
         strategy.d_values.clear();
-        
+
         // 添加K值（上穿）
         strategy.k_values.push_back(15.0);
         strategy.k_values.push_back(18.0);
-        
+
         // 添加D值
         strategy.d_values.push_back(17.0);
         strategy.d_values.push_back(16.0);
-        
+
         // 创建买入父订单
         let parent_order = ParentOrder {
             order_common: Order::new(
                 "test_id".to_string(),
-                100,
+                Quantity::from(100),
                 ProductType::Spot,
                 OrderType::Market,
                 None,
-                1234567890,
+                Timestamp::from_secs(1234567890),
                 None,
                 "BTC/USD".to_string(),
                 Side::Buy,
@@ -292,10 +437,10 @@ mod tests {
             ),
             strategy_id: "stochastic_strategy".to_string(),
         };
-        
+
         // 分割订单
         let child_orders = strategy.split(&parent_order);
-        
+
         // 验证生成了子订单
         assert_eq!(child_orders.len(), 1);
         assert_eq!(child_orders[0].parent_id, "test_id");
@@ -305,15 +450,195 @@ mod tests {
     #[test]
     fn test_order_split_with_non_matching_signal() {
         let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
-        
+
         // 模拟一个买入信号
         // 这里我们直接修改k_values和d_values来创造一个明确的信号情况
         for _ in 0..5 {
             strategy.add_candle(50.0, 60.0, 40.0);
         }
-        
+
         // 手动设置k_values和d_values以确保有买入信号
         strategy.k_values.clear();
         strategy.d_values.clear();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fast_vs_slow_stochastic() {
+        let mut fast = StochasticStrategy::new(5, 3, 80.0, 20.0);
+        let mut slow = StochasticStrategy::new(5, 3, 80.0, 20.0).with_smoothing(3);
+        assert_eq!(fast.smoothing, 1);
+        assert_eq!(slow.smoothing, 3);
+
+        let candles = [
+            (50.0, 60.0, 40.0),
+            (55.0, 65.0, 45.0),
+            (52.0, 58.0, 44.0),
+            (60.0, 66.0, 50.0),
+            (58.0, 62.0, 48.0),
+            (64.0, 70.0, 55.0),
+            (61.0, 67.0, 56.0),
+            (57.0, 63.0, 52.0),
+        ];
+        let mut fast_k = Vec::new();
+        for (close, high, low) in candles {
+            fast.add_candle(close, high, low);
+            slow.add_candle(close, high, low);
+            if let Some(k) = fast.k_values.back() {
+                fast_k.push(*k);
+            }
+        }
+
+        // 慢速%K是最近3个快速%K的平均值
+        let expected = fast_k.iter().rev().take(3).sum::<f64>() / 3.0;
+        let slow_k = *slow.k_values.back().unwrap();
+        assert!((slow_k - expected).abs() < 1e-9);
+        assert!((slow_k - fast_k.last().unwrap()).abs() > 1.0);
+
+        // 慢速指标需要更多K线才能产生%D
+        assert_eq!(fast.d_values.len(), 2);
+        assert_eq!(slow.k_values.len(), 2);
+        assert!(slow.d_values.is_empty());
+    }
+
+    #[test]
+    fn test_values_stay_bounded() {
+        let mut strategy = StochasticStrategy::new(14, 3, 80.0, 20.0).with_smoothing(3);
+        for i in 0..10_000 {
+            let close = 100.0 + (i as f64 * 0.1).sin() * 10.0;
+            strategy.add_candle(close, close + 1.0, close - 1.0);
+        }
+        assert_eq!(strategy.raw_k_values.len(), 3);
+        assert_eq!(strategy.k_values.len(), 3);
+        assert_eq!(strategy.d_values.len(), 2);
+    }
+
+    fn parent_order(side: Side, quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "test_id".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_secs(1234567890),
+                None,
+                "BTC/USD".to_string(),
+                side,
+                "USD".to_string(),
+                Some("Binance".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "stochastic_strategy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_signal_strength() {
+        let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0);
+
+        // 超卖区域内%K上穿%D
+        strategy.k_values.extend([5.0, 15.0]);
+        strategy.d_values.extend([10.0, 12.0]);
+        let signal = strategy.get_signal_strength().unwrap();
+        assert_eq!(signal.side, Side::Buy);
+        assert!((signal.strength - 0.25).abs() < 1e-9);
+
+        // 超买区域内%K下穿%D
+        strategy.k_values.clear();
+        strategy.d_values.clear();
+        strategy.k_values.extend([95.0, 85.0]);
+        strategy.d_values.extend([90.0, 88.0]);
+        let signal = strategy.get_signal_strength().unwrap();
+        assert_eq!(signal.side, Side::Sell);
+        assert!((signal.strength - 0.25).abs() < 1e-9);
+        assert_eq!(strategy.get_signal(), Some(Side::Sell));
+    }
+
+    #[test]
+    fn test_split_scaled_by_strength() {
+        let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0)
+            .with_strength_sizing(StrengthSizing::new(0.5, 0.4));
+        strategy.k_values.extend([95.0, 85.0]);
+        strategy.d_values.extend([90.0, 88.0]);
+        assert_eq!(
+            strategy.split(&parent_order(Side::Sell, 10))[0]
+                .order_common
+                .quantity,
+            Quantity::from(4)
+        );
+
+        // %K接近100时为强信号
+        strategy.k_values.clear();
+        strategy.d_values.clear();
+        strategy.k_values.extend([99.0, 92.0]);
+        strategy.d_values.extend([95.0, 94.0]);
+        assert_eq!(
+            strategy.split(&parent_order(Side::Sell, 10))[0]
+                .order_common
+                .quantity,
+            Quantity::from(10)
+        );
+        assert!(strategy.split(&parent_order(Side::Buy, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_warmup_and_reset() {
+        // 5 根K线得到第一个原始%K，平滑 2 个，%D 需要 3 个%K，交叉还需要前一个%D
+        let mut strategy = StochasticStrategy::new(5, 3, 80.0, 20.0).with_smoothing(2);
+        assert_eq!(strategy.warmup_remaining(), 9);
+        for i in 0..9 {
+            assert!(!strategy.is_ready());
+            let close = 100.0 + (i % 3) as f64;
+            strategy.add_candle(close, close + 1.0, close - 1.0);
+            assert_eq!(strategy.warmup_remaining(), 8 - i);
+        }
+        assert!(strategy.is_ready());
+
+        strategy.reset();
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 9);
+    }
+
+    #[test]
+    fn test_flat_prices_never_finish_warmup() {
+        // 价格区间为0时不产生%K
+        let mut strategy = StochasticStrategy::new(3, 3, 80.0, 20.0);
+        for _ in 0..20 {
+            strategy.add_candle(100.0, 100.0, 100.0);
+        }
+        assert!(!strategy.is_ready());
+        assert_eq!(strategy.warmup_remaining(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut original = StochasticStrategy::new(5, 3, 80.0, 20.0).with_smoothing(2);
+        let candles: Vec<(f64, f64, f64)> = (0..30)
+            .map(|i| {
+                let close = 100.0 + (i as f64 * 0.7).sin() * 10.0;
+                (close, close + 1.0, close - 1.0)
+            })
+            .collect();
+        for &(close, high, low) in &candles[..20] {
+            original.add_candle(close, high, low);
+        }
+
+        let mut restored = StochasticStrategy::restore(&original.snapshot()).unwrap();
+        assert_eq!(restored.warmup_remaining(), original.warmup_remaining());
+        for &(close, high, low) in &candles[20..] {
+            original.add_candle(close, high, low);
+            restored.add_candle(close, high, low);
+            assert_eq!(
+                restored.get_signal_strength(),
+                original.get_signal_strength()
+            );
+            assert_eq!(restored.k_values, original.k_values);
+        }
+    }
+}
@@ -26,4 +26,8 @@ THE SOFTWARE.
    Date: 25/5/24
 ******************************************************************************/
 mod time_based_execution;
-mod volume_based_execution;
+pub mod volume_based_execution;
+pub mod volume_profile;
+
+pub use volume_based_execution::*;
+pub use volume_profile::*;
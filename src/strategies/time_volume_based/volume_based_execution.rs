@@ -49,3 +49,309 @@ Reference for Further Reading:
  including volume-based execution strategies. (Chapter 3 discusses volume and liquidity
  considerations in trading).
  */
+
+use crate::models::{ChildOrder, IdGenerator, ParentOrder, Quantity, Timestamp};
+use crate::strategies::common_strategies::{checked_split, OrderSplitStrategy};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Configuration parameters for the volume-based execution strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeBasedExecutionConfig {
+    /// Length of the rolling volume window (milliseconds)
+    pub window_ms: u64,
+    /// Minimum volume rate (units per second) required to release a child order
+    pub rate_threshold: f64,
+    /// Fraction of the observed volume taken by each child order
+    pub participation_rate: f64,
+    /// Time after the parent timestamp when the remaining quantity must be released (milliseconds)
+    pub deadline_ms: u64,
+}
+
+impl Default for VolumeBasedExecutionConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: 60_000,
+            rate_threshold: 100.0,
+            participation_rate: 0.1,
+            deadline_ms: 3_600_000,
+        }
+    }
+}
+
+/// Volume-based execution strategy releasing child orders during high volume periods
+pub struct VolumeBasedExecutionStrategy {
+    /// Configuration parameters
+    config: VolumeBasedExecutionConfig,
+    /// Recent trades as (timestamp, volume)
+    trades: VecDeque<(u64, f64)>,
+    /// Latest time seen, from a trade or an `emit` call (milliseconds)
+    now: u64,
+    /// Volume traded since the last child order was released
+    volume_since_release: f64,
+    /// Quantity already released for the current parent
+    released_quantity: Quantity,
+    /// Source of child order ids
+    ids: IdGenerator,
+}
+
+impl VolumeBasedExecutionStrategy {
+    /// Create a new instance of the volume-based execution strategy
+    pub fn new(config: VolumeBasedExecutionConfig) -> Self {
+        Self {
+            config,
+            trades: VecDeque::new(),
+            now: 0,
+            volume_since_release: 0.0,
+            released_quantity: Quantity::ZERO,
+            ids: IdGenerator::default(),
+        }
+    }
+
+    /// Take child order ids from the given generator
+    pub fn with_id_generator(mut self, ids: IdGenerator) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Record a trade from the market feed
+    pub fn on_trade(&mut self, volume: f64, ts: u64) {
+        self.trades.push_back((ts, volume));
+        self.volume_since_release += volume;
+        self.advance(ts);
+    }
+
+    /// Move the clock to `now_ms` and drop the trades that left the window
+    fn advance(&mut self, now_ms: u64) {
+        self.now = self.now.max(now_ms);
+        let window_start = self.now.saturating_sub(self.config.window_ms);
+        while let Some(&(trade_ts, _)) = self.trades.front() {
+            if trade_ts >= window_start {
+                break;
+            }
+            self.trades.pop_front();
+        }
+    }
+
+    /// Rolling volume rate over the window (units per second)
+    pub fn volume_rate(&self) -> f64 {
+        let volume: f64 = self.trades.iter().map(|(_, volume)| volume).sum();
+        volume / (self.config.window_ms as f64 / 1000.0)
+    }
+
+    /// Quantity already released for the current parent
+    pub fn released_quantity(&self) -> Quantity {
+        self.released_quantity
+    }
+
+    /// Quantity that would be released at `now_ms` for the parent order
+    fn clip_quantity(&self, parent_order: &ParentOrder, now_ms: u64) -> Quantity {
+        let remaining = parent_order
+            .order_common
+            .quantity
+            .saturating_sub(self.released_quantity);
+        let deadline = parent_order.order_common.timestamp.as_millis() + self.config.deadline_ms;
+
+        if now_ms >= deadline {
+            // Must-complete: release everything left regardless of volume
+            remaining
+        } else if self.volume_rate() > self.config.rate_threshold {
+            let clip =
+                Quantity::from_f64(self.volume_since_release * self.config.participation_rate)
+                    .round_to_step(parent_order.order_common.lot_step());
+            clip.min(remaining)
+        } else {
+            Quantity::ZERO
+        }
+    }
+
+    fn child_order(
+        &self,
+        parent_order: &ParentOrder,
+        quantity: Quantity,
+        now_ms: u64,
+    ) -> ChildOrder {
+        let mut order = parent_order.order_common.clone();
+        order.id = self.ids.next_id();
+        order.quantity = quantity;
+        ChildOrder {
+            order_common: order,
+            strategy_id: parent_order.strategy_id.clone(),
+            parent_id: parent_order.order_common.id.clone(),
+            insert_at: Some(Timestamp::from_millis(
+                now_ms.max(parent_order.order_common.timestamp.as_millis()),
+            )),
+        }
+    }
+
+    /// Release a child order for the parent at `now_ms` if the volume conditions or the
+    /// deadline allow it, recording the released quantity so the next call continues from
+    /// the remainder. The clock and the rolling window advance to `now_ms` even when no trade
+    /// arrived, so a silent market still reaches the deadline.
+    pub fn emit(&mut self, parent_order: &ParentOrder, now_ms: u64) -> Option<ChildOrder> {
+        self.advance(now_ms);
+        let quantity = self.clip_quantity(parent_order, self.now);
+        if quantity.is_zero() {
+            return None;
+        }
+        let child_order = self.child_order(parent_order, quantity, self.now);
+        self.released_quantity += quantity;
+        self.volume_since_release = 0.0;
+        Some(child_order)
+    }
+
+    /// Forget the progress made on the current parent
+    pub fn reset(&mut self) {
+        self.volume_since_release = 0.0;
+        self.released_quantity = Quantity::ZERO;
+    }
+}
+
+/// Preview the child order that would be released right now. Use `emit` to advance the
+/// execution of the parent.
+impl OrderSplitStrategy for VolumeBasedExecutionStrategy {
+    fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+        let quantity = self.clip_quantity(parent_order, self.now);
+        if quantity.is_zero() {
+            return Vec::new();
+        }
+        checked_split(
+            parent_order,
+            vec![self.child_order(parent_order, quantity, self.now)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::orders::{Order, OrderType, ProductType, Side, TimeInForce};
+
+    const START: u64 = 1_700_000_000_000;
+
+    fn parent_order(quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: Order::new(
+                "parent-1".to_string(),
+                Quantity::from(quantity),
+                ProductType::Spot,
+                OrderType::Market,
+                None,
+                Timestamp::from_millis(START),
+                None,
+                "AAPL".to_string(),
+                Side::Buy,
+                "USD".to_string(),
+                Some("NASDAQ".to_string()),
+                Some(TimeInForce::GTC),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            strategy_id: "volume_based".to_string(),
+        }
+    }
+
+    fn config() -> VolumeBasedExecutionConfig {
+        VolumeBasedExecutionConfig {
+            window_ms: 10_000,
+            rate_threshold: 50.0,
+            participation_rate: 0.1,
+            deadline_ms: 600_000,
+        }
+    }
+
+    #[test]
+    fn test_rolling_volume_rate() {
+        let mut strategy = VolumeBasedExecutionStrategy::new(config());
+        strategy.on_trade(100.0, START);
+        strategy.on_trade(100.0, START + 5_000);
+        assert_eq!(strategy.volume_rate(), 20.0);
+
+        // The first trade falls out of the 10s window
+        strategy.on_trade(300.0, START + 12_000);
+        assert_eq!(strategy.volume_rate(), 40.0);
+    }
+
+    #[test]
+    fn test_no_release_below_threshold() {
+        let mut strategy = VolumeBasedExecutionStrategy::new(config());
+        let parent_order = parent_order(1000);
+        for i in 0..10 {
+            strategy.on_trade(10.0, START + i * 1_000);
+            assert!(strategy.emit(&parent_order, START + i * 1_000).is_none());
+        }
+        assert!(strategy.split(&parent_order).is_empty());
+    }
+
+    #[test]
+    fn test_children_cluster_in_bursts() {
+        let mut strategy = VolumeBasedExecutionStrategy::new(config());
+        let parent_order = parent_order(1_000_000);
+        let mut child_orders = Vec::new();
+
+        // One trade per second: quiet, burst at 60-70s, quiet, burst at 180-190s
+        for second in 0..240u64 {
+            let volume = if (60..70).contains(&second) || (180..190).contains(&second) {
+                2_000.0
+            } else {
+                5.0
+            };
+            strategy.on_trade(volume, START + second * 1_000);
+            child_orders.extend(strategy.emit(&parent_order, START + second * 1_000));
+        }
+
+        assert!(!child_orders.is_empty());
+        for child in &child_orders {
+            let second = (child.insert_at.unwrap().as_millis() - START) / 1_000;
+            // Children appear during a burst or while it is still inside the rolling window
+            assert!(
+                (60..80).contains(&second) || (180..200).contains(&second),
+                "child released at {}s",
+                second
+            );
+        }
+        // Clips are proportional to the volume traded since the previous release:
+        // 60 quiet seconds plus the first burst trade
+        assert_eq!(child_orders[0].order_common.quantity, Quantity::from(230));
+    }
+
+    #[test]
+    fn test_deadline_releases_remaining_quantity() {
+        let mut strategy = VolumeBasedExecutionStrategy::new(config());
+        let parent_order = parent_order(1_000);
+
+        strategy.on_trade(2_000.0, START + 1_000);
+        let first = strategy.emit(&parent_order, START + 1_000).unwrap();
+        assert_eq!(first.order_common.quantity, Quantity::from(200));
+
+        // No trade at all until the deadline passes
+        assert!(strategy.emit(&parent_order, START + 599_999).is_none());
+        let last = strategy.emit(&parent_order, START + 600_000).unwrap();
+        assert_eq!(last.order_common.quantity, Quantity::from(800));
+        assert_eq!(
+            last.insert_at,
+            Some(Timestamp::from_millis(START + 600_000))
+        );
+        assert_eq!(strategy.released_quantity(), Quantity::from(1_000));
+        assert!(strategy.emit(&parent_order, START + 600_001).is_none());
+        assert_ne!(first.order_common.id, last.order_common.id);
+    }
+
+    #[test]
+    fn test_old_burst_leaves_the_window_without_trades() {
+        let mut strategy = VolumeBasedExecutionStrategy::new(config());
+        let parent_order = parent_order(1_000_000);
+
+        strategy.on_trade(2_000.0, START);
+        assert!(strategy.emit(&parent_order, START).is_some());
+        strategy.on_trade(2_000.0, START + 1_000);
+
+        // Twenty silent seconds later the burst is out of the 10s window
+        assert!(strategy.emit(&parent_order, START + 21_000).is_none());
+        assert_eq!(strategy.volume_rate(), 0.0);
+    }
+}
@@ -0,0 +1,291 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+use crate::models::market_data::Candle;
+use crate::models::Quantity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::UNIX_EPOCH;
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Intraday volume distribution: the expected fraction of the daily volume traded in each
+/// time-of-day bucket (UTC)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeProfile {
+    pub bucket_width_ms: u64,
+    pub fractions: Vec<f64>,
+}
+
+impl VolumeProfile {
+    /// Time-of-day bucket containing `ts` (UNIX milliseconds)
+    pub fn bucket_of(&self, ts: u64) -> usize {
+        ((ts % MILLIS_PER_DAY) / self.bucket_width_ms) as usize
+    }
+
+    /// Expected volume fraction of a bucket, zero for unknown buckets
+    pub fn fraction(&self, bucket: usize) -> f64 {
+        self.fractions.get(bucket).copied().unwrap_or(0.0)
+    }
+
+    /// Split `quantity` over the buckets from `ts` to the end of the day, proportionally to
+    /// the expected volume, rounding each slot down to a multiple of `step`. Returns
+    /// (bucket start timestamp, quantity) pairs; the first slot starts at `ts`. Empty if no
+    /// volume is expected for the rest of the day.
+    pub fn allocate(&self, quantity: Quantity, step: Quantity, ts: u64) -> Vec<(u64, Quantity)> {
+        let first_bucket = self.bucket_of(ts);
+        let day_start = ts - ts % MILLIS_PER_DAY;
+        let buckets: Vec<usize> = (first_bucket..self.fractions.len())
+            .filter(|&bucket| self.fractions[bucket] > 0.0)
+            .collect();
+        let total: f64 = buckets.iter().map(|&bucket| self.fractions[bucket]).sum();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut remaining = quantity;
+        let mut slots = Vec::with_capacity(buckets.len());
+        for (i, &bucket) in buckets.iter().enumerate() {
+            let slot_quantity = if i == buckets.len() - 1 {
+                remaining
+            } else {
+                let share = quantity
+                    .scale(self.fractions[bucket] / total)
+                    .round_down_to_step(step);
+                share.min(remaining)
+            };
+            remaining -= slot_quantity;
+            let start = (day_start + bucket as u64 * self.bucket_width_ms).max(ts);
+            slots.push((start, slot_quantity));
+        }
+        slots
+    }
+}
+
+/// Estimates a `VolumeProfile` from historical candles
+#[derive(Debug, Clone)]
+pub struct VolumeProfileEstimator {
+    /// Width of each time-of-day bucket (milliseconds)
+    bucket_width_ms: u64,
+    /// Weight multiplier applied per day of age; 1.0 weights all days equally
+    decay: f64,
+    /// Volume per bucket for each day (days since epoch)
+    days: BTreeMap<u64, BTreeMap<usize, f64>>,
+}
+
+impl VolumeProfileEstimator {
+    pub fn new(bucket_width_ms: u64, decay: f64) -> Self {
+        Self {
+            bucket_width_ms: bucket_width_ms.clamp(1, MILLIS_PER_DAY),
+            decay,
+            days: BTreeMap::new(),
+        }
+    }
+
+    /// Add a historical candle; its volume is attributed to the bucket of its timestamp
+    pub fn add_candle(&mut self, candle: &Candle) {
+        let ts = match candle.timestamp.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as u64,
+            Err(_) => return,
+        };
+        let bucket = ((ts % MILLIS_PER_DAY) / self.bucket_width_ms) as usize;
+        *self
+            .days
+            .entry(ts / MILLIS_PER_DAY)
+            .or_default()
+            .entry(bucket)
+            .or_insert(0.0) += candle.volume;
+    }
+
+    pub fn add_candles(&mut self, candles: &[Candle]) {
+        for candle in candles {
+            self.add_candle(candle);
+        }
+    }
+
+    /// Weighted average of the daily volume fractions. A bucket missing on some days (halts)
+    /// is averaged over the days where it traded, and days without data (weekends) are
+    /// ignored. The result is normalized to sum to 1.0.
+    pub fn estimate(&self) -> VolumeProfile {
+        let bucket_count = MILLIS_PER_DAY.div_ceil(self.bucket_width_ms) as usize;
+        let mut weighted = vec![0.0; bucket_count];
+        let mut weights = vec![0.0; bucket_count];
+
+        // Most recent day first, with weight 1.0
+        let mut weight = 1.0;
+        for buckets in self.days.values().rev() {
+            let day_total: f64 = buckets.values().sum();
+            if day_total > 0.0 {
+                for (&bucket, &volume) in buckets {
+                    weighted[bucket] += weight * volume / day_total;
+                    weights[bucket] += weight;
+                }
+            }
+            weight *= self.decay;
+        }
+
+        let mut fractions: Vec<f64> = weighted
+            .iter()
+            .zip(&weights)
+            .map(|(value, weight)| if *weight > 0.0 { value / weight } else { 0.0 })
+            .collect();
+        let total: f64 = fractions.iter().sum();
+        if total > 0.0 {
+            fractions.iter_mut().for_each(|fraction| *fraction /= total);
+        }
+
+        VolumeProfile {
+            bucket_width_ms: self.bucket_width_ms,
+            fractions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const THIRTY_MINUTES: u64 = 1_800_000;
+    // Monday 2024-05-20 00:00 UTC
+    const MONDAY: u64 = 1716163200000;
+    // Session from 14:30 to 21:00 UTC: buckets 29..42
+    const FIRST_BUCKET: usize = 29;
+    const LAST_BUCKET: usize = 41;
+
+    fn candle(ts: u64, volume: f64) -> Candle {
+        Candle {
+            timestamp: UNIX_EPOCH + Duration::from_millis(ts),
+            open: 100.0,
+            high: 100.0,
+            low: 100.0,
+            close: 100.0,
+            volume,
+        }
+    }
+
+    /// Five-minute candles over `days` weekdays with a U-shaped volume curve
+    fn u_shaped_candles(days: u64, skip_bucket: Option<(u64, usize)>) -> Vec<Candle> {
+        let mut candles = Vec::new();
+        for day in 0..days {
+            // Skip Saturday and Sunday
+            let calendar_day = day + day / 5 * 2;
+            let day_start = MONDAY + calendar_day * MILLIS_PER_DAY;
+            for bucket in FIRST_BUCKET..=LAST_BUCKET {
+                if skip_bucket == Some((day, bucket)) {
+                    continue;
+                }
+                let distance = bucket as f64 - 35.0;
+                let volume = 1000.0 + 200.0 * distance * distance + day as f64 * 10.0;
+                for minute in (0..30).step_by(5) {
+                    let ts = day_start + bucket as u64 * THIRTY_MINUTES + minute * 60_000;
+                    candles.push(candle(ts, volume / 6.0));
+                }
+            }
+        }
+        candles
+    }
+
+    fn assert_u_shaped(profile: &VolumeProfile) {
+        let total: f64 = profile.fractions.iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for bucket in FIRST_BUCKET..35 {
+            assert!(profile.fraction(bucket) > profile.fraction(bucket + 1));
+        }
+        for bucket in 35..LAST_BUCKET {
+            assert!(profile.fraction(bucket) < profile.fraction(bucket + 1));
+        }
+        assert_eq!(profile.fraction(FIRST_BUCKET - 1), 0.0);
+        assert_eq!(profile.fraction(LAST_BUCKET + 1), 0.0);
+    }
+
+    #[test]
+    fn test_u_shaped_profile() {
+        let mut estimator = VolumeProfileEstimator::new(THIRTY_MINUTES, 1.0);
+        estimator.add_candles(&u_shaped_candles(10, None));
+        let profile = estimator.estimate();
+        assert_eq!(profile.fractions.len(), 48);
+        assert_u_shaped(&profile);
+    }
+
+    #[test]
+    fn test_missing_bucket_is_normalized() {
+        let mut estimator = VolumeProfileEstimator::new(THIRTY_MINUTES, 0.9);
+        // Trading halt during the midday bucket of the third day
+        estimator.add_candles(&u_shaped_candles(5, Some((2, 35))));
+        let profile = estimator.estimate();
+        assert_u_shaped(&profile);
+        assert!(profile.fraction(35) > 0.0);
+    }
+
+    #[test]
+    fn test_recent_days_dominate_with_decay() {
+        let mut estimator = VolumeProfileEstimator::new(THIRTY_MINUTES, 0.1);
+        // Old day trades mostly at the open, recent day mostly at the close
+        for (day, first, last) in [(0, 900.0, 100.0), (1, 100.0, 900.0)] {
+            let day_start = MONDAY + day * MILLIS_PER_DAY;
+            estimator.add_candle(&candle(
+                day_start + FIRST_BUCKET as u64 * THIRTY_MINUTES,
+                first,
+            ));
+            estimator.add_candle(&candle(
+                day_start + LAST_BUCKET as u64 * THIRTY_MINUTES,
+                last,
+            ));
+        }
+        let profile = estimator.estimate();
+        assert!(profile.fraction(LAST_BUCKET) > profile.fraction(FIRST_BUCKET));
+        assert!((profile.fraction(LAST_BUCKET) - 0.91 / 1.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_allocate_follows_profile() {
+        let mut estimator = VolumeProfileEstimator::new(THIRTY_MINUTES, 1.0);
+        estimator.add_candles(&u_shaped_candles(5, None));
+        let profile = estimator.estimate();
+
+        let open = MONDAY + 7 * MILLIS_PER_DAY + FIRST_BUCKET as u64 * THIRTY_MINUTES;
+        let slots = profile.allocate(Quantity::from(10_000), Quantity::from(1), open);
+        assert_eq!(slots.len(), LAST_BUCKET - FIRST_BUCKET + 1);
+        assert_eq!(slots[0].0, open);
+        assert_eq!(
+            slots.iter().map(|(_, quantity)| quantity).sum::<Quantity>(),
+            Quantity::from(10_000)
+        );
+        assert!(slots[0].1 > slots[6].1);
+
+        // Nothing left to trade after the close
+        assert!(profile
+            .allocate(
+                Quantity::from(10_000),
+                Quantity::from(1),
+                open + 7 * 3_600_000
+            )
+            .is_empty());
+    }
+}
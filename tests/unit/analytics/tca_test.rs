@@ -0,0 +1,206 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod tca_tests {
+    use rust_decimal_macros::dec;
+    use std::time::{Duration, UNIX_EPOCH};
+    use strategy_execution_engine::models::market_data::Trade;
+    use strategy_execution_engine::models::orders::{Order, Side};
+    use strategy_execution_engine::{
+        BenchmarkCalculator, Benchmarks, Fill, Liquidity, ParentOrder, ParentOrderState, Quantity,
+        Slippage, TcaReport, Timestamp,
+    };
+
+    const START: u64 = 1_622_512_800_000;
+
+    fn trade(offset_ms: i64, price: f64, size: f64) -> Trade {
+        Trade {
+            timestamp: UNIX_EPOCH + Duration::from_millis((START as i64 + offset_ms) as u64),
+            price,
+            size,
+            side: Side::Buy,
+        }
+    }
+
+    /// Trades at 100 before the parent arrives, then at 101, 102 and 100 a second apart.
+    fn tape() -> Vec<Trade> {
+        vec![
+            trade(3_000, 100.0, 20.0),
+            trade(-1_000, 100.0, 10.0),
+            trade(1_000, 101.0, 20.0),
+            trade(2_000, 102.0, 10.0),
+            trade(5_000, 99.0, 50.0),
+        ]
+    }
+
+    fn order(id: &str, quantity: u32, side: Side) -> Order {
+        Order::builder()
+            .id(id)
+            .quantity(quantity)
+            .timestamp(Timestamp::from_millis(START))
+            .symbol("AAPL")
+            .side(side)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    fn parent(side: Side) -> ParentOrder {
+        order("parent-1", 100, side).into_parent("slices")
+    }
+
+    fn fill(order_id: &str, price: rust_decimal::Decimal, quantity: u32, offset_ms: u64) -> Fill {
+        Fill::new(
+            order_id.to_string(),
+            format!("exec-{}", offset_ms),
+            price,
+            Quantity::from(quantity),
+            START + offset_ms,
+            dec!(0),
+            Liquidity::Taker,
+        )
+    }
+
+    /// 60 filled at 101 a second in, and 40 at 101.5 three seconds in.
+    fn fills() -> Vec<Fill> {
+        vec![
+            fill("parent-1-1", dec!(101), 60, 1_000),
+            fill("parent-1-2", dec!(101.5), 40, 3_000),
+        ]
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a value");
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_buy_slippage_against_each_benchmark() {
+        let parent = parent(Side::Buy);
+        let children = vec![
+            order("parent-1-1", 60, Side::Buy).into_child("slices", "parent-1", None),
+            order("parent-1-2", 40, Side::Buy).into_child("slices", "parent-1", None),
+        ];
+        let mut state = ParentOrderState::new(parent, children).unwrap();
+        state
+            .on_dispatched("parent-1-1", Timestamp::from_millis(START))
+            .unwrap();
+        state
+            .on_dispatched("parent-1-2", Timestamp::from_millis(START + 2_000))
+            .unwrap();
+
+        let report = BenchmarkCalculator::new(&tape()).report_state(&state, &fills());
+        assert_eq!(report.filled_quantity, 100.0);
+        assert_close(report.average_price, 101.2);
+        assert_eq!(report.end, Timestamp::from_millis(START + 3_000));
+        // Arrival is the trade at 100 before the parent; the VWAP of the trades at 101 x 20,
+        // 102 x 10 and 100 x 20 is 100.8; the price is 100, 101 then 102 for a second each
+        assert_close(report.benchmarks.arrival_price, 100.0);
+        assert_close(report.benchmarks.vwap, 100.8);
+        assert_close(report.benchmarks.twap, 101.0);
+        assert_close(report.slippage.arrival_bps, 120.0);
+        assert_close(report.slippage.vwap_bps, 0.4 / 100.8 * 10_000.0);
+        assert_close(report.slippage.twap_bps, 0.2 / 101.0 * 10_000.0);
+
+        let first = &report.children[0];
+        assert_eq!(first.child_id, "parent-1-1");
+        assert_eq!(first.start, Timestamp::from_millis(START));
+        assert_close(first.benchmarks.vwap, 101.0);
+        assert_close(first.benchmarks.twap, 100.0);
+        assert_close(first.slippage.arrival_bps, 100.0);
+        assert_close(first.slippage.vwap_bps, 0.0);
+        assert_close(first.slippage.twap_bps, 100.0);
+
+        // Dispatched two seconds in: trades at 102 x 10 and 100 x 20, at 102 then 100
+        let second = &report.children[1];
+        assert_eq!(second.average_price, 101.5);
+        assert_close(second.benchmarks.vwap, 302.0 / 3.0);
+        assert_close(second.benchmarks.twap, 102.0);
+        assert_close(second.slippage.arrival_bps, 150.0);
+        assert_close(
+            second.slippage.vwap_bps,
+            (101.5 - 302.0 / 3.0) / (302.0 / 3.0) * 10_000.0,
+        );
+        // Bought below the TWAP: a gain
+        assert_close(second.slippage.twap_bps, -0.5 / 102.0 * 10_000.0);
+    }
+
+    #[test]
+    fn test_sell_slippage_has_the_opposite_sign() {
+        let report = BenchmarkCalculator::new(&tape()).report(&parent(Side::Sell), &fills());
+        assert_close(report.slippage.arrival_bps, -120.0);
+        assert_close(report.slippage.vwap_bps, -0.4 / 100.8 * 10_000.0);
+        // Without dispatch times the children are measured from the parent arrival
+        let second = &report.children[1];
+        assert_eq!(second.start, Timestamp::from_millis(START));
+        assert_close(second.benchmarks.vwap, 100.8);
+        assert_close(second.slippage.arrival_bps, -150.0);
+
+        let json = report.to_string();
+        let decoded: TcaReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, report);
+    }
+
+    #[test]
+    fn test_missing_tape_leaves_out_the_benchmarks_it_cannot_give() {
+        let parent = parent(Side::Buy);
+
+        let report = BenchmarkCalculator::new(&[]).report(&parent, &fills());
+        assert_close(report.average_price, 101.2);
+        assert_eq!(report.benchmarks, Benchmarks::default());
+        assert_eq!(report.slippage, Slippage::default());
+
+        // No trade before the parent: no arrival price unless one is given, while the
+        // trades during the execution still give VWAP and TWAP
+        let during = vec![trade(1_000, 101.0, 20.0), trade(2_000, 102.0, 10.0)];
+        let report = BenchmarkCalculator::new(&during).report(&parent, &fills());
+        assert_eq!(report.benchmarks.arrival_price, None);
+        assert_eq!(report.slippage.arrival_bps, None);
+        assert_close(report.benchmarks.vwap, 304.0 / 3.0);
+        // Priced from the first trade on: 101 for a second, then 102 for a second
+        assert_close(report.benchmarks.twap, 101.5);
+
+        let report = BenchmarkCalculator::new(&during)
+            .with_arrival_price(100.0)
+            .report(&parent, &fills());
+        assert_close(report.slippage.arrival_bps, 120.0);
+
+        let unfilled = BenchmarkCalculator::new(&tape()).report(&parent, &[]);
+        assert_eq!(unfilled.average_price, None);
+        assert_eq!(unfilled.end, unfilled.start);
+        assert_close(unfilled.benchmarks.twap, 100.0);
+        assert_eq!(unfilled.slippage, Slippage::default());
+        assert!(unfilled.children.is_empty());
+    }
+}
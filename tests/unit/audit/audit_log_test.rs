@@ -0,0 +1,335 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod audit_log_tests {
+    use crate::support::{child, order, START};
+    use rust_decimal_macros::dec;
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::{OrderStatus, Side};
+    use strategy_execution_engine::{
+        read_audit_log, AuditError, AuditEvent, AuditLog, AuditRecord, AuditWriter, ChildOrder,
+        Config, ExecutionEngine, Fill, JsonlAuditWriter, Liquidity, MessagingService, MockClient,
+        OrderSplitStrategy, ParentOrder, ParentTimeline, Quantity, RSIStrategy, SignalDecision,
+        SignalStrategy, SimulatedClock, StrategyRegistry, Timestamp,
+    };
+
+    /// A directory of its own for each test, removed afterwards.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("audit-log-{}", uuid::Uuid::now_v7()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Writes the records to a JSONL file and keeps a copy of each as it was emitted.
+    struct Recording {
+        file: JsonlAuditWriter,
+        emitted: Arc<Mutex<Vec<AuditRecord>>>,
+    }
+
+    impl AuditWriter for Recording {
+        fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+            self.emitted.lock().unwrap().push(record.clone());
+            self.file.write(record)
+        }
+
+        fn flush(&mut self) -> Result<(), AuditError> {
+            self.file.flush()
+        }
+    }
+
+    /// Sends a parent in a first slice at once and a second one after a second, buying on
+    /// a fixed signal.
+    struct SignalledSlices;
+
+    impl OrderSplitStrategy for SignalledSlices {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            let parent_id = &parent.order_common.id;
+            vec![
+                child("slices", parent_id, 1, 60, None),
+                child("slices", parent_id, 2, 40, Some(1_000)),
+            ]
+        }
+
+        fn decision(&self) -> Option<SignalDecision> {
+            Some(SignalDecision {
+                ready: true,
+                signal: Some(Side::Buy),
+                indicators: BTreeMap::from([("rsi".to_string(), 25.0)]),
+            })
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    #[tokio::test]
+    async fn test_timeline_matches_the_emitted_sequence() {
+        let dir = TempDir::new();
+        let path = dir.file("audit.jsonl");
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+        let log = AuditLog::start(Recording {
+            file: JsonlAuditWriter::open(&path).unwrap(),
+            emitted: emitted.clone(),
+        });
+
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("slices", |_| Ok(Box::new(SignalledSlices)));
+        let mut config = Config::default();
+        config
+            .strategies
+            .insert("slices".to_string(), serde_json::json!({"slices": 2}));
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let engine = ExecutionEngine::new(
+            config,
+            strategies,
+            MessagingService::with_client(Box::new(client)),
+        )
+        .with_clock(SimulatedClock::new(Timestamp::from_millis(START)))
+        .with_audit(log.sink());
+
+        let first = engine
+            .submit(order("parent-1", 100).into_parent("slices"))
+            .unwrap();
+        let second = engine
+            .submit(order("parent-2", 100).into_parent("slices"))
+            .unwrap();
+        eventually(|| first.dispatches().len() == 1 && second.dispatches().len() == 1).await;
+        first
+            .on_fill(&Fill::new(
+                "parent-1-1".to_string(),
+                "exec-1".to_string(),
+                dec!(10),
+                Quantity::from(60),
+                START,
+                dec!(0),
+                Liquidity::Taker,
+            ))
+            .unwrap();
+        engine.cancel_parent("parent-1").unwrap();
+        engine.cancel_parent("parent-2").unwrap();
+        log.close().unwrap();
+
+        let timeline = ParentTimeline::read(&path, "parent-1").unwrap();
+        let events: Vec<AuditEvent> = timeline.events().cloned().collect();
+        assert_eq!(
+            events,
+            vec![
+                AuditEvent::ParentReceived {
+                    symbol: "AAPL".to_string(),
+                    side: Side::Buy,
+                    quantity: Quantity::from(100),
+                    strategy_id: "slices".to_string(),
+                },
+                AuditEvent::SignalGenerated {
+                    symbol: "AAPL".to_string(),
+                    strategy: "slices".to_string(),
+                    side: Some(Side::Buy),
+                    price: None,
+                    size: None,
+                    reason: None,
+                    indicators: BTreeMap::from([("rsi".to_string(), 25.0)]),
+                },
+                AuditEvent::SplitProduced {
+                    strategy_id: "slices".to_string(),
+                    children: ids(&["parent-1-1", "parent-1-2"]),
+                    config: Some(serde_json::json!({"slices": 2})),
+                    seed: None,
+                },
+                AuditEvent::RiskChecked {
+                    accepted: ids(&["parent-1-1", "parent-1-2"]),
+                    rejected: vec![],
+                    reasons: vec![],
+                },
+                AuditEvent::ChildDispatched {
+                    child_id: "parent-1-1".to_string(),
+                    insert_at: None,
+                    error: None,
+                },
+                AuditEvent::FillApplied {
+                    child_id: "parent-1-1".to_string(),
+                    exec_id: "exec-1".to_string(),
+                    price: dec!(10),
+                    quantity: Quantity::from(60),
+                    status: OrderStatus::Filled,
+                },
+                AuditEvent::Cancelled {
+                    cancelled: ids(&["parent-1-2"]),
+                    pending_cancel: vec![],
+                },
+            ]
+        );
+
+        // The timeline holds the records of the parent exactly as they were emitted
+        let emitted = emitted.lock().unwrap().clone();
+        let expected: Vec<AuditRecord> = emitted
+            .iter()
+            .filter(|record| record.correlation_id.as_deref() == Some("parent-1"))
+            .cloned()
+            .collect();
+        assert_eq!(timeline.records(), expected.as_slice());
+        assert_eq!(read_audit_log(&path).unwrap(), emitted);
+        assert_eq!(
+            emitted
+                .iter()
+                .map(|record| record.sequence)
+                .collect::<Vec<_>>(),
+            (1..=emitted.len() as u64).collect::<Vec<_>>()
+        );
+        assert!(timeline
+            .records()
+            .iter()
+            .all(|record| record.timestamp == Timestamp::from_millis(START)));
+
+        assert_eq!(timeline.children(), ids(&["parent-1-1", "parent-1-2"]));
+        let second_child: Vec<&str> = timeline
+            .child("parent-1-2")
+            .iter()
+            .map(|record| match record.event {
+                AuditEvent::SplitProduced { .. } => "split",
+                AuditEvent::RiskChecked { .. } => "risk",
+                AuditEvent::Cancelled { .. } => "cancelled",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(second_child, vec!["split", "risk", "cancelled"]);
+        assert_eq!(
+            ParentTimeline::read(&path, "parent-2").unwrap().children(),
+            ids(&["parent-2-1", "parent-2-2"])
+        );
+    }
+
+    #[test]
+    fn test_reader_reports_corrupt_lines() {
+        let dir = TempDir::new();
+        let path = dir.file("audit.jsonl");
+        let log = AuditLog::start(JsonlAuditWriter::open(&path).unwrap())
+            .with_clock(SimulatedClock::new(Timestamp::from_millis(START)));
+        let sink = log.sink();
+        sink.emit(
+            Some("parent-1"),
+            AuditEvent::Cancelled {
+                cancelled: ids(&["parent-1-1"]),
+                pending_cancel: vec![],
+            },
+        );
+        log.close().unwrap();
+        // Events sent after the log closed are dropped
+        sink.emit(
+            None,
+            AuditEvent::Cancelled {
+                cancelled: vec![],
+                pending_cancel: vec![],
+            },
+        );
+
+        let records = read_audit_log(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sequence, 1);
+        assert_eq!(records[0].timestamp, Timestamp::from_millis(START));
+
+        // A line cut short, as a crash mid-write would leave it
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "{{\"sequence\":2,\"timest").unwrap();
+        assert!(matches!(
+            read_audit_log(&path),
+            Err(AuditError::Corrupt { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_write_failures_are_reported_on_flush() {
+        let log = AuditLog::start(|record: &AuditRecord| {
+            Err(AuditError::Encoding {
+                sequence: record.sequence,
+                reason: "full".to_string(),
+            })
+        });
+        log.sink().emit(
+            None,
+            AuditEvent::Cancelled {
+                cancelled: vec![],
+                pending_cancel: vec![],
+            },
+        );
+        assert_eq!(
+            log.flush(),
+            Err(AuditError::Encoding {
+                sequence: 1,
+                reason: "full".to_string(),
+            })
+        );
+        assert_eq!(log.failures(), 1);
+        assert_eq!(log.flush(), Ok(()));
+    }
+
+    #[test]
+    fn test_rsi_decision_carries_its_indicator() {
+        let mut strategy = RSIStrategy::new(3, 70.0, 30.0);
+        for price in [10.0, 9.0, 8.0, 7.0, 6.0] {
+            strategy.add_price(price);
+        }
+        let decision = SignalDecision::of(&strategy);
+        assert!(decision.ready);
+        assert_eq!(decision.signal, strategy.signal());
+        assert_eq!(decision.indicators.keys().collect::<Vec<_>>(), vec!["rsi"]);
+        assert_eq!(strategy.decision(), Some(decision));
+    }
+}
@@ -0,0 +1,372 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod backtester_tests {
+    use rust_decimal_macros::dec;
+    use std::time::{Duration, SystemTime};
+    use strategy_execution_engine::models::market_data::Candle;
+    use strategy_execution_engine::models::orders::{Order, OrderType, Side};
+    use strategy_execution_engine::strategies::market_microstructure_based::AdverseSelectionStrategy;
+    use strategy_execution_engine::{
+        BacktestConfig, BacktestReport, Backtester, ChildOrder, Decimal, FillModel, MarketData,
+        OrderSplitStrategy, ParentOrder, SignalStrategy, SplitSeeds, Timestamp,
+    };
+
+    const START: u64 = 1_622_512_800_000;
+
+    fn time(minute: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(START + minute * 60_000)
+    }
+
+    /// One-minute candles from `(open, high, low, close, volume)`.
+    fn candles(prices: &[(f64, f64, f64, f64, f64)]) -> Vec<Candle> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &(open, high, low, close, volume))| Candle {
+                timestamp: time(i as u64),
+                open,
+                high,
+                low,
+                close,
+                volume,
+            })
+            .collect()
+    }
+
+    /// Candles with the given opens and closes.
+    fn series(prices: &[(f64, f64)]) -> Vec<Candle> {
+        let prices: Vec<_> = prices
+            .iter()
+            .map(|&(open, close)| {
+                (
+                    open,
+                    open.max(close) + 1.0,
+                    open.min(close) - 1.0,
+                    close,
+                    1_000.0,
+                )
+            })
+            .collect();
+        candles(&prices)
+    }
+
+    /// Gives the scripted signal of each candle it has seen.
+    struct Script {
+        signals: Vec<Option<Side>>,
+        seen: usize,
+    }
+
+    impl Script {
+        fn new(signals: Vec<Option<Side>>) -> Self {
+            Script { signals, seen: 0 }
+        }
+    }
+
+    impl SignalStrategy for Script {
+        fn update(&mut self, _data: &MarketData) {
+            self.seen += 1;
+        }
+
+        fn signal(&self) -> Option<Side> {
+            self.signals.get(self.seen - 1).cloned().flatten()
+        }
+
+        fn is_ready(&self) -> bool {
+            self.seen > 0
+        }
+
+        fn reset(&mut self) {
+            self.seen = 0;
+        }
+    }
+
+    fn backtester(quantity: f64, allow_short: bool, fill: FillModel) -> Backtester {
+        Backtester::new(BacktestConfig {
+            initial_capital: 10_000.0,
+            quantity,
+            allow_short,
+            fill,
+            ..BacktestConfig::default()
+        })
+    }
+
+    /// A winning then a losing long trade over six candles.
+    fn round_trips(backtester: &Backtester) -> BacktestReport {
+        let candles = series(&[
+            (100.0, 101.0),
+            (102.0, 104.0),
+            (105.0, 103.0),
+            (103.0, 106.0),
+            (107.0, 108.0),
+            (106.0, 105.0),
+        ]);
+        let mut script = Script::new(vec![
+            Some(Side::Buy),
+            None,
+            Some(Side::Sell),
+            Some(Side::Buy),
+            Some(Side::Sell),
+            None,
+        ]);
+        backtester.run_signal(&mut script, &candles)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_signals_fill_at_next_open() {
+        let report = round_trips(&backtester(10.0, false, FillModel::default()));
+
+        assert_eq!(report.trades.len(), 2);
+        let first = &report.trades[0];
+        assert_eq!(first.side, Side::Buy);
+        assert_eq!((first.entry_time, first.exit_time), (time(1), time(3)));
+        assert_close(first.entry_price, 102.0);
+        assert_close(first.exit_price, 103.0);
+        assert_close(first.pnl, 10.0);
+        let second = &report.trades[1];
+        assert_eq!((second.entry_time, second.exit_time), (time(4), time(5)));
+        assert_close(second.pnl, -10.0);
+
+        let expected = [10_000.0, 10_020.0, 10_010.0, 10_010.0, 10_020.0, 10_000.0];
+        assert_eq!(report.equity_curve.len(), expected.len());
+        for (actual, expected) in report.equity_curve.iter().zip(expected) {
+            assert_close(*actual, expected);
+        }
+        assert_close(report.win_rate, 0.5);
+        assert_close(report.total_return, 0.0);
+        assert_close(report.max_drawdown, 20.0 / 10_020.0);
+        assert_close(report.final_equity, 10_000.0);
+    }
+
+    #[test]
+    fn test_slippage_and_commission_cost_each_fill() {
+        let fill = FillModel {
+            slippage_bps: 10.0,
+            commission: 1.0,
+            ..FillModel::default()
+        };
+        let report = round_trips(&backtester(10.0, false, fill));
+
+        let first = &report.trades[0];
+        assert_close(first.entry_price, 102.102);
+        assert_close(first.exit_price, 102.897);
+        assert_close(first.commission, 2.0);
+        assert_close(first.pnl, 5.95);
+        let second = &report.trades[1];
+        assert_close(second.entry_price, 107.107);
+        assert_close(second.exit_price, 105.894);
+        assert_close(second.pnl, -14.13);
+        assert_close(report.final_equity, 10_000.0 + 5.95 - 14.13);
+        assert_close(report.total_return, (5.95 - 14.13) / 10_000.0);
+    }
+
+    #[test]
+    fn test_shorts_and_flips_when_allowed() {
+        let candles = series(&[(100.0, 101.0), (102.0, 104.0), (105.0, 103.0)]);
+        let signals = vec![Some(Side::Buy), Some(Side::Sell), None];
+
+        let report = backtester(10.0, true, FillModel::default())
+            .run_signal(&mut Script::new(signals.clone()), &candles);
+        assert_eq!(report.trades.len(), 1);
+        assert_close(report.trades[0].pnl, 30.0);
+        // The short opened at 105 is marked at the close of 103
+        assert_close(report.final_equity, 10_050.0);
+
+        let long_only = backtester(10.0, false, FillModel::default())
+            .run_signal(&mut Script::new(signals), &candles);
+        assert_eq!(long_only.trades.len(), 1);
+        assert_close(long_only.final_equity, 10_030.0);
+    }
+
+    #[test]
+    fn test_runs_are_deterministic_and_serialize() {
+        let backtester = backtester(10.0, false, FillModel::default());
+        let report = round_trips(&backtester);
+        assert_eq!(report, round_trips(&backtester));
+        assert!(report.sharpe.is_finite());
+
+        let json = report.print_as_json().unwrap();
+        let parsed: BacktestReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+
+        let idle =
+            backtester.run_signal(&mut Script::new(vec![None; 3]), &series(&[(1.0, 2.0); 3]));
+        assert!(idle.trades.is_empty());
+        assert_eq!(
+            (idle.win_rate, idle.sharpe, idle.max_drawdown),
+            (0.0, 0.0, 0.0)
+        );
+        assert_close(idle.total_return, 0.0);
+    }
+
+    /// Splits a parent of 100 into a market child of 50 now and a child of 50 limited at
+    /// 101 a minute later.
+    struct TwoSlices;
+
+    impl OrderSplitStrategy for TwoSlices {
+        fn split(&self, parent_order: &ParentOrder) -> Vec<ChildOrder> {
+            let child = |id: &str, minute: u64, limit: Option<Decimal>| {
+                let builder = Order::builder()
+                    .id(id)
+                    .quantity(50)
+                    .timestamp(Timestamp::from_millis(START))
+                    .symbol("AAPL")
+                    .side(Side::Buy)
+                    .currency("USD");
+                match limit {
+                    Some(limit) => builder.order_type(OrderType::Limit).price(limit),
+                    None => builder,
+                }
+                .build()
+                .unwrap()
+                .into_child(
+                    "two-slices",
+                    parent_order.order_common.id.clone(),
+                    Some(Timestamp::from_millis(START + minute * 60_000)),
+                )
+            };
+            vec![
+                child("child-1", 0, None),
+                child("child-2", 1, Some(dec!(101))),
+            ]
+        }
+    }
+
+    fn parent() -> ParentOrder {
+        Order::builder()
+            .id("parent-1")
+            .quantity(100)
+            .timestamp(Timestamp::from_millis(START))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_parent("two-slices")
+    }
+
+    fn volume_candles() -> Vec<Candle> {
+        candles(&[
+            (100.0, 101.0, 99.0, 100.0, 300.0),
+            (102.0, 103.0, 101.0, 102.0, 300.0),
+            (101.0, 102.0, 100.0, 101.0, 300.0),
+            (100.0, 101.0, 99.0, 100.0, 200.0),
+        ])
+    }
+
+    #[test]
+    fn test_split_children_execute_against_candle_volume() {
+        let fill = FillModel {
+            commission: 1.0,
+            ..FillModel::default()
+        };
+        let report =
+            backtester(1.0, false, fill).run_split(&parent(), &TwoSlices, &volume_candles());
+
+        let fills = |index: usize| -> Vec<(f64, f64)> {
+            report.children[index]
+                .fills
+                .iter()
+                .map(|fill| (fill.quantity, fill.price))
+                .collect()
+        };
+        // 10% of 300 is 30 per candle, shared in split order
+        assert_eq!(fills(0), vec![(30.0, 100.0), (20.0, 102.0)]);
+        assert_eq!(fills(1), vec![(10.0, 101.0), (30.0, 101.0), (10.0, 100.0)]);
+        assert_close(report.children[0].average_price, 100.8);
+        assert_close(report.children[1].average_price, 100.8);
+
+        assert_close(report.filled_quantity, 100.0);
+        assert_close(report.fill_rate, 1.0);
+        assert_close(report.average_price, 100.8);
+        assert_close(report.arrival_price, 100.0);
+        assert_close(report.shortfall_bps, 80.0);
+        assert_close(report.vwap, 110_900.0 / 1_100.0);
+        assert_close(report.commission, 5.0);
+    }
+
+    #[test]
+    fn test_split_leaves_unfilled_remainder() {
+        let candles = &volume_candles()[..2];
+        let report =
+            backtester(1.0, false, FillModel::default()).run_split(&parent(), &TwoSlices, candles);
+
+        assert_close(report.children[0].filled_quantity, 50.0);
+        assert_close(report.children[1].filled_quantity, 10.0);
+        assert_close(report.fill_rate, 0.6);
+        assert_eq!(
+            report,
+            backtester(1.0, false, FillModel::default()).run_split(&parent(), &TwoSlices, candles)
+        );
+    }
+
+    #[test]
+    fn test_split_report_records_strategy_seed() {
+        let backtester = backtester(1.0, false, FillModel::default());
+        let candles = volume_candles();
+        assert_eq!(
+            backtester.run_split(&parent(), &TwoSlices, &candles).seed,
+            None
+        );
+
+        // The seed the split drew from the generator seeded with 11
+        let strategy = AdverseSelectionStrategy::new(None).with_seed(11);
+        let report = backtester.run_split(&parent(), &strategy, &candles);
+        let seed = SplitSeeds::new(11).next();
+        assert_eq!(report.seed, Some(seed));
+        assert!(report
+            .print_as_json()
+            .unwrap()
+            .contains(&format!("\"seed\": {}", seed)));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_candles_from_csv() {
+        let csv = "timestamp,open,high,low,close,volume\n\
+                   1622512800000,100,101,99,100.5,300\n\
+                   1622512860000,100.5,102,100,101,250\n";
+        let candles = strategy_execution_engine::candles_from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[1].timestamp, time(1));
+        assert_eq!(candles[1].close, 101.0);
+
+        assert!(
+            strategy_execution_engine::candles_from_csv("timestamp,open\n1,x\n".as_bytes())
+                .is_err()
+        );
+    }
+}
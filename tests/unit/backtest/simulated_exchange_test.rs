@@ -0,0 +1,329 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod simulated_exchange_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{
+        Order, OrderStatus, OrderType, Side, TimeInForce,
+    };
+    use strategy_execution_engine::{
+        ChildOrder, Decimal, ExchangeEvent, Liquidity, OrderBook, Quantity, SimulatedExchange,
+        SimulatedExchangeConfig, SimulatedExchangeError, Timestamp,
+    };
+
+    const START: u64 = 1_622_512_800_000;
+
+    fn at(millis: u64) -> Timestamp {
+        Timestamp::from_millis(START + millis)
+    }
+
+    fn child(id: &str, side: Side, quantity: u32, limit: Option<Decimal>) -> ChildOrder {
+        let builder = Order::builder()
+            .id(id)
+            .quantity(quantity)
+            .timestamp(at(0))
+            .symbol("AAPL")
+            .side(side)
+            .currency("USD");
+        match limit {
+            Some(limit) => builder.order_type(OrderType::Limit).price(limit),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+        .into_child("paper", "parent-1", None)
+    }
+
+    fn book(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBook {
+        OrderBook {
+            bids: bids.to_vec(),
+            asks: asks.to_vec(),
+        }
+    }
+
+    /// Fills as `(price, quantity, liquidity)`.
+    fn fills(events: &[ExchangeEvent]) -> Vec<(Decimal, Quantity, Liquidity)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ExchangeEvent::Fill(fill) => Some((fill.price, fill.quantity, fill.liquidity)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn statuses(events: &[ExchangeEvent]) -> Vec<OrderStatus> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ExchangeEvent::Status { status, .. } => Some(*status),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn exchange(config: SimulatedExchangeConfig) -> SimulatedExchange {
+        let mut exchange = SimulatedExchange::new(config);
+        exchange.on_book(
+            "AAPL",
+            &book(
+                &[(99.0, 40.0), (98.0, 60.0)],
+                &[(100.0, 50.0), (101.0, 70.0), (102.0, 100.0)],
+            ),
+            at(0),
+        );
+        exchange
+    }
+
+    #[test]
+    fn test_market_order_walks_the_book() {
+        let mut exchange = exchange(SimulatedExchangeConfig::default());
+        exchange
+            .submit(child("child-1", Side::Buy, 150, None), at(0))
+            .unwrap();
+
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![
+                (dec!(100), Quantity::from(50), Liquidity::Taker),
+                (dec!(101), Quantity::from(70), Liquidity::Taker),
+                (dec!(102), Quantity::from(30), Liquidity::Taker),
+            ]
+        );
+        assert_eq!(
+            statuses(&events),
+            vec![
+                OrderStatus::New,
+                OrderStatus::PartiallyFilled,
+                OrderStatus::Filled
+            ]
+        );
+        let order = &exchange.order("child-1").unwrap().order_common;
+        assert_eq!(order.filled_quantity, Quantity::from(150));
+        // (50 * 100 + 70 * 101 + 30 * 102) / 150
+        assert_eq!(order.avg_fill_price, Some(dec!(15130) / dec!(150)));
+        assert_eq!(exchange.book("AAPL").unwrap().asks, vec![(102.0, 70.0)]);
+    }
+
+    #[test]
+    fn test_market_order_beyond_the_book_is_cancelled() {
+        let mut exchange = exchange(SimulatedExchangeConfig::default());
+        exchange
+            .submit(child("child-1", Side::Sell, 150, None), at(0))
+            .unwrap();
+
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![
+                (dec!(99), Quantity::from(40), Liquidity::Taker),
+                (dec!(98), Quantity::from(60), Liquidity::Taker),
+            ]
+        );
+        assert_eq!(exchange.status("child-1"), Some(OrderStatus::Cancelled));
+        assert_eq!(
+            exchange
+                .order("child-1")
+                .unwrap()
+                .order_common
+                .filled_quantity,
+            Quantity::from(100)
+        );
+        assert!(exchange.book("AAPL").unwrap().bids.is_empty());
+    }
+
+    #[test]
+    fn test_passive_limit_fills_only_when_crossed() {
+        let mut exchange = exchange(SimulatedExchangeConfig::default());
+        exchange
+            .submit(child("child-1", Side::Buy, 60, Some(dec!(99.5))), at(0))
+            .unwrap();
+        assert_eq!(statuses(&exchange.drain_events()), vec![OrderStatus::New]);
+
+        exchange.on_book("AAPL", &book(&[(99.0, 40.0)], &[(99.75, 80.0)]), at(10));
+        assert!(exchange.drain_events().is_empty());
+
+        exchange.on_book(
+            "AAPL",
+            &book(
+                &[(99.0, 40.0)],
+                &[(99.25, 30.0), (99.5, 20.0), (100.0, 50.0)],
+            ),
+            at(20),
+        );
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![(dec!(99.5), Quantity::from(50), Liquidity::Maker)]
+        );
+        assert_eq!(statuses(&events), vec![OrderStatus::PartiallyFilled]);
+        assert_eq!(exchange.book("AAPL").unwrap().asks, vec![(100.0, 50.0)]);
+
+        exchange.on_level("AAPL", &Side::Sell, 99.5, 25.0, at(30));
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![(dec!(99.5), Quantity::from(10), Liquidity::Maker)]
+        );
+        assert_eq!(statuses(&events), vec![OrderStatus::Filled]);
+        assert_eq!(
+            exchange.book("AAPL").unwrap().asks,
+            vec![(99.5, 15.0), (100.0, 50.0)]
+        );
+    }
+
+    #[test]
+    fn test_marketable_limit_takes_then_rests() {
+        let mut exchange = exchange(SimulatedExchangeConfig::default());
+        exchange
+            .submit(child("child-1", Side::Buy, 80, Some(dec!(100))), at(0))
+            .unwrap();
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![(dec!(100), Quantity::from(50), Liquidity::Taker)]
+        );
+        assert_eq!(
+            exchange.status("child-1"),
+            Some(OrderStatus::PartiallyFilled)
+        );
+
+        let mut ioc = child("child-2", Side::Sell, 80, Some(dec!(99)));
+        ioc.order_common.timeinforce = Some(TimeInForce::IOC);
+        exchange.submit(ioc, at(0)).unwrap();
+        assert_eq!(
+            fills(&exchange.drain_events()),
+            vec![(dec!(99), Quantity::from(40), Liquidity::Taker)]
+        );
+        assert_eq!(exchange.status("child-2"), Some(OrderStatus::Cancelled));
+
+        exchange.cancel("child-1", at(5)).unwrap();
+        assert_eq!(
+            statuses(&exchange.drain_events()),
+            vec![OrderStatus::Cancelled]
+        );
+        exchange.on_book("AAPL", &book(&[], &[(95.0, 100.0)]), at(10));
+        assert!(exchange.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_latency_delays_arrival() {
+        let mut exchange = exchange(SimulatedExchangeConfig {
+            latency_ms: 100,
+            ..SimulatedExchangeConfig::default()
+        });
+        exchange
+            .submit(child("child-1", Side::Buy, 20, None), at(0))
+            .unwrap();
+        assert!(exchange.drain_events().is_empty());
+
+        exchange.on_book("AAPL", &book(&[], &[(103.0, 100.0)]), at(50));
+        assert!(exchange.drain_events().is_empty());
+
+        exchange.advance(at(100));
+        let events = exchange.drain_events();
+        assert_eq!(
+            fills(&events),
+            vec![(dec!(103), Quantity::from(20), Liquidity::Taker)]
+        );
+        match &events[1] {
+            ExchangeEvent::Fill(fill) => assert_eq!(fill.timestamp, START + 100),
+            _ => panic!("expected a fill"),
+        }
+    }
+
+    #[test]
+    fn test_fill_probability_haircut() {
+        let resting = |config: SimulatedExchangeConfig| {
+            let mut exchange = exchange(config);
+            exchange
+                .submit(child("child-1", Side::Buy, 1, Some(dec!(99.5))), at(0))
+                .unwrap();
+            for i in 1..=20 {
+                exchange.on_book("AAPL", &book(&[], &[(99.0, 1.0)]), at(i));
+            }
+            exchange.drain_events().len()
+        };
+
+        let never = SimulatedExchangeConfig {
+            fill_probability: 0.0,
+            ..SimulatedExchangeConfig::default()
+        };
+        assert_eq!(resting(never), 1);
+        // New, the fill and Filled on the first crossing update
+        assert_eq!(resting(SimulatedExchangeConfig::default()), 3);
+
+        let coin = SimulatedExchangeConfig {
+            fill_probability: 0.5,
+            seed: 7,
+            ..SimulatedExchangeConfig::default()
+        };
+        assert_eq!(resting(coin), resting(coin));
+    }
+
+    #[test]
+    fn test_rejected_submissions_and_cancels() {
+        let mut exchange = exchange(SimulatedExchangeConfig::default());
+        exchange
+            .submit(child("child-1", Side::Buy, 10, None), at(0))
+            .unwrap();
+        assert_eq!(
+            exchange.submit(child("child-1", Side::Buy, 10, None), at(0)),
+            Err(SimulatedExchangeError::DuplicateOrder {
+                order_id: "child-1".to_string()
+            })
+        );
+
+        let mut stop = child("child-2", Side::Buy, 10, None);
+        stop.order_common.order_type = OrderType::Stop {
+            stop_price: dec!(105),
+        };
+        assert_eq!(
+            exchange.submit(stop, at(0)),
+            Err(SimulatedExchangeError::UnsupportedOrderType {
+                order_id: "child-2".to_string()
+            })
+        );
+
+        assert_eq!(
+            exchange.cancel("child-1", at(1)),
+            Err(SimulatedExchangeError::OrderClosed {
+                order_id: "child-1".to_string(),
+                status: OrderStatus::Filled
+            })
+        );
+        assert_eq!(
+            exchange.cancel("child-3", at(1)),
+            Err(SimulatedExchangeError::UnknownOrder {
+                order_id: "child-3".to_string()
+            })
+        );
+    }
+}
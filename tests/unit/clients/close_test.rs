@@ -0,0 +1,152 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+#[cfg(test)]
+mod close_tests {
+    use crate::support::service;
+    use futures_util::StreamExt;
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{ClientError, MessagingClient, MockClient};
+
+    /// Longer than a subscription or stream worker takes to notice anything.
+    const SETTLE: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn test_close_waits_for_pending_produce() {
+        let (service, client) = service();
+        client.set_latency(Duration::from_millis(200));
+
+        std::thread::scope(|scope| {
+            let producing = scope.spawn(|| service.produce("orders", "order-1"));
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(client.sent().is_empty());
+
+            service.close(Duration::from_secs(2)).unwrap();
+            // The produce running when close was called went out before close returned
+            assert_eq!(client.sent().len(), 1);
+            assert_eq!(client.sent()[0].payload, b"order-1");
+            assert_eq!(producing.join().unwrap(), Ok(()));
+        });
+        assert_eq!(service.metrics().messages_produced, 1);
+    }
+
+    #[test]
+    fn test_close_gives_up_after_timeout() {
+        let (service, client) = service();
+        client.set_latency(Duration::from_millis(500));
+
+        std::thread::scope(|scope| {
+            let producing = scope.spawn(|| service.produce("orders", "order-1"));
+            std::thread::sleep(Duration::from_millis(50));
+
+            let result = service.close(Duration::from_millis(50));
+            assert!(
+                matches!(result, Err(ClientError::Transport(_))),
+                "{result:?}"
+            );
+            // The service refuses new calls even though the close did not complete
+            assert_eq!(
+                service.produce("orders", "order-2"),
+                Err(ClientError::Closed)
+            );
+            assert_eq!(producing.join().unwrap(), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_calls_after_close_fail() {
+        let (service, client) = service();
+        client.produce("orders", "order-1").unwrap();
+        service.close(Duration::from_secs(1)).unwrap();
+
+        assert_eq!(
+            service.produce("orders", "order-2"),
+            Err(ClientError::Closed)
+        );
+        assert_eq!(service.consume("orders"), Err(ClientError::Closed));
+        assert_eq!(
+            service.request("orders", "order-3", Duration::from_millis(10)),
+            Err(ClientError::Closed)
+        );
+        assert_eq!(service.health().err(), Some(ClientError::Closed));
+        assert!(matches!(
+            service.subscribe("orders", Box::new(|_| {})),
+            Err(ClientError::Closed)
+        ));
+        // The client itself was closed too, and its clones with it
+        assert_eq!(
+            client.consume_timeout("orders", Duration::ZERO),
+            Err(ClientError::Closed)
+        );
+        // Closing again does nothing
+        assert_eq!(service.close(Duration::from_secs(1)), Ok(()));
+        // Refused calls are not counted as errors
+        let metrics = service.metrics();
+        assert_eq!(metrics.produce_errors, 0);
+        assert_eq!(metrics.consume_errors, 0);
+    }
+
+    #[test]
+    fn test_close_wakes_waiting_consume() {
+        let client = MockClient::new().with_timeout(Duration::from_secs(5));
+        let started = Instant::now();
+        std::thread::scope(|scope| {
+            let waiting = scope.spawn(|| client.consume("orders"));
+            std::thread::sleep(Duration::from_millis(50));
+
+            client.close(Duration::from_secs(1)).unwrap();
+            assert_eq!(waiting.join().unwrap(), Err(ClientError::Closed));
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_close_ends_subscriptions_and_streams() {
+        let (service, _client) = service();
+        let subscription = service.subscribe("orders", Box::new(|_| {})).unwrap();
+        let mut stream = service.consume_stream("fills");
+
+        service.close(Duration::from_secs(1)).unwrap();
+        tokio::time::sleep(SETTLE).await;
+        assert!(!subscription.is_active());
+        assert_eq!(stream.next().await, Some(Err(ClientError::Closed)));
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[test]
+    fn test_dropped_service_closes_its_client() {
+        let (service, client) = service();
+        service.produce("orders", "order-1").unwrap();
+        drop(service);
+
+        assert_eq!(client.pending("orders"), 1);
+        assert_eq!(
+            client.produce("orders", "order-2"),
+            Err(ClientError::Closed)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod compression_tests {
+    use crate::support::instant_service;
+    use strategy_execution_engine::{
+        ClientError, Compression, CompressionConfig, MessageEnvelope, MockClient, OrderBook,
+        CONTENT_ENCODING_HEADER,
+    };
+
+    /// A synthetic snapshot of `levels` price levels a side, about 15 bytes of JSON a level.
+    fn order_book(levels: usize) -> OrderBook {
+        let level = |i: usize| (100.0 + i as f64 * 0.01, 1.0 + (i % 7) as f64 * 100.0);
+        OrderBook {
+            bids: (0..levels).map(level).collect(),
+            asks: (0..levels).map(|i| level(levels + i)).collect(),
+        }
+    }
+
+    /// The envelope as it went over the wire.
+    fn sent_envelope(client: &MockClient, index: usize) -> MessageEnvelope {
+        serde_json::from_slice(&client.sent()[index].payload).unwrap()
+    }
+
+    #[test]
+    fn test_no_compression_by_default() {
+        let (service, client) = instant_service();
+        let book = order_book(500);
+        service.produce_order_book("books", &book, "AAPL").unwrap();
+
+        let envelope = sent_envelope(&client, 0);
+        assert!(!envelope.headers.contains_key(CONTENT_ENCODING_HEADER));
+        assert_eq!(envelope.message_type, "order_book");
+        assert_eq!(service.consume_order_book("books"), Ok(book));
+        assert!(!CompressionConfig::default().applies_to(usize::MAX));
+    }
+
+    #[test]
+    fn test_unsupported_encoding_is_rejected() {
+        let envelope = MessageEnvelope::wrap(&order_book(1), "AAPL")
+            .unwrap()
+            .with_header(CONTENT_ENCODING_HEADER, "brotli");
+        assert_eq!(
+            envelope.open::<OrderBook>(),
+            Err(ClientError::InvalidPayload(
+                "unsupported content encoding brotli".to_string()
+            ))
+        );
+        // Naming no compression is the same as not naming one
+        let envelope = envelope.with_header(CONTENT_ENCODING_HEADER, "identity");
+        assert_eq!(envelope.open::<OrderBook>(), Ok(order_book(1)));
+        assert_eq!(Compression::None.pack("{}"), Ok("{}".to_string()));
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn round_trip(compression: Compression) {
+        let (mut service, client) = instant_service();
+        service.set_compression("books", CompressionConfig::new(compression));
+        let book = order_book(1000);
+        let small = order_book(2);
+        service.produce_order_book("books", &book, "AAPL").unwrap();
+        service.produce_order_book("books", &small, "AAPL").unwrap();
+
+        let envelope = sent_envelope(&client, 0);
+        assert_eq!(
+            envelope.headers[CONTENT_ENCODING_HEADER],
+            compression.as_str()
+        );
+        let json = serde_json::to_string(&book).unwrap();
+        assert!(json.len() > 20_000);
+        assert!(
+            envelope.payload.len() < json.len() / 3,
+            "{}",
+            envelope.payload.len()
+        );
+        assert_eq!(envelope.payload_json().unwrap(), json);
+
+        // Below the threshold the payload goes as it is
+        let envelope = sent_envelope(&client, 1);
+        assert!(!envelope.headers.contains_key(CONTENT_ENCODING_HEADER));
+        assert_eq!(envelope.payload, serde_json::to_string(&small).unwrap());
+
+        // Compressed and plain messages share the topic; each is read by its header
+        assert_eq!(service.consume_order_book("books"), Ok(book));
+        assert_eq!(service.consume_order_book("books"), Ok(small));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_round_trip() {
+        round_trip(Compression::Gzip);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_round_trip() {
+        round_trip(Compression::Zstd);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_threshold_and_corrupt_payload() {
+        let config = CompressionConfig {
+            compression: Compression::Gzip,
+            threshold: 100,
+        };
+        assert!(!config.applies_to(99));
+        assert!(config.applies_to(100));
+
+        let envelope = MessageEnvelope::wrap(&order_book(1), "AAPL")
+            .unwrap()
+            .with_header(CONTENT_ENCODING_HEADER, "gzip");
+        assert!(matches!(
+            envelope.open::<OrderBook>(),
+            Err(ClientError::InvalidPayload(_))
+        ));
+    }
+}
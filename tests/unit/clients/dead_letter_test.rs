@@ -0,0 +1,152 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod dead_letter_tests {
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ClientError, DeadLetter, DeadLetterPolicy, MessageEnvelope, MessagingService, MockClient,
+        Order, OrderType, ParentOrder, Side, Timestamp,
+    };
+
+    fn service(policy: Option<DeadLetterPolicy>) -> MessagingService {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut service = MessagingService::with_client(Box::new(client));
+        if let Some(policy) = policy {
+            service.set_dead_letter_policy(policy);
+        }
+        service
+    }
+
+    fn parent_order(strategy_id: &str) -> ParentOrder {
+        Order::builder()
+            .id("parent1")
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_parent(strategy_id)
+    }
+
+    #[test]
+    fn test_malformed_parent_order_is_dead_lettered() {
+        let service = service(Some(DeadLetterPolicy::default()));
+        service
+            .produce("parent_orders", "{\"not\": \"an order\"}")
+            .unwrap();
+        service
+            .produce_parent_order("parent_orders", &parent_order("strategy1"))
+            .unwrap();
+
+        // The bad message is set aside and the next one returned
+        let received = service.consume_parent_order("parent_orders").unwrap();
+        assert_eq!(received.order_common.id, "parent1");
+
+        let letter: DeadLetter = service.consume_json("parent_orders.dlq").unwrap();
+        assert_eq!(letter.source_topic, "parent_orders");
+        assert_eq!(letter.payload, "{\"not\": \"an order\"}");
+        assert!(
+            letter.error.contains("missing field `message_type`"),
+            "{}",
+            letter.error
+        );
+        assert!(letter.failed_at > Timestamp::from_secs(1_700_000_000));
+        assert_eq!(service.metrics().dead_lettered, 1);
+    }
+
+    #[test]
+    fn test_invalid_order_is_dead_lettered() {
+        let service = service(Some(DeadLetterPolicy {
+            topic_pattern: "dead-letters.{topic}".to_string(),
+            ..DeadLetterPolicy::default()
+        }));
+        // Sent without the validation `produce_parent_order` does
+        let envelope = MessageEnvelope::wrap(&parent_order(""), "parent1").unwrap();
+        service
+            .produce_envelope("parent_orders", &envelope)
+            .unwrap();
+        service
+            .produce_parent_order("parent_orders", &parent_order("strategy1"))
+            .unwrap();
+
+        assert!(service.consume_parent_order("parent_orders").is_ok());
+        let letter: DeadLetter = service.consume_json("dead-letters.parent_orders").unwrap();
+        assert!(letter.error.contains("Strategy ID"), "{}", letter.error);
+        let rejected: MessageEnvelope = serde_json::from_str(&letter.payload).unwrap();
+        assert_eq!(rejected, envelope);
+    }
+
+    #[test]
+    fn test_dead_letters_are_rate_limited() {
+        let service = service(Some(DeadLetterPolicy {
+            max_per_second: 1,
+            ..DeadLetterPolicy::default()
+        }));
+        for i in 0..2 {
+            service
+                .produce("parent_orders", &format!("garbage-{i}"))
+                .unwrap();
+        }
+
+        // Over the limit the bad message comes back as an error, and is not retried
+        let error = service.consume_parent_order("parent_orders").unwrap_err();
+        assert!(
+            matches!(error, ClientError::Deserialization { .. }),
+            "{}",
+            error
+        );
+        assert_eq!(service.metrics().dead_lettered, 1);
+        let letter: DeadLetter = service.consume_json("parent_orders.dlq").unwrap();
+        assert_eq!(letter.payload, "garbage-0");
+        assert!(service.try_consume("parent_orders.dlq").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_without_policy_errors_are_returned() {
+        let service = service(None);
+        service.produce("parent_orders", "garbage").unwrap();
+
+        assert!(matches!(
+            service.consume_parent_order("parent_orders"),
+            Err(ClientError::Deserialization { .. })
+        ));
+        assert!(service.try_consume("parent_orders.dlq").unwrap().is_none());
+        assert_eq!(service.metrics().dead_lettered, 0);
+        // Transport errors are never dead-lettered
+        let service = self::service(Some(DeadLetterPolicy::default()));
+        assert!(matches!(
+            service.consume_parent_order("parent_orders"),
+            Err(ClientError::Timeout { .. })
+        ));
+        assert_eq!(service.metrics().dead_lettered, 0);
+    }
+}
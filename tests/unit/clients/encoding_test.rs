@@ -0,0 +1,137 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod encoding_tests {
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ClientError, Encoding, MessagingClient, MessagingService, Order, OrderType, ParentOrder,
+        Side, Timestamp,
+    };
+
+    /// A text-only transport that hands back a plain text message.
+    struct TextClient;
+
+    impl MessagingClient for TextClient {
+        fn produce(&self, _topic: &str, _message: &str) -> Result<(), ClientError> {
+            Ok(())
+        }
+
+        fn consume(&self, _topic: &str) -> Result<String, ClientError> {
+            Ok("text message".to_string())
+        }
+
+        fn consume_timeout(
+            &self,
+            _topic: &str,
+            _timeout: Duration,
+        ) -> Result<Option<String>, ClientError> {
+            Ok(Some("text message".to_string()))
+        }
+    }
+
+    fn service() -> MessagingService {
+        MessagingService::with_client(Box::new(TextClient))
+    }
+
+    fn parent_order() -> ParentOrder {
+        Order::builder()
+            .id("parent1")
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_parent("strategy1")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_topics_default_to_json() {
+        let service = service();
+        assert_eq!(service.encoding("orders"), Encoding::Json);
+        assert!(service.produce_encoded("orders", &parent_order()).is_ok());
+    }
+
+    #[test]
+    fn test_json_matches_display() {
+        let parent_order = parent_order();
+        let payload = Encoding::Json.encode(&parent_order).unwrap();
+        assert_eq!(payload, parent_order.to_string().into_bytes());
+        let decoded: ParentOrder = Encoding::Json.decode(&payload).unwrap();
+        assert_eq!(decoded.to_string(), parent_order.to_string());
+    }
+
+    #[test]
+    fn test_consume_decoded_reports_bad_payload() {
+        let service = service();
+        assert!(service.consume_decoded::<ParentOrder>("orders").is_err());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_encoding_per_topic() {
+        let mut service = service();
+        service.set_encoding("child_orders", Encoding::MsgPack);
+        assert_eq!(service.encoding("child_orders"), Encoding::MsgPack);
+        assert_eq!(service.encoding("parent_orders"), Encoding::Json);
+        // Text-only transports refuse binary payloads instead of mangling them
+        assert_eq!(
+            service.produce_encoded("child_orders", &parent_order()),
+            Err(ClientError::Serialization(
+                "Topic child_orders only carries UTF-8 payloads".to_string()
+            ))
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_orders_use_the_topic_encoding() {
+        use strategy_execution_engine::{MessageEnvelope, MockClient};
+
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut service = MessagingService::with_client(Box::new(client.clone()));
+        service.set_encoding("child_orders", Encoding::MsgPack);
+        let child_order = parent_order()
+            .order_common
+            .into_child("strategy1", "parent1", None);
+
+        service
+            .produce_child_order("child_orders", &child_order)
+            .unwrap();
+        let sent = client.sent();
+        let envelope: MessageEnvelope = Encoding::MsgPack.decode(&sent[0].payload).unwrap();
+        assert_eq!(envelope.correlation_id, "parent1");
+        assert!(Encoding::Json
+            .decode::<MessageEnvelope>(&sent[0].payload)
+            .is_err());
+        let received = service.consume_child_order("child_orders").unwrap();
+        assert_eq!(received.to_string(), child_order.to_string());
+    }
+}
@@ -0,0 +1,135 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod factory_tests {
+    use strategy_execution_engine::{
+        ClientError, ClientType, Config, HealthStatus, KafkaConfig, MessagingClientFactory,
+        MessagingService, NatsConfig, RedisConfig, SaslMechanism, SecurityProtocol, ZeroMqConfig,
+    };
+
+    #[test]
+    fn test_missing_config() {
+        let config = Config::default();
+        let error = MessagingClientFactory::create_client(ClientType::Kafka, &config)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            ClientError::MissingConfig {
+                section: "kafka",
+                env_var: "KAFKA_URL",
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "missing kafka configuration, set KAFKA_URL"
+        );
+
+        for client_type in [
+            ClientType::Redis,
+            ClientType::Nats,
+            ClientType::RabbitMQ,
+            ClientType::ZeroMQ,
+        ] {
+            assert!(matches!(
+                MessagingService::new(client_type, &config),
+                Err(ClientError::MissingConfig { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_only_needed_section_is_required() {
+        let config = Config {
+            redis: Some(RedisConfig::default()),
+            zeromq: Some(ZeroMqConfig::default()),
+            ..Config::default()
+        };
+        // Clients connect lazily, so building them needs no broker
+        assert!(MessagingClientFactory::create_client(ClientType::Redis, &config).is_ok());
+        // ZeroMQ cannot probe its peers, so it always reports itself healthy
+        let service = MessagingService::new(ClientType::ZeroMQ, &config).unwrap();
+        assert_eq!(service.health(), Ok(HealthStatus::default()));
+        assert!(MessagingService::new(ClientType::Nats, &config).is_err());
+    }
+
+    #[test]
+    fn test_kafka_from_config() {
+        let config = Config {
+            kafka: Some(KafkaConfig {
+                kafka_url: "localhost:9092".to_string(),
+                group_id: "engine".to_string(),
+                ..KafkaConfig::default()
+            }),
+            ..Config::default()
+        };
+        assert!(MessagingService::new(ClientType::Kafka, &config).is_ok());
+    }
+
+    #[test]
+    fn test_kafka_rejects_sasl_without_credentials() {
+        let config = Config {
+            kafka: Some(KafkaConfig {
+                security_protocol: Some(SecurityProtocol::SaslSsl),
+                sasl_mechanism: Some(SaslMechanism::Plain),
+                ..KafkaConfig::default()
+            }),
+            ..Config::default()
+        };
+        assert!(matches!(
+            MessagingService::new(ClientType::Kafka, &config),
+            Err(ClientError::InvalidConfig {
+                section: "kafka",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = Config {
+            nats: Some(NatsConfig {
+                nats_url: "http://localhost:4222".into(),
+                timeout_ms: 1000,
+                ..NatsConfig::default()
+            }),
+            ..Config::default()
+        };
+        let error = MessagingService::new(ClientType::Nats, &config)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            ClientError::InvalidConfig {
+                section: "nats",
+                ..
+            }
+        ));
+    }
+}
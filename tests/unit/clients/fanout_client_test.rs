@@ -0,0 +1,168 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod fanout_client_tests {
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ClientError, ClientType, Config, FanoutClient, FanoutPolicy, MessagingClient,
+        MessagingService, MockClient,
+    };
+
+    fn broker_down() -> ClientError {
+        ClientError::Connection("broker down".to_string())
+    }
+
+    /// A fanout over a primary and a mirror, and handles on both.
+    fn fanout(policy: FanoutPolicy) -> (FanoutClient, MockClient, MockClient) {
+        let primary = MockClient::new().with_timeout(Duration::ZERO);
+        let mirror = MockClient::new().with_timeout(Duration::ZERO);
+        let client = FanoutClient::new(
+            vec![Box::new(primary.clone()), Box::new(mirror.clone())],
+            policy,
+        )
+        .unwrap();
+        (client, primary, mirror)
+    }
+
+    #[test]
+    fn test_produce_reaches_every_backend() {
+        let (client, primary, mirror) = fanout(FanoutPolicy::FailFast);
+        client.produce("orders", "order-1").unwrap();
+        client
+            .produce_keyed("orders", "parent1", "order-2")
+            .unwrap();
+
+        assert_eq!(primary.sent(), mirror.sent());
+        assert_eq!(mirror.sent()[1].key.as_deref(), Some("parent1"));
+        // Only the primary is consumed from
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(
+            client.try_consume("orders"),
+            Ok(Some("order-2".to_string()))
+        );
+        assert_eq!(mirror.pending("orders"), 2);
+    }
+
+    #[test]
+    fn test_best_effort_partial_failure() {
+        let (client, primary, mirror) = fanout(FanoutPolicy::BestEffort);
+        mirror.fail_next_produce(broker_down());
+        assert_eq!(client.produce("orders", "order-1"), Ok(()));
+        assert_eq!(primary.pending("orders"), 1);
+        assert_eq!(mirror.pending("orders"), 0);
+
+        // The mirror still gets the message the primary refused
+        primary.fail_next_produce(broker_down());
+        assert_eq!(client.produce("orders", "order-2"), Err(broker_down()));
+        assert_eq!(primary.pending("orders"), 1);
+        assert_eq!(mirror.pending("orders"), 1);
+
+        let metrics = client.backend_metrics();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(
+            (metrics[0].messages_produced, metrics[0].produce_errors),
+            (1, 1)
+        );
+        assert_eq!(
+            (metrics[1].messages_produced, metrics[1].produce_errors),
+            (1, 1)
+        );
+
+        mirror.fail_next_produce(broker_down());
+        assert_eq!(
+            client.produce_to_all("orders", b"order-3"),
+            vec![Ok(()), Err(broker_down())]
+        );
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let (client, primary, mirror) = fanout(FanoutPolicy::FailFast);
+        primary.fail_next_produce(broker_down());
+        assert_eq!(client.produce("orders", "order-1"), Err(broker_down()));
+        // The mirror is not tried once the primary failed
+        assert!(mirror.sent().is_empty());
+
+        mirror.fail_next_produce(broker_down());
+        assert_eq!(client.produce("orders", "order-2"), Err(broker_down()));
+        assert_eq!(primary.pending("orders"), 1);
+
+        let metrics = client.backend_metrics();
+        assert_eq!(
+            (metrics[0].messages_produced, metrics[0].produce_errors),
+            (1, 1)
+        );
+        assert_eq!(
+            (metrics[1].messages_produced, metrics[1].produce_errors),
+            (0, 1)
+        );
+    }
+
+    #[test]
+    fn test_from_factory() {
+        let service = MessagingService::new(
+            ClientType::Fanout(
+                vec![ClientType::InMemory, ClientType::InMemory],
+                FanoutPolicy::BestEffort,
+            ),
+            &Config::default(),
+        )
+        .unwrap();
+        service.produce("orders", "order").unwrap();
+        assert!(service.health().is_ok());
+        assert_eq!(service.metrics().messages_produced, 1);
+        let backends = service.backend_metrics();
+        assert_eq!(backends.len(), 2);
+        assert!(backends
+            .iter()
+            .all(|backend| backend.topics["orders"].messages_produced == 1));
+
+        assert!(matches!(
+            MessagingService::new(
+                ClientType::Fanout(Vec::new(), FanoutPolicy::FailFast),
+                &Config::default()
+            ),
+            Err(ClientError::InvalidConfig {
+                section: "fanout",
+                ..
+            })
+        ));
+        // A backend that cannot be built fails the whole fanout
+        assert!(matches!(
+            MessagingService::new(
+                ClientType::Fanout(
+                    vec![ClientType::InMemory, ClientType::Kafka],
+                    FanoutPolicy::FailFast
+                ),
+                &Config::default()
+            ),
+            Err(ClientError::MissingConfig { .. })
+        ));
+    }
+}
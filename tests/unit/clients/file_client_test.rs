@@ -0,0 +1,236 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod file_client_tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ChildOrder, ClientError, FileClient, FileClientError, MessagingClient, MessagingService,
+        Order, OrderType, RecordedMessage, ReplayClient, Side, Timestamp,
+    };
+
+    /// A directory of its own for each test, removed afterwards.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("file-client-{}", uuid::Uuid::now_v7()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn child_order(index: usize) -> ChildOrder {
+        Order::builder()
+            .id(format!("parent1-{index}"))
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_child("strategy1", "parent1", None)
+    }
+
+    #[test]
+    fn test_record_and_replay_child_orders() {
+        let dir = TempDir::new();
+        let path = dir.file("orders.jsonl");
+        let recorder = MessagingService::with_client(Box::new(FileClient::open(&path).unwrap()));
+        let started = Instant::now();
+        for index in 0..3 {
+            if index > 0 {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            recorder
+                .produce_child_order("child_orders", &child_order(index))
+                .unwrap();
+            recorder.produce("fills", &format!("fill-{index}")).unwrap();
+        }
+        let recorded = started.elapsed();
+
+        let replay = ReplayClient::from_file(&path, 4.0).unwrap();
+        assert_eq!(replay.remaining(), 6);
+        let replayer = MessagingService::with_client(Box::new(replay));
+        let started = Instant::now();
+        for index in 0..3 {
+            let order = replayer.consume_child_order("child_orders").unwrap();
+            assert_eq!(order.to_string(), child_order(index).to_string());
+        }
+        let replayed = started.elapsed();
+        // Two gaps of at least 100 ms, four times as fast
+        assert!(replayed >= Duration::from_millis(45), "{:?}", replayed);
+        assert!(replayed < recorded / 2, "{:?} of {:?}", replayed, recorded);
+
+        // The other topic keeps its own order and has caught up
+        for index in 0..3 {
+            assert_eq!(replayer.consume("fills"), Ok(format!("fill-{index}")));
+        }
+        assert!(matches!(
+            replayer.consume("fills"),
+            Err(ClientError::Timeout { .. })
+        ));
+        assert!(replayer.produce("fills", "fill").is_err());
+    }
+
+    #[test]
+    fn test_consume_reads_the_recording_in_order() {
+        let dir = TempDir::new();
+        let path = dir.file("orders.jsonl");
+        let client = FileClient::open(&path)
+            .unwrap()
+            .with_timeout(Duration::from_millis(20));
+        client.produce("orders", "order-1").unwrap();
+        client.produce_keyed("fills", "parent1", "fill-1").unwrap();
+        client.produce("orders", "order-2").unwrap();
+
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(client.consume("orders"), Ok("order-2".to_string()));
+        assert_eq!(client.try_consume("orders"), Ok(None));
+        assert_eq!(client.consume("fills"), Ok("fill-1".to_string()));
+
+        // A record written while a consumer waits is picked up
+        client.produce("orders", "order-3").unwrap();
+        assert_eq!(
+            client.consume_timeout("orders", Duration::from_secs(1)),
+            Ok(Some("order-3".to_string()))
+        );
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        let record: RecordedMessage = serde_json::from_str(line.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(record.topic, "fills");
+        assert_eq!(record.key.as_deref(), Some("parent1"));
+        assert_eq!(record.payload, "fill-1");
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let dir = TempDir::new();
+        let path = dir.file("orders.jsonl");
+        let client = FileClient::open(&path).unwrap().with_max_bytes(200);
+        for index in 0..10 {
+            client.produce("orders", &format!("order-{index}")).unwrap();
+        }
+
+        let segments: Vec<_> = std::fs::read_dir(&dir.0).unwrap().collect();
+        assert!(segments.len() > 2, "{} segments", segments.len());
+        for segment in segments {
+            let size = segment.unwrap().metadata().unwrap().len();
+            assert!(size <= 200, "{}", size);
+        }
+        for index in 0..10 {
+            assert_eq!(client.consume("orders"), Ok(format!("order-{index}")));
+        }
+
+        // A new client appends to the last segment, and a replay reads them all
+        let client = FileClient::open(&path).unwrap().with_max_bytes(200);
+        client.produce("orders", "order-10").unwrap();
+        let replay = ReplayClient::from_file(&path, f64::INFINITY).unwrap();
+        for index in 0..11 {
+            assert_eq!(replay.consume("orders"), Ok(format!("order-{index}")));
+        }
+    }
+
+    #[test]
+    fn test_truncated_last_line() {
+        let dir = TempDir::new();
+        let path = dir.file("orders.jsonl");
+        let client = FileClient::open(&path).unwrap();
+        client.produce("orders", "order-1").unwrap();
+        drop(client);
+        // The recorder stopped in the middle of a record
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"{\"topic\":\"orders\",\"timest").unwrap();
+
+        let replay = ReplayClient::from_file(&path, f64::INFINITY).unwrap();
+        assert_eq!(replay.remaining(), 1);
+        assert_eq!(replay.consume("orders"), Ok("order-1".to_string()));
+
+        // Recording again drops the cut record and carries on after it
+        let client = FileClient::open(&path)
+            .unwrap()
+            .with_timeout(Duration::ZERO);
+        client.produce("orders", "order-2").unwrap();
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(client.consume("orders"), Ok("order-2".to_string()));
+        assert!(std::fs::read_to_string(&path).unwrap().ends_with("\n"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = TempDir::new();
+        let path = dir.file("orders.jsonl");
+        let valid = "{\"topic\":\"orders\",\"timestamp\":1622512800000,\"payload\":\"order-1\"}";
+        std::fs::write(&path, format!("{valid}\nnot json\n")).unwrap();
+
+        let error = ReplayClient::from_file(&path, 1.0).err().unwrap();
+        assert!(
+            matches!(error, FileClientError::Corrupt { line: 2, .. }),
+            "{}",
+            error
+        );
+        let client = FileClient::open(&path)
+            .unwrap()
+            .with_timeout(Duration::ZERO);
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert!(matches!(
+            client.consume("orders"),
+            Err(ClientError::InvalidPayload(_))
+        ));
+        // The corrupt line is reported once, then the consumer moves past it
+        client.produce("orders", "order-2").unwrap();
+        assert_eq!(client.consume("orders"), Ok("order-2".to_string()));
+
+        assert_eq!(
+            ReplayClient::from_file(&path, 0.0).err(),
+            Some(FileClientError::InvalidSpeedFactor)
+        );
+        assert!(ReplayClient::from_file(&path, f64::NAN).is_err());
+        assert!(matches!(
+            ReplayClient::from_file(dir.file("missing.jsonl"), 1.0),
+            Err(FileClientError::Io { .. })
+        ));
+    }
+}
@@ -0,0 +1,785 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod kafka_client_tests {
+    use futures_util::StreamExt;
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use std::collections::{HashMap, VecDeque};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ClientError, ConsumedMessage, KafkaClient, KafkaConfig, KafkaConsumer, KafkaMessage,
+        KafkaProducer, KafkaRecord, MessagingClient, MessagingService, Order, SaslMechanism,
+        SecurityProtocol, Side, Timestamp,
+    };
+
+    /// A partition of a topic, and an offset in it.
+    type Position = (String, i32, i64);
+
+    /// Stands in for the broker: records subscriptions, stored offsets and paused partitions,
+    /// and hands out queued messages, all on partition 0.
+    #[derive(Default)]
+    struct Broker {
+        subscriptions: Mutex<Vec<Vec<String>>>,
+        messages: Mutex<VecDeque<KafkaMessage>>,
+        offsets: Mutex<HashMap<String, i64>>,
+        stored: Mutex<Vec<Position>>,
+        paused: Mutex<Vec<(String, i32)>>,
+        left: AtomicBool,
+    }
+
+    impl Broker {
+        fn publish(&self, topic: &str, payload: Option<&str>) {
+            let mut offsets = self.offsets.lock().unwrap();
+            let offset = offsets.entry(topic.to_string()).or_default();
+            self.messages.lock().unwrap().push_back(KafkaMessage {
+                topic: topic.to_string(),
+                partition: 0,
+                offset: *offset,
+                payload: payload.map(|payload| payload.as_bytes().to_vec()),
+            });
+            *offset += 1;
+        }
+
+        fn subscriptions(&self) -> Vec<Vec<String>> {
+            self.subscriptions.lock().unwrap().clone()
+        }
+
+        fn stored(&self) -> Vec<Position> {
+            self.stored.lock().unwrap().clone()
+        }
+
+        fn paused(&self) -> Vec<(String, i32)> {
+            self.paused.lock().unwrap().clone()
+        }
+    }
+
+    struct MockConsumer(Arc<Broker>);
+
+    impl KafkaConsumer for MockConsumer {
+        fn subscribe(&self, topics: &[&str]) -> Result<(), KafkaError> {
+            let topics = topics.iter().map(|topic| topic.to_string()).collect();
+            self.0.subscriptions.lock().unwrap().push(topics);
+            Ok(())
+        }
+
+        fn recv(
+            &self,
+        ) -> Pin<Box<dyn Future<Output = Result<KafkaMessage, KafkaError>> + Send + '_>> {
+            let message = self.0.messages.lock().unwrap().pop_front();
+            Box::pin(async move { message.ok_or(KafkaError::NoMessageReceived) })
+        }
+
+        fn store_offset(&self, topic: &str, partition: i32, offset: i64) -> Result<(), KafkaError> {
+            let position = (topic.to_string(), partition, offset);
+            self.0.stored.lock().unwrap().push(position);
+            Ok(())
+        }
+
+        fn pause(&self, topic: &str, partition: i32) -> Result<(), KafkaError> {
+            self.0
+                .paused
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), partition));
+            Ok(())
+        }
+
+        fn resume(&self, topic: &str, partition: i32) -> Result<(), KafkaError> {
+            let mut paused = self.0.paused.lock().unwrap();
+            paused.retain(|paused| *paused != (topic.to_string(), partition));
+            Ok(())
+        }
+
+        fn close(&self) -> Result<(), KafkaError> {
+            self.0.left.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// A consumer whose broker never delivers anything.
+    struct SilentConsumer;
+
+    impl KafkaConsumer for SilentConsumer {
+        fn subscribe(&self, _topics: &[&str]) -> Result<(), KafkaError> {
+            Ok(())
+        }
+
+        fn recv(
+            &self,
+        ) -> Pin<Box<dyn Future<Output = Result<KafkaMessage, KafkaError>> + Send + '_>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    /// A record as the producer handed it over: topic, key, partition and payload.
+    type Sent = (String, Option<String>, Option<i32>, String);
+
+    #[derive(Clone, Default)]
+    struct CapturingProducer(Arc<Mutex<Vec<Sent>>>);
+
+    impl KafkaProducer for CapturingProducer {
+        fn send(&self, record: KafkaRecord<'_>) -> Result<(), KafkaError> {
+            self.0.lock().unwrap().push((
+                record.topic.to_string(),
+                record
+                    .key
+                    .map(|key| String::from_utf8_lossy(key).into_owned()),
+                record.partition,
+                String::from_utf8_lossy(record.payload).into_owned(),
+            ));
+            Ok(())
+        }
+
+        fn probe(&self, _timeout: Duration) -> Result<String, KafkaError> {
+            Ok("broker-1:9092 (id 1)".to_string())
+        }
+    }
+
+    /// A producer that counts how often it was flushed.
+    #[derive(Clone, Default)]
+    struct FlushingProducer(Arc<AtomicUsize>);
+
+    impl KafkaProducer for FlushingProducer {
+        fn send(&self, _record: KafkaRecord<'_>) -> Result<(), KafkaError> {
+            Ok(())
+        }
+
+        fn probe(&self, _timeout: Duration) -> Result<String, KafkaError> {
+            Ok("broker-1:9092 (id 1)".to_string())
+        }
+
+        fn flush(&self, _timeout: Duration) -> Result<(), KafkaError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// A producer whose broker rejects every message with `code`.
+    struct RejectingProducer(RDKafkaErrorCode);
+
+    impl KafkaProducer for RejectingProducer {
+        fn send(&self, _record: KafkaRecord<'_>) -> Result<(), KafkaError> {
+            Err(KafkaError::MessageProduction(self.0))
+        }
+
+        fn probe(&self, _timeout: Duration) -> Result<String, KafkaError> {
+            Err(KafkaError::MetadataFetch(self.0))
+        }
+    }
+
+    fn client() -> (KafkaClient, Arc<Broker>) {
+        let broker = Arc::new(Broker::default());
+        let client =
+            KafkaClient::with_consumer("localhost:9092".to_string(), MockConsumer(broker.clone()))
+                .unwrap();
+        (client, broker)
+    }
+
+    #[test]
+    fn test_repeated_consume_subscribes_once() {
+        let (client, broker) = client();
+        for i in 0..3 {
+            broker.publish("orders", Some(&format!("order-{i}")));
+        }
+
+        for i in 0..3 {
+            assert_eq!(client.consume("orders"), Ok(format!("order-{i}")));
+        }
+        assert_eq!(broker.subscriptions(), vec![vec!["orders".to_string()]]);
+        assert_eq!(client.subscribed_topics(), vec!["orders".to_string()]);
+    }
+
+    #[test]
+    fn test_new_topic_extends_subscription() {
+        let (client, broker) = client();
+        broker.publish("orders", Some("order"));
+        broker.publish("fills", Some("fill"));
+
+        assert_eq!(client.consume("orders"), Ok("order".to_string()));
+        assert_eq!(client.consume("fills"), Ok("fill".to_string()));
+        assert_eq!(
+            broker.subscriptions(),
+            vec![
+                vec!["orders".to_string()],
+                vec!["orders".to_string(), "fills".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reply_topic_is_stable_per_group() {
+        let config = KafkaConfig {
+            group_id: "engine".to_string(),
+            ..KafkaConfig::default()
+        };
+        assert_eq!(config.reply_topic(), "replies.engine");
+        let config = KafkaConfig {
+            reply_topic: Some("replies.engine-1".to_string()),
+            ..config
+        };
+        assert_eq!(config.reply_topic(), "replies.engine-1");
+
+        let (client, _broker) = client();
+        assert_eq!(client.reply_topic(), "replies.default-group");
+    }
+
+    #[test]
+    fn test_reply_topic_is_subscribed_on_the_first_request() {
+        let (client, broker) = client();
+        let producer = CapturingProducer::default();
+        let client = client.with_producer(producer.clone());
+        assert!(broker.subscriptions().is_empty());
+
+        let reply = client.request("rpc", "ping", Duration::from_millis(20));
+        assert!(matches!(reply, Err(ClientError::Timeout { .. })));
+        assert_eq!(
+            broker.subscriptions(),
+            vec![vec!["replies.default-group".to_string()]]
+        );
+        let sent = producer.0.lock().unwrap();
+        assert_eq!(sent[0].0, "rpc");
+        assert!(sent[0].3.contains("\"reply_to\":\"replies.default-group\""));
+    }
+
+    #[test]
+    fn test_messages_for_other_topics_are_kept() {
+        let (client, broker) = client();
+        client.consume("fills").unwrap_err();
+        broker.publish("fills", Some("fill-1"));
+        broker.publish("fills", Some("fill-2"));
+        broker.publish("orders", Some("order"));
+
+        assert_eq!(client.consume("orders"), Ok("order".to_string()));
+        assert_eq!(client.consume("fills"), Ok("fill-1".to_string()));
+        assert_eq!(client.consume_bytes("fills"), Ok(b"fill-2".to_vec()));
+        assert_eq!(broker.subscriptions().len(), 2);
+    }
+
+    #[test]
+    fn test_only_consumed_offsets_are_stored() {
+        let (client, broker) = client();
+        client.consume("fills").unwrap_err();
+        broker.publish("fills", Some("fill-1"));
+        broker.publish("orders", Some("order"));
+
+        // The fill waiting in the client is not covered by a commit yet
+        assert_eq!(client.consume("orders"), Ok("order".to_string()));
+        assert_eq!(broker.stored(), vec![("orders".to_string(), 0, 0)]);
+
+        assert_eq!(client.consume("fills"), Ok("fill-1".to_string()));
+        assert_eq!(
+            broker.stored(),
+            vec![("orders".to_string(), 0, 0), ("fills".to_string(), 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_waiting_messages_pause_their_partition() {
+        let (client, broker) = client();
+        let client = client.with_max_pending_messages(4);
+        client.consume("fills").unwrap_err();
+        for i in 0..5 {
+            broker.publish("fills", Some(&format!("fill-{i}")));
+        }
+        broker.publish("orders", Some("order"));
+
+        assert_eq!(client.consume("orders"), Ok("order".to_string()));
+        assert_eq!(broker.paused(), vec![("fills".to_string(), 0)]);
+
+        // Fetching resumes once half of the waiting messages were consumed
+        for i in 0..2 {
+            assert_eq!(client.consume("fills"), Ok(format!("fill-{i}")));
+            assert_eq!(broker.paused(), vec![("fills".to_string(), 0)]);
+        }
+        assert_eq!(client.consume("fills"), Ok("fill-2".to_string()));
+        assert!(broker.paused().is_empty());
+        for i in 3..5 {
+            assert_eq!(client.consume("fills"), Ok(format!("fill-{i}")));
+        }
+    }
+
+    #[test]
+    fn test_consume_errors() {
+        let (client, broker) = client();
+        broker.publish("orders", None);
+        broker.publish("orders", Some("\u{fffd}"));
+
+        assert_eq!(
+            client.consume("orders"),
+            Err(ClientError::InvalidPayload(
+                "empty message payload".to_string()
+            ))
+        );
+        assert!(client.consume("orders").is_ok());
+        assert_eq!(
+            client.consume("orders"),
+            Err(ClientError::Timeout {
+                topic: "orders".to_string(),
+                timeout_ms: 0,
+            })
+        );
+        assert_eq!(broker.subscriptions().len(), 1);
+    }
+
+    #[test]
+    fn test_try_consume_returns_waiting_message() {
+        let (client, broker) = client();
+        broker.publish("fills", Some("fill"));
+        broker.publish("orders", Some("order"));
+
+        assert_eq!(client.try_consume("orders"), Ok(Some("order".to_string())));
+        assert_eq!(
+            client.consume_timeout("fills", Duration::from_secs(1)),
+            Ok(Some("fill".to_string()))
+        );
+        assert_eq!(
+            client.try_consume("orders"),
+            Err(ClientError::Timeout {
+                topic: "orders".to_string(),
+                timeout_ms: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_consume_timeout_without_message() {
+        let client =
+            KafkaClient::with_consumer("localhost:9092".to_string(), SilentConsumer).unwrap();
+
+        let start = Instant::now();
+        assert_eq!(
+            client.consume_timeout("orders", Duration::from_millis(50)),
+            Ok(None)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(client.try_consume("orders"), Ok(None));
+    }
+
+    #[test]
+    fn test_consume_timeout_rejects_binary_payload() {
+        let (client, broker) = client();
+        broker.messages.lock().unwrap().push_back(KafkaMessage {
+            topic: "orders".to_string(),
+            partition: 0,
+            offset: 0,
+            payload: Some(vec![0xff, 0xfe]),
+        });
+
+        assert!(matches!(
+            client.try_consume("orders"),
+            Err(ClientError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn test_record_keys_and_partitions() {
+        let producer = CapturingProducer::default();
+        let (client, _broker) = client();
+        let client = client.with_producer(producer.clone());
+
+        client.produce("orders", "order-1").unwrap();
+        client
+            .produce_keyed("orders", "parent1", "order-2")
+            .unwrap();
+        client
+            .produce_to_partition("orders", 3, Some("parent1"), b"order-3")
+            .unwrap();
+        client
+            .produce_to_partition("orders", 0, None, b"order-4")
+            .unwrap();
+
+        let sent = |key: Option<&str>, partition, payload: &str| {
+            (
+                "orders".to_string(),
+                key.map(str::to_string),
+                partition,
+                payload.to_string(),
+            )
+        };
+        assert_eq!(
+            *producer.0.lock().unwrap(),
+            vec![
+                sent(None, None, "order-1"),
+                sent(Some("parent1"), None, "order-2"),
+                sent(Some("parent1"), Some(3), "order-3"),
+                sent(None, Some(0), "order-4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_orders_are_keyed() {
+        let producer = CapturingProducer::default();
+        let (client, _broker) = client();
+        let service =
+            MessagingService::with_client(Box::new(client.with_producer(producer.clone())));
+        let order = Order::builder()
+            .id("parent1")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap();
+        let mut child_order = order.clone().into_child("twap", "parent1", None);
+        child_order.order_common.id = "parent1-0".to_string();
+
+        service
+            .produce_parent_order("parent_orders", &order.into_parent("twap"))
+            .unwrap();
+        service
+            .produce_child_order("child_orders", &child_order)
+            .unwrap();
+
+        let keys: Vec<_> = producer
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(topic, key, _, _)| (topic.clone(), key.clone()))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("parent_orders".to_string(), Some("parent1".to_string())),
+                ("child_orders".to_string(), Some("parent1".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_maps_to_client_config() {
+        let config = KafkaConfig {
+            kafka_url: "broker:9093".to_string(),
+            group_id: "engine".to_string(),
+            security_protocol: Some(SecurityProtocol::SaslSsl),
+            sasl_mechanism: Some(SaslMechanism::ScramSha256),
+            sasl_username: Some("engine".to_string()),
+            sasl_password: Some("hunter2".to_string().into()),
+            ssl_ca_location: Some("/etc/ssl/ca.pem".to_string()),
+            session_timeout_ms: 10_000,
+            message_timeout_ms: 5_000,
+            connect_timeout_ms: Some(2_000),
+            request_timeout_ms: Some(15_000),
+            ..KafkaConfig::default()
+        };
+
+        let consumer = config.consumer_config();
+        assert_eq!(consumer.get("bootstrap.servers"), Some("broker:9093"));
+        assert_eq!(consumer.get("group.id"), Some("engine"));
+        assert_eq!(consumer.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(consumer.get("sasl.mechanism"), Some("SCRAM-SHA-256"));
+        assert_eq!(consumer.get("sasl.username"), Some("engine"));
+        assert_eq!(consumer.get("sasl.password"), Some("hunter2"));
+        assert_eq!(consumer.get("ssl.ca.location"), Some("/etc/ssl/ca.pem"));
+        assert_eq!(consumer.get("session.timeout.ms"), Some("10000"));
+        assert_eq!(
+            consumer.get("socket.connection.setup.timeout.ms"),
+            Some("2000")
+        );
+        assert_eq!(consumer.get("request.timeout.ms"), None);
+        assert_eq!(consumer.get("enable.auto.commit"), Some("true"));
+        assert_eq!(consumer.get("enable.auto.offset.store"), Some("false"));
+
+        let producer = config.producer_config();
+        assert_eq!(producer.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(producer.get("message.timeout.ms"), Some("5000"));
+        assert_eq!(producer.get("request.timeout.ms"), Some("15000"));
+        assert_eq!(producer.get("group.id"), None);
+
+        let plain = KafkaConfig::default().client_config();
+        assert_eq!(plain.get("security.protocol"), None);
+        assert_eq!(plain.get("sasl.mechanism"), None);
+        assert_eq!(plain.get("socket.connection.setup.timeout.ms"), None);
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_security() {
+        let config = KafkaConfig {
+            sasl_mechanism: Some(SaslMechanism::Plain),
+            sasl_username: Some("engine".to_string()),
+            sasl_password: Some("hunter2".to_string().into()),
+            ..KafkaConfig::default()
+        };
+        assert_eq!(
+            KafkaClient::from_config(&config).err(),
+            Some(ClientError::InvalidConfig {
+                section: "kafka",
+                reason: "a SASL mechanism requires the sasl_plaintext or sasl_ssl protocol"
+                    .to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_producer_creation_error_is_returned() {
+        // rdkafka accepts request.timeout.ms from 1 ms, so the producer cannot be created
+        let config = KafkaConfig {
+            request_timeout_ms: Some(0),
+            ..KafkaConfig::default()
+        };
+        assert!(KafkaClient::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_kafka_error_mapping() {
+        let produce = |code| ClientError::from_kafka("orders", KafkaError::MessageProduction(code));
+        assert_eq!(
+            produce(RDKafkaErrorCode::UnknownTopicOrPartition),
+            ClientError::TopicNotFound("orders".to_string())
+        );
+        assert!(matches!(
+            produce(RDKafkaErrorCode::TopicAuthorizationFailed),
+            ClientError::Auth(_)
+        ));
+        assert!(matches!(
+            produce(RDKafkaErrorCode::AllBrokersDown),
+            ClientError::Connection(_)
+        ));
+        assert!(matches!(
+            produce(RDKafkaErrorCode::QueueFull),
+            ClientError::Delivery {
+                retriable: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            produce(RDKafkaErrorCode::MessageSizeTooLarge),
+            ClientError::Delivery {
+                retriable: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            ClientError::from_kafka(
+                "orders",
+                KafkaError::MessageConsumption(RDKafkaErrorCode::SaslAuthenticationFailed)
+            ),
+            ClientError::Auth(_)
+        ));
+        assert!(matches!(
+            ClientError::from_kafka("", KafkaError::ClientCreation("bad config".to_string())),
+            ClientError::InvalidConfig {
+                section: "kafka",
+                ..
+            }
+        ));
+        assert!(matches!(
+            ClientError::from_kafka("orders", KafkaError::Canceled),
+            ClientError::Transport(_)
+        ));
+    }
+
+    #[test]
+    fn test_produce_errors_are_typed() {
+        let (client, _broker) = client();
+        let client = client.with_producer(RejectingProducer(RDKafkaErrorCode::QueueFull));
+        let error = client.produce("orders", "order").unwrap_err();
+        assert!(error.is_retriable(), "{}", error);
+
+        let (client, _broker) = self::client();
+        let client = client.with_producer(RejectingProducer(RDKafkaErrorCode::UnknownTopic));
+        let error = client
+            .produce_keyed("fills", "parent1", "fill")
+            .unwrap_err();
+        assert_eq!(error, ClientError::TopicNotFound("fills".to_string()));
+        assert!(!error.is_retriable());
+    }
+
+    #[test]
+    fn test_health_check_reports_broker() {
+        let (client, _broker) = client();
+        let service = MessagingService::with_client(Box::new(
+            client.with_producer(CapturingProducer::default()),
+        ));
+        let status = service.health().unwrap();
+        assert_eq!(status.broker.as_deref(), Some("broker-1:9092 (id 1)"));
+        assert_eq!(status.version, None);
+    }
+
+    #[test]
+    fn test_health_check_failure() {
+        let (client, _broker) = client();
+        let client = client.with_producer(RejectingProducer(RDKafkaErrorCode::AllBrokersDown));
+        assert!(matches!(
+            client.health_check(),
+            Err(ClientError::Connection(_))
+        ));
+    }
+
+    #[test]
+    fn test_subscribe_delivers_until_unsubscribed() {
+        let (client, broker) = client();
+        let client = Arc::new(client);
+        let count = Arc::new(AtomicUsize::new(0));
+        let handled = count.clone();
+        let subscription = client
+            .clone()
+            .subscribe(
+                "orders",
+                Box::new(move |_message| {
+                    handled.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .unwrap();
+        assert_eq!(client.subscribed_topics(), vec!["orders".to_string()]);
+
+        for i in 0..4 {
+            broker.publish("orders", Some(&format!("order-{i}")));
+        }
+        broker.publish("fills", Some("fill"));
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while count.load(Ordering::SeqCst) < 4 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+
+        subscription.unsubscribe();
+        broker.publish("orders", Some("order-4"));
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+        // Other topics received by the subscription are kept for their consumers
+        assert_eq!(client.consume("fills"), Ok("fill".to_string()));
+        assert_eq!(client.consume("orders"), Ok("order-4".to_string()));
+
+        // The subscription keeps the client, and its runtime, alive until it is cancelled
+        let subscription = client.subscribe("orders", Box::new(|_message| {})).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        drop(subscription);
+    }
+
+    #[tokio::test]
+    async fn test_consume_inside_async_runtime() {
+        let (client, broker) = client();
+        broker.publish("orders", Some("order-1"));
+        broker.publish("orders", Some("order-2"));
+
+        assert_eq!(
+            client.consume_async("orders").await,
+            Ok("order-1".to_string())
+        );
+        // Blocking on the client's runtime from async code would panic; it is refused instead
+        assert!(client.consume("orders").is_err());
+        assert_eq!(
+            client.consume_bytes_async("orders").await,
+            Ok(b"order-2".to_vec())
+        );
+        assert_eq!(broker.subscriptions().len(), 1);
+        // Dropping the client, and its runtime, inside async code must not panic either
+        drop(client);
+    }
+    #[tokio::test]
+    async fn test_consume_stream() {
+        let (client, broker) = client();
+        broker.publish("orders", Some("order-1"));
+        broker.publish("fills", Some("fill-1"));
+        broker.publish("orders", Some("order-2"));
+        let client = Arc::new(client);
+
+        let mut stream = client.clone().consume_stream("orders");
+        for payload in ["order-1", "order-2"] {
+            assert_eq!(
+                stream.next().await,
+                Some(Ok(ConsumedMessage::new(
+                    "orders",
+                    payload.as_bytes().to_vec()
+                )))
+            );
+        }
+        drop(stream);
+        // The fill read on the way stays for its own topic
+        assert_eq!(
+            client.consume_bytes_async("fills").await,
+            Ok(b"fill-1".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_ends_its_task() {
+        let client = Arc::new(
+            KafkaClient::with_consumer("localhost:9092".to_string(), SilentConsumer).unwrap(),
+        );
+        let mut stream = client.clone().consume_stream("orders");
+        let waiting = tokio::time::timeout(Duration::from_millis(50), stream.next()).await;
+        assert!(waiting.is_err());
+        assert_eq!(Arc::strong_count(&client), 2);
+
+        // The task lets go of the client even though no message ever arrives
+        drop(stream);
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while Arc::strong_count(&client) > 1 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(Arc::strong_count(&client), 1);
+    }
+
+    #[test]
+    fn test_close_flushes_and_leaves_group() {
+        let (client, broker) = client();
+        let producer = FlushingProducer::default();
+        let client = client.with_producer(producer.clone());
+        client.produce("orders", "order-1").unwrap();
+
+        client.close(Duration::from_secs(1)).unwrap();
+        assert_eq!(producer.0.load(Ordering::SeqCst), 1);
+        assert!(broker.left.load(Ordering::SeqCst));
+
+        assert_eq!(
+            client.produce("orders", "order-2"),
+            Err(ClientError::Closed)
+        );
+        assert_eq!(
+            client.consume_timeout("orders", Duration::from_millis(10)),
+            Err(ClientError::Closed)
+        );
+        // Closing again neither flushes nor fails
+        client.close(Duration::from_secs(1)).unwrap();
+        assert_eq!(producer.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_close_ends_a_waiting_consume() {
+        let client = Arc::new(
+            KafkaClient::with_consumer("localhost:9092".to_string(), SilentConsumer).unwrap(),
+        );
+        let waiting = {
+            let client = client.clone();
+            std::thread::spawn(move || client.consume("orders"))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+
+        client.close(Duration::from_secs(1)).unwrap();
+        assert_eq!(waiting.join().unwrap(), Err(ClientError::Closed));
+    }
+}
@@ -0,0 +1,163 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+#[cfg(test)]
+mod message_stream_tests {
+    use crate::support::service;
+    use futures_util::StreamExt;
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ClientError, ConsumedMessage, MessagingClient, Order, OrderType, ParentOrder, Side,
+        Timestamp, MESSAGE_STREAM_BUFFER,
+    };
+
+    /// Longer than a stream worker takes to notice anything, which it does once per poll.
+    const SETTLE: Duration = Duration::from_millis(300);
+
+    fn parent_order(id: &str) -> ParentOrder {
+        Order::builder()
+            .id(id)
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_parent("strategy1")
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_messages_in_order() {
+        let (service, client) = service();
+        for i in 0..5 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+
+        let stream = service.consume_stream("orders");
+        assert_eq!(stream.topic(), "orders");
+        let messages: Vec<_> = stream.take(5).collect().await;
+        assert_eq!(
+            messages,
+            (0..5)
+                .map(|i| Ok(ConsumedMessage::new(
+                    "orders",
+                    format!("order-{i}").into_bytes()
+                )))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(service.metrics().messages_consumed, 5);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_stream_stops_the_worker() {
+        let (service, client) = service();
+        for i in 0..3 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+        let mut stream = service.consume_stream("orders");
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+        tokio::time::sleep(SETTLE).await;
+        // The third message may or may not have been buffered by then
+        let left = client.pending("orders");
+
+        // Nothing receives from the topic any more
+        for i in 3..6 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+        tokio::time::sleep(SETTLE).await;
+        assert_eq!(client.pending("orders"), left + 3);
+        assert_eq!(service.metrics().messages_consumed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_holds_back_the_worker() {
+        let (service, client) = service();
+        for i in 0..40 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+        let mut stream = service.consume_stream("orders");
+        assert_eq!(
+            stream.next().await,
+            Some(Ok(ConsumedMessage::new("orders", b"order-0".to_vec())))
+        );
+        tokio::time::sleep(SETTLE).await;
+
+        // A full buffer, and one more message waiting to go in
+        assert_eq!(client.pending("orders"), 40 - 1 - MESSAGE_STREAM_BUFFER - 1);
+        let rest: Vec<_> = stream.take(39).collect().await;
+        assert_eq!(
+            rest.last(),
+            Some(&Ok(ConsumedMessage::new("orders", b"order-39".to_vec())))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_receives_are_passed_on() {
+        let (service, client) = service();
+        client.fail_next_consume(ClientError::Connection("broker down".to_string()));
+        client.produce("orders", "order-1").unwrap();
+
+        let mut stream = service.consume_stream("orders");
+        assert_eq!(
+            stream.next().await,
+            Some(Err(ClientError::Connection("broker down".to_string())))
+        );
+        assert_eq!(
+            stream.next().await,
+            Some(Ok(ConsumedMessage::new("orders", b"order-1".to_vec())))
+        );
+        assert_eq!(service.metrics().consume_errors, 1);
+    }
+
+    #[tokio::test]
+    async fn test_parent_order_stream() {
+        let (service, client) = service();
+        service
+            .produce_parent_order("orders", &parent_order("parent1"))
+            .unwrap();
+        client.produce("orders", "not an envelope").unwrap();
+        service
+            .produce_parent_order("orders", &parent_order("parent2"))
+            .unwrap();
+
+        let orders: Vec<_> = service
+            .consume_parent_order_stream("orders")
+            .take(3)
+            .collect()
+            .await;
+        assert_eq!(orders[0].as_ref().unwrap().order_common.id, "parent1");
+        assert!(matches!(
+            orders[1],
+            Err(ClientError::Deserialization { .. })
+        ));
+        assert_eq!(orders[2].as_ref().unwrap().order_common.id, "parent2");
+    }
+}
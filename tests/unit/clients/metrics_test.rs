@@ -0,0 +1,167 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod metrics_tests {
+    use crate::support::instant_service;
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ChildOrder, ClientError, MetricsSnapshot, Order, OrderType, Side, Timestamp, TopicMetrics,
+    };
+
+    fn broker_down() -> ClientError {
+        ClientError::Connection("broker down".to_string())
+    }
+
+    fn child_order() -> ChildOrder {
+        Order::builder()
+            .id("parent1-0")
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_child("strategy1", "parent1", None)
+    }
+
+    #[test]
+    fn test_counts_successes_and_failures() {
+        let (service, client) = instant_service();
+        service.produce("orders", "order-1").unwrap();
+        service.produce_keyed("fills", "parent1", "fill").unwrap();
+
+        client.fail_next_produce(broker_down());
+        client.fail_next_consume(broker_down());
+        assert!(service.produce("orders", "order-2").is_err());
+        assert!(service.consume("orders").is_err());
+
+        assert_eq!(service.consume("orders"), Ok("order-1".to_string()));
+        // Nothing waiting is neither a message nor an error
+        assert_eq!(service.try_consume("orders"), Ok(None));
+        // A blocking receive that times out is a failed attempt
+        assert!(service.consume("orders").is_err());
+
+        let metrics = service.metrics();
+        assert_eq!(metrics.messages_produced, 2);
+        assert_eq!(metrics.bytes_produced, 11);
+        assert_eq!(metrics.messages_consumed, 1);
+        assert_eq!(metrics.produce_errors, 1);
+        assert_eq!(metrics.consume_errors, 2);
+        assert!(metrics.last_error_ts.unwrap() >= Timestamp::from_secs(1_700_000_000));
+        assert_eq!(
+            metrics.topics["orders"],
+            TopicMetrics {
+                messages_produced: 1,
+                messages_consumed: 1,
+            }
+        );
+        assert_eq!(metrics.topics["fills"].messages_produced, 1);
+    }
+
+    #[test]
+    fn test_every_retry_is_counted() {
+        let (service, client) = instant_service();
+        for _ in 0..3 {
+            client.fail_next_produce(broker_down());
+        }
+        let mut attempts = 0;
+        while service.produce("orders", "order").is_err() {
+            attempts += 1;
+        }
+
+        assert_eq!(attempts, 3);
+        let metrics = service.metrics();
+        assert_eq!(metrics.produce_errors, 3);
+        assert_eq!(metrics.messages_produced, 1);
+        assert_eq!(metrics.bytes_produced, 5);
+    }
+
+    #[test]
+    fn test_typed_messages_are_counted_once() {
+        let (service, _client) = instant_service();
+        for _ in 0..3 {
+            service
+                .produce_child_order("child-orders", &child_order())
+                .unwrap();
+        }
+        service.consume_child_order("child-orders").unwrap();
+
+        let metrics = service.metrics();
+        assert_eq!(
+            metrics.topics["child-orders"],
+            TopicMetrics {
+                messages_produced: 3,
+                messages_consumed: 1,
+            }
+        );
+        assert!(metrics.bytes_produced > 3 * 100);
+        assert_eq!(metrics.produce_errors + metrics.consume_errors, 0);
+
+        // A payload that arrives but does not decode was still consumed
+        service.produce("child-orders", "not an order").unwrap();
+        service.consume_child_order("child-orders").unwrap();
+        service.consume_child_order("child-orders").unwrap();
+        assert!(service.consume_child_order("child-orders").is_err());
+        assert_eq!(service.metrics().messages_consumed, 4);
+        assert_eq!(service.metrics().consume_errors, 0);
+    }
+
+    #[test]
+    fn test_subscription_deliveries_are_counted() {
+        let (service, _client) = instant_service();
+        let subscription = service
+            .subscribe("orders", Box::new(|_message| {}))
+            .unwrap();
+        service.produce("orders", "order-1").unwrap();
+        service.produce("orders", "order-2").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while service.metrics().messages_consumed < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        subscription.unsubscribe();
+        assert_eq!(service.metrics().messages_consumed, 2);
+        assert_eq!(service.metrics().topics["orders"].messages_consumed, 2);
+    }
+
+    #[test]
+    fn test_reset() {
+        let (service, client) = instant_service();
+        service.produce("orders", "order").unwrap();
+        client.fail_next_consume(broker_down());
+        assert!(service.consume("orders").is_err());
+
+        service.reset_metrics();
+        assert_eq!(service.metrics(), MetricsSnapshot::default());
+        service.consume("orders").unwrap();
+        assert_eq!(service.metrics().messages_consumed, 1);
+    }
+}
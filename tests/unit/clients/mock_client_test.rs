@@ -0,0 +1,194 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod mock_client_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ClientError, ClientType, Config, MessagingClient, MessagingService, MockClient,
+        MockMessage, Order, OrderType, Side, Timestamp,
+    };
+
+    fn timeout() -> ClientError {
+        ClientError::Timeout {
+            topic: "orders".to_string(),
+            timeout_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_per_topic() {
+        let client = MockClient::new();
+        client.produce("orders", "order-1").unwrap();
+        client.produce_bytes("fills", &[0, 159, 146, 150]).unwrap();
+        client.produce("orders", "order-2").unwrap();
+
+        assert_eq!(client.pending("orders"), 2);
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(client.consume_bytes("fills"), Ok(vec![0, 159, 146, 150]));
+        assert_eq!(
+            client.try_consume("orders"),
+            Ok(Some("order-2".to_string()))
+        );
+        assert_eq!(client.try_consume("orders"), Ok(None));
+        assert_eq!(client.pending("orders"), 0);
+    }
+
+    #[test]
+    fn test_keyed_messages_are_recorded() {
+        let client = MockClient::new();
+        client.produce_keyed("orders", "parent1", "order").unwrap();
+        client.produce("fills", "fill").unwrap();
+
+        assert_eq!(
+            client.sent(),
+            vec![
+                MockMessage {
+                    topic: "orders".to_string(),
+                    key: Some("parent1".to_string()),
+                    payload: b"order".to_vec(),
+                },
+                MockMessage {
+                    topic: "fills".to_string(),
+                    key: None,
+                    payload: b"fill".to_vec(),
+                },
+            ]
+        );
+        // Consuming does not forget what was sent
+        client.consume("orders").unwrap();
+        assert_eq!(client.sent().len(), 2);
+    }
+
+    #[test]
+    fn test_consume_waits_for_a_message() {
+        let client = MockClient::new().with_timeout(Duration::from_millis(10));
+        assert_eq!(client.consume("orders"), Err(timeout()));
+        assert_eq!(
+            client.consume_timeout("orders", Duration::from_millis(10)),
+            Ok(None)
+        );
+
+        let producer = client.clone();
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            producer.produce("orders", "order").unwrap();
+        });
+        assert_eq!(
+            client.consume_timeout("orders", Duration::from_secs(5)),
+            Ok(Some("order".to_string()))
+        );
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        let client = MockClient::new();
+        client.fail_next_produce(ClientError::Connection("broker down".to_string()));
+        client.fail_next_produce(ClientError::TopicNotFound("orders".to_string()));
+        client.fail_next_consume(timeout());
+
+        assert_eq!(
+            client.produce("orders", "order-1"),
+            Err(ClientError::Connection("broker down".to_string()))
+        );
+        assert_eq!(
+            client.produce_keyed("orders", "parent1", "order-1"),
+            Err(ClientError::TopicNotFound("orders".to_string()))
+        );
+        client.produce("orders", "order-2").unwrap();
+        // The failed receive leaves the message for the next one
+        assert_eq!(client.consume("orders"), Err(timeout()));
+        assert_eq!(client.consume("orders"), Ok("order-2".to_string()));
+        assert_eq!(client.sent().len(), 1);
+    }
+
+    #[test]
+    fn test_latency() {
+        let client = MockClient::new();
+        client.set_latency(Duration::from_millis(30));
+        let started = Instant::now();
+        client.produce("orders", "order").unwrap();
+        client.consume("orders").unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[test]
+    fn test_service_from_factory() {
+        // The in-memory client needs no configuration section
+        let service = MessagingService::new(ClientType::InMemory, &Config::default()).unwrap();
+        let order = Order::builder()
+            .id("parent1")
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+            .into_parent("strategy1");
+
+        service
+            .produce_parent_order("parent_orders", &order)
+            .unwrap();
+        let received = service.consume_parent_order("parent_orders").unwrap();
+        assert_eq!(received.to_string(), order.to_string());
+        assert_eq!(service.metrics().messages_consumed, 1);
+        assert!(service.health().is_ok());
+    }
+
+    #[test]
+    fn test_subscription() {
+        let client = MockClient::new();
+        let service = MessagingService::with_client(Box::new(client.clone()));
+        let count = Arc::new(AtomicUsize::new(0));
+        let handled = count.clone();
+        let subscription = service
+            .subscribe(
+                "orders",
+                Box::new(move |_message| {
+                    handled.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .unwrap();
+
+        for i in 0..3 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while count.load(Ordering::SeqCst) < 3 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        subscription.unsubscribe();
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+        assert_eq!(client.pending("orders"), 0);
+    }
+}
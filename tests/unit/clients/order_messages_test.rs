@@ -0,0 +1,271 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod order_messages_tests {
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ChildOrder, ClientError, EnvelopeMessage, Fill, Liquidity, MessageEnvelope,
+        MessagingService, MockClient, Order, OrderType, ParentOrder, Quantity, Side, Timestamp,
+        SCHEMA_VERSION,
+    };
+
+    fn service() -> MessagingService {
+        MessagingService::with_client(Box::new(MockClient::new().with_timeout(Duration::ZERO)))
+    }
+
+    fn order() -> Order {
+        Order::builder()
+            .id("parent1")
+            .quantity(100)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    fn parent_order() -> ParentOrder {
+        order().into_parent("strategy1")
+    }
+
+    fn child_order() -> ChildOrder {
+        let mut order = order();
+        order.id = "parent1-0".to_string();
+        order.into_child(
+            "strategy1",
+            "parent1",
+            Some(Timestamp::from_secs(1622512860)),
+        )
+    }
+
+    fn fill() -> Fill {
+        Fill::new(
+            "parent1-0".to_string(),
+            "exec1".to_string(),
+            dec!(101.5),
+            Quantity::from(40),
+            1622512870,
+            dec!(0.1),
+            Liquidity::Maker,
+        )
+    }
+
+    #[test]
+    fn test_parent_order_round_trip() {
+        let service = service();
+        let parent_order = parent_order();
+        service
+            .produce_parent_order("parent_orders", &parent_order)
+            .unwrap();
+        let received = service.consume_parent_order("parent_orders").unwrap();
+        assert_eq!(received.to_string(), parent_order.to_string());
+    }
+
+    #[test]
+    fn test_child_order_round_trip() {
+        let service = service();
+        let child_order = child_order();
+        service
+            .produce_child_order("child_orders", &child_order)
+            .unwrap();
+        let received = service.consume_child_order("child_orders").unwrap();
+        assert_eq!(received.to_string(), child_order.to_string());
+        assert_eq!(received.parent_id, "parent1");
+    }
+
+    #[test]
+    fn test_envelope_round_trip_per_message_type() {
+        let service = service();
+        service
+            .produce_parent_order("orders", &parent_order())
+            .unwrap();
+        service
+            .produce_child_order("orders", &child_order())
+            .unwrap();
+        service.produce_fill("orders", &fill(), "parent1").unwrap();
+
+        let received = service.consume_envelope("orders").unwrap();
+        assert_eq!(received.envelope.message_type, "parent_order");
+        assert_eq!(received.envelope.schema_version, SCHEMA_VERSION);
+        assert_eq!(received.envelope.correlation_id, "parent1");
+        match received.message {
+            EnvelopeMessage::ParentOrder(order) => {
+                assert_eq!(order.to_string(), parent_order().to_string())
+            }
+            other => panic!("expected a parent order, got {:?}", other),
+        }
+
+        let received = service.consume_envelope("orders").unwrap();
+        assert_eq!(received.envelope.message_type, "child_order");
+        // The child traces back to its parent
+        assert_eq!(received.envelope.correlation_id, "parent1");
+        match received.message {
+            EnvelopeMessage::ChildOrder(order) => {
+                assert_eq!(order.to_string(), child_order().to_string())
+            }
+            other => panic!("expected a child order, got {:?}", other),
+        }
+
+        let received = service.consume_envelope("orders").unwrap();
+        assert_eq!(received.envelope.message_type, "fill");
+        assert_eq!(received.envelope.correlation_id, "parent1");
+        match received.message {
+            EnvelopeMessage::Fill(received) => assert_eq!(received, fill()),
+            other => panic!("expected a fill, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_envelope_headers_round_trip() {
+        let service = service();
+        let envelope = MessageEnvelope::wrap(&fill(), "parent1")
+            .unwrap()
+            .with_header("venue", "XNAS")
+            .with_header("trace_id", "abc123");
+        service.produce_envelope("fills", &envelope).unwrap();
+
+        let received = service.consume_envelope("fills").unwrap();
+        assert_eq!(received.envelope, envelope);
+        assert_eq!(received.envelope.headers["venue"], "XNAS");
+        assert_eq!(service.consume_fill("fills").ok(), None);
+    }
+
+    #[test]
+    fn test_unknown_message_type_is_surfaced() {
+        let service = service();
+        let mut envelope = MessageEnvelope::wrap(&fill(), "parent1").unwrap();
+        envelope.message_type = "ticker".to_string();
+        envelope.payload = r#"{"bid":101.5,"ask":101.6}"#.to_string();
+        service.produce_envelope("market", &envelope).unwrap();
+
+        let received = service.consume_envelope("market").unwrap();
+        assert!(matches!(received.message, EnvelopeMessage::Unknown));
+        assert_eq!(received.envelope.message_type, "ticker");
+        assert_eq!(received.envelope.payload, r#"{"bid":101.5,"ask":101.6}"#);
+    }
+
+    #[test]
+    fn test_unexpected_message_type() {
+        let service = service();
+        service.produce_fill("orders", &fill(), "parent1").unwrap();
+        assert_eq!(
+            service.consume_parent_order("orders").unwrap_err(),
+            ClientError::UnexpectedMessageType {
+                expected: "parent_order",
+                found: "fill".to_string(),
+            }
+        );
+
+        // A bare order without an envelope is not accepted either
+        service.produce_json("orders", &parent_order()).unwrap();
+        assert!(matches!(
+            service.consume_parent_order("orders"),
+            Err(ClientError::Deserialization { .. })
+        ));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let service = service();
+        service.produce_json("fills", &vec![1, 2, 3]).unwrap();
+        assert_eq!(service.consume_json::<Vec<u32>>("fills"), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_invalid_order_is_not_sent() {
+        let service = service();
+        let mut parent_order = parent_order();
+        parent_order.strategy_id = String::new();
+        assert!(matches!(
+            service.produce_parent_order("parent_orders", &parent_order),
+            Err(ClientError::InvalidOrder(_))
+        ));
+        assert!(service.consume("parent_orders").is_err());
+    }
+
+    #[test]
+    fn test_invalid_order_is_rejected_on_receive() {
+        let service = service();
+        let mut child_order = child_order();
+        child_order.order_common.symbol = String::new();
+        // Sent without validation, as a foreign producer might
+        let envelope = MessageEnvelope::wrap(&child_order, "parent1").unwrap();
+        service.produce_envelope("child_orders", &envelope).unwrap();
+        service.produce_envelope("child_orders", &envelope).unwrap();
+        assert_eq!(
+            service.consume_child_order("child_orders").unwrap_err(),
+            ClientError::InvalidOrder("Symbol cannot be empty".to_string())
+        );
+        assert!(matches!(
+            service.consume_envelope("child_orders"),
+            Err(ClientError::InvalidOrder(_))
+        ));
+    }
+
+    #[test]
+    fn test_deserialization_error_carries_payload() {
+        let service = service();
+        service
+            .produce("parent_orders", r#"{"id":"parent1"}"#)
+            .unwrap();
+        match service.consume_parent_order("parent_orders") {
+            Err(ClientError::Deserialization { payload, .. }) => {
+                assert_eq!(payload, r#"{"id":"parent1"}"#)
+            }
+            other => panic!("unexpected result: {:?}", other.map(|o| o.to_string())),
+        }
+
+        // Long payloads are cut short
+        service.produce("parent_orders", &"x".repeat(1000)).unwrap();
+        match service.consume_json::<ParentOrder>("parent_orders") {
+            Err(ClientError::Deserialization { payload, reason }) => {
+                assert_eq!(payload, format!("{}...", "x".repeat(200)));
+                assert!(reason.starts_with("expected value"), "{}", reason);
+            }
+            other => panic!("unexpected result: {:?}", other.map(|o| o.to_string())),
+        }
+    }
+
+    #[test]
+    fn test_transport_errors_pass_through() {
+        let service = service();
+        let error = service.consume_json::<ParentOrder>("empty").unwrap_err();
+        assert_eq!(
+            error,
+            ClientError::Timeout {
+                topic: "empty".to_string(),
+                timeout_ms: 0,
+            }
+        );
+        assert!(error.is_retriable());
+    }
+}
@@ -0,0 +1,191 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod rabbitmq_client_tests {
+    use strategy_execution_engine::{
+        ClientError, MessagingClient, RabbitMQClient, RabbitMqClientError, RabbitMqConfig,
+    };
+
+    fn client(durable: bool, auto_ack: bool) -> RabbitMQClient {
+        RabbitMQClient::from_config(&RabbitMqConfig {
+            durable,
+            auto_ack,
+            ..RabbitMqConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_durable_options() {
+        let client = client(true, false);
+        assert_eq!(client.exchange(), "strategy_execution_engine");
+        assert!(client.exchange_options().durable);
+        assert!(!client.exchange_options().auto_delete);
+        assert!(client.queue_options().durable);
+        assert!(!client.queue_options().exclusive);
+        assert!(!client.get_options().no_ack);
+        assert_eq!(*client.message_properties().delivery_mode(), Some(2));
+    }
+
+    #[test]
+    fn test_transient_auto_ack_options() {
+        let client = client(false, true);
+        assert!(!client.exchange_options().durable);
+        assert!(!client.queue_options().durable);
+        assert!(client.get_options().no_ack);
+        assert_eq!(*client.message_properties().delivery_mode(), Some(1));
+    }
+
+    #[test]
+    fn test_invalid_url() {
+        let error = RabbitMQClient::from_config(&RabbitMqConfig {
+            rabbitmq_url: "http://localhost:5672".into(),
+            ..RabbitMqConfig::default()
+        })
+        .err()
+        .unwrap();
+        assert!(matches!(error, RabbitMqClientError::InvalidUrl(_)));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        let nack = RabbitMqClientError::Nack {
+            exchange: "orders".to_string(),
+            routing_key: "child_orders".to_string(),
+        };
+        assert!(nack.is_retryable());
+        assert_eq!(
+            nack.to_string(),
+            "RabbitMQ broker nacked the message to exchange orders with routing key child_orders"
+        );
+        let closed = RabbitMqClientError::ChannelClosed {
+            topic: "child_orders".to_string(),
+            reason: "invalid channel state".to_string(),
+        };
+        assert!(closed.is_retryable());
+        let declare = RabbitMqClientError::Declare {
+            exchange: "orders".to_string(),
+            queue: "child_orders".to_string(),
+            reason: "PRECONDITION_FAILED".to_string(),
+        };
+        assert!(!declare.is_retryable());
+
+        // Retryability carries over to the messaging layer
+        assert!(matches!(
+            ClientError::from(nack),
+            ClientError::Delivery {
+                retriable: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            ClientError::from(closed),
+            ClientError::Connection(_)
+        ));
+        assert!(!ClientError::from(declare).is_retriable());
+        assert_eq!(
+            ClientError::from(RabbitMqClientError::Empty {
+                queue: "child_orders".to_string()
+            }),
+            ClientError::Timeout {
+                topic: "child_orders".to_string(),
+                timeout_ms: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unreachable_broker() {
+        // Nothing listens on port 1
+        let client = RabbitMQClient::from_config(&RabbitMqConfig {
+            rabbitmq_url: "amqp://127.0.0.1:1/%2f".into(),
+            ..RabbitMqConfig::default()
+        })
+        .unwrap();
+        let error = client.produce("orders", "order").unwrap_err();
+        assert!(matches!(error, ClientError::Connection(_)), "{}", error);
+        assert!(error.to_string().starts_with("RabbitMQ connection error"));
+        assert!(client.consume("orders").is_err());
+        assert!(matches!(
+            client.try_consume("orders"),
+            Err(ClientError::Connection(_))
+        ));
+        assert!(matches!(
+            client.health_check(),
+            Err(ClientError::Connection(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_blocking_call_inside_runtime() {
+        let client = client(true, false);
+        assert_eq!(
+            client.consume_bytes("orders"),
+            Err(ClientError::Transport(
+                RabbitMqClientError::InsideRuntime.to_string()
+            ))
+        );
+    }
+
+    /// Runs against the broker in `RABBITMQ_URL`, e.g. `docker run -p 5672:5672 rabbitmq`.
+    #[cfg(feature = "integration-tests")]
+    #[test]
+    fn test_round_trip() {
+        let Ok(rabbitmq_url) = std::env::var("RABBITMQ_URL") else {
+            eprintln!("RABBITMQ_URL not set, skipping");
+            return;
+        };
+        let client = RabbitMQClient::from_config(&RabbitMqConfig {
+            rabbitmq_url: rabbitmq_url.into(),
+            exchange: "engine.test".to_string(),
+            durable: false,
+            auto_ack: false,
+            ..RabbitMqConfig::default()
+        })
+        .unwrap();
+
+        // The queue is declared on publish, so the message waits for the consumer
+        client.produce("engine.test.orders", "order-1").unwrap();
+        client.produce_bytes("engine.test.orders", &[0xff]).unwrap();
+        assert_eq!(
+            client.consume("engine.test.orders"),
+            Ok("order-1".to_string())
+        );
+        assert_eq!(client.consume_bytes("engine.test.orders"), Ok(vec![0xff]));
+        assert_eq!(
+            client.consume("engine.test.orders"),
+            Err(ClientError::Timeout {
+                topic: "engine.test.orders".to_string(),
+                timeout_ms: 0,
+            })
+        );
+        assert_eq!(client.try_consume("engine.test.orders"), Ok(None));
+    }
+}
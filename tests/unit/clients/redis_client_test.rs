@@ -0,0 +1,683 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod redis_client_tests {
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ClientError, MessagingClient, RedisClient, RedisClientError, RedisConfig, RedisConnection,
+        RedisMode, RequestMessage, StreamEntry,
+    };
+
+    /// In-memory stand-in for a Redis server.
+    #[derive(Default)]
+    struct Server {
+        lists: HashMap<String, VecDeque<Vec<u8>>>,
+        /// Messages a subscriber to the channel will receive
+        channels: HashMap<String, VecDeque<Vec<u8>>>,
+        published: Vec<(String, Vec<u8>)>,
+        streams: HashMap<String, Vec<StreamEntry>>,
+        groups: HashMap<(String, String), Group>,
+        timeouts: Vec<Duration>,
+        /// Channels subscribed to, once per SUBSCRIBE
+        subscribes: Vec<String>,
+        /// Whether BLPOP on an empty list waits out its timeout, as Redis does
+        blocking: bool,
+        connections: usize,
+        /// Number of upcoming commands that fail as if the socket was reset
+        failures: usize,
+    }
+
+    /// A consumer group: how far it has read and who holds the unacked entries since when.
+    #[derive(Default)]
+    struct Group {
+        delivered: usize,
+        pending: BTreeMap<String, (String, Instant)>,
+    }
+
+    impl Server {
+        fn entry(&self, stream: &str, id: &str) -> StreamEntry {
+            self.streams[stream]
+                .iter()
+                .find(|entry| entry.id == id)
+                .unwrap()
+                .clone()
+        }
+
+        fn pending(&self, stream: &str) -> Vec<(String, String)> {
+            self.groups[&(stream.to_string(), "default-group".to_string())]
+                .pending
+                .iter()
+                .map(|(id, (consumer, _))| (id.clone(), consumer.clone()))
+                .collect()
+        }
+    }
+
+    struct MockConnection {
+        server: Arc<Mutex<Server>>,
+        channels: Vec<String>,
+    }
+
+    impl MockConnection {
+        fn run<T>(&self, command: impl FnOnce(&mut Server) -> T) -> Result<T, RedisClientError> {
+            let mut server = self.server.lock().unwrap();
+            if server.failures > 0 {
+                server.failures -= 1;
+                return Err(RedisClientError::Connection(
+                    "connection reset by peer".to_string(),
+                ));
+            }
+            Ok(command(&mut server))
+        }
+    }
+
+    impl RedisConnection for MockConnection {
+        fn publish(&mut self, channel: &str, payload: &[u8]) -> Result<(), RedisClientError> {
+            self.run(|server| {
+                server
+                    .published
+                    .push((channel.to_string(), payload.to_vec()))
+            })
+        }
+
+        fn rpush(&mut self, key: &str, payload: &[u8]) -> Result<(), RedisClientError> {
+            self.run(|server| {
+                server
+                    .lists
+                    .entry(key.to_string())
+                    .or_default()
+                    .push_back(payload.to_vec())
+            })
+        }
+
+        fn blpop(
+            &mut self,
+            key: &str,
+            timeout: Duration,
+        ) -> Result<Option<Vec<u8>>, RedisClientError> {
+            let started = Instant::now();
+            let blocking = self.run(|server| {
+                server.timeouts.push(timeout);
+                server.blocking
+            })?;
+            loop {
+                let popped =
+                    self.run(|server| server.lists.get_mut(key).and_then(VecDeque::pop_front))?;
+                if popped.is_some() || !blocking || started.elapsed() >= timeout {
+                    return Ok(popped);
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        fn subscribe(&mut self, channel: &str) -> Result<(), RedisClientError> {
+            self.run(|server| server.subscribes.push(channel.to_string()))?;
+            self.channels.push(channel.to_string());
+            Ok(())
+        }
+
+        fn next_message(
+            &mut self,
+            timeout: Duration,
+        ) -> Result<Option<(String, Vec<u8>)>, RedisClientError> {
+            let channels = &self.channels;
+            self.run(|server| {
+                server.timeouts.push(timeout);
+                channels.iter().find_map(|channel| {
+                    let payload = server.channels.get_mut(channel)?.pop_front()?;
+                    Some((channel.clone(), payload))
+                })
+            })
+        }
+
+        fn xadd(&mut self, stream: &str, payload: &[u8]) -> Result<String, RedisClientError> {
+            self.run(|server| {
+                let entries = server.streams.entry(stream.to_string()).or_default();
+                let id = format!("{}-0", entries.len() + 1);
+                entries.push(StreamEntry {
+                    id: id.clone(),
+                    payload: payload.to_vec(),
+                });
+                id
+            })
+        }
+
+        fn create_group(&mut self, stream: &str, group: &str) -> Result<(), RedisClientError> {
+            self.run(|server| {
+                server.streams.entry(stream.to_string()).or_default();
+                server
+                    .groups
+                    .entry((stream.to_string(), group.to_string()))
+                    .or_default();
+            })
+        }
+
+        fn xreadgroup(
+            &mut self,
+            stream: &str,
+            group: &str,
+            consumer: &str,
+            timeout: Duration,
+        ) -> Result<Option<StreamEntry>, RedisClientError> {
+            self.run(|server| {
+                server.timeouts.push(timeout);
+                let Server {
+                    streams, groups, ..
+                } = server;
+                let group = groups
+                    .get_mut(&(stream.to_string(), group.to_string()))
+                    .expect("XREADGROUP on a missing group");
+                let entry = streams[stream].get(group.delivered)?.clone();
+                group.delivered += 1;
+                group
+                    .pending
+                    .insert(entry.id.clone(), (consumer.to_string(), Instant::now()));
+                Some(entry)
+            })
+        }
+
+        fn xack(&mut self, stream: &str, group: &str, id: &str) -> Result<(), RedisClientError> {
+            self.run(|server| {
+                if let Some(group) = server
+                    .groups
+                    .get_mut(&(stream.to_string(), group.to_string()))
+                {
+                    group.pending.remove(id);
+                }
+            })
+        }
+
+        fn ping(&mut self) -> Result<(), RedisClientError> {
+            self.run(|_server| ())
+        }
+
+        fn server_version(&mut self) -> Result<Option<String>, RedisClientError> {
+            self.run(|_server| Some("7.2.4".to_string()))
+        }
+
+        fn xautoclaim(
+            &mut self,
+            stream: &str,
+            group: &str,
+            consumer: &str,
+            min_idle: Duration,
+        ) -> Result<Vec<StreamEntry>, RedisClientError> {
+            self.run(|server| {
+                let group = server
+                    .groups
+                    .get_mut(&(stream.to_string(), group.to_string()))
+                    .expect("XAUTOCLAIM on a missing group");
+                let mut claimed = Vec::new();
+                for (id, (owner, since)) in group.pending.iter_mut() {
+                    if since.elapsed() >= min_idle {
+                        *owner = consumer.to_string();
+                        *since = Instant::now();
+                        claimed.push(id.clone());
+                    }
+                }
+                claimed
+            })
+            .map(|claimed| {
+                let server = self.server.lock().unwrap();
+                claimed.iter().map(|id| server.entry(stream, id)).collect()
+            })
+        }
+    }
+
+    fn client(mode: RedisMode) -> (RedisClient, Arc<Mutex<Server>>) {
+        let server = Arc::new(Mutex::new(Server::default()));
+        let config = RedisConfig {
+            mode,
+            timeout_ms: 250,
+            ..RedisConfig::default()
+        };
+        (connect(&server, config), server)
+    }
+
+    fn connect(server: &Arc<Mutex<Server>>, config: RedisConfig) -> RedisClient {
+        let shared = server.clone();
+        RedisClient::with_connector(config, move || {
+            shared.lock().unwrap().connections += 1;
+            let connection: Box<dyn RedisConnection> = Box::new(MockConnection {
+                server: shared.clone(),
+                channels: Vec::new(),
+            });
+            Ok(connection)
+        })
+    }
+
+    #[test]
+    fn test_queue_mode_round_trip() {
+        let (client, server) = client(RedisMode::Queue);
+        assert_eq!(client.mode(), RedisMode::Queue);
+        client.produce("orders", "order-1").unwrap();
+        client.produce_bytes("orders", &[0xff, 0x00]).unwrap();
+
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        // Redis values are binary safe, unlike the text-only stub transports
+        assert_eq!(client.consume_bytes("orders"), Ok(vec![0xff, 0x00]));
+
+        let server = server.lock().unwrap();
+        assert!(server.published.is_empty());
+        // One connection for commands, one for blocking reads
+        assert_eq!(server.connections, 2);
+        assert_eq!(server.timeouts, vec![Duration::from_millis(250); 2]);
+    }
+
+    #[test]
+    fn test_pubsub_mode() {
+        let (client, server) = client(RedisMode::PubSub);
+        client.produce("fills", "fill-1").unwrap();
+        server
+            .lock()
+            .unwrap()
+            .channels
+            .entry("fills".to_string())
+            .or_default()
+            .push_back(b"fill-2".to_vec());
+
+        assert_eq!(client.consume("fills"), Ok("fill-2".to_string()));
+        let server = server.lock().unwrap();
+        assert_eq!(
+            server.published,
+            vec![("fills".to_string(), b"fill-1".to_vec())]
+        );
+        assert!(server.lists.is_empty());
+    }
+
+    #[test]
+    fn test_pubsub_subscription_is_kept_between_reads() {
+        let (client, server) = client(RedisMode::PubSub);
+        let publish = |channel: &str, payload: &str| {
+            server
+                .lock()
+                .unwrap()
+                .channels
+                .entry(channel.to_string())
+                .or_default()
+                .push_back(payload.as_bytes().to_vec())
+        };
+        assert_eq!(client.try_consume("fills"), Ok(None));
+        assert_eq!(client.try_consume("quotes"), Ok(None));
+
+        // A fill published while quotes are read is kept for the next read of fills
+        publish("fills", "fill-1");
+        publish("quotes", "quote-1");
+        publish("fills", "fill-2");
+        assert_eq!(client.consume("quotes"), Ok("quote-1".to_string()));
+        assert_eq!(client.consume("fills"), Ok("fill-1".to_string()));
+        assert_eq!(client.consume("fills"), Ok("fill-2".to_string()));
+
+        let server = server.lock().unwrap();
+        assert_eq!(
+            server.subscribes,
+            vec!["fills".to_string(), "quotes".to_string()]
+        );
+        assert_eq!(server.connections, 1);
+    }
+
+    #[test]
+    fn test_blocking_read_does_not_hold_up_producers() {
+        let server = Arc::new(Mutex::new(Server {
+            blocking: true,
+            ..Server::default()
+        }));
+        let client = Arc::new(connect(
+            &server,
+            RedisConfig {
+                mode: RedisMode::Queue,
+                timeout_ms: 2_000,
+                ..RedisConfig::default()
+            },
+        ));
+        let waiting = {
+            let client = client.clone();
+            std::thread::spawn(move || client.consume("orders"))
+        };
+        while server.lock().unwrap().timeouts.is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let started = Instant::now();
+        client.produce("orders", "order-1").unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(waiting.join().unwrap(), Ok("order-1".to_string()));
+    }
+
+    #[test]
+    fn test_stream_mode_round_trip() {
+        let (client, server) = client(RedisMode::Stream);
+        client.produce("orders", "order-1").unwrap();
+        client.produce_bytes("orders", &[0xff, 0x00]).unwrap();
+
+        // consume acks what it reads, leaving nothing pending
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(client.consume_bytes("orders"), Ok(vec![0xff, 0x00]));
+        assert_eq!(client.try_consume("orders"), Ok(None));
+
+        let server = server.lock().unwrap();
+        assert_eq!(server.streams["stream:orders"].len(), 2);
+        assert!(server.pending("stream:orders").is_empty());
+        assert!(server.lists.is_empty() && server.published.is_empty());
+    }
+
+    #[test]
+    fn test_stream_entries_stay_pending_until_acked() {
+        let (client, server) = client(RedisMode::Stream);
+        client.produce("orders", "order-1").unwrap();
+
+        let entry = client.receive_entry("orders").unwrap();
+        assert_eq!(
+            entry,
+            StreamEntry {
+                id: "1-0".to_string(),
+                payload: b"order-1".to_vec(),
+            }
+        );
+        assert_eq!(
+            server.lock().unwrap().pending("stream:orders"),
+            vec![("1-0".to_string(), "default-consumer".to_string())]
+        );
+        // A pending entry is not delivered again by a plain read
+        assert_eq!(
+            client.receive_entry("orders"),
+            Err(RedisClientError::Timeout {
+                topic: "orders".to_string(),
+                timeout_ms: 250,
+            })
+        );
+
+        client.ack("orders", &entry.id).unwrap();
+        assert!(server.lock().unwrap().pending("stream:orders").is_empty());
+    }
+
+    #[test]
+    fn test_unacked_entries_are_claimed_by_another_consumer() {
+        let (crashed, server) = client(RedisMode::Stream);
+        let survivor = connect(
+            &server,
+            RedisConfig {
+                mode: RedisMode::Stream,
+                consumer_name: "survivor".to_string(),
+                ..RedisConfig::default()
+            },
+        );
+        crashed.produce("orders", "order-1").unwrap();
+        crashed.produce("orders", "order-2").unwrap();
+        let lost = crashed.receive_entry("orders").unwrap();
+
+        // The group hands each entry out once, so the survivor only sees the next one
+        assert_eq!(survivor.consume("orders"), Ok("order-2".to_string()));
+        assert_eq!(
+            survivor.claim_pending("orders", Duration::from_secs(3600)),
+            Ok(vec![])
+        );
+
+        let claimed = survivor.claim_pending("orders", Duration::ZERO).unwrap();
+        assert_eq!(claimed, vec![lost.clone()]);
+        assert_eq!(
+            server.lock().unwrap().pending("stream:orders"),
+            vec![(lost.id.clone(), "survivor".to_string())]
+        );
+        survivor.ack("orders", &lost.id).unwrap();
+        assert_eq!(survivor.claim_pending("orders", Duration::ZERO), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_stream_calls_require_stream_mode() {
+        let (client, server) = client(RedisMode::Queue);
+        assert!(matches!(
+            client.ack("orders", "1-0"),
+            Err(RedisClientError::Command(_))
+        ));
+        assert!(matches!(
+            client.claim_pending("orders", Duration::ZERO),
+            Err(RedisClientError::Command(_))
+        ));
+        assert!(client.receive_entry("orders").is_err());
+        assert_eq!(server.lock().unwrap().connections, 0);
+    }
+
+    #[test]
+    fn test_consume_timeout() {
+        for mode in [RedisMode::Queue, RedisMode::PubSub] {
+            let (client, _server) = client(mode);
+            assert_eq!(
+                client.receive("orders"),
+                Err(RedisClientError::Timeout {
+                    topic: "orders".to_string(),
+                    timeout_ms: 250,
+                })
+            );
+            assert_eq!(
+                client.consume("orders"),
+                Err(ClientError::Timeout {
+                    topic: "orders".to_string(),
+                    timeout_ms: 250,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_consume() {
+        for mode in [RedisMode::Queue, RedisMode::PubSub] {
+            let (client, server) = client(mode);
+            assert_eq!(client.try_consume("orders"), Ok(None));
+            assert_eq!(
+                client.consume_timeout("orders", Duration::from_millis(20)),
+                Ok(None)
+            );
+            // Zero would make Redis block forever, so the shortest real wait is used instead
+            assert_eq!(
+                server.lock().unwrap().timeouts,
+                vec![Duration::from_millis(1), Duration::from_millis(20)]
+            );
+
+            let mut server = server.lock().unwrap();
+            let waiting = match mode {
+                RedisMode::Queue => &mut server.lists,
+                RedisMode::PubSub => &mut server.channels,
+                RedisMode::Stream => unreachable!("streams are covered by their own tests"),
+            };
+            waiting
+                .entry("orders".to_string())
+                .or_default()
+                .extend([b"order".to_vec(), vec![0xff]]);
+            drop(server);
+            assert_eq!(client.try_consume("orders"), Ok(Some("order".to_string())));
+            assert!(matches!(
+                client.try_consume("orders"),
+                Err(ClientError::InvalidPayload(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_keys_are_ignored() {
+        let (client, server) = client(RedisMode::Queue);
+        client
+            .produce_keyed("orders", "parent1", "order-1")
+            .unwrap();
+        client
+            .produce_bytes_keyed("orders", "parent1", b"order-2")
+            .unwrap();
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(client.consume("orders"), Ok("order-2".to_string()));
+        assert_eq!(server.lock().unwrap().connections, 2);
+    }
+
+    #[test]
+    fn test_reconnects_on_broken_connection() {
+        let (client, server) = client(RedisMode::Queue);
+        client.produce("orders", "order-1").unwrap();
+        server.lock().unwrap().failures = 1;
+
+        client.produce("orders", "order-2").unwrap();
+        assert_eq!(server.lock().unwrap().connections, 2);
+        assert_eq!(server.lock().unwrap().lists["orders"].len(), 2);
+
+        // A connection that breaks again after reconnecting is reported, not retried forever
+        server.lock().unwrap().failures = 2;
+        let error = client.produce("orders", "order-3").unwrap_err();
+        assert!(matches!(error, ClientError::Connection(_)), "{}", error);
+        assert!(error.is_retriable());
+        assert_eq!(client.consume("orders"), Ok("order-1".to_string()));
+        assert_eq!(server.lock().unwrap().connections, 4);
+    }
+
+    #[test]
+    fn test_health_check() {
+        let (client, server) = client(RedisMode::Queue);
+        let status = client.health_check().unwrap();
+        assert_eq!(status.version.as_deref(), Some("7.2.4"));
+
+        // A single broken connection is recovered from, a dead server is not
+        server.lock().unwrap().failures = 1;
+        assert!(client.health_check().is_ok());
+        server.lock().unwrap().failures = 2;
+        assert!(matches!(
+            client.health_check(),
+            Err(ClientError::Connection(_))
+        ));
+
+        let client = RedisClient::new(RedisConfig {
+            redis_url: "redis://127.0.0.1:1".into(),
+            ..RedisConfig::default()
+        });
+        assert!(matches!(
+            client.health_check(),
+            Err(ClientError::Connection(_))
+        ));
+    }
+
+    #[test]
+    fn test_request_reply_over_lists() {
+        let (client, server) = client(RedisMode::Queue);
+        let gateway = std::thread::spawn(move || loop {
+            let mut server = server.lock().unwrap();
+            let Some(request) = server
+                .lists
+                .get_mut("gateway")
+                .and_then(VecDeque::pop_front)
+            else {
+                drop(server);
+                std::thread::sleep(Duration::from_millis(1));
+                continue;
+            };
+            let request: RequestMessage = serde_json::from_slice(&request).unwrap();
+            let reply = serde_json::to_vec(&request.reply("accepted")).unwrap();
+            server
+                .lists
+                .entry(request.reply_to)
+                .or_default()
+                .push_back(reply);
+            return;
+        });
+        assert_eq!(
+            client.request("gateway", "child-1", Duration::from_secs(2)),
+            Ok("accepted".to_string())
+        );
+        gateway.join().unwrap();
+
+        // Replies published on a channel between two reads would be lost
+        let (client, _) = self::client(RedisMode::PubSub);
+        assert!(matches!(
+            client.request("gateway", "child-1", Duration::from_secs(2)),
+            Err(ClientError::Transport(_))
+        ));
+    }
+
+    #[test]
+    fn test_connect_failures_are_errors() {
+        let client = RedisClient::new(RedisConfig {
+            redis_url: "not a redis url".into(),
+            ..RedisConfig::default()
+        });
+        assert!(client.produce("orders", "order").is_err());
+
+        // Nothing listens on port 1
+        let client = RedisClient::new(RedisConfig {
+            redis_url: "redis://127.0.0.1:1".into(),
+            ..RedisConfig::default()
+        });
+        assert!(matches!(
+            client.receive("orders"),
+            Err(RedisClientError::Connection(_))
+        ));
+        assert!(client.consume("orders").unwrap_err().is_retriable());
+        let command = ClientError::from(RedisClientError::Command("WRONGTYPE".to_string()));
+        assert!(matches!(command, ClientError::Transport(_)));
+        assert!(!command.is_retriable());
+    }
+
+    /// Runs against the server in `REDIS_URL`, e.g. `docker run -p 6379:6379 redis`.
+    #[cfg(feature = "integration-tests")]
+    #[test]
+    fn test_stream_round_trip_and_redelivery() {
+        let Ok(redis_url) = std::env::var("REDIS_URL") else {
+            eprintln!("REDIS_URL not set, skipping");
+            return;
+        };
+        // A fresh prefix per run, so entries left by earlier runs are not read back
+        let stream_prefix = format!("engine:test:{}:", uuid::Uuid::now_v7());
+        let consumer = |consumer_name: &str| {
+            RedisClient::new(RedisConfig {
+                redis_url: redis_url.as_str().into(),
+                mode: RedisMode::Stream,
+                timeout_ms: 500,
+                stream_prefix: stream_prefix.clone(),
+                consumer_group: "engine-test".to_string(),
+                consumer_name: consumer_name.to_string(),
+                ..RedisConfig::default()
+            })
+        };
+        let first = consumer("first");
+        let second = consumer("second");
+
+        first.produce("orders", "order-1").unwrap();
+        let entry = first.receive_entry("orders").unwrap();
+        assert_eq!(entry.payload, b"order-1");
+        first.ack("orders", &entry.id).unwrap();
+        assert_eq!(second.claim_pending("orders", Duration::ZERO), Ok(vec![]));
+
+        // The first consumer reads without acking, as if it crashed while handling the entry
+        first.produce("orders", "order-2").unwrap();
+        let unacked = first.receive_entry("orders").unwrap();
+        assert_eq!(second.try_consume("orders"), Ok(None));
+        std::thread::sleep(Duration::from_millis(50));
+        let claimed = second
+            .claim_pending("orders", Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(claimed, vec![unacked.clone()]);
+        second.ack("orders", &unacked.id).unwrap();
+        assert_eq!(second.claim_pending("orders", Duration::ZERO), Ok(vec![]));
+    }
+}
@@ -0,0 +1,214 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+#[cfg(test)]
+mod request_reply_tests {
+    use crate::support::service;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+    use strategy_execution_engine::{
+        ClientError, MessagingClient, MockClient, ReplyMessage, RequestMessage,
+    };
+
+    /// What the execution gateway answers to a child order.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct OrderAck {
+        order_id: String,
+        accepted: bool,
+        reason: Option<String>,
+    }
+
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn next_request(client: &MockClient) -> RequestMessage {
+        serde_json::from_str(&client.consume("gateway").unwrap()).unwrap()
+    }
+
+    fn send_reply(client: &MockClient, request: &RequestMessage, reply: &ReplyMessage) {
+        client
+            .produce(&request.reply_to, &serde_json::to_string(reply).unwrap())
+            .unwrap();
+    }
+
+    /// A gateway that takes `requests` requests off the gateway topic, then answers each
+    /// with the replies `script` makes up for it.
+    fn responder(
+        client: &MockClient,
+        requests: usize,
+        script: impl Fn(&RequestMessage) -> Vec<ReplyMessage> + Send + 'static,
+    ) -> JoinHandle<()> {
+        let client = client.clone();
+        std::thread::spawn(move || {
+            let requests: Vec<RequestMessage> =
+                (0..requests).map(|_| next_request(&client)).collect();
+            for request in requests.iter().rev() {
+                for reply in script(request) {
+                    send_reply(&client, request, &reply);
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_request_json_returns_the_acknowledgment() {
+        let (service, client) = service();
+        let gateway = responder(&client, 1, |request| {
+            let order: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let ack = OrderAck {
+                order_id: order["order_id"].as_str().unwrap().to_string(),
+                accepted: false,
+                reason: Some("insufficient margin".to_string()),
+            };
+            vec![request.reply(&serde_json::to_string(&ack).unwrap())]
+        });
+
+        let ack: OrderAck = service
+            .request_json(
+                "gateway",
+                &serde_json::json!({ "order_id": "child-1" }),
+                REPLY_TIMEOUT,
+            )
+            .unwrap();
+        gateway.join().unwrap();
+        assert_eq!(
+            ack,
+            OrderAck {
+                order_id: "child-1".to_string(),
+                accepted: false,
+                reason: Some("insufficient margin".to_string()),
+            }
+        );
+
+        // The request is keyed by its correlation id and names the client's reply topic
+        let sent = &client.sent()[0];
+        let request: RequestMessage = serde_json::from_slice(&sent.payload).unwrap();
+        assert_eq!(sent.key.as_deref(), Some(request.correlation_id.as_str()));
+        assert_eq!(request.reply_to, client.reply_topic());
+        assert_eq!(request.body, r#"{"order_id":"child-1"}"#);
+
+        let metrics = service.metrics();
+        assert_eq!(metrics.topics["gateway"].messages_produced, 1);
+        assert_eq!(metrics.topics["gateway"].messages_consumed, 1);
+        assert_eq!(metrics.late_replies, 0);
+    }
+
+    #[test]
+    fn test_timeout_and_late_reply() {
+        let (service, client) = service();
+        assert_eq!(
+            service.request("gateway", "child-1", Duration::from_millis(50)),
+            Err(ClientError::Timeout {
+                topic: "gateway".to_string(),
+                timeout_ms: 50,
+            })
+        );
+        assert_eq!(service.metrics().consume_errors, 1);
+
+        // The reply to the first request only arrives while the second one waits
+        let late = next_request(&client);
+        send_reply(&client, &late, &late.reply("accepted"));
+        let gateway = responder(&client, 1, |request| vec![request.reply("rejected")]);
+        assert_eq!(
+            service.request("gateway", "child-2", REPLY_TIMEOUT),
+            Ok("rejected".to_string())
+        );
+        gateway.join().unwrap();
+        assert_eq!(service.metrics().late_replies, 1);
+        assert_eq!(client.late_replies(), 1);
+
+        service.reset_metrics();
+        assert_eq!(service.metrics().late_replies, 0);
+    }
+
+    #[test]
+    fn test_mismatched_replies_are_dropped() {
+        let (service, client) = service();
+        let gateway = responder(&client, 1, |request| {
+            let mut stranger = request.reply("not yours");
+            stranger.correlation_id.push_str("-other");
+            vec![stranger, request.reply("accepted"), request.reply("again")]
+        });
+        assert_eq!(
+            service.request("gateway", "child-1", REPLY_TIMEOUT),
+            Ok("accepted".to_string())
+        );
+        gateway.join().unwrap();
+        // Not a reply at all
+        client.produce(client.reply_topic(), "garbage").unwrap();
+        let gateway = responder(&client, 1, |request| vec![request.reply("accepted")]);
+        service
+            .request("gateway", "child-2", REPLY_TIMEOUT)
+            .unwrap();
+        gateway.join().unwrap();
+
+        // The stranger before the reply, the duplicate after it, and the garbage
+        assert_eq!(service.metrics().late_replies, 3);
+    }
+
+    #[test]
+    fn test_concurrent_requests_get_their_own_replies() {
+        let (service, client) = service();
+        let service = Arc::new(service);
+        // Answers the requests in the opposite order they were taken
+        let gateway = responder(&client, 4, |request| {
+            vec![request.reply(&request.body.to_uppercase())]
+        });
+
+        let requesters: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|body| {
+                let service = service.clone();
+                std::thread::spawn(move || service.request("gateway", body, REPLY_TIMEOUT))
+            })
+            .collect();
+        let replies: Vec<_> = requesters
+            .into_iter()
+            .map(|requester| requester.join().unwrap())
+            .collect();
+        gateway.join().unwrap();
+
+        assert_eq!(
+            replies,
+            ["A", "B", "C", "D"].map(|reply| Ok(reply.to_string()))
+        );
+        assert_eq!(service.metrics().late_replies, 0);
+    }
+
+    #[test]
+    fn test_failed_request_is_counted_as_produce_error() {
+        let (service, client) = service();
+        client.fail_next_produce(ClientError::Connection("broker down".to_string()));
+        assert_eq!(
+            service.request("gateway", "child-1", REPLY_TIMEOUT),
+            Err(ClientError::Connection("broker down".to_string()))
+        );
+        assert_eq!(service.metrics().produce_errors, 1);
+        assert_eq!(client.pending("gateway"), 0);
+    }
+}
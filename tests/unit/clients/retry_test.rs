@@ -0,0 +1,151 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod retry_tests {
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ClientError, ClientType, Config, MessagingService, MockClient, RedisConfig, RetryPolicy,
+        TopicsConfig,
+    };
+
+    fn service(max_retries: u32) -> (MessagingService, MockClient) {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut service = MessagingService::with_client(Box::new(client.clone()));
+        service.set_retry_policy(RetryPolicy {
+            max_retries,
+            backoff_ms: 20,
+            max_backoff_ms: 30,
+        });
+        (service, client)
+    }
+
+    fn connection_lost() -> ClientError {
+        ClientError::Connection("connection reset".to_string())
+    }
+
+    #[test]
+    fn test_retriable_produce_errors_are_retried() {
+        let (service, client) = service(3);
+        client.fail_next_produce(connection_lost());
+        client.fail_next_produce(connection_lost());
+
+        let started = Instant::now();
+        service.produce("orders", "order-1").unwrap();
+        // Waited 20 ms, then twice that capped at 30 ms
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(client.sent().len(), 1);
+
+        let metrics = service.metrics();
+        assert_eq!(metrics.produce_errors, 2);
+        assert_eq!(metrics.messages_produced, 1);
+    }
+
+    #[test]
+    fn test_produce_gives_up_after_max_retries() {
+        let (service, client) = service(1);
+        client.fail_next_produce(connection_lost());
+        client.fail_next_produce(connection_lost());
+
+        assert_eq!(
+            service.produce_json("orders", &"order-1"),
+            Err(connection_lost())
+        );
+        assert!(client.sent().is_empty());
+        assert_eq!(service.metrics().produce_errors, 2);
+    }
+
+    #[test]
+    fn test_other_errors_are_not_retried() {
+        let (service, client) = service(3);
+        let rejected = ClientError::Delivery {
+            retriable: false,
+            detail: "message too large".to_string(),
+        };
+        client.fail_next_produce(rejected.clone());
+
+        assert_eq!(service.produce("orders", "order-1"), Err(rejected));
+        assert_eq!(service.metrics().produce_errors, 1);
+        assert!(client.sent().is_empty());
+    }
+
+    #[test]
+    fn test_no_retries_by_default() {
+        let client = MockClient::new();
+        let service = MessagingService::with_client(Box::new(client.clone()));
+        assert_eq!(service.retry_policy(), &RetryPolicy::default());
+        assert_eq!(service.topics(), &TopicsConfig::default());
+
+        client.fail_next_produce(connection_lost());
+        assert_eq!(service.produce("orders", "order-1"), Err(connection_lost()));
+        assert_eq!(service.metrics().produce_errors, 1);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            backoff_ms: 100,
+            max_backoff_ms: 1000,
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(70), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_service_takes_settings_of_its_section() {
+        let topics = TopicsConfig {
+            parent_orders: "engine.parents".to_string(),
+            ..TopicsConfig::default()
+        };
+        let retry = RetryPolicy {
+            max_retries: 5,
+            ..RetryPolicy::default()
+        };
+        let config = Config {
+            redis: Some(RedisConfig {
+                topics: topics.clone(),
+                retry: retry.clone(),
+                ..RedisConfig::default()
+            }),
+            ..Config::default()
+        };
+
+        // The Redis client connects on first use, so no server is needed here
+        let service = MessagingService::new(ClientType::Redis, &config).unwrap();
+        assert_eq!(service.topics(), &topics);
+        assert_eq!(service.retry_policy(), &retry);
+        service.close(Duration::from_secs(1)).unwrap();
+
+        let service = MessagingService::new(ClientType::InMemory, &config).unwrap();
+        assert_eq!(service.topics(), &TopicsConfig::default());
+    }
+}
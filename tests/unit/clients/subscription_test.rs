@@ -0,0 +1,151 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod subscription_tests {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{ClientError, MessagingClient, MessagingService};
+
+    /// An in-process queue per client, shared by producer and consumer.
+    #[derive(Default)]
+    struct ChannelClient {
+        messages: Mutex<VecDeque<String>>,
+        arrived: Condvar,
+    }
+
+    impl MessagingClient for ChannelClient {
+        fn produce(&self, _topic: &str, message: &str) -> Result<(), ClientError> {
+            self.messages.lock().unwrap().push_back(message.to_string());
+            self.arrived.notify_all();
+            Ok(())
+        }
+
+        fn consume(&self, topic: &str) -> Result<String, ClientError> {
+            self.consume_timeout(topic, Duration::from_secs(1))?
+                .ok_or_else(|| ClientError::Timeout {
+                    topic: topic.to_string(),
+                    timeout_ms: 1000,
+                })
+        }
+
+        fn consume_timeout(
+            &self,
+            _topic: &str,
+            timeout: Duration,
+        ) -> Result<Option<String>, ClientError> {
+            let messages = self.messages.lock().unwrap();
+            let (mut messages, _) = self
+                .arrived
+                .wait_timeout_while(messages, timeout, |messages| messages.is_empty())
+                .unwrap();
+            Ok(messages.pop_front())
+        }
+    }
+
+    fn counter() -> (Arc<AtomicUsize>, Box<dyn Fn(String) + Send + Sync>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handled = count.clone();
+        let handler = Box::new(move |_message: String| {
+            handled.fetch_add(1, Ordering::SeqCst);
+        });
+        (count, handler)
+    }
+
+    fn wait_for(count: &AtomicUsize, expected: usize) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while count.load(Ordering::SeqCst) < expected && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_handler_runs_for_every_message() {
+        let client = Arc::new(ChannelClient::default());
+        let (count, handler) = counter();
+        let subscription = client.clone().subscribe("orders", handler).unwrap();
+        assert_eq!(subscription.topic(), "orders");
+
+        for i in 0..5 {
+            client.produce("orders", &format!("order-{i}")).unwrap();
+        }
+        wait_for(&count, 5);
+        assert_eq!(count.load(Ordering::SeqCst), 5);
+
+        assert!(subscription.is_active());
+        subscription.unsubscribe();
+        client.produce("orders", "order-5").unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(count.load(Ordering::SeqCst), 5);
+        // Left for the next consumer
+        assert_eq!(
+            client.try_consume("orders"),
+            Ok(Some("order-5".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_dropping_handle_unsubscribes() {
+        let service = MessagingService::with_client(Box::new(ChannelClient::default()));
+        let (count, handler) = counter();
+        let subscription = service.subscribe("orders", handler).unwrap();
+        service.produce("orders", "order-1").unwrap();
+        wait_for(&count, 1);
+
+        drop(subscription);
+        service.produce("orders", "order-2").unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(service.consume("orders"), Ok("order-2".to_string()));
+    }
+
+    #[test]
+    fn test_messages_are_passed_in_order() {
+        let client = Arc::new(ChannelClient::default());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let subscription = client
+            .clone()
+            .subscribe(
+                "orders",
+                Box::new(move |message| sink.lock().unwrap().push(message)),
+            )
+            .unwrap();
+
+        client.produce("orders", "order-1").unwrap();
+        client.produce("orders", "order-2").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while received.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        subscription.unsubscribe();
+        assert_eq!(*received.lock().unwrap(), vec!["order-1", "order-2"]);
+    }
+}
@@ -0,0 +1,202 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod zeromq_client_tests {
+    use std::time::{Duration, Instant};
+    use strategy_execution_engine::{
+        ClientError, MessagingClient, SocketMode, ZeroMQClient, ZeroMqClientError, ZeroMqConfig,
+    };
+
+    fn pair(endpoint: &str, mode: SocketMode) -> (ZeroMQClient, ZeroMQClient) {
+        // inproc endpoints only connect sockets of the same context
+        let context = zmq::Context::new();
+        let config = |bind| ZeroMqConfig {
+            zmq_url: endpoint.to_string(),
+            mode,
+            bind,
+            timeout_ms: 200,
+            ..ZeroMqConfig::default()
+        };
+        (
+            ZeroMQClient::with_context(&config(true), context.clone()),
+            ZeroMQClient::with_context(&config(false), context),
+        )
+    }
+
+    #[test]
+    fn test_pub_sub_topic_filtering() {
+        let (publisher, subscriber) = pair("inproc://pub_sub", SocketMode::PubSub);
+        assert_eq!(subscriber.mode(), SocketMode::PubSub);
+        publisher.produce("warmup", "").unwrap();
+        // Subscribes, then times out as nothing has been published yet
+        assert_eq!(
+            subscriber.receive("orders"),
+            Err(ZeroMqClientError::Timeout {
+                topic: "orders".to_string(),
+                timeout_ms: 200,
+            })
+        );
+
+        publisher.produce("fills", "fill-1").unwrap();
+        publisher.produce("orders_eu", "order-eu").unwrap();
+        publisher.produce("orders", "order-1").unwrap();
+
+        assert_eq!(subscriber.consume("orders"), Ok("order-1".to_string()));
+        // Caught by the `orders` prefix subscription and kept for its own topic
+        assert_eq!(subscriber.consume("orders_eu"), Ok("order-eu".to_string()));
+        // Published before anyone subscribed to it
+        assert!(subscriber.consume("fills").is_err());
+    }
+
+    #[test]
+    fn test_push_pull_delivery() {
+        let (puller, pusher) = pair("inproc://push_pull", SocketMode::PushPull);
+        pusher.produce_bytes("orders", &[0xff, 0x00]).unwrap();
+        pusher.produce("fills", "fill-1").unwrap();
+        pusher.produce("orders", "order-2").unwrap();
+
+        assert_eq!(puller.consume("fills"), Ok("fill-1".to_string()));
+        // Binary payloads go through untouched
+        assert_eq!(puller.consume_bytes("orders"), Ok(vec![0xff, 0x00]));
+        assert_eq!(puller.consume("orders"), Ok("order-2".to_string()));
+        assert_eq!(
+            puller.consume("orders"),
+            Err(ClientError::Timeout {
+                topic: "orders".to_string(),
+                timeout_ms: 200,
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_consume() {
+        let (puller, pusher) = pair("inproc://try_consume", SocketMode::PushPull);
+        let start = Instant::now();
+        assert_eq!(puller.try_consume("orders"), Ok(None));
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        pusher.produce("fills", "fill-1").unwrap();
+        pusher.produce_bytes("orders", &[0xff]).unwrap();
+        assert!(matches!(
+            puller.consume_timeout("orders", Duration::from_millis(200)),
+            Err(ClientError::InvalidPayload(_))
+        ));
+        // Already received while waiting for `orders`
+        assert_eq!(puller.try_consume("fills"), Ok(Some("fill-1".to_string())));
+        assert_eq!(
+            puller.consume_timeout("fills", Duration::from_millis(20)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_req_rep_acknowledgement() {
+        let (replier, requester) = pair("inproc://req_rep", SocketMode::ReqRep);
+        let received = std::thread::scope(|scope| {
+            let consumer = scope.spawn(|| replier.consume("orders"));
+            requester.produce("orders", "order-1").unwrap();
+            consumer.join().unwrap()
+        });
+        assert_eq!(received, Ok("order-1".to_string()));
+
+        // Nobody replies to the next request
+        let error = requester.produce("orders", "order-2").unwrap_err();
+        assert_eq!(
+            error,
+            ClientError::Delivery {
+                retriable: true,
+                detail: "Error sending to ZeroMQ topic orders: no reply within 200 ms".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_malformed_message() {
+        let context = zmq::Context::new();
+        let puller = ZeroMQClient::with_context(
+            &ZeroMqConfig {
+                zmq_url: "inproc://malformed".to_string(),
+                mode: SocketMode::PushPull,
+                bind: true,
+                timeout_ms: 200,
+                ..ZeroMqConfig::default()
+            },
+            context.clone(),
+        );
+        assert!(puller.receive("orders").is_err());
+
+        let pusher = context.socket(zmq::PUSH).unwrap();
+        pusher.connect("inproc://malformed").unwrap();
+        pusher.send("no topic frame", 0).unwrap();
+        assert_eq!(
+            puller.receive("orders"),
+            Err(ZeroMqClientError::Malformed { frames: 1 })
+        );
+    }
+
+    #[test]
+    fn test_socket_errors() {
+        let context = zmq::Context::new();
+        let config = ZeroMqConfig {
+            zmq_url: "inproc://taken".to_string(),
+            mode: SocketMode::PushPull,
+            bind: true,
+            timeout_ms: 200,
+            ..ZeroMqConfig::default()
+        };
+        let first = ZeroMQClient::with_context(&config, context.clone());
+        let second = ZeroMQClient::with_context(&config, context);
+        assert!(matches!(
+            first.receive("orders"),
+            Err(ZeroMqClientError::Timeout { .. })
+        ));
+        // Only one socket can bind an endpoint
+        assert!(matches!(
+            second.receive("orders"),
+            Err(ZeroMqClientError::Socket { .. })
+        ));
+
+        let client = ZeroMQClient::from_config(&ZeroMqConfig {
+            zmq_url: "not-an-endpoint".to_string(),
+            ..ZeroMqConfig::default()
+        });
+        assert!(matches!(
+            client.send("orders", b"order"),
+            Err(ZeroMqClientError::Socket { .. })
+        ));
+    }
+
+    #[test]
+    fn test_socket_mode_from_str() {
+        assert_eq!("pubsub".parse::<SocketMode>(), Ok(SocketMode::PubSub));
+        assert_eq!("REQ_REP".parse::<SocketMode>(), Ok(SocketMode::ReqRep));
+        assert_eq!("push_pull".parse::<SocketMode>(), Ok(SocketMode::PushPull));
+        assert!("dealer".parse::<SocketMode>().is_err());
+    }
+}
@@ -26,4 +26,5 @@ THE SOFTWARE.
    Date: 26/5/24
 ******************************************************************************/
 
+#[allow(clippy::module_inception)]
 mod config_test;
@@ -0,0 +1,621 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod execution_engine_tests {
+    use crate::support::{fill, order, START};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, OnceLock};
+    use std::time::{Duration, UNIX_EPOCH};
+    use strategy_execution_engine::models::market_data::{MarketData, MarketDataUpdate, Trade};
+    use strategy_execution_engine::models::orders::{OrderStatus, Side, ValidationError};
+    use strategy_execution_engine::strategies::common_strategies::SplitError;
+    use strategy_execution_engine::{
+        CancelRequest, ChildOrder, ClientError, Clock, Config, EngineError, ExecutionEngine,
+        ExecutionStatus, MarketDataDispatcher, MessagingClient, MessagingService, MockClient,
+        OrderSplitStrategy, ParentOrder, Quantity, SimulatedClock, StrategyError, StrategyRegistry,
+        Timestamp,
+    };
+
+    /// Splits a parent into fixed slices, each sent after its offset from the parent time;
+    /// `None` sends the slice at once.
+    struct FixedSchedule(Vec<(u32, Option<u64>)>);
+
+    impl OrderSplitStrategy for FixedSchedule {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            let timestamp = parent.order_common.timestamp.as_millis();
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, (quantity, offset_ms))| {
+                    order(&format!("{}-{}", parent.order_common.id, i + 1), *quantity).into_child(
+                        "fixed",
+                        &parent.order_common.id,
+                        offset_ms.map(|offset_ms| Timestamp::from_millis(timestamp + offset_ms)),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    /// Pulls the kill switch of the engine while it splits, then sends the whole parent.
+    struct HaltingSplit(Arc<OnceLock<Arc<ExecutionEngine>>>);
+
+    impl OrderSplitStrategy for HaltingSplit {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            self.0.get().unwrap().kill_switch();
+            let id = &parent.order_common.id;
+            vec![order(&format!("{}-1", id), 100).into_child("halting", id, None)]
+        }
+    }
+
+    /// Refuses to send from any thread of a tokio runtime, like the NATS and RabbitMQ clients
+    /// that block on their own.
+    struct RuntimeGuardedClient(MockClient);
+
+    impl RuntimeGuardedClient {
+        fn guard(&self) -> Result<(), ClientError> {
+            match tokio::runtime::Handle::try_current() {
+                Ok(_) => Err(ClientError::Transport(
+                    "called inside a runtime".to_string(),
+                )),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    impl MessagingClient for RuntimeGuardedClient {
+        fn produce(&self, topic: &str, message: &str) -> Result<(), ClientError> {
+            self.guard()?;
+            self.0.produce(topic, message)
+        }
+
+        fn consume(&self, topic: &str) -> Result<String, ClientError> {
+            self.0.consume(topic)
+        }
+
+        fn produce_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), ClientError> {
+            self.guard()?;
+            self.0.produce_bytes(topic, payload)
+        }
+
+        fn consume_timeout(
+            &self,
+            topic: &str,
+            timeout: Duration,
+        ) -> Result<Option<String>, ClientError> {
+            self.0.consume_timeout(topic, timeout)
+        }
+    }
+
+    /// Sends the whole parent at once after seeing three market data updates, and nothing
+    /// before.
+    struct Warmup(usize);
+
+    impl OrderSplitStrategy for Warmup {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            if self.0 < 3 {
+                return Vec::new();
+            }
+            vec![
+                order(&format!("{}-1", parent.order_common.id), 100).into_child(
+                    "warmup",
+                    &parent.order_common.id,
+                    None,
+                ),
+            ]
+        }
+
+        fn on_market_data(&mut self, _data: &MarketData) {
+            self.0 += 1;
+        }
+    }
+
+    fn parent(id: &str) -> ParentOrder {
+        order(id, 100).into_parent("fixed")
+    }
+
+    fn setup(schedule: Vec<(u32, Option<u64>)>) -> (ExecutionEngine, MockClient, SimulatedClock) {
+        setup_with(Config::default(), schedule)
+    }
+
+    fn setup_with(
+        config: Config,
+        schedule: Vec<(u32, Option<u64>)>,
+    ) -> (ExecutionEngine, MockClient, SimulatedClock) {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("fixed", move |_| {
+            Ok(Box::new(FixedSchedule(schedule.clone())))
+        });
+        let engine = ExecutionEngine::new(
+            config,
+            strategies,
+            MessagingService::with_client(Box::new(client.clone())),
+        )
+        .with_clock(clock.clone());
+        (engine, client, clock)
+    }
+
+    /// Waits for the dispatch task to catch up with the clock.
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    /// Lets the dispatch task run, for checks that nothing more was sent.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn test_children_are_sent_at_their_insert_at() {
+        let (engine, client, clock) = setup(vec![(40, None), (30, Some(1_000)), (30, Some(2_500))]);
+        let receiver = MessagingService::with_client(Box::new(client.clone()));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        assert_eq!(engine.child_order_topic(), "child_orders");
+        assert_eq!(handle.status(), ExecutionStatus::Working);
+
+        // A child without insert_at goes out at once
+        eventually(|| handle.dispatches().len() == 1).await;
+        let first = receiver.consume_child_order("child_orders").unwrap();
+        assert_eq!(first.order_common.id, "parent-1-1");
+        assert_eq!(first.parent_id, "parent-1");
+
+        clock.advance(Duration::from_millis(999));
+        settle().await;
+        assert_eq!(handle.dispatches().len(), 1);
+        assert_eq!(client.pending("child_orders"), 0);
+
+        clock.advance(Duration::from_millis(1));
+        eventually(|| handle.dispatches().len() == 2).await;
+        let second = receiver.consume_child_order("child_orders").unwrap();
+        assert_eq!(second.order_common.id, "parent-1-2");
+
+        clock.set(Timestamp::from_millis(START + 5_000));
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        let third = receiver.consume_child_order("child_orders").unwrap();
+        assert_eq!(third.order_common.id, "parent-1-3");
+
+        let dispatched: Vec<(String, u64)> = handle
+            .dispatches()
+            .into_iter()
+            .map(|dispatch| {
+                assert_eq!(dispatch.result, Ok(()));
+                (
+                    dispatch.child_id,
+                    dispatch.dispatched_at.as_millis() - START,
+                )
+            })
+            .collect();
+        assert_eq!(
+            dispatched,
+            vec![
+                ("parent-1-1".to_string(), 0),
+                ("parent-1-2".to_string(), 1_000),
+                ("parent-1-3".to_string(), 5_000),
+            ]
+        );
+        assert!(handle.is_complete());
+        assert_eq!(handle.state().children.len(), 3);
+        assert!(engine.parent("parent-1").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_children_go_out_in_insert_at_order() {
+        let (engine, client, clock) = setup(vec![(30, Some(2_000)), (30, Some(1_000)), (40, None)]);
+        let receiver = MessagingService::with_client(Box::new(client.clone()));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+
+        let sent: Vec<String> = (0..3)
+            .map(|_| {
+                receiver
+                    .consume_child_order("child_orders")
+                    .unwrap()
+                    .order_common
+                    .id
+            })
+            .collect();
+        assert_eq!(sent, vec!["parent-1-3", "parent-1-2", "parent-1-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_past_insert_at_is_sent_at_once() {
+        let (engine, _client, clock) = setup(vec![(50, Some(1_000)), (50, Some(2_000))]);
+        clock.set(Timestamp::from_millis(START + 10_000));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert_eq!(handle.dispatches().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_children_not_sent_yet() {
+        let (engine, client, clock) = setup(vec![(40, None), (30, Some(1_000)), (30, Some(2_000))]);
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        clock.advance(Duration::from_millis(1_000));
+        eventually(|| handle.dispatches().len() == 2).await;
+
+        assert_eq!(handle.cancel(), vec!["parent-1-3".to_string()]);
+        assert_eq!(handle.status(), ExecutionStatus::Cancelled);
+        clock.advance(Duration::from_secs(10));
+        settle().await;
+
+        assert_eq!(handle.wait().await, ExecutionStatus::Cancelled);
+        assert_eq!(handle.dispatches().len(), 2);
+        assert_eq!(client.pending("child_orders"), 2);
+        let state = handle.state();
+        assert_eq!(state.child("parent-1-2").unwrap().status, OrderStatus::New);
+        assert_eq!(
+            state.child("parent-1-3").unwrap().status,
+            OrderStatus::Cancelled
+        );
+        // Nothing is left to cancel
+        assert!(handle.cancel().is_empty());
+
+        // A cancelled parent id can be submitted again
+        assert!(engine.submit(parent("parent-1")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_parent_requests_cancel_of_working_children() {
+        let (engine, client, clock) = setup(vec![(40, None), (30, None), (30, Some(1_000))]);
+        let receiver = MessagingService::with_client(Box::new(client.clone()));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        eventually(|| handle.dispatches().len() == 2).await;
+        handle.on_fill(&fill("parent-1-1", "exec-1", 40)).unwrap();
+        handle.on_fill(&fill("parent-1-2", "exec-2", 10)).unwrap();
+
+        let report = engine.cancel_parent("parent-1").unwrap();
+        assert_eq!(report.parent_id, "parent-1");
+        assert_eq!(report.cancelled, vec!["parent-1-3".to_string()]);
+        assert_eq!(report.pending_cancel, vec!["parent-1-2".to_string()]);
+        assert_eq!(report.filled, vec!["parent-1-1".to_string()]);
+        assert!(report.failed.is_empty());
+
+        let request: CancelRequest = receiver.consume_json("cancels").unwrap();
+        assert_eq!(request.order_id, "parent-1-2");
+        assert_eq!(request.parent_id, "parent-1");
+        assert_eq!(request.symbol, "AAPL");
+        assert_eq!(request.timestamp, START);
+        assert_eq!(client.pending("cancels"), 0);
+
+        let state = handle.state();
+        assert_eq!(
+            state.child("parent-1-2").unwrap().status,
+            OrderStatus::PendingCancel
+        );
+        assert_eq!(
+            state.child("parent-1-3").unwrap().status,
+            OrderStatus::Cancelled
+        );
+
+        // The undelivered child never goes out
+        clock.advance(Duration::from_secs(10));
+        settle().await;
+        assert_eq!(handle.wait().await, ExecutionStatus::Cancelled);
+        assert_eq!(handle.dispatches().len(), 2);
+        assert_eq!(client.pending("child_orders"), 2);
+
+        assert_eq!(
+            engine.cancel_parent("parent-9").unwrap_err(),
+            EngineError::UnknownParent("parent-9".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_during_a_split_refuses_the_parent() {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let slot = Arc::new(OnceLock::new());
+        let mut strategies = StrategyRegistry::new();
+        let engine_slot = slot.clone();
+        strategies.register("halting", move |_| {
+            Ok(Box::new(HaltingSplit(engine_slot.clone())))
+        });
+        let engine = Arc::new(
+            ExecutionEngine::new(
+                Config::default(),
+                strategies,
+                MessagingService::with_client(Box::new(client.clone())),
+            )
+            .with_clock(SimulatedClock::new(Timestamp::from_millis(START))),
+        );
+        slot.set(engine.clone()).ok().unwrap();
+
+        let halted = order("parent-1", 100).into_parent("halting");
+        assert_eq!(engine.submit(halted).unwrap_err(), EngineError::Halted);
+        assert!(engine.parent("parent-1").is_none());
+        settle().await;
+        assert_eq!(client.pending("child_orders"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_halts_until_resumed() {
+        let (engine, client, clock) = setup(vec![(50, None), (50, Some(1_000))]);
+
+        let first = engine.submit(parent("parent-1")).unwrap();
+        let second = engine.submit(parent("parent-2")).unwrap();
+        eventually(|| first.dispatches().len() == 1 && second.dispatches().len() == 1).await;
+        client.fail_next_produce(ClientError::TopicNotFound("cancels".to_string()));
+
+        let reports = engine.kill_switch();
+        assert!(engine.is_halted());
+        let ids: Vec<&str> = reports
+            .iter()
+            .map(|report| report.parent_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["parent-1", "parent-2"]);
+        assert_eq!(reports[0].cancelled, vec!["parent-1-2".to_string()]);
+        assert_eq!(
+            reports[0].failed,
+            vec![(
+                "parent-1-1".to_string(),
+                ClientError::TopicNotFound("cancels".to_string())
+            )]
+        );
+        assert_eq!(reports[1].pending_cancel, vec!["parent-2-1".to_string()]);
+        assert_eq!(client.pending("cancels"), 1);
+
+        assert_eq!(
+            engine.submit(parent("parent-3")).unwrap_err(),
+            EngineError::Halted
+        );
+        clock.advance(Duration::from_secs(10));
+        settle().await;
+        assert_eq!(client.pending("child_orders"), 2);
+        for handle in [&first, &second] {
+            assert_eq!(handle.status(), ExecutionStatus::Cancelled);
+        }
+
+        // Pulling it again requests the cancels still pending, the failed one included
+        let retried: Vec<Vec<String>> = engine
+            .kill_switch()
+            .into_iter()
+            .map(|report| {
+                assert!(report.cancelled.is_empty());
+                report.pending_cancel
+            })
+            .collect();
+        assert_eq!(
+            retried,
+            vec![
+                vec!["parent-1-1".to_string()],
+                vec!["parent-2-1".to_string()]
+            ]
+        );
+        assert_eq!(client.pending("cancels"), 3);
+        engine.resume();
+        assert!(!engine.is_halted());
+        let third = engine.submit(parent("parent-3")).unwrap();
+        assert_eq!(third.wait().await, ExecutionStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_dispatch_is_reported() {
+        let (engine, client, _clock) = setup(vec![(50, None), (50, None)]);
+        client.fail_next_produce(ClientError::TopicNotFound("child_orders".to_string()));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Failed);
+
+        let dispatches = handle.dispatches();
+        assert_eq!(
+            dispatches[0].result,
+            Err(ClientError::TopicNotFound("child_orders".to_string()))
+        );
+        assert_eq!(dispatches[1].result, Ok(()));
+        let state = handle.state();
+        assert_eq!(
+            state.child("parent-1-1").unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(client.pending("child_orders"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejections() {
+        let (engine, client, _clock) = setup(vec![(50, Some(1_000)), (50, Some(2_000))]);
+
+        let mut invalid = parent("parent-1");
+        invalid.order_common.id.clear();
+        assert_eq!(
+            engine.submit(invalid).unwrap_err(),
+            EngineError::InvalidParent(ValidationError::EmptyField("ID"))
+        );
+
+        let unknown = order("parent-2", 100).into_parent("iceberg");
+        assert_eq!(
+            engine.submit(unknown).unwrap_err(),
+            EngineError::Strategy(StrategyError::UnknownStrategy {
+                name: "iceberg".to_string(),
+                registered: vec!["fixed".to_string()],
+            })
+        );
+
+        engine.submit(parent("parent-3")).unwrap();
+        assert_eq!(
+            engine.submit(parent("parent-3")).unwrap_err(),
+            EngineError::DuplicateParent("parent-3".to_string())
+        );
+
+        let strict = Config {
+            strict_split_validation: true,
+            ..Config::default()
+        };
+        let (over_allocating, _, _) = setup_with(strict, vec![(80, None), (80, None)]);
+        assert!(matches!(
+            over_allocating.submit(parent("parent-4")).unwrap_err(),
+            EngineError::InvalidSplit(SplitError::OverAllocated { .. })
+        ));
+
+        let (empty, _, _) = setup(Vec::new());
+        assert_eq!(
+            empty.submit(parent("parent-5")).unwrap_err(),
+            EngineError::EmptySplit {
+                parent_id: "parent-5".to_string(),
+                strategy_id: "fixed".to_string(),
+            }
+        );
+        assert!(client.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_split_is_repaired_without_strict_validation() {
+        let (engine, _client, _clock) = setup(vec![(80, None), (80, None)]);
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        let children = handle.state().children;
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].child.order_common.quantity, Quantity::from(80));
+        assert_eq!(children[1].child.order_common.quantity, Quantity::from(20));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_children_are_sent_outside_the_runtime() {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("fixed", |_| {
+            Ok(Box::new(FixedSchedule(vec![(60, None), (40, None)])))
+        });
+        let engine = ExecutionEngine::new(
+            Config::default(),
+            strategies,
+            MessagingService::with_client(Box::new(RuntimeGuardedClient(client.clone()))),
+        )
+        .with_clock(SimulatedClock::new(Timestamp::from_millis(START)));
+
+        let handle = engine.submit(parent("parent-1")).unwrap();
+        eventually(|| handle.dispatches().len() == 2).await;
+        assert!(handle
+            .dispatches()
+            .iter()
+            .all(|dispatch| dispatch.result.is_ok()));
+        assert_eq!(client.pending("child_orders"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fed_strategy_splits_once_warmed_up() {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("warmup", |_| Ok(Box::new(Warmup(0))));
+        let engine = ExecutionEngine::new(
+            Config::default(),
+            strategies,
+            MessagingService::with_client(Box::new(client)),
+        )
+        .with_clock(SimulatedClock::new(Timestamp::from_millis(START)));
+        let dispatcher = MarketDataDispatcher::new(|_| {});
+        engine
+            .feed_market_data(&dispatcher, "AAPL", "warmup")
+            .unwrap();
+        assert_eq!(dispatcher.symbols(), vec!["AAPL".to_string()]);
+
+        let cold = order("parent-1", 100).into_parent("warmup");
+        assert!(matches!(
+            engine.submit(cold).unwrap_err(),
+            EngineError::EmptySplit { .. }
+        ));
+
+        for price in [100.0, 101.0, 102.0] {
+            let trade = Trade {
+                timestamp: UNIX_EPOCH + Duration::from_millis(START),
+                price,
+                size: 10.0,
+                side: Side::Buy,
+            };
+            dispatcher.dispatch(&MarketDataUpdate::new("AAPL", MarketData::Trade(trade)));
+        }
+        let warm = order("parent-2", 100).into_parent("warmup");
+        let handle = engine.submit(warm).unwrap();
+        assert_eq!(handle.state().children.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_is_built_once_from_config() {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let built = Arc::new(AtomicUsize::new(0));
+        let mut strategies = StrategyRegistry::new();
+        let counter = built.clone();
+        strategies.register("delayed", move |params| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(FixedSchedule(vec![(
+                100,
+                Some(params.field("delay_ms", 0)?),
+            )])))
+        });
+        let mut config = Config::default();
+        config
+            .strategies
+            .insert("delayed".to_string(), serde_json::json!({"delay_ms": 700}));
+        let engine = ExecutionEngine::new(
+            config,
+            strategies,
+            MessagingService::with_client(Box::new(client.clone())),
+        )
+        .with_clock(clock.clone());
+
+        let first = engine
+            .submit(order("parent-1", 100).into_parent("delayed"))
+            .unwrap();
+        let second = engine
+            .submit(order("parent-2", 100).into_parent("DELAYED"))
+            .unwrap();
+        clock.advance(Duration::from_millis(700));
+        assert_eq!(first.wait().await, ExecutionStatus::Completed);
+        assert_eq!(second.wait().await, ExecutionStatus::Completed);
+
+        assert_eq!(
+            first.dispatches()[0].dispatched_at,
+            Timestamp::from_millis(START + 700)
+        );
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_submit_requires_a_runtime() {
+        let (engine, _client, clock) = setup(vec![(100, None)]);
+        assert_eq!(
+            engine.submit(parent("parent-1")).unwrap_err(),
+            EngineError::NoRuntime
+        );
+        assert_eq!(clock.now(), Timestamp::from_millis(START));
+    }
+}
@@ -0,0 +1,247 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod execution_report_consumer_tests {
+    use crate::support::{fill, order, START};
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::OrderStatus;
+    use strategy_execution_engine::{
+        AnomalyKind, ChildOrderScheduler, ClientError, ExecutionReportConsumer, ExecutionStatus,
+        MessagingService, MockClient, ParentOrderHandle, ParentOrderState, PositionTracker,
+        Quantity, QuarantinedReport, ReportAnomaly, SimulatedClock, Timestamp,
+    };
+
+    /// `parent-1` for 100 AAPL with children of 60 and 40, both dispatched.
+    async fn dispatched() -> (ChildOrderScheduler, ParentOrderHandle) {
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let scheduler = ChildOrderScheduler::new(Arc::new(clock), |_: &_| Ok(()));
+        let state = ParentOrderState::new(
+            order("parent-1", 100).into_parent("fixed"),
+            vec![
+                order("child-1", 60).into_child("fixed", "parent-1", None),
+                order("child-2", 40).into_child("fixed", "parent-1", None),
+            ],
+        )
+        .unwrap();
+        let handle = scheduler.schedule(state).unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        (scheduler, handle)
+    }
+
+    fn filled(handle: &ParentOrderHandle, child_id: &str) -> (Quantity, OrderStatus) {
+        let state = handle.state();
+        let child = state.child(child_id).unwrap();
+        (child.child.order_common.filled_quantity, child.status)
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    #[tokio::test]
+    async fn test_reports_update_state_and_positions() {
+        let (scheduler, handle) = dispatched().await;
+        let positions = PositionTracker::new();
+        let consumer = ExecutionReportConsumer::new(scheduler).with_positions(positions.clone());
+
+        let client = MockClient::new();
+        let publisher = MessagingService::with_client(Box::new(client.clone()));
+        publisher
+            .produce_fill("fills", &fill("child-1", "exec-1", 60), "parent-1")
+            .unwrap();
+        // A plain JSON fill is read too
+        publisher
+            .produce_json("fills", &fill("child-2", "exec-2", 10))
+            .unwrap();
+        let messaging = Arc::new(MessagingService::with_client(Box::new(client.clone())));
+        let subscription = consumer.subscribe(messaging).unwrap();
+        assert_eq!(subscription.topic(), "fills");
+
+        eventually(|| consumer.metrics().applied == 2).await;
+        assert_eq!(
+            filled(&handle, "child-1"),
+            (Quantity::from(60), OrderStatus::Filled)
+        );
+        assert_eq!(
+            filled(&handle, "child-2"),
+            (Quantity::from(10), OrderStatus::PartiallyFilled)
+        );
+        assert_eq!(handle.state().filled_quantity(), Quantity::from(70));
+
+        let position = positions.position("AAPL").unwrap();
+        assert_eq!(position.quantity, dec!(70));
+        assert_eq!(position.avg_entry_price, dec!(10));
+        assert_eq!(position.fees, dec!(1.0));
+
+        let metrics = consumer.metrics();
+        assert_eq!((metrics.reports, metrics.quarantined), (2, 0));
+        assert!(metrics.anomalies.is_empty());
+        subscription.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_exec_ids_apply_once() {
+        let (scheduler, handle) = dispatched().await;
+        let positions = PositionTracker::new();
+        let consumer = ExecutionReportConsumer::new(scheduler).with_positions(positions.clone());
+
+        let report = fill("child-2", "exec-1", 10);
+        assert_eq!(consumer.apply(&report), Ok(OrderStatus::PartiallyFilled));
+        assert_eq!(
+            consumer.apply(&report),
+            Err(ReportAnomaly::DuplicateExecution {
+                order_id: "child-2".to_string(),
+                exec_id: "exec-1".to_string(),
+            })
+        );
+        // Same exec_id on another order is still the same execution
+        assert!(matches!(
+            consumer.apply(&fill("child-1", "exec-1", 5)),
+            Err(ReportAnomaly::DuplicateExecution { .. })
+        ));
+        assert_eq!(
+            consumer.apply(&fill("child-2", "exec-2", 10)),
+            Ok(OrderStatus::PartiallyFilled)
+        );
+
+        assert_eq!(
+            filled(&handle, "child-2"),
+            (Quantity::from(20), OrderStatus::PartiallyFilled)
+        );
+        assert_eq!(filled(&handle, "child-1").0, Quantity::from(0));
+        assert_eq!(positions.quantity("AAPL"), dec!(20));
+
+        let metrics = consumer.metrics();
+        assert_eq!((metrics.reports, metrics.applied), (4, 2));
+        assert_eq!(metrics.anomalies[&AnomalyKind::DuplicateExecution], 2);
+    }
+
+    #[tokio::test]
+    async fn test_overfills_are_refused() {
+        let (scheduler, handle) = dispatched().await;
+        let consumer = ExecutionReportConsumer::new(scheduler);
+
+        consumer.apply(&fill("child-2", "exec-1", 30)).unwrap();
+        assert_eq!(
+            consumer.apply(&fill("child-2", "exec-2", 20)),
+            Err(ReportAnomaly::Overfill {
+                order_id: "child-2".to_string(),
+                exec_id: "exec-2".to_string(),
+                quantity: Quantity::from(40),
+                filled: Quantity::from(30),
+                fill_quantity: Quantity::from(20),
+            })
+        );
+        assert_eq!(filled(&handle, "child-2").0, Quantity::from(30));
+
+        // Not applied, so the exec_id can still come through with the right quantity
+        assert_eq!(
+            consumer.apply(&fill("child-2", "exec-2", 10)),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(consumer.metrics().anomalies[&AnomalyKind::Overfill], 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_orders_are_quarantined() {
+        let (scheduler, handle) = dispatched().await;
+        let consumer = ExecutionReportConsumer::new(scheduler);
+
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let publisher = MessagingService::with_client(Box::new(client.clone()));
+        publisher
+            .produce_fill("fills", &fill("ghost-1", "exec-1", 10), "parent-9")
+            .unwrap();
+        publisher.produce("fills", "not a fill").unwrap();
+        publisher
+            .produce_json("fills", &fill("child-1", "exec-2", 10))
+            .unwrap();
+        let messaging = Arc::new(MessagingService::with_client(Box::new(client.clone())));
+        let subscription = consumer.subscribe(messaging).unwrap();
+
+        eventually(|| client.pending("quarantine") == 2).await;
+        let unknown: QuarantinedReport = publisher.consume_json("quarantine").unwrap();
+        assert_eq!(
+            unknown.anomaly,
+            ReportAnomaly::UnknownOrder {
+                order_id: "ghost-1".to_string(),
+                exec_id: "exec-1".to_string(),
+            }
+        );
+        assert!(unknown.report.contains("ghost-1"));
+        let malformed: QuarantinedReport = publisher.consume_json("quarantine").unwrap();
+        assert_eq!(malformed.anomaly.kind(), AnomalyKind::Malformed);
+        assert_eq!(malformed.report, "not a fill");
+
+        eventually(|| consumer.metrics().applied == 1).await;
+        assert_eq!(filled(&handle, "child-1").0, Quantity::from(10));
+        let metrics = consumer.metrics();
+        assert_eq!((metrics.reports, metrics.quarantined), (3, 2));
+        assert_eq!(metrics.anomalies[&AnomalyKind::UnknownOrder], 1);
+        assert_eq!(metrics.anomalies[&AnomalyKind::Malformed], 1);
+        subscription.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn test_custom_decoder() {
+        let (scheduler, handle) = dispatched().await;
+        // "order_id,exec_id,quantity"
+        let consumer = ExecutionReportConsumer::new(scheduler).with_decoder(|message| {
+            let fields: Vec<&str> = message.split(',').collect();
+            match fields[..] {
+                [order_id, exec_id, quantity] => Ok(fill(
+                    order_id,
+                    exec_id,
+                    quantity
+                        .parse()
+                        .map_err(|_| ClientError::InvalidPayload(message.to_string()))?,
+                )),
+                _ => Err(ClientError::InvalidPayload(message.to_string())),
+            }
+        });
+
+        assert_eq!(
+            consumer.apply_message("child-1,exec-1,60"),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(filled(&handle, "child-1").0, Quantity::from(60));
+        assert!(matches!(
+            consumer.apply_message("child-1"),
+            Err(ReportAnomaly::Malformed { .. })
+        ));
+    }
+}
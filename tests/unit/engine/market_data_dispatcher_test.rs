@@ -0,0 +1,221 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod market_data_dispatcher_tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+    use strategy_execution_engine::models::market_data::{MarketData, MarketDataUpdate, Trade};
+    use strategy_execution_engine::models::orders::{Order, OrderType, Side};
+    use strategy_execution_engine::strategies::market_microstructure_based::adverse_selection::{
+        Strategy, StrategySignal, StrategyState,
+    };
+    use strategy_execution_engine::{
+        DeadLetter, DeadLetterPolicy, MarketDataDispatcher, MessagingService, MockClient,
+    };
+
+    /// Records the prices it sees and buys every trade above `threshold`.
+    struct Recorder {
+        name: String,
+        threshold: f64,
+        seen: Arc<Mutex<Vec<f64>>>,
+        state: StrategyState,
+    }
+
+    impl Recorder {
+        fn new(name: &str, threshold: f64) -> (Self, Arc<Mutex<Vec<f64>>>) {
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let recorder = Recorder {
+                name: name.to_string(),
+                threshold,
+                seen: seen.clone(),
+                state: StrategyState::Running,
+            };
+            (recorder, seen)
+        }
+    }
+
+    impl Strategy for Recorder {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "records market data"
+        }
+
+        fn state(&self) -> &StrategyState {
+            &self.state
+        }
+
+        fn set_state(&mut self, state: StrategyState) {
+            self.state = state;
+        }
+
+        fn on_market_data(&mut self, data: &MarketData) -> Option<StrategySignal> {
+            let price = data.price()?;
+            self.seen.lock().unwrap().push(price);
+            (price > self.threshold).then(|| StrategySignal::Buy {
+                price,
+                size: 10.0,
+                order_type: OrderType::Market,
+                reason: "above threshold".to_string(),
+            })
+        }
+
+        fn on_order_executed(&mut self, _order: &Order) {}
+
+        fn on_order_cancelled(&mut self, _order: &Order) {}
+
+        fn reset(&mut self) {}
+    }
+
+    fn trade(symbol: &str, price: f64) -> MarketDataUpdate {
+        MarketDataUpdate::new(
+            symbol,
+            MarketData::Trade(Trade {
+                timestamp: UNIX_EPOCH + Duration::from_millis(1_716_591_600_000),
+                price,
+                size: 1.0,
+                side: Side::Buy,
+            }),
+        )
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    #[test]
+    fn test_updates_are_routed_by_symbol() {
+        let (dispatcher, mut signals) = MarketDataDispatcher::with_channel();
+        let (aapl, aapl_seen) = Recorder::new("aapl", 100.0);
+        let (msft, msft_seen) = Recorder::new("msft", 100.0);
+        dispatcher.register("AAPL", aapl);
+        dispatcher.register("MSFT", msft);
+        assert_eq!(dispatcher.symbols(), vec!["AAPL", "MSFT"]);
+
+        assert_eq!(dispatcher.dispatch(&trade("AAPL", 101.0)), 1);
+        assert_eq!(dispatcher.dispatch(&trade("MSFT", 99.0)), 0);
+        assert_eq!(dispatcher.dispatch(&trade("TSLA", 500.0)), 0);
+
+        assert_eq!(*aapl_seen.lock().unwrap(), vec![101.0]);
+        assert_eq!(*msft_seen.lock().unwrap(), vec![99.0]);
+        let event = signals.try_recv().unwrap();
+        assert_eq!(event.symbol, "AAPL");
+        assert_eq!(event.strategy, "aapl");
+        assert!(matches!(event.signal, StrategySignal::Buy { price, .. } if price == 101.0));
+        assert!(signals.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_message_schema() {
+        let (dispatcher, _signals) = MarketDataDispatcher::with_channel();
+        let (recorder, seen) = Recorder::new("recorder", f64::MAX);
+        dispatcher.register("AAPL", recorder);
+
+        let message = r#"{"symbol": "AAPL", "data": {"Ticker": {"timestamp": 1716591600250, "bid": 189.4, "ask": 189.6, "last": 189.5}}}"#;
+        assert_eq!(dispatcher.dispatch_message(message).unwrap(), 0);
+        let book = r#"{"symbol": "AAPL", "data": {"OrderBook": {"bids": [[189.4, 300.0]], "asks": [[189.6, 200.0]]}}}"#;
+        dispatcher.dispatch_message(book).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![189.5, 189.5]);
+
+        assert!(dispatcher
+            .dispatch_message(r#"{"symbol": "AAPL", "data": {"Quote": {}}}"#)
+            .is_err());
+        assert!(dispatcher
+            .dispatch_message(r#"{"data": {"Ticker": {}}}"#)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_feeds_strategies_in_order() {
+        let client = MockClient::new();
+        let publisher = MessagingService::with_client(Box::new(client.clone()));
+        let (dispatcher, mut signals) = MarketDataDispatcher::with_channel();
+        let (first, first_seen) = Recorder::new("first", 102.0);
+        let (second, second_seen) = Recorder::new("second", 1_000.0);
+        dispatcher.register("AAPL", first);
+        dispatcher.register("AAPL", second);
+
+        let prices = [100.0, 101.0, 103.0, 102.5, 104.0];
+        for price in prices {
+            publisher
+                .produce_json("market_data", &trade("AAPL", price))
+                .unwrap();
+        }
+        let messaging = Arc::new(MessagingService::with_client(Box::new(client.clone())));
+        let subscription = dispatcher.subscribe(messaging).unwrap();
+        assert_eq!(subscription.topic(), "market_data");
+
+        eventually(|| second_seen.lock().unwrap().len() == prices.len()).await;
+        assert_eq!(*first_seen.lock().unwrap(), prices);
+        assert_eq!(*second_seen.lock().unwrap(), prices);
+
+        let mut forwarded = Vec::new();
+        while let Ok(event) = signals.try_recv() {
+            assert_eq!(event.strategy, "first");
+            match event.signal {
+                StrategySignal::Buy { price, .. } => forwarded.push(price),
+                signal => panic!("unexpected signal {:?}", signal),
+            }
+        }
+        assert_eq!(forwarded, vec![103.0, 102.5, 104.0]);
+        subscription.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_messages_are_dead_lettered() {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let publisher = MessagingService::with_client(Box::new(client.clone()));
+        let (dispatcher, _signals) = MarketDataDispatcher::with_channel();
+        let (recorder, seen) = Recorder::new("recorder", f64::MAX);
+        dispatcher.register("AAPL", recorder);
+
+        publisher.produce("market_data", "not market data").unwrap();
+        publisher
+            .produce_json("market_data", &trade("AAPL", 100.0))
+            .unwrap();
+        let mut messaging = MessagingService::with_client(Box::new(client.clone()));
+        messaging.set_dead_letter_policy(DeadLetterPolicy::default());
+        let subscription = dispatcher.subscribe(Arc::new(messaging)).unwrap();
+
+        eventually(|| seen.lock().unwrap().len() == 1).await;
+        eventually(|| client.pending("market_data.dlq") == 1).await;
+        let letter: DeadLetter = publisher.consume_json("market_data.dlq").unwrap();
+        assert_eq!(letter.source_topic, "market_data");
+        assert_eq!(letter.payload, "not market data");
+        subscription.unsubscribe();
+    }
+}
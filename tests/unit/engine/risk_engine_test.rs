@@ -0,0 +1,447 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod risk_engine_tests {
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::{Order, OrderStatus, OrderType, Side};
+    use strategy_execution_engine::{
+        ChildOrder, Config, Decimal, EngineError, ExecutionEngine, ExecutionStatus, Fill,
+        InstrumentRegistry, InstrumentSpec, Liquidity, MessagingService, MockClient,
+        OrderSplitStrategy, ParentOrder, PositionTracker, Quantity, RiskAction, RiskConfig,
+        RiskEngine, RiskLimits, RiskRule, StrategyRegistry, Timestamp,
+    };
+
+    fn order(id: &str, symbol: &str, quantity: u32, price: Option<Decimal>) -> Order {
+        let builder = Order::builder()
+            .id(id)
+            .quantity(quantity)
+            .timestamp(Timestamp::from_millis(1_622_512_800_000))
+            .symbol(symbol)
+            .side(Side::Buy)
+            .currency("USD");
+        match price {
+            Some(price) => builder.order_type(OrderType::Limit).price(price),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+
+    /// Children of `parent-1` at 10 with the quantities of `slices`.
+    fn children(symbol: &str, slices: &[u32]) -> Vec<ChildOrder> {
+        slices
+            .iter()
+            .enumerate()
+            .map(|(i, slice)| {
+                order(
+                    &format!("parent-1-{}", i + 1),
+                    symbol,
+                    *slice,
+                    Some(dec!(10)),
+                )
+                .into_child("fixed", "parent-1", None)
+            })
+            .collect()
+    }
+
+    /// A parent of `quantity` at 10 split into `slices`.
+    fn split(symbol: &str, quantity: u32, slices: &[u32]) -> (ParentOrder, Vec<ChildOrder>) {
+        let parent = order("parent-1", symbol, quantity, Some(dec!(10))).into_parent("fixed");
+        (parent, children(symbol, slices))
+    }
+
+    fn with_limits(limits: RiskLimits) -> RiskEngine {
+        RiskEngine::new(RiskConfig {
+            limits,
+            ..RiskConfig::default()
+        })
+    }
+
+    fn ids(children: &[ChildOrder]) -> Vec<&str> {
+        children
+            .iter()
+            .map(|child| child.order_common.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_compliant_children_pass_untouched() {
+        let risk = with_limits(RiskLimits {
+            max_child_quantity: Some(dec!(50)),
+            max_parent_notional: Some(dec!(1000)),
+            max_open_children: Some(3),
+            max_gross_exposure: Some(dec!(1000)),
+        })
+        .with_positions(PositionTracker::new());
+        let (parent, children) = split("AAPL", 100, &[50, 30, 20]);
+
+        let decision = risk.check(&parent, children.clone(), &HashMap::new());
+        assert!(!decision.is_rejected());
+        assert_eq!(
+            serde_json::to_value(&decision.accepted).unwrap(),
+            serde_json::to_value(&children).unwrap()
+        );
+        let metrics = risk.metrics();
+        assert_eq!(metrics.parents_checked, 1);
+        assert_eq!(metrics.children_checked, 3);
+        assert_eq!(metrics.children_rejected, 0);
+        assert!(metrics.rejections.is_empty());
+    }
+
+    #[test]
+    fn test_max_child_quantity_blocks_the_offending_child() {
+        let risk = with_limits(RiskLimits {
+            max_child_quantity: Some(dec!(40)),
+            ..RiskLimits::default()
+        });
+        let (parent, children) = split("AAPL", 100, &[30, 50, 20]);
+
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1", "parent-1-3"]);
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.child_id.as_deref(), Some("parent-1-2"));
+        assert_eq!(rejection.rule, RiskRule::MaxChildQuantity);
+        assert_eq!((rejection.limit, rejection.value), (dec!(40), dec!(50)));
+        assert_eq!(
+            rejection.to_string(),
+            "child parent-1-2 of parent parent-1 breaches max_child_quantity: 50 over 40"
+        );
+    }
+
+    #[test]
+    fn test_max_parent_notional_blocks_the_parent() {
+        let risk = with_limits(RiskLimits {
+            max_parent_notional: Some(dec!(999)),
+            ..RiskLimits::default()
+        });
+        let (parent, children) = split("AAPL", 100, &[50, 50]);
+
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert!(decision.accepted.is_empty());
+        assert_eq!(decision.rejections.len(), 1);
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.child_id, None);
+        assert_eq!(rejection.rule, RiskRule::MaxParentNotional);
+        assert_eq!(rejection.value, dec!(1000));
+        assert_eq!(risk.metrics().parents_rejected, 1);
+        assert_eq!(risk.metrics().children_rejected, 2);
+    }
+
+    #[test]
+    fn test_unpriced_parent_is_valued_at_its_children() {
+        let risk = with_limits(RiskLimits {
+            max_parent_notional: Some(dec!(999)),
+            ..RiskLimits::default()
+        });
+        let parent = order("parent-1", "AAPL", 100, None).into_parent("fixed");
+
+        let decision = risk.check(&parent, children("AAPL", &[50, 50]), &HashMap::new());
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.rule, RiskRule::MaxParentNotional);
+        assert_eq!(rejection.value, dec!(1000));
+    }
+
+    #[test]
+    fn test_parent_without_any_price_is_rejected_under_a_notional_limit() {
+        let risk = with_limits(RiskLimits {
+            max_parent_notional: Some(dec!(1000)),
+            ..RiskLimits::default()
+        });
+        let parent = order("parent-1", "AAPL", 100, None).into_parent("fixed");
+        let unpriced =
+            vec![order("parent-1-1", "AAPL", 100, None).into_child("fixed", "parent-1", None)];
+
+        let decision = risk.check(&parent, unpriced.clone(), &HashMap::new());
+        assert!(decision.accepted.is_empty());
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.child_id, None);
+        assert_eq!(rejection.rule, RiskRule::UnpricedParent);
+        assert_eq!(
+            rejection.to_string(),
+            "parent parent-1 has no price to check against max_parent_notional 1000"
+        );
+
+        // Without a notional limit there is nothing to value it for
+        let unlimited = RiskEngine::default();
+        assert!(!unlimited
+            .check(&parent, unpriced, &HashMap::new())
+            .is_rejected());
+    }
+
+    #[test]
+    fn test_max_open_children_counts_working_children() {
+        let risk = with_limits(RiskLimits {
+            max_open_children: Some(4),
+            ..RiskLimits::default()
+        });
+        let (parent, children) = split("AAPL", 100, &[25, 25, 25, 25]);
+        let open = HashMap::from([("MSFT".to_string(), 2)]);
+
+        let decision = risk.check(&parent, children, &open);
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1", "parent-1-2"]);
+        assert_eq!(decision.rejections.len(), 2);
+        assert!(decision
+            .rejections
+            .iter()
+            .all(|rejection| rejection.rule == RiskRule::MaxOpenChildren));
+        assert_eq!(decision.rejections[0].value, dec!(5));
+    }
+
+    #[test]
+    fn test_max_gross_exposure_consults_the_positions() {
+        let positions = PositionTracker::new();
+        positions.track_order(&order("held", "MSFT", 10, None));
+        positions
+            .apply_fill(&Fill::new(
+                "held".to_string(),
+                "e1".to_string(),
+                dec!(40),
+                Quantity::from(10),
+                0,
+                dec!(0),
+                Liquidity::Taker,
+            ))
+            .unwrap();
+        positions.mark("MSFT", dec!(50));
+        let risk = with_limits(RiskLimits {
+            max_gross_exposure: Some(dec!(1200)),
+            ..RiskLimits::default()
+        })
+        .with_positions(positions);
+        let (parent, children) = split("AAPL", 100, &[40, 30, 30]);
+
+        // 500 held at the mark, so 700 of children fit
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1", "parent-1-2"]);
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.rule, RiskRule::MaxGrossExposure);
+        assert_eq!(rejection.value, dec!(1500));
+
+        // Without positions the exposure is not checked
+        let unchecked = with_limits(RiskLimits {
+            max_gross_exposure: Some(dec!(1)),
+            ..RiskLimits::default()
+        });
+        let (parent, children) = split("AAPL", 100, &[100]);
+        assert!(!unchecked
+            .check(&parent, children, &HashMap::new())
+            .is_rejected());
+    }
+
+    #[test]
+    fn test_reducing_child_lowers_the_exposure_at_the_limit() {
+        let positions = PositionTracker::new();
+        positions.track_order(&order("held", "AAPL", 100, None));
+        positions
+            .apply_fill(&Fill::new(
+                "held".to_string(),
+                "e1".to_string(),
+                dec!(10),
+                Quantity::from(100),
+                0,
+                dec!(0),
+                Liquidity::Taker,
+            ))
+            .unwrap();
+        let risk = with_limits(RiskLimits {
+            max_gross_exposure: Some(dec!(1000)),
+            ..RiskLimits::default()
+        })
+        .with_positions(positions);
+
+        // Long 100 at 10 is the whole limit: selling reduces it, buying goes over
+        let (mut parent, mut children) = split("AAPL", 70, &[40, 30]);
+        parent.order_common.side = Side::Sell;
+        for child in children.iter_mut() {
+            child.order_common.side = Side::Sell;
+        }
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert!(!decision.is_rejected());
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1", "parent-1-2"]);
+
+        let (parent, children) = split("AAPL", 10, &[10]);
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert!(decision.accepted.is_empty());
+        assert_eq!(decision.rejections[0].rule, RiskRule::MaxGrossExposure);
+        assert_eq!(decision.rejections[0].value, dec!(1100));
+    }
+
+    #[test]
+    fn test_futures_exposure_scales_by_the_contract_multiplier() {
+        let mut instruments = InstrumentRegistry::new();
+        let mut spec = InstrumentSpec::new(dec!(0.25), Quantity::from(1));
+        spec.contract_multiplier = dec!(50);
+        instruments.register("ES", spec).unwrap();
+        let risk = with_limits(RiskLimits {
+            max_gross_exposure: Some(dec!(20000)),
+            ..RiskLimits::default()
+        })
+        .with_positions(PositionTracker::new())
+        .with_instruments(instruments);
+        let (parent, children) = split("ES", 60, &[20, 20, 20]);
+
+        // Each child of 20 at 10 is worth 10000 once multiplied by 50
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1", "parent-1-2"]);
+        let rejection = &decision.rejections[0];
+        assert_eq!(rejection.rule, RiskRule::MaxGrossExposure);
+        assert_eq!(rejection.value, dec!(30000));
+    }
+
+    #[test]
+    fn test_symbol_limits_apply_to_their_symbol() {
+        let risk = RiskEngine::new(RiskConfig {
+            symbols: HashMap::from([(
+                "AAPL".to_string(),
+                RiskLimits {
+                    max_child_quantity: Some(dec!(10)),
+                    max_open_children: Some(1),
+                    ..RiskLimits::default()
+                },
+            )]),
+            ..RiskConfig::default()
+        });
+
+        let (parent, children) = split("MSFT", 100, &[100]);
+        assert!(!risk.check(&parent, children, &HashMap::new()).is_rejected());
+
+        let (parent, children) = split("AAPL", 20, &[10, 10]);
+        let decision = risk.check(&parent, children.clone(), &HashMap::new());
+        assert_eq!(ids(&decision.accepted), vec!["parent-1-1"]);
+        assert_eq!(decision.rejections[0].rule, RiskRule::MaxOpenChildren);
+        assert!(decision.rejections[0].symbol_limit);
+        assert_eq!(
+            decision.rejections[0].to_string(),
+            "child parent-1-2 of parent parent-1 breaches AAPL max_open_children: 2 over 1"
+        );
+
+        let open = HashMap::from([("MSFT".to_string(), 10)]);
+        let (parent, children) = split("AAPL", 20, &[20]);
+        let decision = risk.check(&parent, children, &open);
+        assert_eq!(decision.rejections[0].rule, RiskRule::MaxChildQuantity);
+    }
+
+    #[test]
+    fn test_reject_parent_blocks_every_child() {
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let recorded = rejected.clone();
+        let risk = RiskEngine::new(RiskConfig {
+            limits: RiskLimits {
+                max_child_quantity: Some(dec!(40)),
+                ..RiskLimits::default()
+            },
+            on_breach: RiskAction::RejectParent,
+            ..RiskConfig::default()
+        })
+        .on_rejection(move |rejection| recorded.lock().unwrap().push(rejection.clone()));
+        let (parent, children) = split("AAPL", 100, &[30, 50, 20]);
+
+        let decision = risk.check(&parent, children, &HashMap::new());
+        assert!(decision.accepted.is_empty());
+        assert_eq!(*rejected.lock().unwrap(), decision.rejections);
+        assert_eq!(decision.rejections.len(), 1);
+        let metrics = risk.metrics();
+        assert_eq!(metrics.children_rejected, 3);
+        assert_eq!(metrics.parents_rejected, 1);
+        assert_eq!(metrics.rejections[&RiskRule::MaxChildQuantity], 1);
+    }
+
+    /// Splits a parent into the given slices, all sent at once.
+    struct Slices(Vec<u32>);
+
+    impl OrderSplitStrategy for Slices {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            children(&parent.order_common.symbol, &self.0)
+        }
+    }
+
+    fn setup(slices: Vec<u32>, risk: RiskConfig) -> (ExecutionEngine, MockClient) {
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("fixed", move |_| Ok(Box::new(Slices(slices.clone()))));
+        let config = Config {
+            risk: Some(risk),
+            ..Config::default()
+        };
+        let engine = ExecutionEngine::new(
+            config,
+            strategies,
+            MessagingService::with_client(Box::new(client.clone())),
+        );
+        (engine, client)
+    }
+
+    #[tokio::test]
+    async fn test_engine_dispatches_only_accepted_children() {
+        let limits = RiskLimits {
+            max_child_quantity: Some(dec!(40)),
+            ..RiskLimits::default()
+        };
+        let (engine, client) = setup(
+            vec![30, 50, 20],
+            RiskConfig {
+                limits: limits.clone(),
+                ..RiskConfig::default()
+            },
+        );
+        let parent = order("parent-1", "AAPL", 100, Some(dec!(10))).into_parent("fixed");
+
+        let handle = engine.submit(parent).unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert_eq!(client.pending("child_orders"), 2);
+        let state = handle.state();
+        assert_eq!(
+            state.child("parent-1-2").unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert!(state.child("parent-1-2").unwrap().dispatched_at.is_none());
+        assert_eq!(engine.risk().metrics().children_rejected, 1);
+
+        let (engine, client) = setup(
+            vec![30, 50, 20],
+            RiskConfig {
+                limits,
+                on_breach: RiskAction::RejectParent,
+                ..RiskConfig::default()
+            },
+        );
+        let parent = order("parent-1", "AAPL", 100, Some(dec!(10))).into_parent("fixed");
+        match engine.submit(parent) {
+            Err(EngineError::RiskRejected(rejections)) => {
+                assert_eq!(rejections[0].rule, RiskRule::MaxChildQuantity)
+            }
+            other => panic!("expected a risk rejection, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(client.pending("child_orders"), 0);
+        assert!(engine.parent("parent-1").is_none());
+    }
+}
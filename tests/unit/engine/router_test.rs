@@ -0,0 +1,245 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod router_tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::{Order, Side};
+    use strategy_execution_engine::{
+        ChildOrder, Config, ExecutionEngine, ExecutionStatus, MessagingService, MockClient,
+        OrderSplitStrategy, ParentOrder, RateLimit, Router, RoutingConfig, RoutingError,
+        SimulatedClock, StrategyRegistry, Throttle, ThrottleConfig, Timestamp, VenueQuote,
+    };
+
+    const START: u64 = 1_622_512_800_000;
+
+    fn at(offset_ms: u64) -> Timestamp {
+        Timestamp::from_millis(START + offset_ms)
+    }
+
+    fn order(id: &str, quantity: u32, side: Side) -> Order {
+        Order::builder()
+            .id(id)
+            .quantity(quantity)
+            .timestamp(at(0))
+            .symbol("AAPL")
+            .side(side)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    fn child(n: usize, side: Side) -> ChildOrder {
+        order(&format!("parent-1-{}", n), 10, side).into_child("slices", "parent-1", None)
+    }
+
+    fn routing(venues: &[(&str, f64)], failover: &[&str]) -> RoutingConfig {
+        RoutingConfig {
+            venues: venues
+                .iter()
+                .map(|(venue, weight)| (venue.to_string(), *weight))
+                .collect(),
+            failover: failover.iter().map(|venue| venue.to_string()).collect(),
+            ..RoutingConfig::default()
+        }
+    }
+
+    fn route(router: &Router, child: &mut ChildOrder) -> Result<String, RoutingError> {
+        router.route(child, at(0), &Throttle::default())
+    }
+
+    /// Splits a parent of 40 into four children of 10 sent at once.
+    struct FourSlices;
+
+    impl OrderSplitStrategy for FourSlices {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            (1..=4)
+                .map(|n| {
+                    order(&format!("{}-{}", parent.order_common.id, n), 10, Side::Buy).into_child(
+                        "slices",
+                        &parent.order_common.id,
+                        None,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_children_follow_the_venue_weights() {
+        let router = Router::new(routing(&[("XNAS", 5.0), ("BATS", 3.0), ("IEX", 2.0)], &[]));
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for n in 0..1_000 {
+            let mut child = child(n, Side::Buy);
+            let venue = route(&router, &mut child).unwrap();
+            assert_eq!(child.order_common.exchange.as_deref(), Some(venue.as_str()));
+            *counts.entry(venue).or_default() += 1;
+        }
+        for (venue, share) in [("XNAS", 0.5), ("BATS", 0.3), ("IEX", 0.2)] {
+            let routed = counts[venue] as f64 / 1_000.0;
+            assert!(
+                (routed - share).abs() < 0.01,
+                "{} took {} of the children",
+                venue,
+                routed
+            );
+        }
+        let metrics = router.metrics();
+        assert_eq!(metrics.routed, counts);
+        assert_eq!(metrics.failovers, 0);
+    }
+
+    #[test]
+    fn test_failover_when_venues_are_unhealthy() {
+        let router = Router::new(routing(&[("XNAS", 3.0), ("BATS", 1.0)], &["IEX", "ARCX"]));
+
+        // The other weighted venue takes every child while one is down
+        router.set_healthy("XNAS", false);
+        assert!(!router.is_healthy("XNAS"));
+        for n in 0..4 {
+            assert_eq!(route(&router, &mut child(n, Side::Buy)).unwrap(), "BATS");
+        }
+
+        // Then the failover venues, in order
+        router.set_healthy("BATS", false);
+        assert_eq!(route(&router, &mut child(4, Side::Buy)).unwrap(), "IEX");
+        router.set_healthy("IEX", false);
+        assert_eq!(route(&router, &mut child(5, Side::Buy)).unwrap(), "ARCX");
+
+        router.set_healthy("ARCX", false);
+        let mut stranded = child(6, Side::Buy);
+        assert_eq!(
+            route(&router, &mut stranded),
+            Err(RoutingError::NoVenue("parent-1-6".to_string()))
+        );
+        assert_eq!(stranded.order_common.exchange, None);
+
+        router.set_healthy("XNAS", true);
+        assert_eq!(route(&router, &mut child(7, Side::Buy)).unwrap(), "XNAS");
+        let metrics = router.metrics();
+        assert_eq!(metrics.failovers, 2);
+        assert_eq!(metrics.unroutable, 1);
+    }
+
+    #[test]
+    fn test_rate_limited_venue_and_best_quote() {
+        let router = Router::new(routing(&[("XNAS", 1.0), ("BATS", 1.0)], &[]));
+        let throttle = Throttle::new(ThrottleConfig {
+            exchanges: [("BATS".to_string(), RateLimit::new(1).unwrap())].into(),
+            ..ThrottleConfig::default()
+        });
+        // BATS has used its order for this second, so XNAS takes the children meanwhile
+        throttle.acquire(Some("BATS"), at(0));
+        assert_eq!(throttle.ready_at(Some("BATS"), at(0)), at(1_000));
+        for n in 0..3 {
+            let venue = router
+                .route(&mut child(n, Side::Buy), at(500), &throttle)
+                .unwrap();
+            assert_eq!(venue, "XNAS");
+        }
+
+        let router = Router::new(RoutingConfig {
+            use_quotes: true,
+            ..routing(&[("XNAS", 1.0), ("BATS", 1.0), ("IEX", 1.0)], &[])
+        });
+        let quote = |venue: &str, bid: f64, ask: f64, offset_ms: u64| VenueQuote {
+            venue: venue.to_string(),
+            symbol: "AAPL".to_string(),
+            bid: Some(bid),
+            ask: Some(ask),
+            timestamp: at(offset_ms),
+        };
+        router.on_quote(quote("XNAS", 99.9, 100.2, 0));
+        router.on_quote(quote("BATS", 100.0, 100.1, 0));
+        // Older than the one known, so left out
+        router
+            .on_quote_message(&serde_json::to_string(&quote("BATS", 100.5, 99.0, 0)).unwrap())
+            .unwrap();
+        router.on_quote(quote("IEX", 100.3, 100.0, 0));
+        router.on_quote(quote("BATS", 100.0, 100.1, 900));
+
+        // The IEX quote is too old by now; BATS has the best ask and XNAS the best bid
+        let route_at = |side: Side| {
+            router
+                .route(&mut child(0, side), at(1_500), &Throttle::default())
+                .unwrap()
+        };
+        assert_eq!(route_at(Side::Buy), "BATS");
+        assert_eq!(route_at(Side::Sell), "BATS");
+        router.on_quote(quote("XNAS", 100.05, 100.2, 1_400));
+        assert_eq!(route_at(Side::Sell), "XNAS");
+        assert_eq!(router.metrics().quoted, 3);
+    }
+
+    #[tokio::test]
+    async fn test_engine_routes_each_child() {
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("slices", |_| Ok(Box::new(FourSlices)));
+        let config = Config {
+            routing: Some(routing(&[("XNAS", 1.0), ("BATS", 1.0)], &["IEX"])),
+            ..Config::default()
+        };
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let engine = ExecutionEngine::new(
+            config,
+            strategies,
+            MessagingService::with_client(Box::new(client)),
+        )
+        .with_clock(SimulatedClock::new(at(0)));
+
+        let handle = engine
+            .submit(order("parent-1", 40, Side::Buy).into_parent("slices"))
+            .unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        let exchanges: Vec<Option<String>> = handle
+            .state()
+            .children
+            .iter()
+            .map(|child| child.child.order_common.exchange.clone())
+            .collect();
+        let venue = |name: &str| Some(name.to_string());
+        assert_eq!(
+            exchanges,
+            vec![venue("BATS"), venue("XNAS"), venue("BATS"), venue("XNAS")]
+        );
+
+        let router = engine.router().unwrap();
+        router.set_healthy("XNAS", false);
+        router.set_healthy("BATS", false);
+        let handle = engine
+            .submit(order("parent-2", 40, Side::Buy).into_parent("slices"))
+            .unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert!(handle
+            .state()
+            .children
+            .iter()
+            .all(|child| child.child.order_common.exchange == venue("IEX")));
+    }
+}
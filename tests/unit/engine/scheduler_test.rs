@@ -0,0 +1,293 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod scheduler_tests {
+    use crate::support::{fill, order, START};
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::{OrderStatus, TimeInForce};
+    use strategy_execution_engine::{
+        ChildOrder, ChildOrderScheduler, ClientError, ExecutionStatus, ParentOrderState,
+        SchedulerError, SimulatedClock, Timestamp,
+    };
+
+    /// A parent of 100 split into the given slices, each inserted at its offset from START.
+    fn state(parent_id: &str, slices: &[(u32, Option<i64>)]) -> ParentOrderState {
+        let children = slices
+            .iter()
+            .enumerate()
+            .map(|(i, (quantity, offset_ms))| {
+                order(&format!("{}-{}", parent_id, i + 1), *quantity).into_child(
+                    "fixed",
+                    parent_id,
+                    offset_ms
+                        .map(|offset_ms| Timestamp::from_millis((START as i64 + offset_ms) as u64)),
+                )
+            })
+            .collect();
+        ParentOrderState::new(order(parent_id, 100).into_parent("fixed"), children).unwrap()
+    }
+
+    /// A scheduler recording the ids of the children it dispatches, failing those in `failing`.
+    fn setup(failing: &[&str]) -> (ChildOrderScheduler, Arc<Mutex<Vec<String>>>, SimulatedClock) {
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let failing: Vec<String> = failing.iter().map(|id| id.to_string()).collect();
+        let recorded = sent.clone();
+        let scheduler =
+            ChildOrderScheduler::new(Arc::new(clock.clone()), move |child: &ChildOrder| {
+                let id = child.order_common.id.clone();
+                if failing.contains(&id) {
+                    return Err(ClientError::Transport("broker down".to_string()));
+                }
+                recorded.lock().unwrap().push(id);
+                Ok(())
+            });
+        (scheduler, sent, clock)
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    fn sent_ids(sent: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        sent.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_children_dispatch_in_insert_at_order() {
+        let (scheduler, sent, clock) = setup(&[]);
+        let handle = scheduler
+            .schedule(state(
+                "parent-1",
+                &[(20, Some(2_000)), (20, None), (20, Some(1_000)), (40, None)],
+            ))
+            .unwrap();
+
+        // Children without insert_at go first, in split order
+        eventually(|| sent_ids(&sent).len() == 2).await;
+        assert_eq!(sent_ids(&sent), vec!["parent-1-2", "parent-1-4"]);
+
+        clock.advance(Duration::from_millis(1_000));
+        eventually(|| sent_ids(&sent).len() == 3).await;
+        assert_eq!(sent_ids(&sent)[2], "parent-1-3");
+
+        clock.advance(Duration::from_millis(1_000));
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert_eq!(sent_ids(&sent)[3], "parent-1-1");
+    }
+
+    #[tokio::test]
+    async fn test_past_insert_at_dispatches_immediately() {
+        let (scheduler, sent, _clock) = setup(&[]);
+        let handle = scheduler
+            .schedule(state("parent-1", &[(50, Some(-5_000)), (50, Some(-1_000))]))
+            .unwrap();
+
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert_eq!(sent_ids(&sent), vec!["parent-1-1", "parent-1-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_only_that_parent() {
+        let (scheduler, sent, clock) = setup(&[]);
+        let first = scheduler
+            .schedule(state("parent-1", &[(50, None), (50, Some(1_000))]))
+            .unwrap();
+        let second = scheduler
+            .schedule(state("parent-2", &[(50, None), (50, Some(1_000))]))
+            .unwrap();
+        eventually(|| sent_ids(&sent).len() == 2).await;
+
+        assert_eq!(scheduler.cancel("parent-1"), vec!["parent-1-2"]);
+        assert!(scheduler.cancel("unknown").is_empty());
+        clock.advance(Duration::from_millis(1_000));
+
+        assert_eq!(first.wait().await, ExecutionStatus::Cancelled);
+        assert_eq!(second.wait().await, ExecutionStatus::Completed);
+        settle().await;
+        assert!(!sent_ids(&sent).contains(&"parent-1-2".to_string()));
+        assert!(sent_ids(&sent).contains(&"parent-2-2".to_string()));
+        assert_eq!(
+            first.state().child("parent-1-2").unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_parent_cancels_its_remaining_children() {
+        let (scheduler, sent, clock) = setup(&[]);
+        let mut gtd = state(
+            "parent-1",
+            &[(40, None), (30, Some(1_000)), (30, Some(2_000))],
+        );
+        gtd.parent.order_common.timeinforce = Some(TimeInForce::GTD);
+        gtd.parent.order_common.expiry_date = Some(Timestamp::from_millis(START + 1_500));
+        let handle = scheduler.schedule(gtd).unwrap();
+        eventually(|| sent_ids(&sent).len() == 1).await;
+
+        clock.advance(Duration::from_millis(1_000));
+        eventually(|| sent_ids(&sent).len() == 2).await;
+        clock.advance(Duration::from_millis(1_000));
+
+        assert_eq!(handle.wait().await, ExecutionStatus::Cancelled);
+        assert_eq!(sent_ids(&sent), vec!["parent-1-1", "parent-1-2"]);
+        assert_eq!(
+            handle.state().child("parent-1-3").unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_results_are_recorded_in_state() {
+        let (scheduler, _sent, clock) = setup(&["parent-1-2"]);
+        let handle = scheduler
+            .schedule(state("parent-1", &[(50, Some(500)), (50, Some(500))]))
+            .unwrap();
+        clock.advance(Duration::from_millis(500));
+
+        assert_eq!(handle.wait().await, ExecutionStatus::Failed);
+        let state = handle.state();
+        let dispatched = state.child("parent-1-1").unwrap();
+        assert_eq!(dispatched.status, OrderStatus::New);
+        assert_eq!(
+            dispatched.dispatched_at,
+            Some(Timestamp::from_millis(START + 500))
+        );
+        let failed = state.child("parent-1-2").unwrap();
+        assert_eq!(failed.status, OrderStatus::Cancelled);
+        assert_eq!(failed.dispatched_at, None);
+        assert!(handle.dispatches()[1].result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_undispatched_children_are_scheduled() {
+        let (scheduler, sent, _clock) = setup(&[]);
+        let mut resumed = state("parent-1", &[(50, None), (50, None)]);
+        resumed
+            .on_dispatched("parent-1-1", Timestamp::from_millis(START))
+            .unwrap();
+
+        let handle = scheduler.schedule(resumed).unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+        assert_eq!(sent_ids(&sent), vec!["parent-1-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_parent_is_rejected_while_working() {
+        let (scheduler, _sent, clock) = setup(&[]);
+        let handle = scheduler
+            .schedule(state("parent-1", &[(100, Some(1_000))]))
+            .unwrap();
+
+        assert_eq!(
+            scheduler.check("parent-1"),
+            Err(SchedulerError::DuplicateParent("parent-1".to_string()))
+        );
+        assert!(scheduler
+            .schedule(state("parent-1", &[(100, None)]))
+            .is_err());
+
+        clock.advance(Duration::from_millis(1_000));
+        handle.wait().await;
+        assert_eq!(scheduler.check("parent-1"), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_parent_is_forgotten_once_over() {
+        let (scheduler, _sent, clock) = setup(&[]);
+        let handle = scheduler
+            .schedule(state("parent-1", &[(50, None), (50, None)]))
+            .unwrap();
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+
+        // Dispatched children can still fill
+        handle.on_fill(&fill("parent-1-1", "exec-1", 50)).unwrap();
+        assert!(scheduler.parent("parent-1").is_some());
+        handle.on_fill(&fill("parent-1-2", "exec-2", 50)).unwrap();
+        assert!(handle.is_over());
+        assert!(scheduler.parent("parent-1").is_none());
+
+        // Nothing was sent of a parent cancelled while waiting, so it is over at once
+        let cancelled = scheduler
+            .schedule(state("parent-2", &[(100, Some(1_000))]))
+            .unwrap();
+        scheduler.cancel("parent-2");
+        clock.advance(Duration::from_millis(1_000));
+        assert_eq!(cancelled.wait().await, ExecutionStatus::Cancelled);
+        assert!(scheduler.parents().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_children_are_sent_from_a_bounded_pool() {
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let recorded = threads.clone();
+        let scheduler = ChildOrderScheduler::new(Arc::new(clock), move |_child: &ChildOrder| {
+            recorded.lock().unwrap().insert(std::thread::current().id());
+            Ok(())
+        })
+        .with_dispatch_workers(2);
+
+        let handles: Vec<_> = (1..=10)
+            .map(|i| {
+                let slices = [(30, None), (30, None), (40, None)];
+                scheduler
+                    .schedule(state(&format!("parent-{}", i), &slices))
+                    .unwrap()
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+            assert_eq!(handle.dispatches().len(), 3);
+        }
+        let threads = threads.lock().unwrap();
+        assert!(!threads.is_empty() && threads.len() <= 2);
+    }
+
+    #[test]
+    fn test_scheduling_requires_a_runtime() {
+        let (scheduler, _sent, _clock) = setup(&[]);
+        assert_eq!(
+            scheduler.schedule(state("parent-1", &[(100, None)])).err(),
+            Some(SchedulerError::NoRuntime)
+        );
+    }
+}
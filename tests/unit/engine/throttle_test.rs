@@ -0,0 +1,186 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod throttle_tests {
+    use crate::support::{self, START};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::Order;
+    use strategy_execution_engine::{
+        ChildOrder, ChildOrderScheduler, Config, ExecutionEngine, ExecutionStatus,
+        MessagingService, MockClient, ParentOrderState, RateLimit, SimulatedClock,
+        StrategyRegistry, Throttle, ThrottleConfig, ThrottleMetricsSnapshot, Timestamp,
+    };
+
+    fn at(offset_ms: u64) -> Timestamp {
+        Timestamp::from_millis(START + offset_ms)
+    }
+
+    /// The shared `order`, sent to XNAS.
+    fn order(id: &str, quantity: u32) -> Order {
+        Order {
+            exchange: Some("XNAS".to_string()),
+            ..support::order(id, quantity)
+        }
+    }
+
+    /// A parent of 100 on XNAS split into 50 children of 2, all due at once.
+    fn burst(parent_id: &str) -> ParentOrderState {
+        let children = (1..=50)
+            .map(|i| order(&format!("{}-{}", parent_id, i), 2).into_child("fixed", parent_id, None))
+            .collect();
+        ParentOrderState::new(order(parent_id, 100).into_parent("fixed"), children).unwrap()
+    }
+
+    fn throttle(limit: Option<RateLimit>, exchanges: &[(&str, RateLimit)]) -> Throttle {
+        Throttle::new(ThrottleConfig {
+            limit,
+            exchanges: exchanges
+                .iter()
+                .map(|(exchange, limit)| (exchange.to_string(), *limit))
+                .collect(),
+        })
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    #[tokio::test]
+    async fn test_burst_is_spread_at_the_exchange_rate() {
+        let clock = SimulatedClock::new(at(0));
+        let throttle = throttle(None, &[("XNAS", RateLimit::new(10).unwrap())]);
+        let scheduler = ChildOrderScheduler::new(Arc::new(clock.clone()), |_: &ChildOrder| Ok(()))
+            .with_throttle(throttle.clone());
+        let handle = scheduler.schedule(burst("parent-1")).unwrap();
+
+        // A full bucket lets the first ten go at once
+        eventually(|| handle.dispatches().len() == 10).await;
+        for sent in 11..=50 {
+            clock.advance(Duration::from_millis(100));
+            eventually(|| handle.dispatches().len() == sent).await;
+        }
+        assert_eq!(handle.wait().await, ExecutionStatus::Completed);
+
+        // Then one every 100 ms, each with its insert_at pushed back to its slot
+        for (i, dispatch) in handle.dispatches().iter().enumerate() {
+            let slot = at((i as u64).saturating_sub(9) * 100);
+            assert_eq!(dispatch.dispatched_at, slot, "child {}", i + 1);
+            assert_eq!(dispatch.insert_at, (i >= 10).then_some(slot));
+            assert!(dispatch.result.is_ok());
+        }
+        assert_eq!(
+            throttle.metrics(),
+            ThrottleMetricsSnapshot {
+                acquired: 50,
+                throttled: 40,
+                total_delay_ms: 4_000,
+                max_delay_ms: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn test_children_wait_for_both_global_and_exchange_tokens() {
+        // 4 per second globally, with a burst of 2, and 2 per second on XNAS one at a time
+        let throttle = throttle(
+            Some(RateLimit::new(4).unwrap().with_burst(2).unwrap()),
+            &[("XNAS", RateLimit::new(2).unwrap().with_burst(1).unwrap())],
+        );
+
+        assert_eq!(throttle.acquire(Some("XNAS"), at(0)), at(0));
+        assert_eq!(throttle.acquire(None, at(0)), at(0));
+        // The global bucket is empty
+        assert_eq!(throttle.acquire(None, at(0)), at(250));
+        // XNAS has its next token at 500, and so does the global bucket
+        assert_eq!(throttle.acquire(Some("XNAS"), at(0)), at(500));
+        // An exchange without a limit only waits for the global bucket
+        assert_eq!(throttle.acquire(Some("XLON"), at(0)), at(750));
+        // A quiet spell fills the buckets up again
+        assert_eq!(throttle.acquire(Some("XNAS"), at(5_000)), at(5_000));
+        assert_eq!(throttle.acquire(None, at(5_000)), at(5_000));
+
+        assert_eq!(
+            throttle.metrics(),
+            ThrottleMetricsSnapshot {
+                acquired: 7,
+                throttled: 3,
+                total_delay_ms: 1_500,
+                max_delay_ms: 750,
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_rate_or_burst_is_rejected() {
+        assert_eq!(
+            RateLimit::new(0).unwrap_err().to_string(),
+            "invalid throttle configuration: orders_per_sec must be positive"
+        );
+        assert_eq!(
+            RateLimit::new(10)
+                .unwrap()
+                .with_burst(0)
+                .unwrap_err()
+                .to_string(),
+            "invalid throttle configuration: burst must be positive"
+        );
+    }
+
+    #[test]
+    fn test_engine_takes_the_throttle_of_the_config() {
+        let unlimited = Throttle::default();
+        assert!(unlimited.is_unlimited());
+        assert_eq!(unlimited.acquire(Some("XNAS"), at(0)), at(0));
+
+        let throttle = ThrottleConfig {
+            limit: Some(RateLimit::new(100).unwrap()),
+            ..ThrottleConfig::default()
+        };
+        let config = Config {
+            throttle: Some(throttle.clone()),
+            ..Config::default()
+        };
+        let engine = ExecutionEngine::new(
+            config,
+            StrategyRegistry::new(),
+            MessagingService::with_client(Box::new(MockClient::new())),
+        )
+        .with_clock(SimulatedClock::new(at(0)));
+        // Replacing the clock keeps the throttle
+        assert_eq!(engine.throttle().config(), &throttle);
+        assert!(!engine.throttle().is_unlimited());
+    }
+}
@@ -29,3 +29,18 @@ THE SOFTWARE.
 mod models;
 
 mod config;
+
+mod clients;
+
+mod engine;
+
+mod portfolio;
+
+mod backtest;
+
+mod persistence;
+
+mod audit;
+
+mod analytics;
+mod support;
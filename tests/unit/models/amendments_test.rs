@@ -0,0 +1,247 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod amendments_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::amendments::{AmendError, OrderAmendment};
+    use strategy_execution_engine::models::fills::{apply_fill, Fill, Liquidity};
+    use strategy_execution_engine::models::orders::{Order, OrderType, ProductType, Side};
+    use strategy_execution_engine::{Decimal, Quantity, Timestamp};
+
+    fn order(order_type: OrderType) -> Order {
+        Order::new(
+            String::from("order1"),
+            Quantity::from(100),
+            ProductType::Spot,
+            order_type,
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1622512800),
+            None,
+            String::from("AAPL"),
+            Side::Buy,
+            String::from("USD"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(dec!(10000.0)),
+            Some(7),
+        )
+    }
+
+    fn amendment(order_id: &str, price: Option<Decimal>, quantity: Option<u32>) -> OrderAmendment {
+        OrderAmendment::new(
+            String::from(order_id),
+            price,
+            quantity.map(Quantity::from),
+            1622512900,
+        )
+    }
+
+    #[test]
+    fn test_amend_price_and_quantity() {
+        let order = order(OrderType::Limit);
+        let amended = order
+            .amend(&amendment("order1", Some(dec!(101.0)), Some(150)))
+            .unwrap();
+
+        assert_eq!(amended.id, "order1-1");
+        assert_eq!(amended.original_id.as_deref(), Some("order1"));
+        assert_eq!(amended.version, 1);
+        assert_eq!(amended.nonce, Some(8));
+        assert_eq!(amended.price, Some(dec!(101.0)));
+        assert_eq!(amended.quantity, Quantity::from(150));
+        assert_eq!(amended.timestamp, Timestamp::from_secs(1622512900));
+        assert_eq!(amended.notional, Some(dec!(15150.0)));
+
+        // The original version is left untouched.
+        assert_eq!(order.id, "order1");
+        assert_eq!(order.version, 0);
+        assert_eq!(order.quantity, Quantity::from(100));
+    }
+
+    #[test]
+    fn test_repeated_amendments_keep_lineage() {
+        let order = order(OrderType::Limit);
+        let first = order
+            .amend(&amendment("order1", Some(dec!(99.0)), None))
+            .unwrap();
+        let second = first.amend(&amendment("order1-1", None, Some(80))).unwrap();
+
+        assert_eq!(second.id, "order1-2");
+        assert_eq!(second.root_id(), "order1");
+        assert_eq!(second.version, 2);
+        assert_eq!(second.nonce, Some(9));
+        assert_eq!(second.price, Some(dec!(99.0)));
+        assert_eq!(second.quantity, Quantity::from(80));
+    }
+
+    #[test]
+    fn test_amend_keeps_fills() {
+        let mut order = order(OrderType::Limit);
+        let fill = Fill::new(
+            String::from("order1"),
+            String::from("exec1"),
+            dec!(100.0),
+            Quantity::from(40),
+            1622512850,
+            dec!(0.0),
+            Liquidity::Maker,
+        );
+        apply_fill(&mut order, &fill).unwrap();
+
+        let amended = order.amend(&amendment("order1", None, Some(40))).unwrap();
+        assert_eq!(amended.filled_quantity, Quantity::from(40));
+        assert_eq!(amended.leaves_quantity(), Quantity::ZERO);
+        assert_eq!(amended.avg_fill_price, Some(dec!(100.0)));
+    }
+
+    #[test]
+    fn test_reject_quantity_below_filled() {
+        let mut order = order(OrderType::Limit);
+        let fill = Fill::new(
+            String::from("order1"),
+            String::from("exec1"),
+            dec!(100.0),
+            Quantity::from(60),
+            1622512850,
+            dec!(0.0),
+            Liquidity::Taker,
+        );
+        apply_fill(&mut order, &fill).unwrap();
+
+        let result = order.amend(&amendment("order1", None, Some(50)));
+        assert_eq!(
+            result.err(),
+            Some(AmendError::QuantityBelowFilled {
+                order_id: String::from("order1"),
+                quantity: Quantity::from(50),
+                filled: Quantity::from(60),
+            })
+        );
+    }
+
+    #[test]
+    fn test_reject_price_on_market_order() {
+        let order = order(OrderType::Market);
+        let result = order.amend(&amendment("order1", Some(dec!(101.0)), None));
+        assert_eq!(
+            result.err(),
+            Some(AmendError::PriceOnMarketOrder {
+                order_id: String::from("order1"),
+            })
+        );
+
+        // Quantity-only amendments remain valid for market orders.
+        assert!(order.amend(&amendment("order1", None, Some(50))).is_ok());
+    }
+
+    #[test]
+    fn test_reject_invalid_price() {
+        let order = order(OrderType::Limit);
+        let result = order.amend(&amendment("order1", Some(dec!(-1.0)), None));
+        assert!(matches!(result, Err(AmendError::InvalidPrice { .. })));
+    }
+
+    #[test]
+    fn test_reject_zero_quantity() {
+        let order = order(OrderType::Limit);
+        let result = order.amend(&amendment("order1", None, Some(0)));
+        assert!(matches!(result, Err(AmendError::ZeroQuantity { .. })));
+    }
+
+    #[test]
+    fn test_reject_empty_amendment() {
+        let order = order(OrderType::Limit);
+        let result = order.amend(&amendment("order1", None, None));
+        assert!(matches!(result, Err(AmendError::NoChange { .. })));
+    }
+
+    #[test]
+    fn test_reject_other_order() {
+        let order = order(OrderType::Limit);
+        let result = order.amend(&amendment("order2", Some(dec!(101.0)), None));
+        assert!(matches!(result, Err(AmendError::OrderMismatch { .. })));
+    }
+
+    #[test]
+    fn test_amendment_json_round_trip() {
+        let amendment = amendment("order1", Some(dec!(101.5)), None);
+        let expected =
+            r#"{"order_id":"order1","price":101.5,"quantity":null,"timestamp":1622512900}"#;
+        assert_eq!(format!("{}", amendment), expected);
+
+        let parsed: OrderAmendment = serde_json::from_str(expected).unwrap();
+        assert!(parsed == amendment);
+    }
+
+    #[test]
+    fn test_amended_order_json_round_trip() {
+        let amended = order(OrderType::Limit)
+            .amend(&amendment("order1", Some(dec!(101.0)), None))
+            .unwrap();
+        let json = format!("{}", amended);
+        let parsed: Order = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.original_id.as_deref(), Some("order1"));
+        assert_eq!(parsed.version, 1);
+    }
+
+    #[test]
+    fn test_reject_price_on_stop_order() {
+        let mut order = order(OrderType::Limit);
+        order.order_type = OrderType::Stop {
+            stop_price: dec!(95.0),
+        };
+        let result = order.amend(&amendment("order1", Some(dec!(96.0)), None));
+        assert!(matches!(result, Err(AmendError::PriceOnMarketOrder { .. })));
+    }
+
+    #[test]
+    fn test_amend_stop_limit_price() {
+        let mut order = order(OrderType::Limit);
+        order.order_type = OrderType::StopLimit {
+            stop_price: dec!(101.0),
+            limit_price: dec!(100.0),
+        };
+        let amended = order
+            .amend(&amendment("order1", Some(dec!(100.5)), None))
+            .unwrap();
+
+        assert_eq!(amended.price, Some(dec!(100.5)));
+        assert_eq!(
+            amended.order_type,
+            OrderType::StopLimit {
+                stop_price: dec!(101.0),
+                limit_price: dec!(100.5),
+            }
+        );
+    }
+}
@@ -0,0 +1,199 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod bracket_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::bracket::{BracketError, BracketOrder};
+    use strategy_execution_engine::models::fills::{Fill, FillError, Liquidity};
+    use strategy_execution_engine::models::orders::{
+        OffsetType, Order, OrderType, Side, TimeInForce, ValidationError,
+    };
+    use strategy_execution_engine::{Decimal, ParentOrder, Quantity, Timestamp};
+
+    fn entry(side: Side) -> ParentOrder {
+        Order::builder()
+            .id("entry1")
+            .quantity(10)
+            .order_type(OrderType::Limit)
+            .price(dec!(100))
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(side)
+            .currency("USD")
+            .timeinforce(TimeInForce::IOC)
+            .build_parent("strategy1")
+            .unwrap()
+    }
+
+    fn bracket(side: Side, offset_type: OffsetType) -> BracketOrder {
+        BracketOrder::new(entry(side), dec!(2), dec!(5), offset_type).unwrap()
+    }
+
+    fn fill(exec_id: &str, price: Decimal, quantity: u32, timestamp: u64) -> Fill {
+        Fill::new(
+            String::from("entry1"),
+            String::from(exec_id),
+            price,
+            Quantity::from(quantity),
+            timestamp,
+            dec!(0),
+            Liquidity::Taker,
+        )
+    }
+
+    fn stop_price(bracket: &BracketOrder) -> Option<Decimal> {
+        bracket
+            .stop_loss
+            .as_ref()
+            .and_then(|leg| leg.order_common.order_type.trigger_price())
+    }
+
+    #[test]
+    fn test_no_legs_before_fill() {
+        let bracket = bracket(Side::Buy, OffsetType::Absolute);
+        assert!(bracket.legs().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_non_positive_offsets() {
+        assert!(matches!(
+            BracketOrder::new(entry(Side::Buy), dec!(0), dec!(5), OffsetType::Absolute),
+            Err(ValidationError::NonPositive {
+                field: "Stop offset",
+                ..
+            })
+        ));
+        assert!(matches!(
+            BracketOrder::new(entry(Side::Buy), dec!(100), dec!(5), OffsetType::Percent),
+            Err(ValidationError::InconsistentFields(_))
+        ));
+    }
+
+    #[test]
+    fn test_partial_fills_resize_and_reanchor_legs() {
+        let mut bracket = bracket(Side::Buy, OffsetType::Absolute);
+
+        bracket
+            .on_parent_fill(&fill("exec1", dec!(100), 4, 1622512900))
+            .unwrap();
+        let stop_loss = bracket.stop_loss.clone().unwrap();
+        let take_profit = bracket.take_profit.clone().unwrap();
+        assert_eq!(stop_loss.order_common.quantity, Quantity::from(4));
+        assert_eq!(stop_price(&bracket), Some(dec!(98)));
+        assert_eq!(take_profit.order_common.quantity, Quantity::from(4));
+        assert_eq!(take_profit.order_common.price, Some(dec!(105)));
+
+        // 4 @ 100 and 6 @ 101 average out at 100.6
+        bracket
+            .on_parent_fill(&fill("exec2", dec!(101), 6, 1622513000))
+            .unwrap();
+        assert_eq!(bracket.entry.order_common.avg_fill_price, Some(dec!(100.6)));
+        let stop_loss = bracket.stop_loss.as_ref().unwrap();
+        let take_profit = bracket.take_profit.as_ref().unwrap();
+        assert_eq!(stop_loss.order_common.quantity, Quantity::from(10));
+        assert_eq!(stop_price(&bracket), Some(dec!(98.6)));
+        assert_eq!(take_profit.order_common.quantity, Quantity::from(10));
+        assert_eq!(take_profit.order_common.price, Some(dec!(105.6)));
+        assert_eq!(
+            take_profit.order_common.timestamp,
+            Timestamp::from_secs(1622513000)
+        );
+        assert_eq!(
+            take_profit.insert_at,
+            Some(Timestamp::from_secs(1622512900))
+        );
+    }
+
+    #[test]
+    fn test_legs_close_the_entry() {
+        let mut bracket = bracket(Side::Sell, OffsetType::Percent);
+        bracket
+            .on_parent_fill(&fill("exec1", dec!(200), 10, 1622512900))
+            .unwrap();
+
+        for leg in bracket.legs() {
+            assert_eq!(leg.order_common.side, Side::Buy);
+            assert_eq!(leg.parent_id, "entry1");
+            assert_eq!(leg.strategy_id, "strategy1");
+            assert_eq!(leg.order_common.timeinforce, Some(TimeInForce::GTC));
+            assert_eq!(leg.order_common.filled_quantity, Quantity::ZERO);
+        }
+        assert_eq!(
+            bracket.stop_loss.as_ref().unwrap().order_common.id,
+            "entry1-sl"
+        );
+        // Short entry: stop 2% above, target 5% below the fill
+        assert_eq!(stop_price(&bracket), Some(dec!(204)));
+        assert_eq!(
+            bracket.take_profit.as_ref().unwrap().order_common.price,
+            Some(dec!(190))
+        );
+    }
+
+    #[test]
+    fn test_rejected_fill_leaves_bracket_untouched() {
+        let mut bracket = bracket(Side::Buy, OffsetType::Absolute);
+        bracket
+            .on_parent_fill(&fill("exec1", dec!(100), 4, 1622512900))
+            .unwrap();
+
+        assert!(matches!(
+            bracket.on_parent_fill(&fill("exec2", dec!(100), 7, 1622513000)),
+            Err(BracketError::Fill(FillError::Overfill { .. }))
+        ));
+        assert_eq!(
+            bracket.entry.order_common.filled_quantity,
+            Quantity::from(4)
+        );
+        assert_eq!(stop_price(&bracket), Some(dec!(98)));
+    }
+
+    #[test]
+    fn test_rejects_fill_that_puts_stop_below_zero() {
+        let mut bracket =
+            BracketOrder::new(entry(Side::Buy), dec!(150), dec!(5), OffsetType::Absolute).unwrap();
+        assert!(matches!(
+            bracket.on_parent_fill(&fill("exec1", dec!(100), 4, 1622512900)),
+            Err(BracketError::Invalid(ValidationError::NonPositive { .. }))
+        ));
+        assert_eq!(bracket.entry.order_common.filled_quantity, Quantity::ZERO);
+        assert!(bracket.legs().is_empty());
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut bracket = bracket(Side::Buy, OffsetType::Absolute);
+        bracket
+            .on_parent_fill(&fill("exec1", dec!(100), 4, 1622512900))
+            .unwrap();
+        let json = bracket.to_string();
+        let decoded: BracketOrder = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_string(), json);
+    }
+}
@@ -0,0 +1,143 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod builder_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::builder::OrderBuilder;
+    use strategy_execution_engine::models::orders::{
+        Order, OrderType, ProductType, Side, TimeInForce, ValidationError,
+    };
+    use strategy_execution_engine::{Decimal, Quantity, Timestamp};
+
+    fn builder() -> OrderBuilder {
+        Order::builder()
+            .id("order1")
+            .quantity(100)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+    }
+
+    #[test]
+    fn test_build_with_defaults() {
+        let order = builder().build().unwrap();
+
+        assert_eq!(order.id, "order1");
+        assert_eq!(order.quantity, Quantity::from(100));
+        assert!(matches!(order.product_type, ProductType::Spot));
+        assert_eq!(order.order_type, OrderType::Market);
+        assert_eq!(order.price, None);
+        assert_eq!(order.notional, None);
+        assert_eq!(order.nonce, None);
+        assert!(order.timestamp > Timestamp::from_secs(0));
+    }
+
+    #[test]
+    fn test_build_with_all_setters() {
+        let order = builder()
+            .order_type(OrderType::Limit)
+            .price(dec!(3000.5))
+            .timestamp(Timestamp::from_secs(1622512800))
+            .expiry_date(Timestamp::from_secs(1625114800))
+            .exchange("NASDAQ")
+            .timeinforce(TimeInForce::GTD)
+            .notional(dec!(300050))
+            .nonce(42)
+            .build()
+            .unwrap();
+
+        assert_eq!(order.price, Some(dec!(3000.5)));
+        assert_eq!(order.timestamp, Timestamp::from_secs(1622512800));
+        assert_eq!(order.expiry_date, Some(Timestamp::from_secs(1625114800)));
+        assert_eq!(order.exchange.as_deref(), Some("NASDAQ"));
+        assert_eq!(order.timeinforce, Some(TimeInForce::GTD));
+        assert_eq!(order.notional, Some(dec!(300050)));
+        assert_eq!(order.nonce, Some(42));
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let result = Order::builder()
+            .id("order1")
+            .quantity(100)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(ValidationError::MissingField("currency"))
+        );
+        assert_eq!(
+            ValidationError::MissingField("currency").to_string(),
+            "currency is required"
+        );
+    }
+
+    #[test]
+    fn test_build_runs_validation() {
+        let result = builder().quantity(0).build();
+        assert_eq!(
+            result.err(),
+            Some(ValidationError::NonPositive {
+                field: "Quantity",
+                value: Decimal::ZERO
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_parent_and_child() {
+        let parent = builder().timestamp(1000).build_parent("twap").unwrap();
+        assert_eq!(parent.strategy_id, "twap");
+        assert_eq!(parent.order_common.id, "order1");
+
+        let child = builder()
+            .id("order1-0")
+            .quantity(10)
+            .build_child("twap", "order1", Some(Timestamp::from_secs(1000)))
+            .unwrap();
+        assert_eq!(child.parent_id, "order1");
+        assert_eq!(child.insert_at, Some(Timestamp::from_secs(1000)));
+
+        assert!(builder().build_parent("").is_err());
+        assert!(builder().build_child("twap", "", None).is_err());
+    }
+
+    #[test]
+    fn test_into_parent_and_child() {
+        let order = builder().build().unwrap();
+        let parent = order.clone().into_parent("vwap");
+        let child = order.into_child("vwap", "parent1", None);
+
+        assert_eq!(parent.strategy_id, "vwap");
+        assert_eq!(child.strategy_id, "vwap");
+        assert_eq!(child.parent_id, "parent1");
+    }
+}
@@ -0,0 +1,149 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod calendar_tests {
+    use strategy_execution_engine::models::calendar::{Session, TradingCalendar};
+
+    // Friday 2024-05-24 18:00 EDT
+    const FRIDAY_EVENING: u64 = 1716588000000;
+    // Monday 2024-05-27 09:30 EDT
+    const MONDAY_OPEN: u64 = 1716816600000;
+    // Tuesday 2024-05-28 09:30 EDT
+    const TUESDAY_OPEN: u64 = 1716903000000;
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn test_always_open() {
+        let calendar = TradingCalendar::always_open();
+        assert!(calendar.is_open(FRIDAY_EVENING));
+        assert!(calendar.is_open(0));
+        assert_eq!(calendar.clamp_to_session(FRIDAY_EVENING), FRIDAY_EVENING);
+    }
+
+    #[test]
+    fn test_rth_session_boundaries() {
+        let calendar = TradingCalendar::us_equities();
+        assert!(calendar.is_open(MONDAY_OPEN));
+        assert!(!calendar.is_open(MONDAY_OPEN - 1));
+        // 15:59 is open, 16:00 is closed
+        assert!(calendar.is_open(MONDAY_OPEN + 389 * MINUTE));
+        assert!(!calendar.is_open(MONDAY_OPEN + 390 * MINUTE));
+    }
+
+    #[test]
+    fn test_friday_evening_rolls_to_monday() {
+        let calendar = TradingCalendar::us_equities();
+        assert!(!calendar.is_open(FRIDAY_EVENING));
+        assert_eq!(calendar.next_open(FRIDAY_EVENING), MONDAY_OPEN);
+        assert_eq!(calendar.clamp_to_session(FRIDAY_EVENING), MONDAY_OPEN);
+    }
+
+    #[test]
+    fn test_next_open_while_open() {
+        let calendar = TradingCalendar::us_equities();
+        let monday_noon = MONDAY_OPEN + 150 * MINUTE;
+        assert_eq!(calendar.clamp_to_session(monday_noon), monday_noon);
+        assert_eq!(calendar.next_open(monday_noon), TUESDAY_OPEN);
+    }
+
+    #[test]
+    fn test_holiday() {
+        // Memorial Day
+        let calendar = TradingCalendar::us_equities().with_holiday(2024, 5, 27);
+        assert!(!calendar.is_open(MONDAY_OPEN));
+        assert_eq!(calendar.clamp_to_session(FRIDAY_EVENING), TUESDAY_OPEN);
+    }
+
+    #[test]
+    fn test_early_close() {
+        // Day after Thanksgiving closes at 13:00 EST
+        let calendar = TradingCalendar::us_equities().with_early_close(2024, 11, 29, 13 * 60);
+        let friday_one_pm = 1732903200000;
+        assert!(calendar.is_open(friday_one_pm - MINUTE));
+        assert!(!calendar.is_open(friday_one_pm));
+        assert_eq!(calendar.clamp_to_session(friday_one_pm), 1733149800000);
+    }
+
+    #[test]
+    fn test_custom_sessions() {
+        let session = Some(Session::new(0, 720));
+        let calendar = TradingCalendar::new("UTC mornings", 0, [session; 7]);
+        assert!(calendar.is_open(MONDAY_OPEN - 160 * MINUTE));
+        assert!(!calendar.is_open(MONDAY_OPEN));
+        assert_eq!(calendar.next_open(MONDAY_OPEN), TUESDAY_OPEN - 810 * MINUTE);
+    }
+
+    #[test]
+    fn test_us_equities_follow_daylight_saving() {
+        let calendar = TradingCalendar::us_equities();
+        // 09:30 is 14:30 UTC on EST and 13:30 UTC on EDT
+        let winter_open = 1705069800000; // Friday 2024-01-12 09:30 EST
+        assert!(calendar.is_open(winter_open));
+        assert!(!calendar.is_open(winter_open - MINUTE));
+        assert!(calendar.is_open(MONDAY_OPEN));
+        assert!(!calendar.is_open(MONDAY_OPEN - MINUTE));
+
+        // Clocks go forward on Sunday 2024-03-10: Friday closes on EST, Monday opens on EDT
+        let friday_close = 1709931600000; // Friday 2024-03-08 16:00 EST
+        let monday_open = 1710163800000; // Monday 2024-03-11 09:30 EDT
+        assert_eq!(calendar.next_open(friday_close), monday_open);
+
+        // A fixed offset stays on EST all year
+        let fixed = TradingCalendar::weekdays_rth(-5 * 60);
+        assert_eq!(fixed.next_open(friday_close), monday_open + 60 * MINUTE);
+    }
+
+    #[test]
+    fn test_advance_carries_past_the_close_into_the_next_session() {
+        let calendar = TradingCalendar::us_equities();
+        let monday_close = MONDAY_OPEN + 390 * MINUTE;
+        assert_eq!(
+            calendar.advance(MONDAY_OPEN, 30 * MINUTE),
+            MONDAY_OPEN + 30 * MINUTE
+        );
+        assert_eq!(
+            calendar.advance(monday_close - 10 * MINUTE, 10 * MINUTE),
+            TUESDAY_OPEN
+        );
+        assert_eq!(
+            calendar.advance(monday_close - 10 * MINUTE, 25 * MINUTE),
+            TUESDAY_OPEN + 15 * MINUTE
+        );
+        // A whole session and then some
+        assert_eq!(
+            calendar.advance(MONDAY_OPEN, 400 * MINUTE),
+            TUESDAY_OPEN + 10 * MINUTE
+        );
+        // Starting while shut counts from the next open
+        assert_eq!(
+            calendar.advance(FRIDAY_EVENING, 5 * MINUTE),
+            MONDAY_OPEN + 5 * MINUTE
+        );
+    }
+}
@@ -28,22 +28,23 @@ THE SOFTWARE.
 
 #[cfg(test)]
 mod child_orders_tests {
+    use rust_decimal_macros::dec;
     use serde_json;
     use strategy_execution_engine::models::child_orders::ChildOrder;
     use strategy_execution_engine::models::orders::{
         Futures, OptionType, Options, Order, OrderType, ProductType, Side, Swap, TimeInForce, CFD,
     };
-    use strategy_execution_engine::Validate;
+    use strategy_execution_engine::{Quantity, Timestamp, Validate};
 
     fn create_valid_order() -> Order {
         Order::new(
             "test_id".to_string(),
-            100,
+            Quantity::from(100),
             ProductType::Futures,
             OrderType::Limit,
-            Some(100.0),
-            1234567890,
-            Some(1234567890),
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
             "AAPL".to_string(),
             Side::Buy,
             "USD".to_string(),
@@ -53,7 +54,7 @@ mod child_orders_tests {
             None,
             None,
             None,
-            Some(1000.0),
+            Some(dec!(1000.0)),
             Some(1),
         )
     }
@@ -62,12 +63,12 @@ mod child_orders_tests {
     fn test_child_order_creation() {
         let order = ChildOrder::new(
             "test_id".to_string(),
-            100,
+            Quantity::from(100),
             ProductType::Futures,
             OrderType::Limit,
-            Some(100.0),
-            1234567890,
-            Some(1234567890),
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
             "AAPL".to_string(),
             Side::Buy,
             "USD".to_string(),
@@ -77,11 +78,11 @@ mod child_orders_tests {
             None,
             None,
             None,
-            Some(1000.0),
+            Some(dec!(1000.0)),
             Some(1),
             "strategy_1".to_string(),
             "parent_1".to_string(),
-            Some(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
         );
 
         assert_eq!(order.strategy_id, "strategy_1");
@@ -93,12 +94,12 @@ mod child_orders_tests {
     fn test_child_order_validate_success() {
         let order = ChildOrder::new(
             "test_id".to_string(),
-            100,
+            Quantity::from(100),
             ProductType::Futures,
             OrderType::Limit,
-            Some(100.0),
-            1234567890,
-            Some(1234567890),
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
             "AAPL".to_string(),
             Side::Buy,
             "USD".to_string(),
@@ -108,11 +109,11 @@ mod child_orders_tests {
             None,
             None,
             None,
-            Some(1000.0),
+            Some(dec!(1000.0)),
             Some(1),
             "strategy_1".to_string(),
             "parent_1".to_string(),
-            Some(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
         );
 
         assert!(order.validate().is_ok());
@@ -122,12 +123,12 @@ mod child_orders_tests {
     fn test_child_order_validate_empty_strategy_id() {
         let order = ChildOrder::new(
             "test_id".to_string(),
-            100,
+            Quantity::from(100),
             ProductType::Futures,
             OrderType::Limit,
-            Some(100.0),
-            1234567890,
-            Some(1234567890),
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
             "AAPL".to_string(),
             Side::Buy,
             "USD".to_string(),
@@ -137,11 +138,11 @@ mod child_orders_tests {
             None,
             None,
             None,
-            Some(1000.0),
+            Some(dec!(1000.0)),
             Some(1),
             "".to_string(),
             "parent_1".to_string(),
-            Some(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
         );
 
         assert!(order.validate().is_err());
@@ -151,12 +152,12 @@ mod child_orders_tests {
     fn test_child_order_validate_empty_parent_id() {
         let order = ChildOrder::new(
             "test_id".to_string(),
-            100,
+            Quantity::from(100),
             ProductType::Futures,
             OrderType::Limit,
-            Some(100.0),
-            1234567890,
-            Some(1234567890),
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
             "AAPL".to_string(),
             Side::Buy,
             "USD".to_string(),
@@ -166,11 +167,11 @@ mod child_orders_tests {
             None,
             None,
             None,
-            Some(1000.0),
+            Some(dec!(1000.0)),
             Some(1),
             "strategy_1".to_string(),
             "".to_string(),
-            Some(1234567890),
+            Some(Timestamp::from_secs(1234567890)),
         );
 
         assert!(order.validate().is_err());
@@ -179,13 +180,13 @@ mod child_orders_tests {
     #[test]
     fn test_child_order_common_order_validation() {
         let mut order = create_valid_order();
-        order.quantity = 0; // Invalid order since quantity is 0
+        order.quantity = Quantity::ZERO; // Invalid order since quantity is 0
 
         let child_order = ChildOrder {
             order_common: order,
             strategy_id: "strategy_1".to_string(),
             parent_id: "parent_1".to_string(),
-            insert_at: Some(1234567890),
+            insert_at: Some(Timestamp::from_secs(1234567890)),
         };
 
         assert!(child_order.validate().is_err());
@@ -0,0 +1,227 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(all(test, feature = "csv"))]
+mod csv_orders_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{
+        Futures, OffsetType, OptionType, Order, OrderType, ProductType, Side, Swap, TrailingSpec,
+        ValidationError, CFD,
+    };
+    use strategy_execution_engine::{CsvError, ParentOrder, Quantity, Timestamp};
+
+    const FIXTURE: &str = include_str!("../../fixtures/parent_orders.csv");
+
+    #[test]
+    fn test_import_mixed_products() {
+        let import = ParentOrder::from_csv_reader(FIXTURE.as_bytes()).unwrap();
+        let ids: Vec<&str> = import
+            .orders
+            .iter()
+            .map(|order| order.order_common.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["spot1", "spot2", "fut1", "opt1"]);
+
+        let spot = &import.orders[1];
+        assert_eq!(spot.strategy_id, "strategy1");
+        assert_eq!(spot.order_common.quantity, Quantity::from(dec!(2.5)));
+        assert_eq!(spot.order_common.order_type, OrderType::Market);
+        assert!(spot.order_common.futures_opt.is_none());
+
+        let futures = import.orders[2].order_common.futures_opt.as_ref().unwrap();
+        assert_eq!(
+            import.orders[2].order_common.order_type,
+            OrderType::Stop {
+                stop_price: dec!(4500)
+            }
+        );
+        assert_eq!(futures.delivery_date, Some(1735084800));
+        assert_eq!(futures.contract_size, Some(50.0));
+        assert_eq!(futures.margin, Some(dec!(12000)));
+        assert_eq!(futures.overnight_fee, None);
+
+        let option = import.orders[3].order_common.options_opt.as_ref().unwrap();
+        assert_eq!(option.strike_price, dec!(185));
+        assert!(matches!(option.option_type, OptionType::Call));
+        assert_eq!(option.expiry_date, 1718928000);
+    }
+
+    #[test]
+    fn test_import_reports_invalid_rows() {
+        let import = ParentOrder::from_csv_reader(FIXTURE.as_bytes()).unwrap();
+        assert_eq!(import.errors.len(), 2);
+
+        let strike = &import.errors[0];
+        assert_eq!(strike.line, 6);
+        assert_eq!(strike.id.as_deref(), Some("bad1"));
+        assert!(matches!(
+            strike.error,
+            CsvError::Invalid(ValidationError::NonPositive {
+                field: "Strike price",
+                ..
+            })
+        ));
+
+        let margin = &import.errors[1];
+        assert_eq!(margin.line, 7);
+        assert_eq!(margin.id.as_deref(), Some("bad2"));
+        assert!(matches!(
+            margin.error,
+            CsvError::Invalid(ValidationError::Negative { .. })
+        ));
+    }
+
+    #[test]
+    fn test_import_rejects_unparseable_cells() {
+        let csv = "id,strategy_id,symbol,side,quantity,product_type,order_type,timestamp,currency,leverage\n\
+                   a,s,AAPL,Hold,1,Spot,Market,1,USD,\n\
+                   b,s,AAPL,Buy,1,Spot,Market,1,USD,10\n\
+                   c,s,AAPL,Buy,1,Spot,Market,1,USD,\n";
+        let import = ParentOrder::from_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(import.orders.len(), 1);
+        assert!(matches!(
+            import.errors[0].error,
+            CsvError::InvalidValue { column: "side", .. }
+        ));
+        assert!(matches!(
+            import.errors[1].error,
+            CsvError::UnexpectedColumn {
+                column: "leverage",
+                product: "Spot"
+            }
+        ));
+    }
+
+    #[test]
+    fn test_import_missing_column() {
+        let csv = "id,strategy_id,symbol,side,quantity,product_type,order_type,timestamp\n";
+        assert!(matches!(
+            ParentOrder::from_csv_reader(csv.as_bytes()),
+            Err(CsvError::MissingColumn("currency"))
+        ));
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut orders = ParentOrder::from_csv_reader(FIXTURE.as_bytes())
+            .unwrap()
+            .orders;
+        orders.push(
+            Order::builder()
+                .id("swap1")
+                .quantity(1)
+                .product_type(ProductType::Swap)
+                .order_type(OrderType::StopLimit {
+                    stop_price: dec!(99),
+                    limit_price: dec!(98.5),
+                })
+                .price(dec!(98.5))
+                .timestamp(Timestamp::from_secs(1625097606))
+                .symbol("IRS5Y")
+                .side(Side::Buy)
+                .currency("EUR")
+                .swap(Swap {
+                    fixed_rate: 0.025,
+                    floating_rate_index: "EURIBOR".to_string(),
+                    notional_amount: dec!(1000000),
+                })
+                .build_parent("strategy4")
+                .unwrap(),
+        );
+        orders.push(
+            Order::builder()
+                .id("cfd1")
+                .quantity(Quantity::from(dec!(0.75)))
+                .product_type(ProductType::CFD)
+                .order_type(OrderType::TrailingStop(TrailingSpec::new(
+                    dec!(2),
+                    OffsetType::Percent,
+                )))
+                .timestamp(Timestamp::from_secs(1625097607))
+                .symbol("DAX")
+                .side(Side::Sell)
+                .currency("EUR")
+                .nonce(7)
+                .cfd(CFD {
+                    leverage: Some(20),
+                    margin: Some(dec!(500)),
+                    commission: None,
+                    overnight_fee: Some(dec!(1.5)),
+                    dividend_adjustment: None,
+                    contract_size: Some(1.0),
+                })
+                .build_parent("strategy4")
+                .unwrap(),
+        );
+
+        let mut buffer = Vec::new();
+        ParentOrder::write_csv(&mut buffer, &orders).unwrap();
+        let import = ParentOrder::from_csv_reader(buffer.as_slice()).unwrap();
+
+        assert!(import.errors.is_empty());
+        assert_eq!(import.orders.len(), orders.len());
+        for (read, written) in import.orders.iter().zip(&orders) {
+            assert_eq!(read.to_string(), written.to_string());
+        }
+    }
+
+    #[test]
+    fn test_export_writes_empty_optional_cells() {
+        let futures = Order::builder()
+            .id("fut1")
+            .quantity(2)
+            .product_type(ProductType::Futures)
+            .order_type(OrderType::Market)
+            .timestamp(1)
+            .symbol("ESZ4")
+            .side(Side::Buy)
+            .currency("USD")
+            .futures(Futures {
+                delivery_date: None,
+                contract_size: None,
+                margin: Some(dec!(100)),
+                commission: None,
+                overnight_fee: None,
+            })
+            .build_parent("strategy1")
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        ParentOrder::write_csv(&mut buffer, &[futures]).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        let mut lines = output.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("id,strategy_id,symbol,side,quantity"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "fut1,strategy1,ESZ4,Buy,2,Futures,Market,,,,,,1,,USD,,,,,,,,,,100,,,,,,,,,,"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod decimal_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::decimal::{decimal_from_f64, decimal_to_f64};
+    use strategy_execution_engine::models::orders::{Order, OrderType, ProductType, Side};
+    use strategy_execution_engine::{Decimal, Quantity, Timestamp, Validate};
+
+    fn order(price: Option<Decimal>, notional: Option<Decimal>) -> Order {
+        Order::new(
+            String::from("order1"),
+            Quantity::from(3),
+            ProductType::Spot,
+            OrderType::Limit,
+            price,
+            Timestamp::from_secs(1622512800),
+            None,
+            String::from("AAPL"),
+            Side::Buy,
+            String::from("USD"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            notional,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_sum_compares_exactly() {
+        // With f64 this comparison fails: 0.1 + 0.2 == 0.30000000000000004
+        assert_ne!(0.1_f64 + 0.2_f64, 0.3_f64);
+        assert_eq!(dec!(0.1) + dec!(0.2), dec!(0.3));
+    }
+
+    #[test]
+    fn test_notional_matches_price_times_quantity() {
+        let order = order(Some(dec!(0.1)), Some(dec!(0.3)));
+        let price = order.price.unwrap();
+        assert_eq!(price * order.quantity.value(), order.notional.unwrap());
+    }
+
+    #[test]
+    fn test_price_json_round_trip() {
+        let order = order(Some(dec!(0.1)), Some(dec!(0.3)));
+        let json = format!("{}", order);
+        assert!(json.contains(r#""price":0.1,"#));
+        assert!(json.contains(r#""notional":0.3,"#));
+
+        let parsed: Order = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.price, Some(dec!(0.1)));
+        assert_eq!(parsed.notional, Some(dec!(0.3)));
+    }
+
+    #[test]
+    fn test_round_values_keep_float_format() {
+        let order = order(Some(dec!(3000)), None);
+        let json = format!("{}", order);
+        assert!(json.contains(r#""price":3000.0,"#));
+    }
+
+    #[test]
+    fn test_integer_json_price_parses() {
+        let json = r#"{"id":"order1","quantity":1,"product_type":"Spot","order_type":"Limit","price":101,"timestamp":0,"expiry_date":null,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":null,"timeinforce":null,"futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":null,"nonce":null}"#;
+        let order: Order = serde_json::from_str(json).unwrap();
+        assert_eq!(order.price, Some(dec!(101)));
+    }
+
+    #[test]
+    fn test_f64_conversion_helpers() {
+        assert_eq!(decimal_from_f64(0.1), dec!(0.1));
+        assert_eq!(decimal_from_f64(2500.25), dec!(2500.25));
+        assert_eq!(decimal_from_f64(f64::NAN), Decimal::ZERO);
+        assert_eq!(decimal_to_f64(dec!(0.1)), 0.1);
+    }
+
+    #[test]
+    fn test_validation_uses_exact_zero() {
+        assert!(order(Some(dec!(0.1)), Some(dec!(0.0000001)))
+            .validate()
+            .is_ok());
+        assert!(order(Some(dec!(0.1)), Some(Decimal::ZERO))
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,203 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod fills_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::fills::{apply_fill, Fill, FillError, Liquidity};
+    use strategy_execution_engine::models::orders::{Order, OrderType, ProductType, Side};
+    use strategy_execution_engine::{ChildOrder, Decimal, ParentOrder, Quantity, Timestamp};
+
+    fn order(id: &str, quantity: u32) -> Order {
+        Order::new(
+            String::from(id),
+            Quantity::from(quantity),
+            ProductType::Spot,
+            OrderType::Limit,
+            Some(dec!(100.0)),
+            Timestamp::from_secs(1622512800),
+            None,
+            String::from("AAPL"),
+            Side::Buy,
+            String::from("USD"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn fill(order_id: &str, exec_id: &str, price: Decimal, quantity: u32) -> Fill {
+        Fill::new(
+            String::from(order_id),
+            String::from(exec_id),
+            price,
+            Quantity::from(quantity),
+            1622512800,
+            dec!(0.5),
+            Liquidity::Taker,
+        )
+    }
+
+    fn child(id: &str, parent_id: &str, quantity: u32) -> ChildOrder {
+        ChildOrder {
+            order_common: order(id, quantity),
+            strategy_id: String::from("strategy1"),
+            parent_id: String::from(parent_id),
+            insert_at: None,
+        }
+    }
+
+    fn parent(quantity: u32) -> ParentOrder {
+        ParentOrder {
+            order_common: order("parent1", quantity),
+            strategy_id: String::from("strategy1"),
+        }
+    }
+
+    #[test]
+    fn test_multiple_partial_fills() {
+        let mut order = order("order1", 100);
+        apply_fill(&mut order, &fill("order1", "exec1", dec!(100.0), 30)).unwrap();
+        apply_fill(&mut order, &fill("order1", "exec2", dec!(102.0), 20)).unwrap();
+
+        assert_eq!(order.filled_quantity, Quantity::from(50));
+        assert_eq!(order.leaves_quantity(), Quantity::from(50));
+        assert!(!order.is_filled());
+        assert_eq!(order.avg_fill_price, Some(dec!(100.8)));
+    }
+
+    #[test]
+    fn test_exact_completion() {
+        let mut order = order("order1", 100);
+        apply_fill(&mut order, &fill("order1", "exec1", dec!(100.0), 60)).unwrap();
+        apply_fill(&mut order, &fill("order1", "exec2", dec!(100.0), 40)).unwrap();
+
+        assert_eq!(order.filled_quantity, Quantity::from(100));
+        assert_eq!(order.leaves_quantity(), Quantity::ZERO);
+        assert!(order.is_filled());
+        assert_eq!(order.avg_fill_price, Some(dec!(100.0)));
+    }
+
+    #[test]
+    fn test_overfill_is_rejected() {
+        let mut order = order("order1", 100);
+        apply_fill(&mut order, &fill("order1", "exec1", dec!(100.0), 80)).unwrap();
+        let result = apply_fill(&mut order, &fill("order1", "exec2", dec!(101.0), 30));
+
+        assert_eq!(
+            result,
+            Err(FillError::Overfill {
+                order_id: String::from("order1"),
+                exec_id: String::from("exec2"),
+                quantity: Quantity::from(100),
+                filled: Quantity::from(80),
+                fill_quantity: Quantity::from(30),
+            })
+        );
+        assert_eq!(order.filled_quantity, Quantity::from(80));
+        assert_eq!(order.avg_fill_price, Some(dec!(100.0)));
+    }
+
+    #[test]
+    fn test_fill_for_other_order_is_rejected() {
+        let mut order = order("order1", 100);
+        let result = apply_fill(&mut order, &fill("order2", "exec1", dec!(100.0), 10));
+        assert!(matches!(result, Err(FillError::OrderMismatch { .. })));
+        assert_eq!(order.filled_quantity, Quantity::ZERO);
+    }
+
+    #[test]
+    fn test_zero_quantity_fill_is_rejected() {
+        let mut order = order("order1", 100);
+        let result = apply_fill(&mut order, &fill("order1", "exec1", dec!(100.0), 0));
+        assert!(matches!(result, Err(FillError::ZeroQuantity { .. })));
+    }
+
+    #[test]
+    fn test_parent_aggregates_child_fills() {
+        let parent = parent(100);
+        let mut children = vec![
+            child("child1", "parent1", 50),
+            child("child2", "parent1", 50),
+            child("other", "parent2", 50),
+        ];
+        apply_fill(
+            &mut children[0].order_common,
+            &fill("child1", "exec1", dec!(100.0), 50),
+        )
+        .unwrap();
+        apply_fill(
+            &mut children[1].order_common,
+            &fill("child2", "exec2", dec!(104.0), 25),
+        )
+        .unwrap();
+        apply_fill(
+            &mut children[2].order_common,
+            &fill("other", "exec3", dec!(90.0), 50),
+        )
+        .unwrap();
+
+        assert_eq!(parent.filled_quantity(&children), Quantity::from(75));
+        assert_eq!(parent.leaves_quantity(&children), Quantity::from(25));
+        assert!(!parent.is_filled(&children));
+        let avg = parent.avg_fill_price(&children).unwrap();
+        assert_eq!(avg.round_dp(6), dec!(101.333333));
+    }
+
+    #[test]
+    fn test_parent_without_fills() {
+        let parent = parent(100);
+        let children = vec![child("child1", "parent1", 100)];
+        assert_eq!(parent.filled_quantity(&children), Quantity::ZERO);
+        assert_eq!(parent.leaves_quantity(&children), Quantity::from(100));
+        assert_eq!(parent.avg_fill_price(&children), None);
+    }
+
+    #[test]
+    fn test_fill_json_format() {
+        let fill = fill("order1", "exec1", dec!(100.0), 10);
+        let expected = r#"{"order_id":"order1","exec_id":"exec1","price":100.0,"quantity":10,"timestamp":1622512800,"fee":0.5,"liquidity":"Taker"}"#;
+        assert_eq!(format!("{}", fill), expected);
+
+        let parsed: Fill = serde_json::from_str(expected).unwrap();
+        assert!(parsed == fill);
+    }
+
+    #[test]
+    fn test_order_without_fill_fields_deserializes() {
+        let json = r#"{"id":"order1","quantity":100,"product_type":"Spot","order_type":"Market","price":null,"timestamp":0,"expiry_date":null,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":null,"timeinforce":null,"futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":null,"nonce":null}"#;
+        let order: Order = serde_json::from_str(json).unwrap();
+        assert_eq!(order.filled_quantity, Quantity::ZERO);
+        assert_eq!(order.avg_fill_price, None);
+    }
+}
@@ -26,20 +26,22 @@ THE SOFTWARE.
    Date: 29/5/24
 ******************************************************************************/
 
-#[cfg(test)]
+// These pin the numeric timestamp form; `rfc3339` output is covered in timestamp_test.
+#[cfg(all(test, not(feature = "rfc3339")))]
 mod orders_format_tests {
+    use rust_decimal_macros::dec;
     use strategy_execution_engine::{
-        ChildOrder, Futures, OptionType, Options, Order, OrderType, ParentOrder, ProductType, Side,
-        Swap, TimeInForce, CFD,
+        ChildOrder, Decimal, Futures, OptionType, Options, Order, OrderType, ParentOrder,
+        ProductType, Quantity, Side, Swap, TimeInForce, Timestamp, CFD,
     };
 
     #[test]
     fn test_debug_pretty_print() {
         let cfd = CFD {
             leverage: Some(10),
-            margin: Some(1000.0),
-            commission: Some(0.1),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(1000.0)),
+            commission: Some(dec!(0.1)),
+            overnight_fee: Some(dec!(0.01)),
             dividend_adjustment: None,
             contract_size: Some(100.0),
         };
@@ -61,10 +63,10 @@ mod orders_format_tests {
     fn test_display_single_line() {
         let cfd = CFD {
             leverage: Some(10),
-            margin: Some(1000.0),
-            commission: Some(0.1),
-            overnight_fee: Some(0.01),
-            dividend_adjustment: Some(0.02),
+            margin: Some(dec!(1000.0)),
+            commission: Some(dec!(0.1)),
+            overnight_fee: Some(dec!(0.01)),
+            dividend_adjustment: Some(dec!(0.02)),
             contract_size: None,
         };
 
@@ -79,12 +81,12 @@ mod orders_format_tests {
     fn test_order_debug_display() {
         let order = Order::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -94,7 +96,7 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(300000.0),
+            Some(dec!(300000.0)),
             Some(123456),
         );
 
@@ -102,13 +104,14 @@ mod orders_format_tests {
 
         let display_output = format!("{:?}", order);
         let expected_output = r#"{
+  "schema_version": 2,
   "id": "order1",
   "quantity": 100,
   "product_type": "Spot",
   "order_type": "Market",
   "price": 3000.0,
-  "timestamp": 1622512800,
-  "expiry_date": 1625114800,
+  "timestamp": 1622512800000,
+  "expiry_date": 1625114800000,
   "symbol": "AAPL",
   "side": "Buy",
   "currency": "USD",
@@ -119,7 +122,13 @@ mod orders_format_tests {
   "swap_opt": null,
   "cfd_opt": null,
   "notional": 300000.0,
-  "nonce": 123456
+  "nonce": 123456,
+  "account_id": null,
+  "portfolio_id": null,
+  "filled_quantity": 0,
+  "avg_fill_price": null,
+  "original_id": null,
+  "version": 0
 }"#;
 
         // Test Display
@@ -130,12 +139,12 @@ mod orders_format_tests {
     fn test_order_single_line() {
         let order = Order::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -145,29 +154,95 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(300000.0),
+            Some(dec!(300000.0)),
             Some(123456),
         );
 
         // println!("{}", order);
 
         let display_output = format!("{}", order);
-        let expected_output = r#"{"id":"order1","quantity":100,"product_type":"Spot","order_type":"Market","price":3000.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":"NASDAQ","timeinforce":"GTC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":300000.0,"nonce":123456}"#;
+        let expected_output = r#"{"schema_version":2,"id":"order1","quantity":100,"product_type":"Spot","order_type":"Market","price":3000.0,"timestamp":1622512800000,"expiry_date":1625114800000,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":"NASDAQ","timeinforce":"GTC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":300000.0,"nonce":123456,"account_id":null,"portfolio_id":null,"filled_quantity":0,"avg_fill_price":null,"original_id":null,"version":0}"#;
 
         // Test Display
         assert_eq!(display_output, expected_output);
     }
 
+    fn order_with_type(order_type: OrderType, price: Option<Decimal>) -> Order {
+        Order::new(
+            String::from("stop1"),
+            Quantity::from(10),
+            ProductType::Spot,
+            order_type,
+            price,
+            Timestamp::from_secs(1622512800),
+            None,
+            String::from("AAPL"),
+            Side::Sell,
+            String::from("USD"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_stop_order_single_line() {
+        let order = order_with_type(
+            OrderType::Stop {
+                stop_price: dec!(95.5),
+            },
+            None,
+        );
+
+        let display_output = format!("{}", order);
+        let expected_output = r#"{"schema_version":2,"id":"stop1","quantity":10,"product_type":"Spot","order_type":{"Stop":{"stop_price":95.5}},"price":null,"timestamp":1622512800000,"expiry_date":null,"symbol":"AAPL","side":"Sell","currency":"USD","exchange":null,"timeinforce":null,"futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":null,"nonce":null,"account_id":null,"portfolio_id":null,"filled_quantity":0,"avg_fill_price":null,"original_id":null,"version":0}"#;
+
+        assert_eq!(display_output, expected_output);
+        let parsed: Order = serde_json::from_str(&display_output).unwrap();
+        assert_eq!(parsed.order_type, order.order_type);
+    }
+
+    #[test]
+    fn test_stop_limit_order_debug_display() {
+        let order_type = OrderType::StopLimit {
+            stop_price: dec!(95.5),
+            limit_price: dec!(95.0),
+        };
+
+        let display_output = format!("{:?}", order_with_type(order_type.clone(), None));
+        let expected_fragment = r#"  "order_type": {
+    "StopLimit": {
+      "stop_price": 95.5,
+      "limit_price": 95.0
+    }
+  },"#;
+
+        assert!(display_output.contains(expected_fragment));
+        let parsed: Order = serde_json::from_str(&display_output).unwrap();
+        assert_eq!(parsed.order_type, order_type);
+    }
+
+    #[test]
+    fn test_unit_order_types_keep_string_format() {
+        let json = format!("{}", order_with_type(OrderType::Limit, Some(dec!(100))));
+        assert!(json.contains(r#""order_type":"Limit""#));
+    }
+
     #[test]
     fn test_parent_order_debug_display() {
         let parent_order = ParentOrder::new(
             String::from("parent_order1"),
-            200,
+            Quantity::from(200),
             ProductType::Futures,
             OrderType::Limit,
-            Some(2500.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(2500.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("ES"),
             Side::Sell,
             String::from("USD"),
@@ -177,7 +252,7 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(500000.0),
+            Some(dec!(500000.0)),
             Some(654321),
             String::from("strategy1"),
         );
@@ -186,13 +261,14 @@ mod orders_format_tests {
 
         let display_output = format!("{:?}", parent_order);
         let expected_output = r#"{
+  "schema_version": 2,
   "id": "parent_order1",
   "quantity": 200,
   "product_type": "Futures",
   "order_type": "Limit",
   "price": 2500.0,
-  "timestamp": 1622512800,
-  "expiry_date": 1625114800,
+  "timestamp": 1622512800000,
+  "expiry_date": 1625114800000,
   "symbol": "ES",
   "side": "Sell",
   "currency": "USD",
@@ -204,6 +280,12 @@ mod orders_format_tests {
   "cfd_opt": null,
   "notional": 500000.0,
   "nonce": 654321,
+  "account_id": null,
+  "portfolio_id": null,
+  "filled_quantity": 0,
+  "avg_fill_price": null,
+  "original_id": null,
+  "version": 0,
   "strategy_id": "strategy1"
 }"#;
 
@@ -215,12 +297,12 @@ mod orders_format_tests {
     fn test_parent_order_single_line() {
         let parent_order = ParentOrder::new(
             String::from("parent_order1"),
-            200,
+            Quantity::from(200),
             ProductType::Futures,
             OrderType::Limit,
-            Some(2500.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(2500.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("ES"),
             Side::Sell,
             String::from("USD"),
@@ -230,7 +312,7 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(500000.0),
+            Some(dec!(500000.0)),
             Some(654321),
             String::from("strategy1"),
         );
@@ -238,7 +320,7 @@ mod orders_format_tests {
         // println!("{}", parent_order);
 
         let display_output = format!("{}", parent_order);
-        let expected_output = r#"{"id":"parent_order1","quantity":200,"product_type":"Futures","order_type":"Limit","price":2500.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"ES","side":"Sell","currency":"USD","exchange":"CME","timeinforce":"FOK","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":500000.0,"nonce":654321,"strategy_id":"strategy1"}"#;
+        let expected_output = r#"{"schema_version":2,"id":"parent_order1","quantity":200,"product_type":"Futures","order_type":"Limit","price":2500.0,"timestamp":1622512800000,"expiry_date":1625114800000,"symbol":"ES","side":"Sell","currency":"USD","exchange":"CME","timeinforce":"FOK","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":500000.0,"nonce":654321,"account_id":null,"portfolio_id":null,"filled_quantity":0,"avg_fill_price":null,"original_id":null,"version":0,"strategy_id":"strategy1"}"#;
 
         // Test Display
         assert_eq!(display_output, expected_output);
@@ -248,12 +330,12 @@ mod orders_format_tests {
     fn test_child_order_debug_display() {
         let child_order = ChildOrder::new(
             String::from("child_order1"),
-            50,
+            Quantity::from(50),
             ProductType::Options,
             OrderType::Market,
-            Some(1500.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(1500.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("GOOGL"),
             Side::Buy,
             String::from("USD"),
@@ -263,7 +345,7 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(75000.0),
+            Some(dec!(75000.0)),
             Some(789012),
             String::from("parent_order2"),
             "parent_order2".to_string(),
@@ -274,13 +356,14 @@ mod orders_format_tests {
 
         let display_output = format!("{:?}", child_order);
         let expected_output = r#"{
+  "schema_version": 2,
   "id": "child_order1",
   "quantity": 50,
   "product_type": "Options",
   "order_type": "Market",
   "price": 1500.0,
-  "timestamp": 1622512800,
-  "expiry_date": 1625114800,
+  "timestamp": 1622512800000,
+  "expiry_date": 1625114800000,
   "symbol": "GOOGL",
   "side": "Buy",
   "currency": "USD",
@@ -292,6 +375,12 @@ mod orders_format_tests {
   "cfd_opt": null,
   "notional": 75000.0,
   "nonce": 789012,
+  "account_id": null,
+  "portfolio_id": null,
+  "filled_quantity": 0,
+  "avg_fill_price": null,
+  "original_id": null,
+  "version": 0,
   "strategy_id": "parent_order2",
   "parent_id": "parent_order2",
   "insert_at": null
@@ -305,12 +394,12 @@ mod orders_format_tests {
     fn test_child_order_single_line() {
         let child_order = ChildOrder::new(
             String::from("child_order1"),
-            50,
+            Quantity::from(50),
             ProductType::Options,
             OrderType::Market,
-            Some(1500.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(1500.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("GOOGL"),
             Side::Buy,
             String::from("USD"),
@@ -320,7 +409,7 @@ mod orders_format_tests {
             None,
             None,
             None,
-            Some(75000.0),
+            Some(dec!(75000.0)),
             Some(789012),
             String::from("parent_order2"),
             "parent_order2".to_string(),
@@ -330,7 +419,7 @@ mod orders_format_tests {
         // println!("{}", child_order);
 
         let display_output = format!("{}", child_order);
-        let expected_output = r#"{"id":"child_order1","quantity":50,"product_type":"Options","order_type":"Market","price":1500.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"GOOGL","side":"Buy","currency":"USD","exchange":"NYSE","timeinforce":"IOC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":75000.0,"nonce":789012,"strategy_id":"parent_order2","parent_id":"parent_order2","insert_at":null}"#;
+        let expected_output = r#"{"schema_version":2,"id":"child_order1","quantity":50,"product_type":"Options","order_type":"Market","price":1500.0,"timestamp":1622512800000,"expiry_date":1625114800000,"symbol":"GOOGL","side":"Buy","currency":"USD","exchange":"NYSE","timeinforce":"IOC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":75000.0,"nonce":789012,"account_id":null,"portfolio_id":null,"filled_quantity":0,"avg_fill_price":null,"original_id":null,"version":0,"strategy_id":"parent_order2","parent_id":"parent_order2","insert_at":null}"#;
 
         // Test Display
         assert_eq!(display_output, expected_output);
@@ -341,9 +430,9 @@ mod orders_format_tests {
         let futures = Futures {
             delivery_date: Some(20240101),
             contract_size: Some(100.0),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
         };
 
         // println!("{:?}", futures);
@@ -366,9 +455,9 @@ mod orders_format_tests {
         let futures = Futures {
             delivery_date: Some(20240101),
             contract_size: Some(100.0),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
         };
 
         // println!("{}", futures);
@@ -383,7 +472,7 @@ mod orders_format_tests {
     #[test]
     fn test_options_debug_display() {
         let options = Options {
-            strike_price: 2000.0,
+            strike_price: dec!(2000.0),
             option_type: OptionType::Call,
             expiry_date: 20241231,
         };
@@ -404,7 +493,7 @@ mod orders_format_tests {
     #[test]
     fn test_options_single_line() {
         let options = Options {
-            strike_price: 2000.0,
+            strike_price: dec!(2000.0),
             option_type: OptionType::Call,
             expiry_date: 20241231,
         };
@@ -424,7 +513,7 @@ mod orders_format_tests {
         let swap = Swap {
             fixed_rate: 1.5,
             floating_rate_index: String::from("LIBOR"),
-            notional_amount: 1000000.0,
+            notional_amount: dec!(1000000.0),
         };
 
         // println!("{:?}", swap);
@@ -445,7 +534,7 @@ mod orders_format_tests {
         let swap = Swap {
             fixed_rate: 1.5,
             floating_rate_index: String::from("LIBOR"),
-            notional_amount: 1000000.0,
+            notional_amount: dec!(1000000.0),
         };
 
         // println!("{}", swap);
@@ -457,4 +546,29 @@ mod orders_format_tests {
         // Test Display
         assert_eq!(display_output, expected_output);
     }
+
+    #[test]
+    fn test_account_ids_single_line() {
+        let order = Order::builder()
+            .id("order1")
+            .quantity(100)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .account_id("acct-1")
+            .portfolio_id("pf-7")
+            .build()
+            .unwrap();
+
+        let display_output = format!("{}", order);
+        let expected_output = r#"{"schema_version":2,"id":"order1","quantity":100,"product_type":"Spot","order_type":"Market","price":null,"timestamp":1622512800000,"expiry_date":null,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":null,"timeinforce":null,"futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":null,"nonce":null,"account_id":"acct-1","portfolio_id":"pf-7","filled_quantity":0,"avg_fill_price":null,"original_id":null,"version":0}"#;
+        assert_eq!(display_output, expected_output);
+
+        // Messages written before the account fields existed read with no account
+        let legacy = expected_output.replace(r#""account_id":"acct-1","portfolio_id":"pf-7","#, "");
+        let parsed: Order = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(parsed.account_id, None);
+        assert_eq!(parsed.portfolio_id, None);
+    }
 }
@@ -0,0 +1,160 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod greeks_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{OptionType, Options};
+    use strategy_execution_engine::{Decimal, Greeks};
+
+    const TOLERANCE: f64 = 1e-4;
+    const DAY_MS: u64 = 86_400_000;
+    // 2023-11-14
+    const TODAY: u64 = 19_675;
+
+    fn option(option_type: OptionType, strike: Decimal, days: f64) -> Options {
+        Options {
+            strike_price: strike,
+            option_type,
+            expiry_date: TODAY * DAY_MS + (days * DAY_MS as f64).round() as u64,
+        }
+    }
+
+    fn assert_greeks(actual: Greeks, expected: Greeks) {
+        for (name, actual, expected) in [
+            ("delta", actual.delta, expected.delta),
+            ("gamma", actual.gamma, expected.gamma),
+            ("vega", actual.vega, expected.vega),
+            ("theta", actual.theta, expected.theta),
+            ("rho", actual.rho, expected.rho),
+        ] {
+            assert!(
+                (actual - expected).abs() < TOLERANCE,
+                "{name}: {actual} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_at_the_money_call_and_put() {
+        // S = K = 100, r = 5%, sigma = 20%, one year: call worth 10.4506, put 5.5735
+        let call = option(OptionType::Call, dec!(100), 365.0).greeks(100.0, 0.2, 0.05, TODAY);
+        assert_greeks(
+            call,
+            Greeks {
+                delta: 0.636831,
+                gamma: 0.018762,
+                vega: 37.524035,
+                theta: -6.414028,
+                rho: 53.232482,
+            },
+        );
+
+        let put = option(OptionType::Put, dec!(100), 365.0).greeks(100.0, 0.2, 0.05, TODAY);
+        assert_greeks(
+            put,
+            Greeks {
+                delta: -0.363169,
+                gamma: 0.018762,
+                vega: 37.524035,
+                theta: -1.657880,
+                rho: -41.890461,
+            },
+        );
+    }
+
+    #[test]
+    fn test_hull_example() {
+        // Hull, Options, Futures and Other Derivatives: S = 49, K = 50, r = 5%, sigma = 20%,
+        // 20 weeks; quoted there as delta 0.522, gamma 0.066, vega 12.1, theta -4.31,
+        // rho 8.91
+        let call = option(OptionType::Call, dec!(50), 20.0 / 52.0 * 365.0);
+        assert_greeks(
+            call.greeks_at(49.0, 0.2, 0.05, TODAY * DAY_MS),
+            Greeks {
+                delta: 0.521605,
+                gamma: 0.065544,
+                vega: 12.105480,
+                theta: -4.305330,
+                rho: 8.906962,
+            },
+        );
+    }
+
+    #[test]
+    fn test_put_call_parity() {
+        let call = option(OptionType::Call, dec!(95), 90.0).greeks(100.0, 0.3, 0.02, TODAY);
+        let put = option(OptionType::Put, dec!(95), 90.0).greeks(100.0, 0.3, 0.02, TODAY);
+        assert!((call.delta - put.delta - 1.0).abs() < TOLERANCE);
+        assert!((call.gamma - put.gamma).abs() < TOLERANCE);
+        assert!((call.vega - put.vega).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_expired_options_keep_intrinsic_delta() {
+        let expired =
+            |option_type, spot| option(option_type, dec!(100), -1.0).greeks(spot, 0.2, 0.05, TODAY);
+        let in_the_money = expired(OptionType::Call, 110.0);
+        assert_eq!(
+            in_the_money,
+            Greeks {
+                delta: 1.0,
+                ..Greeks::default()
+            }
+        );
+        assert_eq!(expired(OptionType::Call, 90.0), Greeks::default());
+        assert_eq!(expired(OptionType::Put, 90.0).delta, -1.0);
+        assert_eq!(expired(OptionType::Put, 110.0).delta, 0.0);
+        // Expiring right now counts as expired
+        assert_eq!(
+            option(OptionType::Call, dec!(100), 0.0)
+                .greeks(100.0, 0.2, 0.05, TODAY)
+                .delta,
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_zero_volatility() {
+        // The forward of the strike decides moneyness: K * exp(-rT) = 95.1229
+        let call = option(OptionType::Call, dec!(100), 365.0).greeks(96.0, 0.0, 0.05, TODAY);
+        assert_greeks(
+            call,
+            Greeks {
+                delta: 1.0,
+                gamma: 0.0,
+                vega: 0.0,
+                theta: -4.756147,
+                rho: 95.122942,
+            },
+        );
+        let put = option(OptionType::Put, dec!(100), 365.0).greeks(96.0, 0.0, 0.05, TODAY);
+        assert_eq!(put, Greeks::default());
+        assert!(call.delta.is_finite() && put.gamma.is_finite());
+    }
+}
@@ -0,0 +1,121 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod ids_tests {
+    use std::collections::HashSet;
+    use std::thread;
+    use strategy_execution_engine::models::orders::{Order, Side};
+    use strategy_execution_engine::{IdGenerator, NonceSource};
+
+    #[test]
+    fn test_uuid_ids_are_unique_and_sorted() {
+        let ids = IdGenerator::default();
+        let generated: Vec<String> = (0..1000).map(|_| ids.next_id()).collect();
+
+        let mut sorted = generated.clone();
+        sorted.sort();
+        assert_eq!(sorted, generated);
+        assert_eq!(generated.iter().collect::<HashSet<_>>().len(), 1000);
+        assert_eq!(generated[0].len(), 36);
+    }
+
+    #[test]
+    fn test_sequential_ids_are_shared_between_clones() {
+        let ids = IdGenerator::sequential("order");
+        let clone = ids.clone();
+        assert_eq!(ids.next_id(), "order-1");
+        assert_eq!(clone.next_id(), "order-2");
+        assert_eq!(ids.next_id(), "order-3");
+    }
+
+    #[test]
+    fn test_custom_id_source() {
+        let ids = IdGenerator::from_fn(|| "fixed".to_string());
+        assert_eq!(ids.next_id(), "fixed");
+    }
+
+    #[test]
+    fn test_counting_nonces() {
+        let nonces = NonceSource::starting_at(10);
+        assert_eq!(nonces.next_nonce(), 10);
+        assert_eq!(nonces.clone().next_nonce(), 11);
+        assert_eq!(nonces.next_nonce(), 12);
+    }
+
+    #[test]
+    fn test_clock_nonces_strictly_increase_across_threads() {
+        let nonces = NonceSource::from_clock();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let nonces = nonces.clone();
+                thread::spawn(move || (0..1000).map(|_| nonces.next_nonce()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        let mut all = HashSet::new();
+        for handle in handles {
+            let values = handle.join().unwrap();
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+            all.extend(values);
+        }
+        assert_eq!(all.len(), 4000);
+        assert!(nonces.next_nonce() > 1_600_000_000_000_000);
+    }
+
+    #[test]
+    fn test_builder_uses_generators() {
+        let ids = IdGenerator::sequential("order");
+        let nonces = NonceSource::starting_at(1);
+        let builder = Order::builder()
+            .quantity(100)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD");
+
+        let first = builder
+            .clone()
+            .generate_id(&ids)
+            .next_nonce(&nonces)
+            .build()
+            .unwrap();
+        let second = builder
+            .clone()
+            .generate_id(&ids)
+            .next_nonce(&nonces)
+            .build()
+            .unwrap();
+        assert_eq!((first.id.as_str(), first.nonce), ("order-1", Some(1)));
+        assert_eq!((second.id.as_str(), second.nonce), ("order-2", Some(2)));
+
+        // Without an explicit id the builder falls back to a UUIDv7
+        let generated = builder.build().unwrap();
+        assert_eq!(generated.id.len(), 36);
+        assert_ne!(generated.id, first.id);
+    }
+}
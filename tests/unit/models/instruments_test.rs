@@ -0,0 +1,199 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod instruments_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{Order, OrderType, Side, ValidationError};
+    use strategy_execution_engine::{Decimal, InstrumentRegistry, InstrumentSpec, Quantity};
+
+    fn registry() -> InstrumentRegistry {
+        let mut registry = InstrumentRegistry::new();
+        registry
+            .register("AAPL", InstrumentSpec::new(dec!(0.01), Quantity::from(1)))
+            .unwrap();
+        let mut future = InstrumentSpec::new(dec!(0.25), Quantity::from(10));
+        future.min_qty = Quantity::from(20);
+        future.contract_multiplier = dec!(50);
+        registry.register("ESZ4", future).unwrap();
+        registry
+    }
+
+    fn limit_order(symbol: &str, price: Decimal, quantity: u32) -> Order {
+        Order::builder()
+            .id("order1")
+            .quantity(quantity)
+            .order_type(OrderType::Limit)
+            .price(price)
+            .symbol(symbol)
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_spec_defaults() {
+        let spec = InstrumentSpec::new(dec!(0.0100), Quantity::from(5));
+        assert_eq!(spec.price_precision, 2);
+        assert_eq!(spec.min_qty, Quantity::from(5));
+        assert_eq!(spec.contract_multiplier, dec!(1));
+    }
+
+    #[test]
+    fn test_round_price_is_passive() {
+        let registry = registry();
+        assert_eq!(
+            registry.round_price("AAPL", dec!(100.003), &Side::Buy),
+            dec!(100.00)
+        );
+        assert_eq!(
+            registry.round_price("AAPL", dec!(100.003), &Side::Sell),
+            dec!(100.01)
+        );
+        assert_eq!(
+            registry.round_price("ESZ4", dec!(4500.10), &Side::Buy),
+            dec!(4500.00)
+        );
+        assert_eq!(
+            registry.round_price("ESZ4", dec!(4500.10), &Side::Sell),
+            dec!(4500.25)
+        );
+        // Prices already on the grid are kept
+        assert_eq!(
+            registry.round_price("ESZ4", dec!(4500.50), &Side::Sell),
+            dec!(4500.50)
+        );
+        // Unknown symbols are not constrained
+        assert_eq!(
+            registry.round_price("MSFT", dec!(1.2345), &Side::Buy),
+            dec!(1.2345)
+        );
+    }
+
+    #[test]
+    fn test_round_qty() {
+        let registry = registry();
+        assert_eq!(
+            registry.round_qty("ESZ4", Quantity::from(37)),
+            Quantity::from(30)
+        );
+        assert_eq!(
+            registry.round_qty("ESZ4", Quantity::from(17)),
+            Quantity::ZERO
+        );
+        assert_eq!(
+            registry.round_qty("AAPL", Quantity::new(dec!(7.9))),
+            Quantity::from(7)
+        );
+        assert_eq!(
+            registry.round_qty("MSFT", Quantity::from(7)),
+            Quantity::from(7)
+        );
+    }
+
+    #[test]
+    fn test_off_tick_limit_price_is_rejected() {
+        let registry = registry();
+        let order = limit_order("AAPL", dec!(100.003), 100);
+        assert!(order.validate_with(&InstrumentRegistry::new()).is_ok());
+        assert_eq!(
+            order.validate_with(&registry),
+            Err(ValidationError::OffTick {
+                field: "Price",
+                value: dec!(100.003),
+                tick_size: dec!(0.01),
+            })
+        );
+        assert!(limit_order("AAPL", dec!(100.01), 100)
+            .validate_with(&registry)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_stop_price_off_tick_is_rejected() {
+        let mut order = limit_order("ESZ4", dec!(4500.25), 20);
+        order.order_type = OrderType::Stop {
+            stop_price: dec!(4499.9),
+        };
+        order.price = None;
+        assert!(matches!(
+            order.validate_with(&registry()),
+            Err(ValidationError::OffTick {
+                field: "Stop price",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_off_lot_and_small_quantities_are_rejected() {
+        let registry = registry();
+        assert_eq!(
+            limit_order("ESZ4", dec!(4500.25), 25).validate_with(&registry),
+            Err(ValidationError::OffLot {
+                quantity: Quantity::from(25),
+                lot_size: Quantity::from(10),
+            })
+        );
+        assert_eq!(
+            limit_order("ESZ4", dec!(4500.25), 10).validate_with(&registry),
+            Err(ValidationError::BelowMinimum {
+                quantity: Quantity::from(10),
+                min_qty: Quantity::from(20),
+            })
+        );
+        assert!(limit_order("ESZ4", dec!(4500.25), 30)
+            .validate_with(&registry)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_round_order() {
+        let registry = registry();
+        let mut order = limit_order("ESZ4", dec!(4500.10), 37);
+        order.side = Side::Sell;
+        registry.round_order(&mut order);
+        assert_eq!(order.price, Some(dec!(4500.25)));
+        assert_eq!(order.quantity, Quantity::from(30));
+        assert!(order.validate_with(&registry).is_ok());
+    }
+
+    #[test]
+    fn test_register_rejects_invalid_spec() {
+        let mut registry = InstrumentRegistry::new();
+        assert!(matches!(
+            registry.register("BAD", InstrumentSpec::new(dec!(0), Quantity::from(1))),
+            Err(ValidationError::NonPositive {
+                field: "Tick size",
+                ..
+            })
+        ));
+        assert!(registry.get("BAD").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+#[cfg(test)]
+mod market_data_tests {
+    use std::time::SystemTime;
+    use strategy_execution_engine::models::market_data::{
+        Candle, MarketData, OrderBook, Ticker, Trade,
+    };
+    use strategy_execution_engine::models::orders::Side;
+
+    #[test]
+    fn test_order_book_mid_price() {
+        let book = OrderBook {
+            bids: vec![(99.0, 1.0), (99.5, 2.0)],
+            asks: vec![(101.0, 1.0), (100.5, 3.0)],
+        };
+        assert_eq!(book.mid_price(), Some(100.0));
+        assert_eq!(OrderBook::default().mid_price(), None);
+    }
+
+    #[test]
+    fn test_market_data_price() {
+        let now = SystemTime::now();
+        let trade = MarketData::Trade(Trade {
+            timestamp: now,
+            price: 10.0,
+            size: 1.0,
+            side: Side::Buy,
+        });
+        let ticker = MarketData::Ticker(Ticker {
+            timestamp: now,
+            bid: 10.5,
+            ask: 11.5,
+            last: 11.0,
+        });
+        let candle = MarketData::Candle(Candle {
+            timestamp: now,
+            open: 11.0,
+            high: 13.0,
+            low: 10.0,
+            close: 12.0,
+            volume: 100.0,
+        });
+        assert_eq!(trade.price(), Some(10.0));
+        assert_eq!(ticker.price(), Some(11.0));
+        assert_eq!(candle.price(), Some(12.0));
+        assert_eq!(MarketData::OrderBook(OrderBook::default()).price(), None);
+    }
+
+    #[test]
+    fn test_timestamps_serialize_as_epoch_millis() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let trade = Trade {
+            timestamp: UNIX_EPOCH + Duration::from_millis(1_716_591_600_250),
+            price: 10.0,
+            size: 2.0,
+            side: Side::Sell,
+        };
+        let json = serde_json::to_string(&trade).unwrap();
+        assert!(json.contains("\"timestamp\":1716591600250"));
+
+        let restored: Trade = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.timestamp, trade.timestamp);
+        assert_eq!(restored.side, Side::Sell);
+    }
+}
@@ -26,7 +26,28 @@ THE SOFTWARE.
    Date: 26/5/24
 ******************************************************************************/
 
+mod amendments_test;
+mod bracket_test;
+mod builder_test;
+mod calendar_test;
+#[allow(unused_imports, clippy::single_component_path_imports)]
 mod child_orders_test;
+mod csv_orders_test;
+mod decimal_test;
+mod fills_test;
 mod format_test;
+mod greeks_test;
+mod ids_test;
+mod instruments_test;
+mod market_data_test;
+mod msgpack_test;
+mod oco_test;
+#[allow(unused_imports)]
 mod orders_test;
+#[allow(unused_imports, clippy::single_component_path_imports)]
 mod parent_orders_test;
+mod parent_state_test;
+mod proto_test;
+mod quantity_test;
+mod schema_test;
+mod timestamp_test;
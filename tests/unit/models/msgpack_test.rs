@@ -0,0 +1,130 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(all(test, feature = "msgpack"))]
+mod msgpack_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{
+        OffsetType, Order, OrderType, ProductType, Side, Swap, TimeInForce, TrailingSpec, CFD,
+    };
+    use strategy_execution_engine::{ChildOrder, Encoding, ParentOrder, Quantity, Timestamp};
+
+    // Every optional field populated, including the fill and amendment state
+    fn order() -> Order {
+        let mut order = Order::builder()
+            .id("order1")
+            .quantity(Quantity::new(dec!(1500.5)))
+            .product_type(ProductType::CFD)
+            .order_type(OrderType::Limit)
+            .price(dec!(101.25))
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("EURUSD")
+            .side(Side::Buy)
+            .currency("USD")
+            .exchange("LMAX")
+            .timeinforce(TimeInForce::GTD)
+            .expiry_date(Timestamp::from_secs(1625114800))
+            .cfd(CFD {
+                leverage: Some(30),
+                margin: Some(dec!(1000.5)),
+                commission: Some(dec!(0.1)),
+                overnight_fee: Some(dec!(0.01)),
+                dividend_adjustment: Some(dec!(0.02)),
+                contract_size: Some(100000.0),
+            })
+            .notional(dec!(151925.625))
+            .nonce(42)
+            .account_id("acct-1")
+            .build()
+            .unwrap();
+        order.filled_quantity = Quantity::new(dec!(0.5));
+        order.avg_fill_price = Some(dec!(101.2));
+        order.original_id = Some(String::from("order0"));
+        order.version = 3;
+        order.quantity_step = Some(Quantity::new(dec!(0.5)));
+        order
+    }
+
+    #[test]
+    fn test_order_round_trip() {
+        let order = order();
+        let decoded = Order::from_msgpack(&order.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), order.to_string());
+        assert_eq!(decoded.quantity, Quantity::new(dec!(1500.5)));
+        assert_eq!(decoded.quantity_step, Some(Quantity::new(dec!(0.5))));
+    }
+
+    #[test]
+    fn test_trailing_stop_swap_round_trip() {
+        let mut order = order();
+        order.product_type = ProductType::Swap;
+        order.cfd_opt = None;
+        order.swap_opt = Some(Swap {
+            fixed_rate: 0.025,
+            floating_rate_index: String::from("SOFR"),
+            notional_amount: dec!(1000000),
+        });
+        order.order_type =
+            OrderType::TrailingStop(TrailingSpec::new(dec!(1.5), OffsetType::Percent));
+        order.price = None;
+        let decoded = Order::from_msgpack(&order.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), order.to_string());
+    }
+
+    #[test]
+    fn test_parent_and_child_round_trip() {
+        let parent_order = order().into_parent("strategy1");
+        let decoded = ParentOrder::from_msgpack(&parent_order.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), parent_order.to_string());
+
+        let child_order = order().into_child(
+            "strategy1",
+            "parent1",
+            Some(Timestamp::from_secs(1622512860)),
+        );
+        let decoded = ChildOrder::from_msgpack(&child_order.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), child_order.to_string());
+    }
+
+    #[test]
+    fn test_msgpack_is_smaller_than_json() {
+        let child_order = order().into_child("strategy1", "parent1", None);
+        let json = Encoding::Json.encode(&child_order).unwrap();
+        let msgpack = Encoding::MsgPack.encode(&child_order).unwrap();
+        assert_eq!(json, child_order.to_string().into_bytes());
+        assert!(msgpack.len() < json.len());
+        let decoded: ChildOrder = Encoding::MsgPack.decode(&msgpack).unwrap();
+        assert_eq!(decoded.to_string(), child_order.to_string());
+    }
+
+    #[test]
+    fn test_decode_errors_are_reported() {
+        assert!(Order::from_msgpack(&[0xc1]).is_err());
+        assert!(Encoding::MsgPack.decode::<Order>(b"{}").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod oco_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::fills::{Fill, FillError, Liquidity};
+    use strategy_execution_engine::models::oco::{OcoError, OcoGroup};
+    use strategy_execution_engine::models::orders::{
+        Order, OrderStatus, OrderType, Side, ValidationError,
+    };
+    use strategy_execution_engine::{Quantity, Timestamp};
+
+    fn entry(side: Side) -> Order {
+        Order::builder()
+            .id("entry1")
+            .quantity(10)
+            .order_type(OrderType::Limit)
+            .price(dec!(100))
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(side)
+            .currency("USD")
+            .exchange("NASDAQ")
+            .build()
+            .unwrap()
+    }
+
+    fn group() -> OcoGroup {
+        OcoGroup::from_position(
+            &entry(Side::Buy),
+            dec!(100),
+            Quantity::from(10),
+            dec!(0.01),
+            dec!(0.02),
+        )
+        .unwrap()
+    }
+
+    fn fill(order_id: &str, exec_id: &str, quantity: u32) -> Fill {
+        Fill::new(
+            String::from(order_id),
+            String::from(exec_id),
+            dec!(102),
+            Quantity::from(quantity),
+            1622512900,
+            dec!(0),
+            Liquidity::Maker,
+        )
+    }
+
+    fn status(group: &OcoGroup, order_id: &str) -> OrderStatus {
+        group.member(order_id).unwrap().status
+    }
+
+    #[test]
+    fn test_from_long_position() {
+        let group = group();
+        assert_eq!(group.group_id, "entry1-oco");
+        let take_profit = &group.member("entry1-tp").unwrap().order;
+        assert_eq!(take_profit.side, Side::Sell);
+        assert_eq!(take_profit.order_type, OrderType::Limit);
+        assert_eq!(take_profit.price, Some(dec!(102)));
+        assert_eq!(take_profit.exchange.as_deref(), Some("NASDAQ"));
+        let stop = &group.member("entry1-sl").unwrap().order;
+        assert_eq!(stop.side, Side::Sell);
+        assert_eq!(
+            stop.order_type,
+            OrderType::Stop {
+                stop_price: dec!(99)
+            }
+        );
+        assert_eq!(stop.quantity, Quantity::from(10));
+        assert!(group.is_active());
+    }
+
+    #[test]
+    fn test_from_short_position() {
+        let group = OcoGroup::from_position(
+            &entry(Side::Sell),
+            dec!(100),
+            Quantity::from(10),
+            dec!(0.01),
+            dec!(0.02),
+        )
+        .unwrap();
+        let take_profit = &group.member("entry1-tp").unwrap().order;
+        assert_eq!(take_profit.side, Side::Buy);
+        assert_eq!(take_profit.price, Some(dec!(98)));
+        assert_eq!(
+            group.member("entry1-sl").unwrap().order.order_type,
+            OrderType::Stop {
+                stop_price: dec!(101)
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_position_rejects_non_positive_percentages() {
+        let result = OcoGroup::from_position(
+            &entry(Side::Buy),
+            dec!(100),
+            Quantity::from(10),
+            dec!(0),
+            dec!(0.02),
+        );
+        assert!(matches!(
+            result,
+            Err(OcoError::Invalid(ValidationError::NonPositive { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_needs_two_distinct_members() {
+        let order = entry(Side::Buy);
+        assert!(matches!(
+            OcoGroup::new(String::from("oco1"), vec![order.clone()]),
+            Err(OcoError::TooFewMembers { .. })
+        ));
+        assert!(matches!(
+            OcoGroup::new(String::from("oco1"), vec![order.clone(), order]),
+            Err(OcoError::DuplicateMember { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fill_one_cancels_other() {
+        let mut group = group();
+        let cancelled = group.on_fill(&fill("entry1-tp", "exec1", 10)).unwrap();
+        assert_eq!(cancelled, vec![String::from("entry1-sl")]);
+        assert_eq!(status(&group, "entry1-tp"), OrderStatus::Filled);
+        assert_eq!(status(&group, "entry1-sl"), OrderStatus::Cancelled);
+        assert_eq!(group.triggered_by.as_deref(), Some("entry1-tp"));
+        assert!(!group.is_active());
+        assert!(group.is_done());
+    }
+
+    #[test]
+    fn test_partial_fill_triggers_group() {
+        let mut group = group();
+        let cancelled = group.on_fill(&fill("entry1-tp", "exec1", 4)).unwrap();
+        assert_eq!(cancelled, vec![String::from("entry1-sl")]);
+        assert_eq!(status(&group, "entry1-tp"), OrderStatus::PartiallyFilled);
+        assert!(!group.is_done());
+
+        // The triggering member keeps filling
+        assert!(group
+            .on_fill(&fill("entry1-tp", "exec2", 6))
+            .unwrap()
+            .is_empty());
+        assert_eq!(status(&group, "entry1-tp"), OrderStatus::Filled);
+        assert!(group.is_done());
+    }
+
+    #[test]
+    fn test_double_fill_race_rejected() {
+        let mut group = group();
+        group.on_fill(&fill("entry1-tp", "exec1", 10)).unwrap();
+
+        let result = group.on_fill(&fill("entry1-sl", "exec2", 10));
+        assert_eq!(
+            result,
+            Err(OcoError::AlreadyTriggered {
+                group_id: String::from("entry1-oco"),
+                order_id: String::from("entry1-sl"),
+                triggered_by: String::from("entry1-tp"),
+            })
+        );
+        let stop = group.member("entry1-sl").unwrap();
+        assert_eq!(stop.status, OrderStatus::Cancelled);
+        assert_eq!(stop.order.filled_quantity, Quantity::ZERO);
+    }
+
+    #[test]
+    fn test_independent_cancel_keeps_group() {
+        let mut group = group();
+        group.on_cancel("entry1-sl").unwrap();
+        assert_eq!(status(&group, "entry1-sl"), OrderStatus::Cancelled);
+        assert!(group.is_active());
+        assert_eq!(group.open_orders().count(), 1);
+
+        assert!(group
+            .on_fill(&fill("entry1-tp", "exec1", 10))
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            group.on_fill(&fill("entry1-sl", "exec2", 1)),
+            Err(OcoError::AlreadyTriggered { .. })
+        ));
+        assert!(matches!(
+            group.on_cancel("entry1-sl"),
+            Err(OcoError::MemberClosed {
+                status: OrderStatus::Cancelled,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_rejected_fill_leaves_group_untouched() {
+        let mut group = group();
+        assert!(matches!(
+            group.on_fill(&fill("entry1-tp", "exec1", 11)),
+            Err(OcoError::Fill(FillError::Overfill { .. }))
+        ));
+        assert!(matches!(
+            group.on_fill(&fill("other", "exec2", 1)),
+            Err(OcoError::UnknownOrder { .. })
+        ));
+        assert!(group.is_active());
+        assert_eq!(status(&group, "entry1-sl"), OrderStatus::New);
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut group = group();
+        group.on_fill(&fill("entry1-sl", "exec1", 10)).unwrap();
+        let json = group.to_string();
+        assert!(json.contains("\"status\":\"Cancelled\""));
+        let decoded: OcoGroup = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.triggered_by.as_deref(), Some("entry1-sl"));
+        assert_eq!(decoded.to_string(), json);
+    }
+}
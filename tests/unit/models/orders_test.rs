@@ -29,10 +29,13 @@ THE SOFTWARE.
 #[cfg(test)]
 mod orders_tests {
     use super::*;
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::builder::OrderBuilder;
     use strategy_execution_engine::models::orders::{
-        Futures, OptionType, Options, Order, OrderType, ProductType, Side, Swap, TimeInForce, CFD,
+        AssetClass, Futures, OffsetType, OptionType, Options, Order, OrderType, ProductType, Side,
+        Spot, Swap, TimeInForce, TrailingSpec, CFD,
     };
-    use strategy_execution_engine::Validate;
+    use strategy_execution_engine::{Decimal, Quantity, Timestamp, Validate, ValidationError};
 
     #[test]
     fn test_create_product_type() {
@@ -92,27 +95,27 @@ mod orders_tests {
         let futures = Futures {
             delivery_date: Some(20240101),
             contract_size: Some(100.0),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
         };
 
         assert_eq!(futures.delivery_date, Some(20240101));
         assert_eq!(futures.contract_size, Some(100.0));
-        assert_eq!(futures.margin, Some(0.05));
-        assert_eq!(futures.commission, Some(0.02));
-        assert_eq!(futures.overnight_fee, Some(0.01));
+        assert_eq!(futures.margin, Some(dec!(0.05)));
+        assert_eq!(futures.commission, Some(dec!(0.02)));
+        assert_eq!(futures.overnight_fee, Some(dec!(0.01)));
     }
 
     #[test]
     fn test_create_options() {
         let options = Options {
-            strike_price: 3000.0,
+            strike_price: dec!(3000.0),
             option_type: OptionType::Call,
             expiry_date: 20241231,
         };
 
-        assert_eq!(options.strike_price, 3000.0);
+        assert_eq!(options.strike_price, dec!(3000.0));
         assert_eq!(format!("{:?}", options.option_type), "Call");
         assert_eq!(options.expiry_date, 20241231);
     }
@@ -122,30 +125,30 @@ mod orders_tests {
         let swap = Swap {
             fixed_rate: 1.5,
             floating_rate_index: String::from("LIBOR"),
-            notional_amount: 1000000.0,
+            notional_amount: dec!(1000000.0),
         };
 
         assert_eq!(swap.fixed_rate, 1.5);
         assert_eq!(swap.floating_rate_index, "LIBOR");
-        assert_eq!(swap.notional_amount, 1000000.0);
+        assert_eq!(swap.notional_amount, dec!(1000000.0));
     }
 
     #[test]
     fn test_create_cfd() {
         let cfd = CFD {
             leverage: Some(10),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
-            dividend_adjustment: Some(0.005),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
+            dividend_adjustment: Some(dec!(0.005)),
             contract_size: Some(100.0),
         };
 
         assert_eq!(cfd.leverage, Some(10));
-        assert_eq!(cfd.margin, Some(0.05));
-        assert_eq!(cfd.commission, Some(0.02));
-        assert_eq!(cfd.overnight_fee, Some(0.01));
-        assert_eq!(cfd.dividend_adjustment, Some(0.005));
+        assert_eq!(cfd.margin, Some(dec!(0.05)));
+        assert_eq!(cfd.commission, Some(dec!(0.02)));
+        assert_eq!(cfd.overnight_fee, Some(dec!(0.01)));
+        assert_eq!(cfd.dividend_adjustment, Some(dec!(0.005)));
         assert_eq!(cfd.contract_size, Some(100.0));
     }
 
@@ -153,12 +156,12 @@ mod orders_tests {
     fn test_create_order() {
         let order = Order::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -173,12 +176,12 @@ mod orders_tests {
         );
 
         assert_eq!(order.id, "order1");
-        assert_eq!(order.quantity, 100);
+        assert_eq!(order.quantity, Quantity::from(100));
         assert_eq!(format!("{:?}", order.product_type), "Spot");
         assert_eq!(format!("{:?}", order.order_type), "Market");
-        assert_eq!(order.price, Some(3000.0));
-        assert_eq!(order.timestamp, 1622512800);
-        assert_eq!(order.expiry_date, Some(1625114800));
+        assert_eq!(order.price, Some(dec!(3000.0)));
+        assert_eq!(order.timestamp, Timestamp::from_secs(1622512800));
+        assert_eq!(order.expiry_date, Some(Timestamp::from_secs(1625114800)));
         assert_eq!(order.symbol, "AAPL");
         assert_eq!(format!("{:?}", order.side), "Buy");
         assert_eq!(order.currency, "USD");
@@ -195,18 +198,18 @@ mod orders_tests {
         let futures = Futures {
             delivery_date: Some(20240101),
             contract_size: Some(100.0),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
         };
 
         let order = Order::new(
             String::from("order2"),
-            50,
+            Quantity::from(50),
             ProductType::Futures,
             OrderType::Limit,
-            Some(3200.0),
-            1622512800,
+            Some(dec!(3200.0)),
+            Timestamp::from_secs(1622512800),
             None,
             String::from("ES"),
             Side::Sell,
@@ -222,11 +225,11 @@ mod orders_tests {
         );
 
         assert_eq!(order.id, "order2");
-        assert_eq!(order.quantity, 50);
+        assert_eq!(order.quantity, Quantity::from(50));
         assert_eq!(format!("{:?}", order.product_type), "Futures");
         assert_eq!(format!("{:?}", order.order_type), "Limit");
-        assert_eq!(order.price, Some(3200.0));
-        assert_eq!(order.timestamp, 1622512800);
+        assert_eq!(order.price, Some(dec!(3200.0)));
+        assert_eq!(order.timestamp, Timestamp::from_secs(1622512800));
         assert_eq!(order.expiry_date, None);
         assert_eq!(order.symbol, "ES");
         assert_eq!(format!("{:?}", order.side), "Sell");
@@ -242,19 +245,19 @@ mod orders_tests {
     #[test]
     fn test_order_with_options() {
         let options = Options {
-            strike_price: 3000.0,
+            strike_price: dec!(3000.0),
             option_type: OptionType::Call,
             expiry_date: 20241231,
         };
 
         let order = Order::new(
             String::from("order3"),
-            75,
+            Quantity::from(75),
             ProductType::Options,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -264,17 +267,17 @@ mod orders_tests {
             Some(options),
             None,
             None,
-            Some(13.3),
+            Some(dec!(13.3)),
             Some(123456),
         );
 
         assert_eq!(order.id, "order3");
-        assert_eq!(order.quantity, 75);
+        assert_eq!(order.quantity, Quantity::from(75));
         assert_eq!(format!("{:?}", order.product_type), "Options");
         assert_eq!(format!("{:?}", order.order_type), "Market");
-        assert_eq!(order.price, Some(3000.0));
-        assert_eq!(order.timestamp, 1622512800);
-        assert_eq!(order.expiry_date, Some(1625114800));
+        assert_eq!(order.price, Some(dec!(3000.0)));
+        assert_eq!(order.timestamp, Timestamp::from_secs(1622512800));
+        assert_eq!(order.expiry_date, Some(Timestamp::from_secs(1625114800)));
         assert_eq!(order.symbol, "AAPL");
         assert_eq!(format!("{:?}", order.side), "Buy");
         assert_eq!(order.currency, "USD");
@@ -284,7 +287,7 @@ mod orders_tests {
         assert!(order.options_opt.is_some());
         assert!(order.swap_opt.is_none());
         assert!(order.cfd_opt.is_none());
-        assert_eq!(order.notional, Some(13.3));
+        assert_eq!(order.notional, Some(dec!(13.3)));
         assert_eq!(order.nonce, Some(123456));
     }
 
@@ -292,12 +295,12 @@ mod orders_tests {
     fn test_order_validation() {
         let order = Order::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
-            OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            OrderType::Limit,
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -317,12 +320,12 @@ mod orders_tests {
     fn test_order_validation_failure() {
         let order = Order::new(
             String::new(),
-            0,
+            Quantity::ZERO,
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::new(),
             Side::Buy,
             String::new(),
@@ -337,4 +340,436 @@ mod orders_tests {
         );
         assert!(order.validate().is_err());
     }
+
+    fn stop_order(order_type: OrderType, price: Option<Decimal>) -> Order {
+        Order::new(
+            String::from("order1"),
+            Quantity::from(100),
+            ProductType::Spot,
+            order_type,
+            price,
+            Timestamp::from_secs(1622512800),
+            None,
+            String::from("AAPL"),
+            Side::Sell,
+            String::from("USD"),
+            None,
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_stop_order_validation() {
+        let stop = OrderType::Stop {
+            stop_price: dec!(95.5),
+        };
+        assert_eq!(stop.trigger_price(), Some(dec!(95.5)));
+        assert!(stop.is_market());
+        assert!(stop_order(stop, None).validate().is_ok());
+
+        let zero_stop = OrderType::Stop {
+            stop_price: Decimal::ZERO,
+        };
+        assert!(stop_order(zero_stop, None).validate().is_err());
+    }
+
+    #[test]
+    fn test_stop_limit_order_validation() {
+        let stop_limit = OrderType::StopLimit {
+            stop_price: dec!(95.5),
+            limit_price: dec!(95.0),
+        };
+        assert_eq!(stop_limit.trigger_price(), Some(dec!(95.5)));
+        assert!(!stop_limit.is_market());
+        assert!(stop_order(stop_limit.clone(), Some(dec!(95.0)))
+            .validate()
+            .is_ok());
+        assert_eq!(
+            stop_order(stop_limit.clone(), None).validate(),
+            Err(ValidationError::MissingForProduct {
+                product: "Stop limit",
+                field: "a price"
+            })
+        );
+
+        // The order price, when given, must agree with the stop limit price
+        let result = stop_order(stop_limit, Some(dec!(94.0))).validate();
+        assert_eq!(
+            result,
+            Err(ValidationError::InconsistentFields(
+                "Price must match the stop limit price".to_string()
+            ))
+        );
+
+        let negative_limit = OrderType::StopLimit {
+            stop_price: dec!(95.5),
+            limit_price: dec!(-1),
+        };
+        assert!(stop_order(negative_limit, None).validate().is_err());
+    }
+
+    #[test]
+    fn test_plain_order_types_have_no_trigger() {
+        assert_eq!(OrderType::Market.trigger_price(), None);
+        assert_eq!(OrderType::Limit.trigger_price(), None);
+    }
+
+    #[test]
+    fn test_trailing_stop_order_validation() {
+        let trailing =
+            |offset, offset_type| OrderType::TrailingStop(TrailingSpec::new(offset, offset_type));
+
+        assert!(stop_order(trailing(dec!(1.5), OffsetType::Absolute), None)
+            .validate()
+            .is_ok());
+        assert!(stop_order(trailing(dec!(2), OffsetType::Percent), None)
+            .validate()
+            .is_ok());
+        assert!(
+            stop_order(trailing(Decimal::ZERO, OffsetType::Absolute), None)
+                .validate()
+                .is_err()
+        );
+        assert!(stop_order(trailing(dec!(-2), OffsetType::Percent), None)
+            .validate()
+            .is_err());
+        assert!(trailing(dec!(2), OffsetType::Percent).is_market());
+    }
+
+    #[test]
+    fn test_trailing_spec_stop_levels() {
+        let absolute = TrailingSpec::new(dec!(2), OffsetType::Absolute);
+        assert_eq!(absolute.stop_from(dec!(100), &Side::Sell), dec!(98));
+        assert_eq!(absolute.stop_from(dec!(100), &Side::Buy), dec!(102));
+
+        let percent = TrailingSpec::new(dec!(0.5), OffsetType::Percent);
+        assert_eq!(percent.stop_from(dec!(200), &Side::Sell), dec!(199));
+        assert_eq!(percent.stop_from(dec!(200), &Side::Buy), dec!(201));
+    }
+
+    fn timed_order(timeinforce: TimeInForce, expiry_date: Option<u64>) -> Order {
+        Order::new(
+            String::from("order1"),
+            Quantity::from(100),
+            ProductType::Spot,
+            OrderType::Limit,
+            Some(dec!(3000.0)),
+            Timestamp::from_millis(1_000),
+            expiry_date.map(Timestamp::from_millis),
+            String::from("AAPL"),
+            Side::Buy,
+            String::from("USD"),
+            None,
+            Some(timeinforce),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_gtd_requires_expiry_date() {
+        assert!(timed_order(TimeInForce::GTD, Some(2_000))
+            .validate()
+            .is_ok());
+        assert_eq!(
+            timed_order(TimeInForce::GTD, None).validate(),
+            Err(ValidationError::MissingForProduct {
+                product: "GTD",
+                field: "an expiry date"
+            })
+        );
+        assert_eq!(
+            timed_order(TimeInForce::GTD, Some(1_000)).validate(),
+            Err(ValidationError::InconsistentFields(
+                "Expiry date must be after the order timestamp".to_string()
+            ))
+        );
+        assert!(timed_order(TimeInForce::GTC, None).validate().is_ok());
+    }
+
+    #[test]
+    fn test_gtd_expiry() {
+        let order = timed_order(TimeInForce::GTD, Some(2_000));
+        assert!(!order.is_expired(1_500));
+        assert!(order.is_expired(2_000));
+        assert!(order.is_expired(3_000));
+    }
+
+    #[test]
+    fn test_non_gtd_orders_do_not_expire() {
+        for timeinforce in [TimeInForce::GTC, TimeInForce::IOC, TimeInForce::FOK] {
+            assert!(!timed_order(timeinforce, Some(2_000)).is_expired(3_000));
+        }
+    }
+
+    #[test]
+    fn test_spot_validation() {
+        let stock = Spot {
+            asset_class: Some(AssetClass::Stock),
+        };
+        assert!(stock.validate().is_ok());
+        assert!(stock.validate_for(&ProductType::Spot).is_ok());
+        assert_eq!(
+            stock.validate_for(&ProductType::Futures),
+            Err(ValidationError::InconsistentFields(
+                "Spot details require a Spot product type".to_string()
+            ))
+        );
+
+        let derivative = Spot {
+            asset_class: Some(AssetClass::Derivative),
+        };
+        assert_eq!(
+            derivative.validate(),
+            Err(ValidationError::InconsistentFields(
+                "Spot asset class cannot be Derivative".to_string()
+            ))
+        );
+
+        assert!(Spot { asset_class: None }.validate().is_ok());
+    }
+
+    fn futures() -> Futures {
+        Futures {
+            delivery_date: Some(20240101),
+            contract_size: Some(100.0),
+            margin: None,
+            commission: None,
+            overnight_fee: None,
+        }
+    }
+
+    fn options() -> Options {
+        Options {
+            strike_price: dec!(3000.0),
+            option_type: OptionType::Call,
+            expiry_date: 20241231,
+        }
+    }
+
+    fn cfd() -> CFD {
+        CFD {
+            leverage: Some(10),
+            margin: None,
+            commission: None,
+            overnight_fee: None,
+            dividend_adjustment: None,
+            contract_size: None,
+        }
+    }
+
+    fn base_order() -> OrderBuilder {
+        Order::builder()
+            .id("order1")
+            .quantity(10)
+            .timestamp(1_000)
+            .symbol("ES")
+            .side(Side::Buy)
+            .currency("USD")
+    }
+
+    fn rejection(builder: OrderBuilder) -> String {
+        match builder.build() {
+            Err(error) => error.to_string(),
+            other => panic!("expected a validation error, got {:?}", other.map(|o| o.id)),
+        }
+    }
+
+    #[test]
+    fn test_limit_orders_require_positive_price() {
+        assert!(base_order()
+            .order_type(OrderType::Limit)
+            .price(dec!(10))
+            .build()
+            .is_ok());
+        assert_eq!(
+            rejection(base_order().order_type(OrderType::Limit)),
+            "Limit orders require a price"
+        );
+        assert_eq!(
+            rejection(
+                base_order()
+                    .order_type(OrderType::Limit)
+                    .price(Decimal::ZERO)
+            ),
+            "Price must be greater than zero"
+        );
+    }
+
+    #[test]
+    fn test_market_orders_reject_price() {
+        assert!(base_order().order_type(OrderType::Market).build().is_ok());
+        assert_eq!(
+            rejection(base_order().order_type(OrderType::Market).price(dec!(10))),
+            "Market and stop orders cannot have a price"
+        );
+        let stop = OrderType::Stop {
+            stop_price: dec!(9),
+        };
+        assert!(base_order().order_type(stop.clone()).build().is_ok());
+        assert_eq!(
+            rejection(base_order().order_type(stop).price(dec!(10))),
+            "Market and stop orders cannot have a price"
+        );
+    }
+
+    #[test]
+    fn test_derivative_details_match_product_type() {
+        assert!(base_order()
+            .product_type(ProductType::Futures)
+            .futures(futures())
+            .build()
+            .is_ok());
+        assert!(base_order()
+            .product_type(ProductType::Options)
+            .options(options())
+            .build()
+            .is_ok());
+        assert!(base_order()
+            .product_type(ProductType::CFD)
+            .cfd(cfd())
+            .build()
+            .is_ok());
+        assert_eq!(
+            rejection(
+                base_order()
+                    .product_type(ProductType::Options)
+                    .futures(futures())
+            ),
+            "Options orders cannot carry futures details"
+        );
+        assert_eq!(
+            rejection(base_order().product_type(ProductType::Swap).cfd(cfd())),
+            "Swap orders cannot carry cfd details"
+        );
+    }
+
+    #[test]
+    fn test_only_one_derivative_detail() {
+        assert_eq!(
+            rejection(
+                base_order()
+                    .product_type(ProductType::Futures)
+                    .futures(futures())
+                    .cfd(cfd())
+            ),
+            "Only one derivative detail struct may be set"
+        );
+    }
+
+    #[test]
+    fn test_spot_orders_carry_no_derivative_details() {
+        assert!(base_order().product_type(ProductType::Spot).build().is_ok());
+        assert_eq!(
+            rejection(
+                base_order()
+                    .product_type(ProductType::Spot)
+                    .options(options())
+            ),
+            "Spot orders cannot carry derivative details"
+        );
+    }
+
+    #[test]
+    fn test_validation_errors_are_typed() {
+        let zero_quantity = base_order().quantity(0).build().err();
+        assert_eq!(
+            zero_quantity,
+            Some(ValidationError::NonPositive {
+                field: "Quantity",
+                value: Decimal::ZERO
+            })
+        );
+
+        let bad_strike = Options {
+            strike_price: dec!(-5),
+            ..options()
+        };
+        assert_eq!(
+            bad_strike.validate(),
+            Err(ValidationError::NonPositive {
+                field: "Strike price",
+                value: dec!(-5)
+            })
+        );
+
+        let negative_margin = CFD {
+            margin: Some(dec!(-1)),
+            ..cfd()
+        };
+        assert_eq!(
+            negative_margin.validate(),
+            Err(ValidationError::Negative {
+                field: "Margin",
+                value: dec!(-1)
+            })
+        );
+    }
+
+    #[test]
+    fn test_validation_error_messages() {
+        let cases = [
+            (
+                ValidationError::EmptyField("Symbol"),
+                "Symbol cannot be empty",
+            ),
+            (
+                ValidationError::NonPositive {
+                    field: "Strike price",
+                    value: Decimal::ZERO,
+                },
+                "Strike price must be greater than zero",
+            ),
+            (
+                ValidationError::Negative {
+                    field: "Commission",
+                    value: dec!(-0.1),
+                },
+                "Commission cannot be negative",
+            ),
+            (
+                ValidationError::MissingForProduct {
+                    product: "Limit",
+                    field: "a price",
+                },
+                "Limit orders require a price",
+            ),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_order_enums_have_one_definition() {
+        // The crate root, the models module and models::orders all expose the same types,
+        // so values flow between them without conversion.
+        fn same<T>(_: &T, _: &T) {}
+
+        same(&Side::Buy, &strategy_execution_engine::Side::Buy);
+        same(&Side::Buy, &strategy_execution_engine::models::Side::Sell);
+        same(
+            &OrderType::Market,
+            &strategy_execution_engine::OrderType::Limit,
+        );
+        same(
+            &ProductType::Spot,
+            &strategy_execution_engine::ProductType::CFD,
+        );
+        same(
+            &TimeInForce::FOK,
+            &strategy_execution_engine::TimeInForce::GTD,
+        );
+        assert_eq!(Side::Buy, strategy_execution_engine::Side::Buy);
+    }
 }
@@ -28,23 +28,24 @@ THE SOFTWARE.
 
 #[cfg(test)]
 mod parent_orders_tests {
+    use rust_decimal_macros::dec;
     use serde_json;
     use strategy_execution_engine::models::orders::{
         Futures, OptionType, Options, OrderType, ProductType, Side, Swap, TimeInForce, CFD,
     };
     use strategy_execution_engine::models::parent_orders::ParentOrder;
-    use strategy_execution_engine::Validate;
+    use strategy_execution_engine::{Decimal, Quantity, Timestamp, Validate, ValidationError};
 
     #[test]
     fn test_create_parent_order() {
         let parent_order = ParentOrder::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -60,7 +61,7 @@ mod parent_orders_tests {
         );
 
         assert_eq!(parent_order.order_common.id, "order1");
-        assert_eq!(parent_order.order_common.quantity, 100);
+        assert_eq!(parent_order.order_common.quantity, Quantity::from(100));
         assert_eq!(
             format!("{:?}", parent_order.order_common.product_type),
             "Spot"
@@ -69,9 +70,15 @@ mod parent_orders_tests {
             format!("{:?}", parent_order.order_common.order_type),
             "Market"
         );
-        assert_eq!(parent_order.order_common.price, Some(3000.0));
-        assert_eq!(parent_order.order_common.timestamp, 1622512800);
-        assert_eq!(parent_order.order_common.expiry_date, Some(1625114800));
+        assert_eq!(parent_order.order_common.price, Some(dec!(3000.0)));
+        assert_eq!(
+            parent_order.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
+        assert_eq!(
+            parent_order.order_common.expiry_date,
+            Some(Timestamp::from_secs(1625114800))
+        );
         assert_eq!(parent_order.order_common.symbol, "AAPL");
         assert_eq!(format!("{:?}", parent_order.order_common.side), "Buy");
         assert_eq!(parent_order.order_common.currency, "USD");
@@ -95,18 +102,18 @@ mod parent_orders_tests {
         let futures = Futures {
             delivery_date: Some(20240101),
             contract_size: Some(100.0),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
         };
 
         let parent_order = ParentOrder::new(
             String::from("order2"),
-            50,
+            Quantity::from(50),
             ProductType::Futures,
             OrderType::Limit,
-            Some(3200.0),
-            1622512800,
+            Some(dec!(3200.0)),
+            Timestamp::from_secs(1622512800),
             None,
             String::from("ES"),
             Side::Sell,
@@ -117,13 +124,13 @@ mod parent_orders_tests {
             None,
             None,
             None,
-            Some(100.0),
+            Some(dec!(100.0)),
             Some(1234567890),
             String::from("strategy2"),
         );
 
         assert_eq!(parent_order.order_common.id, "order2");
-        assert_eq!(parent_order.order_common.quantity, 50);
+        assert_eq!(parent_order.order_common.quantity, Quantity::from(50));
         assert_eq!(
             format!("{:?}", parent_order.order_common.product_type),
             "Futures"
@@ -132,8 +139,11 @@ mod parent_orders_tests {
             format!("{:?}", parent_order.order_common.order_type),
             "Limit"
         );
-        assert_eq!(parent_order.order_common.price, Some(3200.0));
-        assert_eq!(parent_order.order_common.timestamp, 1622512800);
+        assert_eq!(parent_order.order_common.price, Some(dec!(3200.0)));
+        assert_eq!(
+            parent_order.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
         assert_eq!(parent_order.order_common.expiry_date, None);
         assert_eq!(parent_order.order_common.symbol, "ES");
         assert_eq!(format!("{:?}", parent_order.order_common.side), "Sell");
@@ -158,19 +168,19 @@ mod parent_orders_tests {
     #[test]
     fn test_parent_order_with_options() {
         let options = Options {
-            strike_price: 3000.0,
+            strike_price: dec!(3000.0),
             option_type: OptionType::Call,
             expiry_date: 20241231,
         };
 
         let parent_order = ParentOrder::new(
             String::from("order3"),
-            75,
+            Quantity::from(75),
             ProductType::Options,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -186,7 +196,7 @@ mod parent_orders_tests {
         );
 
         assert_eq!(parent_order.order_common.id, "order3");
-        assert_eq!(parent_order.order_common.quantity, 75);
+        assert_eq!(parent_order.order_common.quantity, Quantity::from(75));
         assert_eq!(
             format!("{:?}", parent_order.order_common.product_type),
             "Options"
@@ -195,9 +205,15 @@ mod parent_orders_tests {
             format!("{:?}", parent_order.order_common.order_type),
             "Market"
         );
-        assert_eq!(parent_order.order_common.price, Some(3000.0));
-        assert_eq!(parent_order.order_common.timestamp, 1622512800);
-        assert_eq!(parent_order.order_common.expiry_date, Some(1625114800));
+        assert_eq!(parent_order.order_common.price, Some(dec!(3000.0)));
+        assert_eq!(
+            parent_order.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
+        assert_eq!(
+            parent_order.order_common.expiry_date,
+            Some(Timestamp::from_secs(1625114800))
+        );
         assert_eq!(parent_order.order_common.symbol, "AAPL");
         assert_eq!(format!("{:?}", parent_order.order_common.side), "Buy");
         assert_eq!(parent_order.order_common.currency, "USD");
@@ -221,16 +237,16 @@ mod parent_orders_tests {
         let swap = Swap {
             fixed_rate: 1.5,
             floating_rate_index: String::from("LIBOR"),
-            notional_amount: 1000000.0,
+            notional_amount: dec!(1000000.0),
         };
 
         let parent_order = ParentOrder::new(
             String::from("order4"),
-            25,
+            Quantity::from(25),
             ProductType::Swap,
             OrderType::Market,
             None,
-            1622512800,
+            Timestamp::from_secs(1622512800),
             None,
             String::from("SWAP1"),
             Side::Buy,
@@ -247,7 +263,7 @@ mod parent_orders_tests {
         );
 
         assert_eq!(parent_order.order_common.id, "order4");
-        assert_eq!(parent_order.order_common.quantity, 25);
+        assert_eq!(parent_order.order_common.quantity, Quantity::from(25));
         assert_eq!(
             format!("{:?}", parent_order.order_common.product_type),
             "Swap"
@@ -257,7 +273,10 @@ mod parent_orders_tests {
             "Market"
         );
         assert_eq!(parent_order.order_common.price, None);
-        assert_eq!(parent_order.order_common.timestamp, 1622512800);
+        assert_eq!(
+            parent_order.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
         assert_eq!(parent_order.order_common.expiry_date, None);
         assert_eq!(parent_order.order_common.symbol, "SWAP1");
         assert_eq!(format!("{:?}", parent_order.order_common.side), "Buy");
@@ -281,20 +300,20 @@ mod parent_orders_tests {
     fn test_parent_order_with_cfd() {
         let cfd = CFD {
             leverage: Some(10),
-            margin: Some(0.05),
-            commission: Some(0.02),
-            overnight_fee: Some(0.01),
-            dividend_adjustment: Some(0.005),
+            margin: Some(dec!(0.05)),
+            commission: Some(dec!(0.02)),
+            overnight_fee: Some(dec!(0.01)),
+            dividend_adjustment: Some(dec!(0.005)),
             contract_size: Some(100.0),
         };
 
         let parent_order = ParentOrder::new(
             String::from("order5"),
-            30,
+            Quantity::from(30),
             ProductType::CFD,
             OrderType::Limit,
-            Some(2500.0),
-            1622512800,
+            Some(dec!(2500.0)),
+            Timestamp::from_secs(1622512800),
             None,
             String::from("CFD1"),
             Side::Sell,
@@ -311,7 +330,7 @@ mod parent_orders_tests {
         );
 
         assert_eq!(parent_order.order_common.id, "order5");
-        assert_eq!(parent_order.order_common.quantity, 30);
+        assert_eq!(parent_order.order_common.quantity, Quantity::from(30));
         assert_eq!(
             format!("{:?}", parent_order.order_common.product_type),
             "CFD"
@@ -320,8 +339,11 @@ mod parent_orders_tests {
             format!("{:?}", parent_order.order_common.order_type),
             "Limit"
         );
-        assert_eq!(parent_order.order_common.price, Some(2500.0));
-        assert_eq!(parent_order.order_common.timestamp, 1622512800);
+        assert_eq!(parent_order.order_common.price, Some(dec!(2500.0)));
+        assert_eq!(
+            parent_order.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
         assert_eq!(parent_order.order_common.expiry_date, None);
         assert_eq!(parent_order.order_common.symbol, "CFD1");
         assert_eq!(format!("{:?}", parent_order.order_common.side), "Sell");
@@ -342,15 +364,16 @@ mod parent_orders_tests {
     }
 
     #[test]
+    #[cfg(not(feature = "rfc3339"))]
     fn test_serialize_parent_order() {
         let parent_order = ParentOrder::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -371,8 +394,8 @@ mod parent_orders_tests {
         assert!(serialized.contains("\"product_type\":\"Spot\""));
         assert!(serialized.contains("\"order_type\":\"Market\""));
         assert!(serialized.contains("\"price\":3000.0"));
-        assert!(serialized.contains("\"timestamp\":1622512800"));
-        assert!(serialized.contains("\"expiry_date\":1625114800"));
+        assert!(serialized.contains("\"timestamp\":1622512800000"));
+        assert!(serialized.contains("\"expiry_date\":1625114800000"));
         assert!(serialized.contains("\"symbol\":\"AAPL\""));
         assert!(serialized.contains("\"side\":\"Buy\""));
         assert!(serialized.contains("\"currency\":\"USD\""));
@@ -403,7 +426,7 @@ mod parent_orders_tests {
 
         let deserialized: ParentOrder = serde_json::from_str(data).unwrap();
         assert_eq!(deserialized.order_common.id, "order1");
-        assert_eq!(deserialized.order_common.quantity, 100);
+        assert_eq!(deserialized.order_common.quantity, Quantity::from(100));
         assert_eq!(
             format!("{:?}", deserialized.order_common.product_type),
             "Spot"
@@ -412,9 +435,15 @@ mod parent_orders_tests {
             format!("{:?}", deserialized.order_common.order_type),
             "Market"
         );
-        assert_eq!(deserialized.order_common.price, Some(3000.0));
-        assert_eq!(deserialized.order_common.timestamp, 1622512800);
-        assert_eq!(deserialized.order_common.expiry_date, Some(1625114800));
+        assert_eq!(deserialized.order_common.price, Some(dec!(3000.0)));
+        assert_eq!(
+            deserialized.order_common.timestamp,
+            Timestamp::from_secs(1622512800)
+        );
+        assert_eq!(
+            deserialized.order_common.expiry_date,
+            Some(Timestamp::from_secs(1625114800))
+        );
         assert_eq!(deserialized.order_common.symbol, "AAPL");
         assert_eq!(format!("{:?}", deserialized.order_common.side), "Buy");
         assert_eq!(deserialized.order_common.currency, "USD");
@@ -433,12 +462,12 @@ mod parent_orders_tests {
     fn test_parent_order_validation() {
         let parent_order = ParentOrder::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
-            OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            OrderType::Limit,
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -459,12 +488,12 @@ mod parent_orders_tests {
     fn test_parent_order_validation_failure() {
         let parent_order = ParentOrder::new(
             String::from("order1"),
-            100,
+            Quantity::from(100),
             ProductType::Spot,
             OrderType::Market,
-            Some(3000.0),
-            1622512800,
-            Some(1625114800),
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
             String::from("AAPL"),
             Side::Buy,
             String::from("USD"),
@@ -480,4 +509,55 @@ mod parent_orders_tests {
         );
         assert!(parent_order.validate().is_err());
     }
+
+    fn spot_parent(id: &str, quantity: u32, strategy_id: &str) -> ParentOrder {
+        ParentOrder::new(
+            String::from(id),
+            Quantity::from(quantity),
+            ProductType::Spot,
+            OrderType::Market,
+            Some(dec!(3000.0)),
+            Timestamp::from_secs(1622512800),
+            Some(Timestamp::from_secs(1625114800)),
+            String::from("AAPL"),
+            Side::Buy,
+            String::from("USD"),
+            Some(String::from("NASDAQ")),
+            Some(TimeInForce::GTC),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            String::from(strategy_id),
+        )
+    }
+
+    #[test]
+    fn test_parent_order_validate_empty_strategy_id() {
+        let parent_order = spot_parent("order1", 100, "");
+        assert_eq!(
+            parent_order.validate(),
+            Err(ValidationError::EmptyField("Strategy ID"))
+        );
+    }
+
+    #[test]
+    fn test_parent_order_common_order_validation() {
+        let parent_order = spot_parent("", 100, "strategy1");
+        assert_eq!(
+            parent_order.validate(),
+            Err(ValidationError::EmptyField("ID"))
+        );
+
+        let parent_order = spot_parent("order1", 0, "strategy1");
+        assert_eq!(
+            parent_order.validate(),
+            Err(ValidationError::NonPositive {
+                field: "Quantity",
+                value: Decimal::ZERO
+            })
+        );
+    }
 }
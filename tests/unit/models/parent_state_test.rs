@@ -0,0 +1,288 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod parent_state_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::fills::{Fill, FillError, Liquidity};
+    use strategy_execution_engine::models::orders::{Order, OrderStatus, Side, ValidationError};
+    use strategy_execution_engine::models::parent_state::{ParentOrderState, ParentStateError};
+    use strategy_execution_engine::{ChildOrder, ParentOrder, Quantity, Timestamp};
+
+    fn order(id: &str, quantity: u32) -> Order {
+        Order::builder()
+            .id(id)
+            .quantity(quantity)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .account_id("acct-1")
+            .build()
+            .unwrap()
+    }
+
+    fn parent() -> ParentOrder {
+        order("parent-1", 100).into_parent("twap")
+    }
+
+    fn child(id: &str, quantity: u32) -> ChildOrder {
+        order(id, quantity).into_child("twap", "parent-1", None)
+    }
+
+    fn state() -> ParentOrderState {
+        ParentOrderState::new(
+            parent(),
+            vec![
+                child("child-1", 40),
+                child("child-2", 30),
+                child("child-3", 30),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn fill(order_id: &str, exec_id: &str, quantity: u32) -> Fill {
+        Fill::new(
+            String::from(order_id),
+            String::from(exec_id),
+            dec!(100),
+            Quantity::from(quantity),
+            1622512900000,
+            dec!(0),
+            Liquidity::Taker,
+        )
+    }
+
+    fn outstanding(state: &ParentOrderState) -> Vec<&str> {
+        state
+            .outstanding_children()
+            .map(|child| child.order_common.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_new_state() {
+        let state = state();
+        assert_eq!(state.expected_quantity(), Quantity::from(100));
+        assert_eq!(state.remaining_quantity(), Quantity::from(100));
+        assert_eq!(state.completion_pct(), 0.0);
+        assert!(!state.is_complete());
+        assert_eq!(outstanding(&state), vec!["child-1", "child-2", "child-3"]);
+        assert_eq!(state.child("child-1").unwrap().status, OrderStatus::New);
+    }
+
+    #[test]
+    fn test_partial_completion() {
+        let mut state = state();
+        assert_eq!(
+            state.on_fill(&fill("child-1", "exec-1", 40)),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(
+            state.on_fill(&fill("child-2", "exec-2", 10)),
+            Ok(OrderStatus::PartiallyFilled)
+        );
+
+        assert_eq!(state.filled_quantity(), Quantity::from(50));
+        assert_eq!(state.remaining_quantity(), Quantity::from(50));
+        assert_eq!(state.completion_pct(), 50.0);
+        assert!(!state.is_complete());
+        assert_eq!(outstanding(&state), vec!["child-2", "child-3"]);
+    }
+
+    #[test]
+    fn test_full_completion() {
+        let mut state = state();
+        state.on_fill(&fill("child-1", "exec-1", 40)).unwrap();
+        state.on_fill(&fill("child-2", "exec-2", 30)).unwrap();
+        state.on_fill(&fill("child-3", "exec-3", 10)).unwrap();
+        state.on_fill(&fill("child-3", "exec-4", 20)).unwrap();
+
+        assert_eq!(state.remaining_quantity(), Quantity::ZERO);
+        assert_eq!(state.completion_pct(), 100.0);
+        assert!(state.is_complete());
+        assert!(outstanding(&state).is_empty());
+        assert_eq!(
+            state.on_fill(&fill("child-3", "exec-5", 1)),
+            Err(ParentStateError::ChildClosed {
+                order_id: String::from("child-3"),
+                status: OrderStatus::Filled,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cancelled_child_reduces_expected_quantity() {
+        let mut state = state();
+        state.on_fill(&fill("child-1", "exec-1", 40)).unwrap();
+        state.on_fill(&fill("child-2", "exec-2", 10)).unwrap();
+        state.on_cancel("child-2").unwrap();
+
+        // child-2 only counts for the 10 it filled before the cancel
+        assert_eq!(state.expected_quantity(), Quantity::from(80));
+        assert_eq!(state.remaining_quantity(), Quantity::from(30));
+        assert_eq!(state.completion_pct(), 62.5);
+        assert_eq!(outstanding(&state), vec!["child-3"]);
+
+        state.on_cancel("child-3").unwrap();
+        assert!(state.is_complete());
+        assert_eq!(state.completion_pct(), 100.0);
+        assert_eq!(state.filled_quantity(), Quantity::from(50));
+        assert_eq!(
+            state.on_cancel("child-3"),
+            Err(ParentStateError::ChildClosed {
+                order_id: String::from("child-3"),
+                status: OrderStatus::Cancelled,
+            })
+        );
+    }
+
+    #[test]
+    fn test_over_allocation() {
+        let result =
+            ParentOrderState::new(parent(), vec![child("child-1", 60), child("child-2", 50)]);
+        assert_eq!(
+            result.err(),
+            Some(ParentStateError::OverAllocated {
+                parent_id: String::from("parent-1"),
+                allocated: Quantity::from(110),
+                quantity: Quantity::from(100),
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_duplicate_children() {
+        let foreign = order("child-9", 10).into_child("twap", "parent-2", None);
+        assert!(matches!(
+            ParentOrderState::new(parent(), vec![foreign]).err(),
+            Some(ParentStateError::Invalid(
+                ValidationError::InconsistentFields(_)
+            ))
+        ));
+        assert_eq!(
+            ParentOrderState::new(parent(), vec![child("child-1", 10), child("child-1", 10)]).err(),
+            Some(ParentStateError::DuplicateChild {
+                parent_id: String::from("parent-1"),
+                order_id: String::from("child-1"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_event_errors_leave_state_untouched() {
+        let mut state = state();
+        assert_eq!(
+            state.on_fill(&fill("child-7", "exec-1", 10)),
+            Err(ParentStateError::UnknownChild {
+                parent_id: String::from("parent-1"),
+                order_id: String::from("child-7"),
+            })
+        );
+        assert!(matches!(
+            state.on_fill(&fill("child-2", "exec-1", 31)),
+            Err(ParentStateError::Fill(FillError::Overfill { .. }))
+        ));
+        assert_eq!(state.filled_quantity(), Quantity::ZERO);
+        assert_eq!(state.child("child-2").unwrap().status, OrderStatus::New);
+    }
+
+    #[test]
+    fn test_dispatch_tracking() {
+        let mut state = state();
+        let at = Timestamp::from_millis(1622512801000);
+        state.on_dispatched("child-1", at).unwrap();
+        state.on_cancel("child-3").unwrap();
+
+        assert_eq!(state.child("child-1").unwrap().dispatched_at, Some(at));
+        let undispatched: Vec<&str> = state
+            .undispatched_children()
+            .map(|child| child.order_common.id.as_str())
+            .collect();
+        assert_eq!(undispatched, vec!["child-2"]);
+        assert_eq!(
+            state.on_dispatched("child-3", at),
+            Err(ParentStateError::ChildClosed {
+                order_id: String::from("child-3"),
+                status: OrderStatus::Cancelled,
+            })
+        );
+        // Dispatching does not change what is still expected to fill
+        assert_eq!(state.remaining_quantity(), Quantity::from(70));
+    }
+
+    #[test]
+    fn test_pending_cancel() {
+        let mut state = state();
+        state.on_cancel_requested("child-1").unwrap();
+        state.on_cancel_requested("child-2").unwrap();
+        assert_eq!(
+            state.child("child-1").unwrap().status,
+            OrderStatus::PendingCancel
+        );
+        // Still working until the cancel is confirmed
+        assert_eq!(outstanding(&state), vec!["child-1", "child-2", "child-3"]);
+
+        assert_eq!(
+            state.on_fill(&fill("child-1", "exec-1", 10)),
+            Ok(OrderStatus::PendingCancel)
+        );
+        assert_eq!(
+            state.on_fill(&fill("child-2", "exec-2", 30)),
+            Ok(OrderStatus::Filled)
+        );
+        state.on_cancel("child-1").unwrap();
+        assert_eq!(state.expected_quantity(), Quantity::from(70));
+        assert_eq!(
+            state.on_cancel_requested("child-2"),
+            Err(ParentStateError::ChildClosed {
+                order_id: String::from("child-2"),
+                status: OrderStatus::Filled,
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut state = state();
+        state.on_fill(&fill("child-1", "exec-1", 20)).unwrap();
+        state.on_cancel("child-3").unwrap();
+        state
+            .on_dispatched("child-2", Timestamp::from_millis(1622512801000))
+            .unwrap();
+
+        let json = state.to_string();
+        assert!(json.contains(r#""status":"PartiallyFilled""#));
+        assert!(json.contains(r#""status":"Cancelled""#));
+        let decoded: ParentOrderState = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_string(), json);
+        assert_eq!(decoded.remaining_quantity(), state.remaining_quantity());
+        assert_eq!(decoded.completion_pct(), state.completion_pct());
+    }
+}
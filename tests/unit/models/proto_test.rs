@@ -0,0 +1,237 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(all(test, feature = "proto"))]
+mod proto_tests {
+    use prost::Message;
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{
+        Futures, OffsetType, OptionType, Options, Order, OrderType, ProductType, Side, Swap,
+        TimeInForce, TrailingSpec, CFD,
+    };
+    use strategy_execution_engine::models::proto::{pb, ProtoError};
+    use strategy_execution_engine::{ChildOrder, ParentOrder, Quantity, Timestamp};
+
+    fn order(product_type: ProductType) -> Order {
+        let builder = Order::builder()
+            .id("order1")
+            .quantity(Quantity::new(dec!(12.5)))
+            .order_type(OrderType::Limit)
+            .price(dec!(101.25))
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("ES")
+            .side(Side::Sell)
+            .currency("USD")
+            .exchange("CME")
+            .timeinforce(TimeInForce::GTD)
+            .expiry_date(Timestamp::from_secs(1625114800))
+            .notional(dec!(1265.625))
+            .nonce(42)
+            .account_id("acct-1")
+            .portfolio_id("pf-7");
+        let builder = match product_type {
+            ProductType::Spot => builder,
+            ProductType::Futures => builder.product_type(product_type).futures(Futures {
+                delivery_date: Some(20240101),
+                contract_size: Some(50.0),
+                margin: Some(dec!(0.05)),
+                commission: None,
+                overnight_fee: Some(dec!(0.01)),
+            }),
+            ProductType::Options => builder.product_type(product_type).options(Options {
+                strike_price: dec!(4200.5),
+                option_type: OptionType::Put,
+                expiry_date: 1625114800,
+            }),
+            ProductType::Swap => builder.product_type(product_type).swap(Swap {
+                fixed_rate: 0.025,
+                floating_rate_index: String::from("SOFR"),
+                notional_amount: dec!(1000000),
+            }),
+            ProductType::CFD => builder.product_type(product_type).cfd(CFD {
+                leverage: Some(10),
+                margin: Some(dec!(1000.0)),
+                commission: Some(dec!(0.1)),
+                overnight_fee: None,
+                dividend_adjustment: Some(dec!(0.02)),
+                contract_size: None,
+            }),
+        };
+        builder.build().unwrap()
+    }
+
+    fn round_trip(order: &Order) -> Order {
+        Order::from_proto_bytes(&order.to_proto_bytes()).unwrap()
+    }
+
+    fn json_round_trip(order: &Order) -> Order {
+        serde_json::from_str(&order.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_every_product_type_round_trips() {
+        for product_type in [
+            ProductType::Spot,
+            ProductType::Futures,
+            ProductType::Options,
+            ProductType::Swap,
+            ProductType::CFD,
+        ] {
+            let order = order(product_type);
+            assert_eq!(round_trip(&order).to_string(), order.to_string());
+        }
+    }
+
+    #[test]
+    fn test_every_order_type_round_trips() {
+        for order_type in [
+            OrderType::Market,
+            OrderType::Stop {
+                stop_price: dec!(99.5),
+            },
+            OrderType::StopLimit {
+                stop_price: dec!(99.5),
+                limit_price: dec!(99),
+            },
+            OrderType::TrailingStop(TrailingSpec::new(dec!(1.5), OffsetType::Percent)),
+        ] {
+            let mut order = order(ProductType::Spot);
+            order.order_type = order_type.clone();
+            assert_eq!(round_trip(&order).order_type, order_type);
+        }
+    }
+
+    #[test]
+    fn test_missing_optionals_stay_none() {
+        let mut order = order(ProductType::Spot);
+        order.price = None;
+        order.expiry_date = None;
+        order.exchange = None;
+        order.timeinforce = None;
+        order.notional = None;
+        order.nonce = None;
+
+        let decoded = round_trip(&order);
+        assert_eq!(decoded.price, None);
+        assert_eq!(decoded.expiry_date, None);
+        assert_eq!(decoded.exchange, None);
+        assert_eq!(decoded.timeinforce, None);
+        assert_eq!(decoded.notional, None);
+        assert_eq!(decoded.nonce, None);
+        assert!(decoded.futures_opt.is_none());
+    }
+
+    #[test]
+    fn test_decimals_keep_their_exact_value() {
+        let mut order = order(ProductType::Spot);
+        order.price = Some(dec!(0.1) + dec!(0.2));
+        order.filled_quantity = Quantity::new(dec!(2.5));
+        order.avg_fill_price = Some(dec!(101.125));
+        order.quantity_step = Some(Quantity::new(dec!(0.5)));
+
+        let decoded = round_trip(&order);
+        assert_eq!(decoded.price, Some(dec!(0.3)));
+        assert_eq!(decoded.filled_quantity, Quantity::new(dec!(2.5)));
+        assert_eq!(decoded.avg_fill_price, Some(dec!(101.125)));
+        assert_eq!(decoded.quantity_step, Some(Quantity::new(dec!(0.5))));
+    }
+
+    #[test]
+    fn test_json_and_proto_round_trips_agree() {
+        for product_type in [ProductType::Futures, ProductType::Options, ProductType::CFD] {
+            let order = order(product_type);
+            assert_eq!(
+                json_round_trip(&order).to_string(),
+                round_trip(&order).to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parent_and_child_round_trip() {
+        let parent_order = order(ProductType::Swap).into_parent("strategy1");
+        let decoded = ParentOrder::from_proto_bytes(&parent_order.to_proto_bytes()).unwrap();
+        assert_eq!(decoded.to_string(), parent_order.to_string());
+
+        let child_order = order(ProductType::Options).into_child(
+            "strategy1",
+            "parent1",
+            Some(Timestamp::from_secs(7)),
+        );
+        let decoded = ChildOrder::from_proto_bytes(&child_order.to_proto_bytes()).unwrap();
+        assert_eq!(decoded.to_string(), child_order.to_string());
+    }
+
+    #[test]
+    fn test_enum_numbering_is_stable() {
+        assert_eq!(pb::ProductType::Spot as i32, 1);
+        assert_eq!(pb::ProductType::Cfd as i32, 5);
+        assert_eq!(pb::Side::Buy as i32, 1);
+        assert_eq!(pb::Side::Sell as i32, 2);
+        assert_eq!(pb::TimeInForce::Fok as i32, 4);
+        assert_eq!(pb::OptionType::Put as i32, 2);
+        assert_eq!(pb::OffsetType::Percent as i32, 2);
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_unspecified_enums() {
+        let mut message = pb::Order::from(&order(ProductType::Spot));
+        message.side = 9;
+        assert!(matches!(
+            Order::from_proto_bytes(&message.encode_to_vec()),
+            Err(ProtoError::UnknownEnum {
+                field: "side",
+                value: 9
+            })
+        ));
+
+        message.side = pb::Side::Unspecified as i32;
+        assert!(matches!(
+            Order::from_proto_bytes(&message.encode_to_vec()),
+            Err(ProtoError::MissingField("side"))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_missing_order_type_and_garbage() {
+        let mut message = pb::Order::from(&order(ProductType::Spot));
+        message.order_type = None;
+        assert!(matches!(
+            Order::from_proto_bytes(&message.encode_to_vec()),
+            Err(ProtoError::MissingField("order_type"))
+        ));
+        assert!(matches!(
+            ParentOrder::from_proto_bytes(&[]),
+            Err(ProtoError::MissingField("order"))
+        ));
+        assert!(matches!(
+            Order::from_proto_bytes(&[0xff, 0xff]),
+            Err(ProtoError::Decode(_))
+        ));
+    }
+}
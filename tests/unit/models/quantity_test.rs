@@ -0,0 +1,146 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod quantity_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::builder::OrderBuilder;
+    use strategy_execution_engine::models::orders::{Order, OrderType, Side};
+    use strategy_execution_engine::{Quantity, Timestamp, Validate, ValidationError};
+
+    fn order(quantity: Quantity) -> Order {
+        OrderBuilder::new()
+            .id("order1")
+            .quantity(quantity)
+            .order_type(OrderType::Market)
+            .timestamp(Timestamp::from_secs(1622512800))
+            .symbol("BTC/USD")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_integer_json_round_trip() {
+        let json = serde_json::to_string(&order(Quantity::from(100))).unwrap();
+        assert!(json.contains("\"quantity\":100,"));
+        let decoded: Order = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.quantity, Quantity::from(100));
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_decimal_json_round_trip() {
+        let json = serde_json::to_string(&order(Quantity::new(dec!(0.05)))).unwrap();
+        assert!(json.contains("\"quantity\":0.05,"));
+        let decoded: Order = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.quantity, Quantity::new(dec!(0.05)));
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_deserialize_accepts_integer_decimal_and_string() {
+        let parse = |raw: &str| serde_json::from_str::<Quantity>(raw).unwrap();
+        assert_eq!(parse("100"), Quantity::from(100));
+        assert_eq!(parse("100.0"), Quantity::from(100));
+        assert_eq!(parse("1500.5"), Quantity::new(dec!(1500.5)));
+        assert_eq!(parse("\"0.05\""), Quantity::new(dec!(0.05)));
+        assert!(serde_json::from_str::<Quantity>("\"lots\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_quantity() {
+        let mut order = order(Quantity::from(1));
+        order.quantity = Quantity::ZERO;
+        assert_eq!(
+            order.validate(),
+            Err(ValidationError::NonPositive {
+                field: "Quantity",
+                value: dec!(0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_respects_quantity_step() {
+        let mut order = order(Quantity::new(dec!(0.05)));
+        order.quantity_step = Some(Quantity::new(dec!(0.01)));
+        assert!(order.validate().is_ok());
+
+        order.quantity = Quantity::new(dec!(0.055));
+        assert!(matches!(
+            order.validate(),
+            Err(ValidationError::InconsistentFields(_))
+        ));
+
+        order.quantity_step = Some(Quantity::ZERO);
+        assert!(order.validate().is_err());
+    }
+
+    #[test]
+    fn test_lot_step_defaults_to_quantity_precision() {
+        assert_eq!(order(Quantity::from(100)).lot_step(), Quantity::from(1));
+        assert_eq!(
+            order(Quantity::new(dec!(0.05))).lot_step(),
+            Quantity::new(dec!(0.01))
+        );
+    }
+
+    #[test]
+    fn test_split_keeps_total_and_step() {
+        let parts = Quantity::new(dec!(1500.5)).split(4, Quantity::new(dec!(0.1)));
+        assert_eq!(
+            parts,
+            vec![
+                Quantity::new(dec!(375.1)),
+                Quantity::new(dec!(375.1)),
+                Quantity::new(dec!(375.1)),
+                Quantity::new(dec!(375.2)),
+            ]
+        );
+        assert_eq!(parts.iter().sum::<Quantity>(), Quantity::new(dec!(1500.5)));
+    }
+
+    #[test]
+    fn test_rounding_to_step() {
+        let step = Quantity::new(dec!(0.25));
+        assert_eq!(
+            Quantity::new(dec!(1.3)).round_down_to_step(step),
+            Quantity::new(dec!(1.25))
+        );
+        assert_eq!(
+            Quantity::new(dec!(1.375)).round_to_step(step),
+            Quantity::new(dec!(1.5))
+        );
+        assert_eq!(
+            Quantity::from(5).saturating_sub(Quantity::from(7)),
+            Quantity::ZERO
+        );
+    }
+}
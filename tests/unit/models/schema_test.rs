@@ -0,0 +1,111 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod schema_tests {
+    use rust_decimal_macros::dec;
+    use serde_json::Value;
+    use strategy_execution_engine::models::schema::{from_json, migrate, migrate_v1_to_v2};
+    use strategy_execution_engine::{ChildOrder, Order, ParentOrder, Quantity, SCHEMA_VERSION};
+
+    // Written before versioning, as asserted by the original format tests
+    const V1_ORDER: &str = r#"{"id":"order1","quantity":100,"product_type":"Spot","order_type":"Market","price":3000.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"AAPL","side":"Buy","currency":"USD","exchange":"NASDAQ","timeinforce":"GTC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":300000.0,"nonce":123456}"#;
+    const V1_PARENT_ORDER: &str = r#"{"id":"parent_order1","quantity":200,"product_type":"Futures","order_type":"Limit","price":2500.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"ES","side":"Sell","currency":"USD","exchange":"CME","timeinforce":"FOK","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":500000.0,"nonce":654321,"strategy_id":"strategy1"}"#;
+    const V1_CHILD_ORDER: &str = r#"{"id":"child_order1","quantity":50,"product_type":"Options","order_type":"Market","price":1500.0,"timestamp":1622512800,"expiry_date":1625114800,"symbol":"GOOGL","side":"Buy","currency":"USD","exchange":"NYSE","timeinforce":"IOC","futures_opt":null,"options_opt":null,"swap_opt":null,"cfd_opt":null,"notional":75000.0,"nonce":789012,"strategy_id":"parent_order2","parent_id":"parent_order2","insert_at":null}"#;
+
+    #[test]
+    fn test_v1_order_reads_as_version_1() {
+        let order: Order = serde_json::from_str(V1_ORDER).unwrap();
+        assert_eq!(order.schema_version, 1);
+        assert_eq!(order.quantity, Quantity::from(100));
+        assert_eq!(order.price, Some(dec!(3000)));
+        assert_eq!(order.filled_quantity, Quantity::ZERO);
+    }
+
+    #[test]
+    fn test_v1_order_migrates_to_current_version() {
+        let order: Order = from_json(V1_ORDER).unwrap();
+        assert_eq!(order.schema_version, SCHEMA_VERSION);
+        assert_eq!(order.id, "order1");
+        assert_eq!(order.nonce, Some(123456));
+        assert_eq!(order.version, 0);
+        assert!(order
+            .to_string()
+            .starts_with(r#"{"schema_version":2,"id":"order1","#));
+    }
+
+    #[test]
+    fn test_v1_parent_order_round_trip() {
+        let parent_order: ParentOrder = from_json(V1_PARENT_ORDER).unwrap();
+        assert_eq!(parent_order.strategy_id, "strategy1");
+        assert_eq!(parent_order.order_common.schema_version, SCHEMA_VERSION);
+        let json = parent_order.to_string();
+        let decoded: ParentOrder = from_json(&json).unwrap();
+        assert_eq!(decoded.to_string(), json);
+    }
+
+    #[test]
+    fn test_v1_child_order_round_trip() {
+        let child_order: ChildOrder = from_json(V1_CHILD_ORDER).unwrap();
+        assert_eq!(child_order.parent_id, "parent_order2");
+        assert_eq!(child_order.order_common.quantity, Quantity::from(50));
+        let json = child_order.to_string();
+        let decoded: ChildOrder = from_json(&json).unwrap();
+        assert_eq!(decoded.to_string(), json);
+    }
+
+    #[test]
+    fn test_migrate_v1_to_v2_fills_new_fields() {
+        let value: Value = serde_json::from_str(V1_ORDER).unwrap();
+        let migrated = migrate_v1_to_v2(value);
+        assert_eq!(migrated["schema_version"], 2);
+        assert_eq!(migrated["filled_quantity"], 0);
+        assert_eq!(migrated["avg_fill_price"], Value::Null);
+        assert_eq!(migrated["version"], 0);
+        assert_eq!(migrated["nonce"], 123456);
+    }
+
+    #[test]
+    fn test_current_version_is_not_migrated() {
+        let value: Value = serde_json::from_str(r#"{"schema_version":2,"version":3}"#).unwrap();
+        assert_eq!(migrate(value.clone()), value);
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        let json = V1_CHILD_ORDER.replacen(
+            r#"{"id""#,
+            r#"{"schema_version":7,"status":"New","routing":{"venue":"X"},"id""#,
+            1,
+        );
+        let child_order: ChildOrder = from_json(&json).unwrap();
+        assert_eq!(child_order.order_common.schema_version, 7);
+        assert_eq!(child_order.order_common.id, "child_order1");
+        assert!(!child_order.to_string().contains("routing"));
+    }
+}
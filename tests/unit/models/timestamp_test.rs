@@ -0,0 +1,225 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 17/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod timestamp_tests {
+    use serde_json::{json, Value};
+    use strategy_execution_engine::models::orders::{Order, OrderType, Side, TimeInForce};
+    use strategy_execution_engine::models::timestamp::TimestampError;
+    use strategy_execution_engine::{ChildOrder, Timestamp};
+
+    // 2021-06-01T02:00:00Z
+    const SECS: u64 = 1622512800;
+    const MILLIS: u64 = 1622512800000;
+
+    fn order() -> Order {
+        Order::builder()
+            .id("order1")
+            .quantity(100)
+            .order_type(OrderType::Limit)
+            .price(rust_decimal_macros::dec!(101.5))
+            .timestamp(Timestamp::from_millis(MILLIS))
+            .expiry_date(Timestamp::from_millis(MILLIS + 3_600_000))
+            .timeinforce(TimeInForce::GTD)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    fn with_timestamps(timestamp: Value, expiry_date: Value) -> Order {
+        let mut value = serde_json::to_value(order()).unwrap();
+        value["timestamp"] = timestamp;
+        value["expiry_date"] = expiry_date;
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_constructors() {
+        assert_eq!(Timestamp::from_secs(SECS), Timestamp::from_millis(MILLIS));
+        assert_eq!(Timestamp::from_millis(MILLIS + 999).as_secs(), SECS);
+        assert_eq!(Timestamp::from(MILLIS).as_millis(), MILLIS);
+        assert_eq!(u64::from(Timestamp::from_secs(SECS)), MILLIS);
+        assert!(Timestamp::now() > Timestamp::from_millis(MILLIS));
+    }
+
+    #[test]
+    fn test_from_epoch_normalizes_seconds() {
+        assert_eq!(Timestamp::from_epoch(SECS).as_millis(), MILLIS);
+        assert_eq!(Timestamp::from_epoch(MILLIS).as_millis(), MILLIS);
+        assert_eq!(Timestamp::from_epoch(0), Timestamp::EPOCH);
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let timestamp = Timestamp::from_millis(MILLIS + 250);
+        assert_eq!(timestamp.to_rfc3339(), "2021-06-01T02:00:00.250Z");
+        assert_eq!(
+            Timestamp::parse_rfc3339("2021-06-01T02:00:00.250Z"),
+            Ok(timestamp)
+        );
+        assert_eq!(
+            Timestamp::parse_rfc3339("2021-06-01T04:00:00.250+02:00"),
+            Ok(timestamp)
+        );
+        assert_eq!(
+            Timestamp::parse_rfc3339("yesterday"),
+            Err(TimestampError::Invalid("yesterday".to_string()))
+        );
+        assert_eq!(
+            Timestamp::parse_rfc3339("1969-12-31T23:59:59Z"),
+            Err(TimestampError::BeforeEpoch(
+                "1969-12-31T23:59:59Z".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "1622512800".parse::<Timestamp>(),
+            Ok(Timestamp::from_millis(MILLIS))
+        );
+        assert_eq!(
+            "1622512800000".parse::<Timestamp>(),
+            Ok(Timestamp::from_millis(MILLIS))
+        );
+        assert_eq!(
+            "2021-06-01T02:00:00Z".parse::<Timestamp>(),
+            Ok(Timestamp::from_millis(MILLIS))
+        );
+        assert_eq!(Timestamp::from_millis(MILLIS).to_string(), "1622512800000");
+    }
+
+    #[test]
+    fn test_deserialize_millis() {
+        let order = with_timestamps(json!(MILLIS), json!(MILLIS + 3_600_000));
+        assert_eq!(order.timestamp.as_millis(), MILLIS);
+        assert_eq!(order.expiry_date.unwrap().as_millis(), MILLIS + 3_600_000);
+    }
+
+    #[test]
+    fn test_deserialize_seconds_as_millis() {
+        let order = with_timestamps(json!(SECS), json!(SECS + 3_600));
+        assert_eq!(order.timestamp.as_millis(), MILLIS);
+        assert_eq!(order.expiry_date.unwrap().as_millis(), MILLIS + 3_600_000);
+        assert!(!order.is_expired(MILLIS + 1_000));
+        assert!(order.is_expired(MILLIS + 3_600_000));
+    }
+
+    #[test]
+    fn test_deserialize_rfc3339() {
+        let order = with_timestamps(
+            json!("2021-06-01T02:00:00Z"),
+            json!("2021-06-01T05:00:00+02:00"),
+        );
+        assert_eq!(order.timestamp.as_millis(), MILLIS);
+        assert_eq!(order.expiry_date.unwrap().as_millis(), MILLIS + 3_600_000);
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid() {
+        let mut value = serde_json::to_value(order()).unwrap();
+        value["timestamp"] = json!("not a time");
+        assert!(serde_json::from_value::<Order>(value.clone()).is_err());
+        value["timestamp"] = json!(-1);
+        assert!(serde_json::from_value::<Order>(value).is_err());
+    }
+
+    #[test]
+    fn test_child_insert_at_round_trip() {
+        let child = Order::builder()
+            .quantity(100)
+            .symbol("AAPL")
+            .side(Side::Buy)
+            .currency("USD")
+            .build_child("twap", "parent-1", Some(Timestamp::from_secs(SECS)))
+            .unwrap();
+        let json = serde_json::to_string(&child).unwrap();
+        let decoded: ChildOrder = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.insert_at, Some(Timestamp::from_millis(MILLIS)));
+
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        value["insert_at"] = json!(SECS);
+        let decoded: ChildOrder = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.insert_at, Some(Timestamp::from_millis(MILLIS)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "rfc3339"))]
+    fn test_serializes_as_millis() {
+        let value = serde_json::to_value(order()).unwrap();
+        assert_eq!(value["timestamp"], json!(MILLIS));
+        assert_eq!(value["expiry_date"], json!(MILLIS + 3_600_000));
+    }
+}
+
+#[cfg(all(test, feature = "rfc3339"))]
+mod timestamp_rfc3339_tests {
+    use serde_json::{json, Value};
+    use strategy_execution_engine::{ChildOrder, Order, Side, Timestamp};
+
+    const MILLIS: u64 = 1622512800000;
+
+    fn child() -> ChildOrder {
+        Order::builder()
+            .id("order1")
+            .quantity(100)
+            .timestamp(Timestamp::from_millis(MILLIS))
+            .symbol("AAPL")
+            .side(Side::Sell)
+            .currency("USD")
+            .build_child(
+                "twap",
+                "parent-1",
+                Some(Timestamp::from_millis(MILLIS + 60_000)),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_uses_rfc3339() {
+        let value: Value = serde_json::from_str(&child().to_string()).unwrap();
+        assert_eq!(value["timestamp"], json!("2021-06-01T02:00:00.000Z"));
+        assert_eq!(value["insert_at"], json!("2021-06-01T02:01:00.000Z"));
+        assert_eq!(value["expiry_date"], Value::Null);
+    }
+
+    #[test]
+    fn test_rfc3339_round_trip() {
+        let json = child().to_string();
+        let decoded: ChildOrder = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.order_common.timestamp.as_millis(), MILLIS);
+        assert_eq!(
+            decoded.insert_at,
+            Some(Timestamp::from_millis(MILLIS + 60_000))
+        );
+        assert_eq!(decoded.to_string(), json);
+    }
+}
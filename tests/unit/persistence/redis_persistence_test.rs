@@ -0,0 +1,107 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(test)]
+mod redis_persistence_tests {
+    use crate::support::{child, order};
+    use strategy_execution_engine::{
+        ParentOrderState, Persistence, PersistenceError, RedisConfig, RedisPersistence,
+    };
+
+    fn state(parent_id: &str) -> ParentOrderState {
+        let children = (1..=2)
+            .map(|n| child("fixed", parent_id, n, 50, None))
+            .collect();
+        ParentOrderState::new(order(parent_id, 100).into_parent("fixed"), children).unwrap()
+    }
+
+    #[test]
+    fn test_unreachable_server_is_a_backend_error() {
+        let invalid = RedisPersistence::new(&RedisConfig {
+            redis_url: "http://localhost".into(),
+            ..RedisConfig::default()
+        });
+        assert!(matches!(invalid, Err(PersistenceError::Backend(_))));
+
+        // Nothing listens on port 1
+        let store = RedisPersistence::new(&RedisConfig {
+            redis_url: "redis://127.0.0.1:1".into(),
+            ..RedisConfig::default()
+        })
+        .unwrap()
+        .with_prefix("engine:test:");
+        assert_eq!(store.prefix(), "engine:test:");
+        let state = state("parent-1");
+        assert!(matches!(
+            store.save_parent(&state.parent),
+            Err(PersistenceError::Backend(_))
+        ));
+        assert!(matches!(
+            store.load_open_orders(),
+            Err(PersistenceError::Backend(_))
+        ));
+    }
+
+    /// Runs against the server in `REDIS_URL`, e.g. `docker run -p 6379:6379 redis`.
+    #[cfg(feature = "integration-tests")]
+    #[test]
+    fn test_round_trip() {
+        use strategy_execution_engine::models::orders::OrderStatus;
+
+        let Ok(redis_url) = std::env::var("REDIS_URL") else {
+            eprintln!("REDIS_URL not set, skipping");
+            return;
+        };
+        // A fresh prefix per run, so orders left by earlier runs are not read back
+        let store = RedisPersistence::new(&RedisConfig {
+            redis_url: redis_url.into(),
+            ..RedisConfig::default()
+        })
+        .unwrap()
+        .with_prefix(format!("engine:test:{}:", uuid::Uuid::now_v7()));
+        let unknown = state("parent-2");
+        let mut state = state("parent-1");
+        store.save_parent(&state.parent).unwrap();
+        store.save_children("parent-1", &state.children).unwrap();
+
+        state.on_cancel("parent-1-1").unwrap();
+        store.update_status(&state.children[0]).unwrap();
+        let open = store.load_open_orders().unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].children[0].status, OrderStatus::Cancelled);
+        assert_eq!(open[0].children[1].child.order_common.id, "parent-1-2");
+
+        state.on_cancel("parent-1-2").unwrap();
+        store.update_status(&state.children[1]).unwrap();
+        assert!(store.load_open_orders().unwrap().is_empty());
+        assert_eq!(
+            store.update_status(&unknown.children[0]),
+            Err(PersistenceError::UnknownOrder("parent-2-1".to_string()))
+        );
+    }
+}
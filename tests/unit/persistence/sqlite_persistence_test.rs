@@ -0,0 +1,230 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+#[cfg(all(test, feature = "sqlite"))]
+mod sqlite_persistence_tests {
+    use crate::support::{child, fill, order, START};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use strategy_execution_engine::models::orders::OrderStatus;
+    use strategy_execution_engine::{
+        ChildOrder, Config, ExecutionEngine, MessagingService, MockClient, OrderSplitStrategy,
+        ParentOrder, ParentOrderState, Persistence, PersistenceError, Quantity, RecoveryReport,
+        SimulatedClock, SqlitePersistence, StrategyRegistry, Timestamp,
+    };
+
+    /// Splits a parent into fixed slices, each sent after its offset from the parent time;
+    /// `None` sends the slice at once.
+    struct FixedSchedule(Vec<(u32, Option<u64>)>);
+
+    impl OrderSplitStrategy for FixedSchedule {
+        fn split(&self, parent: &ParentOrder) -> Vec<ChildOrder> {
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, (quantity, offset_ms))| {
+                    child(
+                        "fixed",
+                        &parent.order_common.id,
+                        i + 1,
+                        *quantity,
+                        *offset_ms,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    fn engine(
+        persistence: &Arc<SqlitePersistence>,
+        clock: &SimulatedClock,
+        client: &MockClient,
+    ) -> ExecutionEngine {
+        let mut strategies = StrategyRegistry::new();
+        strategies.register("fixed", |_| {
+            Ok(Box::new(FixedSchedule(vec![
+                (40, None),
+                (20, Some(1_000)),
+                (20, Some(2_000)),
+                (20, Some(60_000)),
+            ])))
+        });
+        ExecutionEngine::new(
+            Config::default(),
+            strategies,
+            MessagingService::with_client(Box::new(client.clone())),
+        )
+        .with_clock(clock.clone())
+        .with_persistence(persistence.clone())
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    fn statuses(state: &ParentOrderState) -> Vec<OrderStatus> {
+        state.children.iter().map(|child| child.status).collect()
+    }
+
+    #[test]
+    fn test_saved_orders_load_until_complete() {
+        let store = SqlitePersistence::in_memory().unwrap();
+        let mut state = ParentOrderState::new(
+            order("parent-1", 100).into_parent("fixed"),
+            vec![
+                child("fixed", "parent-1", 1, 60, None),
+                child("fixed", "parent-1", 2, 40, None),
+            ],
+        )
+        .unwrap();
+        store.save_parent(&state.parent).unwrap();
+        store.save_children("parent-1", &state.children).unwrap();
+
+        state
+            .on_dispatched("parent-1-1", Timestamp::from_millis(START))
+            .unwrap();
+        state.on_fill(&fill("parent-1-1", "exec-1", 25)).unwrap();
+        store.update_status(&state.children[0]).unwrap();
+
+        let open = store.load_open_orders().unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].parent.order_common.id, "parent-1");
+        assert_eq!(
+            statuses(&open[0]),
+            vec![OrderStatus::PartiallyFilled, OrderStatus::New]
+        );
+        assert_eq!(open[0].filled_quantity(), Quantity::from(25));
+        assert_eq!(
+            open[0].children[0].dispatched_at,
+            Some(Timestamp::from_millis(START))
+        );
+
+        // Once every child is closed the parent is no longer loaded
+        state.on_cancel("parent-1-1").unwrap();
+        state.on_cancel("parent-1-2").unwrap();
+        for child in &state.children {
+            store.update_status(child).unwrap();
+        }
+        assert!(store.load_open_orders().unwrap().is_empty());
+        assert_eq!(
+            store.child("parent-1-2").unwrap().map(|child| child.status),
+            Some(OrderStatus::Cancelled)
+        );
+
+        let unknown = child("fixed", "parent-2", 1, 10, None);
+        let unknown =
+            ParentOrderState::new(order("parent-2", 10).into_parent("fixed"), vec![unknown])
+                .unwrap();
+        assert_eq!(
+            store.update_status(&unknown.children[0]),
+            Err(PersistenceError::UnknownOrder("parent-2-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_engine_recovers_orders_after_a_crash() {
+        let store = Arc::new(SqlitePersistence::in_memory().unwrap());
+        let clock = SimulatedClock::new(Timestamp::from_millis(START));
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+
+        // The first engine fills its first child and sends the second, then its runtime dies
+        // with the dispatch tasks on it
+        let crashed = tokio::runtime::Runtime::new().unwrap();
+        crashed.block_on(async {
+            let engine = engine(&store, &clock, &client);
+            let handle = engine
+                .submit(order("parent-1", 100).into_parent("fixed"))
+                .unwrap();
+            eventually(|| handle.dispatches().len() == 1).await;
+            handle.on_fill(&fill("parent-1-1", "exec-1", 40)).unwrap();
+            clock.advance(Duration::from_millis(1_000));
+            eventually(|| handle.dispatches().len() == 2).await;
+        });
+        drop(crashed);
+        assert_eq!(client.pending("child_orders"), 2);
+
+        // The third child was due while the engine was down
+        clock.advance(Duration::from_millis(4_000));
+        // The restarted engine comes with its own connection
+        let client = MockClient::new().with_timeout(Duration::ZERO);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let engine = engine(&store, &clock, &client);
+            let report = engine.recover().unwrap();
+            assert_eq!(
+                report,
+                RecoveryReport {
+                    parents: vec!["parent-1".to_string()],
+                    rescheduled: vec!["parent-1-4".to_string()],
+                    working: vec!["parent-1-2".to_string()],
+                    review: vec!["parent-1-3".to_string()],
+                }
+            );
+            // Recovering twice leaves the parent as it is
+            assert_eq!(engine.recover().unwrap(), RecoveryReport::default());
+
+            let handle = engine.parent("parent-1").unwrap();
+            assert_eq!(
+                statuses(&handle.state()),
+                vec![
+                    OrderStatus::Filled,
+                    OrderStatus::New,
+                    OrderStatus::New,
+                    OrderStatus::New
+                ]
+            );
+            // The held child is not sent, the one still ahead is at its insert_at
+            clock.set(Timestamp::from_millis(START + 60_000));
+            eventually(|| handle.dispatches().len() == 1).await;
+            assert_eq!(handle.dispatches()[0].child_id, "parent-1-4");
+            assert_eq!(client.pending("child_orders"), 1);
+
+            // Fills of a child sent before the crash still apply, and are saved
+            handle.on_fill(&fill("parent-1-2", "exec-2", 20)).unwrap();
+            assert_eq!(
+                store.child("parent-1-2").unwrap().map(|child| child.status),
+                Some(OrderStatus::Filled)
+            );
+            assert!(store
+                .child("parent-1-4")
+                .unwrap()
+                .is_some_and(|child| child.dispatched_at.is_some()));
+
+            // Cancelling the parent drops the held child, which closes the parent
+            engine.cancel_parent("parent-1").unwrap();
+            handle.on_fill(&fill("parent-1-4", "exec-4", 20)).unwrap();
+            assert!(store.load_open_orders().unwrap().is_empty());
+        });
+    }
+}
@@ -0,0 +1,283 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 26/5/24
+******************************************************************************/
+
+#[cfg(test)]
+mod position_tracker_tests {
+    use rust_decimal_macros::dec;
+    use strategy_execution_engine::models::orders::{Order, Side};
+    use strategy_execution_engine::{
+        Decimal, Fill, Liquidity, PositionError, PositionTracker, Quantity, Timestamp,
+    };
+
+    fn order(id: &str, symbol: &str, side: Side) -> Order {
+        Order::builder()
+            .id(id)
+            .quantity(1_000)
+            .timestamp(Timestamp::from_millis(1_622_512_800_000))
+            .symbol(symbol)
+            .side(side)
+            .currency("USD")
+            .build()
+            .unwrap()
+    }
+
+    fn fill(order_id: &str, exec_id: &str, price: Decimal, quantity: u32, fee: Decimal) -> Fill {
+        Fill::new(
+            order_id.to_string(),
+            exec_id.to_string(),
+            price,
+            Quantity::from(quantity),
+            1_622_512_800_000,
+            fee,
+            Liquidity::Taker,
+        )
+    }
+
+    /// A tracker following a buy and a sell order in AAPL.
+    fn tracker() -> PositionTracker {
+        let tracker = PositionTracker::new();
+        tracker.track_order(&order("buy-1", "AAPL", Side::Buy));
+        tracker.track_order(&order("sell-1", "AAPL", Side::Sell));
+        tracker
+    }
+
+    #[test]
+    fn test_scale_in_averages_entry_price() {
+        let tracker = tracker();
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(10), 100, dec!(1)))
+            .unwrap();
+        let position = tracker
+            .apply_fill(&fill("buy-1", "e2", dec!(13), 50, dec!(0.5)))
+            .unwrap();
+
+        assert_eq!(position.quantity, dec!(150));
+        assert_eq!(position.avg_entry_price, dec!(11));
+        assert_eq!(position.realized_pnl, dec!(0));
+        assert_eq!(position.fees, dec!(1.5));
+        assert_eq!(position.side(), Some(Side::Buy));
+    }
+
+    #[test]
+    fn test_partial_close_realizes_pnl() {
+        let tracker = tracker();
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(10), 100, dec!(1)))
+            .unwrap();
+        tracker
+            .apply_fill(&fill("buy-1", "e2", dec!(13), 50, dec!(0.5)))
+            .unwrap();
+        let position = tracker
+            .apply_fill(&fill("sell-1", "e3", dec!(12), 60, dec!(0.6)))
+            .unwrap();
+
+        assert_eq!(position.quantity, dec!(90));
+        // Closing leaves the entry price of what is still open
+        assert_eq!(position.avg_entry_price, dec!(11));
+        assert_eq!(position.realized_pnl, dec!(60));
+        assert_eq!(position.fees, dec!(2.1));
+        assert_eq!(position.net_pnl(), dec!(57.9));
+    }
+
+    #[test]
+    fn test_flip_opens_the_rest_at_the_fill_price() {
+        let tracker = tracker();
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(11), 90, dec!(0)))
+            .unwrap();
+        let short = tracker
+            .apply_fill(&fill("sell-1", "e2", dec!(12.5), 140, dec!(0)))
+            .unwrap();
+        assert_eq!(short.quantity, dec!(-50));
+        assert_eq!(short.avg_entry_price, dec!(12.5));
+        assert_eq!(short.realized_pnl, dec!(135));
+        assert_eq!(short.side(), Some(Side::Sell));
+
+        let flat = tracker
+            .apply_fill(&fill("buy-1", "e3", dec!(12), 50, dec!(0)))
+            .unwrap();
+        assert!(flat.is_flat());
+        assert_eq!(flat.side(), None);
+        assert_eq!(flat.avg_entry_price, dec!(0));
+        assert_eq!(flat.realized_pnl, dec!(160));
+
+        let long = tracker
+            .apply_fill(&fill("buy-1", "e4", dec!(12.2), 10, dec!(0)))
+            .unwrap();
+        assert_eq!(long.quantity, dec!(10));
+        assert_eq!(long.avg_entry_price, dec!(12.2));
+    }
+
+    #[test]
+    fn test_pnl_follows_the_mark_price() {
+        let tracker = tracker();
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(11), 90, dec!(1)))
+            .unwrap();
+
+        assert_eq!(tracker.mark("AAPL", dec!(10.5)).unrealized_pnl, dec!(-45));
+        assert_eq!(tracker.mark("AAPL", dec!(12)).unrealized_pnl, dec!(90));
+
+        // A fill revalues at the last mark
+        let position = tracker
+            .apply_fill(&fill("sell-1", "e2", dec!(12.5), 140, dec!(1)))
+            .unwrap();
+        assert_eq!(position.realized_pnl, dec!(135));
+        assert_eq!(position.unrealized_pnl, dec!(25));
+        assert_eq!(tracker.mark("AAPL", dec!(13)).unrealized_pnl, dec!(-25));
+        assert_eq!(tracker.position("AAPL").unwrap().net_pnl(), dec!(108));
+
+        let flat = tracker
+            .apply_fill(&fill("buy-1", "e3", dec!(13), 50, dec!(0)))
+            .unwrap();
+        assert_eq!(flat.unrealized_pnl, dec!(0));
+        assert_eq!(flat.realized_pnl, dec!(110));
+    }
+
+    #[test]
+    fn test_rejected_fills_leave_positions_untouched() {
+        let tracker = tracker();
+        assert_eq!(
+            tracker.apply_fill(&fill("other", "e1", dec!(10), 10, dec!(0))),
+            Err(PositionError::UnknownOrder {
+                order_id: "other".to_string(),
+                exec_id: "e1".to_string(),
+            })
+        );
+        assert_eq!(
+            tracker.apply_fill(&fill("buy-1", "e2", dec!(10), 0, dec!(0))),
+            Err(PositionError::ZeroQuantity {
+                exec_id: "e2".to_string()
+            })
+        );
+        assert!(tracker.position("AAPL").is_none());
+
+        // Fills of untracked orders name their instrument
+        let position = tracker
+            .apply_execution(
+                "MSFT",
+                &Side::Sell,
+                &fill("other", "e3", dec!(400), 5, dec!(0)),
+            )
+            .unwrap();
+        assert_eq!(position.quantity, dec!(-5));
+    }
+
+    #[test]
+    fn test_filled_orders_stop_being_tracked() {
+        let tracker = tracker();
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(10), 600, dec!(0)))
+            .unwrap();
+        tracker
+            .apply_fill(&fill("buy-1", "e2", dec!(10), 400, dec!(0)))
+            .unwrap();
+
+        assert_eq!(
+            tracker.apply_fill(&fill("buy-1", "e3", dec!(10), 1, dec!(0))),
+            Err(PositionError::UnknownOrder {
+                order_id: "buy-1".to_string(),
+                exec_id: "e3".to_string(),
+            })
+        );
+        assert_eq!(tracker.quantity("AAPL"), dec!(1000));
+    }
+
+    #[test]
+    fn test_untracked_orders_take_no_more_fills() {
+        let tracker = tracker();
+        assert!(tracker.untrack_order("sell-1"));
+        assert!(!tracker.untrack_order("sell-1"));
+
+        assert!(matches!(
+            tracker.apply_fill(&fill("sell-1", "e1", dec!(10), 10, dec!(0))),
+            Err(PositionError::UnknownOrder { .. })
+        ));
+        assert!(tracker.position("AAPL").is_none());
+    }
+
+    #[test]
+    fn test_snapshot_totals_every_symbol() {
+        let tracker = tracker();
+        tracker.track_order(&order("buy-2", "MSFT", Side::Buy));
+        tracker
+            .apply_fill(&fill("buy-1", "e1", dec!(10), 100, dec!(1)))
+            .unwrap();
+        tracker
+            .apply_fill(&fill("sell-1", "e2", dec!(12), 40, dec!(1)))
+            .unwrap();
+        tracker
+            .apply_fill(&fill("buy-2", "e3", dec!(400), 2, dec!(0.5)))
+            .unwrap();
+        tracker.mark("MSFT", dec!(390));
+
+        let snapshot = tracker.snapshot();
+        let symbols: Vec<&str> = snapshot
+            .positions
+            .iter()
+            .map(|position| position.symbol.as_str())
+            .collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+        assert_eq!(snapshot.realized_pnl, dec!(80));
+        assert_eq!(snapshot.unrealized_pnl, dec!(-20));
+        assert_eq!(snapshot.fees, dec!(2.5));
+        assert_eq!(snapshot.net_pnl, dec!(57.5));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&snapshot.print_as_json().unwrap()).unwrap();
+        assert_eq!(json["positions"][0]["quantity"], serde_json::json!(60.0));
+        assert_eq!(json["positions"][1]["mark_price"], serde_json::json!(390.0));
+        assert_eq!(json["net_pnl"], serde_json::json!(57.5));
+    }
+
+    #[test]
+    fn test_clones_share_positions_across_threads() {
+        let tracker = tracker();
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let tracker = tracker.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        let exec_id = format!("e{}-{}", worker, i);
+                        tracker
+                            .apply_fill(&fill("buy-1", &exec_id, dec!(10), 1, dec!(0.01)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let position = tracker.position("AAPL").unwrap();
+        assert_eq!(position.quantity, dec!(100));
+        assert_eq!(position.avg_entry_price, dec!(10));
+        assert_eq!(position.fees, dec!(1));
+    }
+}
@@ -0,0 +1,99 @@
+/*******************************************************************************
+Copyright (c) 2024.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+******************************************************************************/
+
+/******************************************************************************
+   Author: Joaquín Béjar García
+   Email: jb@taunais.com
+   Date: 18/10/26
+******************************************************************************/
+
+//! Fixtures shared by the test modules.
+
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use strategy_execution_engine::models::orders::{Order, Side};
+use strategy_execution_engine::{
+    ChildOrder, Fill, Liquidity, MessagingService, MockClient, Quantity, Timestamp,
+};
+
+/// Time of the orders and fills below, in milliseconds since the Unix epoch.
+pub const START: u64 = 1_622_512_800_000;
+
+/// A buy of `quantity` AAPL in USD at `START`.
+pub fn order(id: &str, quantity: u32) -> Order {
+    Order::builder()
+        .id(id)
+        .quantity(quantity)
+        .timestamp(Timestamp::from_millis(START))
+        .symbol("AAPL")
+        .side(Side::Buy)
+        .currency("USD")
+        .build()
+        .unwrap()
+}
+
+/// Child `n` of `parent_id`, split by `strategy_id` and sent `offset_ms` after `START`, or at
+/// once without an offset.
+pub fn child(
+    strategy_id: &str,
+    parent_id: &str,
+    n: usize,
+    quantity: u32,
+    offset_ms: Option<u64>,
+) -> ChildOrder {
+    order(&format!("{}-{}", parent_id, n), quantity).into_child(
+        strategy_id,
+        parent_id,
+        offset_ms.map(|offset_ms| Timestamp::from_millis(START + offset_ms)),
+    )
+}
+
+/// A taker fill of `quantity` at 10 with a fee of 0.5, a second after `START`.
+pub fn fill(order_id: &str, exec_id: &str, quantity: u32) -> Fill {
+    Fill::new(
+        order_id.to_string(),
+        exec_id.to_string(),
+        dec!(10),
+        Quantity::from(quantity),
+        START + 1_000,
+        dec!(0.5),
+        Liquidity::Taker,
+    )
+}
+
+/// A messaging service over a fresh `MockClient`, returned too so a test can look at what
+/// was sent.
+pub fn service() -> (MessagingService, MockClient) {
+    service_over(MockClient::new())
+}
+
+/// Like `service`, but a receive on an empty topic times out at once.
+pub fn instant_service() -> (MessagingService, MockClient) {
+    service_over(MockClient::new().with_timeout(Duration::ZERO))
+}
+
+fn service_over(client: MockClient) -> (MessagingService, MockClient) {
+    (
+        MessagingService::with_client(Box::new(client.clone())),
+        client,
+    )
+}